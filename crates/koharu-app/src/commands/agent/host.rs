@@ -6,7 +6,10 @@ use base64::{Engine as _, engine::general_purpose::STANDARD};
 use koharu_agent::{Control, Host, Invocation, Tool, ToolCall};
 use koharu_desktop::{Desktop, Frame};
 use koharu_pipeline::{Committer, Operation, RunStatus, Scope, Stage, StageOutput, StopToken};
-use koharu_scene::{Commit, EntityId, Snapshot};
+use koharu_scene::{
+    Commit, Direction, EntityId, Filter, GenerationFilter, OriginFilter, PageRange, ProducerId,
+    Query, RegionKind, RelationKind, Snapshot,
+};
 use schemars::{JsonSchema, schema_for};
 use serde::Deserialize;
 use serde_json::{Value, json};
@@ -94,7 +97,6 @@ impl KoharuHost {
             let mut current = current.project.lock().await;
             let project = current.as_mut().context("no project is open")?;
             let (commit, value) = mutation(project).await?;
-            project.record_commit(&commit);
             project.reconcile_page();
             (commit, value, project.active_page(), project.info())
         };
@@ -188,7 +190,11 @@ impl Host for KoharuHost {
                 vec![
                     definition::<InspectProject>(
                         "inspect_project",
-                        "Read the latest complete semantic project state after edits. This does not include page images.",
+                        "Read the latest complete semantic project state after edits, including reviewer annotations on each page. This does not include page images.",
+                    ),
+                    definition::<FindElements>(
+                        "find_elements",
+                        "Find entities matching every filter, optionally on a zero-based page range, and return their IDs with their pages. Component kinds include dev.koharu.layer.text (text layers), dev.koharu.text.content, dev.koharu.analysis.region, dev.koharu.annotation, and dev.koharu.layer.raster. Region kinds are dev.koharu.region.text, .bubble, and .panel. Relation kinds include dev.koharu.relation.presents (text layer to content), .fits-to (text layer to region), and .recognized-from (content to region).",
                     ),
                     definition::<ViewPage>(
                         "view_page",
@@ -241,6 +247,20 @@ impl Host for KoharuHost {
                 let _: InspectProject = arguments(&call)?;
                 Invocation::read(self.project_context().await?)
             }
+            "find_elements" => {
+                let arguments: FindElements = arguments(&call)?;
+                let query = arguments.query.try_into()?;
+                let snapshot = self
+                    .handle
+                    .state::<CurrentProject>()
+                    .project
+                    .lock()
+                    .await
+                    .as_ref()
+                    .context("no project is open")?
+                    .snapshot();
+                Invocation::read(json!({ "matches": Project::query(&snapshot, &query)? }))
+            }
             "view_page" => {
                 let arguments: ViewPage = arguments(&call)?;
                 let page = entity(&arguments.page)?;
@@ -469,7 +489,6 @@ impl Committer for AgentCommitter {
             let Some(commit) = project.commit_rebased(output.patch).await? else {
                 return Ok(project.snapshot());
             };
-            project.record_commit(&commit);
             (commit, project.active_page())
         };
         let snapshot = commit.snapshot.clone();
@@ -503,6 +522,138 @@ fn entities(values: &[String]) -> Result<Vec<EntityId>> {
 #[derive(Deserialize, JsonSchema)]
 struct InspectProject {}
 
+#[derive(Deserialize, JsonSchema)]
+struct FindElements {
+    #[serde(flatten)]
+    query: AgentQuery,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct AgentQuery {
+    first_page: Option<u32>,
+    last_page: Option<u32>,
+    #[serde(default)]
+    filters: Vec<AgentFilter>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "filter", rename_all = "snake_case")]
+enum AgentFilter {
+    /// The entity carries a component of this kind.
+    Has { component: String },
+    /// The entity carries no component of this kind.
+    Lacks { component: String },
+    /// The entity carries this component with a matching origin.
+    Origin {
+        component: String,
+        origin: AgentOrigin,
+    },
+    /// The entity is an analysis region of this kind.
+    Region { kind: String },
+    /// The translation shown for a text content or text layer has a matching origin.
+    Translation { origin: AgentOrigin },
+    /// The source text or translation contains this text, ignoring case.
+    Text { text: String },
+    /// A relation of this kind connects the entity to one matching the nested query.
+    Related {
+        relation: String,
+        direction: AgentDirection,
+        query: Box<AgentQuery>,
+    },
+}
+
+#[derive(Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum AgentDirection {
+    Outgoing,
+    Incoming,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum AgentOrigin {
+    User,
+    Generated {
+        producer: Option<String>,
+        model: Option<String>,
+        min_confidence: Option<f32>,
+        below_confidence: Option<f32>,
+    },
+}
+
+impl TryFrom<AgentQuery> for Query {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AgentQuery) -> Result<Self> {
+        let pages = match (value.first_page, value.last_page) {
+            (None, None) => None,
+            (first, last) => Some(PageRange {
+                first: first.unwrap_or(0),
+                last: last.unwrap_or(u32::MAX),
+            }),
+        };
+        Ok(Self {
+            pages,
+            filters: value
+                .filters
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<AgentFilter> for Filter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AgentFilter) -> Result<Self> {
+        Ok(match value {
+            AgentFilter::Has { component } => Self::Has(component),
+            AgentFilter::Lacks { component } => Self::Lacks(component),
+            AgentFilter::Origin { component, origin } => Self::Origin {
+                component,
+                origin: origin.try_into()?,
+            },
+            AgentFilter::Region { kind } => Self::Region(RegionKind::new(kind)?),
+            AgentFilter::Translation { origin } => Self::Translation(origin.try_into()?),
+            AgentFilter::Text { text } => Self::Text(text),
+            AgentFilter::Related {
+                relation,
+                direction,
+                query,
+            } => Self::Related {
+                kind: RelationKind::new(relation)?,
+                direction: match direction {
+                    AgentDirection::Outgoing => Direction::Outgoing,
+                    AgentDirection::Incoming => Direction::Incoming,
+                },
+                query: Box::new((*query).try_into()?),
+            },
+        })
+    }
+}
+
+impl TryFrom<AgentOrigin> for OriginFilter {
+    type Error = anyhow::Error;
+
+    fn try_from(value: AgentOrigin) -> Result<Self> {
+        Ok(match value {
+            AgentOrigin::User => Self::User,
+            AgentOrigin::Generated {
+                producer,
+                model,
+                min_confidence,
+                below_confidence,
+            } => Self::Generated(GenerationFilter {
+                producer: producer.map(ProducerId::new).transpose()?,
+                model,
+                min_confidence,
+                below_confidence,
+            }),
+        })
+    }
+}
+
 #[derive(Deserialize, JsonSchema)]
 struct ViewPage {
     page: String,
@@ -654,6 +805,7 @@ enum AgentPipelineOperation {
     Full,
     Detection,
     Ocr,
+    Sfx,
     Translation,
     Inpainting,
 }
@@ -666,6 +818,7 @@ impl From<AgentPipelineOperation> for Operation {
                 stage: Stage::Detection,
             },
             AgentPipelineOperation::Ocr => Self::Only { stage: Stage::Ocr },
+            AgentPipelineOperation::Sfx => Self::Only { stage: Stage::Sfx },
             AgentPipelineOperation::Translation => Self::Only {
                 stage: Stage::Translation,
             },
@@ -4,6 +4,7 @@ use anyhow::{Context as _, anyhow, bail};
 use image::{GrayImage, ImageEncoder as _, codecs::png::PngEncoder};
 use koharu_desktop::{CanvasState, Desktop, Frame, TransformFrame};
 use koharu_rasterizer::ResourceId;
+pub use koharu_scene::Point;
 use koharu_scene::{EntityId, Revision};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
@@ -14,17 +15,13 @@ use tauri::{
 };
 
 use super::{
-    ChannelExt as _, Error, processing,
+    ChannelExt as _, Error,
+    editing::ShapeDraft,
+    processing,
     processing::{JobChannel, JobId, Processing},
     project::{CurrentProject, Page, Project, RasterStrokeMode},
 };
 
-#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, Type)]
-pub struct Point {
-    pub x: f64,
-    pub y: f64,
-}
-
 #[derive(Clone, Copy, Debug, Deserialize, Type)]
 pub struct PaintBrush {
     pub diameter: f32,
@@ -157,7 +154,6 @@ pub(crate) async fn add_point_text(
             .active_page()
             .context("the project has no active page")?;
         let (commit, layer) = project.add_point_text(page, point).await?;
-        project.record_commit(&commit);
         (commit, project.active_page(), layer)
     };
     desktop.synchronize(&commit.snapshot, page, &commit).await?;
@@ -189,7 +185,38 @@ pub(crate) async fn add_text_box(
             .active_page()
             .context("the project has no active page")?;
         let (commit, layer) = project.add_text_box(page, frame).await?;
-        project.record_commit(&commit);
+        (commit, project.active_page(), layer)
+    };
+    desktop.synchronize(&commit.snapshot, page, &commit).await?;
+    canvas_channel.channel.publish(desktop.canvas_state());
+    Ok(LayerCommit {
+        revision: commit.revision,
+        layer,
+    })
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "shape_added",
+    skip_all,
+    fields(origin = "user", entity_count = 1_u64)
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_shape(
+    frame: Frame,
+    shape: ShapeDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<LayerCommit, Error> {
+    let (commit, page, layer) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let page = project
+            .active_page()
+            .context("the project has no active page")?;
+        let (commit, layer) = project.add_shape(page, frame, shape).await?;
         (commit, project.active_page(), layer)
     };
     desktop.synchronize(&commit.snapshot, page, &commit).await?;
@@ -262,6 +289,39 @@ pub(crate) async fn commit_erase(
     .await
 }
 
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "mask_committed",
+    skip_all,
+    fields(origin = "user", point_count = points.len(), size = f64::from(diameter)),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn commit_mask(
+    expected_revision: Revision,
+    layer: EntityId,
+    points: Vec<Point>,
+    diameter: f32,
+    reveal: bool,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<LayerCommit, Error> {
+    let value = if reveal { 255 } else { 0 };
+    commit_raster_stroke(
+        expected_revision,
+        Some(layer),
+        points,
+        diameter,
+        [value, value, value, 255],
+        RasterStrokeMode::Mask,
+        &desktop,
+        &project,
+        &canvas_channel,
+    )
+    .await
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn commit_raster_stroke(
     expected_revision: Revision,
@@ -282,22 +342,8 @@ async fn commit_raster_stroke(
             .active_page()
             .context("the project has no active page")?;
         let (commit, element) = project
-            .apply_raster_stroke(
-                page,
-                layer,
-                mode,
-                color,
-                diameter,
-                points
-                    .into_iter()
-                    .map(|point| koharu_scene::Point {
-                        x: point.x,
-                        y: point.y,
-                    })
-                    .collect(),
-            )
+            .apply_raster_stroke(page, layer, mode, color, diameter, points)
             .await?;
-        project.record_commit(&commit);
         (commit, project.active_page(), element)
     };
     desktop.synchronize(&commit.snapshot, page, &commit).await?;
@@ -332,7 +378,6 @@ pub(crate) async fn commit_transform(
         let project = project.as_mut().context("no project is open")?;
         ensure_revision(project.snapshot().revision(), expected_revision)?;
         let commit = project.set_geometries(geometries).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     desktop.synchronize(&commit.snapshot, page, &commit).await?;
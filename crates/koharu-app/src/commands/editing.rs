@@ -1,6 +1,9 @@
 use anyhow::Context as _;
 use koharu_desktop::{CanvasState, Desktop};
-use koharu_scene::EntityId;
+use koharu_scene::{
+    BlendMode, Chapter, Character, EntityId, GlossaryTerm, Origin, ReadingDirection, ShapeLayer,
+    ShapeOutline, ShapeStroke, TextSpan, TextStyle, TypographyOverride, Volume,
+};
 use serde::Deserialize;
 use specta::Type;
 use tauri::State;
@@ -31,6 +34,115 @@ pub struct TypographyUpdate {
     pub typography: Typography,
 }
 
+#[derive(Clone, Debug, Deserialize, Type)]
+pub struct CharacterDraft {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub speech_notes: String,
+    pub typography: Option<TypographyOverride>,
+}
+
+impl CharacterDraft {
+    pub(crate) fn into_character(self) -> Character {
+        Character {
+            origin: Origin::User,
+            name: self.name,
+            aliases: self.aliases,
+            speech_notes: self.speech_notes,
+            typography: self.typography,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Type)]
+pub struct TextStyleDraft {
+    pub name: String,
+    pub typography: TypographyOverride,
+    /// The style this one inherits unset fields from.
+    pub based_on: Option<EntityId>,
+}
+
+impl TextStyleDraft {
+    pub(crate) fn into_style(self) -> (TextStyle, Option<EntityId>) {
+        (
+            TextStyle {
+                origin: Origin::User,
+                name: self.name,
+                typography: self.typography,
+            },
+            self.based_on,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Type)]
+pub struct GlossaryTermDraft {
+    pub source: String,
+    /// The required rendering; ignored when the term is kept untranslated.
+    pub target: String,
+    pub notes: String,
+    pub case_sensitive: bool,
+    pub do_not_translate: bool,
+}
+
+impl GlossaryTermDraft {
+    pub(crate) fn into_term(self) -> GlossaryTerm {
+        GlossaryTerm {
+            origin: Origin::User,
+            source: self.source,
+            target: self.target,
+            notes: self.notes,
+            case_sensitive: self.case_sensitive,
+            do_not_translate: self.do_not_translate,
+        }
+    }
+}
+
+/// The title and number of a volume or chapter.
+#[derive(Clone, Debug, Deserialize, Type)]
+pub struct HeadingDraft {
+    pub title: String,
+    pub number: Option<f32>,
+}
+
+impl HeadingDraft {
+    pub(crate) fn into_volume(self) -> Volume {
+        Volume {
+            origin: Origin::User,
+            title: self.title,
+            number: self.number,
+        }
+    }
+
+    pub(crate) fn into_chapter(self) -> Chapter {
+        Chapter {
+            origin: Origin::User,
+            title: self.title,
+            number: self.number,
+        }
+    }
+}
+
+/// The outline and paint of a shape layer.
+#[derive(Clone, Debug, Deserialize, Type)]
+pub struct ShapeDraft {
+    pub outline: ShapeOutline,
+    pub fill: Option<[u8; 4]>,
+    pub stroke: Option<ShapeStroke>,
+}
+
+impl ShapeDraft {
+    pub(crate) fn into_shape(self, name: String) -> ShapeLayer {
+        ShapeLayer {
+            origin: Origin::User,
+            name,
+            outline: self.outline,
+            fill: self.fill,
+            stroke: self.stroke,
+        }
+    }
+}
+
 #[tracing::instrument(
     target = "koharu_metrics",
     name = "page_renamed",
@@ -50,7 +162,6 @@ pub(crate) async fn rename_page(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.rename_page(page, label).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -76,7 +187,6 @@ pub(crate) async fn delete_pages(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.delete_pages(pages).await?;
-        project.record_commit(&commit);
         project.reconcile_page();
         (commit, project.active_page())
     };
@@ -104,7 +214,6 @@ pub(crate) async fn move_page(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.move_page(page, index as usize).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -131,7 +240,6 @@ pub(crate) async fn set_source_text(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.set_source_text(layer, text).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -162,7 +270,32 @@ pub(crate) async fn set_translation(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.set_translation(layer, text).await?;
-        project.record_commit(&commit);
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "translation_styled",
+    skip_all,
+    fields(origin = "user", span_count = spans.len()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_translation_spans(
+    layer: EntityId,
+    spans: Vec<TextSpan>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_translation_spans(layer, spans).await?;
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -188,7 +321,6 @@ pub(crate) async fn set_typography(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.set_typography(updates).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -214,7 +346,32 @@ pub(crate) async fn set_geometry(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.set_geometry(updates).await?;
-        project.record_commit(&commit);
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "shape_edited",
+    skip_all,
+    fields(origin = "user", entity_count = 1_u64)
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_shape(
+    layer: EntityId,
+    shape: ShapeDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_shape(layer, shape).await?;
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -242,7 +399,58 @@ pub(crate) async fn set_visibility(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.set_visibility(layers, visible, opacity).await?;
-        project.record_commit(&commit);
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "blending_edited",
+    skip_all,
+    fields(origin = "user", entity_count = layers.len()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_blending(
+    layers: Vec<EntityId>,
+    mode: Option<BlendMode>,
+    clipped: Option<bool>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_blending(layers, mode, clipped).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "mask_cleared",
+    skip_all,
+    fields(origin = "user", entity_count = 1_u64)
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn clear_mask(
+    layer: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.clear_mask(layer).await?;
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -268,7 +476,6 @@ pub(crate) async fn delete_layers(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.delete_layers(layers).await?;
-        project.record_commit(&commit);
         (commit, project.active_page())
     };
     let canvas = synchronize_canvas(&desktop, &commit, page).await?;
@@ -296,7 +503,6 @@ pub(crate) async fn move_layer(
         let mut project = project.project.lock().await;
         let project = project.as_mut().context("no project is open")?;
         let commit = project.move_layer(layer, parent, index as usize).await?;
-        project.record_commit(&commit);
         let page = project.active_page().context("no active page")?;
         let view = Project::page(&commit.snapshot, page)?;
         (commit, page, view)
@@ -308,6 +514,662 @@ pub(crate) async fn move_layer(
     Ok(view)
 }
 
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "annotation_added",
+    skip_all,
+    fields(origin = "user", character_count = body.chars().count()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_annotation(
+    anchor: EntityId,
+    author: String,
+    body: String,
+    mark: Option<Vec<Point>>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, annotation) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, annotation) = project.add_annotation(anchor, author, body, mark).await?;
+        (commit, project.active_page(), annotation)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(annotation)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "annotation_replied",
+    skip_all,
+    fields(origin = "user", character_count = body.chars().count()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn reply_to_annotation(
+    annotation: EntityId,
+    author: String,
+    body: String,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project
+            .reply_to_annotation(annotation, author, body)
+            .await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "annotation_resolved",
+    skip_all,
+    fields(origin = "user", resolved)
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn resolve_annotation(
+    annotation: EntityId,
+    resolved: bool,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.resolve_annotation(annotation, resolved).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "character_added",
+    skip_all,
+    fields(origin = "user", alias_count = draft.aliases.len()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_character(
+    draft: CharacterDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, character) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, character) = project.add_character(draft).await?;
+        (commit, project.active_page(), character)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(character)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "character_updated",
+    skip_all,
+    fields(origin = "user", alias_count = draft.aliases.len()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn update_character(
+    character: EntityId,
+    draft: CharacterDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.update_character(character, draft).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "character_removed",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn remove_character(
+    character: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.remove_character(character).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "speaker_set",
+    skip_all,
+    fields(origin = "user", layer_count = layers.len(), cleared = character.is_none()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_speaker(
+    layers: Vec<EntityId>,
+    character: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_speaker(layers, character).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "text_style_added",
+    skip_all,
+    fields(origin = "user", based = draft.based_on.is_some()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_text_style(
+    draft: TextStyleDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, style) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, style) = project.add_text_style(draft).await?;
+        (commit, project.active_page(), style)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(style)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "text_style_updated",
+    skip_all,
+    fields(origin = "user", based = draft.based_on.is_some()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn update_text_style(
+    style: EntityId,
+    draft: TextStyleDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.update_text_style(style, draft).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "text_style_removed",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn remove_text_style(
+    style: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.remove_text_style(style).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "text_style_applied",
+    skip_all,
+    fields(
+        origin = "user",
+        layer_count = layers.len(),
+        cleared = style.is_none(),
+        clear_overrides,
+    ),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn apply_text_style(
+    layers: Vec<EntityId>,
+    style: Option<EntityId>,
+    clear_overrides: bool,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project
+            .apply_text_style(layers, style, clear_overrides)
+            .await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "glossary_term_added",
+    skip_all,
+    fields(origin = "user", do_not_translate = draft.do_not_translate),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_glossary_term(
+    draft: GlossaryTermDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, term) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, term) = project.add_glossary_term(draft).await?;
+        (commit, project.active_page(), term)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(term)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "glossary_term_updated",
+    skip_all,
+    fields(origin = "user", do_not_translate = draft.do_not_translate),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn update_glossary_term(
+    term: EntityId,
+    draft: GlossaryTermDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.update_glossary_term(term, draft).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "glossary_term_removed",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn remove_glossary_term(
+    term: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.remove_glossary_term(term).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "volume_added",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_volume(
+    draft: HeadingDraft,
+    before: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, entity) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, entity) = project.add_volume(draft, before).await?;
+        (commit, project.active_page(), entity)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(entity)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "volume_updated",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn update_volume(
+    volume: EntityId,
+    draft: HeadingDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.update_volume(volume, draft).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "volume_moved",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn move_volume(
+    volume: EntityId,
+    before: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.move_volume(volume, before).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "volume_removed",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn remove_volume(
+    volume: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.remove_volume(volume).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "chapter_added",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn add_chapter(
+    draft: HeadingDraft,
+    volume: Option<EntityId>,
+    before: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<EntityId, Error> {
+    let (commit, page, entity) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let (commit, entity) = project.add_chapter(draft, volume, before).await?;
+        (commit, project.active_page(), entity)
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(entity)
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "chapter_updated",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn update_chapter(
+    chapter: EntityId,
+    draft: HeadingDraft,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.update_chapter(chapter, draft).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "chapter_moved",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn move_chapter(
+    chapter: EntityId,
+    volume: Option<EntityId>,
+    before: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.move_chapter(chapter, volume, before).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "chapter_removed",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn remove_chapter(
+    chapter: EntityId,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.remove_chapter(chapter).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "chapter_assigned",
+    skip_all,
+    fields(origin = "user", entity_count = pages.len(), cleared = chapter.is_none()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_chapter(
+    pages: Vec<EntityId>,
+    chapter: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_chapter(pages, chapter).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "reading_direction_set",
+    skip_all,
+    fields(origin = "user", direction = ?direction),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_reading_direction(
+    direction: ReadingDirection,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, page) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_reading_direction(direction).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, page).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "spread_paired",
+    skip_all,
+    fields(origin = "user", cleared = partner.is_none()),
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn set_spread(
+    page: EntityId,
+    partner: Option<EntityId>,
+    desktop: State<'_, Desktop>,
+    project: State<'_, CurrentProject>,
+    canvas_channel: State<'_, CanvasChannel>,
+) -> Result<(), Error> {
+    let (commit, active) = {
+        let mut project = project.project.lock().await;
+        let project = project.as_mut().context("no project is open")?;
+        let commit = project.set_spread(page, partner).await?;
+        (commit, project.active_page())
+    };
+    let canvas = synchronize_canvas(&desktop, &commit, active).await?;
+    canvas_channel.channel.publish(canvas);
+    Ok(())
+}
+
 #[tracing::instrument(
     target = "koharu_metrics",
     name = "undo",
@@ -44,9 +44,91 @@ pub(super) struct Page {
     pub(super) format: ImageFormat,
     pub(super) width: u32,
     pub(super) height: u32,
+    /// The archive folder the page was found in, which becomes its chapter.
+    pub(super) chapter: Option<ChapterFolder>,
 }
 
-fn decode(path: &Path, source: EncodedPage) -> Result<Page> {
+/// A top-level folder of one imported archive.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(super) struct ChapterFolder {
+    pub(super) archive: usize,
+    pub(super) title: String,
+}
+
+impl ChapterFolder {
+    /// The chapter number a folder name carries, such as 12 in "Vol.2
+    /// Chapter 012", 12.5 in "ch12.5" or 7 in "第7話". A number after a
+    /// chapter marker wins; otherwise the first number in the name does.
+    pub(super) fn number(&self) -> Option<f32> {
+        let title = self.title.as_str();
+        let mut first = None;
+        let mut rest = 0;
+        while let Some(offset) = title[rest..].find(|character: char| character.is_ascii_digit()) {
+            let start = rest + offset;
+            let digits = &title[start..];
+            let mut end = digits
+                .find(|character: char| !character.is_ascii_digit())
+                .unwrap_or(digits.len());
+            if let Some(fraction) = digits[end..].strip_prefix('.')
+                && fraction.starts_with(|character: char| character.is_ascii_digit())
+            {
+                end += 1 + fraction
+                    .find(|character: char| !character.is_ascii_digit())
+                    .unwrap_or(fraction.len());
+            }
+            let number = digits[..end].parse().ok();
+            rest = start + end;
+            if marks_chapter(&title[..start], &title[rest..]) {
+                return number;
+            }
+            first = first.or(number);
+        }
+        first
+    }
+}
+
+/// Whether the number between `before` and `after` is marked as a chapter
+/// number: "ch", "chap" or "chapter" before it, or "第" before and "話" after.
+fn marks_chapter(before: &str, after: &str) -> bool {
+    let before = before.trim_end_matches([' ', '.', '_', '-', '#']);
+    if before.ends_with('第') {
+        return after.starts_with(['話', '话', '章', '回']);
+    }
+    ["chapter", "chap", "ch"].iter().any(|marker| {
+        before.len() >= marker.len()
+            && before.is_char_boundary(before.len() - marker.len())
+            && before[before.len() - marker.len()..].eq_ignore_ascii_case(marker)
+            && !before[..before.len() - marker.len()].ends_with(char::is_alphabetic)
+    })
+}
+
+/// Names each member's chapter after its top-level folder, once any folders
+/// that every member shares are set aside. Members outside such a folder
+/// belong to no chapter.
+fn chapter_titles(names: &[&str]) -> Vec<Option<String>> {
+    let folders = names
+        .iter()
+        .map(|name| {
+            let mut components = name.split(['/', '\\']).collect::<Vec<_>>();
+            components.pop();
+            components
+        })
+        .collect::<Vec<_>>();
+    let mut depth = 0;
+    while let Some(first) = folders.first().and_then(|folders| folders.get(depth))
+        && folders
+            .iter()
+            .all(|folders| folders.get(depth) == Some(first))
+    {
+        depth += 1;
+    }
+    folders
+        .iter()
+        .map(|folders| folders.get(depth).map(|folder| (*folder).to_owned()))
+        .collect()
+}
+
+fn decode(path: &Path, source: EncodedPage, chapter: Option<ChapterFolder>) -> Result<Page> {
     let EncodedPage { name, bytes } = source;
     let format = image::guess_format(&bytes).with_context(|| {
         format!(
@@ -68,6 +150,7 @@ fn decode(path: &Path, source: EncodedPage) -> Result<Page> {
         format,
         width,
         height,
+        chapter,
     })
 }
 
@@ -77,11 +160,13 @@ pub(super) fn import(mut paths: Vec<PathBuf>) -> Result<Vec<Page>> {
     });
     let mut groups = paths
         .into_par_iter()
-        .map(|path| -> Result<Vec<Page>> {
+        .enumerate()
+        .map(|(index, path)| -> Result<Vec<Page>> {
             let extension = path
                 .extension()
                 .and_then(|extension| extension.to_str())
                 .and_then(|extension| extension.parse::<Format>().ok());
+            let archive = matches!(extension, Some(Format::Zip | Format::Rar));
             let encoded = match extension {
                 Some(Format::Raster) => vec![EncodedPage {
                     name: path
@@ -96,9 +181,26 @@ pub(super) fn import(mut paths: Vec<PathBuf>) -> Result<Vec<Page>> {
                 Some(Format::Pdf) => pdf::render(&path)?,
                 None => bail!("unsupported page import path {}", path.display()),
             };
+            let chapters = if archive {
+                chapter_titles(
+                    &encoded
+                        .iter()
+                        .map(|page| page.name.as_str())
+                        .collect::<Vec<_>>(),
+                )
+            } else {
+                vec![None; encoded.len()]
+            };
             encoded
                 .into_iter()
-                .map(|source| decode(&path, source))
+                .zip(chapters)
+                .map(|(source, title)| {
+                    let chapter = title.map(|title| ChapterFolder {
+                        archive: index,
+                        title,
+                    });
+                    decode(&path, source, chapter)
+                })
                 .collect()
         })
         .collect::<Result<Vec<_>>>()?;
@@ -145,4 +247,36 @@ mod tests {
             ["page1.png", "page2.png", "page10.PNG"]
         );
     }
+
+    #[test]
+    fn top_level_archive_folders_name_chapters() {
+        assert_eq!(
+            chapter_titles(&[
+                "Volume 1/Chapter 01/001.png",
+                "Volume 1/Chapter 01/002.png",
+                "Volume 1/Chapter 02/001.png",
+                "Volume 1/cover.png",
+            ]),
+            [
+                Some("Chapter 01".to_owned()),
+                Some("Chapter 01".to_owned()),
+                Some("Chapter 02".to_owned()),
+                None,
+            ]
+        );
+        assert_eq!(
+            chapter_titles(&["pages/001.png", "pages/002.png"]),
+            [None, None]
+        );
+        let folder = |title: &str| ChapterFolder {
+            archive: 0,
+            title: title.to_owned(),
+        };
+        assert_eq!(folder("Chapter 012").number(), Some(12.0));
+        assert_eq!(folder("Vol.2 ch12.5 extra").number(), Some(12.5));
+        assert_eq!(folder("Vol.2 Chapter 012 (2019)").number(), Some(12.0));
+        assert_eq!(folder("第3巻 第7話").number(), Some(7.0));
+        assert_eq!(folder("Batch 3 - 05").number(), Some(3.0));
+        assert_eq!(folder("Prologue").number(), None);
+    }
 }
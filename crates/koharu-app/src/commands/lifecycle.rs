@@ -1,6 +1,11 @@
+use std::{collections::HashMap, path::PathBuf};
+
 use anyhow::{Context as _, Result};
 use koharu_desktop::{CanvasState, Desktop};
-use koharu_scene::{AssetInput, AssetMetadata, AssetRole, At, PageDraft};
+use koharu_scene::{
+    AssetInput, AssetMetadata, AssetRole, At, Chapter as SceneChapter, PageDraft, Query,
+    ReadingDirection, Revision,
+};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -16,7 +21,8 @@ use super::{
     preferences::Preferences,
     processing::{Job, JobChannel, Processing},
     project::{
-        CurrentProject, Page, PageSummary, Project, ProjectInfo, ProjectLibrary, ProjectSummary,
+        Chapter, Character, CurrentProject, GlossaryTerm, Page, PageSummary, Project, ProjectInfo,
+        ProjectLibrary, ProjectSummary, QueryMatch, RevisionDiff, TextStyle, Volume,
     },
 };
 
@@ -247,6 +253,133 @@ pub(crate) async fn get_page(
         .transpose()?)
 }
 
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn query_entities(
+    project: State<'_, CurrentProject>,
+    query: Query,
+) -> std::result::Result<Vec<QueryMatch>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::query(&snapshot, &query)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn diff_revisions(
+    project: State<'_, CurrentProject>,
+    from: Revision,
+    to: Option<Revision>,
+) -> std::result::Result<RevisionDiff, Error> {
+    let project = project.project.lock().await;
+    let project = project.as_ref().context("no project is open")?;
+    Ok(project.diff_revisions(from, to).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn diff_bundles(
+    before: PathBuf,
+    after: PathBuf,
+) -> std::result::Result<RevisionDiff, Error> {
+    Ok(Project::diff_bundles(before, after).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn list_characters(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<Vec<Character>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::characters(&snapshot)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn list_text_styles(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<Vec<TextStyle>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::text_styles(&snapshot)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn list_glossary(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<Vec<GlossaryTerm>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::glossary(&snapshot)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn list_volumes(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<Vec<Volume>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::volumes(&snapshot)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn list_chapters(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<Vec<Chapter>, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(Project::chapters(&snapshot)?)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn get_reading_direction(
+    project: State<'_, CurrentProject>,
+) -> std::result::Result<ReadingDirection, Error> {
+    let snapshot = project
+        .project
+        .lock()
+        .await
+        .as_ref()
+        .context("no project is open")?
+        .snapshot();
+    Ok(snapshot.reading_direction()?)
+}
+
 #[tauri::command]
 #[specta::specta]
 pub(crate) async fn list_projects(
@@ -423,6 +556,8 @@ pub(crate) async fn import_pages(
         let project = project.as_mut().context("no project is open")?;
         let source = AssetRole::new("source")?;
         let patch = project.snapshot().patch(|edit| {
+            // Each top-level folder of an archive becomes a chapter of its pages.
+            let mut chapters = HashMap::new();
             for imported in pages {
                 let page = edit.add_page(
                     PageDraft::new(
@@ -445,11 +580,25 @@ pub(crate) async fn import_pages(
                         },
                     ),
                 )?;
+                if let Some(folder) = imported.chapter {
+                    let chapter = match chapters.get(&folder) {
+                        Some(chapter) => *chapter,
+                        None => {
+                            let chapter = edit.add_chapter(
+                                None,
+                                &SceneChapter::new(folder.title.clone(), folder.number()),
+                                At::End,
+                            )?;
+                            chapters.insert(folder, chapter);
+                            chapter
+                        }
+                    };
+                    edit.set_chapter(page, Some(chapter))?;
+                }
             }
             Ok(())
         })?;
         let commit = project.session.commit(patch).await?;
-        project.record(vec![commit.revision]);
         project.reconcile_page();
         let page = project.active_page();
         (commit, page)
@@ -461,6 +610,34 @@ pub(crate) async fn import_pages(
     Ok(())
 }
 
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "translation_memory_import",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn import_translation_memory(
+    window: WebviewWindow<Cef>,
+    pipeline: State<'_, koharu_pipeline::Pipeline>,
+) -> std::result::Result<Option<u32>, Error> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("TMX", &["tmx"])
+        .set_parent(&window)
+        .pick_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let tmx = tokio::fs::read_to_string(file.path())
+        .await
+        .with_context(|| format!("failed to read {}", file.path().display()))?;
+    let unit_count = pipeline.translation_memory().import_tmx(&tmx).await?;
+    tracing::info!(target: "koharu_metrics", metric = "translation_memory_imported", unit_count);
+    Ok(Some(u32::try_from(unit_count).unwrap_or(u32::MAX)))
+}
+
 #[tracing::instrument(
     target = "koharu_metrics",
     name = "page_selected",
@@ -67,28 +67,68 @@ pub fn bindings() -> tauri_specta::Builder<tauri::Cef> {
             lifecycle::get_project,
             lifecycle::get_pages,
             lifecycle::get_page,
+            lifecycle::query_entities,
+            lifecycle::diff_revisions,
+            lifecycle::diff_bundles,
+            lifecycle::list_characters,
+            lifecycle::list_text_styles,
+            lifecycle::list_glossary,
+            lifecycle::list_volumes,
+            lifecycle::list_chapters,
+            lifecycle::get_reading_direction,
             lifecycle::list_projects,
             lifecycle::create_project,
             lifecycle::open_project,
             lifecycle::delete_project,
             lifecycle::close_project,
             lifecycle::import_pages,
+            lifecycle::import_translation_memory,
             lifecycle::select_page,
             editing::rename_page,
             editing::delete_pages,
             editing::move_page,
             editing::set_source_text,
             editing::set_translation,
+            editing::set_translation_spans,
             editing::set_typography,
             editing::set_geometry,
+            editing::set_shape,
             editing::set_visibility,
+            editing::set_blending,
+            editing::clear_mask,
             editing::delete_layers,
             editing::move_layer,
+            editing::add_annotation,
+            editing::reply_to_annotation,
+            editing::resolve_annotation,
+            editing::add_character,
+            editing::update_character,
+            editing::remove_character,
+            editing::set_speaker,
+            editing::add_text_style,
+            editing::update_text_style,
+            editing::remove_text_style,
+            editing::apply_text_style,
+            editing::add_glossary_term,
+            editing::update_glossary_term,
+            editing::remove_glossary_term,
+            editing::add_volume,
+            editing::update_volume,
+            editing::move_volume,
+            editing::remove_volume,
+            editing::add_chapter,
+            editing::update_chapter,
+            editing::move_chapter,
+            editing::remove_chapter,
+            editing::set_chapter,
+            editing::set_reading_direction,
+            editing::set_spread,
             editing::undo,
             editing::redo,
             processing::process,
             processing::stop_job,
             output::export_pages,
+            output::export_translation_memory,
             output::get_thumbnail,
             fonts::get_fonts,
             fonts::get_font_preview,
@@ -102,8 +142,10 @@ pub fn bindings() -> tauri_specta::Builder<tauri::Cef> {
             canvas::get_canvas_page_resource,
             canvas::add_point_text,
             canvas::add_text_box,
+            canvas::add_shape,
             canvas::commit_paint,
             canvas::commit_erase,
+            canvas::commit_mask,
             canvas::commit_transform,
             canvas::commit_inpaint,
         ])
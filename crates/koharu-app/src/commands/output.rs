@@ -10,7 +10,7 @@ use koharu_renderer::{Frame, Renderer};
 use koharu_scene::{AssetRole, EntityId, Snapshot};
 use serde::Deserialize;
 use specta::Type;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 use tauri::{Cef, State, WebviewWindow, ipc::IpcResponse};
 
 use super::{Error, project::CurrentProject};
@@ -33,13 +33,16 @@ impl IpcResponse for ThumbnailBytes {
 pub enum ExportFormat {
     Png,
     Psd,
+    /// One CBZ archive of PNG pages per chapter. Pages outside any chapter
+    /// share one more archive.
+    Cbz,
 }
 
 #[tracing::instrument(
     target = "koharu_metrics",
     name = "export",
     skip_all,
-    fields(origin = "user", format = ?format),
+    fields(origin = "user", format = ?format, spreads),
 )]
 #[tauri::command]
 #[specta::specta]
@@ -47,6 +50,7 @@ pub(crate) async fn export_pages(
     window: WebviewWindow<Cef>,
     pages: Vec<EntityId>,
     format: ExportFormat,
+    spreads: bool,
     project: State<'_, CurrentProject>,
     desktop: State<'_, Desktop>,
 ) -> std::result::Result<(), Error> {
@@ -71,73 +75,40 @@ pub(crate) async fn export_pages(
     if pages.is_empty() {
         return Err(anyhow::anyhow!("there are no pages to export").into());
     }
+    if spreads && matches!(format, ExportFormat::Psd) {
+        return Err(anyhow::anyhow!("spreads export as PNG or CBZ").into());
+    }
+    let units = export_units(&snapshot, pages, spreads)?;
     let renderer = desktop.renderer();
     let rasterizer = desktop.rasterizer().await?;
-    let jobs = pages
-        .into_iter()
-        .enumerate()
-        .map(|(index, page_id)| {
-            let page = snapshot.page(page_id)?.page()?;
-            let name = page
-                .label
-                .trim()
-                .trim_end_matches(|character: char| character == '.' || character.is_whitespace());
-            let name = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
-            let name = name
-                .chars()
-                .map(|character| {
-                    if matches!(
-                        character,
-                        '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'
-                    ) {
-                        '_'
-                    } else {
-                        character
-                    }
-                })
-                .collect::<String>();
-            let stem = format!(
-                "{:04}_{}",
-                index + 1,
-                if name.is_empty() { "page" } else { &name }
-            );
-            Ok::<_, anyhow::Error>((page_id, stem))
-        })
-        .collect::<Result<Vec<_>>>()?;
+    if let ExportFormat::Cbz = format {
+        export_chapters(&renderer, rasterizer, &snapshot, units, &directory).await?;
+        return Ok(());
+    }
+    let jobs = unit_stems(&snapshot, units)?;
     stream::iter(jobs)
-        .map(|(page_id, stem)| {
+        .map(|(unit, stem)| {
             let renderer = renderer.clone();
             let rasterizer = Arc::clone(&rasterizer);
             let snapshot = snapshot.clone();
             let directory = directory.clone();
             async move {
-                let frame = renderer.render(&snapshot, page_id).await?;
                 match format {
                     ExportFormat::Png => {
                         let image =
-                            rasterize(Arc::clone(&rasterizer), &frame, RasterOptions::default())
-                                .await?
-                                .image;
+                            render_image(&renderer, Arc::clone(&rasterizer), &snapshot, &unit)
+                                .await?;
                         tokio::task::spawn_blocking(move || -> Result<()> {
                             let file =
                                 std::fs::File::create(directory.join(format!("{stem}.png")))?;
-                            PngEncoder::new_with_quality(
-                                file,
-                                CompressionType::Best,
-                                FilterType::Adaptive,
-                            )
-                            .write_image(
-                                image.as_raw(),
-                                image.width(),
-                                image.height(),
-                                ExtendedColorType::Rgba8,
-                            )?;
-                            Ok(())
+                            write_png(file, &image)
                         })
                         .await
                         .context("PNG export worker stopped unexpectedly")??;
                     }
+                    ExportFormat::Cbz => unreachable!("chapter archives are exported above"),
                     ExportFormat::Psd => {
+                        let frame = renderer.render(&snapshot, unit[0]).await?;
                         let bytes = export_page(
                             Arc::clone(&rasterizer),
                             &snapshot,
@@ -162,6 +133,197 @@ pub(crate) async fn export_pages(
     Ok(())
 }
 
+/// Groups the exported pages into the images an export writes. With
+/// `spreads`, a page and the page paired with it share one image when both are
+/// exported; every other page stands alone.
+fn export_units(
+    snapshot: &Snapshot,
+    pages: Vec<EntityId>,
+    spreads: bool,
+) -> Result<Vec<Vec<EntityId>>> {
+    if !spreads {
+        return Ok(pages.into_iter().map(|page| vec![page]).collect());
+    }
+    let selected = pages.iter().copied().collect::<HashSet<_>>();
+    let mut exported = HashSet::new();
+    let mut units = Vec::new();
+    for page in pages {
+        if exported.contains(&page) {
+            continue;
+        }
+        let spread = snapshot
+            .page(page)?
+            .spread()
+            .into_iter()
+            .map(|page| page.id())
+            .collect::<Vec<_>>();
+        let unit = if spread.iter().all(|page| selected.contains(page)) {
+            spread
+        } else {
+            vec![page]
+        };
+        exported.extend(unit.iter().copied());
+        units.push(unit);
+    }
+    Ok(units)
+}
+
+/// Names each export file after its position and the label of its first page.
+fn unit_stems(
+    snapshot: &Snapshot,
+    units: Vec<Vec<EntityId>>,
+) -> Result<Vec<(Vec<EntityId>, String)>> {
+    units
+        .into_iter()
+        .enumerate()
+        .map(|(index, unit)| {
+            let page = snapshot.page(unit[0])?.page()?;
+            let name = page
+                .label
+                .trim()
+                .trim_end_matches(|character: char| character == '.' || character.is_whitespace());
+            let name = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+            Ok((unit, file_stem(index, name, "page")))
+        })
+        .collect()
+}
+
+fn file_stem(index: usize, name: &str, fallback: &str) -> String {
+    let name = name
+        .trim()
+        .chars()
+        .map(|character| {
+            if matches!(
+                character,
+                '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*'
+            ) {
+                '_'
+            } else {
+                character
+            }
+        })
+        .collect::<String>();
+    format!(
+        "{:04}_{}",
+        index + 1,
+        if name.is_empty() { fallback } else { &name }
+    )
+}
+
+fn write_png(writer: impl std::io::Write, image: &image::RgbaImage) -> Result<()> {
+    PngEncoder::new_with_quality(writer, CompressionType::Best, FilterType::Adaptive).write_image(
+        image.as_raw(),
+        image.width(),
+        image.height(),
+        ExtendedColorType::Rgba8,
+    )?;
+    Ok(())
+}
+
+/// Writes one CBZ archive per chapter, in the order the pages are given. A
+/// spread belongs to the chapter of its first page.
+async fn export_chapters(
+    renderer: &Renderer,
+    rasterizer: Arc<Rasterizer>,
+    snapshot: &Snapshot,
+    units: Vec<Vec<EntityId>>,
+    directory: &std::path::Path,
+) -> Result<()> {
+    let mut chapters: Vec<(Option<EntityId>, Vec<Vec<EntityId>>)> = Vec::new();
+    for unit in units {
+        let chapter = snapshot
+            .page(unit[0])?
+            .chapter()?
+            .map(|chapter| chapter.id());
+        match chapters.iter_mut().find(|(id, _)| *id == chapter) {
+            Some((_, units)) => units.push(unit),
+            None => chapters.push((chapter, vec![unit])),
+        }
+    }
+    for (index, (chapter, units)) in chapters.into_iter().enumerate() {
+        let title = match chapter {
+            Some(chapter) => snapshot.chapter(chapter)?.chapter()?.title,
+            None => String::new(),
+        };
+        let path = directory.join(format!("{}.cbz", file_stem(index, &title, "pages")));
+        // Each page is written as soon as it is encoded, so a chapter never
+        // holds more than the pages being rendered in memory.
+        let mut members = std::pin::pin!(
+            stream::iter(unit_stems(snapshot, units)?)
+                .map(|(unit, stem)| {
+                    let rasterizer = Arc::clone(&rasterizer);
+                    async move {
+                        let image = render_image(renderer, rasterizer, snapshot, &unit).await?;
+                        let bytes = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+                            let mut bytes = Vec::new();
+                            write_png(&mut bytes, &image)?;
+                            Ok(bytes)
+                        })
+                        .await
+                        .context("PNG export worker stopped unexpectedly")??;
+                        Ok::<_, anyhow::Error>((format!("{stem}.png"), bytes))
+                    }
+                })
+                .buffered(4)
+        );
+        // PNG data is already compressed.
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        let mut archive = tokio::task::spawn_blocking(move || -> Result<_> {
+            Ok(zip::ZipWriter::new(std::fs::File::create(&path)?))
+        })
+        .await
+        .context("CBZ export worker stopped unexpectedly")??;
+        let mut page_count = 0_usize;
+        while let Some((name, bytes)) = members.try_next().await? {
+            archive = tokio::task::spawn_blocking(move || -> Result<_> {
+                archive.start_file(name, options)?;
+                std::io::Write::write_all(&mut archive, &bytes)?;
+                Ok(archive)
+            })
+            .await
+            .context("CBZ export worker stopped unexpectedly")??;
+            page_count += 1;
+        }
+        tokio::task::spawn_blocking(move || archive.finish())
+            .await
+            .context("CBZ export worker stopped unexpectedly")??;
+        tracing::info!(
+            target: "koharu_metrics",
+            metric = "chapter_exported",
+            page_count,
+        );
+    }
+    Ok(())
+}
+
+#[tracing::instrument(
+    target = "koharu_metrics",
+    name = "translation_memory_export",
+    skip_all,
+    fields(origin = "user")
+)]
+#[tauri::command]
+#[specta::specta]
+pub(crate) async fn export_translation_memory(
+    window: WebviewWindow<Cef>,
+    pipeline: State<'_, koharu_pipeline::Pipeline>,
+) -> std::result::Result<(), Error> {
+    let Some(file) = rfd::AsyncFileDialog::new()
+        .add_filter("TMX", &["tmx"])
+        .set_file_name("translation-memory.tmx")
+        .set_parent(&window)
+        .save_file()
+        .await
+    else {
+        return Ok(());
+    };
+    tokio::fs::write(file.path(), pipeline.translation_memory().export_tmx())
+        .await
+        .with_context(|| format!("failed to write {}", file.path().display()))?;
+    Ok(())
+}
+
 #[tauri::command]
 #[specta::specta]
 pub(crate) async fn get_thumbnail(
@@ -201,11 +363,13 @@ pub(crate) async fn rendered_preview(
     snapshot: &Snapshot,
     page: EntityId,
 ) -> Result<Vec<u8>> {
-    snapshot.page(page)?;
-    let frame = renderer.render(snapshot, page).await?;
-    let image = rasterize(rasterizer, &frame, RasterOptions::default())
-        .await?
-        .image;
+    let spread = snapshot
+        .page(page)?
+        .spread()
+        .into_iter()
+        .map(|page| page.id())
+        .collect::<Vec<_>>();
+    let image = render_image(renderer, rasterizer, snapshot, &spread).await?;
     tokio::task::spawn_blocking(move || {
         let image = image::DynamicImage::ImageRgba8(image)
             .resize(1024, 1024, image::imageops::FilterType::Lanczos3)
@@ -217,6 +381,32 @@ pub(crate) async fn rendered_preview(
     .context("preview encode worker stopped unexpectedly")?
 }
 
+/// Renders one page, or a spread of paired pages laid out in the project's
+/// reading direction, as one image.
+async fn render_image(
+    renderer: &Renderer,
+    rasterizer: Arc<Rasterizer>,
+    snapshot: &Snapshot,
+    pages: &[EntityId],
+) -> Result<image::RgbaImage> {
+    if let [page] = pages {
+        let frame = renderer.render(snapshot, *page).await?;
+        return Ok(rasterize(rasterizer, &frame, RasterOptions::default())
+            .await?
+            .image);
+    }
+    let spread = renderer.render_spread(snapshot, pages[0]).await?;
+    let (width, height) = spread.size();
+    let mut image = image::RgbaImage::new(width, height);
+    for ((x, y), frame) in spread.frames() {
+        let page = rasterize(Arc::clone(&rasterizer), frame, RasterOptions::default())
+            .await?
+            .image;
+        image::imageops::replace(&mut image, &page, i64::from(x), i64::from(y));
+    }
+    Ok(image)
+}
+
 async fn rasterize(
     rasterizer: Arc<Rasterizer>,
     frame: &Frame,
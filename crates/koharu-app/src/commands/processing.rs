@@ -218,7 +218,6 @@ pub(crate) async fn process(
                     let Some(commit) = project.commit_rebased(output.patch).await? else {
                         return Ok(project.snapshot());
                     };
-                    project.record_commit(&commit);
                     let page = project.active_page();
                     (commit, page)
                 };
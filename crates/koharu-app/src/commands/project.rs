@@ -1,15 +1,19 @@
 use std::{collections::HashSet, io::Cursor, path::PathBuf};
 
-use anyhow::{Context as _, Result, anyhow, bail};
-use image::{DynamicImage, ImageFormat, RgbaImage};
+use anyhow::{Context as _, Result, bail};
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
 use koharu_desktop::Frame;
 use koharu_scene::{
-    AssetInput, AssetMetadata, AssetRole, At, Authored, Commit, EntityId, EntityOrigin,
+    Annotation as SceneAnnotation, AnnotationMark, AnnotationReply as SceneAnnotationReply,
+    AssetInput, AssetMetadata, AssetRole, At, Authored, BlendMode, Blending as SceneBlending,
+    Character as SceneCharacter, Commit, DiffReport, EntityId, EntityOrigin,
     Geometry as SceneGeometry, Group as SceneGroup, Origin, PageDraft, Point as ScenePoint,
-    Presents, RasterLayer as SceneRasterLayer, RasterLayerKind, Region as SceneRegion,
-    RemovePolicy, Revision, Session, Snapshot, SourceText as SceneSourceText,
-    TextGroup as SceneTextGroup, TextLayout as SceneTextLayout, TextLayoutKind,
-    Translation as SceneTranslation, Typography as SceneTypography, Visibility as SceneVisibility,
+    Presents, Query, RasterLayer as SceneRasterLayer, RasterLayerKind, Reading, ReadingDirection,
+    Region as SceneRegion, RemovePolicy, Revision, Session, ShapeLayer as SceneShapeLayer,
+    ShapeOutline, ShapeStroke, Snapshot, SourceText as SceneSourceText,
+    TextGroup as SceneTextGroup, TextLayout as SceneTextLayout, TextLayoutKind, TextSpan,
+    Translation as SceneTranslation, Typography as SceneTypography, TypographyOverride,
+    Visibility as SceneVisibility,
 };
 use serde::Serialize;
 use specta::Type;
@@ -17,13 +21,18 @@ use tokio::sync::Mutex;
 
 use super::{
     canvas::Point,
-    editing::{GeometryUpdate, TypographyUpdate},
+    editing::{
+        CharacterDraft, GeometryUpdate, GlossaryTermDraft, HeadingDraft, ShapeDraft,
+        TextStyleDraft, TypographyUpdate,
+    },
 };
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) enum RasterStrokeMode {
     Paint,
     Erase,
+    /// Paints the layer's `mask` asset, which starts fully white when absent.
+    Mask,
 }
 
 #[derive(Clone, Debug, Serialize, Type)]
@@ -54,6 +63,8 @@ pub struct PageSummary {
     pub source_asset: Option<String>,
     #[specta(type = f64)]
     pub layer_count: usize,
+    /// The page facing this one in a two-page spread.
+    pub spread: Option<EntityId>,
 }
 
 #[derive(Clone, Debug, Serialize, Type)]
@@ -63,6 +74,7 @@ pub struct Page {
     pub size: PageSize,
     pub layers: Vec<Layer>,
     pub regions: Vec<AnalysisRegion>,
+    pub annotations: Vec<Annotation>,
 }
 
 #[derive(Clone, Debug, Serialize, Type)]
@@ -82,6 +94,8 @@ pub enum Layer {
         visibility: LayerVisibility,
         content: Box<TextContent>,
         typography: Option<Typography>,
+        /// The project text style the layer uses beneath its own typography.
+        style: Option<EntityId>,
         layout: TextLayoutKind,
         automatic_region: Option<EntityId>,
     },
@@ -92,6 +106,11 @@ pub enum Layer {
         image: Option<String>,
         name: String,
         kind: RasterLayerKind,
+        blend: BlendMode,
+        /// Whether the layer only shows where the raster layer beneath it does.
+        clipped: bool,
+        /// The grayscale mask asset, white where the layer shows.
+        mask: Option<String>,
     },
     Image {
         id: EntityId,
@@ -107,6 +126,16 @@ pub enum Layer {
         visibility: LayerVisibility,
         image: String,
     },
+    Shape {
+        id: EntityId,
+        parent: Option<EntityId>,
+        geometry: Geometry,
+        visibility: LayerVisibility,
+        name: String,
+        outline: ShapeOutline,
+        fill: Option<[u8; 4]>,
+        stroke: Option<ShapeStroke>,
+    },
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Type)]
@@ -122,6 +151,7 @@ pub struct TextContent {
     pub translation: Option<Translation>,
     pub role: Option<String>,
     pub source_region: Option<EntityId>,
+    pub speaker: Option<EntityId>,
 }
 
 #[derive(Clone, Debug, Serialize, Type)]
@@ -133,6 +163,99 @@ pub struct AnalysisRegion {
     pub label: Option<String>,
 }
 
+/// A note anchored to the page, an analysis region, or a text layer. A mark of
+/// one point pins it; three or more outline a polygon.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct Annotation {
+    pub id: EntityId,
+    pub anchor: Option<EntityId>,
+    pub author: String,
+    pub body: String,
+    pub mark: Option<Vec<Point>>,
+    pub replies: Vec<AnnotationReply>,
+    pub resolved: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct AnnotationReply {
+    pub author: String,
+    pub body: String,
+}
+
+/// A speaking character of the project. Its typography fills in whatever the
+/// text layers of its lines leave unset.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct Character {
+    pub id: EntityId,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub speech_notes: String,
+    pub typography: Option<TypographyOverride>,
+}
+
+/// A volume of the series and its chapters in reading order.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct Volume {
+    pub id: EntityId,
+    pub title: String,
+    pub number: Option<f32>,
+    pub chapters: Vec<EntityId>,
+}
+
+/// A chapter of the series and its pages in page order.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct Chapter {
+    pub id: EntityId,
+    pub title: String,
+    pub number: Option<f32>,
+    pub volume: Option<EntityId>,
+    pub pages: Vec<EntityId>,
+}
+
+/// A named text style shared by the project's text layers.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct TextStyle {
+    pub id: EntityId,
+    pub name: String,
+    pub typography: TypographyOverride,
+    pub based_on: Option<EntityId>,
+}
+
+/// A project term and the rendering translations must use for it.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct GlossaryTerm {
+    pub id: EntityId,
+    pub source: String,
+    pub target: String,
+    pub notes: String,
+    pub case_sensitive: bool,
+    pub do_not_translate: bool,
+}
+
+/// What changed between two revisions or deliveries, as structured data and
+/// as a plain-text report for reviewers.
+#[derive(Clone, Debug, Serialize, Type)]
+pub struct RevisionDiff {
+    pub report: DiffReport,
+    pub text: String,
+}
+
+impl From<DiffReport> for RevisionDiff {
+    fn from(report: DiffReport) -> Self {
+        Self {
+            text: report.to_string(),
+            report,
+        }
+    }
+}
+
+/// An entity a query selected, with the page that contains it.
+#[derive(Clone, Copy, Debug, Serialize, Type)]
+pub struct QueryMatch {
+    pub id: EntityId,
+    pub page: EntityId,
+}
+
 #[derive(Clone, Debug, Serialize, Type)]
 pub struct Geometry {
     pub points: Vec<Point>,
@@ -154,6 +277,8 @@ pub struct SourceText {
 pub struct Translation {
     pub text: String,
     pub language: Option<String>,
+    /// Styled byte ranges of `text`, in order.
+    pub spans: Vec<TextSpan>,
 }
 
 #[derive(Clone, Debug, serde::Deserialize, Serialize, Type)]
@@ -254,8 +379,6 @@ pub(crate) struct Project {
     pub(crate) session: Session,
     pub(crate) name: String,
     pub(crate) active_page: Option<EntityId>,
-    pub(crate) undo: Vec<Vec<Revision>>,
-    pub(crate) redo: Vec<Vec<Revision>>,
 }
 
 impl Project {
@@ -279,8 +402,6 @@ impl Project {
             session,
             name,
             active_page,
-            undo: Vec::new(),
-            redo: Vec::new(),
         }
     }
 
@@ -292,6 +413,44 @@ impl Project {
         self.snapshot().revision()
     }
 
+    /// Compares two recorded revisions; `to` defaults to the current one.
+    /// Logged revisions are replayed on the checkpoint they extend.
+    pub(crate) async fn diff_revisions(
+        &self,
+        from: Revision,
+        to: Option<Revision>,
+    ) -> Result<RevisionDiff> {
+        let before = self.session.open_revision(from).await?;
+        let after = match to {
+            Some(to) => self.session.open_revision(to).await?,
+            None => self.snapshot(),
+        };
+        Ok(after.diff_since(&before)?.into())
+    }
+
+    /// Compares two bundles of one project, such as two deliveries from a
+    /// translator. Each is imported into a scratch directory that is removed
+    /// afterwards.
+    pub(crate) async fn diff_bundles(before: PathBuf, after: PathBuf) -> Result<RevisionDiff> {
+        let scratch = std::env::temp_dir().join(format!("koharu-diff-{}", uuid::Uuid::new_v4()));
+        let report = async {
+            let before = Session::import_bundle(&before, scratch.join("before.khrproj"))
+                .await
+                .with_context(|| format!("failed to import {}", before.display()))?;
+            let after = Session::import_bundle(&after, scratch.join("after.khrproj"))
+                .await
+                .with_context(|| format!("failed to import {}", after.display()))?;
+            anyhow::Ok(after.snapshot().diff_since(&before.snapshot())?)
+        }
+        .await;
+        if let Err(error) = tokio::fs::remove_dir_all(&scratch).await
+            && error.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!(%error, path = %scratch.display(), "failed to remove diff scratch directory");
+        }
+        Ok(report?.into())
+    }
+
     pub(crate) fn active_page(&self) -> Option<EntityId> {
         self.active_page
     }
@@ -317,8 +476,8 @@ impl Project {
             name: self.name.clone(),
             revision: self.revision(),
             active_page: self.active_page,
-            can_undo: !self.undo.is_empty(),
-            can_redo: !self.redo.is_empty(),
+            can_undo: self.session.can_undo(),
+            can_redo: self.session.can_redo(),
         }
     }
 
@@ -345,6 +504,11 @@ impl Project {
                     },
                     source_asset,
                     layer_count,
+                    spread: page
+                        .spread()
+                        .into_iter()
+                        .map(|page| page.id())
+                        .find(|id| *id != page.id()),
                 })
             })
             .collect()
@@ -470,6 +634,52 @@ impl Project {
         ))
     }
 
+    pub(crate) async fn add_shape(
+        &mut self,
+        page: EntityId,
+        frame: Frame,
+        shape: ShapeDraft,
+    ) -> Result<(Commit, EntityId)> {
+        let snapshot = self.snapshot();
+        let geometry = Self::geometry_from_frame(frame)?;
+        let name = format!(
+            "Shape {}",
+            snapshot
+                .children(page)?
+                .filter(|entity| {
+                    snapshot
+                        .component::<SceneShapeLayer>(*entity)
+                        .ok()
+                        .flatten()
+                        .is_some()
+                })
+                .count()
+                + 1
+        );
+        let at = snapshot
+            .page(page)?
+            .text_group()?
+            .map_or(At::End, |group| At::Before(group.id()));
+        let mut layer = None;
+        let patch = snapshot.patch(|edit| {
+            layer = Some(edit.add_shape_layer(page, at, &shape.into_shape(name), &geometry)?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            layer.expect("shape layer was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn set_shape(&mut self, layer: EntityId, shape: ShapeDraft) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        let current = snapshot
+            .component::<SceneShapeLayer>(layer)?
+            .context("layer is not a shape layer")?;
+        let patch = snapshot.patch(|edit| edit.set(layer, &shape.into_shape(current.name)))?;
+        self.commit(patch).await
+    }
+
     pub(crate) async fn set_source_text(
         &mut self,
         layer: EntityId,
@@ -501,26 +711,508 @@ impl Project {
     ) -> Result<Commit> {
         let snapshot = self.snapshot();
         let content = Self::text_content(&snapshot, layer)?;
-        let language = snapshot
-            .component::<SceneTranslation>(content)?
-            .and_then(|translation| translation.language);
+        // Edits target the selected edition, or the translation shown without one.
+        let language = match snapshot.edition_locale()? {
+            Some(edition) => Some(edition),
+            None => snapshot
+                .text_content(content)?
+                .translation()?
+                .and_then(|translation| translation.language),
+        };
         let patch = snapshot.patch(|edit| {
             edit.promote_entity_to_user(layer)?;
             edit.promote_entity_to_user(content)?;
             match text {
-                Some(text) => edit.set(
+                Some(text) => edit.set_translation(
                     content,
                     &SceneTranslation {
                         text: Authored::user(text),
                         language,
                     },
                 ),
-                None => edit.remove::<SceneTranslation>(content),
+                None => edit.remove_translation(content, language.as_ref()),
+            }
+        })?;
+        self.commit(patch).await
+    }
+
+    /// Styles ranges of the translation that `set_translation` would edit.
+    pub(crate) async fn set_translation_spans(
+        &mut self,
+        layer: EntityId,
+        spans: Vec<TextSpan>,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        let content = Self::text_content(&snapshot, layer)?;
+        let language = match snapshot.edition_locale()? {
+            Some(edition) => Some(edition),
+            None => snapshot
+                .text_content(content)?
+                .translation()?
+                .and_then(|translation| translation.language),
+        };
+        let patch = snapshot.patch(|edit| {
+            edit.promote_entity_to_user(layer)?;
+            edit.promote_entity_to_user(content)?;
+            edit.set_translation_spans(content, language.as_ref(), spans)
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn add_annotation(
+        &mut self,
+        anchor: EntityId,
+        author: String,
+        body: String,
+        mark: Option<Vec<Point>>,
+    ) -> Result<(Commit, EntityId)> {
+        let snapshot = self.snapshot();
+        // Two points are the opposite corners of a dragged box.
+        let mark = mark.and_then(|points| match points[..] {
+            [] => None,
+            [pin] => Some(AnnotationMark::Point(pin)),
+            [from, to] => Some(AnnotationMark::Polygon(vec![
+                from,
+                Point { x: to.x, y: from.y },
+                to,
+                Point { x: from.x, y: to.y },
+            ])),
+            _ => Some(AnnotationMark::Polygon(points)),
+        });
+        let value = SceneAnnotation {
+            mark,
+            ..SceneAnnotation::new(author, body)
+        };
+        let mut annotation = None;
+        let patch = snapshot.patch(|edit| {
+            annotation = Some(edit.add_annotation(anchor, &value)?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            annotation.expect("annotation was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn reply_to_annotation(
+        &mut self,
+        annotation: EntityId,
+        author: String,
+        body: String,
+    ) -> Result<Commit> {
+        let patch = self.snapshot().patch(|edit| {
+            edit.reply_to_annotation(annotation, SceneAnnotationReply { author, body })
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn resolve_annotation(
+        &mut self,
+        annotation: EntityId,
+        resolved: bool,
+    ) -> Result<Commit> {
+        let patch = self
+            .snapshot()
+            .patch(|edit| edit.resolve_annotation(annotation, resolved))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn add_character(
+        &mut self,
+        draft: CharacterDraft,
+    ) -> Result<(Commit, EntityId)> {
+        let value = draft.into_character();
+        let mut character = None;
+        let patch = self.snapshot().patch(|edit| {
+            character = Some(edit.add_character(&value)?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            character.expect("character was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn update_character(
+        &mut self,
+        character: EntityId,
+        draft: CharacterDraft,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.character(character)?;
+        let patch = snapshot.patch(|edit| edit.set(character, &draft.into_character()))?;
+        self.commit(patch).await
+    }
+
+    /// Removes a character; the lines it spoke keep their text without a speaker.
+    pub(crate) async fn remove_character(&mut self, character: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.character(character)?;
+        let patch = snapshot.patch(|edit| edit.remove_entity(character, RemovePolicy::Cascade))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn set_speaker(
+        &mut self,
+        layers: Vec<EntityId>,
+        character: Option<EntityId>,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        let contents = layers
+            .iter()
+            .map(|layer| Self::text_content(&snapshot, *layer))
+            .collect::<Result<Vec<_>>>()?;
+        let patch = snapshot.patch(|edit| {
+            for content in contents {
+                edit.promote_entity_to_user(content)?;
+                edit.set_speaker(content, character)?;
             }
+            Ok(())
         })?;
         self.commit(patch).await
     }
 
+    pub(crate) fn characters(snapshot: &Snapshot) -> Result<Vec<Character>> {
+        snapshot
+            .characters()?
+            .into_iter()
+            .map(|character| {
+                let value = character.character()?;
+                Ok(Character {
+                    id: character.id(),
+                    name: value.name,
+                    aliases: value.aliases,
+                    speech_notes: value.speech_notes,
+                    typography: value.typography,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) async fn add_volume(
+        &mut self,
+        draft: HeadingDraft,
+        before: Option<EntityId>,
+    ) -> Result<(Commit, EntityId)> {
+        let value = draft.into_volume();
+        let mut volume = None;
+        let patch = self.snapshot().patch(|edit| {
+            volume = Some(edit.add_volume(&value, Self::at(before))?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            volume.expect("volume was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn update_volume(
+        &mut self,
+        volume: EntityId,
+        draft: HeadingDraft,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.volume(volume)?;
+        let patch = snapshot.patch(|edit| edit.set(volume, &draft.into_volume()))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn move_volume(
+        &mut self,
+        volume: EntityId,
+        before: Option<EntityId>,
+    ) -> Result<Commit> {
+        let patch = self
+            .snapshot()
+            .patch(|edit| edit.move_volume(volume, Self::at(before)))?;
+        self.commit(patch).await
+    }
+
+    /// Removes a volume; its chapters stay in the project where it was.
+    pub(crate) async fn remove_volume(&mut self, volume: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        let chapters = snapshot
+            .volume(volume)?
+            .chapters()?
+            .into_iter()
+            .map(|chapter| chapter.id())
+            .collect::<Vec<_>>();
+        let patch = snapshot.patch(|edit| {
+            for chapter in chapters {
+                edit.move_chapter(chapter, None, At::Before(volume))?;
+            }
+            edit.remove_entity(volume, RemovePolicy::RejectNonEmpty)
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn add_chapter(
+        &mut self,
+        draft: HeadingDraft,
+        volume: Option<EntityId>,
+        before: Option<EntityId>,
+    ) -> Result<(Commit, EntityId)> {
+        let value = draft.into_chapter();
+        let mut chapter = None;
+        let patch = self.snapshot().patch(|edit| {
+            chapter = Some(edit.add_chapter(volume, &value, Self::at(before))?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            chapter.expect("chapter was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn update_chapter(
+        &mut self,
+        chapter: EntityId,
+        draft: HeadingDraft,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.chapter(chapter)?;
+        let patch = snapshot.patch(|edit| edit.set(chapter, &draft.into_chapter()))?;
+        self.commit(patch).await
+    }
+
+    /// Moves a chapter, with its pages, before another chapter of `volume` or
+    /// to the end of it.
+    pub(crate) async fn move_chapter(
+        &mut self,
+        chapter: EntityId,
+        volume: Option<EntityId>,
+        before: Option<EntityId>,
+    ) -> Result<Commit> {
+        let patch = self
+            .snapshot()
+            .patch(|edit| edit.move_chapter(chapter, volume, Self::at(before)))?;
+        self.commit(patch).await
+    }
+
+    /// Removes a chapter; its pages stay in the project without one.
+    pub(crate) async fn remove_chapter(&mut self, chapter: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.chapter(chapter)?;
+        let patch = snapshot.patch(|edit| edit.remove_entity(chapter, RemovePolicy::Cascade))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn set_chapter(
+        &mut self,
+        pages: Vec<EntityId>,
+        chapter: Option<EntityId>,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        for page in &pages {
+            snapshot.page(*page)?;
+        }
+        let patch = snapshot.patch(|edit| {
+            for page in pages {
+                edit.set_chapter(page, chapter)?;
+            }
+            Ok(())
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn set_reading_direction(
+        &mut self,
+        direction: ReadingDirection,
+    ) -> Result<Commit> {
+        let patch = self
+            .snapshot()
+            .patch(|edit| edit.set_project(&Reading { direction }))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn set_spread(
+        &mut self,
+        page: EntityId,
+        partner: Option<EntityId>,
+    ) -> Result<Commit> {
+        let patch = self
+            .snapshot()
+            .patch(|edit| edit.set_spread(page, partner))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) fn volumes(snapshot: &Snapshot) -> Result<Vec<Volume>> {
+        snapshot
+            .volumes()?
+            .into_iter()
+            .map(|volume| {
+                let value = volume.volume()?;
+                Ok(Volume {
+                    id: volume.id(),
+                    title: value.title,
+                    number: value.number,
+                    chapters: volume
+                        .chapters()?
+                        .into_iter()
+                        .map(|chapter| chapter.id())
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn chapters(snapshot: &Snapshot) -> Result<Vec<Chapter>> {
+        snapshot
+            .chapters()?
+            .into_iter()
+            .map(|chapter| {
+                let value = chapter.chapter()?;
+                Ok(Chapter {
+                    id: chapter.id(),
+                    title: value.title,
+                    number: value.number,
+                    volume: chapter.volume()?.map(|volume| volume.id()),
+                    pages: chapter.pages().into_iter().map(|page| page.id()).collect(),
+                })
+            })
+            .collect()
+    }
+
+    fn at(before: Option<EntityId>) -> At {
+        before.map_or(At::End, At::Before)
+    }
+
+    pub(crate) async fn add_text_style(
+        &mut self,
+        draft: TextStyleDraft,
+    ) -> Result<(Commit, EntityId)> {
+        let (value, based_on) = draft.into_style();
+        let mut style = None;
+        let patch = self.snapshot().patch(|edit| {
+            style = Some(edit.add_text_style(&value, based_on)?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            style.expect("text style was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn update_text_style(
+        &mut self,
+        style: EntityId,
+        draft: TextStyleDraft,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.text_style(style)?;
+        let (value, based_on) = draft.into_style();
+        let patch = snapshot.patch(|edit| {
+            edit.set(style, &value)?;
+            edit.set_text_style_base(style, based_on)
+        })?;
+        self.commit(patch).await
+    }
+
+    /// Removes a text style; layers that used it keep their own typography,
+    /// and styles based on it stand alone.
+    pub(crate) async fn remove_text_style(&mut self, style: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.text_style(style)?;
+        let patch = snapshot.patch(|edit| edit.remove_entity(style, RemovePolicy::Cascade))?;
+        self.commit(patch).await
+    }
+
+    /// Applies a text style to the selected layers, or clears theirs. With
+    /// `clear_overrides`, the layers also drop their own typography so the
+    /// style shows unchanged.
+    pub(crate) async fn apply_text_style(
+        &mut self,
+        layers: Vec<EntityId>,
+        style: Option<EntityId>,
+        clear_overrides: bool,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        for layer in &layers {
+            snapshot.text_layer(*layer)?;
+        }
+        let patch = snapshot.patch(|edit| {
+            for layer in layers {
+                edit.promote_entity_to_user(layer)?;
+                edit.set_text_style(layer, style)?;
+                if clear_overrides && snapshot.component::<SceneTypography>(layer)?.is_some() {
+                    edit.remove::<SceneTypography>(layer)?;
+                }
+            }
+            Ok(())
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) fn text_styles(snapshot: &Snapshot) -> Result<Vec<TextStyle>> {
+        snapshot
+            .text_styles()?
+            .into_iter()
+            .map(|style| {
+                let value = style.style()?;
+                Ok(TextStyle {
+                    id: style.id(),
+                    name: value.name,
+                    typography: value.typography,
+                    based_on: style.base()?.map(|base| base.id()),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) async fn add_glossary_term(
+        &mut self,
+        draft: GlossaryTermDraft,
+    ) -> Result<(Commit, EntityId)> {
+        let value = draft.into_term();
+        let mut term = None;
+        let patch = self.snapshot().patch(|edit| {
+            term = Some(edit.add_glossary_term(&value)?);
+            Ok(())
+        })?;
+        Ok((
+            self.commit(patch).await?,
+            term.expect("glossary term was added while building the patch"),
+        ))
+    }
+
+    pub(crate) async fn update_glossary_term(
+        &mut self,
+        term: EntityId,
+        draft: GlossaryTermDraft,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.glossary_term(term)?;
+        let patch = snapshot.patch(|edit| edit.set(term, &draft.into_term()))?;
+        self.commit(patch).await
+    }
+
+    /// Removes a glossary term; translations already flagged for it keep
+    /// their notes.
+    pub(crate) async fn remove_glossary_term(&mut self, term: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        snapshot.glossary_term(term)?;
+        let patch = snapshot.patch(|edit| edit.remove_entity(term, RemovePolicy::Cascade))?;
+        self.commit(patch).await
+    }
+
+    pub(crate) fn glossary(snapshot: &Snapshot) -> Result<Vec<GlossaryTerm>> {
+        snapshot
+            .glossary()?
+            .into_iter()
+            .map(|term| {
+                let value = term.term()?;
+                Ok(GlossaryTerm {
+                    id: term.id(),
+                    source: value.source,
+                    target: value.target,
+                    notes: value.notes,
+                    case_sensitive: value.case_sensitive,
+                    do_not_translate: value.do_not_translate,
+                })
+            })
+            .collect()
+    }
+
     pub(crate) async fn set_typography(
         &mut self,
         updates: Vec<TypographyUpdate>,
@@ -565,11 +1257,20 @@ impl Project {
         let updates = updates
             .into_iter()
             .map(|update| {
+                if snapshot
+                    .component::<SceneShapeLayer>(update.layer)?
+                    .is_some()
+                {
+                    if update.points.is_none() {
+                        bail!("shape layers cannot reset their geometry");
+                    }
+                    return Ok((update, None));
+                }
                 if snapshot
                     .component::<SceneTextLayout>(update.layer)?
                     .is_none()
                 {
-                    bail!("only text layers can change geometry");
+                    bail!("only text and shape layers can change geometry");
                 }
                 let content = Self::text_content(&snapshot, update.layer)?;
                 if update.points.is_none()
@@ -580,25 +1281,21 @@ impl Project {
                 {
                     bail!("only automatically placed text can reset its geometry");
                 }
-                Ok((update, content))
+                Ok((update, Some(content)))
             })
             .collect::<Result<Vec<_>>>()?;
         let patch = snapshot.patch(|edit| {
             for (update, content) in updates {
                 edit.promote_entity_to_user(update.layer)?;
-                edit.promote_entity_to_user(content)?;
+                if let Some(content) = content {
+                    edit.promote_entity_to_user(content)?;
+                }
                 match update.points {
                     Some(points) => edit.set(
                         update.layer,
                         &SceneGeometry {
                             origin: Origin::User,
-                            points: points
-                                .into_iter()
-                                .map(|point| ScenePoint {
-                                    x: point.x,
-                                    y: point.y,
-                                })
-                                .collect(),
+                            points,
                         },
                     )?,
                     None => edit.remove::<SceneGeometry>(update.layer)?,
@@ -640,6 +1337,52 @@ impl Project {
         self.commit(patch).await
     }
 
+    pub(crate) async fn set_blending(
+        &mut self,
+        layers: Vec<EntityId>,
+        mode: Option<BlendMode>,
+        clipped: Option<bool>,
+    ) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        for layer in &layers {
+            if snapshot.component::<SceneRasterLayer>(*layer)?.is_none() {
+                bail!("only raster layers can change blending");
+            }
+        }
+        let patch = snapshot.patch(|edit| {
+            for layer in layers {
+                let mut value =
+                    snapshot
+                        .component::<SceneBlending>(layer)?
+                        .unwrap_or(SceneBlending {
+                            origin: Origin::User,
+                            mode: BlendMode::Normal,
+                            clipped: false,
+                        });
+                if let Some(mode) = mode {
+                    value.mode = mode;
+                }
+                if let Some(clipped) = clipped {
+                    value.clipped = clipped;
+                }
+                value.origin = Origin::User;
+                edit.set(layer, &value)?;
+            }
+            Ok(())
+        })?;
+        self.commit(patch).await
+    }
+
+    pub(crate) async fn clear_mask(&mut self, layer: EntityId) -> Result<Commit> {
+        let snapshot = self.snapshot();
+        if snapshot.component::<SceneRasterLayer>(layer)?.is_none() {
+            bail!("only raster layers have masks");
+        }
+        let mask = AssetRole::new("mask")?;
+        let patch = snapshot.patch(|edit| edit.remove_asset(layer, &mask))?;
+        self.commit(patch).await
+    }
+
     pub(crate) async fn delete_layers(&mut self, layers: Vec<EntityId>) -> Result<Commit> {
         let snapshot = self.snapshot();
         let mut expanded = Vec::new();
@@ -714,17 +1457,22 @@ impl Project {
         let geometries = geometries
             .into_iter()
             .map(|(element, geometry)| {
+                if snapshot.component::<SceneShapeLayer>(element)?.is_some() {
+                    return Ok((element, geometry, None));
+                }
                 if snapshot.component::<SceneTextLayout>(element)?.is_none() {
-                    bail!("only text layers can change geometry");
+                    bail!("only text and shape layers can change geometry");
                 }
                 let content = Self::text_content(&snapshot, element)?;
-                Ok((element, geometry, content))
+                Ok((element, geometry, Some(content)))
             })
             .collect::<Result<Vec<_>>>()?;
         let patch = snapshot.patch(|edit| {
             for (element, mut geometry, content) in geometries {
                 edit.promote_entity_to_user(element)?;
-                edit.promote_entity_to_user(content)?;
+                if let Some(content) = content {
+                    edit.promote_entity_to_user(content)?;
+                }
                 geometry.origin = Origin::User;
                 edit.set(element, &geometry)?;
             }
@@ -758,6 +1506,11 @@ impl Project {
         if width == 0 || height == 0 {
             bail!("page dimensions must be positive");
         }
+        let role = AssetRole::new(if mode == RasterStrokeMode::Mask {
+            "mask"
+        } else {
+            "source"
+        })?;
         let mut raster_layer = None;
         let mut promote_layer = false;
         let mut image = if let Some(layer) = layer {
@@ -772,16 +1525,21 @@ impl Project {
                     .component::<EntityOrigin>(layer)?
                     .is_some_and(|origin| origin.origin != Origin::User);
             raster_layer = Some(target);
-            match snapshot.asset(layer, &AssetRole::new("source")?)? {
+            match snapshot.asset(layer, &role)? {
                 Some(asset) => {
                     let bytes = snapshot.read_blob(asset.blob).await?;
                     image::load_from_memory(&bytes)?.to_rgba8()
                 }
+                None if mode == RasterStrokeMode::Mask => {
+                    RgbaImage::from_pixel(width, height, Rgba([255; 4]))
+                }
                 None => RgbaImage::new(width, height),
             }
         } else {
-            if mode == RasterStrokeMode::Erase {
-                bail!("eraser requires a raster layer target");
+            match mode {
+                RasterStrokeMode::Paint => {}
+                RasterStrokeMode::Erase => bail!("eraser requires a raster layer target"),
+                RasterStrokeMode::Mask => bail!("a mask requires a raster layer target"),
             }
             RgbaImage::new(width, height)
         };
@@ -791,7 +1549,6 @@ impl Project {
         rasterize_stroke(&mut image, mode, color, diameter, &points);
         let mut bytes = Cursor::new(Vec::new());
         DynamicImage::ImageRgba8(image).write_to(&mut bytes, ImageFormat::Png)?;
-        let source = AssetRole::new("source")?;
         let name = format!(
             "Paint {}",
             snapshot
@@ -837,7 +1594,7 @@ impl Project {
             };
             edit.set_asset(
                 layer,
-                &source,
+                &role,
                 AssetInput::new(
                     bytes.into_inner(),
                     "image/png",
@@ -856,42 +1613,11 @@ impl Project {
     }
 
     pub(crate) async fn undo(&mut self) -> Result<Commit> {
-        let revisions = self.undo.pop().ok_or_else(|| anyhow!("nothing to undo"))?;
-        let commit = match self.session.undo_many(revisions.iter().copied()).await {
-            Ok(commit) => commit,
-            Err(error) => {
-                self.undo.push(revisions);
-                return Err(error.into());
-            }
-        };
-        self.redo.push(vec![commit.revision]);
-        Ok(commit)
+        Ok(self.session.undo_last().await?)
     }
 
     pub(crate) async fn redo(&mut self) -> Result<Commit> {
-        let revisions = self.redo.pop().ok_or_else(|| anyhow!("nothing to redo"))?;
-        let commit = match self.session.undo_many(revisions.iter().copied()).await {
-            Ok(commit) => commit,
-            Err(error) => {
-                self.redo.push(revisions);
-                return Err(error.into());
-            }
-        };
-        self.undo.push(vec![commit.revision]);
-        Ok(commit)
-    }
-
-    pub(crate) fn record(&mut self, revisions: Vec<Revision>) {
-        if !revisions.is_empty() {
-            self.undo.push(revisions);
-            self.redo.clear();
-        }
-    }
-
-    pub(crate) fn record_commit(&mut self, commit: &Commit) {
-        if commit.changes.to != commit.changes.from {
-            self.record(vec![commit.revision]);
-        }
+        Ok(self.session.redo_last().await?)
     }
 
     pub(crate) async fn commit_rebased(
@@ -970,6 +1696,12 @@ impl Project {
             .into_iter()
             .flatten()
             .collect();
+        let mut annotations = Vec::new();
+        for entity in snapshot.children(page)? {
+            if snapshot.component::<SceneAnnotation>(entity)?.is_some() {
+                annotations.push(Self::annotation_view(snapshot, entity)?);
+            }
+        }
         Ok(Page {
             id: page,
             label: value.label,
@@ -979,6 +1711,48 @@ impl Project {
             },
             layers,
             regions,
+            annotations,
+        })
+    }
+
+    pub(crate) fn query(snapshot: &Snapshot, query: &Query) -> Result<Vec<QueryMatch>> {
+        snapshot
+            .query(query)?
+            .into_iter()
+            .map(|entity| {
+                let mut page = entity.id();
+                while let Some(parent) = snapshot.parent(page)? {
+                    page = parent;
+                }
+                Ok(QueryMatch {
+                    id: entity.id(),
+                    page,
+                })
+            })
+            .collect()
+    }
+
+    fn annotation_view(snapshot: &Snapshot, id: EntityId) -> Result<Annotation> {
+        let annotation = snapshot.annotation(id)?;
+        let value = annotation.annotation()?;
+        Ok(Annotation {
+            id,
+            anchor: annotation.anchor()?,
+            author: value.author,
+            body: value.body,
+            mark: value.mark.map(|mark| match mark {
+                AnnotationMark::Point(pin) => vec![pin],
+                AnnotationMark::Polygon(points) => points,
+            }),
+            replies: value
+                .replies
+                .into_iter()
+                .map(|reply| AnnotationReply {
+                    author: reply.author,
+                    body: reply.body,
+                })
+                .collect(),
+            resolved: value.resolved,
         })
     }
 
@@ -1003,14 +1777,20 @@ impl Project {
                 text: source.text.value,
                 language: source.language.map(|language| language.to_string()),
             });
-            let translation = content.translation()?.map(|translation| Translation {
-                text: translation.text.value,
-                language: translation.language.map(|language| language.to_string()),
-            });
+            let translation = match content.translation()? {
+                Some(translation) => Some(Translation {
+                    spans: content.spans(&translation)?,
+                    text: translation.text.value,
+                    language: translation.language.map(|language| language.to_string()),
+                }),
+                None => None,
+            };
             let role = content.role()?.map(|role| role.role);
             let source_region = content.source_region()?.map(|region| region.id());
+            let speaker = content.speaker()?.map(|character| character.id());
             let automatic_region = text_layer.automatic_target()?.map(|region| region.id());
             let typography = text_layer.typography()?.map(Self::typography_view);
+            let style = text_layer.style()?.map(|style| style.id());
             return Ok(Layer::Text {
                 id: layer,
                 parent,
@@ -1024,13 +1804,16 @@ impl Project {
                     translation,
                     role,
                     source_region,
+                    speaker,
                 }),
                 typography,
+                style,
                 layout: layout.kind,
                 automatic_region,
             });
         }
         if let Some(raster) = snapshot.component::<SceneRasterLayer>(layer)? {
+            let blending = snapshot.component::<SceneBlending>(layer)?;
             return Ok(Layer::Raster {
                 id: layer,
                 parent,
@@ -1038,6 +1821,26 @@ impl Project {
                 image: Self::asset_id(snapshot, layer, "source")?,
                 name: raster.name,
                 kind: raster.kind,
+                blend: blending
+                    .as_ref()
+                    .map_or(BlendMode::Normal, |blending| blending.mode),
+                clipped: blending.is_some_and(|blending| blending.clipped),
+                mask: Self::asset_id(snapshot, layer, "mask")?,
+            });
+        }
+        if let Some(shape) = snapshot.component::<SceneShapeLayer>(layer)? {
+            return Ok(Layer::Shape {
+                id: layer,
+                parent,
+                geometry: snapshot
+                    .component::<SceneGeometry>(layer)?
+                    .map(Self::geometry_view)
+                    .context("shape layer has no geometry")?,
+                visibility,
+                name: shape.name,
+                outline: shape.outline,
+                fill: shape.fill,
+                stroke: shape.stroke,
             });
         }
         let geometry = snapshot
@@ -1108,14 +1911,7 @@ impl Project {
 
     fn geometry_view(geometry: SceneGeometry) -> Geometry {
         Geometry {
-            points: geometry
-                .points
-                .into_iter()
-                .map(|point| Point {
-                    x: point.x,
-                    y: point.y,
-                })
-                .collect(),
+            points: geometry.points,
         }
     }
 
@@ -1129,6 +1925,7 @@ impl Project {
         Ok(snapshot.component::<SceneGroup>(entity)?.is_some()
             || snapshot.component::<SceneTextLayout>(entity)?.is_some()
             || snapshot.component::<SceneRasterLayer>(entity)?.is_some()
+            || snapshot.component::<SceneShapeLayer>(entity)?.is_some()
             || (snapshot.component::<SceneGeometry>(entity)?.is_some()
                 && snapshot
                     .asset(entity, &AssetRole::new("source")?)?
@@ -1298,7 +2095,7 @@ fn rasterize_stroke(
                 }
                 let pixel = image.get_pixel_mut(x, y);
                 match mode {
-                    RasterStrokeMode::Paint => {
+                    RasterStrokeMode::Paint | RasterStrokeMode::Mask => {
                         let source_alpha = f32::from(color[3]) / 255.0 * coverage;
                         let destination_alpha = f32::from(pixel[3]) / 255.0;
                         let output_alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
@@ -1326,7 +2123,7 @@ fn rasterize_stroke(
 
 #[cfg(test)]
 mod tests {
-    use koharu_scene::{Generation, ProducerId, WritingMode};
+    use koharu_scene::{Generation, PageChange, ProducerId, WritingMode};
 
     use super::*;
 
@@ -1438,6 +2235,51 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn revisions_that_were_only_logged_can_be_diffed() {
+        let mut session = Session::memory().await.unwrap();
+        let mut setup = session.snapshot().edit();
+        for index in 0..8 {
+            setup
+                .add_page(
+                    PageDraft::new(format!("page {index}"), 100.0, 100.0),
+                    At::End,
+                )
+                .unwrap();
+        }
+        session.commit(setup.finish().unwrap()).await.unwrap();
+        let mut project = Project::new(session, "test".to_owned());
+
+        let mut revisions = Vec::new();
+        for label in ["logged a", "logged b", "logged c"] {
+            let patch = project
+                .snapshot()
+                .patch(|edit| {
+                    edit.add_page(PageDraft::new(label, 100.0, 100.0), At::End)?;
+                    Ok(())
+                })
+                .unwrap();
+            let commit = project.commit(patch).await.unwrap();
+            assert!(!commit.checkpoint);
+            revisions.push(commit.revision);
+        }
+
+        let diff = project
+            .diff_revisions(revisions[0], Some(revisions[1]))
+            .await
+            .unwrap();
+        assert_eq!(diff.report.from, revisions[0]);
+        assert_eq!(diff.report.to, revisions[1]);
+        assert_eq!(
+            diff.report
+                .pages
+                .iter()
+                .map(|page| (page.page.label.as_str(), page.change))
+                .collect::<Vec<_>>(),
+            [("logged b", PageChange::Added { position: 9 })]
+        );
+    }
+
     #[test]
     fn raster_strokes_are_continuous_and_erasable() {
         let mut image = RgbaImage::new(32, 16);
@@ -811,6 +811,7 @@ impl CanvasState {
         let mut commands = Vec::new();
         let mut vectors = Scene::new();
         let mut vectors_pending = false;
+        let mut base_drawn = false;
         for layer in frame.layers() {
             let transform = normalize
                 * self
@@ -834,15 +835,20 @@ impl CanvasState {
                     page_rect,
                     viewport_rect,
                 );
-                if presentation.visible
+                let shown = presentation.visible
                     && presentation.opacity.is_finite()
-                    && presentation.opacity > 0.0
-                {
+                    && presentation.opacity > 0.0;
+                if !presentation.clipped {
+                    base_drawn = shown;
+                }
+                if shown && (!presentation.clipped || base_drawn) {
                     commands.push(CompositionCommand::Raster(RasterDraw {
                         image: image.clone(),
                         transform: camera * transform * layer.placement(),
                         opacity: presentation.opacity.clamp(0.0, 1.0),
                         erase: erase.and_then(|edit| edit.layer) == Some(layer.id()),
+                        blend: presentation.blend,
+                        clipped: presentation.clipped,
                     }));
                 }
             } else {
@@ -904,7 +910,11 @@ fn raster_tiles_for_source(
         .layers
         .iter()
         .filter_map(|layer| match &layer.content {
-            PreparedContent::Raster(raster) if raster.source == source => Some(&raster.tiles),
+            PreparedContent::Raster(raster)
+                if raster.source == source || raster.mask == Some(source) =>
+            {
+                Some(&raster.tiles)
+            }
             _ => None,
         })
         .flatten()
@@ -1393,7 +1403,7 @@ impl WebCanvas {
                 .layer(id)
                 .ok_or_else(|| js_message("transform element is not in the prepared frame"))?;
             let presentation = layer.presentation();
-            if layer.kind() != LayerKind::Text
+            if !matches!(layer.kind(), LayerKind::Text | LayerKind::Shape)
                 || !presentation.visible
                 || presentation.opacity <= 0.0
             {
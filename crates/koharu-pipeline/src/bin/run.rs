@@ -10,14 +10,16 @@ use anyhow::{Context as _, Result};
 use clap::{Parser, ValueEnum};
 use koharu_config::Config;
 use koharu_pipeline::{
-    Committer, DetectionModel, Flux2KleinConfig, InpaintingModel, KoharuLayoutRFDetrSeg2XLConfig,
-    OcrModel, Operation, Pipeline, PipelineConfig, Progress, Request, RoremMixedConfig, Scope,
-    StageOutput, TranslationConfig,
+    Committer, DetectionModel, Flux2KleinConfig, FontsConfig, InpaintingModel,
+    KoharuLayoutRFDetrSeg2XLConfig, OcrModel, Operation, Pipeline, PipelineConfig, Progress,
+    Request, RoremMixedConfig, Scope, SfxConfig, SfxTreatment, StageOutput, TranslationConfig,
 };
 use koharu_rasterizer::{RasterOptions, Rasterizer};
 use koharu_renderer::Renderer;
 use koharu_scene::{AssetInput, AssetMetadata, AssetRole, At, PageDraft, Session};
-use koharu_translator::{GenerationConfig, Language, ModelSelection, Provider, ProvidersConfig};
+use koharu_translator::{
+    GenerationConfig, Language, ModelSelection, Provider, ProvidersConfig, TranslationMemory,
+};
 
 #[derive(Debug, Parser)]
 #[command(version, about = "Run Koharu's complete in-process pipeline")]
@@ -34,6 +36,12 @@ struct Arguments {
     #[arg(long, value_enum, default_value = "paddleocr-vl-1.6")]
     ocr: OcrChoice,
 
+    #[arg(long, value_enum, default_value = "leave")]
+    sfx: SfxChoice,
+
+    #[arg(long)]
+    no_font_matching: bool,
+
     #[arg(long, value_enum, default_value = "lama")]
     inpainting: InpaintingChoice,
 
@@ -75,6 +83,13 @@ enum OcrChoice {
     BaberuOcr,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum SfxChoice {
+    Leave,
+    Replace,
+    Annotate,
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum InpaintingChoice {
     #[value(name = "lama")]
@@ -113,6 +128,19 @@ impl Arguments {
                 generation: GenerationConfig::default(),
                 target_language: self.target_language,
                 instructions: self.translation_instructions.clone(),
+                ..TranslationConfig::default()
+            },
+            sfx: SfxConfig {
+                enabled: true,
+                treatment: match self.sfx {
+                    SfxChoice::Leave => SfxTreatment::Leave,
+                    SfxChoice::Replace => SfxTreatment::Replace,
+                    SfxChoice::Annotate => SfxTreatment::Annotate,
+                },
+            },
+            fonts: FontsConfig {
+                detect: !self.no_font_matching,
+                ..FontsConfig::default()
             },
             inpainting: match self.inpainting {
                 InpaintingChoice::LaMa => InpaintingModel::LaMa {},
@@ -176,6 +204,7 @@ async fn main() -> Result<()> {
     let pipeline = Pipeline::from_config(
         Config::memory(arguments.pipeline_config()),
         Config::memory(ProvidersConfig::default()),
+        TranslationMemory::memory(),
         device,
     )?;
     let snapshot = session.snapshot();
@@ -10,6 +10,8 @@ pub struct PipelineConfig {
     pub detection: DetectionModel,
     pub ocr: OcrModel,
     pub translation: TranslationConfig,
+    pub sfx: SfxConfig,
+    pub fonts: FontsConfig,
     pub inpainting: InpaintingModel,
     /// Settings for every model are kept independently of the active model.
     /// The active stage fields above only select which profile is used.
@@ -22,6 +24,8 @@ struct PipelineFile {
     detection: ModelSelection,
     ocr: ModelSelection,
     translation: TranslationConfig,
+    sfx: SfxConfig,
+    fonts: FontsConfig,
     inpainting: ModelSelection,
     #[serde(default)]
     processor: ProcessorConfig,
@@ -37,6 +41,8 @@ impl Default for PipelineFile {
                 model: "paddleocr-vl-1.6".to_owned(),
             },
             translation: TranslationConfig::default(),
+            sfx: SfxConfig::default(),
+            fonts: FontsConfig::default(),
             inpainting: ModelSelection {
                 model: "lama".to_owned(),
             },
@@ -92,6 +98,8 @@ impl Serialize for PipelineConfig {
                 model: ocr.to_owned(),
             },
             translation: self.translation.clone(),
+            sfx: self.sfx.clone(),
+            fonts: self.fonts.clone(),
             inpainting: ModelSelection {
                 model: inpainting.to_owned(),
             },
@@ -149,6 +157,8 @@ impl<'de> Deserialize<'de> for PipelineConfig {
             detection,
             ocr,
             translation: file.translation,
+            sfx: file.sfx,
+            fonts: file.fonts,
             inpainting,
             processor: file.processor,
         })
@@ -163,6 +173,8 @@ impl Default for PipelineConfig {
             ),
             ocr: OcrModel::PaddleOcrVl1_6,
             translation: TranslationConfig::default(),
+            sfx: SfxConfig::default(),
+            fonts: FontsConfig::default(),
             inpainting: InpaintingModel::LaMa {},
             processor: ProcessorConfig::default(),
         }
@@ -176,6 +188,13 @@ pub struct TranslationConfig {
     #[specta(type = String)]
     pub target_language: Language,
     pub instructions: Option<String>,
+    /// How many preceding pages, in reading order, are sent as context.
+    #[serde(default = "default_context_pages")]
+    pub context_pages: u32,
+}
+
+fn default_context_pages() -> u32 {
+    2
 }
 
 impl Default for TranslationConfig {
@@ -185,10 +204,114 @@ impl Default for TranslationConfig {
             generation: GenerationConfig::default(),
             target_language: Language::English,
             instructions: None,
+            context_pages: default_context_pages(),
+        }
+    }
+}
+
+/// How recognized sound effects are presented on the translated page. The
+/// treatment is applied when a page's sound effects are read; pages that
+/// already have them keep theirs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct SfxConfig {
+    /// Whether full, translation, and inpainting runs read sound effects.
+    /// Runs that name the SFX stage read them either way.
+    pub enabled: bool,
+    pub treatment: SfxTreatment,
+}
+
+impl SfxConfig {
+    /// Whether runs that do not name the SFX stage still schedule it. Left
+    /// sound effects change nothing on the page, so they are only read on
+    /// request.
+    pub(crate) fn scheduled(&self) -> bool {
+        self.enabled && self.treatment != SfxTreatment::Leave
+    }
+
+    /// Whether the stage writes a mask the inpainting stage merges.
+    pub(crate) fn masks(&self) -> bool {
+        self.treatment == SfxTreatment::Replace
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SfxTreatment {
+    /// Keep the original lettering and letter the translation in a hidden
+    /// layer, kept for reference.
+    #[default]
+    Leave,
+    /// Clean the original lettering and letter the translation in its place.
+    Replace,
+    /// Keep the original lettering and letter the translation beside it.
+    Annotate,
+}
+
+/// How the source font predicted for each detected text region is matched to
+/// the family its translation is lettered in. Detection is off by default, so
+/// the font model is only loaded once it is asked for, and the default
+/// mappings only set weights: a named family may not be installed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct FontsConfig {
+    /// Whether detection predicts the source font of each text region.
+    pub detect: bool,
+    /// The family and weight each class of source font is lettered in.
+    /// Classes without a mapping keep the typesetting font stack.
+    pub families: Vec<FontMapping>,
+}
+
+impl Default for FontsConfig {
+    fn default() -> Self {
+        Self {
+            detect: false,
+            families: vec![
+                FontMapping {
+                    source: SourceFontClass::BoldGothic,
+                    family: None,
+                    weight: Some(700),
+                },
+                FontMapping {
+                    source: SourceFontClass::BoldMincho,
+                    family: None,
+                    weight: Some(700),
+                },
+            ],
         }
     }
 }
 
+impl FontsConfig {
+    /// The mapping for a class of source font, if one is configured.
+    #[must_use]
+    pub fn mapping(&self, source: SourceFontClass) -> Option<&FontMapping> {
+        self.families
+            .iter()
+            .find(|mapping| mapping.source == source)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct FontMapping {
+    pub source: SourceFontClass,
+    /// Tried ahead of the typesetting font stack, which letters the text when
+    /// the family is not installed.
+    pub family: Option<String>,
+    pub weight: Option<u16>,
+}
+
+/// The classes font predictions are reduced to. Japanese lettering
+/// distinguishes sans-serif gothic faces from serif mincho faces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceFontClass {
+    Gothic,
+    BoldGothic,
+    Mincho,
+    BoldMincho,
+}
+
 impl PipelineConfig {
     pub fn load() -> anyhow::Result<koharu_config::Config<Self>> {
         koharu_config::load("pipeline")
@@ -421,6 +544,44 @@ mod tests {
         assert_eq!(config.prompt, "saved prompt");
     }
 
+    #[test]
+    fn parses_source_font_mappings() {
+        let config = toml::from_str::<PipelineConfig>(
+            r#"
+                [fonts]
+                detect = true
+
+                [[fonts.families]]
+                source = "bold_gothic"
+                family = "Komika Axis"
+
+                [[fonts.families]]
+                source = "mincho"
+                family = "Noto Serif"
+                weight = 500
+            "#,
+        )
+        .unwrap();
+
+        assert!(config.fonts.detect);
+        assert_eq!(
+            config.fonts.mapping(SourceFontClass::BoldGothic),
+            Some(&FontMapping {
+                source: SourceFontClass::BoldGothic,
+                family: Some("Komika Axis".to_owned()),
+                weight: None,
+            })
+        );
+        assert_eq!(
+            config
+                .fonts
+                .mapping(SourceFontClass::Mincho)
+                .and_then(|mapping| mapping.weight),
+            Some(500)
+        );
+        assert_eq!(config.fonts.mapping(SourceFontClass::Gothic), None);
+    }
+
     #[test]
     fn serializes_model_profiles_under_processor() {
         let config = PipelineConfig {
@@ -430,6 +591,14 @@ mod tests {
             }),
             ocr: OcrModel::PaddleOcrVl1_6,
             translation: TranslationConfig::default(),
+            sfx: SfxConfig {
+                enabled: true,
+                treatment: SfxTreatment::Annotate,
+            },
+            fonts: FontsConfig {
+                detect: false,
+                ..Default::default()
+            },
             inpainting: InpaintingModel::Flux2Klein(Flux2KleinConfig {
                 prompt: "Keep the line art.".to_owned(),
             }),
@@ -440,9 +609,12 @@ mod tests {
         assert!(document.contains("[processor.koharu-layout-rfdetr-seg-2xl]"));
         assert!(document.contains("[processor.flux2-klein]"));
         assert!(document.contains("[translation]"));
+        assert!(document.contains("[sfx]\nenabled = true\ntreatment = \"annotate\""));
         assert!(!document.contains("prompt = \"Keep the line art.\"\n[inpainting]"));
 
         let restored = toml::from_str::<PipelineConfig>(&document).unwrap();
+        assert_eq!(restored.sfx, config.sfx);
+        assert_eq!(restored.fonts, config.fonts);
         assert!(matches!(
             restored.inpainting().unwrap(),
             InpaintingModel::Flux2Klein(config) if config.prompt == "Keep the line art."
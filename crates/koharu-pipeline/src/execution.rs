@@ -50,11 +50,12 @@ impl<'a> Execution<'a> {
         let base = snapshot.revision();
         let stages = request
             .operation
-            .stages()
+            .stages(runner.sfx())
             .map_err(|error| PipelineError::new(ErrorKind::InvalidInput, None, error))?;
         let scope = NormalizedScope::new(&snapshot, &request.scope, &stages)
             .map_err(|error| PipelineError::new(ErrorKind::InvalidInput, None, error))?;
         let pages = scope.pages().to_vec();
+        let scheduler = Scheduler::new(&pages, &stages, runner.sfx().masks());
         if let Some(mask) = request.inpainting_mask.as_ref()
             && (!pages.contains(&mask.page) || !stages.contains(&Stage::Inpainting))
         {
@@ -79,7 +80,7 @@ impl<'a> Execution<'a> {
             stop: request.stop,
             progress: request.progress,
             scope,
-            scheduler: Scheduler::new(&pages, &stages),
+            scheduler,
             scene: snapshot,
             images: BTreeMap::new(),
             busy_stages: BTreeSet::new(),
@@ -213,6 +214,7 @@ impl<'a> Execution<'a> {
         validate_commit(&self.scene, &next)
             .map_err(|error| PipelineError::new(ErrorKind::Commit, Some(stage), error))?;
         self.scene = next;
+        self.runner.committed(stage, page, &self.scene).await;
         Ok(true)
     }
 
@@ -18,7 +18,8 @@ mod stage_runner;
 mod stages;
 
 pub use config::{
-    DetectionModel, InpaintingModel, OcrModel, PipelineConfig, ProcessorConfig, TranslationConfig,
+    DetectionModel, FontMapping, FontsConfig, InpaintingModel, OcrModel, PipelineConfig,
+    ProcessorConfig, SfxConfig, SfxTreatment, SourceFontClass, TranslationConfig,
 };
 pub use error::{ErrorKind, PipelineError};
 pub use pipeline::Pipeline;
@@ -13,6 +13,7 @@ use crate::{
 #[derive(Clone)]
 pub struct Pipeline {
     current: Arc<ArcSwap<StageRunner>>,
+    memory: koharu_translator::TranslationMemory,
     resources: Arc<ResourceMonitor>,
     execution: Arc<tokio::sync::Mutex<()>>,
 }
@@ -22,6 +23,15 @@ impl Pipeline {
         Self::from_config(
             PipelineConfig::load()?,
             koharu_translator::ProvidersConfig::load()?,
+            // An unreadable memory is left untouched for the editor to
+            // recover, and this session's lines are not saved over it.
+            koharu_translator::TranslationMemory::load().unwrap_or_else(|error| {
+                tracing::warn!(
+                    %error,
+                    "failed to load the translation memory; using an unsaved one"
+                );
+                koharu_translator::TranslationMemory::memory()
+            }),
             device,
         )
     }
@@ -30,17 +40,25 @@ impl Pipeline {
     pub fn from_config(
         config: Config<PipelineConfig>,
         providers: Config<koharu_translator::ProvidersConfig>,
+        memory: koharu_translator::TranslationMemory,
         device: koharu_ml::Device,
     ) -> Result<Self> {
         let translator = koharu_translator::Translator::from_config(device.clone(), providers)?;
         let resources = ResourceMonitor::new(&device);
         let runner = {
             let value = config.read()?;
-            StageRunner::new(&value, translator.clone(), &device, resources.clone())?
+            StageRunner::new(
+                &value,
+                translator.clone(),
+                memory.clone(),
+                &device,
+                resources.clone(),
+            )?
         };
         let current = Arc::new(ArcSwap::from_pointee(runner));
         let watched = current.clone();
         let watched_resources = resources.clone();
+        let watched_memory = memory.clone();
         let _watcher = tokio::runtime::Handle::try_current()
             .context("pipeline requires a Tokio runtime")?
             .spawn(async move {
@@ -50,6 +68,7 @@ impl Pipeline {
                         StageRunner::new(
                             &value,
                             translator.clone(),
+                            watched_memory.clone(),
                             &device,
                             watched_resources.clone(),
                         )
@@ -62,11 +81,17 @@ impl Pipeline {
             });
         Ok(Self {
             current,
+            memory,
             resources,
             execution: Arc::new(tokio::sync::Mutex::new(())),
         })
     }
 
+    /// The translation memory the translation stage reads and extends.
+    pub fn translation_memory(&self) -> &koharu_translator::TranslationMemory {
+        &self.memory
+    }
+
     pub fn subscribe_resources(&self) -> tokio::sync::watch::Receiver<ResourceSnapshot> {
         self.resources.start();
         self.resources.subscribe()
@@ -7,7 +7,7 @@ use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
-use crate::{ProgressSink, Scope, Stage};
+use crate::{ProgressSink, Scope, SfxConfig, Stage};
 use koharu_scene::EntityId;
 
 #[derive(Clone, Debug)]
@@ -33,21 +33,24 @@ pub enum Operation {
 }
 
 impl Operation {
-    pub(crate) fn stages(&self) -> Result<Vec<Stage>> {
+    /// The stages the operation runs. Operations that do not name the SFX
+    /// stage only include it when `sfx` schedules it.
+    pub(crate) fn stages(&self, sfx: &SfxConfig) -> Result<Vec<Stage>> {
         let stages = match self {
             Self::Full => Stage::ALL.to_vec(),
             Self::Through {
                 stage: Stage::Detection,
             } => vec![Stage::Detection],
             Self::Through { stage: Stage::Ocr } => vec![Stage::Detection, Stage::Ocr],
+            Self::Through { stage: Stage::Sfx } => vec![Stage::Detection, Stage::Sfx],
             Self::Through {
                 stage: Stage::Translation,
             } => {
-                vec![Stage::Detection, Stage::Ocr, Stage::Translation]
+                vec![Stage::Detection, Stage::Ocr, Stage::Sfx, Stage::Translation]
             }
             Self::Through {
                 stage: Stage::Inpainting,
-            } => vec![Stage::Detection, Stage::Inpainting],
+            } => vec![Stage::Detection, Stage::Sfx, Stage::Inpainting],
             Self::Only { stage } => vec![*stage],
             Self::Stages { stages } => Stage::ALL
                 .into_iter()
@@ -57,7 +60,17 @@ impl Operation {
         if stages.is_empty() {
             bail!("at least one pipeline stage must be selected");
         }
-        Ok(stages)
+        let named = matches!(
+            self,
+            Self::Through { stage: Stage::Sfx } | Self::Only { .. } | Self::Stages { .. }
+        );
+        if named || sfx.scheduled() {
+            return Ok(stages);
+        }
+        Ok(stages
+            .into_iter()
+            .filter(|stage| *stage != Stage::Sfx)
+            .collect())
     }
 }
 
@@ -34,14 +34,15 @@ impl PageWork {
             .all(|work| work.state == WorkState::Finished)
     }
 
-    fn ready(&self, index: usize) -> bool {
-        let Some(prerequisite) = prerequisite(self.stages[index].stage) else {
-            return true;
-        };
-        self.stages
+    fn ready(&self, index: usize, sfx_masks: bool) -> bool {
+        prerequisites(self.stages[index].stage, sfx_masks)
             .iter()
-            .find(|work| work.stage == prerequisite)
-            .is_none_or(|work| work.state == WorkState::Finished)
+            .all(|prerequisite| {
+                self.stages
+                    .iter()
+                    .find(|work| work.stage == *prerequisite)
+                    .is_none_or(|work| work.state == WorkState::Finished)
+            })
     }
 }
 
@@ -52,10 +53,13 @@ pub(crate) struct Scheduler {
     active_pages: usize,
     head: usize,
     total: usize,
+    sfx_masks: bool,
 }
 
 impl Scheduler {
-    pub(crate) fn new(pages: &[EntityId], stages: &[Stage]) -> Self {
+    /// `sfx_masks` is whether the SFX stage writes a mask the inpainting stage
+    /// merges, which makes inpainting wait for it.
+    pub(crate) fn new(pages: &[EntityId], stages: &[Stage], sfx_masks: bool) -> Self {
         let pages = pages
             .iter()
             .map(|page| PageWork {
@@ -81,6 +85,7 @@ impl Scheduler {
             active_pages: 0,
             head: 0,
             total,
+            sfx_masks,
         }
     }
 
@@ -105,7 +110,7 @@ impl Scheduler {
                     .find_map(|(index, work)| {
                         (work.state == WorkState::Pending
                             && !busy_stages.contains(&work.stage)
-                            && self.pages[page_index].ready(index))
+                            && self.pages[page_index].ready(index, self.sfx_masks))
                         .then_some(index)
                     });
             let Some(stage_index) = stage_index else {
@@ -142,11 +147,17 @@ impl Scheduler {
     }
 }
 
-const fn prerequisite(stage: Stage) -> Option<Stage> {
+// SFX follows OCR because both write the page's text; it follows detection,
+// which replaces the page's regions. Inpainting only waits for SFX when it has
+// a mask to merge.
+const fn prerequisites(stage: Stage, sfx_masks: bool) -> &'static [Stage] {
     match stage {
-        Stage::Detection => None,
-        Stage::Ocr | Stage::Inpainting => Some(Stage::Detection),
-        Stage::Translation => Some(Stage::Ocr),
+        Stage::Detection => &[],
+        Stage::Ocr => &[Stage::Detection],
+        Stage::Sfx => &[Stage::Detection, Stage::Ocr],
+        Stage::Translation => &[Stage::Ocr, Stage::Sfx],
+        Stage::Inpainting if sfx_masks => &[Stage::Detection, Stage::Sfx],
+        Stage::Inpainting => &[Stage::Detection],
     }
 }
 
@@ -161,7 +172,7 @@ mod tests {
     #[test]
     fn starts_pages_in_order_and_models_independently() {
         let pages = pages(2);
-        let mut scheduler = Scheduler::new(&pages, &Stage::ALL);
+        let mut scheduler = Scheduler::new(&pages, &Stage::ALL, true);
         let mut busy = BTreeSet::new();
 
         let first = scheduler.start_next(&busy).unwrap();
@@ -173,27 +184,56 @@ mod tests {
         assert!(!scheduler.complete_stage(pages[0], Stage::Detection));
         let ocr = scheduler.start_next(&busy).unwrap();
         busy.insert(ocr.1);
-        let inpainting = scheduler.start_next(&busy).unwrap();
-        busy.insert(inpainting.1);
         let next_page = scheduler.start_next(&busy).unwrap();
         busy.insert(next_page.1);
         assert_eq!(ocr, (pages[0], Stage::Ocr));
-        assert_eq!(inpainting, (pages[0], Stage::Inpainting));
         assert_eq!(next_page, (pages[1], Stage::Detection));
+        assert!(scheduler.start_next(&busy).is_none());
 
         assert!(!scheduler.complete_stage(pages[0], Stage::Ocr));
         busy.remove(&Stage::Ocr);
+        let sfx = scheduler.start_next(&busy).unwrap();
+        assert_eq!(sfx, (pages[0], Stage::Sfx));
+        busy.insert(sfx.1);
+        assert!(scheduler.start_next(&busy).is_none());
+
+        assert!(!scheduler.complete_stage(pages[0], Stage::Sfx));
+        busy.remove(&Stage::Sfx);
         let translation = scheduler.start_next(&busy).unwrap();
+        busy.insert(translation.1);
+        let inpainting = scheduler.start_next(&busy).unwrap();
         assert_eq!(translation, (pages[0], Stage::Translation));
+        assert_eq!(inpainting, (pages[0], Stage::Inpainting));
         assert!(busy.contains(&Stage::Detection));
-        assert!(busy.contains(&Stage::Inpainting));
+    }
+
+    #[test]
+    fn inpainting_waits_for_sfx_only_when_it_merges_a_mask() {
+        let pages = pages(1);
+        let stages = [Stage::Detection, Stage::Sfx, Stage::Inpainting];
+        let mut busy = BTreeSet::new();
+
+        for sfx_masks in [true, false] {
+            let mut scheduler = Scheduler::new(&pages, &stages, sfx_masks);
+            busy.clear();
+            scheduler.start_next(&busy).unwrap();
+            assert!(!scheduler.complete_stage(pages[0], Stage::Detection));
+            let sfx = scheduler.start_next(&busy).unwrap();
+            assert_eq!(sfx, (pages[0], Stage::Sfx));
+            busy.insert(sfx.1);
+            let inpainting = scheduler.start_next(&busy);
+            assert_eq!(
+                inpainting,
+                (!sfx_masks).then_some((pages[0], Stage::Inpainting))
+            );
+        }
     }
 
     #[test]
     fn sliding_window_backpressures_fast_upstream_models() {
         let pages = pages(4);
         let stages = [Stage::Detection, Stage::Ocr, Stage::Inpainting];
-        let mut scheduler = Scheduler::new(&pages, &stages);
+        let mut scheduler = Scheduler::new(&pages, &stages, true);
         let mut busy = BTreeSet::new();
 
         assert_eq!(
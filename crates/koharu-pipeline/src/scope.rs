@@ -5,7 +5,8 @@ use std::{
 
 use anyhow::{Result, bail};
 use koharu_scene::{
-    EntityId, FitsTo, FlowsIn, Geometry, Inside, Presents, RecognizedFrom, RelationSpec, Snapshot,
+    EntityId, FitsTo, FlowsIn, Geometry, Inside, Presents, Query, RecognizedFrom, RelationSpec,
+    Snapshot,
 };
 use serde::{Deserialize, Serialize};
 use specta::Type;
@@ -57,6 +58,11 @@ pub enum Scope {
         bounds: Bounds,
     },
     Entities(Vec<EntityId>),
+    /// The pages of one chapter, in page order.
+    Chapter(EntityId),
+    /// The entities a query selects when the run starts, with the same
+    /// semantic closure as an entity scope.
+    Query(Query),
 }
 
 #[derive(Clone, Debug)]
@@ -68,12 +74,12 @@ pub(crate) struct NormalizedScope {
 
 impl NormalizedScope {
     pub(crate) fn new(snapshot: &Snapshot, scope: &Scope, stages: &[Stage]) -> Result<Self> {
-        if matches!(scope, Scope::Entities(_))
+        if matches!(scope, Scope::Entities(_) | Scope::Query(_))
             && stages
                 .iter()
-                .any(|stage| matches!(stage, Stage::Detection | Stage::Inpainting))
+                .any(|stage| matches!(stage, Stage::Detection | Stage::Sfx | Stage::Inpainting))
         {
-            bail!("detection and inpainting do not support entity-only scope");
+            bail!("detection, SFX, and inpainting do not support entity-only scope");
         }
 
         match scope {
@@ -110,33 +116,63 @@ impl NormalizedScope {
                     region: Some((*page, *bounds)),
                 })
             }
-            Scope::Entities(requested) => {
-                if requested.is_empty() {
-                    bail!("entity scope is empty");
-                }
-                let requested = requested.iter().copied().collect::<BTreeSet<_>>();
-                let mut page_set = BTreeSet::new();
-                for entity in &requested {
-                    snapshot.entity(*entity)?;
-                    page_set.insert(containing_page(snapshot, *entity)?);
-                }
-                let mut hierarchy = BTreeSet::new();
-                for entity in requested {
-                    hierarchy.extend(snapshot.subtree(entity)?.map(|entity| entity.id()));
-                }
-                let entities = semantic_closure(snapshot, hierarchy);
+            Scope::Chapter(chapter) => {
                 let pages = snapshot
+                    .chapter(*chapter)?
                     .pages()
+                    .into_iter()
                     .map(|page| page.id())
-                    .filter(|id| page_set.contains(id))
                     .collect::<Arc<[_]>>();
+                if pages.is_empty() {
+                    bail!("chapter {chapter} has no pages");
+                }
                 Ok(Self {
                     pages,
-                    entities: Some(Arc::new(entities)),
+                    entities: None,
                     region: None,
                 })
             }
+            Scope::Entities(requested) => {
+                if requested.is_empty() {
+                    bail!("entity scope is empty");
+                }
+                Self::from_entities(snapshot, requested.iter().copied().collect())
+            }
+            Scope::Query(query) => {
+                let selected = snapshot
+                    .query(query)?
+                    .into_iter()
+                    .map(|entity| entity.id())
+                    .collect::<BTreeSet<_>>();
+                if selected.is_empty() {
+                    bail!("query scope matches no entities");
+                }
+                Self::from_entities(snapshot, selected)
+            }
+        }
+    }
+
+    fn from_entities(snapshot: &Snapshot, requested: BTreeSet<EntityId>) -> Result<Self> {
+        let mut page_set = BTreeSet::new();
+        for entity in &requested {
+            snapshot.entity(*entity)?;
+            page_set.insert(containing_page(snapshot, *entity)?);
+        }
+        let mut hierarchy = BTreeSet::new();
+        for entity in requested {
+            hierarchy.extend(snapshot.subtree(entity)?.map(|entity| entity.id()));
         }
+        let entities = semantic_closure(snapshot, hierarchy);
+        let pages = snapshot
+            .pages()
+            .map(|page| page.id())
+            .filter(|id| page_set.contains(id))
+            .collect::<Arc<[_]>>();
+        Ok(Self {
+            pages,
+            entities: Some(Arc::new(entities)),
+            region: None,
+        })
     }
 
     pub(crate) fn pages(&self) -> &[EntityId] {
@@ -4,10 +4,10 @@ use std::{
 };
 
 use anyhow::Result;
-use koharu_scene::{EntityId, Patch};
+use koharu_scene::{EntityId, Patch, Snapshot};
 
 use crate::{
-    ErrorKind, PipelineConfig, PipelineError, Progress, ProgressSink, Stage, StopToken,
+    ErrorKind, PipelineConfig, PipelineError, Progress, ProgressSink, SfxConfig, Stage, StopToken,
     accelerator::AcceleratorGate,
     progress,
     resources::ResourceMonitor,
@@ -16,6 +16,7 @@ use crate::{
 
 pub(crate) struct StageRunner {
     stages: Stages,
+    sfx: SfxConfig,
     accelerator: AcceleratorGate,
 }
 
@@ -23,15 +24,23 @@ impl StageRunner {
     pub(crate) fn new(
         config: &PipelineConfig,
         translator: koharu_translator::Translator,
+        memory: koharu_translator::TranslationMemory,
         device: &koharu_ml::Device,
         resources: Arc<ResourceMonitor>,
     ) -> Result<Self> {
         Ok(Self {
-            stages: Stages::new(config, translator, device)?,
+            stages: Stages::new(config, translator, memory, device)?,
+            sfx: config.sfx.clone(),
             accelerator: AcceleratorGate::new(device, resources),
         })
     }
 
+    /// The SFX settings the runner was built with, which decide whether runs
+    /// schedule the SFX stage.
+    pub(crate) fn sfx(&self) -> &SfxConfig {
+        &self.sfx
+    }
+
     #[tracing::instrument(skip_all)]
     pub(crate) async fn run(&self, job: StageJob) -> StageCompletion {
         let started = Instant::now();
@@ -47,6 +56,14 @@ impl StageRunner {
         }
     }
 
+    /// Lets the stage follow up on its output for `page` once it is
+    /// committed. The output stays committed if this fails.
+    pub(crate) async fn committed(&self, stage: Stage, page: EntityId, scene: &Snapshot) {
+        if let Err(error) = self.stages.committed(stage, page, scene).await {
+            tracing::warn!(%stage, %page, %error, "failed to follow up on committed stage output");
+        }
+    }
+
     async fn run_with_recovery(
         &self,
         job: &StageJob,
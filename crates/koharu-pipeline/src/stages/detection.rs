@@ -24,15 +24,19 @@ use koharu_ml::koharu_layout_rfdetr_seg_2xl::{
 use koharu_scene::{
     AssetInput, AssetMetadata, AssetRole, At, BubbleRegion, DetectionAnalysis, DetectionLabel,
     EntityId, EntityOrigin, FitsTo, FlowsIn, Generation, Geometry, Inside, Origin, PanelRegion,
-    Point, RecognizedFrom, Region, RegionKind, RegionSpec, RemovePolicy, TextLayout,
-    TextLayoutKind, TextRegion, TextRole, Typography, WritingMode,
+    Point, ReadingDirection, RecognizedFrom, Region, RegionKind, RegionSpec, RemovePolicy,
+    TextLayout, TextLayoutKind, TextRegion, TextRole, Typography, WritingMode,
 };
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 
-use super::{StageInput, StageProcessor, finish, generation};
-use crate::{DetectionModel, ModelCell};
+use super::{
+    StageInput, StageProcessor, finish,
+    fonts::{FontMatcher, SourceFont},
+    generation,
+};
+use crate::{DetectionModel, FontsConfig, ModelCell};
 
 const MODEL_ID: &str = "mayocream/koharu-layout-rfdetr-seg-2xl-1152";
 const MODEL_NAME: &str = "koharu-layout-rfdetr-seg-2xl";
@@ -59,12 +63,17 @@ pub struct KoharuLayoutRFDetrSeg2XLConfig {
 
 pub(super) struct Processor {
     config: DetectionModel,
+    fonts: FontsConfig,
     device: koharu_ml::Device,
     model: ModelCell<Model>,
 }
 
 impl Processor {
-    pub(super) fn new(mut config: DetectionModel, device: koharu_ml::Device) -> Self {
+    pub(super) fn new(
+        mut config: DetectionModel,
+        mut fonts: FontsConfig,
+        device: koharu_ml::Device,
+    ) -> Self {
         let DetectionModel::KoharuLayoutRFDetrSeg2XL(settings) = &mut config;
         for (name, value) in [
             ("text", &mut settings.text_threshold),
@@ -84,9 +93,22 @@ impl Processor {
                 *value = None;
             }
         }
+        for mapping in &mut fonts.families {
+            if let Some(weight) = mapping.weight
+                && !(1..=1000).contains(&weight)
+            {
+                tracing::warn!(
+                    source = ?mapping.source,
+                    weight,
+                    "font weight is not between 1 and 1000; keeping the font's own weight"
+                );
+                mapping.weight = None;
+            }
+        }
 
         Self {
             config,
+            fonts,
             device,
             model: ModelCell::new(),
         }
@@ -119,7 +141,7 @@ impl StageProcessor for Processor {
 
     async fn load(&self) -> Result<()> {
         self.model
-            .ensure(|| Model::load(self.device.clone(), &self.config))
+            .ensure(|| Model::load(self.device.clone(), &self.config, &self.fonts))
             .await
     }
 
@@ -137,19 +159,33 @@ impl StageProcessor for Processor {
 struct Model {
     network: Arc<Mutex<KoharuLayoutRFDetrSeg2XL>>,
     thresholds: KoharuLayoutThresholds,
+    fonts: Option<FontMatcher>,
 }
 
 impl Model {
-    async fn load(device: koharu_ml::Device, config: &DetectionModel) -> Result<Self> {
+    async fn load(
+        device: koharu_ml::Device,
+        config: &DetectionModel,
+        fonts: &FontsConfig,
+    ) -> Result<Self> {
         let DetectionModel::KoharuLayoutRFDetrSeg2XL(config) = config;
-        let network = KoharuLayoutRFDetrSeg2XL::load(device).await?;
+        let network = KoharuLayoutRFDetrSeg2XL::load(device.clone()).await?;
         let mut thresholds = network.recommended_thresholds();
         thresholds.text = config.text_threshold.unwrap_or(thresholds.text);
         thresholds.bubble = config.bubble_threshold.unwrap_or(thresholds.bubble);
         thresholds.panel = config.panel_threshold.unwrap_or(thresholds.panel);
+        // Source fonts only inform typesetting, so detection goes on
+        // without them.
+        let fonts = FontMatcher::load(device, fonts)
+            .await
+            .unwrap_or_else(|error| {
+                tracing::warn!(%error, "failed to load the font detector; skipping font detection");
+                None
+            });
         Ok(Self {
             network: Arc::new(Mutex::new(network)),
             thresholds,
+            fonts,
         })
     }
 
@@ -161,7 +197,14 @@ impl Model {
             .await?
             .ok_or_else(|| anyhow!("page {page} has no source image"))?;
         let output = self.detect(image.clone()).await?;
-        build_patch(&input, &image, output, &generation(PRODUCER, MODEL_ID)?).await
+        build_patch(
+            &input,
+            &image,
+            output,
+            self.fonts.as_ref(),
+            &generation(PRODUCER, MODEL_ID)?,
+        )
+        .await
     }
 
     async fn detect(&self, image: Arc<DynamicImage>) -> Result<KoharuLayoutDetections> {
@@ -214,6 +257,7 @@ async fn build_patch(
     input: &StageInput,
     image: &DynamicImage,
     output: KoharuLayoutDetections,
+    fonts: Option<&FontMatcher>,
     generation: &Generation,
 ) -> Result<koharu_scene::Patch> {
     let page = input.page;
@@ -221,7 +265,7 @@ async fn build_patch(
     edit.observe_subtree(page)?;
     remove_previous_regions(input, &mut edit, generation)
         .context("failed to replace the previous detection regions")?;
-    write_page(input, &mut edit, page, image, output, generation)
+    write_page(input, &mut edit, page, image, output, fonts, generation)
         .await
         .context("failed to write detection output")?;
     finish(edit)
@@ -262,6 +306,7 @@ async fn write_page(
     page: EntityId,
     image: &DynamicImage,
     output: KoharuLayoutDetections,
+    fonts: Option<&FontMatcher>,
     generation: &Generation,
 ) -> Result<()> {
     let KoharuLayoutDetections {
@@ -277,11 +322,32 @@ async fn write_page(
         detections.retain(|detection| intersects(detection.bbox, region));
     }
     non_maximum_suppression(&mut detections, 0.5);
-    sort_by_layout(&mut detections);
+    sort_by_layout(&mut detections, input.scene.reading_direction()?);
+    let fonts = match fonts {
+        Some(fonts) => {
+            let bounds = detections
+                .iter()
+                .map(|detection| (detection.label == "text").then_some(detection.bbox))
+                .collect::<Vec<_>>();
+            fonts.predict(image, &bounds).await.unwrap_or_else(|error| {
+                tracing::warn!(%error, "failed to detect source fonts");
+                vec![None; detections.len()]
+            })
+        }
+        None => vec![None; detections.len()],
+    };
 
     let image = image.to_rgb8();
-    let regions = write_regions(&input.scene, edit, page, &image, &detections, generation)
-        .context("failed to write detected regions")?;
+    let regions = write_regions(
+        &input.scene,
+        edit,
+        page,
+        &image,
+        &detections,
+        fonts,
+        generation,
+    )
+    .context("failed to write detected regions")?;
     link_dialogue_regions(edit, &regions, generation)
         .context("failed to associate detected text with dialogue regions")?;
     write_masks(input, edit, page, &detections, size)
@@ -295,6 +361,7 @@ fn write_regions<'a>(
     page: EntityId,
     image: &RgbImage,
     detections: &'a [KoharuLayoutDetection],
+    fonts: Vec<Option<SourceFont>>,
     generation: &Generation,
 ) -> Result<PageRegions<'a>> {
     let mut regions = PageRegions::default();
@@ -315,8 +382,10 @@ fn write_regions<'a>(
     } else {
         None
     };
-    for (index, (detection, inferred)) in detections.iter().zip(inferred).enumerate() {
-        match write_region(edit, page, detection, inferred, generation)
+    for (index, ((detection, inferred), font)) in
+        detections.iter().zip(inferred).zip(fonts).enumerate()
+    {
+        match write_region(edit, page, detection, inferred, font, generation)
             .with_context(|| format!("failed to write {} detection {index}", detection.label))?
         {
             RegionOutput::Bubble(bubble) => regions.bubbles.push(bubble),
@@ -338,6 +407,7 @@ fn write_region<'a>(
     page: EntityId,
     detection: &'a KoharuLayoutDetection,
     inferred: Option<InferredTypography>,
+    font: Option<SourceFont>,
     generation: &Generation,
 ) -> Result<RegionOutput<'a>> {
     let entity = edit
@@ -404,8 +474,8 @@ fn write_region<'a>(
         layer,
         &Typography {
             origin: Origin::Generated(generation.clone()),
-            preferred_font: None,
-            font_weight: None,
+            preferred_font: font.as_ref().and_then(|font| font.family.clone()),
+            font_weight: font.as_ref().and_then(|font| font.weight),
             font_style: None,
             size: None,
             auto_fit: true,
@@ -415,7 +485,9 @@ fn write_region<'a>(
                 .map(|color| [color[0], color[1], color[2], u8::MAX]),
             stroke_width: inferred.and_then(|value| value.stroke_width),
             alignment: None,
-            writing_mode: inferred.map(|value| value.writing_mode),
+            writing_mode: inferred
+                .map(|value| value.writing_mode)
+                .or(font.map(|font| font.writing_mode)),
             extensions: Default::default(),
         },
     )
@@ -1408,7 +1480,7 @@ async fn write_mask(
     Ok(())
 }
 
-async fn preserve_mask_outside_region(
+pub(super) async fn preserve_mask_outside_region(
     input: &StageInput,
     page: EntityId,
     role: &str,
@@ -1650,8 +1722,10 @@ fn non_maximum_suppression(detections: &mut Vec<KoharuLayoutDetection>, threshol
     *detections = kept;
 }
 
-fn sort_by_layout(detections: &mut Vec<KoharuLayoutDetection>) {
-    let order = layout_order(detections);
+/// Orders detections the way a reader meets them: panel by panel, with each
+/// panel's bubbles and text following it.
+fn sort_by_layout(detections: &mut Vec<KoharuLayoutDetection>, direction: ReadingDirection) {
+    let order = layout_order(detections, direction);
     let mut values = std::mem::take(detections)
         .into_iter()
         .map(Some)
@@ -1666,7 +1740,7 @@ fn sort_by_layout(detections: &mut Vec<KoharuLayoutDetection>) {
         .collect();
 }
 
-fn layout_order(detections: &[KoharuLayoutDetection]) -> Vec<usize> {
+fn layout_order(detections: &[KoharuLayoutDetection], direction: ReadingDirection) -> Vec<usize> {
     let panels = indices_with_label(detections, "panel");
     let bubbles = indices_with_label(detections, "bubble");
     let texts = indices_with_label(detections, "text");
@@ -1702,9 +1776,9 @@ fn layout_order(detections: &[KoharuLayoutDetection]) -> Vec<usize> {
             roots.push(index);
         }
     }
-    sort_spatial(detections, &mut roots);
+    sort_spatial(detections, &mut roots, direction);
     for siblings in &mut children {
-        sort_spatial(detections, siblings);
+        sort_spatial(detections, siblings, direction);
     }
 
     let mut order = Vec::with_capacity(detections.len());
@@ -1729,9 +1803,30 @@ fn append_layout_subtree(index: usize, children: &[Vec<usize>], order: &mut Vec<
     }
 }
 
-fn sort_spatial(detections: &[KoharuLayoutDetection], indices: &mut [usize]) {
+/// Orders boxes by row from the top, and within a row in the reading
+/// direction. Vertical scrolling reads each row from left to right.
+pub(super) fn reading_order(
+    left: [f32; 4],
+    right: [f32; 4],
+    direction: ReadingDirection,
+) -> Ordering {
+    let across = match direction {
+        ReadingDirection::RightToLeft => right[0].total_cmp(&left[0]),
+        ReadingDirection::LeftToRight | ReadingDirection::VerticalScroll => {
+            left[0].total_cmp(&right[0])
+        }
+    };
+    left[1].total_cmp(&right[1]).then(across)
+}
+
+fn sort_spatial(
+    detections: &[KoharuLayoutDetection],
+    indices: &mut [usize],
+    direction: ReadingDirection,
+) {
     indices.sort_by(|&left, &right| {
-        detection_order(&detections[left], &detections[right])
+        reading_order(detections[left].bbox, detections[right].bbox, direction)
+            .then_with(|| detection_order(&detections[left], &detections[right]))
             .then_with(|| detections[right].score.total_cmp(&detections[left].score))
             .then_with(|| left.cmp(&right))
     });
@@ -1845,11 +1940,13 @@ mod tests {
 
     use super::{
         DIALOGUE_MASK_CONTAINMENT_THRESHOLD, DetectedRegion, DetectedText, DetectionModel,
-        ImageSize, KoharuLayoutRFDetrSeg2XLConfig, MaskPixel, PageRegions, Processor, RegionOutput,
-        StageInput, StageProcessor, closed_mask_for, color_palette, generation, infer_typography,
-        layout_order, link_dialogue_regions, mask_containment, mask_for, mask_geometry,
-        non_maximum_suppression, normalize_text_color, write_region,
+        ImageSize, KoharuLayoutRFDetrSeg2XLConfig, MaskPixel, PageRegions, Processor,
+        ReadingDirection, RegionOutput, SourceFont, StageInput, StageProcessor, closed_mask_for,
+        color_palette, generation, infer_typography, layout_order, link_dialogue_regions,
+        mask_containment, mask_for, mask_geometry, non_maximum_suppression, normalize_text_color,
+        write_region,
     };
+    use crate::{FontMapping, FontsConfig, SourceFontClass};
 
     #[test]
     fn out_of_range_thresholds_fall_back_to_the_model_defaults() {
@@ -1859,6 +1956,7 @@ mod tests {
                 bubble_threshold: Some(f32::NAN),
                 panel_threshold: Some(0.55),
             }),
+            FontsConfig::default(),
             koharu_ml::Device::cpu(),
         );
 
@@ -1868,6 +1966,37 @@ mod tests {
         assert_eq!(settings.panel_threshold, Some(0.55));
     }
 
+    #[test]
+    fn out_of_range_font_weights_keep_the_font_weight() {
+        let processor = Processor::new(
+            DetectionModel::KoharuLayoutRFDetrSeg2XL(KoharuLayoutRFDetrSeg2XLConfig::default()),
+            FontsConfig {
+                detect: true,
+                families: vec![
+                    FontMapping {
+                        source: SourceFontClass::BoldGothic,
+                        family: Some("CCWildWords".to_owned()),
+                        weight: Some(1200),
+                    },
+                    FontMapping {
+                        source: SourceFontClass::Mincho,
+                        family: None,
+                        weight: Some(300),
+                    },
+                ],
+            },
+            koharu_ml::Device::cpu(),
+        );
+
+        let weights = processor
+            .fonts
+            .families
+            .iter()
+            .map(|mapping| mapping.weight)
+            .collect::<Vec<_>>();
+        assert_eq!(weights, [None, Some(300)]);
+    }
+
     #[tokio::test]
     async fn detection_skips_a_page_with_existing_text() {
         let mut session = Session::memory().await.unwrap();
@@ -1898,6 +2027,7 @@ mod tests {
         );
         let processor = Processor::new(
             DetectionModel::KoharuLayoutRFDetrSeg2XL(KoharuLayoutRFDetrSeg2XLConfig::default()),
+            FontsConfig::default(),
             koharu_ml::Device::cpu(),
         );
 
@@ -2245,7 +2375,8 @@ mod tests {
         let mut layer = None;
         let patch = snapshot
             .patch(|edit| {
-                let output = write_region(edit, page, &detection, inferred, &generation).unwrap();
+                let output =
+                    write_region(edit, page, &detection, inferred, None, &generation).unwrap();
                 let RegionOutput::Text(text) = output else {
                     panic!("expected a text region");
                 };
@@ -2264,6 +2395,50 @@ mod tests {
         assert_eq!(typography.stroke_width, Some(3.0));
     }
 
+    #[tokio::test]
+    async fn matched_source_fonts_are_written_as_generated_typography() {
+        let mut session = Session::memory().await.unwrap();
+        let mut page = None;
+        let create = session
+            .snapshot()
+            .patch(|edit| {
+                page = Some(edit.add_page(PageDraft::new("page", 96.0, 96.0), At::End)?);
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(create).await.unwrap().snapshot;
+        let page = page.unwrap();
+        let (_, detection) = outlined_text(3, [0, 0, 0], [255, 255, 255]);
+        let generation = generation(super::PRODUCER, super::MODEL_ID).unwrap();
+        let font = SourceFont {
+            family: Some("CCWildWords".to_owned()),
+            weight: Some(700),
+            writing_mode: WritingMode::Vertical,
+        };
+        let mut layer = None;
+        let patch = snapshot
+            .patch(|edit| {
+                let output =
+                    write_region(edit, page, &detection, None, Some(font), &generation).unwrap();
+                let RegionOutput::Text(text) = output else {
+                    panic!("expected a text region");
+                };
+                layer = Some(text.layer);
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+        let typography = snapshot
+            .component::<Typography>(layer.unwrap())
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(typography.origin, Origin::Generated(_)));
+        assert_eq!(typography.preferred_font.as_deref(), Some("CCWildWords"));
+        assert_eq!(typography.font_weight, Some(700));
+        assert_eq!(typography.writing_mode, Some(WritingMode::Vertical));
+    }
+
     #[test]
     fn nms_removes_lower_scored_overlapping_regions_per_class() {
         let mut detections = vec![
@@ -2482,7 +2657,7 @@ mod tests {
             detection("bubble", 0.7, [120.0, 100.0, 190.0, 160.0]),
         ];
 
-        let text_scores = layout_order(&detections)
+        let text_scores = layout_order(&detections, ReadingDirection::RightToLeft)
             .into_iter()
             .filter_map(|index| {
                 (detections[index].label == "text").then_some(detections[index].score)
@@ -2490,6 +2665,15 @@ mod tests {
             .collect::<Vec<_>>();
 
         assert_eq!(text_scores, [0.61, 0.62, 0.63]);
+
+        let text_scores = layout_order(&detections, ReadingDirection::LeftToRight)
+            .into_iter()
+            .filter_map(|index| {
+                (detections[index].label == "text").then_some(detections[index].score)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(text_scores, [0.63, 0.61, 0.62]);
     }
 
     #[test]
@@ -2502,7 +2686,7 @@ mod tests {
             detection("text", 0.1, [20.0, 0.0, 180.0, 20.0]),
         ];
 
-        let text_scores = layout_order(&detections)
+        let text_scores = layout_order(&detections, ReadingDirection::RightToLeft)
             .into_iter()
             .filter_map(|index| {
                 (detections[index].label == "text").then_some(detections[index].score)
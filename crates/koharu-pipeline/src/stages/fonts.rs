@@ -0,0 +1,242 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context as _, Result, anyhow};
+use image::DynamicImage;
+use koharu_ml::font_detector::{FontDetector, FontPrediction, NamedFontPrediction, TextDirection};
+use koharu_scene::WritingMode;
+
+use crate::{FontsConfig, SourceFontClass};
+
+const TOP_FONTS: usize = 5;
+const BATCH_SIZE: usize = 16;
+const MIN_CROP_SIDE: u32 = 8;
+
+/// Typography matched to the source font predicted for one text region.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct SourceFont {
+    pub(super) family: Option<String>,
+    pub(super) weight: Option<u16>,
+    pub(super) writing_mode: WritingMode,
+}
+
+pub(super) struct FontMatcher {
+    network: Arc<Mutex<FontDetector>>,
+    config: FontsConfig,
+}
+
+impl FontMatcher {
+    pub(super) async fn load(
+        device: koharu_ml::Device,
+        config: &FontsConfig,
+    ) -> Result<Option<Self>> {
+        if !config.detect {
+            return Ok(None);
+        }
+        let network = FontDetector::load(device).await?;
+        Ok(Some(Self {
+            network: Arc::new(Mutex::new(network)),
+            config: config.clone(),
+        }))
+    }
+
+    /// Predicts the source font inside each of `bounds`. Boxes that are
+    /// missing or too small to read yield no match.
+    pub(super) async fn predict(
+        &self,
+        image: &DynamicImage,
+        bounds: &[Option<[f32; 4]>],
+    ) -> Result<Vec<Option<SourceFont>>> {
+        let (indices, crops): (Vec<_>, Vec<_>) = bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(index, bounds)| Some((index, crop(image, (*bounds)?)?)))
+            .unzip();
+        let count = bounds.len();
+        let network = self.network.clone();
+        let predictions = tokio::task::spawn_blocking(move || {
+            let network = network
+                .lock()
+                .map_err(|_| anyhow!("font detector lock is poisoned"))?;
+            let mut predictions = vec![None; count];
+            for (indices, crops) in indices.chunks(BATCH_SIZE).zip(crops.chunks(BATCH_SIZE)) {
+                for (&index, prediction) in indices.iter().zip(network.inference(crops, TOP_FONTS)?)
+                {
+                    predictions[index] = Some(prediction);
+                }
+            }
+            anyhow::Ok(predictions)
+        })
+        .await
+        .context("font detection task panicked")??;
+        Ok(predictions
+            .into_iter()
+            .map(|prediction| prediction.map(|prediction| source_font(&self.config, &prediction)))
+            .collect())
+    }
+}
+
+fn crop(image: &DynamicImage, [left, top, right, bottom]: [f32; 4]) -> Option<DynamicImage> {
+    let left = left.floor().clamp(0.0, image.width() as f32) as u32;
+    let top = top.floor().clamp(0.0, image.height() as f32) as u32;
+    let right = right.ceil().clamp(0.0, image.width() as f32) as u32;
+    let bottom = bottom.ceil().clamp(0.0, image.height() as f32) as u32;
+    (right >= left + MIN_CROP_SIDE && bottom >= top + MIN_CROP_SIDE)
+        .then(|| image.crop_imm(left, top, right - left, bottom - top))
+}
+
+fn source_font(config: &FontsConfig, prediction: &FontPrediction) -> SourceFont {
+    let mapping = classify(prediction).and_then(|class| config.mapping(class));
+    SourceFont {
+        family: mapping
+            .and_then(|mapping| mapping.family.clone())
+            .filter(|family| !family.trim().is_empty()),
+        weight: mapping.and_then(|mapping| mapping.weight),
+        writing_mode: match prediction.direction {
+            TextDirection::Horizontal => WritingMode::Horizontal,
+            TextDirection::Vertical => WritingMode::Vertical,
+        },
+    }
+}
+
+/// Classifies a prediction by the probability mass of its top fonts rather
+/// than the first font alone, since neighbouring weights of one family often
+/// score almost equally.
+fn classify(prediction: &FontPrediction) -> Option<SourceFontClass> {
+    let mass = |include: fn(&NamedFontPrediction) -> bool| {
+        prediction
+            .named_fonts
+            .iter()
+            .filter(|font| include(font))
+            .map(|font| font.probability)
+            .sum::<f32>()
+    };
+    let total = mass(|_| true);
+    if !total.is_finite() || total <= 0.0 {
+        return None;
+    }
+    let serif = mass(|font| font.serif) * 2.0 > total;
+    let bold = mass(|font| is_bold(&font.name)) * 2.0 > total;
+    Some(match (serif, bold) {
+        (false, false) => SourceFontClass::Gothic,
+        (false, true) => SourceFontClass::BoldGothic,
+        (true, false) => SourceFontClass::Mincho,
+        (true, true) => SourceFontClass::BoldMincho,
+    })
+}
+
+/// Reads the weight from a font file name. Japanese foundries often mark it
+/// with a short suffix, such as `-DB`, or a Hiragino-style `W8`.
+fn is_bold(name: &str) -> bool {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    let stem = file
+        .rsplit_once('.')
+        .map_or(file, |(stem, _)| stem)
+        .to_ascii_lowercase();
+    ["bold", "black", "heavy"]
+        .iter()
+        .any(|weight| stem.contains(weight))
+        || stem
+            .split(|character: char| !character.is_ascii_alphanumeric())
+            .any(|token| {
+                matches!(token, "b" | "db" | "eb" | "h")
+                    || token
+                        .strip_prefix('w')
+                        .and_then(|weight| weight.parse::<u8>().ok())
+                        .is_some_and(|weight| weight >= 6)
+            })
+}
+
+#[cfg(test)]
+mod tests {
+    use koharu_ml::font_detector::{FontPrediction, NamedFontPrediction, TextDirection};
+    use koharu_scene::WritingMode;
+
+    use super::{SourceFont, classify, is_bold, source_font};
+    use crate::{FontsConfig, SourceFontClass};
+
+    fn prediction(fonts: &[(&str, f32, bool)]) -> FontPrediction {
+        FontPrediction {
+            named_fonts: fonts
+                .iter()
+                .enumerate()
+                .map(|(index, &(name, probability, serif))| NamedFontPrediction {
+                    index,
+                    name: name.to_owned(),
+                    language: Some("ja".to_owned()),
+                    probability,
+                    serif,
+                })
+                .collect(),
+            direction: TextDirection::Vertical,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weight_hints_in_font_names_mark_bold_faces() {
+        for name in [
+            "ja/KozGoPr6N-Heavy.otf",
+            "ja/HiraKakuStd-W8.otf",
+            "ja/FOT-RodinPro-DB.otf",
+            "cjk/SourceHanSerifJP-Bold.otf",
+        ] {
+            assert!(is_bold(name), "{name}");
+        }
+        for name in [
+            "ja/A-OTF-ShinGoPro-Regular.otf",
+            "ja/HiraMinStd-W3.otf",
+            "cjk/NotoSansJP-Light.otf",
+            "en/Bahnschrift.ttf",
+        ] {
+            assert!(!is_bold(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn predictions_are_classed_by_probability_mass() {
+        let prediction = prediction(&[
+            ("ja/HiraMinStd-W3.otf", 0.4, true),
+            ("ja/KozGoPr6N-Heavy.otf", 0.35, false),
+            ("ja/FOT-RodinPro-DB.otf", 0.25, false),
+        ]);
+
+        assert_eq!(classify(&prediction), Some(SourceFontClass::BoldGothic));
+        assert_eq!(
+            source_font(&FontsConfig::default(), &prediction),
+            SourceFont {
+                family: None,
+                weight: Some(700),
+                writing_mode: WritingMode::Vertical,
+            }
+        );
+    }
+
+    #[test]
+    fn unmapped_classes_keep_the_typesetting_font_stack() {
+        let prediction = prediction(&[("ja/A-OTF-ShinGoPro-Regular.otf", 0.9, false)]);
+
+        assert_eq!(classify(&prediction), Some(SourceFontClass::Gothic));
+        assert_eq!(
+            source_font(&FontsConfig::default(), &prediction),
+            SourceFont {
+                family: None,
+                weight: None,
+                writing_mode: WritingMode::Vertical,
+            }
+        );
+        assert_eq!(classify(&FontPrediction::default()), None);
+    }
+
+    #[test]
+    fn default_settings_load_no_model_and_name_no_family() {
+        let config = FontsConfig::default();
+
+        assert!(!config.detect);
+        assert!(
+            config
+                .families
+                .iter()
+                .all(|mapping| mapping.family.is_none())
+        );
+    }
+}
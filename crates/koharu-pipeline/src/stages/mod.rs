@@ -1,13 +1,15 @@
 mod detection;
+mod fonts;
 mod inpainting;
 mod ocr;
+mod sfx;
 mod translation;
 
 use std::{collections::BTreeSet, sync::Arc};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use koharu_scene::{Edit, EntityId, Generation, Patch, ProducerId, Snapshot};
+use koharu_scene::{Edit, EntityId, Generation, LanguageTag, Patch, ProducerId, Project, Snapshot};
 
 pub use detection::KoharuLayoutRFDetrSeg2XLConfig;
 pub use inpainting::{Flux2KleinConfig, RoremMixedConfig};
@@ -47,6 +49,18 @@ impl StageInput {
         self.page
     }
 
+    /// The language of text read from the page: the language it was already
+    /// read in, else the project's source locale, else Japanese.
+    fn source_language(&self, previous: Option<LanguageTag>) -> Result<Option<LanguageTag>> {
+        if previous.is_some() {
+            return Ok(previous);
+        }
+        let project = self.scene.project_component::<Project>()?;
+        Ok(project
+            .and_then(|project| project.source_locale)
+            .or_else(|| LanguageTag::new("ja-JP").ok()))
+    }
+
     fn contains_entity(&self, entity: EntityId) -> Result<bool> {
         crate::scope::contains_entity(
             &self.scene,
@@ -67,11 +81,16 @@ trait StageProcessor: Send + Sync {
     fn unload(&self) -> bool;
     async fn load(&self) -> Result<()>;
     async fn process(&self, input: StageInput) -> Result<Patch>;
+    /// Runs once the stage's output for `page` is committed.
+    async fn committed(&self, _page: EntityId, _scene: &Snapshot) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub(crate) struct Stages {
     detection: detection::Processor,
     ocr: ocr::Processor,
+    sfx: sfx::Processor,
     translation: translation::Processor,
     inpainting: inpainting::Processor,
 }
@@ -80,12 +99,22 @@ impl Stages {
     pub(crate) fn new(
         config: &PipelineConfig,
         translator: koharu_translator::Translator,
+        memory: koharu_translator::TranslationMemory,
         device: &koharu_ml::Device,
     ) -> Result<Self> {
         Ok(Self {
-            detection: detection::Processor::new(config.detection()?, device.clone()),
+            detection: detection::Processor::new(
+                config.detection()?,
+                config.fonts.clone(),
+                device.clone(),
+            ),
             ocr: ocr::Processor::new(config.ocr.clone(), device.clone()),
-            translation: translation::Processor::new(config.translation.clone(), translator),
+            sfx: sfx::Processor::new(config.sfx.clone(), device.clone()),
+            translation: translation::Processor::new(
+                config.translation.clone(),
+                translator,
+                memory,
+            ),
             inpainting: inpainting::Processor::new(config.inpainting()?, device.clone())?,
         })
     }
@@ -94,6 +123,7 @@ impl Stages {
         match stage {
             Stage::Detection => &self.detection,
             Stage::Ocr => &self.ocr,
+            Stage::Sfx => &self.sfx,
             Stage::Translation => &self.translation,
             Stage::Inpainting => &self.inpainting,
         }
@@ -115,6 +145,15 @@ impl Stages {
         self.processor(stage).process(input).await
     }
 
+    pub(crate) async fn committed(
+        &self,
+        stage: Stage,
+        page: EntityId,
+        scene: &Snapshot,
+    ) -> Result<()> {
+        self.processor(stage).committed(page, scene).await
+    }
+
     pub(crate) fn unload(&self, stage: Stage) -> bool {
         self.processor(stage).unload()
     }
@@ -156,6 +195,7 @@ mod tests {
         let stages = Stages::new(
             &PipelineConfig::default(),
             translator,
+            koharu_translator::TranslationMemory::memory(),
             &koharu_ml::Device::cpu(),
         )
         .unwrap();
@@ -165,6 +205,7 @@ mod tests {
             [
                 "koharu-layout-rfdetr-seg-2xl",
                 "paddleocr-vl-1.6",
+                "comic-onomatopoeia",
                 "local",
                 "lama",
             ]
@@ -203,11 +244,9 @@ mod tests {
 
         let mut text_edit = base.edit();
         text_edit.observe::<koharu_scene::SourceText>(text).unwrap();
+        text_edit.observe_translations(text).unwrap();
         text_edit
-            .observe::<koharu_scene::Translation>(text)
-            .unwrap();
-        text_edit
-            .set(
+            .set_translation(
                 text,
                 &koharu_scene::Translation {
                     text: koharu_scene::Authored::user("after".to_owned()),
@@ -10,8 +10,8 @@ use koharu_ml::{
     paddle_ocr_vl_quantized::PaddleOCRVLQuantized,
 };
 use koharu_scene::{
-    Authored, EntityId, Geometry, LanguageTag, OcrAnalysis, Origin, RecognizedFrom, Region,
-    RegionSpec, SourceText, TextDirection, TextRegion,
+    Authored, EntityId, Geometry, OcrAnalysis, Origin, RecognizedFrom, Region, RegionSpec,
+    SourceText, TextDirection, TextRegion,
 };
 
 const PRODUCER: &str = "dev.koharu.pipeline.ocr";
@@ -164,10 +164,8 @@ impl Model {
             edit.observe::<SourceText>(result.content)?;
         }
         for result in results {
-            let language = result
-                .previous
-                .and_then(|value| value.language)
-                .or_else(|| LanguageTag::new("ja-JP").ok());
+            let language =
+                input.source_language(result.previous.and_then(|value| value.language))?;
             edit.set(
                 result.content,
                 &SourceText {
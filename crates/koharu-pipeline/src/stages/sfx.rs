@@ -0,0 +1,629 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    io::Cursor,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context as _, Result, anyhow, ensure};
+use async_trait::async_trait;
+use image::{
+    DynamicImage, ExtendedColorType, GrayImage, ImageEncoder as _, Luma,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+};
+use imageproc::drawing::draw_polygon_mut;
+use koharu_ml::comic_onomatopoeia::{
+    ComicOnomatopoeiaDetector, ComicOnomatopoeiaRecognizer, Detection,
+};
+use koharu_scene::{
+    AssetInput, AssetMetadata, AssetRole, At, Authored, DetectionAnalysis, DetectionLabel,
+    EntityId, Generation, Geometry, LanguageTag, OcrAnalysis, OnomatopoeiaRegion, Origin, Point,
+    ReadingDirection, RecognizedFrom, Region, RegionSpec, SourceText, TextDirection, TextLayout,
+    TextLayoutKind, TextRole, Typography, Visibility,
+};
+
+use super::{
+    StageInput, StageProcessor, detection::preserve_mask_outside_region, finish, generation,
+};
+use crate::{ModelCell, SfxConfig, SfxTreatment};
+
+const MODEL_ID: &str = "mayocream/coo-comic-onomatopoeia-safetensors";
+const MODEL_NAME: &str = "comic-onomatopoeia";
+const PRODUCER: &str = "dev.koharu.pipeline.sfx";
+const MASK_ROLE: &str = "coo-mask";
+/// The text role of recognized sound effects, which translation hints as SFX.
+pub(super) const ROLE: &str = "dev.koharu.text.onomatopoeia";
+// COO's reported operating points, also used by the `comic_onomatopoeia` CLI.
+const DETECTION_THRESHOLD: f32 = 0.48;
+const RECOGNITION_THRESHOLD: f32 = 0.47;
+/// The height of an annotation relative to the sound effect it explains.
+const ANNOTATION_HEIGHT: f32 = 0.35;
+
+pub(super) struct Processor {
+    config: SfxConfig,
+    device: koharu_ml::Device,
+    model: ModelCell<Model>,
+}
+
+impl Processor {
+    pub(super) fn new(config: SfxConfig, device: koharu_ml::Device) -> Self {
+        Self {
+            config,
+            device,
+            model: ModelCell::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl StageProcessor for Processor {
+    fn model(&self) -> &'static str {
+        MODEL_NAME
+    }
+
+    // Recognized sound effects may have been corrected or restyled, so a page
+    // that already has them keeps them, as detection keeps existing text.
+    fn skip(&self, input: &StageInput) -> Result<bool> {
+        for entity in input.scene.descendants(input.page)? {
+            if input.contains_entity(entity.id())?
+                && entity
+                    .component::<Region>()?
+                    .is_some_and(|region| region.kind == OnomatopoeiaRegion::kind())
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn unload(&self) -> bool {
+        self.model.unload()
+    }
+
+    async fn load(&self) -> Result<()> {
+        self.model.ensure(|| Model::load(self.device.clone())).await
+    }
+
+    async fn process(&self, input: StageInput) -> Result<koharu_scene::Patch> {
+        let model = self.model.lock().await;
+        let model = model
+            .as_ref()
+            .ok_or_else(|| anyhow!("SFX model is not loaded"))?;
+        let page = input.page;
+        let image = input
+            .images
+            .get(&input.scene, page, "source")
+            .await?
+            .ok_or_else(|| anyhow!("page {page} has no source image"))?;
+        let size = (image.width(), image.height());
+        let mut sounds = model.recognize(image).await?;
+        if let Some(region) = input.region {
+            sounds.retain(|sound| intersects(sound.detection.bounding_box, region));
+        }
+        let direction = input.scene.reading_direction()?;
+        sounds.sort_by(|left, right| reading_order(left, right, direction));
+        build_patch(
+            &input,
+            size,
+            sounds,
+            self.config.treatment,
+            &generation(PRODUCER, MODEL_ID)?,
+        )
+        .await
+    }
+}
+
+struct Model {
+    detector: Arc<Mutex<ComicOnomatopoeiaDetector>>,
+    recognizer: Arc<Mutex<ComicOnomatopoeiaRecognizer>>,
+}
+
+impl Model {
+    async fn load(device: koharu_ml::Device) -> Result<Self> {
+        Ok(Self {
+            detector: Arc::new(Mutex::new(
+                ComicOnomatopoeiaDetector::load(device.clone()).await?,
+            )),
+            recognizer: Arc::new(Mutex::new(ComicOnomatopoeiaRecognizer::load(device).await?)),
+        })
+    }
+
+    async fn recognize(&self, image: Arc<DynamicImage>) -> Result<Vec<Sound>> {
+        let detector = self.detector.clone();
+        let recognizer = self.recognizer.clone();
+        tokio::task::spawn_blocking(move || {
+            let detections = detector
+                .lock()
+                .map_err(|_| anyhow!("SFX detector lock is poisoned"))?
+                .inference(&image)?;
+            let recognizer = recognizer
+                .lock()
+                .map_err(|_| anyhow!("SFX recognizer lock is poisoned"))?;
+            let mut sounds = Vec::new();
+            for detection in detections {
+                if detection.score < DETECTION_THRESHOLD {
+                    continue;
+                }
+                let recognition = recognizer.inference(&crop(&image, &detection)?)?;
+                let text = recognition.text.trim();
+                if recognition.confidence >= RECOGNITION_THRESHOLD && !text.is_empty() {
+                    sounds.push(Sound {
+                        text: text.to_owned(),
+                        confidence: recognition.confidence,
+                        detection,
+                    });
+                }
+            }
+            Ok(sounds)
+        })
+        .await
+        .context("SFX recognition task panicked")?
+    }
+}
+
+/// A sound effect that was both detected and read.
+struct Sound {
+    detection: Detection,
+    text: String,
+    confidence: f32,
+}
+
+async fn build_patch(
+    input: &StageInput,
+    (width, height): (u32, u32),
+    sounds: Vec<Sound>,
+    treatment: SfxTreatment,
+    generation: &Generation,
+) -> Result<koharu_scene::Patch> {
+    let page = input.page;
+    let mut edit = input.scene.edit_as(generation.clone());
+    edit.observe_assets(page)?;
+    let language = input.source_language(None)?;
+    for (index, sound) in sounds.iter().enumerate() {
+        write_sound(
+            &mut edit,
+            page,
+            sound,
+            language.as_ref(),
+            treatment,
+            height,
+            generation,
+        )
+        .with_context(|| format!("failed to write sound effect {index}"))?;
+    }
+    // Only replaced lettering is cleaned, so only it enters the mask inpainting
+    // merges with the text mask.
+    if treatment == SfxTreatment::Replace && !sounds.is_empty() {
+        let mut mask = GrayImage::new(width, height);
+        for sound in &sounds {
+            stamp_polygon(&mut mask, &sound.detection);
+        }
+        if let Some(bounds) = input.region {
+            preserve_mask_outside_region(input, page, MASK_ROLE, bounds, &mut mask).await?;
+        }
+        write_mask(&mut edit, page, &mask).context("failed to write the SFX mask")?;
+    }
+    finish(edit)
+}
+
+fn write_sound(
+    edit: &mut koharu_scene::Edit,
+    page: EntityId,
+    sound: &Sound,
+    language: Option<&LanguageTag>,
+    treatment: SfxTreatment,
+    page_height: u32,
+    generation: &Generation,
+) -> Result<()> {
+    let detection = &sound.detection;
+    let region = edit.add_entity(page, At::End)?;
+    let outline = if detection.polygon.len() >= 3 {
+        &detection.polygon[..]
+    } else {
+        &detection.rotated_box[..]
+    };
+    edit.set(region, &polygon_geometry(outline))?;
+    edit.set(
+        region,
+        &Region {
+            origin: Origin::Generated(generation.clone()),
+            kind: OnomatopoeiaRegion::kind(),
+            label: Some("onomatopoeia".to_owned()),
+        },
+    )?;
+    edit.set(
+        region,
+        &DetectionAnalysis {
+            origin: Origin::Generated(generation.clone()),
+            labels: vec![DetectionLabel {
+                kind: OnomatopoeiaRegion::kind(),
+                confidence: detection.score,
+            }],
+        },
+    )?;
+    let [left, top, right, bottom] = detection.bounding_box;
+    edit.set(
+        region,
+        &OcrAnalysis {
+            origin: Origin::Generated(generation.clone()),
+            direction: if bottom - top >= (right - left) * 1.15 {
+                TextDirection::Vertical
+            } else {
+                TextDirection::Horizontal
+            },
+            confidence: Some(sound.confidence.clamp(0.0, 1.0)),
+            line_boundaries: Vec::new(),
+        },
+    )?;
+
+    let content = edit.add_text_content(page, At::End)?;
+    edit.set(
+        content,
+        &SourceText {
+            text: Authored::generated(sound.text.clone(), generation.clone()),
+            language: language.cloned(),
+        },
+    )?;
+    edit.set(
+        content,
+        &TextRole {
+            origin: Origin::Generated(generation.clone()),
+            role: ROLE.to_owned(),
+        },
+    )?;
+    edit.relate::<RecognizedFrom>(content, region)?;
+
+    // Every sound effect gets a layer so translation reaches it; a left sound
+    // effect keeps its translation hidden for reference.
+    let layer = edit.add_text_layer(
+        page,
+        At::End,
+        content,
+        &TextLayout {
+            origin: Origin::Generated(generation.clone()),
+            kind: TextLayoutKind::Paragraph,
+        },
+    )?;
+    let frame = match treatment {
+        SfxTreatment::Leave | SfxTreatment::Replace => polygon_geometry(&detection.rotated_box),
+        SfxTreatment::Annotate => annotation_geometry(detection.bounding_box, page_height),
+    };
+    edit.set(layer, &frame)?;
+    edit.set(
+        layer,
+        &Typography {
+            origin: Origin::Generated(generation.clone()),
+            preferred_font: None,
+            font_weight: None,
+            font_style: None,
+            size: None,
+            auto_fit: true,
+            color: None,
+            stroke_color: None,
+            stroke_width: None,
+            alignment: None,
+            writing_mode: None,
+            extensions: Default::default(),
+        },
+    )?;
+    if treatment == SfxTreatment::Leave {
+        edit.set(
+            layer,
+            &Visibility {
+                origin: Origin::Generated(generation.clone()),
+                visible: false,
+                opacity: 1.0,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn polygon_geometry(points: &[[f32; 2]]) -> Geometry {
+    Geometry {
+        origin: Origin::User,
+        points: points
+            .iter()
+            .map(|[x, y]| Point {
+                x: f64::from(*x),
+                y: f64::from(*y),
+            })
+            .collect(),
+    }
+}
+
+/// A band below the sound effect, or above it when it sits at the bottom of
+/// the page, so the translation explains the art without covering it.
+fn annotation_geometry([left, top, right, bottom]: [f32; 4], page_height: u32) -> Geometry {
+    let band = ((bottom - top) * ANNOTATION_HEIGHT).max(1.0);
+    let y = if bottom + band <= page_height as f32 {
+        bottom
+    } else {
+        (top - band).max(0.0)
+    };
+    Geometry::rectangle(
+        f64::from(left),
+        f64::from(y),
+        f64::from((right - left).max(1.0)),
+        f64::from(band),
+    )
+}
+
+fn stamp_polygon(mask: &mut GrayImage, detection: &Detection) {
+    let mut points = detection
+        .polygon
+        .iter()
+        .map(|[x, y]| imageproc::point::Point::new(x.round() as i32, y.round() as i32))
+        .collect::<Vec<_>>();
+    points.dedup();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() >= 3 {
+        draw_polygon_mut(mask, &points, Luma([u8::MAX]));
+    }
+}
+
+fn write_mask(edit: &mut koharu_scene::Edit, page: EntityId, mask: &GrayImage) -> Result<()> {
+    let mut bytes = Cursor::new(Vec::new());
+    PngEncoder::new_with_quality(&mut bytes, CompressionType::Fast, FilterType::NoFilter)
+        .write_image(
+            mask.as_raw(),
+            mask.width(),
+            mask.height(),
+            ExtendedColorType::L8,
+        )?;
+    edit.set_asset(
+        page,
+        &AssetRole::new(MASK_ROLE)?,
+        AssetInput::new(
+            Arc::<[u8]>::from(bytes.into_inner()),
+            "image/png",
+            AssetMetadata {
+                width: Some(mask.width()),
+                height: Some(mask.height()),
+                attributes: BTreeMap::new(),
+            },
+        ),
+    )?;
+    Ok(())
+}
+
+// COO's TRBA evaluation data crops the axis-aligned polygon bounds with an
+// exclusive upper coordinate.
+// https://github.com/ku21fan/COO-Comic-Onomatopoeia/blob/d8028f015b8ce99a4dd798427342f97087529357/COO-data/data_for_TRBA.ipynb
+fn crop(image: &DynamicImage, detection: &Detection) -> Result<DynamicImage> {
+    let [left, top, right, bottom] = detection.polygon.iter().fold(
+        [
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ],
+        |[left, top, right, bottom], [x, y]| {
+            [left.min(*x), top.min(*y), right.max(*x), bottom.max(*y)]
+        },
+    );
+    let left = left.floor().clamp(0.0, image.width() as f32) as u32;
+    let top = top.floor().clamp(0.0, image.height() as f32) as u32;
+    let right = right.ceil().clamp(0.0, image.width() as f32) as u32;
+    let bottom = bottom.ceil().clamp(0.0, image.height() as f32) as u32;
+    ensure!(
+        right > left && bottom > top,
+        "sound effect detection produced an empty crop"
+    );
+    Ok(image.crop_imm(left, top, right - left, bottom - top))
+}
+
+fn intersects([left, top, right, bottom]: [f32; 4], region: crate::Bounds) -> bool {
+    left < (region.x + region.width) as f32
+        && right > region.x as f32
+        && top < (region.y + region.height) as f32
+        && bottom > region.y as f32
+}
+
+fn reading_order(left: &Sound, right: &Sound, direction: ReadingDirection) -> Ordering {
+    let [left_x, left_y, ..] = left.detection.bounding_box;
+    let [right_x, right_y, ..] = right.detection.bounding_box;
+    left_y.total_cmp(&right_y).then_with(|| match direction {
+        ReadingDirection::RightToLeft => right_x.total_cmp(&left_x),
+        ReadingDirection::LeftToRight | ReadingDirection::VerticalScroll => {
+            left_x.total_cmp(&right_x)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use koharu_scene::{PageDraft, Project, Session};
+
+    use super::*;
+
+    fn sound(text: &str, [left, top, right, bottom]: [f32; 4]) -> Sound {
+        let corners = [[left, top], [right, top], [right, bottom], [left, bottom]];
+        Sound {
+            detection: Detection {
+                polygon: corners.to_vec(),
+                rotated_box: corners,
+                bounding_box: [left, top, right, bottom],
+                score: 0.9,
+            },
+            text: text.to_owned(),
+            confidence: 0.8,
+        }
+    }
+
+    async fn page() -> (Session, EntityId) {
+        let mut session = Session::memory().await.unwrap();
+        let mut page = None;
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                page = Some(edit.add_page(PageDraft::new("page", 20.0, 20.0), At::End)?);
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        (session, page.unwrap())
+    }
+
+    fn input(session: &Session, page: EntityId) -> StageInput {
+        StageInput::new(
+            session.snapshot(),
+            page,
+            None,
+            None,
+            Arc::new(crate::ImageCache::default()),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn replaced_sound_effects_are_masked_and_lettered_in_place() {
+        let (mut session, page) = page().await;
+        let input = input(&session, page);
+        let generation = generation(PRODUCER, MODEL_ID).unwrap();
+        let patch = build_patch(
+            &input,
+            (20, 20),
+            vec![sound("ドン", [2.0, 2.0, 10.0, 8.0])],
+            SfxTreatment::Replace,
+            &generation,
+        )
+        .await
+        .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+
+        let region = snapshot
+            .descendants(page)
+            .unwrap()
+            .find(|entity| {
+                entity
+                    .component::<Region>()
+                    .unwrap()
+                    .is_some_and(|region| region.kind == OnomatopoeiaRegion::kind())
+            })
+            .unwrap()
+            .id();
+        let content = snapshot
+            .relations_to_as::<RecognizedFrom>(region)
+            .next()
+            .unwrap()
+            .value()
+            .source;
+        assert_eq!(
+            snapshot
+                .component::<TextRole>(content)
+                .unwrap()
+                .unwrap()
+                .role,
+            ROLE
+        );
+        let layer = snapshot.text_layers().unwrap().next().unwrap().id();
+        assert_eq!(
+            snapshot
+                .component::<Geometry>(layer)
+                .unwrap()
+                .unwrap()
+                .points[2],
+            Point { x: 10.0, y: 8.0 }
+        );
+        assert!(snapshot.component::<Visibility>(layer).unwrap().is_none());
+
+        let mask = input
+            .images
+            .get(&snapshot, page, MASK_ROLE)
+            .await
+            .unwrap()
+            .unwrap()
+            .to_luma8();
+        assert_eq!(mask.get_pixel(5, 5).0, [u8::MAX]);
+        assert_eq!(mask.get_pixel(15, 15).0, [0]);
+    }
+
+    #[tokio::test]
+    async fn left_sound_effects_keep_the_art_and_hide_their_translation() {
+        let (mut session, page) = page().await;
+        let input = input(&session, page);
+        let generation = generation(PRODUCER, MODEL_ID).unwrap();
+        let patch = build_patch(
+            &input,
+            (20, 20),
+            vec![sound("ドン", [2.0, 2.0, 10.0, 8.0])],
+            SfxTreatment::Leave,
+            &generation,
+        )
+        .await
+        .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+
+        let layer = snapshot.text_layers().unwrap().next().unwrap().id();
+        assert!(
+            !snapshot
+                .component::<Visibility>(layer)
+                .unwrap()
+                .unwrap()
+                .visible
+        );
+        assert!(
+            snapshot
+                .asset(page, &AssetRole::new(MASK_ROLE).unwrap())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Processor::new(SfxConfig::default(), koharu_ml::Device::cpu())
+                .skip(&StageInput::new(
+                    snapshot,
+                    page,
+                    None,
+                    None,
+                    Arc::new(crate::ImageCache::default()),
+                    None,
+                ))
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn sound_effects_are_read_in_the_project_source_locale() {
+        let (mut session, page) = page().await;
+        let korean = LanguageTag::new("ko-KR").unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                edit.set_project(&Project {
+                    source_locale: Some(korean.clone()),
+                    target_locales: Vec::new(),
+                })
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        let generation = generation(PRODUCER, MODEL_ID).unwrap();
+        let patch = build_patch(
+            &input(&session, page),
+            (20, 20),
+            vec![sound("쾅", [2.0, 2.0, 10.0, 8.0])],
+            SfxTreatment::Leave,
+            &generation,
+        )
+        .await
+        .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+
+        let content = snapshot
+            .descendants(page)
+            .unwrap()
+            .find_map(|entity| entity.component::<SourceText>().unwrap())
+            .unwrap();
+        assert_eq!(content.language, Some(korean));
+    }
+
+    #[test]
+    fn annotations_sit_below_the_sound_effect_unless_it_ends_the_page() {
+        let below = annotation_geometry([0.0, 10.0, 10.0, 30.0], 100);
+        assert_eq!(below.points[0], Point { x: 0.0, y: 30.0 });
+        let above = annotation_geometry([0.0, 70.0, 10.0, 100.0], 100);
+        assert_eq!(above.points[0].y, 59.5);
+    }
+}
@@ -1,25 +1,70 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use async_trait::async_trait;
-use koharu_scene::{Authored, LanguageTag, Origin, SourceText, Translation};
-use koharu_translator::{TranslationRequest, Translator};
+use koharu_scene::{
+    Authored, Edit, EntityId, Generation, Geometry, GlossaryTerm, LanguageTag, Origin, ProducerId,
+    Snapshot, SourceText, Translation, TranslationCandidates,
+};
+use koharu_translator::{
+    Language, MemoryUnit, TranslationCharacter, TranslationContext, TranslationGlossaryTerm,
+    TranslationMemory, TranslationRequest, Translator, UNDETERMINED_LANGUAGE,
+};
 
-use crate::TranslationConfig;
+use crate::{TranslationConfig, scope::geometry_extents};
 
-use super::{StageInput, StageProcessor, finish, generation};
+use super::{StageInput, StageProcessor, detection::reading_order, finish, generation};
 
 const PRODUCER: &str = "dev.koharu.pipeline.translation";
+const MEMORY_PRODUCER: &str = "dev.koharu.pipeline.translation-memory";
+/// How many remembered lines each segment contributes as examples.
+const MEMORY_EXAMPLES: usize = 2;
 
 pub(super) struct Processor {
     config: TranslationConfig,
     translator: Translator,
+    memory: TranslationMemory,
 }
 
 impl Processor {
-    pub(super) fn new(config: TranslationConfig, translator: Translator) -> Self {
-        Self { config, translator }
+    pub(super) fn new(
+        config: TranslationConfig,
+        translator: Translator,
+        memory: TranslationMemory,
+    ) -> Self {
+        Self {
+            config,
+            translator,
+            memory,
+        }
+    }
+
+    /// The language to translate into and the tag the results are recorded
+    /// under. The selected edition decides both.
+    fn languages(&self, scene: &Snapshot) -> Result<(Language, LanguageTag)> {
+        Ok(match scene.edition_locale()? {
+            Some(edition) => (
+                edition.as_str().parse::<Language>().map_err(|_| {
+                    anyhow::anyhow!("edition language {edition} cannot be translated into")
+                })?,
+                edition,
+            ),
+            None => (
+                self.config.target_language,
+                LanguageTag::new(self.config.target_language.tag())?,
+            ),
+        })
     }
 }
 
+/// The memory's language tag for source text, or `und` when it is unknown.
+fn source_language(language: Option<&LanguageTag>) -> String {
+    language.map_or_else(
+        || UNDETERMINED_LANGUAGE.to_owned(),
+        |language| language.as_str().to_owned(),
+    )
+}
+
 #[async_trait]
 impl StageProcessor for Processor {
     fn model(&self) -> &'static str {
@@ -46,13 +91,133 @@ impl StageProcessor for Processor {
                     continue;
                 };
                 if !source.text.value.trim().is_empty() {
-                    targets.push((content.id(), source.text.value));
+                    targets.push(Target {
+                        entity: content.id(),
+                        language: source_language(source.language.as_ref()),
+                        source: source.text.value,
+                        speaker: content.speaker()?.map(|character| character.id()),
+                        sound_effect: content
+                            .role()?
+                            .is_some_and(|role| role.role == super::sfx::ROLE),
+                    });
                 }
             }
         }
+        let (target_language, language) = self.languages(&input.scene)?;
+        // Lines an editor already translated are applied without asking the
+        // model. The closest remembered lines for the rest become candidates,
+        // and the model sees them as examples.
+        let recalled = Generation::new(ProducerId::new(MEMORY_PRODUCER)?);
+        let mut proposals = Vec::new();
+        let mut examples = Vec::<TranslationContext>::new();
+        let mut pending = Vec::new();
+        for (index, target) in targets.iter().enumerate() {
+            let exact = self
+                .memory
+                .exact(&target.language, language.as_str(), &target.source);
+            if let Some(hit) = &exact
+                && hit.reviewed
+            {
+                proposals.push(Proposal {
+                    target: index,
+                    text: hit.target.clone(),
+                    generation: recalled.clone(),
+                    confidence: Some(1.0),
+                    translates: true,
+                });
+                continue;
+            }
+            // Lines earlier releases kept from model output are only offered,
+            // however closely they match.
+            let mut similar = self
+                .memory
+                .similar(
+                    &target.language,
+                    language.as_str(),
+                    &target.source,
+                    MEMORY_EXAMPLES,
+                )
+                .await?;
+            if let Some(hit) = exact {
+                similar.insert(0, hit);
+                similar.truncate(MEMORY_EXAMPLES);
+            }
+            if let Some(closest) = similar.first() {
+                proposals.push(Proposal {
+                    target: index,
+                    text: closest.target.clone(),
+                    generation: recalled.clone(),
+                    confidence: closest.reviewed.then_some(closest.similarity),
+                    translates: false,
+                });
+            }
+            for entry in similar {
+                let example = TranslationContext::new(entry.source, entry.target);
+                if !examples.contains(&example) {
+                    examples.push(example);
+                }
+            }
+            pending.push(index);
+        }
+        // Each speaker is described once; segments refer to it by position.
+        let mut speakers = Vec::<EntityId>::new();
+        let mut characters = Vec::new();
+        let mut segment_speakers = Vec::with_capacity(pending.len());
+        for index in &pending {
+            let Some(speaker) = targets[*index].speaker else {
+                segment_speakers.push(None);
+                continue;
+            };
+            let index = match speakers.iter().position(|known| *known == speaker) {
+                Some(index) => index,
+                None => {
+                    let character = input.scene.character(speaker)?.character()?;
+                    speakers.push(speaker);
+                    characters.push(TranslationCharacter {
+                        name: character.name,
+                        aliases: character.aliases,
+                        speech_notes: character.speech_notes,
+                    });
+                    speakers.len() - 1
+                }
+            };
+            segment_speakers.push(Some(index));
+        }
+        // Only the terms the model's segments use are sent, and every result
+        // is checked against the terms its source uses.
+        let mut glossary = Vec::new();
+        for term in input.scene.glossary()? {
+            let term = term.term()?;
+            if targets.iter().any(|target| term.occurs_in(&target.source)) {
+                glossary.push(term);
+            }
+        }
         let mut request = TranslationRequest::new(
-            targets.iter().map(|(_, source)| source.clone()),
-            self.config.target_language,
+            pending.iter().map(|index| targets[*index].source.clone()),
+            target_language,
+        )
+        .with_speakers(characters, segment_speakers)
+        .with_sound_effects(pending.iter().map(|index| targets[*index].sound_effect))
+        .with_context(preceding_context(
+            &input.scene,
+            input.page,
+            &language,
+            self.config.context_pages,
+        )?)
+        .with_examples(examples)
+        .with_glossary(
+            glossary
+                .iter()
+                .filter(|term| {
+                    pending
+                        .iter()
+                        .any(|index| term.occurs_in(&targets[*index].source))
+                })
+                .map(|term| TranslationGlossaryTerm {
+                    source: term.source.clone(),
+                    target: term.rendering().to_owned(),
+                    notes: term.notes.clone(),
+                }),
         );
         if let Some(instructions) = self.config.instructions.as_deref() {
             request = request.with_instructions(instructions);
@@ -66,34 +231,512 @@ impl StageProcessor for Processor {
             .translator
             .translate(&self.config.model, self.config.generation, request)
             .await?;
-        let language = LanguageTag::new(self.config.target_language.tag())?;
         let generated = generation(PRODUCER, provider)?;
+        for (index, segment) in pending.into_iter().zip(translated) {
+            let text = if targets[index].source.trim() == "\u{2026}" {
+                "\u{2026}".to_owned()
+            } else {
+                segment.text
+            };
+            proposals.push(Proposal {
+                target: index,
+                text,
+                generation: generated.clone(),
+                confidence: segment.confidence,
+                translates: true,
+            });
+        }
         let mut edit = input.scene.edit_as(generated.clone());
-        for (entity, _) in &targets {
-            edit.observe::<SourceText>(*entity)?;
-            edit.observe::<Translation>(*entity)?;
+        for target in &targets {
+            edit.observe::<SourceText>(target.entity)?;
+            edit.observe_translations(target.entity)?;
         }
-        for ((entity, source), text) in targets.into_iter().zip(translated) {
+        for proposal in proposals {
+            let target = &targets[proposal.target];
             if input
                 .scene
-                .component::<Translation>(entity)?
-                .is_some_and(|value| matches!(value.text.origin, Origin::User))
+                .component::<TranslationCandidates>(target.entity)?
+                .is_some_and(|candidates| candidates.approved(&language).is_some())
             {
                 continue;
             }
-            let text = if source.trim() == "\u{2026}" {
-                "\u{2026}".to_owned()
-            } else {
-                text
-            };
-            edit.set(
-                entity,
-                &Translation {
-                    text: Authored::generated(text, generated.clone()),
-                    language: Some(language.clone()),
-                },
-            )?;
+            // Every result is kept for review as a candidate. A translation
+            // that is already shown is never replaced.
+            let violations = glossary_violations(&glossary, &target.source, &proposal.text);
+            edit.with_generation(proposal.generation.clone(), |edit| {
+                let candidate = edit.add_translation_candidate(
+                    target.entity,
+                    &language,
+                    proposal.text.clone(),
+                    proposal.confidence,
+                )?;
+                match violations.clone() {
+                    Some(notes) => {
+                        edit.flag_translation_candidate(target.entity, &language, candidate, notes)
+                    }
+                    None => Ok(()),
+                }
+            })?;
+            if proposal.translates {
+                fill_translation(
+                    &mut edit,
+                    &input.scene,
+                    target.entity,
+                    &language,
+                    proposal.text,
+                    Generation {
+                        confidence: proposal.confidence,
+                        ..proposal.generation
+                    },
+                )?;
+            }
         }
         finish(edit)
     }
+
+    /// Remembers the translations of `page` an editor wrote or approved, so
+    /// model output never comes back as a match.
+    async fn committed(&self, page: EntityId, scene: &Snapshot) -> Result<()> {
+        let (_, language) = self.languages(scene)?;
+        let Some(group) = scene.page(page)?.text_group()? else {
+            return Ok(());
+        };
+        let mut reviewed = BTreeMap::<String, Vec<MemoryUnit>>::new();
+        for layer in group.text_layers()? {
+            let content = layer.content()?;
+            let Some(source) = content.source()? else {
+                continue;
+            };
+            if source.text.value.trim().is_empty() {
+                continue;
+            }
+            if let Some(translation) = scene.translation(content.id(), Some(&language))?
+                && translation.language.as_ref() == Some(&language)
+                && matches!(translation.text.origin, Origin::User)
+            {
+                reviewed
+                    .entry(source_language(source.language.as_ref()))
+                    .or_default()
+                    .push(MemoryUnit {
+                        source: source.text.value,
+                        target: translation.text.value,
+                    });
+            }
+        }
+        for (source_language, units) in reviewed {
+            self.memory
+                .correct(&source_language, language.as_str(), units)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+/// A text content the stage translates.
+struct Target {
+    entity: EntityId,
+    source: String,
+    /// The language tag of the source text, or `und` when it is unknown.
+    language: String,
+    speaker: Option<EntityId>,
+    /// Whether the text is a sound effect lettered into the artwork.
+    sound_effect: bool,
+}
+
+/// A translation of one target from the model or the memory.
+struct Proposal {
+    target: usize,
+    text: String,
+    generation: Generation,
+    confidence: Option<f32>,
+    /// Whether the text may fill in a missing translation, not only become
+    /// a candidate.
+    translates: bool,
+}
+
+/// Sets the translation when the text has none in `language` yet, so a
+/// translated page shows something before review. Later results only add
+/// candidates.
+fn fill_translation(
+    edit: &mut Edit,
+    scene: &Snapshot,
+    entity: EntityId,
+    language: &LanguageTag,
+    text: String,
+    generation: Generation,
+) -> Result<()> {
+    if scene.translation(entity, Some(language))?.is_some() {
+        return Ok(());
+    }
+    edit.with_generation(generation.clone(), |edit| {
+        edit.set_translation(
+            entity,
+            &Translation {
+                text: Authored::generated(text, generation),
+                language: Some(language.clone()),
+            },
+        )
+    })?;
+    Ok(())
+}
+
+/// Describes the glossary terms `source` uses that `translation` does not
+/// render as required, if there are any.
+fn glossary_violations(
+    glossary: &[GlossaryTerm],
+    source: &str,
+    translation: &str,
+) -> Option<String> {
+    let missed = glossary
+        .iter()
+        .filter(|term| term.occurs_in(source) && !term.is_rendered_in(translation))
+        .map(|term| format!("\"{}\" should read \"{}\"", term.source, term.rendering()))
+        .collect::<Vec<_>>();
+    (!missed.is_empty()).then(|| format!("Glossary: {}", missed.join("; ")))
+}
+
+/// The source and translation pairs of up to `pages` pages before `page`, in
+/// reading order. Text without a translation in `language` is left out.
+fn preceding_context(
+    scene: &Snapshot,
+    page: EntityId,
+    language: &LanguageTag,
+    pages: u32,
+) -> Result<Vec<TranslationContext>> {
+    let order = scene.pages().map(|page| page.id()).collect::<Vec<_>>();
+    let Some(position) = order.iter().position(|candidate| *candidate == page) else {
+        return Ok(Vec::new());
+    };
+    let start = position.saturating_sub(pages as usize);
+    let direction = scene.reading_direction()?;
+    let mut context = Vec::new();
+    for page in &order[start..position] {
+        let Some(group) = scene.page(*page)?.text_group()? else {
+            continue;
+        };
+        // Layers are read in the order detection sorts regions; layers
+        // without geometry follow in layer order.
+        let mut layers = Vec::new();
+        for layer in group.text_layers()? {
+            let bounds = scene
+                .component::<Geometry>(layer.id())?
+                .as_ref()
+                .and_then(geometry_extents)
+                .map(|(min_x, min_y, max_x, max_y)| {
+                    [min_x as f32, min_y as f32, max_x as f32, max_y as f32]
+                });
+            layers.push((bounds, layer));
+        }
+        layers.sort_by(|(left, _), (right, _)| match (left, right) {
+            (Some(left), Some(right)) => reading_order(*left, *right, direction),
+            (left, right) => left.is_none().cmp(&right.is_none()),
+        });
+        for (_, layer) in layers {
+            let content = layer.content()?;
+            let Some(source) = content.source()? else {
+                continue;
+            };
+            let Some(translation) = scene.translation(content.id(), Some(language))? else {
+                continue;
+            };
+            if source.text.value.trim().is_empty() || translation.text.value.trim().is_empty() {
+                continue;
+            }
+            context.push(TranslationContext::new(
+                source.text.value,
+                translation.text.value,
+            ));
+        }
+    }
+    Ok(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use koharu_scene::{At, PageDraft, Session, TextLayout, TextLayoutKind};
+
+    use super::*;
+
+    #[test]
+    fn translations_missing_a_glossary_rendering_are_described() {
+        let glossary = [
+            GlossaryTerm::new("ハナ", "Hana"),
+            GlossaryTerm {
+                do_not_translate: true,
+                ..GlossaryTerm::new("Koharu", "")
+            },
+        ];
+        assert_eq!(
+            glossary_violations(&glossary, "ハナ、Koharuへ", "Hanna, to Koharu"),
+            Some("Glossary: \"ハナ\" should read \"Hana\"".to_owned())
+        );
+        assert_eq!(
+            glossary_violations(&glossary, "ハナ、Koharuへ", "Hana, to koharu"),
+            None
+        );
+        assert_eq!(glossary_violations(&glossary, "行こう", "Let's go"), None);
+    }
+
+    #[tokio::test]
+    async fn reviewed_lines_are_applied_without_the_model() {
+        let mut session = Session::memory().await.unwrap();
+        let english = LanguageTag::new("en-US").unwrap();
+        let mut target = None;
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(PageDraft::new("page", 10.0, 10.0), At::End)?;
+                let content = edit.add_text_content(page, At::End)?;
+                edit.add_text_layer(
+                    page,
+                    At::End,
+                    content,
+                    &TextLayout {
+                        origin: Origin::User,
+                        kind: TextLayoutKind::Paragraph,
+                    },
+                )?;
+                edit.set(
+                    content,
+                    &SourceText {
+                        text: Authored::user("えっ？！".to_owned()),
+                        language: None,
+                    },
+                )?;
+                target = Some((page, content));
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+        let (page, content) = target.unwrap();
+
+        let memory = TranslationMemory::memory();
+        memory
+            .correct(
+                UNDETERMINED_LANGUAGE,
+                "en-US",
+                [MemoryUnit {
+                    source: "えっ?!".to_owned(),
+                    target: "Huh?!".to_owned(),
+                }],
+            )
+            .await
+            .unwrap();
+        let translator = Translator::from_config(
+            koharu_ml::Device::cpu(),
+            koharu_config::Config::memory(koharu_translator::ProvidersConfig::default()),
+        )
+        .unwrap();
+        let processor = Processor::new(TranslationConfig::default(), translator, memory);
+        let patch = processor
+            .process(StageInput::new(
+                snapshot,
+                page,
+                None,
+                None,
+                std::sync::Arc::new(crate::ImageCache::default()),
+                None,
+            ))
+            .await
+            .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+        let translation = snapshot
+            .translation(content, Some(&english))
+            .unwrap()
+            .unwrap();
+        assert_eq!(translation.text.value, "Huh?!");
+        assert!(matches!(
+            translation.text.origin,
+            Origin::Generated(generation) if generation.producer.as_str() == MEMORY_PRODUCER
+        ));
+    }
+
+    #[tokio::test]
+    async fn only_translations_editors_own_are_remembered() {
+        let mut session = Session::memory().await.unwrap();
+        let english = LanguageTag::new("en-US").unwrap();
+        let mut page = None;
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let added = edit.add_page(PageDraft::new("page", 10.0, 10.0), At::End)?;
+                for (source, translation) in [
+                    ("よし", Authored::user("Alright.".to_owned())),
+                    (
+                        "行こう",
+                        Authored::generated(
+                            "Let's go.".to_owned(),
+                            Generation::new(ProducerId::new(PRODUCER)?),
+                        ),
+                    ),
+                ] {
+                    let content = edit.add_text_content(added, At::End)?;
+                    edit.add_text_layer(
+                        added,
+                        At::End,
+                        content,
+                        &TextLayout {
+                            origin: Origin::User,
+                            kind: TextLayoutKind::Paragraph,
+                        },
+                    )?;
+                    edit.set(
+                        content,
+                        &SourceText {
+                            text: Authored::user(source.to_owned()),
+                            language: None,
+                        },
+                    )?;
+                    edit.set_translation(
+                        content,
+                        &Translation {
+                            text: translation,
+                            language: Some(english.clone()),
+                        },
+                    )?;
+                }
+                page = Some(added);
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(patch).await.unwrap().snapshot;
+
+        let memory = TranslationMemory::memory();
+        let translator = Translator::from_config(
+            koharu_ml::Device::cpu(),
+            koharu_config::Config::memory(koharu_translator::ProvidersConfig::default()),
+        )
+        .unwrap();
+        let processor = Processor::new(TranslationConfig::default(), translator, memory.clone());
+        processor.committed(page.unwrap(), &snapshot).await.unwrap();
+        let remembered = memory
+            .exact(UNDETERMINED_LANGUAGE, "en-US", "よし")
+            .unwrap();
+        assert_eq!(remembered.target, "Alright.");
+        assert!(remembered.reviewed);
+        assert_eq!(memory.exact(UNDETERMINED_LANGUAGE, "en-US", "行こう"), None);
+    }
+
+    #[tokio::test]
+    async fn context_covers_the_preceding_pages_in_reading_order() {
+        let mut session = Session::memory().await.unwrap();
+        let english = LanguageTag::new("en-US").unwrap();
+        let mut pages = Vec::new();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                for (label, source, translation) in [
+                    ("one", "一", Some("One")),
+                    ("two", "二", Some("Two")),
+                    ("three", "三", None),
+                    ("four", "四", Some("Four")),
+                ] {
+                    let page = edit.add_page(PageDraft::new(label, 10.0, 10.0), At::End)?;
+                    let content = edit.add_text_content(page, At::End)?;
+                    edit.add_text_layer(
+                        page,
+                        At::End,
+                        content,
+                        &TextLayout {
+                            origin: Origin::User,
+                            kind: TextLayoutKind::Paragraph,
+                        },
+                    )?;
+                    edit.set(
+                        content,
+                        &SourceText {
+                            text: Authored::user(source.to_owned()),
+                            language: None,
+                        },
+                    )?;
+                    if let Some(translation) = translation {
+                        edit.set_translation(
+                            content,
+                            &Translation {
+                                text: Authored::user(translation.to_owned()),
+                                language: Some(english.clone()),
+                            },
+                        )?;
+                    }
+                    pages.push(page);
+                }
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        let snapshot = session.snapshot();
+
+        let context = preceding_context(&snapshot, pages[3], &english, 3).unwrap();
+        assert_eq!(
+            context,
+            [
+                TranslationContext::new("一", "One"),
+                TranslationContext::new("二", "Two"),
+            ]
+        );
+        let context = preceding_context(&snapshot, pages[3], &english, 1).unwrap();
+        assert!(context.is_empty());
+        let context = preceding_context(&snapshot, pages[0], &english, 3).unwrap();
+        assert!(context.is_empty());
+    }
+
+    #[tokio::test]
+    async fn context_reads_each_page_in_the_reading_direction() {
+        let mut session = Session::memory().await.unwrap();
+        let english = LanguageTag::new("en-US").unwrap();
+        let mut pages = Vec::new();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(PageDraft::new("one", 10.0, 10.0), At::End)?;
+                // Layers are added top left, top right, then bottom right;
+                // right-to-left reading starts at the top right.
+                for (source, translation, x, y) in [
+                    ("左", "Left", 0.0, 0.0),
+                    ("右", "Right", 6.0, 0.0),
+                    ("下", "Below", 6.0, 6.0),
+                ] {
+                    let content = edit.add_text_content(page, At::End)?;
+                    let layer = edit.add_text_layer(
+                        page,
+                        At::End,
+                        content,
+                        &TextLayout {
+                            origin: Origin::User,
+                            kind: TextLayoutKind::Paragraph,
+                        },
+                    )?;
+                    edit.set(layer, &Geometry::rectangle(x, y, 4.0, 4.0))?;
+                    edit.set(
+                        content,
+                        &SourceText {
+                            text: Authored::user(source.to_owned()),
+                            language: None,
+                        },
+                    )?;
+                    edit.set_translation(
+                        content,
+                        &Translation {
+                            text: Authored::user(translation.to_owned()),
+                            language: Some(english.clone()),
+                        },
+                    )?;
+                }
+                pages.push(page);
+                pages.push(edit.add_page(PageDraft::new("two", 10.0, 10.0), At::End)?);
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+
+        let context = preceding_context(&session.snapshot(), pages[1], &english, 1).unwrap();
+        assert_eq!(
+            context,
+            [
+                TranslationContext::new("右", "Right"),
+                TranslationContext::new("左", "Left"),
+                TranslationContext::new("下", "Below"),
+            ]
+        );
+    }
 }
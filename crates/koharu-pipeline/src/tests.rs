@@ -95,6 +95,7 @@ fn pipeline(translation: TranslationConfig) -> Pipeline {
     Pipeline::from_config(
         koharu_config::Config::memory(config),
         koharu_config::Config::memory(koharu_translator::ProvidersConfig::default()),
+        koharu_translator::TranslationMemory::memory(),
         koharu_ml::Device::cpu(),
     )
     .unwrap()
@@ -109,19 +110,26 @@ impl Committer for RejectCommitter {
 
 #[test]
 fn operations_expand_to_the_supported_workflows() {
+    let sfx = SfxConfig::default();
     assert_eq!(
         Operation::Through {
             stage: Stage::Translation,
         }
-        .stages()
+        .stages(&sfx)
         .unwrap(),
         vec![Stage::Detection, Stage::Ocr, Stage::Translation],
     );
+    assert_eq!(
+        Operation::Through { stage: Stage::Sfx }
+            .stages(&sfx)
+            .unwrap(),
+        vec![Stage::Detection, Stage::Sfx],
+    );
     assert_eq!(
         Operation::Through {
             stage: Stage::Inpainting,
         }
-        .stages()
+        .stages(&sfx)
         .unwrap(),
         vec![Stage::Detection, Stage::Inpainting],
     );
@@ -129,7 +137,7 @@ fn operations_expand_to_the_supported_workflows() {
         Operation::Only {
             stage: Stage::Translation,
         }
-        .stages()
+        .stages(&sfx)
         .unwrap(),
         vec![Stage::Translation],
     );
@@ -137,8 +145,41 @@ fn operations_expand_to_the_supported_workflows() {
         Operation::Stages {
             stages: vec![Stage::Translation, Stage::Detection, Stage::Translation],
         }
-        .stages()
+        .stages(&sfx)
         .unwrap(),
         vec![Stage::Detection, Stage::Translation],
     );
+    assert_eq!(
+        Operation::Stages {
+            stages: vec![Stage::Sfx, Stage::Ocr],
+        }
+        .stages(&sfx)
+        .unwrap(),
+        vec![Stage::Ocr, Stage::Sfx],
+    );
+}
+
+#[test]
+fn full_runs_read_sound_effects_only_when_enabled_with_a_visible_treatment() {
+    let stages = |enabled, treatment| {
+        Operation::Full
+            .stages(&SfxConfig { enabled, treatment })
+            .unwrap()
+            .contains(&Stage::Sfx)
+    };
+    assert!(!stages(false, SfxTreatment::Replace));
+    assert!(!stages(true, SfxTreatment::Leave));
+    assert!(stages(true, SfxTreatment::Replace));
+    assert!(stages(true, SfxTreatment::Annotate));
+    assert_eq!(
+        Operation::Through {
+            stage: Stage::Translation,
+        }
+        .stages(&SfxConfig {
+            enabled: true,
+            treatment: SfxTreatment::Annotate,
+        })
+        .unwrap(),
+        vec![Stage::Detection, Stage::Ocr, Stage::Sfx, Stage::Translation],
+    );
 }
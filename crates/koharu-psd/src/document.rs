@@ -12,10 +12,10 @@ use koharu_rasterizer::{Raster, RasterOptions, Rasterizer};
 use koharu_renderer::{
     Frame, ImageKind, LayerKind, TextAlign, TextMetadata as RenderedText, WritingMode,
 };
-use koharu_scene::{AssetRole, Snapshot};
+use koharu_scene::{AssetRole, BlendMode, Snapshot};
 
 use crate::{
-    engine_data::{TextJustification, TextOrientation},
+    engine_data::{TextJustification, TextOrientation, TextStyleRun},
     error::PsdExportError,
 };
 
@@ -66,6 +66,10 @@ pub(crate) struct Layer {
     pub top: i32,
     pub pixels: RgbaImage,
     pub hidden: bool,
+    pub blend: BlendMode,
+    pub clipped: bool,
+    /// A grayscale mask aligned with `pixels`, white where the layer shows.
+    pub mask: Option<RgbaImage>,
     pub text: Option<TextMetadata>,
 }
 
@@ -83,6 +87,7 @@ pub(crate) struct TextMetadata {
     pub color: [u8; 4],
     pub box_width: f64,
     pub box_height: f64,
+    pub runs: Vec<TextStyleRun>,
 }
 
 pub(crate) async fn build(
@@ -139,7 +144,7 @@ pub(crate) async fn build(
         .iter()
         .filter_map(|layer| match layer.kind() {
             LayerKind::Text(text) => Some((layer.entity(), text)),
-            LayerKind::Image(_) => None,
+            LayerKind::Image(_) | LayerKind::Shape(_) => None,
         })
         .filter(|(_, text)| !text.text.trim().is_empty())
         .collect::<Vec<_>>();
@@ -149,7 +154,7 @@ pub(crate) async fn build(
     for visual in frame.layers().iter().rev() {
         let image = match visual.kind() {
             LayerKind::Image(image) => Some(image),
-            LayerKind::Text(_) => None,
+            LayerKind::Text(_) | LayerKind::Shape(_) => None,
         };
         let raster =
             image.is_some_and(|image| matches!(image.kind, ImageKind::Cleanup | ImageKind::Paint));
@@ -166,6 +171,17 @@ pub(crate) async fn build(
                 ImageKind::Paint => "Paint".to_owned(),
             });
             validate_pixels(&name, &rendered.image)?;
+            // The cropped layer is unmasked and blends normally, so its mask and
+            // blending are written as layer metadata instead of into its pixels.
+            let presentation = visual.presentation();
+            let mask = visual
+                .raster_image()
+                .and_then(|image| image.mask())
+                .filter(|mask| mask.size() == rendered.image.dimensions())
+                .and_then(|mask| {
+                    let (width, height) = mask.size();
+                    RgbaImage::from_raw(width, height, mask.pixels().to_vec())
+                });
             layers.push(Layer {
                 id: 0,
                 name,
@@ -173,11 +189,33 @@ pub(crate) async fn build(
                 top: rendered.top,
                 pixels: rendered.image,
                 hidden: false,
+                blend: presentation.blend,
+                clipped: presentation.clipped,
+                mask,
+                text: None,
+            });
+        } else if let LayerKind::Shape(shape) = visual.kind() {
+            // Shapes are written as pixel layers; their vector outline stays in the project.
+            let cropped = frame
+                .cropped(visual.entity())?
+                .ok_or(PsdExportError::MissingRenderedEntity(visual.entity()))?;
+            let rendered = rasterize(Arc::clone(&rasterizer), &cropped, raster_options).await?;
+            validate_pixels(&shape.name, &rendered.image)?;
+            layers.push(Layer {
+                id: 0,
+                name: shape.name.clone(),
+                left: rendered.left,
+                top: rendered.top,
+                pixels: rendered.image,
+                hidden: false,
+                blend: BlendMode::Normal,
+                clipped: false,
+                mask: None,
                 text: None,
             });
         } else if let Some(text) = match visual.kind() {
             LayerKind::Text(text) if !text.text.trim().is_empty() => Some(text),
-            LayerKind::Text(_) | LayerKind::Image(_) => None,
+            LayerKind::Text(_) | LayerKind::Image(_) | LayerKind::Shape(_) => None,
         } {
             let offset = text_entities
                 .iter()
@@ -197,6 +235,9 @@ pub(crate) async fn build(
                 top: rendered.top,
                 pixels: rendered.image,
                 hidden: false,
+                blend: BlendMode::Normal,
+                clipped: false,
+                mask: None,
                 text: match options.text_layer_mode {
                     TextLayerMode::Rasterized => None,
                     TextLayerMode::Editable => Some(text_metadata(index, text, &font_set)),
@@ -306,6 +347,9 @@ fn push_raster_layer(
         top: 0,
         pixels,
         hidden,
+        blend: BlendMode::Normal,
+        clipped: false,
+        mask: None,
         text: None,
     });
     Ok(())
@@ -326,7 +370,11 @@ fn validate_pixels(layer: &str, pixels: &RgbaImage) -> Result<(), PsdExportError
 
 fn collect_fonts<'a>(texts: impl Iterator<Item = &'a RenderedText>) -> Vec<String> {
     let mut fonts = Vec::new();
-    for font in texts.flat_map(|text| &text.post_script_fonts) {
+    for font in texts.flat_map(|text| {
+        text.post_script_fonts
+            .iter()
+            .chain(text.runs.iter().map(|run| &run.post_script_font))
+    }) {
         if !fonts.iter().any(|candidate| candidate == font) {
             fonts.push(font.clone());
         }
@@ -337,9 +385,11 @@ fn collect_fonts<'a>(texts: impl Iterator<Item = &'a RenderedText>) -> Vec<Strin
 fn text_metadata(index: i32, text: &RenderedText, font_set: &[String]) -> TextMetadata {
     let angle = f64::from(text.angle_degrees).to_radians();
     let bounds = text.layout_bounds;
-    let primary_font = text.post_script_fonts.first();
-    let font_index = primary_font
-        .and_then(|font| font_set.iter().position(|candidate| candidate == font))
+    let index_of = |font: &String| font_set.iter().position(|candidate| candidate == font);
+    let font_index = text
+        .post_script_fonts
+        .first()
+        .and_then(index_of)
         .unwrap_or(0);
     TextMetadata {
         index,
@@ -373,6 +423,18 @@ fn text_metadata(index: i32, text: &RenderedText, font_set: &[String]) -> TextMe
         color: text.color,
         box_width: f64::from(bounds.width.max(1.0)),
         box_height: f64::from(bounds.height.max(1.0)),
+        runs: text
+            .runs
+            .iter()
+            .map(|run| TextStyleRun {
+                range: run.range.clone(),
+                font_index: index_of(&run.post_script_font).unwrap_or(font_index),
+                font_size: f64::from(run.font_size),
+                color: run.color,
+                faux_bold: run.faux_bold,
+                faux_italic: run.faux_italic,
+            })
+            .collect(),
     }
 }
 
@@ -404,6 +466,7 @@ mod tests {
             alignment: TextAlign::Center,
             writing_mode: WritingMode::Horizontal,
             angle_degrees: 0.0,
+            runs: Vec::new(),
         }
     }
 
@@ -431,6 +494,36 @@ mod tests {
         assert!((metadata.transform[1] - 1.0).abs() < 1e-12);
     }
 
+    #[test]
+    fn text_metadata_maps_styled_runs_into_the_font_set() {
+        let mut text = rendered_text(&["Primary"]);
+        text.runs = vec![
+            koharu_renderer::TextRun {
+                range: 0..2,
+                post_script_font: "Primary".to_owned(),
+                font_size: 24.0,
+                color: [1, 2, 3, 255],
+                faux_bold: false,
+                faux_italic: false,
+            },
+            koharu_renderer::TextRun {
+                range: 2..5,
+                post_script_font: "Bold".to_owned(),
+                font_size: 36.0,
+                color: [200, 0, 0, 255],
+                faux_bold: true,
+                faux_italic: false,
+            },
+        ];
+        let fonts = collect_fonts(std::iter::once(&text));
+        assert_eq!(fonts, ["Primary", "Bold"]);
+        let metadata = text_metadata(0, &text, &fonts);
+        assert_eq!(metadata.runs.len(), 2);
+        assert_eq!(metadata.runs[1].font_index, 1);
+        assert_eq!(metadata.runs[1].font_size, 36.0);
+        assert!(metadata.runs[1].faux_bold);
+    }
+
     #[test]
     fn combine_masks_rejects_different_scene_asset_sizes() {
         let error = combine_masks(
@@ -5,6 +5,8 @@
 //! GIMP reference: `TySh` transform, descriptors, and bounds consumed by the importer:
 //! https://github.com/GNOME/gimp/blob/758fb4ed995bbb339282d3f777089a33f0a391b8/plug-ins/file-psd/psd-layer-res-load.c#L1230-L1315
 
+use std::ops::Range;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextOrientation {
     Horizontal,
@@ -31,6 +33,20 @@ pub struct TextEngineSpec {
     pub justification: TextJustification,
     pub box_width: f64,
     pub box_height: f64,
+    /// Styled runs covering `text` in order. Empty applies the spec's own
+    /// style to the whole text.
+    pub runs: Vec<TextStyleRun>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TextStyleRun {
+    /// UTF-8 byte range of the run in the spec's text.
+    pub range: Range<usize>,
+    pub font_index: usize,
+    pub font_size: f64,
+    pub color: [u8; 4],
+    pub faux_bold: bool,
+    pub faux_italic: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -60,7 +76,7 @@ pub fn encode_engine_data(spec: &TextEngineSpec) -> Vec<u8> {
 
     let paragraph_properties = paragraph_properties(spec.justification);
     let base_style_sheet = base_style_sheet(font_index);
-    let style_run_sheet = style_run_sheet(spec, font_index);
+    let style_runs = style_runs(spec, total_length);
     let font_set = spec
         .font_set
         .iter()
@@ -185,17 +201,29 @@ pub fn encode_engine_data(spec: &TextEngineSpec) -> Vec<u8> {
                         ),
                         (
                             "RunArray".to_string(),
-                            EngineValue::Array(vec![EngineValue::Dict(vec![(
-                                "StyleSheet".to_string(),
-                                EngineValue::Dict(vec![(
-                                    "StyleSheetData".to_string(),
-                                    EngineValue::Dict(style_run_sheet.clone()),
-                                )]),
-                            )])]),
+                            EngineValue::Array(
+                                style_runs
+                                    .iter()
+                                    .map(|(sheet, _)| {
+                                        EngineValue::Dict(vec![(
+                                            "StyleSheet".to_string(),
+                                            EngineValue::Dict(vec![(
+                                                "StyleSheetData".to_string(),
+                                                EngineValue::Dict(sheet.clone()),
+                                            )]),
+                                        )])
+                                    })
+                                    .collect(),
+                            ),
                         ),
                         (
                             "RunLengthArray".to_string(),
-                            EngineValue::Array(vec![EngineValue::Int(total_length)]),
+                            EngineValue::Array(
+                                style_runs
+                                    .iter()
+                                    .map(|(_, length)| EngineValue::Int(*length))
+                                    .collect(),
+                            ),
                         ),
                         ("IsJoinable".to_string(), EngineValue::Int(2)),
                     ]),
@@ -452,20 +480,64 @@ fn base_style_sheet(font_index: i32) -> Vec<(String, EngineValue)> {
     ]
 }
 
-fn style_run_sheet(spec: &TextEngineSpec, font_index: i32) -> Vec<(String, EngineValue)> {
+/// Pairs each style sheet with its UTF-16 run length. The trailing paragraph
+/// return belongs to the last run; runs that do not cover the text exactly
+/// collapse into one run in the spec's own style.
+fn style_runs(spec: &TextEngineSpec, total_length: i32) -> Vec<(Vec<(String, EngineValue)>, i32)> {
+    let whole = || {
+        vec![(
+            style_run_sheet(
+                spec.font_index,
+                spec.font_size,
+                spec.color,
+                spec.faux_bold,
+                spec.faux_italic,
+            ),
+            total_length,
+        )]
+    };
+    let mut runs = Vec::with_capacity(spec.runs.len());
+    for run in &spec.runs {
+        let Some(text) = spec.text.get(run.range.clone()) else {
+            return whole();
+        };
+        let sheet = style_run_sheet(
+            run.font_index,
+            run.font_size,
+            run.color,
+            run.faux_bold,
+            run.faux_italic,
+        );
+        let text = text.replace("\r\n", "\n");
+        runs.push((sheet, utf16_len(&text) as i32));
+    }
+    if let Some((_, length)) = runs.last_mut() {
+        *length += 1;
+    }
+    let covered = runs.iter().map(|(_, length)| *length).sum::<i32>();
+    if runs.is_empty() || covered != total_length || runs.iter().any(|(_, length)| *length <= 0) {
+        return whole();
+    }
+    runs
+}
+
+fn style_run_sheet(
+    font_index: usize,
+    font_size: f64,
+    color: [u8; 4],
+    faux_bold: bool,
+    faux_italic: bool,
+) -> Vec<(String, EngineValue)> {
     vec![
-        ("Font".to_string(), EngineValue::Int(font_index)),
-        ("FontSize".to_string(), EngineValue::Float(spec.font_size)),
-        ("FauxBold".to_string(), EngineValue::Bool(spec.faux_bold)),
-        (
-            "FauxItalic".to_string(),
-            EngineValue::Bool(spec.faux_italic),
-        ),
+        ("Font".to_string(), EngineValue::Int(font_index as i32)),
+        ("FontSize".to_string(), EngineValue::Float(font_size)),
+        ("FauxBold".to_string(), EngineValue::Bool(faux_bold)),
+        ("FauxItalic".to_string(), EngineValue::Bool(faux_italic)),
         ("AutoKerning".to_string(), EngineValue::Bool(true)),
         ("Kerning".to_string(), EngineValue::Int(0)),
         (
             "FillColor".to_string(),
-            EngineValue::Dict(color_type_values(spec.color)),
+            EngineValue::Dict(color_type_values(color)),
         ),
     ]
 }
@@ -771,7 +843,9 @@ fn serialize_float(value: f64, key: Option<&str>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{TextEngineSpec, TextJustification, TextOrientation, encode_engine_data};
+    use super::{
+        TextEngineSpec, TextJustification, TextOrientation, TextStyleRun, encode_engine_data,
+    };
 
     #[test]
     fn engine_data_contains_expected_sections_and_utf16_text() {
@@ -787,6 +861,7 @@ mod tests {
             justification: TextJustification::Center,
             box_width: 100.0,
             box_height: 32.0,
+            runs: Vec::new(),
         });
 
         assert!(
@@ -821,6 +896,7 @@ mod tests {
             justification: TextJustification::Center,
             box_width: 100.0,
             box_height: 32.0,
+            runs: Vec::new(),
         });
 
         assert!(
@@ -834,4 +910,65 @@ mod tests {
                 .any(|w| w == b"/Axis [ 1.0 0.0 1.0 ]")
         );
     }
+
+    #[test]
+    fn engine_data_writes_one_style_run_per_styled_range() {
+        let run = |range, font_index, font_size| TextStyleRun {
+            range,
+            font_index,
+            font_size,
+            color: [1, 2, 3, 255],
+            faux_bold: false,
+            faux_italic: false,
+        };
+        let bytes = encode_engine_data(&TextEngineSpec {
+            text: "Hi \u{1F600}\r\nyo".to_string(),
+            font_index: 1,
+            font_set: vec!["AdobeInvisFont".to_string(), "ArialMT".to_string()],
+            font_size: 14.0,
+            color: [1, 2, 3, 255],
+            faux_bold: false,
+            faux_italic: false,
+            orientation: TextOrientation::Horizontal,
+            justification: TextJustification::Left,
+            box_width: 100.0,
+            box_height: 32.0,
+            runs: vec![run(0..3, 1, 14.0), run(3..9, 2, 21.0), run(9..11, 1, 14.0)],
+        });
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+
+        assert!(contains(b"/FontSize 21.0"));
+        assert!(contains(b"/RunLengthArray [ 3 3 3 ]"));
+    }
+
+    #[test]
+    fn engine_data_ignores_runs_that_do_not_cover_the_text() {
+        let bytes = encode_engine_data(&TextEngineSpec {
+            text: "Hello".to_string(),
+            font_index: 1,
+            font_set: vec!["AdobeInvisFont".to_string(), "ArialMT".to_string()],
+            font_size: 14.0,
+            color: [1, 2, 3, 255],
+            faux_bold: false,
+            faux_italic: false,
+            orientation: TextOrientation::Horizontal,
+            justification: TextJustification::Left,
+            box_width: 100.0,
+            box_height: 32.0,
+            runs: vec![TextStyleRun {
+                range: 0..2,
+                font_index: 1,
+                font_size: 30.0,
+                color: [1, 2, 3, 255],
+                faux_bold: false,
+                faux_italic: false,
+            }],
+        });
+
+        assert!(
+            !bytes
+                .windows(b"/FontSize 30.0".len())
+                .any(|w| w == b"/FontSize 30.0")
+        );
+    }
 }
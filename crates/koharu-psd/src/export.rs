@@ -12,7 +12,7 @@
 use image::RgbaImage;
 use koharu_rasterizer::{RasterOptions, Rasterizer};
 use koharu_renderer::Frame;
-use koharu_scene::Snapshot;
+use koharu_scene::{BlendMode, Snapshot};
 use std::sync::Arc;
 
 use crate::{
@@ -86,10 +86,9 @@ fn build_layer_and_mask_info(
     layer_info.write_i16(if merged_has_alpha { -count } else { count });
 
     let mut encoded_layers = Vec::with_capacity(layers.len());
-    let mut extra_data = Vec::with_capacity(layers.len());
     for layer in layers {
         // GIMP writes alpha first for layer channel data, followed by color channels.
-        let channels = encode_image_rle(
+        let mut channels = encode_image_rle(
             &layer.pixels,
             &[
                 ChannelId::Alpha,
@@ -99,11 +98,13 @@ fn build_layer_and_mask_info(
             ],
             &layer.name,
         )?;
+        if let Some(mask) = layer.mask.as_ref() {
+            channels.extend(encode_image_rle(mask, &[ChannelId::Mask], &layer.name)?);
+        }
         encoded_layers.push(channels);
-        extra_data.push(build_extra_data(layer)?);
     }
 
-    for ((layer, channels), extra) in layers.iter().zip(&encoded_layers).zip(&extra_data) {
+    for (layer, channels) in layers.iter().zip(&encoded_layers) {
         let width = i32::try_from(layer.pixels.width()).map_err(|_| {
             PsdExportError::InvalidLayerBounds {
                 layer: layer.name.clone(),
@@ -136,6 +137,7 @@ fn build_layer_and_mask_info(
                     width,
                     height,
                 })?;
+        let extra = build_extra_data(layer, [layer.top, layer.left, bottom, right])?;
 
         layer_info.write_i32(layer.top);
         layer_info.write_i32(layer.left);
@@ -147,13 +149,13 @@ fn build_layer_and_mask_info(
             layer_info.write_u32((2 + channel.data.len()) as u32);
         }
         layer_info.write_signature("8BIM");
-        layer_info.write_signature("norm");
+        layer_info.write_signature(blend_key(layer.blend));
         layer_info.write_u8(255);
-        layer_info.write_u8(0);
+        layer_info.write_u8(u8::from(layer.clipped));
         layer_info.write_u8(if layer.hidden { 2 } else { 0 });
         layer_info.write_u8(0);
         layer_info.write_u32(extra.len() as u32);
-        layer_info.write_bytes(extra);
+        layer_info.write_bytes(&extra);
     }
 
     for channels in &encoded_layers {
@@ -171,9 +173,21 @@ fn build_layer_and_mask_info(
     Ok(full.into_inner())
 }
 
-fn build_extra_data(layer: &Layer) -> Result<Vec<u8>, PsdExportError> {
+/// `bounds` is the layer rectangle in top/left/bottom/right order, which the
+/// mask shares because it is the size of the layer's pixels.
+fn build_extra_data(layer: &Layer, bounds: [i32; 4]) -> Result<Vec<u8>, PsdExportError> {
     let mut extra = PsdWriter::new();
-    extra.write_u32(0); // Layer mask data.
+    if layer.mask.is_some() {
+        extra.write_u32(20); // Layer mask data.
+        for edge in bounds {
+            extra.write_i32(edge);
+        }
+        extra.write_u8(255); // Default color outside the mask rectangle.
+        extra.write_u8(0); // Flags: the mask is enabled and moves with the layer.
+        extra.write_u16(0); // Padding.
+    } else {
+        extra.write_u32(0); // Layer mask data.
+    }
     extra.write_u32(0); // Layer blending ranges.
     extra.write_pascal_string(&layer.name, 4);
 
@@ -187,6 +201,15 @@ fn build_extra_data(layer: &Layer) -> Result<Vec<u8>, PsdExportError> {
     Ok(extra.into_inner())
 }
 
+fn blend_key(mode: BlendMode) -> &'static str {
+    match mode {
+        BlendMode::Normal => "norm",
+        BlendMode::Multiply => "mul ",
+        BlendMode::Screen => "scrn",
+        BlendMode::Overlay => "over",
+    }
+}
+
 fn luni_body(name: &str) -> Vec<u8> {
     let mut units = Vec::new();
     for character in name.chars() {
@@ -221,6 +244,7 @@ fn tysh_body(text: &TextMetadata) -> Result<Vec<u8>, PsdExportError> {
         justification: text.justification,
         box_width: text.box_width,
         box_height: text.box_height,
+        runs: text.runs.clone(),
     });
     let bounds = bounds_descriptor(
         "bounds",
@@ -390,6 +414,9 @@ mod tests {
             top: 0,
             pixels: RgbaImage::from_pixel(1, 1, Rgba([1, 2, 3, 4])),
             hidden,
+            blend: BlendMode::Normal,
+            clipped: false,
+            mask: None,
             text: None,
         }
     }
@@ -444,6 +471,36 @@ mod tests {
         assert_eq!(ids, [-1, 0, 1, 2]);
     }
 
+    #[test]
+    fn blended_clipped_and_masked_layers_write_photoshop_keys() {
+        let masked = Layer {
+            blend: BlendMode::Multiply,
+            clipped: true,
+            mask: Some(RgbaImage::from_pixel(1, 1, Rgba([128, 128, 128, 255]))),
+            ..layer("masked", false)
+        };
+        let bytes = build_layer_and_mask_info(&[masked], true).expect("layer info");
+        let start = 4 + 2 + 16;
+        assert_eq!(u16::from_be_bytes([bytes[start], bytes[start + 1]]), 5);
+        let mask_record = start + 2 + 4 * 6;
+        assert_eq!(
+            i16::from_be_bytes([bytes[mask_record], bytes[mask_record + 1]]),
+            -2
+        );
+        let blend = bytes
+            .windows(8)
+            .position(|bytes| bytes == b"8BIMmul ")
+            .expect("blend signature");
+        assert_eq!(bytes[blend + 9], 1);
+        let extra = blend + 16;
+        assert_eq!(&bytes[extra..extra + 4], &20u32.to_be_bytes());
+        assert_eq!(
+            &bytes[extra + 4..extra + 20],
+            &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1]
+        );
+        assert_eq!(bytes[extra + 20], 255);
+    }
+
     #[test]
     fn transparent_merged_preview_uses_photoshop_white_matte() {
         let mut image = RgbaImage::from_pixel(1, 1, Rgba([0, 64, 255, 0]));
@@ -466,6 +523,7 @@ mod tests {
             color: [0, 0, 0, 255],
             box_width: 10.0,
             box_height: 8.0,
+            runs: Vec::new(),
         };
         let mut text_layer = layer("Text", false);
         text_layer.text = Some(text);
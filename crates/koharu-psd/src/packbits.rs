@@ -15,6 +15,8 @@ pub enum ChannelId {
     Green,
     Blue,
     Alpha,
+    /// The layer mask, read from the red channel of a grayscale image.
+    Mask,
 }
 
 impl ChannelId {
@@ -24,12 +26,13 @@ impl ChannelId {
             Self::Green => 1,
             Self::Blue => 2,
             Self::Alpha => -1,
+            Self::Mask => -2,
         }
     }
 
     fn rgba_offset(self) -> usize {
         match self {
-            Self::Red => 0,
+            Self::Red | Self::Mask => 0,
             Self::Green => 1,
             Self::Blue => 2,
             Self::Alpha => 3,
@@ -14,7 +14,7 @@ use vello::{
 
 #[cfg(target_arch = "wasm32")]
 use crate::PreparedRasterTile;
-use crate::{Error, RasterImage, ResourceId, Result};
+use crate::{BlendMode, Error, RasterImage, ResourceId, Result};
 
 pub const DEFAULT_RASTER_CACHE_BUDGET_BYTES: u64 = 256 * 1024 * 1024;
 
@@ -37,6 +37,9 @@ struct VertexOutput {
 @group(0) @binding(1) var erase_texture: texture_2d<f32>;
 @group(0) @binding(2) var texture_sampler: sampler;
 @group(0) @binding(3) var<uniform> uniforms: DrawUniforms;
+@group(0) @binding(4) var mask_texture: texture_2d<f32>;
+@group(0) @binding(5) var clip_texture: texture_2d<f32>;
+@group(0) @binding(6) var backdrop_texture: texture_2d<f32>;
 
 @vertex
 fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
@@ -64,25 +67,61 @@ fn vertex(@builtin(vertex_index) index: u32) -> VertexOutput {
     return output;
 }
 
-@fragment
-fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
-    var color: vec4<f32>;
+fn texel(texture: texture_2d<f32>, uv: vec2<f32>) -> vec4<f32> {
     if uniforms.format_options.y > 0.0 {
-        let dimensions = textureDimensions(source_texture);
+        let dimensions = textureDimensions(texture);
         let coordinate = clamp(
-            vec2<i32>(floor(input.uv * vec2<f32>(dimensions))),
+            vec2<i32>(floor(uv * vec2<f32>(dimensions))),
             vec2<i32>(0),
             vec2<i32>(dimensions) - vec2<i32>(1),
         );
-        color = textureLoad(source_texture, coordinate, 0);
-    } else {
-        color = textureSample(source_texture, texture_sampler, input.uv);
+        return textureLoad(texture, coordinate, 0);
+    }
+    return textureSample(texture, texture_sampler, uv);
+}
+
+fn blend(mode: f32, backdrop: vec3<f32>, source: vec3<f32>) -> vec3<f32> {
+    if mode < 1.5 {
+        return backdrop * source;
+    }
+    if mode < 2.5 {
+        return backdrop + source - backdrop * source;
     }
+    // Overlay is hard light with the backdrop and source exchanged.
+    let doubled = 2.0 * backdrop;
+    return select(
+        source + doubled - 1.0 - source * (doubled - 1.0),
+        source * doubled,
+        backdrop <= vec3<f32>(0.5),
+    );
+}
+
+@fragment
+fn fragment(input: VertexOutput) -> @location(0) vec4<f32> {
+    var color = texel(source_texture, input.uv);
     var erase = 0.0;
     if uniforms.target_options.w > 0.0 {
         erase = textureSample(erase_texture, texture_sampler, input.screen_uv).a;
     }
-    return color * (uniforms.target_options.z * (1.0 - erase * uniforms.target_options.w));
+    color = color * (uniforms.target_options.z * (1.0 - erase * uniforms.target_options.w));
+    if uniforms.format_options.w > 0.0 {
+        color = color * texel(mask_texture, input.uv).r;
+    }
+    if uniforms.format_options.z > 0.0 {
+        color = color * textureSample(clip_texture, texture_sampler, input.screen_uv).a;
+    }
+    if uniforms.format_options.x > 0.0 {
+        let backdrop = textureSample(backdrop_texture, texture_sampler, input.screen_uv);
+        let mixed = blend(
+            uniforms.format_options.x,
+            backdrop.rgb / max(backdrop.a, 1e-6),
+            color.rgb / max(color.a, 1e-6),
+        );
+        // Premultiplied blending adds the backdrop weighted by one minus the
+        // source alpha, which completes the separable blend equation.
+        return vec4<f32>(color.rgb * (1.0 - backdrop.a) + color.a * backdrop.a * mixed, color.a);
+    }
+    return color;
 }
 "#;
 
@@ -96,6 +135,21 @@ pub struct RasterDraw {
     pub transform: Affine,
     pub opacity: f32,
     pub erase: bool,
+    pub blend: BlendMode,
+    /// Whether the draw shows only where the closest preceding unclipped raster
+    /// draw is opaque.
+    pub clipped: bool,
+}
+
+/// Per-draw inputs beyond the source texture, opacity, and placement.
+#[derive(Clone, Copy, Default)]
+struct Effects<'a> {
+    erase: Option<&'a wgpu::TextureView>,
+    mask: Option<&'a wgpu::TextureView>,
+    clip: Option<&'a wgpu::TextureView>,
+    /// A copy of the target beneath the draw, read by non-normal blend modes.
+    backdrop: Option<&'a wgpu::TextureView>,
+    blend: BlendMode,
 }
 
 struct CachedTexture {
@@ -113,7 +167,12 @@ struct ScratchTarget {
 }
 
 impl ScratchTarget {
-    fn new(device: &wgpu::Device, label: &str, size: (u32, u32)) -> Self {
+    fn new(
+        device: &wgpu::Device,
+        label: &str,
+        size: (u32, u32),
+        usage: wgpu::TextureUsages,
+    ) -> Self {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(label),
             size: wgpu::Extent3d {
@@ -125,7 +184,7 @@ impl ScratchTarget {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            usage: usage | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         });
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -139,11 +198,14 @@ impl ScratchTarget {
 
 pub struct GpuCompositor {
     pipeline: wgpu::RenderPipeline,
+    replace_pipeline: wgpu::RenderPipeline,
     bind_group_layout: wgpu::BindGroupLayout,
     sampler: wgpu::Sampler,
     empty_mask: CachedTexture,
     overlay: Option<ScratchTarget>,
     erase: Option<ScratchTarget>,
+    clip: Option<ScratchTarget>,
+    backdrop: Option<ScratchTarget>,
     images: HashMap<ResourceId, CachedTexture>,
     image_bytes: u64,
     image_budget: u64,
@@ -188,6 +250,9 @@ impl GpuCompositor {
                     },
                     count: None,
                 },
+                texture_layout(4),
+                texture_layout(5),
+                texture_layout(6),
             ],
         });
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -199,38 +264,46 @@ impl GpuCompositor {
             label: Some("koharu raster compositor shader"),
             source: wgpu::ShaderSource::Wgsl(SHADER.into()),
         });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("koharu raster compositor pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vertex"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fragment"),
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Rgba8Unorm,
-                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview_mask: None,
-            cache: None,
-        });
+        let pipeline = |label: &str, blend: Option<wgpu::BlendState>| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vertex"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fragment"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: wgpu::TextureFormat::Rgba8Unorm,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview_mask: None,
+                cache: None,
+            })
+        };
         Self {
-            pipeline,
+            pipeline: pipeline(
+                "koharu raster compositor pipeline",
+                Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            ),
+            replace_pipeline: pipeline("koharu raster compositor replace pipeline", None),
             bind_group_layout,
             sampler,
             empty_mask: create_texture(device, "koharu empty erase mask", 1, 1, None),
             overlay: None,
             erase: None,
+            clip: None,
+            backdrop: None,
             images: HashMap::new(),
             image_bytes: 0,
             image_budget,
@@ -385,6 +458,9 @@ impl GpuCompositor {
         }
     }
 
+    /// Composes `commands` over `background` into `target`. Draws with a
+    /// non-normal blend mode read the target back, so it must also be
+    /// bindable as a texture.
     #[allow(clippy::too_many_arguments)]
     pub fn render(
         &mut self,
@@ -409,26 +485,63 @@ impl GpuCompositor {
                 .as_ref()
                 .is_none_or(|target| target.size != size)
         {
-            self.overlay = Some(ScratchTarget::new(device, "koharu vector overlay", size));
+            self.overlay = Some(ScratchTarget::new(
+                device,
+                "koharu vector overlay",
+                size,
+                wgpu::TextureUsages::STORAGE_BINDING,
+            ));
         }
         if erase_mask.is_some() && self.erase.as_ref().is_none_or(|target| target.size != size) {
-            self.erase = Some(ScratchTarget::new(device, "koharu erase overlay", size));
+            self.erase = Some(ScratchTarget::new(
+                device,
+                "koharu erase overlay",
+                size,
+                wgpu::TextureUsages::STORAGE_BINDING,
+            ));
         }
-        let active = commands
-            .iter()
-            .flat_map(|command| match command {
-                CompositionCommand::Raster(draw) => draw
-                    .image
+        let rasters = || {
+            commands.iter().filter_map(|command| match command {
+                CompositionCommand::Raster(draw) => Some(draw),
+                CompositionCommand::Vector(_) => None,
+            })
+        };
+        if rasters().any(|draw| draw.clipped)
+            && self.clip.as_ref().is_none_or(|target| target.size != size)
+        {
+            self.clip = Some(ScratchTarget::new(
+                device,
+                "koharu clipping base",
+                size,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ));
+        }
+        if rasters().any(|draw| draw.blend != BlendMode::Normal)
+            && self
+                .backdrop
+                .as_ref()
+                .is_none_or(|target| target.size != size)
+        {
+            self.backdrop = Some(ScratchTarget::new(
+                device,
+                "koharu blend backdrop",
+                size,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ));
+        }
+        let active = rasters()
+            .flat_map(|draw| {
+                draw.image
                     .tiles()
                     .iter()
+                    .chain(draw.image.mask().into_iter().flat_map(RasterImage::tiles))
                     .map(|tile| tile.id())
-                    .collect::<Vec<_>>(),
-                CompositionCommand::Vector(_) => Vec::new(),
             })
             .collect::<HashSet<_>>();
-        for command in commands {
-            if let CompositionCommand::Raster(draw) = command {
-                self.upload(device, queue, &draw.image)?;
+        for draw in rasters() {
+            self.upload(device, queue, &draw.image)?;
+            if let Some(mask) = draw.image.mask() {
+                self.upload(device, queue, mask)?;
             }
         }
         if let Some(mask) = erase_mask {
@@ -451,39 +564,87 @@ impl GpuCompositor {
             self.trim_cache_protected(&active);
             return Ok(());
         }
-        for command in commands {
+        let mut has_base = false;
+        for (index, command) in commands.iter().enumerate() {
             match command {
+                CompositionCommand::Raster(draw) if draw.clipped && !has_base => {}
                 CompositionCommand::Raster(draw) => {
-                    let erase = if draw.erase && erase_mask.is_some() {
+                    let erase = (draw.erase && erase_mask.is_some()).then(|| {
                         &self
                             .erase
                             .as_ref()
                             .expect("erase target created above")
                             .view
-                    } else {
-                        &self.empty_mask.view
-                    };
-                    for tile in draw.image.tiles() {
-                        let source = &self.images[&tile.id()].view;
-                        let origin = tile.origin();
-                        let gutter = tile.gutter();
+                    });
+                    let clip_base = !draw.clipped
+                        && commands[index + 1..]
+                            .iter()
+                            .find_map(|command| match command {
+                                CompositionCommand::Raster(draw) => Some(draw.clipped),
+                                CompositionCommand::Vector(_) => None,
+                            })
+                            .unwrap_or(false);
+                    if !draw.clipped {
+                        has_base = clip_base;
+                    }
+                    if clip_base {
+                        let base = &self.clip.as_ref().expect("clip target created above").view;
+                        clear_target(device, queue, base, [0; 4]);
+                        self.draw_raster(
+                            device,
+                            queue,
+                            draw,
+                            base,
+                            size,
+                            1.0,
+                            Effects {
+                                erase,
+                                ..Effects::default()
+                            },
+                            clip,
+                        );
+                    }
+                    let backdrop = (draw.blend != BlendMode::Normal).then(|| {
+                        let backdrop = &self
+                            .backdrop
+                            .as_ref()
+                            .expect("backdrop target created above")
+                            .view;
                         self.draw(
                             device,
                             queue,
-                            source,
-                            erase,
+                            &self.replace_pipeline,
                             target,
-                            draw.transform
-                                * Affine::translate((f64::from(origin.0), f64::from(origin.1))),
-                            tile.size(),
-                            (gutter[0], gutter[1]),
-                            tile.resource_size(),
+                            Effects::default(),
+                            backdrop,
+                            Affine::IDENTITY,
+                            size,
+                            (0, 0),
                             size,
-                            draw.opacity.clamp(0.0, 1.0),
-                            draw.erase && erase_mask.is_some(),
+                            size,
+                            1.0,
                             clip,
                         );
-                    }
+                        backdrop
+                    });
+                    self.draw_raster(
+                        device,
+                        queue,
+                        draw,
+                        target,
+                        size,
+                        draw.opacity.clamp(0.0, 1.0),
+                        Effects {
+                            erase,
+                            mask: None,
+                            clip: draw.clipped.then(|| {
+                                &self.clip.as_ref().expect("clip target created above").view
+                            }),
+                            backdrop,
+                            blend: draw.blend,
+                        },
+                        clip,
+                    );
                 }
                 CompositionCommand::Vector(scene) => {
                     let overlay = &self
@@ -495,8 +656,9 @@ impl GpuCompositor {
                     self.draw(
                         device,
                         queue,
+                        &self.pipeline,
                         overlay,
-                        &self.empty_mask.view,
+                        Effects::default(),
                         target,
                         Affine::IDENTITY,
                         size,
@@ -504,7 +666,6 @@ impl GpuCompositor {
                         size,
                         size,
                         1.0,
-                        false,
                         clip,
                     );
                 }
@@ -517,6 +678,43 @@ impl GpuCompositor {
         Ok(())
     }
 
+    /// Draws every tile of a raster, with the matching tile of its mask.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_raster(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        draw: &RasterDraw,
+        target: &wgpu::TextureView,
+        size: (u32, u32),
+        opacity: f32,
+        effects: Effects<'_>,
+        clip: [u32; 4],
+    ) {
+        let masks = draw.image.mask().map(RasterImage::tiles);
+        for (index, tile) in draw.image.tiles().iter().enumerate() {
+            let source = &self.images[&tile.id()].view;
+            let mask = masks.map(|masks| &self.images[&masks[index].id()].view);
+            let origin = tile.origin();
+            let gutter = tile.gutter();
+            self.draw(
+                device,
+                queue,
+                &self.pipeline,
+                source,
+                Effects { mask, ..effects },
+                target,
+                draw.transform * Affine::translate((f64::from(origin.0), f64::from(origin.1))),
+                tile.size(),
+                (gutter[0], gutter[1]),
+                tile.resource_size(),
+                size,
+                opacity,
+                clip,
+            );
+        }
+    }
+
     fn upload(
         &mut self,
         device: &wgpu::Device,
@@ -617,8 +815,9 @@ impl GpuCompositor {
         &self,
         device: &wgpu::Device,
         queue: &wgpu::Queue,
+        pipeline: &wgpu::RenderPipeline,
         source: &wgpu::TextureView,
-        erase: &wgpu::TextureView,
+        effects: Effects<'_>,
         target: &wgpu::TextureView,
         transform: Affine,
         source_size: (u32, u32),
@@ -626,12 +825,17 @@ impl GpuCompositor {
         resource_size: (u32, u32),
         target_size: (u32, u32),
         opacity: f32,
-        use_erase: bool,
         clip: [u32; 4],
     ) {
         let [a, b, c, d, e, f] = transform.as_coeffs();
         let pixel_aligned =
             a == 1.0 && b == 0.0 && c == 0.0 && d == 1.0 && e.fract() == 0.0 && f.fract() == 0.0;
+        let blend = match effects.backdrop.map(|_| effects.blend) {
+            None | Some(BlendMode::Normal) => 0.0,
+            Some(BlendMode::Multiply) => 1.0,
+            Some(BlendMode::Screen) => 2.0,
+            Some(BlendMode::Overlay) => 3.0,
+        };
         let values = [
             a as f32,
             b as f32,
@@ -644,11 +848,11 @@ impl GpuCompositor {
             target_size.0 as f32,
             target_size.1 as f32,
             opacity,
-            f32::from(use_erase),
-            0.0,
+            f32::from(effects.erase.is_some()),
+            blend,
             f32::from(pixel_aligned),
-            0.0,
-            0.0,
+            f32::from(effects.clip.is_some()),
+            f32::from(effects.mask.is_some()),
             sample_origin.0 as f32,
             sample_origin.1 as f32,
             resource_size.0 as f32,
@@ -673,7 +877,9 @@ impl GpuCompositor {
                 },
                 wgpu::BindGroupEntry {
                     binding: 1,
-                    resource: wgpu::BindingResource::TextureView(erase),
+                    resource: wgpu::BindingResource::TextureView(
+                        effects.erase.unwrap_or(&self.empty_mask.view),
+                    ),
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
@@ -683,6 +889,24 @@ impl GpuCompositor {
                     binding: 3,
                     resource: uniforms.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(
+                        effects.mask.unwrap_or(&self.empty_mask.view),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(
+                        effects.clip.unwrap_or(&self.empty_mask.view),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(
+                        effects.backdrop.unwrap_or(&self.empty_mask.view),
+                    ),
+                },
             ],
         });
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -706,7 +930,7 @@ impl GpuCompositor {
                 multiview_mask: None,
             });
             pass.set_scissor_rect(clip[0], clip[1], clip[2], clip[3]);
-            pass.set_pipeline(&self.pipeline);
+            pass.set_pipeline(pipeline);
             pass.set_bind_group(0, &bind_group, &[]);
             pass.draw(0..6, 0..1);
         }
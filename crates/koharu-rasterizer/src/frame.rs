@@ -11,8 +11,8 @@ use vello::{
 use crate::{
     Bounds, CompositionCommand, Error, FillRule, LayerId, LayerKind, PathElement, PreparedContent,
     PreparedElementFrame, PreparedFrame, PreparedFrameBundle, PreparedFrameManifest, PreparedPath,
-    PreparedResource, PreparedResourceStore, PreparedScene, PreparedSceneCommand, Presentation,
-    RasterDraw, ResourceId, Result, Revision,
+    PreparedRaster, PreparedResource, PreparedResourceStore, PreparedScene, PreparedSceneCommand,
+    Presentation, RasterDraw, ResourceId, Result, Revision,
 };
 
 #[derive(Clone)]
@@ -88,6 +88,7 @@ impl Frame {
         let mut commands = Vec::new();
         let mut vectors = Scene::new();
         let mut vectors_pending = false;
+        let mut base_drawn = false;
         for layer in self.layers() {
             let presentation = layer.presentation();
             if let Some(image) = layer.raster_image() {
@@ -98,15 +99,20 @@ impl Frame {
                     ))));
                     vectors_pending = false;
                 }
-                if presentation.visible
+                let shown = presentation.visible
                     && presentation.opacity.is_finite()
-                    && presentation.opacity > 0.0
-                {
+                    && presentation.opacity > 0.0;
+                if !presentation.clipped {
+                    base_drawn = shown;
+                }
+                if shown && (!presentation.clipped || base_drawn) {
                     commands.push(CompositionCommand::Raster(RasterDraw {
                         image: image.clone(),
                         transform: outer * layer.placement(),
                         opacity: presentation.opacity.clamp(0.0, 1.0),
                         erase: false,
+                        blend: presentation.blend,
+                        clipped: presentation.clipped,
                     }));
                 }
             } else {
@@ -236,6 +242,7 @@ pub struct RasterImage {
     height: u32,
     tiles: Arc<[RasterTile]>,
     pixels: Option<Arc<[u8]>>,
+    mask: Option<Arc<RasterImage>>,
 }
 
 #[derive(Clone)]
@@ -264,6 +271,13 @@ impl RasterImage {
         &self.tiles
     }
 
+    /// The layer mask, tiled like the image itself, whose red channel scales
+    /// the image's coverage.
+    #[must_use]
+    pub fn mask(&self) -> Option<&RasterImage> {
+        self.mask.as_deref()
+    }
+
     pub(crate) fn pixels(&self) -> Option<&Arc<[u8]>> {
         self.pixels.as_ref()
     }
@@ -353,60 +367,21 @@ fn compile_frame(
     for prepared in frame.layers {
         let (scene, image) = match &prepared.content {
             PreparedContent::Raster(raster) => {
-                let Some(PreparedResource::EncodedRaster { width, height, .. }) =
-                    resources.get(&raster.source).copied()
-                else {
-                    return Err(Error::invalid(
-                        "raster references a missing encoded source resource",
-                    ));
-                };
-                if (*width, *height) != (raster.width, raster.height) {
-                    return Err(Error::invalid(
-                        "encoded source dimensions do not match the raster",
-                    ));
-                }
-                let pixels = raster_sources
-                    .map(|sources| {
-                        sources.get(&raster.source).cloned().ok_or_else(|| {
-                            Error::invalid(format!(
-                                "native raster source {} is not installed",
-                                raster.source
-                            ))
-                        })
+                let mask = raster
+                    .mask
+                    .map(|mask| {
+                        raster_image(raster, mask, None, resources, raster_sources).map(Arc::new)
                     })
                     .transpose()?;
-                if let Some(pixels) = pixels.as_ref() {
-                    let expected =
-                        usize::try_from(u64::from(raster.width) * u64::from(raster.height) * 4)
-                            .map_err(|_| {
-                                Error::invalid("native raster byte length exceeds usize")
-                            })?;
-                    if pixels.len() != expected {
-                        return Err(Error::invalid(
-                            "native raster byte length does not match its dimensions",
-                        ));
-                    }
-                }
-                let mut tiles = Vec::with_capacity(raster.tiles.len());
-                for prepared_tile in &raster.tiles {
-                    tiles.push(RasterTile {
-                        id: prepared_tile.id(raster.source),
-                        x: prepared_tile.x,
-                        y: prepared_tile.y,
-                        width: prepared_tile.width,
-                        height: prepared_tile.height,
-                        gutter: prepared_tile.gutter,
-                    });
-                }
                 (
                     Scene::new(),
-                    Some(RasterImage {
-                        source: raster.source,
-                        width: raster.width,
-                        height: raster.height,
-                        tiles: tiles.into(),
-                        pixels,
-                    }),
+                    Some(raster_image(
+                        raster,
+                        raster.source,
+                        mask,
+                        resources,
+                        raster_sources,
+                    )?),
                 )
             }
             PreparedContent::Vector(prepared_scene) => (
@@ -441,6 +416,63 @@ fn compile_frame(
     })))
 }
 
+fn raster_image(
+    raster: &PreparedRaster,
+    source: ResourceId,
+    mask: Option<Arc<RasterImage>>,
+    resources: &HashMap<ResourceId, &PreparedResource>,
+    raster_sources: Option<&HashMap<ResourceId, Arc<[u8]>>>,
+) -> Result<RasterImage> {
+    let Some(PreparedResource::EncodedRaster { width, height, .. }) =
+        resources.get(&source).copied()
+    else {
+        return Err(Error::invalid(
+            "raster references a missing encoded source resource",
+        ));
+    };
+    if (*width, *height) != (raster.width, raster.height) {
+        return Err(Error::invalid(
+            "encoded source dimensions do not match the raster",
+        ));
+    }
+    let pixels = raster_sources
+        .map(|sources| {
+            sources.get(&source).cloned().ok_or_else(|| {
+                Error::invalid(format!("native raster source {source} is not installed"))
+            })
+        })
+        .transpose()?;
+    if let Some(pixels) = pixels.as_ref() {
+        let expected = usize::try_from(u64::from(raster.width) * u64::from(raster.height) * 4)
+            .map_err(|_| Error::invalid("native raster byte length exceeds usize"))?;
+        if pixels.len() != expected {
+            return Err(Error::invalid(
+                "native raster byte length does not match its dimensions",
+            ));
+        }
+    }
+    let tiles = raster
+        .tiles
+        .iter()
+        .map(|prepared_tile| RasterTile {
+            id: prepared_tile.id(source),
+            x: prepared_tile.x,
+            y: prepared_tile.y,
+            width: prepared_tile.width,
+            height: prepared_tile.height,
+            gutter: prepared_tile.gutter,
+        })
+        .collect();
+    Ok(RasterImage {
+        source,
+        width: raster.width,
+        height: raster.height,
+        tiles,
+        pixels,
+        mask,
+    })
+}
+
 fn compile_scene(
     prepared: &PreparedScene,
     resources: &HashMap<ResourceId, &PreparedResource>,
@@ -532,7 +564,7 @@ fn rgba([r, g, b, a]: [u8; 4]) -> Color {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Point, PreparedFrame, PreparedLayer, PreparedRaster, PreparedRasterTile};
+    use crate::{BlendMode, Point, PreparedFrame, PreparedLayer, PreparedRasterTile};
 
     #[test]
     fn compiled_frame_preserves_raster_identity_and_metadata() {
@@ -576,6 +608,8 @@ mod tests {
                     presentation: Presentation {
                         visible: true,
                         opacity: 0.5,
+                        blend: BlendMode::Normal,
+                        clipped: false,
                     },
                     kind: LayerKind::Raster,
                     placement: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
@@ -584,6 +618,7 @@ mod tests {
                         width: 1,
                         height: 1,
                         tiles: vec![prepared_tile],
+                        mask: None,
                     }),
                     element_frame: None,
                 }],
@@ -15,7 +15,7 @@ pub use frame::{Frame, Layer, RasterImage, RasterTile};
 #[cfg(feature = "native")]
 pub use native::{DownsampleFilter, Raster, RasterOptions, Rasterizer};
 pub use prepared::{
-    Bounds, FillRule, LayerId, LayerKind, PREPARED_FRAME_MANIFEST_VERSION,
+    BlendMode, Bounds, FillRule, LayerId, LayerKind, PREPARED_FRAME_MANIFEST_VERSION,
     PREPARED_RASTER_TILE_DIMENSION, PREPARED_RESOURCE_FORMAT_VERSION, PathElement, Point,
     PreparedContent, PreparedElementFrame, PreparedFrame, PreparedFrameBundle,
     PreparedFrameManifest, PreparedGlyph, PreparedGlyphRun, PreparedLayer, PreparedPath,
@@ -198,6 +198,8 @@ impl Rasterizer {
                         transform: transform * draw.transform,
                         opacity: draw.opacity,
                         erase: draw.erase,
+                        blend: draw.blend,
+                        clipped: draw.clipped,
                     }),
                     CompositionCommand::Vector(scene) => {
                         let mut scaled = Scene::new();
@@ -11,7 +11,7 @@ use crate::{Error, Result};
 
 const MANIFEST_MAGIC: [u8; 8] = *b"KHRMANF\0";
 const RESOURCE_MAGIC: [u8; 8] = *b"KHRRSRC\0";
-pub const PREPARED_FRAME_MANIFEST_VERSION: u16 = 4;
+pub const PREPARED_FRAME_MANIFEST_VERSION: u16 = 6;
 pub const PREPARED_RESOURCE_FORMAT_VERSION: u16 = 3;
 /// Logical raster tile edge used by portable frames. The one-pixel sampling
 /// gutter is stored outside this logical extent where adjacent pixels exist.
@@ -442,7 +442,7 @@ impl PreparedLayer {
             (LayerKind::Raster, PreparedContent::Raster(raster)) => {
                 raster.validate(resources, referenced)?;
             }
-            (LayerKind::Text, PreparedContent::Vector(scene)) => {
+            (LayerKind::Text | LayerKind::Shape, PreparedContent::Vector(scene)) => {
                 scene.validate(resources, referenced)?;
             }
             _ => {
@@ -459,6 +459,7 @@ impl PreparedLayer {
 pub enum LayerKind {
     Raster,
     Text,
+    Shape,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -479,6 +480,20 @@ pub struct Bounds {
 pub struct Presentation {
     pub visible: bool,
     pub opacity: f32,
+    pub blend: BlendMode,
+    /// Whether the layer shows only where the closest unclipped raster layer
+    /// beneath it is opaque.
+    pub clipped: bool,
+}
+
+/// Separable blend modes, as defined by the W3C compositing specification.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -513,6 +528,8 @@ pub struct PreparedRaster {
     pub width: u32,
     pub height: u32,
     pub tiles: Vec<PreparedRasterTile>,
+    /// Grayscale coverage with the source's dimensions and tiling.
+    pub mask: Option<ResourceId>,
 }
 
 impl PreparedRaster {
@@ -541,6 +558,23 @@ impl PreparedRaster {
             ));
         }
         referenced.insert(self.source);
+        if let Some(mask) = self.mask {
+            let Some(PreparedResourceRef {
+                kind: PreparedResourceKind::EncodedRaster { width, height },
+                ..
+            }) = resources.get(&mask).copied()
+            else {
+                return Err(Error::invalid(
+                    "raster references a missing encoded mask resource",
+                ));
+            };
+            if (*width, *height) != (self.width, self.height) {
+                return Err(Error::invalid(
+                    "encoded raster mask dimensions do not match the raster",
+                ));
+            }
+            referenced.insert(mask);
+        }
         let columns = self.width.div_ceil(PREPARED_RASTER_TILE_DIMENSION);
         let rows = self.height.div_ceil(PREPARED_RASTER_TILE_DIMENSION);
         if usize::try_from(u64::from(columns) * u64::from(rows)).ok() != Some(self.tiles.len()) {
@@ -1003,6 +1037,8 @@ mod tests {
                     presentation: Presentation {
                         visible: true,
                         opacity: 1.0,
+                        blend: BlendMode::Normal,
+                        clipped: false,
                     },
                     kind: LayerKind::Raster,
                     placement: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
@@ -1017,6 +1053,7 @@ mod tests {
                             height: 1,
                             gutter: [0; 4],
                         }],
+                        mask: None,
                     }),
                     element_frame: None,
                 }],
@@ -1114,6 +1151,7 @@ mod tests {
                     gutter: [1, 0, 0, 0],
                 },
             ],
+            mask: None,
         });
 
         let manifest = bundle.manifest().unwrap();
@@ -3,9 +3,9 @@
 use std::{collections::HashMap, sync::Arc};
 
 use koharu_rasterizer::{
-    Bounds, LayerId, LayerKind, Point, PreparedContent, PreparedFrame, PreparedFrameBundle,
-    PreparedLayer, PreparedRaster, PreparedRasterTile, PreparedResource, Presentation,
-    RasterOptions, Rasterizer, Revision,
+    BlendMode, Bounds, LayerId, LayerKind, Point, PreparedContent, PreparedFrame,
+    PreparedFrameBundle, PreparedLayer, PreparedRaster, PreparedRasterTile, PreparedResource,
+    Presentation, RasterOptions, Rasterizer, Revision,
 };
 
 #[test]
@@ -48,6 +48,8 @@ fn linear_filtering_does_not_add_borders_to_any_raster_layer() {
                     presentation: Presentation {
                         visible: true,
                         opacity: 1.0,
+                        blend: BlendMode::Normal,
+                        clipped: false,
                     },
                     kind: LayerKind::Raster,
                     placement: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
@@ -62,6 +64,7 @@ fn linear_filtering_does_not_add_borders_to_any_raster_layer() {
                             height: 1,
                             gutter: [0; 4],
                         }],
+                        mask: None,
                     }),
                     element_frame: None,
                 },
@@ -88,6 +91,8 @@ fn linear_filtering_does_not_add_borders_to_any_raster_layer() {
                     presentation: Presentation {
                         visible: true,
                         opacity: 1.0,
+                        blend: BlendMode::Normal,
+                        clipped: false,
                     },
                     kind: LayerKind::Raster,
                     placement: [1.0, 0.0, 0.0, 1.0, 0.5, 0.0],
@@ -102,6 +107,7 @@ fn linear_filtering_does_not_add_borders_to_any_raster_layer() {
                             height: 1,
                             gutter: [0; 4],
                         }],
+                        mask: None,
                     }),
                     element_frame: None,
                 },
@@ -126,3 +132,120 @@ fn linear_filtering_does_not_add_borders_to_any_raster_layer() {
     assert!((126..=129).contains(&edge[2]));
     assert_eq!(edge[3], u8::MAX);
 }
+
+fn blended_layer(
+    id: u8,
+    source: koharu_rasterizer::ResourceId,
+    mask: Option<koharu_rasterizer::ResourceId>,
+    blend: BlendMode,
+    clipped: bool,
+) -> PreparedLayer {
+    let bounds = Bounds {
+        x: 0.0,
+        y: 0.0,
+        width: 3.0,
+        height: 1.0,
+    };
+    PreparedLayer {
+        id: LayerId::from_bytes([id; 16]),
+        geometry: vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 3.0, y: 0.0 },
+            Point { x: 3.0, y: 1.0 },
+            Point { x: 0.0, y: 1.0 },
+        ],
+        bounds,
+        local_bounds: bounds,
+        presentation: Presentation {
+            visible: true,
+            opacity: 1.0,
+            blend,
+            clipped,
+        },
+        kind: LayerKind::Raster,
+        placement: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        content: PreparedContent::Raster(PreparedRaster {
+            source,
+            width: 3,
+            height: 1,
+            tiles: vec![PreparedRasterTile {
+                x: 0,
+                y: 0,
+                width: 3,
+                height: 1,
+                gutter: [0; 4],
+            }],
+            mask,
+        }),
+        element_frame: None,
+    }
+}
+
+#[test]
+fn blend_modes_masks_and_clipping_combine_with_the_layers_beneath() {
+    let resource = |bytes: &'static [u8]| {
+        PreparedResource::encoded_raster(3, 1, "image/png", Arc::from(bytes)).unwrap()
+    };
+    let background = resource(b"background");
+    let base = resource(b"base");
+    let multiply = resource(b"multiply");
+    let mask = resource(b"mask");
+    let clipped = resource(b"clipped");
+    let frame = PreparedFrameBundle {
+        frame: PreparedFrame {
+            revision: Revision::new(1),
+            page: LayerId::from_bytes([1; 16]),
+            width: 3,
+            height: 1,
+            origin: (0, 0),
+            normalization: [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            layers: vec![
+                blended_layer(2, background.id(), None, BlendMode::Normal, false),
+                blended_layer(3, base.id(), None, BlendMode::Normal, false),
+                blended_layer(4, multiply.id(), Some(mask.id()), BlendMode::Multiply, true),
+                blended_layer(5, clipped.id(), None, BlendMode::Screen, true),
+            ],
+        },
+        resources: vec![
+            background.clone(),
+            base.clone(),
+            multiply.clone(),
+            mask.clone(),
+            clipped.clone(),
+        ],
+    }
+    .into_frame_with_raster_sources(&HashMap::from([
+        (background.id(), Arc::from(&[0, 0, 255, 255].repeat(3)[..])),
+        (
+            base.id(),
+            Arc::from(&[128, 128, 128, 255, 128, 128, 128, 255, 0, 0, 0, 0][..]),
+        ),
+        (multiply.id(), Arc::from(&[255, 0, 255, 255].repeat(3)[..])),
+        (
+            mask.id(),
+            Arc::from(&[255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255][..]),
+        ),
+        (
+            clipped.id(),
+            Arc::from(&[0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255][..]),
+        ),
+    ]))
+    .unwrap();
+
+    let raster = Rasterizer::new()
+        .unwrap()
+        .rasterize(&frame, RasterOptions::default())
+        .unwrap();
+
+    // Multiply keeps the red and blue of the gray base, and a black screen
+    // leaves the result unchanged.
+    let blended = raster.image.get_pixel(0, 0).0;
+    assert!((126..=129).contains(&blended[0]));
+    assert_eq!(blended[1], 0);
+    assert!((126..=129).contains(&blended[2]));
+    assert_eq!(blended[3], u8::MAX);
+    // The mask hides the multiply layer, so white screens the base to white.
+    assert_eq!(raster.image.get_pixel(1, 0).0, [255; 4]);
+    // The base is transparent here, so both clipped layers are hidden.
+    assert_eq!(raster.image.get_pixel(2, 0).0, [0, 0, 255, 255]);
+}
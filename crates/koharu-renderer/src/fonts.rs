@@ -1304,4 +1304,28 @@ mod tests {
             })
         );
     }
+
+    #[tokio::test]
+    async fn preferred_families_that_are_not_installed_fall_back_to_the_stack() {
+        let fonts = Fonts::new();
+        fonts.ensure_system().await.unwrap();
+
+        let resolved = fonts
+            .resolve(
+                Some("Koharu Missing Family"),
+                Some(700),
+                None,
+                &[],
+                "Hi",
+                None,
+            )
+            .unwrap();
+
+        assert!(!resolved.is_empty());
+        assert!(
+            resolved
+                .iter()
+                .all(|font| font.family_name() != "Koharu Missing Family")
+        );
+    }
 }
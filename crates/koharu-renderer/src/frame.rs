@@ -1,16 +1,16 @@
 //! Immutable retained page output and synchronous vector access.
 
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, ops::Range, sync::Arc};
 
 use anyhow::anyhow;
 use koharu_rasterizer::{
-    Bounds as PreparedBounds, LayerId, LayerKind as PreparedLayerKind,
-    PREPARED_RASTER_TILE_DIMENSION, Point as PreparedPoint, PreparedContent, PreparedElementFrame,
-    PreparedFrame, PreparedFrameBundle, PreparedLayer, PreparedRaster, PreparedRasterTile,
-    PreparedResource, Presentation as PreparedPresentation, ResourceId,
-    Revision as PreparedRevision,
+    BlendMode as PreparedBlendMode, Bounds as PreparedBounds, LayerId,
+    LayerKind as PreparedLayerKind, PREPARED_RASTER_TILE_DIMENSION, Point as PreparedPoint,
+    PreparedContent, PreparedElementFrame, PreparedFrame, PreparedFrameBundle, PreparedLayer,
+    PreparedRaster, PreparedRasterTile, PreparedResource, Presentation as PreparedPresentation,
+    ResourceId, Revision as PreparedRevision,
 };
-use koharu_scene::{BlobId, EntityId, Geometry, LanguageTag, RelationId, Revision};
+use koharu_scene::{BlendMode, BlobId, EntityId, Geometry, LanguageTag, RelationId, Revision};
 use vello::kurbo::Affine;
 
 use crate::{Error, Result, TextAlign, WritingMode};
@@ -110,6 +110,7 @@ impl Frame {
             .layers()
             .iter()
             .filter_map(|layer| layer.raster_image())
+            .flat_map(|image| std::iter::once(image).chain(image.mask.as_deref()))
             .map(|image| (image.source, Arc::clone(&image.pixels)))
             .collect::<HashMap<_, _>>();
         self.0
@@ -123,8 +124,9 @@ impl Frame {
     /// Returns one entity normalized into a tightly cropped frame.
     ///
     /// The original layer retains authored presentation. The isolated copy is
-    /// visible at full opacity so layered exports can store pixels separately
-    /// from their visibility and opacity metadata.
+    /// visible at full opacity, blends normally, is unclipped, and leaves out
+    /// its mask, so layered exports can store pixels separately from that
+    /// metadata.
     pub fn cropped(&self, entity: EntityId) -> Result<Option<Self>> {
         let Some(layer) = self.layer(entity).cloned() else {
             return Ok(None);
@@ -164,11 +166,33 @@ impl Frame {
                 "cropped surface {width}x{height} exceeds renderer limits"
             )));
         }
+        let node = &layer.0.node;
+        let unmasked = node
+            .image
+            .as_ref()
+            .filter(|image| image.mask.is_some())
+            .map(|image| {
+                Arc::new(RetainedNode {
+                    descriptor: node.descriptor.clone(),
+                    scene: node.scene.clone(),
+                    resources: node.resources.clone(),
+                    local_bounds: node.local_bounds,
+                    image: Some(RasterImage {
+                        mask: None,
+                        ..image.clone()
+                    }),
+                    text: node.text.clone(),
+                    diagnostics: node.diagnostics.clone(),
+                })
+            });
         let isolated = Layer(Arc::new(LayerData {
             presentation: Presentation {
                 visible: true,
                 opacity: 1.0,
+                blend: BlendMode::Normal,
+                clipped: false,
             },
+            node: unmasked.unwrap_or_else(|| node.clone()),
             ..layer.0.clone_for_frame()
         }));
         let layers: Arc<[Layer]> = vec![isolated].into();
@@ -316,12 +340,19 @@ impl Layer {
 pub struct Presentation {
     pub visible: bool,
     pub opacity: f32,
+    /// How a raster layer blends with the layers beneath it. Other layers
+    /// always blend normally.
+    pub blend: BlendMode,
+    /// Whether a raster layer shows only where the closest unclipped raster
+    /// layer beneath it is opaque.
+    pub clipped: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum LayerKind {
     Image(ImageMetadata),
     Text(TextMetadata),
+    Shape(ShapeMetadata),
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -338,6 +369,11 @@ pub struct ImageMetadata {
     pub kind: ImageKind,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapeMetadata {
+    pub name: String,
+}
+
 #[derive(Clone)]
 pub struct RasterImage {
     pub(crate) blob: BlobId,
@@ -347,6 +383,7 @@ pub struct RasterImage {
     pub(crate) media_type: String,
     pub(crate) encoded: Arc<[u8]>,
     pub(crate) pixels: Arc<[u8]>,
+    pub(crate) mask: Option<Arc<RasterImage>>,
 }
 
 impl RasterImage {
@@ -364,6 +401,13 @@ impl RasterImage {
     pub fn pixels(&self) -> &[u8] {
         &self.pixels
     }
+
+    /// The layer mask, the same size as the image, whose red channel is the
+    /// coverage the image shows with.
+    #[must_use]
+    pub fn mask(&self) -> Option<&RasterImage> {
+        self.mask.as_deref()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -378,6 +422,21 @@ pub struct TextMetadata {
     pub alignment: TextAlign,
     pub writing_mode: WritingMode,
     pub angle_degrees: f32,
+    /// Consecutive styled runs covering the whole text, or empty when one
+    /// style applies throughout.
+    pub runs: Vec<TextRun>,
+}
+
+/// One run of uniformly styled text as the renderer resolved it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextRun {
+    /// UTF-8 byte range of the run in the text.
+    pub range: Range<usize>,
+    pub post_script_font: String,
+    pub font_size: f32,
+    pub color: [u8; 4],
+    pub faux_bold: bool,
+    pub faux_italic: bool,
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
@@ -442,6 +501,7 @@ impl From<crate::bubble::LayoutBox> for RenderBounds {
 pub(crate) enum NodeDescriptor {
     Image(ImageNodeDescriptor),
     Text(Box<crate::text_renderer::TextNodeDescriptor>),
+    Shape(crate::outline::ShapeNodeDescriptor),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -450,6 +510,13 @@ pub(crate) struct ImageNodeDescriptor {
     pub(crate) media_type: String,
     pub(crate) expected_size: Option<(u32, u32)>,
     pub(crate) require_size: Option<(u32, u32)>,
+    pub(crate) mask: Option<MaskNodeDescriptor>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct MaskNodeDescriptor {
+    pub(crate) blob: BlobId,
+    pub(crate) media_type: String,
 }
 
 pub(crate) struct RetainedNode {
@@ -469,6 +536,7 @@ pub(crate) struct LocalTextMetadata {
     pub(crate) post_script_fonts: Vec<String>,
     pub(crate) font_size: f32,
     pub(crate) color: [u8; 4],
+    pub(crate) runs: Vec<TextRun>,
 }
 
 pub(crate) fn prepare_frame(
@@ -488,7 +556,11 @@ pub(crate) fn prepare_frame(
                 PreparedLayerKind::Raster,
                 PreparedContent::Raster(prepare_raster_tiles(image, &mut resources)?),
             )
-        } else if matches!(layer.kind(), LayerKind::Text(_)) {
+        } else if let Some(kind) = match layer.kind() {
+            LayerKind::Text(_) => Some(PreparedLayerKind::Text),
+            LayerKind::Shape(_) => Some(PreparedLayerKind::Shape),
+            LayerKind::Image(_) => None,
+        } {
             for resource in layer.0.node.resources.iter() {
                 if !resources
                     .iter()
@@ -498,7 +570,7 @@ pub(crate) fn prepare_frame(
                 }
             }
             (
-                PreparedLayerKind::Text,
+                kind,
                 PreparedContent::Vector(layer.0.node.scene.as_ref().clone()),
             )
         } else {
@@ -522,6 +594,13 @@ pub(crate) fn prepare_frame(
             presentation: PreparedPresentation {
                 visible: layer.presentation().visible,
                 opacity: layer.presentation().opacity.clamp(0.0, 1.0),
+                blend: match layer.presentation().blend {
+                    BlendMode::Normal => PreparedBlendMode::Normal,
+                    BlendMode::Multiply => PreparedBlendMode::Multiply,
+                    BlendMode::Screen => PreparedBlendMode::Screen,
+                    BlendMode::Overlay => PreparedBlendMode::Overlay,
+                },
+                clipped: layer.presentation().clipped,
             },
             kind,
             placement: layer.placement().as_coeffs(),
@@ -547,17 +626,11 @@ fn prepare_raster_tiles(
     image: &RasterImage,
     resources: &mut Vec<PreparedResource>,
 ) -> Result<PreparedRaster> {
-    let resource = PreparedResource::encoded_raster(
-        image.width,
-        image.height,
-        image.media_type.clone(),
-        Arc::clone(&image.encoded),
-    )
-    .map_err(|error| Error::Backend(anyhow!(error)))?;
-    let source = resource.id();
-    if !resources.iter().any(|candidate| candidate.id() == source) {
-        resources.push(resource);
-    }
+    let source = prepare_encoded_raster(image, resources)?;
+    let mask = image
+        .mask()
+        .map(|mask| prepare_encoded_raster(mask, resources))
+        .transpose()?;
     let mut tiles = Vec::new();
     for y in (0..image.height).step_by(PREPARED_RASTER_TILE_DIMENSION as usize) {
         let height = (image.height - y).min(PREPARED_RASTER_TILE_DIMENSION);
@@ -583,9 +656,28 @@ fn prepare_raster_tiles(
         width: image.width,
         height: image.height,
         tiles,
+        mask,
     })
 }
 
+fn prepare_encoded_raster(
+    image: &RasterImage,
+    resources: &mut Vec<PreparedResource>,
+) -> Result<ResourceId> {
+    let resource = PreparedResource::encoded_raster(
+        image.width,
+        image.height,
+        image.media_type.clone(),
+        Arc::clone(&image.encoded),
+    )
+    .map_err(|error| Error::Backend(anyhow!(error)))?;
+    let id = resource.id();
+    if !resources.iter().any(|candidate| candidate.id() == id) {
+        resources.push(resource);
+    }
+    Ok(id)
+}
+
 fn layer_id(entity: EntityId) -> LayerId {
     LayerId::from_bytes(*entity.as_uuid().as_bytes())
 }
@@ -600,8 +692,10 @@ fn prepared_bounds(bounds: RenderBounds) -> PreparedBounds {
 }
 
 fn element_frame(layer: &Layer) -> Option<PreparedElementFrame> {
-    let LayerKind::Text(text) = layer.kind() else {
-        return None;
+    let text = match layer.kind() {
+        LayerKind::Text(text) => text,
+        LayerKind::Shape(_) => return geometry_frame(layer.geometry()),
+        LayerKind::Image(_) => return None,
     };
     let bounds = text.rendered_bounds;
     if bounds.width > 0.0 && bounds.height > 0.0 {
@@ -176,6 +176,17 @@ struct ContourConstraint {
     air_scale: f32,
 }
 
+/// Styling for one byte range of the laid-out text.
+#[derive(Clone, Debug)]
+pub(crate) struct StyledRange<'a> {
+    pub(crate) range: Range<usize>,
+    /// Fonts tried before the layout's own, or `None` to keep the layout's.
+    pub(crate) fonts: Option<&'a [Font]>,
+    /// Multiplies the layout's font size.
+    pub(crate) scale: f32,
+    pub(crate) color: Option<[u8; 4]>,
+}
+
 #[derive(Clone)]
 pub struct TextLayout<'a> {
     writing_mode: WritingMode,
@@ -185,6 +196,7 @@ pub struct TextLayout<'a> {
     comic_balloon: Option<ComicBalloon>,
     font: &'a Font,
     fallback_fonts: &'a [Font],
+    styles: &'a [StyledRange<'a>],
     font_size: Option<f32>,
     min_font_size: Option<f32>,
     max_font_size: Option<f32>,
@@ -254,6 +266,7 @@ impl<'a> TextLayout<'a> {
             comic_balloon: None,
             font,
             fallback_fonts: &[],
+            styles: &[],
             font_size: None,
             min_font_size: None,
             max_font_size: None,
@@ -359,6 +372,12 @@ impl<'a> TextLayout<'a> {
         self
     }
 
+    /// Styles ranges of the text. Ranges must be ordered and must not overlap.
+    pub(crate) fn with_styles(mut self, styles: &'a [StyledRange<'a>]) -> Self {
+        self.styles = styles;
+        self
+    }
+
     #[must_use]
     pub fn with_max_width(mut self, width: f32) -> Self {
         self.max_width = Some(width);
@@ -518,10 +537,16 @@ impl<'a> TextLayout<'a> {
         let metrics = font_ref.metrics(Size::new(font_size), self.font.location());
         let ascent = metrics.ascent;
         let descent = -metrics.descent;
+        // Lines make room for the largest span so enlarged text does not collide.
+        let line_scale = self
+            .styles
+            .iter()
+            .map(|style| style.scale)
+            .fold(1.0, f32::max);
         let line_height = self.line_height.map_or_else(
             || (ascent + descent + metrics.leading).max(font_size),
             |ratio| font_size * ratio,
-        );
+        ) * line_scale;
 
         let bidi_info = BidiInfo::new(text, None);
 
@@ -602,7 +627,6 @@ impl<'a> TextLayout<'a> {
                         char_indices.next();
                     }
 
-                    let run_text = &text[run_start..run_end];
                     let mut run_options = options.clone();
                     run_options.direction = if self.writing_mode.is_vertical() {
                         harfrust::Direction::TopToBottom
@@ -612,48 +636,70 @@ impl<'a> TextLayout<'a> {
                         harfrust::Direction::LeftToRight
                     };
 
-                    let normalized_punctuation = self
-                        .cjk_punctuation_layout
-                        .then(|| normalize_cjk_emphasis_punctuation(run_text))
-                        .flatten();
-                    let shaping_text = normalized_punctuation
-                        .as_ref()
-                        .map_or(run_text, |(text, _)| text.as_str());
-                    let script_runs =
-                        shape_script_runs(&shaper, shaping_text, &fonts, &run_options)?;
-                    for mut shaped in script_runs {
-                        self.apply_spacing(shaping_text, &mut shaped);
-                        if self.writing_mode.is_vertical() && self.center_vertical_punctuation {
-                            self.center_vertical_punctuation(
-                                font_size,
-                                shaping_text,
-                                &mut shaped.glyphs,
-                            );
-                        }
-                        if self.cjk_punctuation_layout {
-                            self.layout_cjk_emphasis_runs(font_size, shaping_text, &mut shaped);
-                        }
-                        if let Some((_, cluster_map)) = &normalized_punctuation {
-                            for glyph in &mut shaped.glyphs {
-                                if let Some(&source_cluster) =
-                                    cluster_map.get(glyph.cluster as usize)
-                                {
-                                    glyph.cluster = source_cluster;
+                    // A styled span shapes separately at its own size, in its own
+                    // fonts first, so each run keeps one style.
+                    for (piece, style) in self.styled_pieces(run_start..run_end) {
+                        let run_start = piece.start;
+                        let run_text = &text[piece];
+                        let piece_size = font_size * style.map_or(1.0, |style| style.scale);
+                        let mut piece_options = run_options.clone();
+                        piece_options.font_size = piece_size;
+                        let styled_fonts = style.and_then(|style| style.fonts).map(|styled| {
+                            styled
+                                .iter()
+                                .chain(fonts.iter().copied())
+                                .collect::<Vec<&Font>>()
+                        });
+                        let piece_fonts = styled_fonts.as_deref().unwrap_or(&fonts);
+
+                        let normalized_punctuation = self
+                            .cjk_punctuation_layout
+                            .then(|| normalize_cjk_emphasis_punctuation(run_text))
+                            .flatten();
+                        let shaping_text = normalized_punctuation
+                            .as_ref()
+                            .map_or(run_text, |(text, _)| text.as_str());
+                        let script_runs =
+                            shape_script_runs(&shaper, shaping_text, piece_fonts, &piece_options)?;
+                        for mut shaped in script_runs {
+                            self.apply_spacing(shaping_text, &mut shaped);
+                            if self.writing_mode.is_vertical() && self.center_vertical_punctuation {
+                                self.center_vertical_punctuation(
+                                    piece_size,
+                                    shaping_text,
+                                    &mut shaped.glyphs,
+                                );
+                            }
+                            if self.cjk_punctuation_layout {
+                                self.layout_cjk_emphasis_runs(
+                                    piece_size,
+                                    shaping_text,
+                                    &mut shaped,
+                                );
+                            }
+                            if let Some((_, cluster_map)) = &normalized_punctuation {
+                                for glyph in &mut shaped.glyphs {
+                                    if let Some(&source_cluster) =
+                                        cluster_map.get(glyph.cluster as usize)
+                                    {
+                                        glyph.cluster = source_cluster;
+                                    }
                                 }
                             }
-                        }
 
-                        for glyph in &mut shaped.glyphs {
-                            glyph.cluster += run_start as u32;
-                        }
+                            for glyph in &mut shaped.glyphs {
+                                glyph.cluster += run_start as u32;
+                                glyph.color = style.and_then(|style| style.color);
+                            }
 
-                        segment_advance += if self.writing_mode.is_vertical() {
-                            shaped.y_advance.abs()
-                        } else {
-                            shaped.x_advance.abs()
-                        };
+                            segment_advance += if self.writing_mode.is_vertical() {
+                                shaped.y_advance.abs()
+                            } else {
+                                shaped.x_advance.abs()
+                            };
 
-                        segment_runs.push(LineRun { shaped, level });
+                            segment_runs.push(LineRun { shaped, level });
+                        }
                     }
                 }
             }
@@ -706,7 +752,7 @@ impl<'a> TextLayout<'a> {
             }
         };
         let line_ink = if self.comic_balloon.is_some() {
-            self.shaped_block_extents(font_size, &shaped_segments)
+            self.shaped_block_extents(&shaped_segments)
                 .unwrap_or(fallback_ink)
         } else {
             fallback_ink
@@ -815,7 +861,7 @@ impl<'a> TextLayout<'a> {
         const PAD: f32 = 1.0;
         let inline_extent = lines
             .iter()
-            .filter_map(|line| self.ink_bounds(font_size, std::slice::from_ref(line)))
+            .filter_map(|line| self.ink_bounds(std::slice::from_ref(line)))
             .map(|(min_x, min_y, max_x, max_y)| {
                 if self.writing_mode.is_vertical() {
                     max_y - min_y
@@ -831,7 +877,7 @@ impl<'a> TextLayout<'a> {
         if let Some((_, inline_start, inline_center, inline_end)) = inline_box {
             for line in &mut lines {
                 if let Some((min_x, min_y, max_x, max_y)) =
-                    self.ink_bounds(font_size, std::slice::from_ref(line))
+                    self.ink_bounds(std::slice::from_ref(line))
                 {
                     let (minimum, center, maximum) = if self.writing_mode.is_vertical() {
                         (min_y, (min_y + max_y) * 0.5, max_y)
@@ -857,9 +903,7 @@ impl<'a> TextLayout<'a> {
         let (mut width, mut height) = (0.0, 0.0);
         let mut placement_offset_x = 0.0;
         let mut placement_offset_y = 0.0;
-        if let Some((mut min_x, mut min_y, mut max_x, mut max_y)) =
-            self.ink_bounds(font_size, &lines)
-        {
+        if let Some((mut min_x, mut min_y, mut max_x, mut max_y)) = self.ink_bounds(&lines) {
             // Keep a tiny safety pad for hinting/AA differences.
             min_x -= PAD;
             min_y -= PAD;
@@ -927,11 +971,29 @@ impl<'a> TextLayout<'a> {
         })
     }
 
-    fn shaped_block_extents(
-        &self,
-        font_size: f32,
-        segments: &[ShapedSegment<'a>],
-    ) -> Option<InkBand> {
+    /// Splits a range of the text at style boundaries.
+    fn styled_pieces(&self, range: Range<usize>) -> Vec<(Range<usize>, Option<&StyledRange<'a>>)> {
+        let mut pieces = Vec::new();
+        let mut position = range.start;
+        for style in &self.styles {
+            let start = style.range.start.max(position);
+            let end = style.range.end.min(range.end);
+            if start >= end {
+                continue;
+            }
+            if position < start {
+                pieces.push((position..start, None));
+            }
+            pieces.push((start..end, Some(style)));
+            position = end;
+        }
+        if position < range.end {
+            pieces.push((position..range.end, None));
+        }
+        pieces
+    }
+
+    fn shaped_block_extents(&self, segments: &[ShapedSegment<'a>]) -> Option<InkBand> {
         let mut metrics_cache = HashMap::new();
         let mut minimum = f32::INFINITY;
         let mut maximum = f32::NEG_INFINITY;
@@ -943,7 +1005,8 @@ impl<'a> TextLayout<'a> {
                 .flat_map(|suffix| suffix.runs.iter());
             for run in segment.runs.iter().chain(suffix_runs) {
                 for glyph in &run.shaped.glyphs {
-                    let key = font_key(glyph.font);
+                    let font_size = glyph.font_size;
+                    let key = (font_key(glyph.font), font_size.to_bits());
                     let glyph_metrics = match metrics_cache.entry(key) {
                         std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
                         std::collections::hash_map::Entry::Vacant(entry) => {
@@ -1170,7 +1233,7 @@ impl<'a> TextLayout<'a> {
         next_offset
     }
 
-    fn ink_bounds(&self, font_size: f32, lines: &[LayoutLine<'a>]) -> Option<(f32, f32, f32, f32)> {
+    fn ink_bounds(&self, lines: &[LayoutLine<'a>]) -> Option<(f32, f32, f32, f32)> {
         let mut metrics_cache = HashMap::new();
 
         let mut min_x = f32::INFINITY;
@@ -1181,7 +1244,8 @@ impl<'a> TextLayout<'a> {
         for line in lines {
             let (mut x, mut y) = line.baseline;
             for g in &line.glyphs {
-                let key = font_key(g.font);
+                let font_size = g.font_size;
+                let key = (font_key(g.font), font_size.to_bits());
                 let glyph_metrics = match metrics_cache.entry(key) {
                     std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
                     std::collections::hash_map::Entry::Vacant(entry) => {
@@ -3293,7 +3357,7 @@ mod tests {
         let metrics = TextLayout::new(&font);
         let line_top = |layout: &LayoutRun<'_>| {
             metrics
-                .ink_bounds(16.0, std::slice::from_ref(&layout.lines[1]))
+                .ink_bounds(std::slice::from_ref(&layout.lines[1]))
                 .unwrap()
                 .1
         };
@@ -3326,19 +3390,14 @@ mod tests {
         let tops = layout
             .lines
             .iter()
-            .map(|line| {
-                metrics
-                    .ink_bounds(16.0, std::slice::from_ref(line))
-                    .unwrap()
-                    .1
-            })
+            .map(|line| metrics.ink_bounds(std::slice::from_ref(line)).unwrap().1)
             .collect::<Vec<_>>();
 
         assert_eq!(tops.len(), 3);
         for top in &tops[1..] {
             assert_approx_eq(*top, tops[0]);
         }
-        let (_, ink_top, _, ink_bottom) = metrics.ink_bounds(16.0, &layout.lines).unwrap();
+        let (_, ink_top, _, ink_bottom) = metrics.ink_bounds(&layout.lines).unwrap();
         assert_approx_eq(ink_top, layout.height - ink_bottom);
         assert!(layout.height < 160.0);
         assert_approx_eq(layout.placement_offset_y(), 0.0);
@@ -3363,7 +3422,7 @@ mod tests {
         let metrics = TextLayout::new(&font);
         let ink_left = |layout: &LayoutRun<'_>| {
             metrics
-                .ink_bounds(16.0, std::slice::from_ref(&layout.lines[1]))
+                .ink_bounds(std::slice::from_ref(&layout.lines[1]))
                 .unwrap()
                 .0
         };
@@ -7,10 +7,12 @@ mod fonts;
 mod frame;
 mod images;
 mod layout;
+mod outline;
 mod renderer;
 mod script;
 mod segment;
 mod shape;
+mod spread;
 mod text_renderer;
 mod types;
 
@@ -18,10 +20,11 @@ pub use config::TypesettingConfig;
 pub use error::{Error, Result};
 pub use frame::{
     Frame, ImageKind, ImageMetadata, Layer, LayerKind, Presentation, RasterImage, RenderBounds,
-    RenderDependency, RenderDiagnostic, RetentionStats, TextMetadata,
+    RenderDependency, RenderDiagnostic, RetentionStats, ShapeMetadata, TextMetadata, TextRun,
 };
 pub use layout::WritingMode;
 pub use renderer::Renderer;
+pub use spread::Spread;
 pub use types::{FontFace, FontFamily, FontMetadata, FontRange, FontSource, FontStyle, TextAlign};
 
-pub(crate) use layout::{HyphenationPolicy, LayoutRun, TextLayout};
+pub(crate) use layout::{HyphenationPolicy, LayoutRun, StyledRange, TextLayout};
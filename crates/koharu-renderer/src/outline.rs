@@ -0,0 +1,194 @@
+//! Shape layer outlines built as prepared vector paths.
+
+use std::f64::consts::TAU;
+
+use koharu_rasterizer::{FillRule, PathElement, PreparedPath, PreparedScene, PreparedSceneCommand};
+use koharu_scene::{Point, ShapeOutline, ShapeStroke};
+use vello::kurbo::{
+    self, Affine, Arc, BezPath, Cap, Ellipse, Join, PathEl, Rect, RoundedRect, Shape as _, Stroke,
+    StrokeOpts, Vec2,
+};
+
+use crate::RenderBounds;
+
+const TOLERANCE: f64 = 0.1;
+/// Half the angle, measured on the ellipse, between the two points where a
+/// balloon tail leaves its body.
+const TAIL_HALF_ANGLE: f64 = 0.3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ShapeNodeDescriptor {
+    pub(crate) outline: ShapeOutline,
+    pub(crate) fill: Option<[u8; 4]>,
+    pub(crate) stroke: Option<ShapeStroke>,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+}
+
+pub(crate) struct RenderedShape {
+    pub(crate) scene: PreparedScene,
+    pub(crate) local_bounds: RenderBounds,
+}
+
+/// Builds the fill and then the stroke of a shape in its frame's local space,
+/// where the frame spans `(0, 0)` to `(width, height)`.
+pub(crate) fn render(descriptor: &ShapeNodeDescriptor) -> RenderedShape {
+    let frame = Rect::new(
+        0.0,
+        0.0,
+        f64::from(descriptor.width),
+        f64::from(descriptor.height),
+    );
+    let path = outline_path(&descriptor.outline, frame);
+    let mut bounds = frame.union(path.bounding_box());
+    let mut commands = Vec::new();
+    if let Some(color) = descriptor.fill.filter(|color| color[3] > 0) {
+        commands.push(fill(&path, color));
+    }
+    if let Some(stroke) = descriptor.stroke.filter(|stroke| stroke.color[3] > 0) {
+        let mut style = Stroke::new(f64::from(stroke.width));
+        if matches!(descriptor.outline, ShapeOutline::Path { closed: false, .. }) {
+            style = style.with_caps(Cap::Round).with_join(Join::Round);
+        }
+        let outline = kurbo::stroke(path.iter(), &style, &StrokeOpts::default(), TOLERANCE);
+        bounds = bounds.union(outline.bounding_box());
+        commands.push(fill(&outline, stroke.color));
+    }
+    RenderedShape {
+        scene: PreparedScene { commands },
+        local_bounds: RenderBounds {
+            x: bounds.x0 as f32,
+            y: bounds.y0 as f32,
+            width: bounds.width() as f32,
+            height: bounds.height() as f32,
+        },
+    }
+}
+
+fn outline_path(outline: &ShapeOutline, frame: Rect) -> BezPath {
+    let at = |point: &Point| kurbo::Point::new(point.x * frame.width(), point.y * frame.height());
+    match outline {
+        ShapeOutline::Rectangle => frame.to_path(TOLERANCE),
+        ShapeOutline::RoundedRectangle { radius } => {
+            RoundedRect::from_rect(frame, radius * frame.width().min(frame.height()))
+                .to_path(TOLERANCE)
+        }
+        ShapeOutline::Ellipse => Ellipse::from_rect(frame).to_path(TOLERANCE),
+        ShapeOutline::Path { points, closed } => {
+            let mut path = BezPath::new();
+            for (index, point) in points.iter().enumerate() {
+                if index == 0 {
+                    path.move_to(at(point));
+                } else {
+                    path.line_to(at(point));
+                }
+            }
+            if *closed {
+                path.close_path();
+            }
+            path
+        }
+        ShapeOutline::Balloon { tail } => balloon(frame, at(tail)),
+    }
+}
+
+/// One outline for the ellipse filling `frame` and its tail, so the stroke
+/// runs around both without a seam where they meet.
+fn balloon(frame: Rect, tip: kurbo::Point) -> BezPath {
+    let center = frame.center();
+    let radii = Vec2::new(frame.width() * 0.5, frame.height() * 0.5);
+    let toward = ((tip.y - center.y) / radii.y).atan2((tip.x - center.x) / radii.x);
+    let start = toward + TAIL_HALF_ANGLE;
+    let body = Arc {
+        center,
+        radii,
+        start_angle: start,
+        sweep_angle: TAU - 2.0 * TAIL_HALF_ANGLE,
+        x_rotation: 0.0,
+    };
+    let mut path = BezPath::new();
+    path.move_to(center + Vec2::new(radii.x * start.cos(), radii.y * start.sin()));
+    path.extend(body.append_iter(TOLERANCE));
+    path.line_to(tip);
+    path.close_path();
+    path
+}
+
+fn fill(path: &BezPath, color: [u8; 4]) -> PreparedSceneCommand {
+    PreparedSceneCommand::FillPath(PreparedPath {
+        fill: FillRule::NonZero,
+        transform: Affine::IDENTITY.as_coeffs(),
+        color,
+        elements: path
+            .elements()
+            .iter()
+            .map(|element| match *element {
+                PathEl::MoveTo(point) => PathElement::MoveTo([point.x, point.y]),
+                PathEl::LineTo(point) => PathElement::LineTo([point.x, point.y]),
+                PathEl::QuadTo(control, point) => {
+                    PathElement::QuadTo([control.x, control.y, point.x, point.y])
+                }
+                PathEl::CurveTo(first, second, point) => {
+                    PathElement::CurveTo([first.x, first.y, second.x, second.y, point.x, point.y])
+                }
+                PathEl::ClosePath => PathElement::Close,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(outline: ShapeOutline, stroke: Option<ShapeStroke>) -> ShapeNodeDescriptor {
+        ShapeNodeDescriptor {
+            outline,
+            fill: Some([255, 255, 255, 255]),
+            stroke,
+            width: 100.0,
+            height: 50.0,
+        }
+    }
+
+    #[test]
+    fn strokes_and_tails_extend_the_frame_bounds() {
+        let boxed = render(&descriptor(
+            ShapeOutline::Rectangle,
+            Some(ShapeStroke {
+                color: [0, 0, 0, 255],
+                width: 4.0,
+            }),
+        ));
+        assert_eq!(boxed.scene.commands.len(), 2);
+        assert_eq!(
+            boxed.local_bounds,
+            RenderBounds {
+                x: -2.0,
+                y: -2.0,
+                width: 104.0,
+                height: 54.0,
+            }
+        );
+
+        let balloon = render(&descriptor(
+            ShapeOutline::Balloon {
+                tail: Point { x: 0.25, y: 1.5 },
+            },
+            None,
+        ));
+        assert_eq!(balloon.scene.commands.len(), 1);
+        assert_eq!(balloon.local_bounds.height, 75.0);
+        let PreparedSceneCommand::FillPath(path) = &balloon.scene.commands[0] else {
+            panic!("a filled shape prepares a path");
+        };
+        assert!(path.elements.contains(&PathElement::LineTo([25.0, 75.0])));
+
+        let hollow = render(&ShapeNodeDescriptor {
+            fill: None,
+            ..descriptor(ShapeOutline::Ellipse, None)
+        });
+        assert!(hollow.scene.commands.is_empty());
+        assert_eq!(hollow.local_bounds.width, 100.0);
+    }
+}
@@ -9,11 +9,12 @@ use anyhow::{Context, anyhow};
 use arc_swap::ArcSwap;
 use koharu_rasterizer::{RasterOptions, Rasterizer};
 use koharu_scene::{
-    Asset, AssetRole, BlobId, Change, Component, ComponentOwner, EntityChange, EntityId, FitsTo,
-    FlowsIn, Geometry, Group, OcrAnalysis, Origin, Page, Presents, RasterLayer, RasterLayerKind,
-    RecognizedFrom, Region, RelationChange, RelationId, RelationSpec, Revision, Snapshot,
-    TextAlignment, TextDirection, TextLayout as SceneTextLayout, TextLayoutKind, Translation,
-    Typography, Visibility,
+    Asset, AssetRole, BasedOn, BlendMode, Blending, BlobId, Change, Character, Component,
+    ComponentOwner, EntityChange, EntityId, FitsTo, FlowsIn, Geometry, Group, OcrAnalysis, Origin,
+    Page, Presents, RasterLayer, RasterLayerKind, RecognizedFrom, Region, RelationChange,
+    RelationId, RelationSpec, Revision, ShapeLayer, Snapshot, SpokenBy, StyledBy, TextAlignment,
+    TextDirection, TextLayout as SceneTextLayout, TextLayoutKind, TextStyle, Typography,
+    TypographyOverrides, Visibility,
 };
 use parking_lot::Mutex;
 use rayon::prelude::*;
@@ -31,16 +32,17 @@ use vello::{
 use crate::{
     Error, FontFamily, FontStyle, Frame, ImageKind, ImageMetadata, Layer, LayerKind, Presentation,
     RasterImage, RenderBounds, RenderDependency, RenderDiagnostic, Result, RetentionStats,
-    TextAlign, TextMetadata, TypesettingConfig, WritingMode,
+    ShapeMetadata, TextAlign, TextMetadata, TypesettingConfig, WritingMode,
     bubble::{GeometryFrame, LayoutBox, contour, flow_cells, geometry_bounds, geometry_frame},
     fonts::{FontPreview, FontRequest, Fonts},
     frame::{
-        FrameData, ImageNodeDescriptor, LayerData, LocalTextMetadata, NodeDescriptor, RetainedNode,
-        prepare_frame,
+        FrameData, ImageNodeDescriptor, LayerData, LocalTextMetadata, MaskNodeDescriptor,
+        NodeDescriptor, RetainedNode, prepare_frame,
     },
     images::{DecodedImage, ImageCache, decode},
+    outline::{self, ShapeNodeDescriptor},
     script::{is_chinese_or_japanese_text, shaping_direction_for_text},
-    text_renderer::{StrokeOptions, TextNodeDescriptor, TextRenderer},
+    text_renderer::{StrokeOptions, TextNodeDescriptor, TextRenderer, TextSpanDescriptor},
 };
 
 const MAX_SURFACE_DIMENSION: u32 = 32_768;
@@ -48,6 +50,7 @@ const MAX_SURFACE_PIXELS: u64 = 268_435_456;
 const DEFAULT_RETAINED_NODES: usize = 2_048;
 const MAX_RESOURCE_READS: usize = 8;
 const ASSETS_KIND: &str = "dev.koharu.assets";
+const TRANSLATION_KIND: &str = "dev.koharu.text.translation";
 const MINIMUM_FONT_SIZE: f32 = 9.0;
 
 #[derive(Clone)]
@@ -291,9 +294,11 @@ impl Renderer {
             .map_err(Error::FontResource)?;
         let image_ids = pending
             .iter()
-            .filter_map(|(_, descriptor)| match descriptor {
-                NodeDescriptor::Image(image) => Some(image.blob),
-                NodeDescriptor::Text(_) => None,
+            .flat_map(|(_, descriptor)| match descriptor {
+                NodeDescriptor::Image(image) => std::iter::once(image.blob)
+                    .chain(image.mask.as_ref().map(|mask| mask.blob))
+                    .collect(),
+                NodeDescriptor::Text(_) | NodeDescriptor::Shape(_) => Vec::new(),
             })
             .collect::<BTreeSet<_>>();
         let images = Arc::new(self.load_images(snapshot, image_ids).await?);
@@ -489,6 +494,7 @@ enum DraftMetadata {
         writing_mode: WritingMode,
         angle_degrees: f32,
     },
+    Shape(ShapeMetadata),
 }
 
 struct CompiledPage {
@@ -507,6 +513,7 @@ impl Renderer {
         let page_value = snapshot.page(page)?.page()?;
         let (width, height) = surface_size(&page_value)?;
         let source_role = AssetRole::new("source")?;
+        let mask_role = AssetRole::new("mask")?;
         let flow_plan = resolve_balloon_flows(snapshot, page)?;
         let mut traversal = Traversal {
             snapshot,
@@ -514,6 +521,7 @@ impl Renderer {
             width,
             height,
             source_role: &source_role,
+            mask_role: &mask_role,
             font_families: &typesetting.font_families,
             balloon_flows: flow_plan.placements,
             layers: Vec::new(),
@@ -552,6 +560,8 @@ impl Renderer {
                 Presentation {
                     visible: true,
                     opacity: 1.0,
+                    blend: BlendMode::Normal,
+                    clipped: false,
                 },
                 Arc::from([]),
                 &mut dependencies,
@@ -567,6 +577,8 @@ impl Renderer {
             Presentation {
                 visible: true,
                 opacity: 1.0,
+                blend: BlendMode::Normal,
+                clipped: false,
             },
             &[],
         )?;
@@ -588,6 +600,7 @@ struct Traversal<'a> {
     width: u32,
     height: u32,
     source_role: &'a AssetRole,
+    mask_role: &'a AssetRole,
     font_families: &'a [String],
     balloon_flows: HashMap<EntityId, ResolvedPlacement>,
     layers: Vec<LayerDraft>,
@@ -623,6 +636,8 @@ impl Traversal<'_> {
             let presentation = Presentation {
                 visible: inherited.visible && visibility.visible,
                 opacity: inherited.opacity * visibility.opacity,
+                blend: BlendMode::Normal,
+                clipped: false,
             };
             self.dependencies.extend(common.iter().cloned());
             if self.snapshot.component::<Group>(entity)?.is_some() {
@@ -635,10 +650,26 @@ impl Traversal<'_> {
             }
             if let Some(raster) = self.snapshot.component::<RasterLayer>(entity)? {
                 common.insert(component_dependency::<RasterLayer>(entity));
+                common.insert(component_dependency::<Blending>(entity));
                 common.insert(RenderDependency::Component {
                     entity,
                     kind: ASSETS_KIND.to_owned(),
                 });
+                let presentation = match self.snapshot.component::<Blending>(entity)? {
+                    Some(blending) => Presentation {
+                        blend: blending.mode,
+                        clipped: blending.clipped,
+                        ..presentation
+                    },
+                    None => presentation,
+                };
+                let mask =
+                    self.snapshot
+                        .asset(entity, self.mask_role)?
+                        .map(|mask| MaskNodeDescriptor {
+                            blob: mask.blob,
+                            media_type: mask.media_type,
+                        });
                 if let Some(asset) = self.snapshot.asset(entity, self.source_role)? {
                     common.insert(RenderDependency::Blob(asset.blob));
                     let geometry = Geometry::rectangle(
@@ -654,7 +685,10 @@ impl Traversal<'_> {
                     self.layers.push(image_draft(
                         entity,
                         geometry,
-                        ImageNodeDescriptor::from_asset(asset, None),
+                        ImageNodeDescriptor {
+                            mask,
+                            ..ImageNodeDescriptor::from_asset(asset, None)
+                        },
                         ImageMetadata {
                             name: Some(raster.name),
                             kind,
@@ -672,9 +706,26 @@ impl Traversal<'_> {
                 self.dependencies.extend(common);
                 continue;
             }
+            if let Some(shape) = self.snapshot.component::<ShapeLayer>(entity)? {
+                common.insert(component_dependency::<ShapeLayer>(entity));
+                common.insert(component_dependency::<Geometry>(entity));
+                if let Some(geometry) = self.snapshot.component::<Geometry>(entity)? {
+                    self.layers.push(shape_draft(
+                        entity,
+                        geometry,
+                        shape,
+                        presentation,
+                        Arc::from(ancestry),
+                        &common,
+                    )?);
+                }
+                self.dependencies.extend(common);
+                continue;
+            }
             if let Some(layout) = self.snapshot.component::<SceneTextLayout>(entity)? {
                 common.insert(component_dependency::<SceneTextLayout>(entity));
                 common.insert(component_dependency::<Typography>(entity));
+                common.insert(component_dependency::<TypographyOverrides>(entity));
                 common.insert(component_dependency::<Geometry>(entity));
                 for kind in [Presents::KIND, FitsTo::KIND, FlowsIn::KIND] {
                     common.insert(RenderDependency::RelationQuery {
@@ -740,14 +791,27 @@ impl Traversal<'_> {
         dependencies.insert(RenderDependency::Relation(presents.id()));
         let content = presents.value().target;
         dependencies.insert(RenderDependency::Entity(content));
-        dependencies.insert(component_dependency::<Translation>(content));
+        dependencies.insert(translation_dependency(content));
         dependencies.insert(RenderDependency::RelationQuery {
             source: content,
             kind: RecognizedFrom::KIND.to_owned(),
         });
-        let Some(translation) = self.snapshot.component::<Translation>(content)? else {
+        let content_ref = self.snapshot.text_content(content)?;
+        let Some(translation) = content_ref.translation()? else {
             return Ok(None);
         };
+        let spans = content_ref
+            .spans(&translation)?
+            .into_iter()
+            .map(|span| TextSpanDescriptor {
+                range: span.start as usize..span.end as usize,
+                preferred_font: span.style.font,
+                font_weight: span.style.font_weight,
+                font_style: span.style.font_style.map(Into::into),
+                scale: span.style.size_scale.unwrap_or(1.0),
+                color: span.style.color,
+            })
+            .collect::<Vec<_>>();
         let text = translation.text.value;
         if text.trim().is_empty() {
             return Ok(None);
@@ -787,7 +851,35 @@ impl Traversal<'_> {
                 placement.balloon_contour,
             )
         };
-        let typography = self.snapshot.component::<Typography>(entity)?;
+        // A speaker's typography fills in whatever the layer leaves unset.
+        dependencies.insert(RenderDependency::RelationQuery {
+            source: content,
+            kind: SpokenBy::KIND.to_owned(),
+        });
+        if let Some(speaker) = self.snapshot.relation_from::<SpokenBy>(content)? {
+            dependencies.insert(RenderDependency::Relation(speaker.id()));
+            dependencies.insert(component_dependency::<Character>(speaker.value().target));
+        }
+        // So does its text style, and every style that style is based on.
+        dependencies.insert(RenderDependency::RelationQuery {
+            source: entity,
+            kind: StyledBy::KIND.to_owned(),
+        });
+        let mut style = self.snapshot.relation_from::<StyledBy>(entity)?;
+        while let Some(relation) = style {
+            let target = relation.value().target;
+            dependencies.insert(RenderDependency::Relation(relation.id()));
+            dependencies.insert(component_dependency::<TextStyle>(target));
+            dependencies.insert(RenderDependency::RelationQuery {
+                source: target,
+                kind: BasedOn::KIND.to_owned(),
+            });
+            style = self.snapshot.relation_from::<BasedOn>(target)?;
+        }
+        let typography = self
+            .snapshot
+            .text_layer(entity)?
+            .typography_for(translation.language.as_ref())?;
         let analysis =
             if let Some(recognized) = self.snapshot.relation_from::<RecognizedFrom>(content)? {
                 dependencies.insert(RenderDependency::Relation(recognized.id()));
@@ -812,7 +904,11 @@ impl Traversal<'_> {
             .as_ref()
             .and_then(|value| value.preferred_font.clone());
         let font_families = self.font_families.to_vec();
-        for family in preferred_font.iter().chain(font_families.iter()) {
+        for family in preferred_font
+            .iter()
+            .chain(font_families.iter())
+            .chain(spans.iter().filter_map(|span| span.preferred_font.as_ref()))
+        {
             dependencies.insert(RenderDependency::Font(family.clone()));
         }
         let is_bubble = balloon_contour.is_some();
@@ -846,6 +942,7 @@ impl Traversal<'_> {
             word_spacing: 0.0,
             text_inset: [4.0; 4],
             point_text: !is_bubble && layout.kind == TextLayoutKind::Point,
+            spans,
         };
         Ok(Some(LayerDraft {
             entity,
@@ -971,12 +1068,12 @@ fn resolve_balloon_flows(snapshot: &Snapshot, page: EntityId) -> Result<BalloonF
             dependencies.insert(RenderDependency::Relation(presents.id()));
             let content = presents.value().target;
             dependencies.insert(RenderDependency::Entity(content));
-            dependencies.insert(component_dependency::<Translation>(content));
+            dependencies.insert(translation_dependency(content));
             dependencies.insert(RenderDependency::RelationQuery {
                 source: content,
                 kind: RecognizedFrom::KIND.to_owned(),
             });
-            let Some(translation) = snapshot.component::<Translation>(content)? else {
+            let Some(translation) = snapshot.text_content(content)?.translation()? else {
                 continue;
             };
             if translation.text.value.trim().is_empty() {
@@ -1072,6 +1169,7 @@ impl ImageNodeDescriptor {
             media_type: asset.media_type,
             expected_size: asset.metadata.width.zip(asset.metadata.height),
             require_size,
+            mask: None,
         }
     }
 }
@@ -1092,6 +1190,9 @@ fn image_draft(
         })
         .ok_or_else(|| Error::invalid(format!("invalid image geometry for entity {entity}")))?;
     dependencies.insert(RenderDependency::Blob(descriptor.blob));
+    if let Some(mask) = &descriptor.mask {
+        dependencies.insert(RenderDependency::Blob(mask.blob));
+    }
     Ok(LayerDraft {
         entity,
         geometry,
@@ -1104,6 +1205,34 @@ fn image_draft(
     })
 }
 
+fn shape_draft(
+    entity: EntityId,
+    geometry: Geometry,
+    shape: ShapeLayer,
+    presentation: Presentation,
+    ancestry: Arc<[EntityId]>,
+    dependencies: &BTreeSet<RenderDependency>,
+) -> Result<LayerDraft> {
+    let frame = geometry_frame(&geometry)
+        .ok_or_else(|| Error::invalid(format!("invalid shape geometry for entity {entity}")))?;
+    Ok(LayerDraft {
+        entity,
+        geometry,
+        frame,
+        presentation,
+        ancestry,
+        descriptor: NodeDescriptor::Shape(ShapeNodeDescriptor {
+            outline: shape.outline,
+            fill: shape.fill,
+            stroke: shape.stroke,
+            width: frame.bounds.width,
+            height: frame.bounds.height,
+        }),
+        metadata: DraftMetadata::Shape(ShapeMetadata { name: shape.name }),
+        dependencies: dependencies.iter().cloned().collect(),
+    })
+}
+
 fn build_node(
     descriptor: NodeDescriptor,
     fonts: &Fonts,
@@ -1132,20 +1261,33 @@ fn build_node(
                     image.blob, decoded.width, decoded.height, image.require_size
                 )));
             }
-            let raster = RasterImage {
-                blob: image.blob,
-                source: koharu_rasterizer::ResourceId::for_encoded_raster(
-                    decoded.width,
-                    decoded.height,
-                    &image.media_type,
-                    &decoded.encoded,
-                ),
-                width: decoded.width,
-                height: decoded.height,
-                media_type: image.media_type.clone(),
-                encoded: decoded.encoded.clone(),
-                pixels: decoded.pixels.clone(),
-            };
+            let mask = image
+                .mask
+                .as_ref()
+                .map(|mask| {
+                    let decoded_mask = images.get(&mask.blob).ok_or_else(|| {
+                        Error::invalid(format!("mask blob {} was not loaded", mask.blob))
+                    })?;
+                    if (decoded_mask.width, decoded_mask.height) != (decoded.width, decoded.height)
+                    {
+                        return Err(Error::invalid(format!(
+                            "mask blob {} is {}x{}, expected {}x{}",
+                            mask.blob,
+                            decoded_mask.width,
+                            decoded_mask.height,
+                            decoded.width,
+                            decoded.height
+                        )));
+                    }
+                    Ok(Arc::new(raster_image(
+                        mask.blob,
+                        &mask.media_type,
+                        decoded_mask,
+                        None,
+                    )))
+                })
+                .transpose()?;
+            let raster = raster_image(image.blob, &image.media_type, decoded, mask);
             Ok(RetainedNode {
                 descriptor,
                 scene: Arc::new(koharu_rasterizer::PreparedScene::default()),
@@ -1175,10 +1317,46 @@ fn build_node(
                     post_script_fonts: rendered.metadata.post_script_fonts,
                     font_size: rendered.metadata.font_size,
                     color: rendered.metadata.color,
+                    runs: rendered.metadata.runs,
                 }),
                 diagnostics: rendered.diagnostics.into(),
             })
         }
+        NodeDescriptor::Shape(shape) => {
+            let rendered = outline::render(shape);
+            Ok(RetainedNode {
+                descriptor,
+                scene: Arc::new(rendered.scene),
+                resources: Arc::from([]),
+                local_bounds: rendered.local_bounds,
+                image: None,
+                text: None,
+                diagnostics: Arc::from([]),
+            })
+        }
+    }
+}
+
+fn raster_image(
+    blob: BlobId,
+    media_type: &str,
+    decoded: &DecodedImage,
+    mask: Option<Arc<RasterImage>>,
+) -> RasterImage {
+    RasterImage {
+        blob,
+        source: koharu_rasterizer::ResourceId::for_encoded_raster(
+            decoded.width,
+            decoded.height,
+            media_type,
+            &decoded.encoded,
+        ),
+        width: decoded.width,
+        height: decoded.height,
+        media_type: media_type.to_owned(),
+        encoded: decoded.encoded.clone(),
+        pixels: decoded.pixels.clone(),
+        mask,
     }
 }
 
@@ -1194,6 +1372,7 @@ fn assemble_frame(
         let bounds = transform_bounds(node.local_bounds, placement);
         let kind = match draft.metadata {
             DraftMetadata::Image(metadata) => LayerKind::Image(metadata),
+            DraftMetadata::Shape(metadata) => LayerKind::Shape(metadata),
             DraftMetadata::Text {
                 text,
                 language,
@@ -1216,6 +1395,7 @@ fn assemble_frame(
                     alignment,
                     writing_mode,
                     angle_degrees,
+                    runs: metadata.runs.clone(),
                 })
             }
         };
@@ -1278,7 +1458,7 @@ fn placement(draft: &LayerDraft, node: &RetainedNode) -> Affine {
             f64::from(draft.frame.bounds.x),
             f64::from(draft.frame.bounds.y),
         )),
-        NodeDescriptor::Text(_) => {
+        NodeDescriptor::Text(_) | NodeDescriptor::Shape(_) => {
             let frame = draft.frame.bounds;
             Affine::translate((
                 f64::from(frame.x + frame.width * 0.5),
@@ -1333,22 +1513,30 @@ fn font_requests(pending: &[(usize, NodeDescriptor)]) -> Vec<FontRequest> {
         let NodeDescriptor::Text(text) = descriptor else {
             continue;
         };
-        for family in text
-            .preferred_font
-            .iter()
-            .chain(text.font_families.first())
-            .take(1)
+        let family = text.preferred_font.as_ref().or(text.font_families.first());
+        let styled = text.spans.iter().map(|span| {
+            (
+                span.preferred_font.as_ref().or(family),
+                span.font_weight.or(text.font_weight),
+                span.font_style.or(text.font_style),
+            )
+        });
+        for (family, weight, font_style) in
+            std::iter::once((family, text.font_weight, text.font_style)).chain(styled)
         {
-            let style = match text.font_style.unwrap_or(FontStyle::Normal) {
+            let Some(family) = family else {
+                continue;
+            };
+            let style = match font_style.unwrap_or(FontStyle::Normal) {
                 FontStyle::Normal => 0,
                 FontStyle::Italic => 1,
                 FontStyle::Oblique => 2,
             };
-            if seen.insert((family.to_ascii_lowercase(), text.font_weight, style)) {
+            if seen.insert((family.to_ascii_lowercase(), weight, style)) {
                 requests.push(FontRequest {
                     family: family.clone(),
-                    weight: text.font_weight,
-                    style: text.font_style,
+                    weight,
+                    style: font_style,
                 });
             }
         }
@@ -1363,6 +1551,16 @@ fn component_dependency<T: Component>(entity: EntityId) -> RenderDependency {
     }
 }
 
+/// The edition is a project component, and project changes invalidate every
+/// layer, so only the content's own translations, with their spans, are
+/// tracked.
+fn translation_dependency(content: EntityId) -> RenderDependency {
+    RenderDependency::Component {
+        entity: content,
+        kind: TRANSLATION_KIND.to_owned(),
+    }
+}
+
 fn belongs_to_page(snapshot: &Snapshot, mut entity: EntityId, page: EntityId) -> Result<bool> {
     loop {
         if entity == page {
@@ -1631,7 +1829,7 @@ fn draw_font_preview(scene: &mut Scene, layout: &crate::LayoutRun<'_>) -> anyhow
             if let Some(outline) = font.outline_glyphs().get(GlyphId::new(glyph.glyph_id)) {
                 let mut path = BezPath::new();
                 outline.draw(
-                    DrawSettings::unhinted(Size::new(layout.font_size), glyph.font.location()),
+                    DrawSettings::unhinted(Size::new(glyph.font_size), glyph.font.location()),
                     &mut PreviewOutline(&mut path),
                 )?;
                 let transform = Affine::translate((
@@ -1683,8 +1881,9 @@ mod tests {
     use std::{collections::BTreeMap, io::Cursor};
 
     use koharu_scene::{
-        AssetInput, AssetMetadata, At, Authored, BubbleRegion, Generation, PageDraft, ProducerId,
-        Session, SourceText, TextLayout as SceneTextLayout, TextLayoutKind,
+        AssetInput, AssetMetadata, At, Authored, BubbleRegion, Edition, Generation, LanguageTag,
+        PageDraft, ProducerId, Session, SourceText, TextLayout as SceneTextLayout, TextLayoutKind,
+        Translation, TypographyOverride,
     };
 
     use super::*;
@@ -1846,7 +2045,7 @@ mod tests {
 
         let translation = snapshot
             .patch(|edit| {
-                edit.set(
+                edit.set_translation(
                     content,
                     &Translation {
                         text: Authored::user("visible translation".to_owned()),
@@ -1860,6 +2059,93 @@ mod tests {
         assert!(compiled.layers.iter().any(|layer| layer.entity == text));
     }
 
+    #[tokio::test]
+    async fn edition_selects_translation_and_typography() {
+        let mut session = Session::memory().await.unwrap();
+        let spanish = LanguageTag::new("es").unwrap();
+        let mut ids = None;
+        let create = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(PageDraft::new("page", 200.0, 120.0), At::End)?;
+                let content = edit.add_text_content(page, At::End)?;
+                edit.set(
+                    content,
+                    &SourceText {
+                        text: Authored::user("source".to_owned()),
+                        language: None,
+                    },
+                )?;
+                edit.set_translation(
+                    content,
+                    &Translation {
+                        text: Authored::user("hello".to_owned()),
+                        language: Some(LanguageTag::new("en").unwrap()),
+                    },
+                )?;
+                edit.set_translation(
+                    content,
+                    &Translation {
+                        text: Authored::user("hola".to_owned()),
+                        language: Some(spanish.clone()),
+                    },
+                )?;
+                let text = edit.add_text_layer(
+                    page,
+                    At::End,
+                    content,
+                    &SceneTextLayout {
+                        origin: Origin::User,
+                        kind: TextLayoutKind::Paragraph,
+                    },
+                )?;
+                edit.set(text, &Geometry::rectangle(10.0, 10.0, 80.0, 40.0))?;
+                edit.set(
+                    text,
+                    &TypographyOverrides {
+                        origin: Origin::User,
+                        languages: BTreeMap::from([(
+                            spanish.clone(),
+                            TypographyOverride {
+                                preferred_font: Some("Spanish Display".to_owned()),
+                                ..TypographyOverride::default()
+                            },
+                        )]),
+                    },
+                )?;
+                ids = Some(page);
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(create).await.unwrap().snapshot;
+        let page = ids.unwrap();
+        let renderer = Renderer::default();
+        let rendered = |snapshot: &Snapshot| {
+            let compiled = renderer.compile(snapshot, page).unwrap();
+            let [layer] = compiled.layers.as_slice() else {
+                panic!("expected one text layer");
+            };
+            let NodeDescriptor::Text(descriptor) = &layer.descriptor else {
+                panic!("expected a text layer");
+            };
+            (descriptor.text.clone(), descriptor.preferred_font.clone())
+        };
+        assert_eq!(rendered(&snapshot), ("hello".to_owned(), None));
+
+        let edition = snapshot
+            .patch(|edit| {
+                edit.set_project(&Edition {
+                    locale: spanish.clone(),
+                })
+            })
+            .unwrap();
+        let snapshot = session.commit(edition).await.unwrap().snapshot;
+        assert_eq!(
+            rendered(&snapshot),
+            ("hola".to_owned(), Some("Spanish Display".to_owned()))
+        );
+    }
+
     #[tokio::test]
     async fn analysis_regions_are_not_promoted_to_image_layers() {
         let mut session = Session::memory().await.unwrap();
@@ -1909,7 +2195,7 @@ mod tests {
                         language: None,
                     },
                 )?;
-                edit.set(
+                edit.set_translation(
                     first_content,
                     &Translation {
                         text: Authored::user("first".to_owned()),
@@ -1934,7 +2220,7 @@ mod tests {
                         language: None,
                     },
                 )?;
-                edit.set(
+                edit.set_translation(
                     second_content,
                     &Translation {
                         text: Authored::user("second".to_owned()),
@@ -2039,7 +2325,7 @@ mod tests {
                             language: None,
                         },
                     )?;
-                    edit.set(
+                    edit.set_translation(
                         content,
                         &Translation {
                             text: Authored::user(translation.to_owned()),
@@ -2158,6 +2444,8 @@ mod tests {
             Presentation {
                 visible: false,
                 opacity: 0.3,
+                blend: BlendMode::Normal,
+                clipped: false,
             }
         );
 
@@ -2185,10 +2473,74 @@ mod tests {
             Presentation {
                 visible: true,
                 opacity: 1.0,
+                blend: BlendMode::Normal,
+                clipped: false,
             }
         );
     }
 
+    #[tokio::test]
+    async fn raster_blending_and_masks_reach_the_prepared_frame() {
+        let mut session = Session::memory().await.unwrap();
+        let source = AssetRole::new("source").unwrap();
+        let mask = AssetRole::new("mask").unwrap();
+        let mut ids = None;
+        let create = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(PageDraft::new("page", 4.0, 4.0), At::End)?;
+                edit.set_asset(page, &source, asset(png(4, 4, [1, 2, 3, 255]), 4, 4))?;
+                let paint = edit.add_entity(page, At::End)?;
+                edit.set(
+                    paint,
+                    &RasterLayer {
+                        origin: Origin::User,
+                        name: "Paint 1".to_owned(),
+                        kind: RasterLayerKind::Paint,
+                    },
+                )?;
+                edit.set(
+                    paint,
+                    &Blending {
+                        origin: Origin::User,
+                        mode: BlendMode::Multiply,
+                        clipped: true,
+                    },
+                )?;
+                edit.set_asset(paint, &source, asset(png(4, 4, [9, 8, 7, 255]), 4, 4))?;
+                edit.set_asset(paint, &mask, asset(png(4, 4, [0, 0, 0, 255]), 4, 4))?;
+                ids = Some((page, paint));
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = session.commit(create).await.unwrap().snapshot;
+        let (page, paint) = ids.unwrap();
+        let frame = Renderer::new()
+            .unwrap()
+            .render(&snapshot, page)
+            .await
+            .unwrap();
+        let layer = frame.layer(paint).unwrap();
+        assert_eq!(layer.presentation().blend, BlendMode::Multiply);
+        assert!(layer.presentation().clipped);
+        assert_eq!(layer.raster_image().unwrap().mask().unwrap().size(), (4, 4));
+
+        let prepared = &frame.prepared().frame.layers[1];
+        assert_eq!(
+            prepared.presentation.blend,
+            koharu_rasterizer::BlendMode::Multiply
+        );
+        let koharu_rasterizer::PreparedContent::Raster(raster) = &prepared.content else {
+            panic!("a raster layer prepares raster content");
+        };
+        assert!(raster.mask.is_some());
+
+        let cropped = frame.cropped(paint).unwrap().unwrap();
+        let isolated = cropped.layer(paint).unwrap();
+        assert_eq!(isolated.presentation().blend, BlendMode::Normal);
+        assert!(isolated.raster_image().unwrap().mask().is_none());
+    }
+
     #[tokio::test]
     async fn update_rejects_a_non_contiguous_revision_chain() {
         let mut session = Session::memory().await.unwrap();
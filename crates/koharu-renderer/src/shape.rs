@@ -29,6 +29,11 @@ pub struct PositionedGlyph<'a> {
     /// How much the glyph moves on the Y-axis before drawing it, this should
     /// not affect how much the line advances.
     pub y_offset: f32,
+    /// Size the glyph was shaped at, which differs from the layout's size
+    /// inside a scaled span.
+    pub font_size: f32,
+    /// Fill color of a styled span, replacing the layer's color.
+    pub color: Option<[u8; 4]>,
 }
 
 /// A shaped run of text, containing positioned glyphs and overall advance.
@@ -114,6 +119,8 @@ impl TextShaper {
                 y_offset: (pos.y_offset as f32) * scale,
                 x_advance: (pos.x_advance as f32) * scale,
                 y_advance: (pos.y_advance as f32) * scale,
+                font_size: options.font_size,
+                color: None,
             });
         }
 
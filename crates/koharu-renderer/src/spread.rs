@@ -0,0 +1,109 @@
+//! Facing pages composed into one spread in the project's reading direction.
+
+use koharu_scene::{EntityId, ReadingDirection, Snapshot};
+
+use crate::{Frame, Renderer, Result};
+
+/// The page frames of one spread and where each one sits on the combined
+/// surface.
+#[derive(Clone, Debug)]
+pub struct Spread {
+    size: (u32, u32),
+    frames: Vec<((u32, u32), Frame)>,
+}
+
+impl Spread {
+    fn new(direction: ReadingDirection, frames: Vec<Frame>) -> Self {
+        let sizes = frames.iter().map(Frame::size).collect::<Vec<_>>();
+        let (size, offsets) = place(direction, &sizes);
+        Self {
+            size,
+            frames: offsets.into_iter().zip(frames).collect(),
+        }
+    }
+
+    #[must_use]
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// The page frames in reading order, each with the offset of its top-left
+    /// corner on the spread surface.
+    pub fn frames(&self) -> impl ExactSizeIterator<Item = ((u32, u32), &Frame)> {
+        self.frames.iter().map(|(offset, frame)| (*offset, frame))
+    }
+}
+
+impl Renderer {
+    /// Renders a page together with the page facing it, if the two are
+    /// paired, laid out in the project's reading direction. An unpaired page
+    /// renders as a spread of one.
+    #[tracing::instrument(level = "info", skip_all, fields(page = %page, revision = %snapshot.revision()))]
+    pub async fn render_spread(&self, snapshot: &Snapshot, page: EntityId) -> Result<Spread> {
+        let direction = snapshot.reading_direction()?;
+        let mut frames = Vec::with_capacity(2);
+        for page in snapshot.page(page)?.spread() {
+            frames.push(self.render(snapshot, page.id()).await?);
+        }
+        Ok(Spread::new(direction, frames))
+    }
+}
+
+/// Lays pages out in reading order. Facing pages share a vertical center, and
+/// vertically scrolled pages share a horizontal one.
+fn place(direction: ReadingDirection, sizes: &[(u32, u32)]) -> ((u32, u32), Vec<(u32, u32)>) {
+    let along = |(width, height): (u32, u32)| match direction {
+        ReadingDirection::VerticalScroll => (height, width),
+        ReadingDirection::RightToLeft | ReadingDirection::LeftToRight => (width, height),
+    };
+    let length = sizes.iter().map(|size| along(*size).0).sum::<u32>();
+    let breadth = sizes.iter().map(|size| along(*size).1).max().unwrap_or(0);
+    let mut cursor = 0;
+    let offsets = sizes
+        .iter()
+        .map(|size| {
+            let (extent, cross) = along(*size);
+            let start = match direction {
+                ReadingDirection::RightToLeft => length - cursor - extent,
+                ReadingDirection::LeftToRight | ReadingDirection::VerticalScroll => cursor,
+            };
+            cursor += extent;
+            let centered = (breadth - cross) / 2;
+            match direction {
+                ReadingDirection::VerticalScroll => (centered, start),
+                ReadingDirection::RightToLeft | ReadingDirection::LeftToRight => (start, centered),
+            }
+        })
+        .collect();
+    let size = match direction {
+        ReadingDirection::VerticalScroll => (breadth, length),
+        ReadingDirection::RightToLeft | ReadingDirection::LeftToRight => (length, breadth),
+    };
+    (size, offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_placed_in_reading_order() {
+        let sizes = [(100, 150), (120, 160)];
+        assert_eq!(
+            place(ReadingDirection::RightToLeft, &sizes),
+            ((220, 160), vec![(120, 5), (0, 0)])
+        );
+        assert_eq!(
+            place(ReadingDirection::LeftToRight, &sizes),
+            ((220, 160), vec![(0, 5), (100, 0)])
+        );
+        assert_eq!(
+            place(ReadingDirection::VerticalScroll, &sizes),
+            ((120, 310), vec![(10, 0), (0, 150)])
+        );
+        assert_eq!(
+            place(ReadingDirection::RightToLeft, &sizes[..1]),
+            ((100, 150), vec![(0, 0)])
+        );
+    }
+}
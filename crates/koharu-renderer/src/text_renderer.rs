@@ -1,6 +1,6 @@
 //! Text layout and portable glyph recording.
 
-use std::sync::Arc;
+use std::{ops::Range, sync::Arc};
 
 use anyhow::Result;
 use koharu_rasterizer::{
@@ -11,9 +11,9 @@ use vello::kurbo::Affine;
 
 use crate::{
     Error, FontStyle, HyphenationPolicy, LayoutRun, RenderBounds, RenderDiagnostic,
-    Result as RenderResult, TextAlign, TextLayout, WritingMode,
+    Result as RenderResult, StyledRange, TextAlign, TextLayout, TextRun, WritingMode,
     bubble::LayoutBox,
-    fonts::{Fonts, font_key},
+    fonts::{Font, Fonts, font_key},
     script::is_chinese_or_japanese_text,
 };
 
@@ -42,6 +42,24 @@ pub(crate) struct TextNodeDescriptor {
     pub(crate) word_spacing: f32,
     pub(crate) text_inset: [f32; 4],
     pub(crate) point_text: bool,
+    pub(crate) spans: Vec<TextSpanDescriptor>,
+}
+
+/// A styled byte range of a text node. Unset fields keep the node's values.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextSpanDescriptor {
+    pub(crate) range: Range<usize>,
+    pub(crate) preferred_font: Option<String>,
+    pub(crate) font_weight: Option<u16>,
+    pub(crate) font_style: Option<FontStyle>,
+    pub(crate) scale: f32,
+    pub(crate) color: Option<[u8; 4]>,
+}
+
+impl TextSpanDescriptor {
+    fn changes_font(&self) -> bool {
+        self.preferred_font.is_some() || self.font_weight.is_some() || self.font_style.is_some()
+    }
 }
 
 pub(crate) struct RenderedTextNode {
@@ -58,6 +76,7 @@ pub(crate) struct RenderedTextMetadata {
     pub(crate) post_script_fonts: Vec<String>,
     pub(crate) font_size: f32,
     pub(crate) color: [u8; 4],
+    pub(crate) runs: Vec<TextRun>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -169,6 +188,46 @@ impl TextRenderer {
                 descriptor.entity
             )));
         }
+        let language = descriptor
+            .language
+            .as_ref()
+            .map(koharu_scene::LanguageTag::as_str);
+        let span_fonts = descriptor
+            .spans
+            .iter()
+            .map(|span| {
+                if !span.changes_font() {
+                    return Ok(None);
+                }
+                fonts
+                    .resolve(
+                        span.preferred_font
+                            .as_deref()
+                            .or(descriptor.preferred_font.as_deref()),
+                        span.font_weight.or(descriptor.font_weight),
+                        span.font_style.or(descriptor.font_style),
+                        &descriptor.font_families,
+                        &descriptor.text[span.range.clone()],
+                        language,
+                    )
+                    .map(Some)
+                    .map_err(|source| Error::Font {
+                        entity: descriptor.entity,
+                        source,
+                    })
+            })
+            .collect::<RenderResult<Vec<_>>>()?;
+        let styles = descriptor
+            .spans
+            .iter()
+            .zip(&span_fonts)
+            .map(|(span, fonts)| StyledRange {
+                range: span.range.clone(),
+                fonts: fonts.as_deref(),
+                scale: span.scale,
+                color: span.color,
+            })
+            .collect::<Vec<_>>();
         let fonts = fonts
             .resolve(
                 descriptor.preferred_font.as_deref(),
@@ -176,10 +235,7 @@ impl TextRenderer {
                 descriptor.font_style,
                 &descriptor.font_families,
                 &descriptor.text,
-                descriptor
-                    .language
-                    .as_ref()
-                    .map(koharu_scene::LanguageTag::as_str),
+                language,
             )
             .map_err(|source| Error::Font {
                 entity: descriptor.entity,
@@ -194,6 +250,7 @@ impl TextRenderer {
         let minimum = descriptor.minimum_font_size.min(maximum);
         let mut layout = TextLayout::new(&fonts[0])
             .with_fallback_fonts(&fonts[1..])
+            .with_styles(&styles)
             .with_writing_mode(descriptor.writing_mode)
             .with_alignment(descriptor.alignment)
             .with_line_height(descriptor.line_height)
@@ -329,12 +386,65 @@ impl TextRenderer {
                     .collect(),
                 font_size: layout.font_size,
                 color,
+                runs: text_runs(descriptor, &fonts, &span_fonts, layout.font_size),
             },
             diagnostics,
         })
     }
 }
 
+/// Resolves the styled runs that cover a node's text, leaving out the runs
+/// when no span styles it.
+fn text_runs(
+    descriptor: &TextNodeDescriptor,
+    fonts: &[Font],
+    span_fonts: &[Option<Vec<Font>>],
+    font_size: f32,
+) -> Vec<TextRun> {
+    if descriptor.spans.is_empty() {
+        return Vec::new();
+    }
+    let run = |range, font: &Font, font_size, color| TextRun {
+        range,
+        post_script_font: font.post_script_name().to_owned(),
+        font_size,
+        color,
+        faux_bold: font.synthetic_bold(),
+        faux_italic: font.synthetic_skew().is_some(),
+    };
+    let mut runs = Vec::new();
+    let mut position = 0;
+    for (span, span_fonts) in descriptor.spans.iter().zip(span_fonts) {
+        if position < span.range.start {
+            runs.push(run(
+                position..span.range.start,
+                &fonts[0],
+                font_size,
+                descriptor.foreground_color,
+            ));
+        }
+        runs.push(run(
+            span.range.clone(),
+            span_fonts
+                .as_ref()
+                .and_then(|fonts| fonts.first())
+                .unwrap_or(&fonts[0]),
+            font_size * span.scale,
+            span.color.unwrap_or(descriptor.foreground_color),
+        ));
+        position = span.range.end;
+    }
+    if position < descriptor.text.len() {
+        runs.push(run(
+            position..descriptor.text.len(),
+            &fonts[0],
+            font_size,
+            descriptor.foreground_color,
+        ));
+    }
+    runs
+}
+
 fn automatic_maximum(
     descriptor: &TextNodeDescriptor,
     bounds: LayoutBox,
@@ -398,10 +508,14 @@ fn draw_layout(
         let mut start = 0;
 
         while start < line.glyphs.len() {
-            let font = line.glyphs[start].font;
-            let key = font_key(font);
+            let first = &line.glyphs[start];
+            let font = first.font;
+            let key = (font_key(font), first.font_size.to_bits(), first.color);
             let mut end = start + 1;
-            while end < line.glyphs.len() && font_key(line.glyphs[end].font) == key {
+            while end < line.glyphs.len() && {
+                let glyph = &line.glyphs[end];
+                (font_key(glyph.font), glyph.font_size.to_bits(), glyph.color) == key
+            } {
                 end += 1;
             }
 
@@ -431,7 +545,7 @@ fn draw_layout(
                 .push(PreparedSceneCommand::GlyphRun(PreparedGlyphRun {
                     font: font_id,
                     font_index: font.index(),
-                    font_size: layout.font_size,
+                    font_size: first.font_size,
                     normalized_coords: font.normalized_coords().to_vec(),
                     transform: transform.as_coeffs(),
                     glyph_transform,
@@ -440,7 +554,11 @@ fn draw_layout(
                     // to that same zoom instead of being rendered at a fixed pixel size.
                     hint: options.hint_glyphs && paint.dilation_px == 0.0,
                     embolden: [synthetic_bold + paint.dilation_px; 2],
-                    color: paint.color,
+                    // A span's color fills its glyphs; the border keeps one color.
+                    color: match first.color {
+                        Some(color) if paint.dilation_px == 0.0 => color,
+                        _ => paint.color,
+                    },
                     glyphs,
                 }));
             start = end;
@@ -451,7 +569,7 @@ fn draw_layout(
 #[cfg(test)]
 mod tests {
     use koharu_rasterizer::{
-        Bounds, CompositionCommand, LayerId, LayerKind as PreparedLayerKind, Point,
+        BlendMode, Bounds, CompositionCommand, LayerId, LayerKind as PreparedLayerKind, Point,
         PreparedContent, PreparedFrame, PreparedFrameBundle, PreparedFrameManifest, PreparedLayer,
         PreparedResourcePacket, PreparedResourceStore, Presentation, Revision,
     };
@@ -486,6 +604,7 @@ mod tests {
             word_spacing: 0.0,
             text_inset: [0.0; 4],
             point_text: false,
+            spans: Vec::new(),
         };
         let bounds = LayoutBox {
             x: 0.0,
@@ -553,6 +672,8 @@ mod tests {
                     presentation: Presentation {
                         visible: true,
                         opacity: 1.0,
+                        blend: BlendMode::Normal,
+                        clipped: false,
                     },
                     kind: PreparedLayerKind::Text,
                     placement: Affine::IDENTITY.as_coeffs(),
@@ -2,7 +2,12 @@ use std::collections::{BTreeMap, BTreeSet};
 
 use revision::revisioned;
 
-use crate::{EntityId, RelationId, Revision, patch::Operation};
+use crate::{
+    EntityId, RelationId, Revision,
+    component::{ComponentKey, ComponentRecord},
+    patch::Operation,
+    state::{PageState, State},
+};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Change {
@@ -166,4 +171,152 @@ impl Change {
             relations: relations.into_values().collect(),
         }
     }
+
+    /// Describes the difference between two complete states that are not
+    /// connected by recorded operations, such as a restored revision.
+    pub(crate) fn between(before: &State, after: &State) -> Self {
+        let before_nodes = hierarchy_nodes(before);
+        let after_nodes = hierarchy_nodes(after);
+        let mut entities = Vec::new();
+        let mut hierarchy = BTreeSet::new();
+        let mut components = Vec::new();
+
+        for (id, node) in &before_nodes {
+            if !after_nodes.contains_key(id) {
+                entities.push(EntityChange::Removed(*id));
+                hierarchy.insert(*id);
+                hierarchy.extend(node.parent);
+            }
+        }
+        for (id, node) in &after_nodes {
+            let Some(previous) = before_nodes.get(id) else {
+                entities.push(EntityChange::Inserted(*id));
+                hierarchy.insert(*id);
+                hierarchy.extend(node.parent);
+                continue;
+            };
+            if previous.page != node.page
+                || previous.parent != node.parent
+                || previous.children != node.children
+            {
+                hierarchy.insert(*id);
+            }
+            let (Ok(previous), Ok(current)) = (before.entity(*id), after.entity(*id)) else {
+                continue;
+            };
+            diff_components(
+                ComponentOwner::Entity(*id),
+                &previous.components,
+                &current.components,
+                &mut components,
+            );
+        }
+        for (index, page) in after.page_order.iter().enumerate() {
+            if before
+                .page_order
+                .iter()
+                .position(|candidate| candidate == page)
+                .is_some_and(|previous| previous != index)
+            {
+                hierarchy.insert(*page);
+            }
+        }
+        diff_components(
+            ComponentOwner::Project,
+            &before.project_components,
+            &after.project_components,
+            &mut components,
+        );
+
+        let mut relations = BTreeMap::new();
+        for id in before.relations.keys() {
+            if !after.relations.contains_key(id) {
+                relations.insert(*id, RelationChange::Removed(*id));
+            }
+        }
+        for (id, relation) in &after.relations {
+            let Some(previous) = before.relations.get(id) else {
+                relations.insert(*id, RelationChange::Inserted(*id));
+                continue;
+            };
+            if previous.value != relation.value || previous.components != relation.components {
+                relations.insert(*id, RelationChange::Changed(*id));
+            }
+            diff_components(
+                ComponentOwner::Relation(*id),
+                &previous.components,
+                &relation.components,
+                &mut components,
+            );
+        }
+
+        entities.sort_by_key(|change| match change {
+            EntityChange::Inserted(id) | EntityChange::Removed(id) => *id,
+        });
+        components.sort_by(|left, right| (left.owner, &left.kind).cmp(&(right.owner, &right.kind)));
+        Self {
+            from: before.revision,
+            to: after.revision,
+            entities,
+            hierarchy: hierarchy.into_iter().collect(),
+            components,
+            relations: relations.into_values().collect(),
+        }
+    }
+}
+
+struct HierarchyNode {
+    page: EntityId,
+    parent: Option<EntityId>,
+    children: Vec<EntityId>,
+}
+
+fn hierarchy_nodes(state: &State) -> BTreeMap<EntityId, HierarchyNode> {
+    let mut nodes = BTreeMap::new();
+    for (page_id, page) in &state.pages {
+        let page: &PageState = page;
+        for node in page.entities.values() {
+            nodes.insert(
+                node.id,
+                HierarchyNode {
+                    page: *page_id,
+                    parent: node.parent.map(|key| page.entities[key].id),
+                    children: node
+                        .children
+                        .iter()
+                        .map(|key| page.entities[*key].id)
+                        .collect(),
+                },
+            );
+        }
+    }
+    nodes
+}
+
+fn diff_components(
+    owner: ComponentOwner,
+    before: &[(ComponentKey, ComponentRecord)],
+    after: &[(ComponentKey, ComponentRecord)],
+    changes: &mut Vec<ComponentChange>,
+) {
+    let mut kinds = BTreeMap::<&str, (Option<&ComponentRecord>, Option<&ComponentRecord>)>::new();
+    for (key, record) in before {
+        kinds.entry(&key.kind).or_default().0 = Some(record);
+    }
+    for (key, record) in after {
+        kinds.entry(&key.kind).or_default().1 = Some(record);
+    }
+    for (kind, values) in kinds {
+        let change = match values {
+            (None, Some(_)) => ValueChangeKind::Inserted,
+            (Some(_), None) => ValueChangeKind::Removed,
+            (Some(previous), Some(current)) if previous != current => ValueChangeKind::Replaced,
+            _ => continue,
+        };
+        changes.push(ComponentChange {
+            owner,
+            kind: kind.to_owned(),
+            change,
+        });
+    }
 }
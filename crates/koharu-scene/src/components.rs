@@ -5,25 +5,43 @@
 //! to `document`.
 
 mod analysis;
+mod annotations;
 mod assets;
+mod chapters;
+mod characters;
+mod glossary;
 mod groups;
 mod layers;
 mod provenance;
 mod spatial;
 mod structure;
+mod styles;
 mod text;
 
 pub use analysis::{
     DetectionAnalysis, DetectionLabel, OcrAnalysis, Region, RegionKind, TextDirection,
 };
+pub use annotations::{Annotation, AnnotationMark, AnnotationReply};
 pub(crate) use assets::Assets;
 pub use assets::{Asset, AssetInput, AssetMetadata, AssetRole};
+pub use chapters::{Chapter, Volume};
+pub use characters::Character;
+pub use glossary::GlossaryTerm;
 pub use groups::{Group, TextGroup};
 pub use layers::{
-    FontStyle, RasterLayer, RasterLayerKind, TextAlignment, TextLayout, TextLayoutKind, Typography,
-    WritingMode,
+    BlendMode, Blending, FontStyle, RasterLayer, RasterLayerKind, ShapeLayer, ShapeOutline,
+    ShapeStroke, TextAlignment, TextLayout, TextLayoutKind, Typography, TypographyOverride,
+    TypographyOverrides, WritingMode,
 };
 pub use provenance::{Authored, Generation, Origin};
 pub use spatial::{Geometry, Point, Visibility};
-pub use structure::{EntityOrigin, Page, PageDraft, Project, Relation, RelationKind};
-pub use text::{LanguageTag, SourceText, TextContent, TextRole, Translation};
+pub use structure::{
+    Edition, EntityOrigin, Page, PageDraft, Project, Reading, ReadingDirection, Relation,
+    RelationKind,
+};
+pub use styles::TextStyle;
+pub use text::{
+    LanguageTag, ReviewState, SourceText, SpanStyle, TextContent, TextRole, TextSpan, Translation,
+    TranslationCandidate, TranslationCandidates,
+};
+pub(crate) use text::{StoredTranslation, Translations, translation_store};
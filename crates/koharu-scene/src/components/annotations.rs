@@ -0,0 +1,100 @@
+use revision::revisioned;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Error, Result,
+    component::{Component, ValidationContext},
+};
+
+use super::Point;
+
+/// Marks the part of an anchor an annotation refers to, in page coordinates.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub enum AnnotationMark {
+    Point(Point),
+    Polygon(Vec<Point>),
+}
+
+impl AnnotationMark {
+    fn validate(&self) -> Result<()> {
+        let points = match self {
+            Self::Point(point) => std::slice::from_ref(point),
+            Self::Polygon(points) if (3..=4096).contains(&points.len()) => points,
+            Self::Polygon(_) => {
+                return Err(Error::invalid(
+                    "annotation polygon must contain 3 to 4096 points",
+                ));
+            }
+        };
+        if points
+            .iter()
+            .all(|point| point.x.is_finite() && point.y.is_finite())
+        {
+            Ok(())
+        } else {
+            Err(Error::invalid("annotation mark points must be finite"))
+        }
+    }
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct AnnotationReply {
+    pub author: String,
+    pub body: String,
+}
+
+/// A reviewer's note left on a page, region, or text layer, followed by the
+/// replies to it. Annotations are always user-authored.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct Annotation {
+    pub author: String,
+    pub body: String,
+    pub mark: Option<AnnotationMark>,
+    pub replies: Vec<AnnotationReply>,
+    pub resolved: bool,
+}
+
+impl Annotation {
+    #[must_use]
+    pub fn new(author: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            author: author.into(),
+            body: body.into(),
+            mark: None,
+            replies: Vec::new(),
+            resolved: false,
+        }
+    }
+}
+
+impl Component for Annotation {
+    const KIND: &'static str = "dev.koharu.annotation";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        if let Some(mark) = &self.mark {
+            mark.validate()?;
+        }
+        if self.replies.len() > 4096 {
+            return Err(Error::invalid("annotation has too many replies"));
+        }
+        validate_message(&self.author, &self.body)?;
+        for reply in &self.replies {
+            validate_message(&reply.author, &reply.body)?;
+        }
+        Ok(())
+    }
+}
+
+fn validate_message(author: &str, body: &str) -> Result<()> {
+    let author_valid = !author.trim().is_empty() && author.len() <= 256 && !author.contains('\0');
+    let body_valid = !body.trim().is_empty() && body.len() <= 64 * 1024 && !body.contains('\0');
+    if author_valid && body_valid {
+        Ok(())
+    } else {
+        Err(Error::invalid("annotation author or message is invalid"))
+    }
+}
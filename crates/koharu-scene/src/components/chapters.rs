@@ -0,0 +1,100 @@
+use revision::revisioned;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Error, Result,
+    component::{Component, ValidationContext},
+};
+
+use super::Origin;
+
+/// A volume of the series. Its chapters are its children in the project arena.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct Volume {
+    pub origin: Origin,
+    pub title: String,
+    pub number: Option<f32>,
+}
+
+impl Volume {
+    #[must_use]
+    pub fn new(title: impl Into<String>, number: Option<f32>) -> Self {
+        Self {
+            origin: Origin::User,
+            title: title.into(),
+            number,
+        }
+    }
+}
+
+impl Component for Volume {
+    const KIND: &'static str = "dev.koharu.volume";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        validate_heading("volume", &self.title, self.number)
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
+
+/// A chapter of the series, at the project root or inside a volume. Pages
+/// join it with an `in-chapter` relation.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct Chapter {
+    pub origin: Origin,
+    pub title: String,
+    /// The number the series gives the chapter; extras may use fractions
+    /// such as 12.5.
+    pub number: Option<f32>,
+}
+
+impl Chapter {
+    #[must_use]
+    pub fn new(title: impl Into<String>, number: Option<f32>) -> Self {
+        Self {
+            origin: Origin::User,
+            title: title.into(),
+            number,
+        }
+    }
+}
+
+impl Component for Chapter {
+    const KIND: &'static str = "dev.koharu.chapter";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        validate_heading("chapter", &self.title, self.number)
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
+
+fn validate_heading(role: &str, title: &str, number: Option<f32>) -> Result<()> {
+    if title.len() > 4096
+        || title.contains('\0')
+        || number.is_some_and(|number| !number.is_finite() || number < 0.0)
+    {
+        Err(Error::invalid(format!("{role} title or number is invalid")))
+    } else {
+        Ok(())
+    }
+}
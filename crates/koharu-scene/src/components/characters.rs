@@ -0,0 +1,72 @@
+use revision::revisioned;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Error, Result,
+    component::{Component, ValidationContext},
+};
+
+use super::{Origin, TypographyOverride};
+
+/// A speaking character of the project. Text content names its speaker with a
+/// `spoken-by` relation, so translations can keep one voice per character and
+/// text layers can inherit the character's typography.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct Character {
+    pub origin: Origin,
+    pub name: String,
+    /// Other names the character goes by in the source, such as nicknames.
+    pub aliases: Vec<String>,
+    /// How the character speaks, for translators: register, dialect, or verbal tics.
+    pub speech_notes: String,
+    /// Typography that the character's text layers use where their own leaves
+    /// a field unset.
+    pub typography: Option<TypographyOverride>,
+}
+
+impl Character {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            origin: Origin::User,
+            name: name.into(),
+            aliases: Vec::new(),
+            speech_notes: String::new(),
+            typography: None,
+        }
+    }
+}
+
+impl Component for Character {
+    const KIND: &'static str = "dev.koharu.character";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        let name_valid =
+            |name: &str| !name.trim().is_empty() && name.len() <= 256 && !name.contains('\0');
+        if !name_valid(&self.name)
+            || self.aliases.len() > 256
+            || !self.aliases.iter().all(|alias| name_valid(alias))
+        {
+            return Err(Error::invalid("character name or aliases are invalid"));
+        }
+        if self.speech_notes.len() > 64 * 1024 || self.speech_notes.contains('\0') {
+            return Err(Error::invalid("character speech notes are invalid"));
+        }
+        match &self.typography {
+            Some(typography) => typography.validate(),
+            None => Ok(()),
+        }
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
@@ -0,0 +1,103 @@
+use revision::revisioned;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Error, Result,
+    component::{Component, ValidationContext},
+};
+
+use super::Origin;
+
+/// A recurring term of the project and the rendering every translation must
+/// use for it, so names and terminology read the same on every page.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct GlossaryTerm {
+    pub origin: Origin,
+    /// The term as it appears in source text.
+    pub source: String,
+    /// The rendering translations must use. Ignored when the term is kept
+    /// untranslated.
+    pub target: String,
+    /// Guidance for translators, such as where the term comes from.
+    pub notes: String,
+    /// Whether source text and translations must match the term's case.
+    pub case_sensitive: bool,
+    /// Whether translations keep the source term as written.
+    pub do_not_translate: bool,
+}
+
+impl GlossaryTerm {
+    #[must_use]
+    pub fn new(source: impl Into<String>, target: impl Into<String>) -> Self {
+        Self {
+            origin: Origin::User,
+            source: source.into(),
+            target: target.into(),
+            notes: String::new(),
+            case_sensitive: false,
+            do_not_translate: false,
+        }
+    }
+
+    /// The text a translation must contain wherever the term occurs.
+    #[must_use]
+    pub fn rendering(&self) -> &str {
+        if self.do_not_translate {
+            &self.source
+        } else {
+            &self.target
+        }
+    }
+
+    /// Whether `text` contains the source term.
+    #[must_use]
+    pub fn occurs_in(&self, text: &str) -> bool {
+        self.contains(text, &self.source)
+    }
+
+    /// Whether `translation` of a text containing the term uses the required
+    /// rendering.
+    #[must_use]
+    pub fn is_rendered_in(&self, translation: &str) -> bool {
+        self.contains(translation, self.rendering())
+    }
+
+    fn contains(&self, text: &str, term: &str) -> bool {
+        if self.case_sensitive {
+            text.contains(term)
+        } else {
+            text.to_lowercase().contains(&term.to_lowercase())
+        }
+    }
+}
+
+impl Component for GlossaryTerm {
+    const KIND: &'static str = "dev.koharu.glossary.term";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        // A term kept untranslated needs no rendering, but any it has must
+        // still be a valid term.
+        let term_valid = |term: &str, required: bool| {
+            (!required || !term.trim().is_empty()) && term.len() <= 256 && !term.contains('\0')
+        };
+        if !term_valid(&self.source, true) || !term_valid(&self.target, !self.do_not_translate) {
+            return Err(Error::invalid("glossary term or its rendering is invalid"));
+        }
+        if self.notes.len() > 64 * 1024 || self.notes.contains('\0') {
+            return Err(Error::invalid("glossary term notes are invalid"));
+        }
+        Ok(())
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
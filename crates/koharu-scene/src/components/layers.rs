@@ -10,7 +10,7 @@ use crate::{
     id::validate_namespaced,
 };
 
-use super::Origin;
+use super::{LanguageTag, Origin, Point};
 
 #[revisioned(revision = 1)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
@@ -50,6 +50,143 @@ impl Component for RasterLayer {
     }
 }
 
+#[revisioned(revision = 1)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+}
+
+/// How a raster layer combines with the layers beneath it. A clipped layer
+/// shows only where its clipping base, the closest unclipped raster layer
+/// beneath it, is opaque. A raster layer without this component blends
+/// normally and is not clipped.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct Blending {
+    pub origin: Origin,
+    pub mode: BlendMode,
+    pub clipped: bool,
+}
+
+impl Component for Blending {
+    const KIND: &'static str = "dev.koharu.layer.blending";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
+
+/// The outline of a shape layer. Points are relative to the layer's geometry
+/// frame, from `(0, 0)` at its top-left corner to `(1, 1)` at the opposite one,
+/// so moving, scaling, or rotating the frame carries the outline with it.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeOutline {
+    Rectangle,
+    /// Corners rounded by `radius`, a fraction of the frame's shorter side
+    /// up to one half.
+    RoundedRectangle {
+        radius: f64,
+    },
+    Ellipse,
+    Path {
+        points: Vec<Point>,
+        closed: bool,
+    },
+    /// An elliptical balloon with a tail pointing at `tail`, which lies
+    /// outside the ellipse.
+    Balloon {
+        tail: Point,
+    },
+}
+
+impl ShapeOutline {
+    fn validate(&self) -> Result<()> {
+        let valid = match self {
+            Self::Rectangle | Self::Ellipse => true,
+            Self::RoundedRectangle { radius } => radius.is_finite() && (0.0..=0.5).contains(radius),
+            Self::Path { points, closed } => {
+                (if *closed { 3 } else { 2 }..=65_536).contains(&points.len())
+                    && points
+                        .iter()
+                        .all(|point| point.x.is_finite() && point.y.is_finite())
+            }
+            Self::Balloon { tail } => {
+                tail.x.is_finite()
+                    && tail.y.is_finite()
+                    && (tail.x * 2.0 - 1.0).powi(2) + (tail.y * 2.0 - 1.0).powi(2) > 1.0
+            }
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::invalid("shape outline is invalid"))
+        }
+    }
+}
+
+#[revisioned(revision = 1)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct ShapeStroke {
+    pub color: [u8; 4],
+    pub width: f32,
+}
+
+/// A user-drawn vector layer, such as a caption box or a patched balloon. Its
+/// `Geometry` is the frame the outline fills, and its `Visibility` carries the
+/// layer opacity.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct ShapeLayer {
+    pub origin: Origin,
+    pub name: String,
+    pub outline: ShapeOutline,
+    pub fill: Option<[u8; 4]>,
+    pub stroke: Option<ShapeStroke>,
+}
+
+impl Component for ShapeLayer {
+    const KIND: &'static str = "dev.koharu.layer.shape";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        if self.name.is_empty() || self.name.len() > 4096 || self.name.contains('\0') {
+            return Err(Error::invalid("shape layer name is invalid"));
+        }
+        if self
+            .stroke
+            .is_some_and(|stroke| !stroke.width.is_finite() || stroke.width <= 0.0)
+        {
+            return Err(Error::invalid("shape stroke width is invalid"));
+        }
+        self.outline.validate()
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
+
 #[revisioned(revision = 1)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
 #[serde(rename_all = "snake_case")]
@@ -167,3 +304,155 @@ impl Component for Typography {
         true
     }
 }
+
+/// Typography that one edition of a text layer replaces. Unset fields keep the
+/// layer's own typography.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct TypographyOverride {
+    pub preferred_font: Option<String>,
+    pub font_weight: Option<u16>,
+    pub font_style: Option<FontStyle>,
+    pub size: Option<f32>,
+    pub auto_fit: Option<bool>,
+    pub color: Option<[u8; 4]>,
+    pub stroke_color: Option<[u8; 4]>,
+    pub stroke_width: Option<f32>,
+    pub alignment: Option<TextAlignment>,
+    pub writing_mode: Option<WritingMode>,
+}
+
+impl TypographyOverride {
+    /// Applies the override to a layer's typography, or to automatic
+    /// typography when the layer has none.
+    #[must_use]
+    pub fn apply(&self, typography: Option<Typography>) -> Typography {
+        let mut typography = typography.unwrap_or_else(|| Typography {
+            origin: Origin::User,
+            preferred_font: None,
+            font_weight: None,
+            font_style: None,
+            size: None,
+            auto_fit: true,
+            color: None,
+            stroke_color: None,
+            stroke_width: None,
+            alignment: None,
+            writing_mode: None,
+            extensions: BTreeMap::new(),
+        });
+        if let Some(font) = &self.preferred_font {
+            typography.preferred_font = Some(font.clone());
+        }
+        typography.font_weight = self.font_weight.or(typography.font_weight);
+        typography.font_style = self.font_style.or(typography.font_style);
+        typography.size = self.size.or(typography.size);
+        typography.auto_fit = self.auto_fit.unwrap_or(typography.auto_fit);
+        typography.color = self.color.or(typography.color);
+        typography.stroke_color = self.stroke_color.or(typography.stroke_color);
+        typography.stroke_width = self.stroke_width.or(typography.stroke_width);
+        typography.alignment = self.alignment.or(typography.alignment);
+        typography.writing_mode = self.writing_mode.or(typography.writing_mode);
+        typography
+    }
+
+    /// Fills the fields a layer's typography leaves unset, such as with a
+    /// speaking character's defaults. Without layer typography this is
+    /// [`Self::apply`] to automatic typography.
+    #[must_use]
+    pub fn fill(&self, typography: Option<Typography>) -> Typography {
+        let Some(mut typography) = typography else {
+            return self.apply(None);
+        };
+        if typography.preferred_font.is_none() {
+            typography.preferred_font.clone_from(&self.preferred_font);
+        }
+        typography.font_weight = typography.font_weight.or(self.font_weight);
+        typography.font_style = typography.font_style.or(self.font_style);
+        typography.size = typography.size.or(self.size);
+        typography.color = typography.color.or(self.color);
+        typography.stroke_color = typography.stroke_color.or(self.stroke_color);
+        typography.stroke_width = typography.stroke_width.or(self.stroke_width);
+        typography.alignment = typography.alignment.or(self.alignment);
+        typography.writing_mode = typography.writing_mode.or(self.writing_mode);
+        typography
+    }
+
+    /// Keeps this override's fields and takes the ones it leaves unset from
+    /// `base`, such as a text style's parent.
+    #[must_use]
+    pub fn inherit(&self, base: &Self) -> Self {
+        Self {
+            preferred_font: self
+                .preferred_font
+                .clone()
+                .or_else(|| base.preferred_font.clone()),
+            font_weight: self.font_weight.or(base.font_weight),
+            font_style: self.font_style.or(base.font_style),
+            size: self.size.or(base.size),
+            auto_fit: self.auto_fit.or(base.auto_fit),
+            color: self.color.or(base.color),
+            stroke_color: self.stroke_color.or(base.stroke_color),
+            stroke_width: self.stroke_width.or(base.stroke_width),
+            alignment: self.alignment.or(base.alignment),
+            writing_mode: self.writing_mode.or(base.writing_mode),
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self
+            .preferred_font
+            .as_ref()
+            .is_some_and(|font| font.len() > 4096)
+            || self
+                .font_weight
+                .is_some_and(|weight| !(1..=1000).contains(&weight))
+            || self
+                .size
+                .is_some_and(|size| !size.is_finite() || size <= 0.0)
+            || self
+                .stroke_width
+                .is_some_and(|width| !width.is_finite() || width < 0.0)
+        {
+            Err(Error::invalid("typography override is invalid"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Per-language typography of a text layer, such as a different font or size
+/// for an edition whose script sets differently.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct TypographyOverrides {
+    pub origin: Origin,
+    pub languages: BTreeMap<LanguageTag, TypographyOverride>,
+}
+
+impl Component for TypographyOverrides {
+    const KIND: &'static str = "dev.koharu.text.typography.overrides";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        if self.languages.len() > 1024 {
+            return Err(Error::invalid(
+                "text layer has too many typography overrides",
+            ));
+        }
+        for (language, value) in &self.languages {
+            language.validate()?;
+            value.validate()?;
+        }
+        Ok(())
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
@@ -40,6 +40,45 @@ impl Component for Project {
     }
 }
 
+/// The edition a project currently renders, exports, and translates into.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
+pub struct Edition {
+    pub locale: LanguageTag,
+}
+
+impl Component for Edition {
+    const KIND: &'static str = "dev.koharu.project.edition";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.locale.validate()
+    }
+}
+
+/// The order in which readers move through pages and across a spread.
+#[revisioned(revision = 1)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Type)]
+pub enum ReadingDirection {
+    /// Pages and panels read from right to left, as in Japanese manga. A
+    /// project without a `Reading` component reads this way.
+    #[default]
+    RightToLeft,
+    LeftToRight,
+    /// One continuous column read from top to bottom, as in webtoons.
+    VerticalScroll,
+}
+
+/// How readers move through the project's pages.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Type)]
+pub struct Reading {
+    pub direction: ReadingDirection,
+}
+
+impl Component for Reading {
+    const KIND: &'static str = "dev.koharu.project.reading";
+}
+
 #[revisioned(revision = 1)]
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
 pub struct Page {
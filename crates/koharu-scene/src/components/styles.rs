@@ -0,0 +1,54 @@
+use revision::revisioned;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Error, Result,
+    component::{Component, ValidationContext},
+};
+
+use super::{Origin, TypographyOverride};
+
+/// A named text style shared by the project, such as "narration" or "shout".
+/// Text layers refer to it with a `styled-by` relation and keep their own
+/// typography on top, and a style may inherit the fields it leaves unset from
+/// another through `based-on`.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct TextStyle {
+    pub origin: Origin,
+    pub name: String,
+    pub typography: TypographyOverride,
+}
+
+impl TextStyle {
+    #[must_use]
+    pub fn new(name: impl Into<String>, typography: TypographyOverride) -> Self {
+        Self {
+            origin: Origin::User,
+            name: name.into(),
+            typography,
+        }
+    }
+}
+
+impl Component for TextStyle {
+    const KIND: &'static str = "dev.koharu.text.style";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        self.origin.validate()?;
+        if self.name.trim().is_empty() || self.name.len() > 256 || self.name.contains('\0') {
+            return Err(Error::invalid("text style name is invalid"));
+        }
+        self.typography.validate()
+    }
+
+    fn origin(&self) -> Option<&Origin> {
+        Some(&self.origin)
+    }
+
+    fn set_origin(&mut self, origin: Origin) -> bool {
+        self.origin = origin;
+        true
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, fmt, str::FromStr};
 
 use revision::{DeserializeRevisioned, Revisioned, SerializeRevisioned, revisioned};
 use serde::{Deserialize, Serialize};
@@ -10,7 +10,7 @@ use crate::{
     id::validate_namespaced,
 };
 
-use super::{Authored, Origin};
+use super::{Authored, FontStyle, Generation, Origin};
 
 #[revisioned(revision = 1)]
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, Type)]
@@ -135,6 +135,8 @@ impl Component for SourceText {
     }
 }
 
+/// One translation of a text content: its text and the language of the
+/// edition that shows it, or `None` for the fallback.
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
 pub struct Translation {
     pub text: Authored<String>,
@@ -168,24 +170,255 @@ impl DeserializeRevisioned for Translation {
     }
 }
 
-impl Component for Translation {
+/// Every translation of one text content, keyed by target language so each
+/// edition has its own. The translation without a language is the fallback
+/// for editions that have none. Revision 1 stored a single `Translation`;
+/// `translation_store` upgrades it to the entry of its language.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Translations {
+    pub(crate) languages: BTreeMap<Option<LanguageTag>, StoredTranslation>,
+}
+
+impl Revisioned for Translations {
+    fn revision() -> u16 {
+        2
+    }
+}
+
+impl SerializeRevisioned for Translations {
+    fn serialize_revisioned<W: std::io::Write>(
+        &self,
+        writer: &mut W,
+    ) -> std::result::Result<(), revision::Error> {
+        self.languages.len().serialize_revisioned(writer)?;
+        for (language, translation) in &self.languages {
+            language.serialize_revisioned(writer)?;
+            translation.text.serialize_revisioned(writer)?;
+            translation.spans.serialize_revisioned(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeserializeRevisioned for Translations {
+    fn deserialize_revisioned<R: std::io::Read>(
+        reader: &mut R,
+    ) -> std::result::Result<Self, revision::Error> {
+        let len = usize::deserialize_revisioned(reader)?;
+        let mut languages = BTreeMap::new();
+        for _ in 0..len {
+            let language = Option::deserialize_revisioned(reader)?;
+            let translation = StoredTranslation {
+                text: Authored::deserialize_revisioned(reader)?,
+                spans: Vec::deserialize_revisioned(reader)?,
+            };
+            if languages.insert(language, translation).is_some() {
+                return Err(revision::Error::Conversion(
+                    "duplicate translation language".to_owned(),
+                ));
+            }
+        }
+        Ok(Self { languages })
+    }
+}
+
+/// One translation together with the spans that style its text.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StoredTranslation {
+    pub(crate) text: Authored<String>,
+    pub(crate) spans: Vec<TextSpan>,
+}
+
+impl Component for Translations {
     const KIND: &'static str = "dev.koharu.text.translation";
 
     fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
-        validate_authored_text(&self.text)?;
-        if let Some(language) = &self.language {
-            language.validate()?;
+        if self.languages.len() > 1025 {
+            return Err(Error::invalid("text content has too many translations"));
+        }
+        for (language, translation) in &self.languages {
+            if let Some(language) = language {
+                language.validate()?;
+            }
+            validate_authored_text(&translation.text)?;
+            if translation.spans.is_empty() {
+                continue;
+            }
+            validate_spans(&translation.spans)?;
+            if !translation
+                .spans
+                .iter()
+                .all(|span| span.fits(&translation.text.value))
+            {
+                return Err(Error::invalid(
+                    "text span does not select whole characters of the translation",
+                ));
+            }
         }
         Ok(())
     }
+}
 
-    fn origin(&self) -> Option<&Origin> {
-        Some(&self.text.origin)
+/// Upgrades a revision 1 translation, a single `Translation`, into the store
+/// of every language.
+pub(crate) fn translation_store(payload: &[u8]) -> Result<Vec<u8>> {
+    let translation: Translation = revision::from_slice(payload)?;
+    let stored = StoredTranslation {
+        text: translation.text,
+        spans: Vec::new(),
+    };
+    Ok(revision::to_vec(&Translations {
+        languages: BTreeMap::from([(translation.language, stored)]),
+    })?)
+}
+
+/// Styling of one range of a translation. Fields that are not set keep the
+/// text layer's resolved typography.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct SpanStyle {
+    pub font: Option<String>,
+    pub font_weight: Option<u16>,
+    pub font_style: Option<FontStyle>,
+    /// Multiplies the layer's font size.
+    pub size_scale: Option<f32>,
+    pub color: Option<[u8; 4]>,
+}
+
+impl SpanStyle {
+    fn validate(&self) -> Result<()> {
+        if self
+            .font
+            .as_ref()
+            .is_some_and(|font| font.trim().is_empty() || font.len() > 4096 || font.contains('\0'))
+            || self
+                .font_weight
+                .is_some_and(|weight| !(1..=1000).contains(&weight))
+            || self
+                .size_scale
+                .is_some_and(|scale| !scale.is_finite() || !(0.1..=10.0).contains(&scale))
+        {
+            return Err(Error::invalid("text span style is invalid"));
+        }
+        Ok(())
     }
+}
 
-    fn set_origin(&mut self, origin: Origin) -> bool {
-        self.text.origin = origin;
-        true
+/// A styled range of a translation, in UTF-8 byte offsets of its text.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct TextSpan {
+    pub start: u32,
+    pub end: u32,
+    pub style: SpanStyle,
+}
+
+impl TextSpan {
+    /// Reports whether the span selects whole characters of `text`.
+    #[must_use]
+    pub fn fits(&self, text: &str) -> bool {
+        let (start, end) = (self.start as usize, self.end as usize);
+        start < end
+            && end <= text.len()
+            && text.is_char_boundary(start)
+            && text.is_char_boundary(end)
+    }
+}
+
+/// Checks that spans are ordered, non-empty, and free of overlaps.
+fn validate_spans(spans: &[TextSpan]) -> Result<()> {
+    if spans.is_empty() || spans.len() > 4096 {
+        return Err(Error::invalid("text span count is invalid"));
+    }
+    let mut previous_end = 0;
+    for span in spans {
+        if span.start >= span.end || span.start < previous_end {
+            return Err(Error::invalid(
+                "text spans must be non-empty, ordered, and free of overlaps",
+            ));
+        }
+        span.style.validate()?;
+        previous_end = span.end;
+    }
+    Ok(())
+}
+
+#[revisioned(revision = 1)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewState {
+    #[default]
+    Unreviewed,
+    NeedsWork,
+    Approved,
+}
+
+/// One proposed translation, such as the output of one provider, and the
+/// editors' verdict on it.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct TranslationCandidate {
+    pub text: String,
+    pub generation: Generation,
+    pub review: ReviewState,
+    pub notes: String,
+}
+
+/// Candidate translations of one text content keyed by language. At most one
+/// candidate per language is approved, and producers never replace it.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct TranslationCandidates {
+    pub languages: BTreeMap<LanguageTag, Vec<TranslationCandidate>>,
+}
+
+impl TranslationCandidates {
+    #[must_use]
+    pub fn approved(&self, language: &LanguageTag) -> Option<&TranslationCandidate> {
+        self.languages
+            .get(language)?
+            .iter()
+            .find(|candidate| candidate.review == ReviewState::Approved)
+    }
+}
+
+impl Component for TranslationCandidates {
+    const KIND: &'static str = "dev.koharu.text.candidates";
+
+    fn validate(&self, _context: &ValidationContext<'_>) -> Result<()> {
+        if self.languages.len() > 1024 {
+            return Err(Error::invalid(
+                "text content has too many candidate languages",
+            ));
+        }
+        for (language, candidates) in &self.languages {
+            language.validate()?;
+            if candidates.is_empty() || candidates.len() > 256 {
+                return Err(Error::invalid(format!(
+                    "candidate count for {language} is invalid"
+                )));
+            }
+            let approved = candidates
+                .iter()
+                .filter(|candidate| candidate.review == ReviewState::Approved)
+                .count();
+            if approved > 1 {
+                return Err(Error::invalid(format!(
+                    "multiple {language} candidates are approved"
+                )));
+            }
+            for candidate in candidates {
+                candidate.generation.validate()?;
+                if candidate.text.len() > 16 * 1024 * 1024
+                    || candidate.text.contains('\0')
+                    || candidate.notes.len() > 64 * 1024
+                    || candidate.notes.contains('\0')
+                {
+                    return Err(Error::invalid("translation candidate is invalid"));
+                }
+            }
+        }
+        Ok(())
     }
 }
 
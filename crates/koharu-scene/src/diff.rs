@@ -0,0 +1,556 @@
+//! Semantic differences between two snapshots of one project.
+//!
+//! A [`Change`](crate::Change) names the records that operations touched. A
+//! [`DiffReport`] instead describes what an editor sees changed between any two
+//! revisions, such as two deliveries of a translation: pages, text down to the
+//! character, moved geometry, and repainted layers.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    AssetRole, EntityId, Error, Geometry, LanguageTag, Point, RasterLayer, RasterLayerKind, Result,
+    Revision, Snapshot, SourceText, TextContent, components::Assets,
+};
+
+/// Character diffs whose changed middles would need more comparisons than
+/// this are reported as one deletion and one insertion.
+const MAX_DIFF_CELLS: usize = 1 << 22;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct DiffReport {
+    pub from: Revision,
+    pub to: Revision,
+    pub pages: Vec<PageDiff>,
+    pub texts: Vec<TextDiff>,
+    pub geometry: Vec<GeometryDiff>,
+    pub layers: Vec<LayerDiff>,
+}
+
+/// The page a difference belongs to, with its label for readers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct DiffPage {
+    pub id: EntityId,
+    pub label: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct PageDiff {
+    pub page: DiffPage,
+    pub change: PageChange,
+}
+
+/// Zero-based page positions. A page only counts as moved when its order
+/// relative to the other kept pages changed, not when an insertion or
+/// removal shifted it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum PageChange {
+    Added { position: u32 },
+    Removed { position: u32 },
+    Moved { from: u32, to: u32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct TextDiff {
+    pub page: DiffPage,
+    pub content: EntityId,
+    pub field: TextField,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    /// Character-level edits that turn `before` into `after`.
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "field", content = "language", rename_all = "snake_case")]
+pub enum TextField {
+    Source,
+    /// A translation, or the fallback translation without a language.
+    Translation(Option<LanguageTag>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "edit", content = "text", rename_all = "snake_case")]
+pub enum TextEdit {
+    Equal(String),
+    Insert(String),
+    Delete(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct GeometryDiff {
+    pub page: DiffPage,
+    pub entity: EntityId,
+    pub before: Vec<Point>,
+    pub after: Vec<Point>,
+    /// How far the center of the bounding box moved.
+    pub offset: Point,
+}
+
+/// An entity whose asset bytes were replaced, added, or removed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+pub struct LayerDiff {
+    pub page: DiffPage,
+    pub entity: EntityId,
+    /// The raster layer kind, or `None` for other asset owners such as pages.
+    pub kind: Option<RasterLayerKind>,
+    pub roles: Vec<AssetRole>,
+}
+
+impl DiffReport {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+            && self.texts.is_empty()
+            && self.geometry.is_empty()
+            && self.layers.is_empty()
+    }
+}
+
+impl Snapshot {
+    /// Reports what changed between `base` and this snapshot of the same
+    /// project, such as two recorded revisions or two imported bundles.
+    pub fn diff_since(&self, base: &Self) -> Result<DiffReport> {
+        if base.state.document != self.state.document {
+            return Err(Error::invalid("snapshot belongs to another project"));
+        }
+        let sides = Sides {
+            before: base,
+            after: self,
+        };
+        Ok(DiffReport {
+            from: base.revision(),
+            to: self.revision(),
+            pages: sides.pages()?,
+            texts: sides.texts()?,
+            geometry: sides.geometry()?,
+            layers: sides.layers()?,
+        })
+    }
+}
+
+struct Sides<'a> {
+    before: &'a Snapshot,
+    after: &'a Snapshot,
+}
+
+impl Sides<'_> {
+    /// The page that holds `entity`, preferring its place after the change.
+    fn page_of(&self, entity: EntityId) -> Result<DiffPage> {
+        let snapshot = if self.after.state.contains_entity(entity) {
+            self.after
+        } else {
+            self.before
+        };
+        page_in(snapshot, snapshot.state.page_for(entity)?)
+    }
+
+    fn pages(&self) -> Result<Vec<PageDiff>> {
+        let before = &self.before.state.page_order;
+        let after = &self.after.state.page_order;
+        let before_positions = positions(before.iter().copied());
+        let after_positions = positions(after.iter().copied());
+        let kept = after
+            .iter()
+            .filter_map(|page| before_positions.get(page).copied())
+            .collect::<Vec<_>>();
+        let in_order = increasing_subsequence(&kept)
+            .into_iter()
+            .map(|index| before[kept[index] as usize])
+            .collect::<BTreeSet<_>>();
+
+        let mut pages = Vec::new();
+        for (position, page) in after.iter().enumerate() {
+            let position = position as u32;
+            let change = match before_positions.get(page) {
+                None => PageChange::Added { position },
+                Some(_) if in_order.contains(page) => continue,
+                Some(&from) => PageChange::Moved { from, to: position },
+            };
+            pages.push(PageDiff {
+                page: page_in(self.after, *page)?,
+                change,
+            });
+        }
+        for (position, page) in before.iter().enumerate() {
+            if !after_positions.contains_key(page) {
+                pages.push(PageDiff {
+                    page: page_in(self.before, *page)?,
+                    change: PageChange::Removed {
+                        position: position as u32,
+                    },
+                });
+            }
+        }
+        Ok(pages)
+    }
+
+    fn texts(&self) -> Result<Vec<TextDiff>> {
+        let mut texts = Vec::new();
+        for content in self.union::<TextContent>()? {
+            let source = |snapshot: &Snapshot| -> Result<Option<String>> {
+                if !snapshot.state.contains_entity(content) {
+                    return Ok(None);
+                }
+                Ok(snapshot
+                    .component::<SourceText>(content)?
+                    .map(|source| source.text.value))
+            };
+            let translations = |snapshot: &Snapshot| -> Result<BTreeMap<_, _>> {
+                if !snapshot.state.contains_entity(content) {
+                    return Ok(BTreeMap::new());
+                }
+                Ok(snapshot
+                    .translations(content)?
+                    .into_iter()
+                    .map(|translation| (translation.language, translation.text.value))
+                    .collect())
+            };
+            let mut fields = vec![(TextField::Source, source(self.before)?, source(self.after)?)];
+            let mut before = translations(self.before)?;
+            let mut after = translations(self.after)?;
+            let languages = before.keys().chain(after.keys()).cloned();
+            for language in languages.collect::<BTreeSet<_>>() {
+                fields.push((
+                    TextField::Translation(language.clone()),
+                    before.remove(&language),
+                    after.remove(&language),
+                ));
+            }
+            for (field, before, after) in fields {
+                if before == after {
+                    continue;
+                }
+                texts.push(TextDiff {
+                    page: self.page_of(content)?,
+                    content,
+                    field,
+                    edits: text_edits(
+                        before.as_deref().unwrap_or_default(),
+                        after.as_deref().unwrap_or_default(),
+                    ),
+                    before,
+                    after,
+                });
+            }
+        }
+        Ok(texts)
+    }
+
+    fn geometry(&self) -> Result<Vec<GeometryDiff>> {
+        let mut moved = Vec::new();
+        for entity in self.after.entities_with::<Geometry>()? {
+            let entity = entity.id();
+            if !self.before.state.contains_entity(entity) {
+                continue;
+            }
+            let (Some(before), Some(after)) = (
+                self.before.component::<Geometry>(entity)?,
+                self.after.component::<Geometry>(entity)?,
+            ) else {
+                continue;
+            };
+            if before.points == after.points {
+                continue;
+            }
+            let (before_center, after_center) = (center(&before.points), center(&after.points));
+            moved.push(GeometryDiff {
+                page: self.page_of(entity)?,
+                entity,
+                offset: Point {
+                    x: after_center.x - before_center.x,
+                    y: after_center.y - before_center.y,
+                },
+                before: before.points,
+                after: after.points,
+            });
+        }
+        Ok(moved)
+    }
+
+    fn layers(&self) -> Result<Vec<LayerDiff>> {
+        let mut layers = Vec::new();
+        for entity in self.after.entities_with::<Assets>()? {
+            let entity = entity.id();
+            if !self.before.state.contains_entity(entity) {
+                continue;
+            }
+            let blobs = |snapshot: &Snapshot| -> Result<BTreeMap<AssetRole, _>> {
+                Ok(snapshot
+                    .component::<Assets>(entity)?
+                    .map(|assets| assets.values)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(role, asset)| (role, asset.blob))
+                    .collect())
+            };
+            let (before, after) = (blobs(self.before)?, blobs(self.after)?);
+            let roles = before
+                .keys()
+                .chain(after.keys())
+                .filter(|role| before.get(*role) != after.get(*role))
+                .cloned()
+                .collect::<BTreeSet<_>>();
+            if roles.is_empty() {
+                continue;
+            }
+            layers.push(LayerDiff {
+                page: self.page_of(entity)?,
+                entity,
+                kind: self
+                    .after
+                    .component::<RasterLayer>(entity)?
+                    .map(|layer| layer.kind),
+                roles: roles.into_iter().collect(),
+            });
+        }
+        Ok(layers)
+    }
+
+    /// Entities with a `T` on either side: those after the change in project
+    /// order, then those only before it.
+    fn union<T: crate::Component>(&self) -> Result<Vec<EntityId>> {
+        let mut entities = self
+            .after
+            .entities_with::<T>()?
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>();
+        let seen = entities.iter().copied().collect::<BTreeSet<_>>();
+        entities.extend(
+            self.before
+                .entities_with::<T>()?
+                .map(|entity| entity.id())
+                .filter(|entity| !seen.contains(entity)),
+        );
+        Ok(entities)
+    }
+}
+
+fn page_in(snapshot: &Snapshot, page: EntityId) -> Result<DiffPage> {
+    Ok(DiffPage {
+        id: page,
+        label: snapshot.page(page)?.page()?.label,
+    })
+}
+
+fn positions(pages: impl Iterator<Item = EntityId>) -> BTreeMap<EntityId, u32> {
+    pages
+        .enumerate()
+        .map(|(position, page)| (page, position as u32))
+        .collect()
+}
+
+/// Indices of one longest strictly increasing subsequence of `values`.
+fn increasing_subsequence(values: &[u32]) -> Vec<usize> {
+    // `tails[length]` is the index ending the best subsequence of `length + 1`.
+    let mut tails = Vec::<usize>::new();
+    let mut previous = vec![None; values.len()];
+    for (index, value) in values.iter().enumerate() {
+        let length = tails.partition_point(|tail| values[*tail] < *value);
+        previous[index] = length.checked_sub(1).map(|length| tails[length]);
+        if length == tails.len() {
+            tails.push(index);
+        } else {
+            tails[length] = index;
+        }
+    }
+    let mut indices = Vec::with_capacity(tails.len());
+    let mut cursor = tails.last().copied();
+    while let Some(index) = cursor {
+        indices.push(index);
+        cursor = previous[index];
+    }
+    indices.reverse();
+    indices
+}
+
+fn center(points: &[Point]) -> Point {
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for point in points {
+        min_x = min_x.min(point.x);
+        min_y = min_y.min(point.y);
+        max_x = max_x.max(point.x);
+        max_y = max_y.max(point.y);
+    }
+    if points.is_empty() {
+        return Point::default();
+    }
+    Point {
+        x: (min_x + max_x) / 2.0,
+        y: (min_y + max_y) / 2.0,
+    }
+}
+
+/// Diffs two texts by characters through their longest common subsequence.
+fn text_edits(before: &str, after: &str) -> Vec<TextEdit> {
+    let before = before.chars().collect::<Vec<_>>();
+    let after = after.chars().collect::<Vec<_>>();
+    let prefix = before
+        .iter()
+        .zip(&after)
+        .take_while(|(left, right)| left == right)
+        .count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(left, right)| left == right)
+        .count();
+    let old = &before[prefix..before.len() - suffix];
+    let new = &after[prefix..after.len() - suffix];
+
+    let mut edits = Vec::new();
+    push_edit(&mut edits, TextEdit::Equal, &before[..prefix]);
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        push_edit(&mut edits, TextEdit::Delete, old);
+        push_edit(&mut edits, TextEdit::Insert, new);
+    } else {
+        // `common[i * width + j]` is the common subsequence length of
+        // `old[i..]` and `new[j..]`.
+        let width = new.len() + 1;
+        let mut common = vec![0_u32; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                common[i * width + j] = if old[i] == new[j] {
+                    common[(i + 1) * width + j + 1] + 1
+                } else {
+                    common[(i + 1) * width + j].max(common[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() && j < new.len() {
+            if old[i] == new[j] {
+                push_edit(&mut edits, TextEdit::Equal, &old[i..=i]);
+                i += 1;
+                j += 1;
+            } else if common[(i + 1) * width + j] >= common[i * width + j + 1] {
+                push_edit(&mut edits, TextEdit::Delete, &old[i..=i]);
+                i += 1;
+            } else {
+                push_edit(&mut edits, TextEdit::Insert, &new[j..=j]);
+                j += 1;
+            }
+        }
+        push_edit(&mut edits, TextEdit::Delete, &old[i..]);
+        push_edit(&mut edits, TextEdit::Insert, &new[j..]);
+    }
+    push_edit(
+        &mut edits,
+        TextEdit::Equal,
+        &before[before.len() - suffix..],
+    );
+    edits
+}
+
+/// Appends characters, extending the last edit when it is of the same kind.
+fn push_edit(edits: &mut Vec<TextEdit>, edit: fn(String) -> TextEdit, chars: &[char]) {
+    if chars.is_empty() {
+        return;
+    }
+    let text = chars.iter().collect::<String>();
+    match (edits.last_mut(), edit(String::new())) {
+        (Some(TextEdit::Equal(last)), TextEdit::Equal(_))
+        | (Some(TextEdit::Insert(last)), TextEdit::Insert(_))
+        | (Some(TextEdit::Delete(last)), TextEdit::Delete(_)) => last.push_str(&text),
+        _ => edits.push(edit(text)),
+    }
+}
+
+/// A plain-text report: removals as `[-text-]` and insertions as `{+text+}`.
+impl fmt::Display for DiffReport {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            formatter,
+            "Changes from revision {} to {}",
+            self.from, self.to
+        )?;
+        if self.is_empty() {
+            return writeln!(formatter, "No changes.");
+        }
+        if !self.pages.is_empty() {
+            writeln!(formatter, "\nPages")?;
+            for page in &self.pages {
+                let label = &page.page.label;
+                match page.change {
+                    PageChange::Added { position } => {
+                        writeln!(formatter, "  added {label:?} at {}", position + 1)?;
+                    }
+                    PageChange::Removed { position } => {
+                        writeln!(formatter, "  removed {label:?} from {}", position + 1)?;
+                    }
+                    PageChange::Moved { from, to } => {
+                        writeln!(
+                            formatter,
+                            "  moved {label:?} from {} to {}",
+                            from + 1,
+                            to + 1
+                        )?;
+                    }
+                }
+            }
+        }
+        if !self.texts.is_empty() {
+            writeln!(formatter, "\nText")?;
+            for text in &self.texts {
+                let field = match &text.field {
+                    TextField::Source => "source".to_owned(),
+                    TextField::Translation(Some(language)) => format!("translation ({language})"),
+                    TextField::Translation(None) => "translation".to_owned(),
+                };
+                write!(
+                    formatter,
+                    "  {:?} {} {field}: ",
+                    text.page.label, text.content
+                )?;
+                for edit in &text.edits {
+                    match edit {
+                        TextEdit::Equal(value) => write!(formatter, "{value}")?,
+                        TextEdit::Insert(value) => write!(formatter, "{{+{value}+}}")?,
+                        TextEdit::Delete(value) => write!(formatter, "[-{value}-]")?,
+                    }
+                }
+                writeln!(formatter)?;
+            }
+        }
+        if !self.geometry.is_empty() {
+            writeln!(formatter, "\nGeometry")?;
+            for moved in &self.geometry {
+                writeln!(
+                    formatter,
+                    "  {:?} {} moved by ({:.1}, {:.1})",
+                    moved.page.label, moved.entity, moved.offset.x, moved.offset.y
+                )?;
+            }
+        }
+        if !self.layers.is_empty() {
+            writeln!(formatter, "\nLayers")?;
+            for layer in &self.layers {
+                let kind = match layer.kind {
+                    Some(RasterLayerKind::Cleanup) => "cleanup layer",
+                    Some(RasterLayerKind::Paint) => "paint layer",
+                    None => "assets",
+                };
+                let roles = layer
+                    .roles
+                    .iter()
+                    .map(AssetRole::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                writeln!(
+                    formatter,
+                    "  {:?} {} {kind} repainted: {roles}",
+                    layer.page.label, layer.entity
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
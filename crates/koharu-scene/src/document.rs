@@ -1,9 +1,14 @@
 //! Resolved document views and intent-level edits over the generic scene kernel.
 
 use crate::{
-    At, DetectionAnalysis, Edit, EntityId, Geometry, Group, OcrAnalysis, Origin, PageRef, Presents,
-    Region, RegionSpec, Result, Snapshot, SourceText, TextContent, TextGroup, TextLayout, TextRole,
-    Translation, Typography, Visibility,
+    Annotates, Annotation, At, BasedOn, Chapter, Character, DetectionAnalysis, Edit, Edition,
+    EntityId, Geometry, GlossaryTerm, Group, InChapter, LanguageTag, OcrAnalysis, Origin, PageRef,
+    Presents, Reading, ReadingDirection, Region, RegionSpec, Result, ShapeLayer, Snapshot,
+    SourceText, SpokenBy, SpreadWith, StyledBy, TextContent, TextGroup, TextLayout, TextRole,
+    TextSpan, TextStyle, Translation, TranslationCandidates, Typography, TypographyOverride,
+    TypographyOverrides, Visibility, Volume,
+    component::{Component, key},
+    components::Translations,
 };
 
 #[derive(Copy, Clone)]
@@ -70,6 +75,45 @@ impl<'a> TextLayerRef<'a> {
         self.snapshot.component(self.id)
     }
 
+    /// Returns the text style the layer uses, if any.
+    pub fn style(self) -> Result<Option<TextStyleRef<'a>>> {
+        self.snapshot
+            .relation_from::<StyledBy>(self.id)?
+            .map(|relation| self.snapshot.text_style(relation.value().target))
+            .transpose()
+    }
+
+    /// Returns the layer's typography, with its text style's and then its
+    /// speaker's typography filling the fields it leaves unset and the
+    /// override for `language` applied.
+    pub fn typography_for(self, language: Option<&LanguageTag>) -> Result<Option<Typography>> {
+        let mut typography = self.typography()?;
+        if let Some(style) = self.style()? {
+            typography = Some(style.typography()?.fill(typography));
+        }
+        let content = self
+            .snapshot
+            .relation_from::<Presents>(self.id)?
+            .map(|relation| self.snapshot.text_content(relation.value().target))
+            .transpose()?;
+        if let Some(speaker) = content.map(TextContentRef::speaker).transpose()?.flatten()
+            && let Some(defaults) = speaker.character()?.typography
+        {
+            typography = Some(defaults.fill(typography));
+        }
+        let overrides = match language {
+            Some(language) => self
+                .snapshot
+                .component::<TypographyOverrides>(self.id)?
+                .and_then(|mut overrides| overrides.languages.remove(language)),
+            None => None,
+        };
+        Ok(match overrides {
+            Some(value) => Some(value.apply(typography)),
+            None => typography,
+        })
+    }
+
     pub fn visibility(self) -> Result<Option<Visibility>> {
         self.snapshot.component(self.id)
     }
@@ -132,7 +176,36 @@ impl<'a> TextContentRef<'a> {
         self.snapshot.component(self.id)
     }
 
+    /// Returns the translation the current edition shows.
     pub fn translation(self) -> Result<Option<Translation>> {
+        self.translation_for(self.snapshot.edition_locale()?.as_ref())
+    }
+
+    /// Returns the translation an edition in `language` shows: its own, or the
+    /// fallback translation without a language. Without a language this is the
+    /// fallback, or else the first translation by language.
+    pub fn translation_for(self, language: Option<&LanguageTag>) -> Result<Option<Translation>> {
+        if let Some(language) = language {
+            if let Some(translation) = self.snapshot.translation(self.id, Some(language))? {
+                return Ok(Some(translation));
+            }
+            return self.snapshot.translation(self.id, None);
+        }
+        Ok(self.translations()?.into_iter().next())
+    }
+
+    pub fn translations(self) -> Result<Vec<Translation>> {
+        self.snapshot.translations(self.id)
+    }
+
+    /// Returns the spans that style `translation`, one of this content's
+    /// translations.
+    pub fn spans(self, translation: &Translation) -> Result<Vec<TextSpan>> {
+        self.snapshot
+            .translation_spans(self.id, translation.language.as_ref())
+    }
+
+    pub fn candidates(self) -> Result<Option<TranslationCandidates>> {
         self.snapshot.component(self.id)
     }
 
@@ -146,6 +219,210 @@ impl<'a> TextContentRef<'a> {
             .map(|relation| self.snapshot.analysis_region(relation.value().target))
             .transpose()
     }
+
+    /// Returns the character who speaks this text, if one is named.
+    pub fn speaker(self) -> Result<Option<CharacterRef<'a>>> {
+        self.snapshot
+            .relation_from::<SpokenBy>(self.id)?
+            .map(|relation| self.snapshot.character(relation.value().target))
+            .transpose()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct TextStyleRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl<'a> TextStyleRef<'a> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn style(self) -> Result<TextStyle> {
+        required(self.snapshot.component(self.id)?, self.id, "text style")
+    }
+
+    /// Returns the style this one inherits unset fields from, if any.
+    pub fn base(self) -> Result<Option<TextStyleRef<'a>>> {
+        self.snapshot
+            .relation_from::<BasedOn>(self.id)?
+            .map(|relation| self.snapshot.text_style(relation.value().target))
+            .transpose()
+    }
+
+    /// Returns the style's typography with the fields it leaves unset taken
+    /// from the styles it is based on, nearest first.
+    pub fn typography(self) -> Result<TypographyOverride> {
+        let mut typography = self.style()?.typography;
+        let mut base = self.base()?;
+        while let Some(style) = base {
+            typography = typography.inherit(&style.style()?.typography);
+            base = style.base()?;
+        }
+        Ok(typography)
+    }
+
+    /// Lists the text layers that use this style, in no particular order.
+    pub fn layers(self) -> Vec<TextLayerRef<'a>> {
+        self.snapshot
+            .relations_to_as::<StyledBy>(self.id)
+            .map(|relation| TextLayerRef {
+                snapshot: self.snapshot,
+                id: relation.value().source,
+            })
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct VolumeRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl<'a> VolumeRef<'a> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn volume(self) -> Result<Volume> {
+        required(self.snapshot.component(self.id)?, self.id, "volume")
+    }
+
+    /// Lists the volume's chapters in reading order.
+    pub fn chapters(self) -> Result<Vec<ChapterRef<'a>>> {
+        Ok(self
+            .snapshot
+            .children(self.id)?
+            .map(|id| ChapterRef {
+                snapshot: self.snapshot,
+                id,
+            })
+            .collect())
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct ChapterRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl<'a> ChapterRef<'a> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn chapter(self) -> Result<Chapter> {
+        required(self.snapshot.component(self.id)?, self.id, "chapter")
+    }
+
+    /// Returns the volume that contains the chapter, if any.
+    pub fn volume(self) -> Result<Option<VolumeRef<'a>>> {
+        Ok(self
+            .snapshot
+            .parent(self.id)?
+            .filter(|parent| *parent != EntityId::PROJECT)
+            .map(|id| VolumeRef {
+                snapshot: self.snapshot,
+                id,
+            }))
+    }
+
+    /// Lists the chapter's pages in page order.
+    pub fn pages(self) -> Vec<PageRef<'a>> {
+        let members = self
+            .snapshot
+            .relations_to_as::<InChapter>(self.id)
+            .map(|relation| relation.value().source)
+            .collect::<std::collections::HashSet<_>>();
+        self.snapshot
+            .pages()
+            .filter(|page| members.contains(&page.id()))
+            .collect()
+    }
+}
+
+impl<'a> PageRef<'a> {
+    /// Returns the chapter the page belongs to, if any.
+    pub fn chapter(self) -> Result<Option<ChapterRef<'a>>> {
+        self.snapshot
+            .relation_from::<InChapter>(self.id)?
+            .map(|relation| self.snapshot.chapter(relation.value().target))
+            .transpose()
+    }
+
+    /// Lists the pages of the page's spread in page order: the page itself,
+    /// and the page facing it if the two are paired.
+    pub fn spread(self) -> Vec<PageRef<'a>> {
+        let partner = self
+            .snapshot
+            .relations_from_as::<SpreadWith>(self.id)
+            .map(|relation| relation.value().target)
+            .chain(
+                self.snapshot
+                    .relations_to_as::<SpreadWith>(self.id)
+                    .map(|relation| relation.value().source),
+            )
+            .next();
+        let Some(partner) = partner else {
+            return vec![self];
+        };
+        self.snapshot
+            .pages()
+            .filter(|page| page.id() == self.id || page.id() == partner)
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct CharacterRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl<'a> CharacterRef<'a> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn character(self) -> Result<Character> {
+        required(self.snapshot.component(self.id)?, self.id, "character")
+    }
+
+    /// Lists the text contents this character speaks, in no particular order.
+    pub fn lines(self) -> Vec<TextContentRef<'a>> {
+        self.snapshot
+            .relations_to_as::<SpokenBy>(self.id)
+            .map(|relation| TextContentRef {
+                snapshot: self.snapshot,
+                id: relation.value().source,
+            })
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone)]
+pub struct GlossaryTermRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl GlossaryTermRef<'_> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn term(self) -> Result<GlossaryTerm> {
+        required(self.snapshot.component(self.id)?, self.id, "glossary term")
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -177,6 +454,32 @@ impl AnalysisRegionRef<'_> {
     }
 }
 
+#[derive(Copy, Clone)]
+pub struct AnnotationRef<'a> {
+    snapshot: &'a Snapshot,
+    id: EntityId,
+}
+
+impl AnnotationRef<'_> {
+    #[must_use]
+    pub const fn id(self) -> EntityId {
+        self.id
+    }
+
+    pub fn annotation(self) -> Result<Annotation> {
+        required(self.snapshot.component(self.id)?, self.id, "annotation")
+    }
+
+    /// Returns the page, region, or text layer the annotation is anchored to.
+    /// An annotation whose anchor was removed stays on its page without one.
+    pub fn anchor(self) -> Result<Option<EntityId>> {
+        Ok(self
+            .snapshot
+            .relation_from::<Annotates>(self.id)?
+            .map(|relation| relation.value().target))
+    }
+}
+
 impl Snapshot {
     pub fn group(&self, id: EntityId) -> Result<GroupRef<'_>> {
         required(self.component::<Group>(id)?, id, "group")?;
@@ -198,6 +501,173 @@ impl Snapshot {
         Ok(AnalysisRegionRef { snapshot: self, id })
     }
 
+    pub fn annotation(&self, id: EntityId) -> Result<AnnotationRef<'_>> {
+        required(self.component::<Annotation>(id)?, id, "annotation")?;
+        Ok(AnnotationRef { snapshot: self, id })
+    }
+
+    /// Lists the annotations anchored to a page, region, or text layer.
+    pub fn annotations_on(&self, anchor: EntityId) -> Vec<AnnotationRef<'_>> {
+        self.relations_to_as::<Annotates>(anchor)
+            .map(|relation| AnnotationRef {
+                snapshot: self,
+                id: relation.value().source,
+            })
+            .collect()
+    }
+
+    pub fn annotations(&self) -> Result<impl ExactSizeIterator<Item = AnnotationRef<'_>>> {
+        Ok(self
+            .entities_with::<Annotation>()?
+            .map(|entity| AnnotationRef {
+                snapshot: self,
+                id: entity.id(),
+            }))
+    }
+
+    pub fn character(&self, id: EntityId) -> Result<CharacterRef<'_>> {
+        required(self.component::<Character>(id)?, id, "character")?;
+        Ok(CharacterRef { snapshot: self, id })
+    }
+
+    /// Lists the project's characters in the order they were added.
+    pub fn characters(&self) -> Result<Vec<CharacterRef<'_>>> {
+        Ok(self
+            .project_entities_with::<Character>()?
+            .into_iter()
+            .map(|id| CharacterRef { snapshot: self, id })
+            .collect())
+    }
+
+    pub fn glossary_term(&self, id: EntityId) -> Result<GlossaryTermRef<'_>> {
+        required(self.component::<GlossaryTerm>(id)?, id, "glossary term")?;
+        Ok(GlossaryTermRef { snapshot: self, id })
+    }
+
+    /// Lists the project's glossary in the order terms were added.
+    pub fn glossary(&self) -> Result<Vec<GlossaryTermRef<'_>>> {
+        Ok(self
+            .project_entities_with::<GlossaryTerm>()?
+            .into_iter()
+            .map(|id| GlossaryTermRef { snapshot: self, id })
+            .collect())
+    }
+
+    pub fn volume(&self, id: EntityId) -> Result<VolumeRef<'_>> {
+        required(self.component::<Volume>(id)?, id, "volume")?;
+        Ok(VolumeRef { snapshot: self, id })
+    }
+
+    /// Lists the project's volumes in reading order.
+    pub fn volumes(&self) -> Result<Vec<VolumeRef<'_>>> {
+        Ok(self
+            .project_entities_with::<Volume>()?
+            .into_iter()
+            .map(|id| VolumeRef { snapshot: self, id })
+            .collect())
+    }
+
+    pub fn chapter(&self, id: EntityId) -> Result<ChapterRef<'_>> {
+        required(self.component::<Chapter>(id)?, id, "chapter")?;
+        Ok(ChapterRef { snapshot: self, id })
+    }
+
+    /// Lists every chapter of the project in reading order, including the
+    /// chapters inside volumes.
+    pub fn chapters(&self) -> Result<Vec<ChapterRef<'_>>> {
+        Ok(self
+            .project_entities_with::<Chapter>()?
+            .into_iter()
+            .map(|id| ChapterRef { snapshot: self, id })
+            .collect())
+    }
+
+    pub fn text_style(&self, id: EntityId) -> Result<TextStyleRef<'_>> {
+        required(self.component::<TextStyle>(id)?, id, "text style")?;
+        Ok(TextStyleRef { snapshot: self, id })
+    }
+
+    /// Lists the project's text styles in the order they were added.
+    pub fn text_styles(&self) -> Result<Vec<TextStyleRef<'_>>> {
+        Ok(self
+            .project_entities_with::<TextStyle>()?
+            .into_iter()
+            .map(|id| TextStyleRef { snapshot: self, id })
+            .collect())
+    }
+
+    fn project_entities_with<T: Component>(&self) -> Result<Vec<EntityId>> {
+        let key = key::<T>()?;
+        let mut ids = Vec::new();
+        for id in self.state.project_entities() {
+            if self.has_component(id, &key)? {
+                ids.push(id);
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The language of the edition the project renders, exports, and
+    /// translates into, if one is selected.
+    pub fn edition_locale(&self) -> Result<Option<LanguageTag>> {
+        Ok(self
+            .project_component::<Edition>()?
+            .map(|edition| edition.locale))
+    }
+
+    /// The direction the project's pages read in.
+    pub fn reading_direction(&self) -> Result<ReadingDirection> {
+        Ok(self
+            .project_component::<Reading>()?
+            .map(|reading| reading.direction)
+            .unwrap_or_default())
+    }
+
+    /// Returns the translation recorded for exactly one language, where `None`
+    /// is the fallback translation without a language.
+    pub fn translation(
+        &self,
+        entity: EntityId,
+        language: Option<&LanguageTag>,
+    ) -> Result<Option<Translation>> {
+        Ok(self
+            .component::<Translations>(entity)?
+            .and_then(|mut translations| translations.languages.remove(&language.cloned()))
+            .map(|translation| Translation {
+                text: translation.text,
+                language: language.cloned(),
+            }))
+    }
+
+    /// Returns the spans that style the translation recorded for exactly one
+    /// language.
+    pub fn translation_spans(
+        &self,
+        entity: EntityId,
+        language: Option<&LanguageTag>,
+    ) -> Result<Vec<TextSpan>> {
+        Ok(self
+            .component::<Translations>(entity)?
+            .and_then(|mut translations| translations.languages.remove(&language.cloned()))
+            .map(|translation| translation.spans)
+            .unwrap_or_default())
+    }
+
+    /// Lists every translation of a text content, the fallback first and the
+    /// others by language.
+    pub fn translations(&self, entity: EntityId) -> Result<Vec<Translation>> {
+        Ok(self
+            .component::<Translations>(entity)?
+            .map(|translations| translations.languages)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(language, translation)| Translation {
+                text: translation.text,
+                language,
+            })
+            .collect())
+    }
+
     pub fn text_layers(&self) -> Result<impl ExactSizeIterator<Item = TextLayerRef<'_>>> {
         Ok(self
             .entities_with::<TextLayout>()?
@@ -253,6 +723,68 @@ impl Edit {
         Ok(layer)
     }
 
+    /// Adds a shape layer to a page or a layer group, framed by `geometry`.
+    pub fn add_shape_layer(
+        &mut self,
+        parent: EntityId,
+        at: At,
+        value: &ShapeLayer,
+        geometry: &Geometry,
+    ) -> Result<EntityId> {
+        let layer = self.add_entity(parent, at)?;
+        self.set(layer, value)?;
+        self.set(layer, geometry)?;
+        Ok(layer)
+    }
+
+    /// Adds a character to the project. Characters belong to no page.
+    pub fn add_character(&mut self, value: &Character) -> Result<EntityId> {
+        let entity = self.add_entity(EntityId::PROJECT, At::End)?;
+        self.set(entity, value)?;
+        Ok(entity)
+    }
+
+    /// Adds a term to the project glossary. Terms belong to no page.
+    pub fn add_glossary_term(&mut self, value: &GlossaryTerm) -> Result<EntityId> {
+        let entity = self.add_entity(EntityId::PROJECT, At::End)?;
+        self.set(entity, value)?;
+        Ok(entity)
+    }
+
+    /// Adds a volume to the project.
+    pub fn add_volume(&mut self, value: &Volume, at: At) -> Result<EntityId> {
+        let entity = self.add_entity(EntityId::PROJECT, at)?;
+        self.set(entity, value)?;
+        Ok(entity)
+    }
+
+    /// Adds a chapter inside `volume`, or at the project root. Pages join it
+    /// with [`Edit::set_chapter`].
+    pub fn add_chapter(
+        &mut self,
+        volume: Option<EntityId>,
+        value: &Chapter,
+        at: At,
+    ) -> Result<EntityId> {
+        let entity = self.add_entity(volume.unwrap_or(EntityId::PROJECT), at)?;
+        self.set(entity, value)?;
+        Ok(entity)
+    }
+
+    /// Adds a text style to the project, optionally based on another one.
+    pub fn add_text_style(
+        &mut self,
+        value: &TextStyle,
+        base: Option<EntityId>,
+    ) -> Result<EntityId> {
+        let entity = self.add_entity(EntityId::PROJECT, At::End)?;
+        self.set(entity, value)?;
+        if let Some(base) = base {
+            self.relate::<BasedOn>(entity, base)?;
+        }
+        Ok(entity)
+    }
+
     pub fn add_analysis_region<R: RegionSpec>(
         &mut self,
         parent: EntityId,
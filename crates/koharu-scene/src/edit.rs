@@ -1,13 +1,16 @@
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use smallvec::SmallVec;
 
 use crate::{
-    Asset, AssetInput, AssetRole, BlobId, ComponentOwner, EntityId, EntityOrigin, Error,
-    Generation, Group, Origin, Page, PageDraft, Patch, Relation, RelationId, RelationKind,
-    RelationSpec, Result, Snapshot, TextGroup, Visibility,
+    Annotates, Annotation, AnnotationReply, Asset, AssetInput, AssetRole, Authored, BasedOn,
+    BlobId, Chapter, ComponentConflict, ComponentOwner, EntityId, EntityOrigin, Error,
+    FunctionalRelation, Generation, Group, InChapter, LanguageTag, Origin, Page, PageDraft, Patch,
+    Relation, RelationId, RelationKind, RelationSpec, Result, ReviewState, Snapshot, SpokenBy,
+    SpreadWith, StyledBy, TextGroup, TextSpan, Translation, TranslationCandidate,
+    TranslationCandidates, Visibility, Volume,
     component::{Component, ComponentKey, ComponentRecord, ValidationContext, decode, encode, key},
-    components::Assets,
+    components::{Assets, StoredTranslation, Translations},
     patch::{Observation, Operation},
     schema,
     state::{Components, State, store_components},
@@ -51,6 +54,26 @@ impl Edit {
         }
     }
 
+    /// Runs `f` as another producer, such as a cache the edit's producer
+    /// consults before generating results itself. Components stay owned by
+    /// whichever producer wrote them, so one producer hands a value to the
+    /// other by removing it first. Only a generation edit can do this.
+    pub fn with_generation<T>(
+        &mut self,
+        generation: Generation,
+        f: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let Some(previous) = self.generation.replace(generation) else {
+            self.generation = None;
+            return Err(Error::Authorship(
+                "user edits cannot act as a generation".to_owned(),
+            ));
+        };
+        let result = f(self);
+        self.generation = Some(previous);
+        result
+    }
+
     /// Observes the page that owns this subtree. Page epochs include hierarchy,
     /// component, and incident-relation changes, making the check constant-time.
     pub fn observe_subtree(&mut self, root: EntityId) -> Result<()> {
@@ -71,6 +94,11 @@ impl Edit {
         self.observe_component(ComponentOwner::Entity(entity), key::<Assets>()?)
     }
 
+    /// Observes every translation of a text content, in every language.
+    pub fn observe_translations(&mut self, entity: EntityId) -> Result<()> {
+        self.observe_component(ComponentOwner::Entity(entity), key::<Translations>()?)
+    }
+
     pub fn add_page(&mut self, page: PageDraft, at: At) -> Result<EntityId> {
         self.observe_page_order_write();
         let id = EntityId::new();
@@ -88,6 +116,7 @@ impl Edit {
             position: position as u32,
             components: stored,
         });
+        self.unpair_split_spreads()?;
         Ok(id)
     }
 
@@ -110,6 +139,10 @@ impl Edit {
         let stored = store_components(&components);
         self.state
             .insert_entity(page, id, parent, position, components)?;
+        if page == EntityId::PROJECT {
+            // The project arena only holds entities that a document role claims.
+            self.validate_entities.insert(id);
+        }
         self.operations.push(Operation::InsertEntity {
             page,
             id,
@@ -121,7 +154,7 @@ impl Edit {
     }
 
     pub fn set_page(&mut self, entity: EntityId, page: PageDraft) -> Result<()> {
-        if !self.state.pages.contains_key(&entity) {
+        if entity == EntityId::PROJECT || !self.state.pages.contains_key(&entity) {
             return Err(Error::EntityNotFound(entity));
         }
         self.replace_component(
@@ -137,24 +170,14 @@ impl Edit {
         parent: Option<EntityId>,
         at: At,
     ) -> Result<()> {
+        reject_project_root(entity)?;
         let page = self.state.page_for(entity)?;
         if entity == page {
             if parent.is_some() {
                 return Err(Error::invalid("pages must remain at the project root"));
             }
-            let before = self.state.parent_and_position(entity)?.1;
-            let position = resolve_position(&self.state.page_order, at, Some(entity))?;
-            if before == position {
-                return Ok(());
-            }
-            self.observe_page_order_write();
-            self.state.move_page(entity, position)?;
-            self.operations.push(Operation::MovePage {
-                id: entity,
-                before: before as u32,
-                after: position as u32,
-            });
-            return Ok(());
+            self.reorder_page(entity, at)?;
+            return self.unpair_split_spreads();
         }
 
         let parent = parent.ok_or_else(|| Error::invalid("only pages may use the project root"))?;
@@ -199,7 +222,45 @@ impl Edit {
         Ok(())
     }
 
+    fn reorder_page(&mut self, page: EntityId, at: At) -> Result<()> {
+        let before = self.state.parent_and_position(page)?.1;
+        let position = resolve_position(&self.state.page_order, at, Some(page))?;
+        if before == position {
+            return Ok(());
+        }
+        self.observe_page_order_write();
+        self.state.move_page(page, position)?;
+        self.operations.push(Operation::MovePage {
+            id: page,
+            before: before as u32,
+            after: position as u32,
+        });
+        Ok(())
+    }
+
+    /// Unpairs every spread whose pages a page order change left apart.
+    fn unpair_split_spreads(&mut self) -> Result<()> {
+        let kind = SpreadWith::kind();
+        let split = self
+            .state
+            .page_order
+            .iter()
+            .filter_map(|page| self.state.outgoing.get(page))
+            .flat_map(|relations| relations.iter().copied())
+            .filter(|id| {
+                let relation = &self.state.relations[id].value;
+                relation.kind == kind
+                    && !self.state.adjacent_pages(relation.source, relation.target)
+            })
+            .collect::<Vec<_>>();
+        for relation in split {
+            self.remove_relation(relation)?;
+        }
+        Ok(())
+    }
+
     pub fn remove_entity(&mut self, entity: EntityId, policy: RemovePolicy) -> Result<()> {
+        reject_project_root(entity)?;
         let page = self.state.page_for(entity)?;
         if entity != page
             && self
@@ -405,6 +466,438 @@ impl Edit {
         }
     }
 
+    /// Sets the translation of a text content for its language, leaving the
+    /// other languages untouched. A translation without a language is the
+    /// fallback. The translation keeps the origin it was given, as far as
+    /// `claimed_origin` allows. Changing the text discards the spans that
+    /// styled the previous text.
+    pub fn set_translation(&mut self, entity: EntityId, value: &Translation) -> Result<()> {
+        let origin = self.claimed_origin(&value.text.origin);
+        let mut translations = self.translations(entity)?.unwrap_or_default();
+        let spans = match translations.languages.get(&value.language) {
+            Some(previous) => {
+                self.validate_origin_removal(Some(&previous.text.origin))?;
+                if previous.text.value == value.text.value {
+                    previous.spans.clone()
+                } else {
+                    Vec::new()
+                }
+            }
+            None => Vec::new(),
+        };
+        translations.languages.insert(
+            value.language.clone(),
+            StoredTranslation {
+                text: Authored {
+                    value: value.text.value.clone(),
+                    origin,
+                },
+                spans,
+            },
+        );
+        self.write_translations(entity, translations)
+    }
+
+    /// Removes the translation recorded for exactly one language, where `None`
+    /// is the fallback translation, together with its spans.
+    pub fn remove_translation(
+        &mut self,
+        entity: EntityId,
+        language: Option<&LanguageTag>,
+    ) -> Result<()> {
+        let Some(mut translations) = self.translations(entity)? else {
+            return Ok(());
+        };
+        let Some(existing) = translations.languages.remove(&language.cloned()) else {
+            return Ok(());
+        };
+        self.validate_origin_removal(Some(&existing.text.origin))?;
+        self.write_translations(entity, translations)
+    }
+
+    /// Styles ranges of the translation recorded for exactly one language,
+    /// where `None` is the fallback translation. Spans are ordered byte ranges
+    /// of whole characters of its text and must not overlap. An empty list
+    /// removes the styling.
+    pub fn set_translation_spans(
+        &mut self,
+        entity: EntityId,
+        language: Option<&LanguageTag>,
+        spans: Vec<TextSpan>,
+    ) -> Result<()> {
+        let mut translations = self.translations(entity)?.unwrap_or_default();
+        let Some(translation) = translations.languages.get_mut(&language.cloned()) else {
+            if spans.is_empty() {
+                return Ok(());
+            }
+            return Err(Error::invalid(format!(
+                "text content {entity} has no translation to style"
+            )));
+        };
+        if translation.spans == spans {
+            return Ok(());
+        }
+        self.validate_origin_removal(Some(&translation.text.origin))?;
+        translation.spans = spans;
+        self.write_translations(entity, translations)
+    }
+
+    /// Adds a producer's translation of a text content as a candidate, with
+    /// the edit's generation as its provenance. It replaces an unapproved
+    /// candidate from the same producer and model, and returns its index.
+    pub fn add_translation_candidate(
+        &mut self,
+        entity: EntityId,
+        language: &LanguageTag,
+        text: String,
+        confidence: Option<f32>,
+    ) -> Result<usize> {
+        let Some(generation) = self.generation.clone() else {
+            return Err(Error::Authorship(
+                "translation candidates must be produced by a generation".to_owned(),
+            ));
+        };
+        let owner = ComponentOwner::Entity(entity);
+        let key = key::<TranslationCandidates>()?;
+        let mut value = self
+            .decode_component::<TranslationCandidates>(owner, &key)?
+            .unwrap_or_default();
+        self.observe_component(owner, key.clone())?;
+        let candidate = TranslationCandidate {
+            text,
+            generation: Generation {
+                confidence,
+                ..generation
+            },
+            review: ReviewState::Unreviewed,
+            notes: String::new(),
+        };
+        let candidates = value.languages.entry(language.clone()).or_default();
+        let index = match candidates.iter().position(|existing| {
+            existing.review != ReviewState::Approved
+                && existing.generation.producer == candidate.generation.producer
+                && existing.generation.model == candidate.generation.model
+        }) {
+            Some(index) => {
+                candidates[index] = candidate;
+                index
+            }
+            None => {
+                candidates.push(candidate);
+                candidates.len() - 1
+            }
+        };
+        let record = self.encode_value(&value)?;
+        self.replace_component(owner, key, Some(record))?;
+        self.validate_entities.insert(entity);
+        Ok(index)
+    }
+
+    /// Records an editor's review of one candidate. Approving a candidate
+    /// withdraws any other approval in that language and makes its text the
+    /// user-owned translation.
+    pub fn review_translation_candidate(
+        &mut self,
+        entity: EntityId,
+        language: &LanguageTag,
+        index: usize,
+        review: ReviewState,
+        notes: String,
+    ) -> Result<()> {
+        if self.generation.is_some() {
+            return Err(Error::Authorship(
+                "pipeline cannot review translation candidates".to_owned(),
+            ));
+        }
+        let owner = ComponentOwner::Entity(entity);
+        let key = key::<TranslationCandidates>()?;
+        let mut value = self
+            .decode_component::<TranslationCandidates>(owner, &key)?
+            .unwrap_or_default();
+        let Some(candidates) = value.languages.get_mut(language) else {
+            return Err(Error::invalid(format!(
+                "entity {entity} has no {language} translation candidates"
+            )));
+        };
+        if index >= candidates.len() {
+            return Err(Error::invalid(format!(
+                "entity {entity} has no {language} translation candidate {index}"
+            )));
+        }
+        if review == ReviewState::Approved {
+            for candidate in candidates.iter_mut() {
+                if candidate.review == ReviewState::Approved {
+                    candidate.review = ReviewState::Unreviewed;
+                }
+            }
+        }
+        let candidate = &mut candidates[index];
+        candidate.review = review;
+        candidate.notes = notes;
+        let approved = (review == ReviewState::Approved).then(|| candidate.text.clone());
+        self.observe_component(owner, key.clone())?;
+        let record = self.encode_value(&value)?;
+        self.replace_component(owner, key, Some(record))?;
+        self.validate_entities.insert(entity);
+        match approved {
+            Some(text) => self.set_translation(
+                entity,
+                &Translation {
+                    text: Authored::user(text),
+                    language: Some(language.clone()),
+                },
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Marks an unapproved candidate as needing work, with notes saying why,
+    /// so it shows up for review. Producers use this to flag their own
+    /// results, such as translations that miss a glossary term.
+    pub fn flag_translation_candidate(
+        &mut self,
+        entity: EntityId,
+        language: &LanguageTag,
+        index: usize,
+        notes: String,
+    ) -> Result<()> {
+        let owner = ComponentOwner::Entity(entity);
+        let key = key::<TranslationCandidates>()?;
+        let mut value = self
+            .decode_component::<TranslationCandidates>(owner, &key)?
+            .unwrap_or_default();
+        let Some(candidate) = value
+            .languages
+            .get_mut(language)
+            .and_then(|candidates| candidates.get_mut(index))
+        else {
+            return Err(Error::invalid(format!(
+                "entity {entity} has no {language} translation candidate {index}"
+            )));
+        };
+        if candidate.review == ReviewState::Approved {
+            return Err(Error::invalid(format!(
+                "approved {language} candidate {index} of entity {entity} cannot be flagged"
+            )));
+        }
+        candidate.review = ReviewState::NeedsWork;
+        candidate.notes = notes;
+        self.observe_component(owner, key.clone())?;
+        let record = self.encode_value(&value)?;
+        self.replace_component(owner, key, Some(record))?;
+        self.validate_entities.insert(entity);
+        Ok(())
+    }
+
+    /// Leaves an annotation on a page, analysis region, or text layer. The
+    /// annotation is placed on the anchor's page, and the anchor keeps its
+    /// origin. If regenerating removes the anchor, the note stays on the page.
+    pub fn add_annotation(&mut self, anchor: EntityId, value: &Annotation) -> Result<EntityId> {
+        if self.generation.is_some() {
+            return Err(Error::Authorship(
+                "pipeline cannot leave annotations".to_owned(),
+            ));
+        }
+        let page = self.state.page_for(anchor)?;
+        let entity = self.add_entity(page, At::End)?;
+        self.set(entity, value)?;
+        self.relate::<Annotates>(entity, anchor)?;
+        Ok(entity)
+    }
+
+    /// Appends a reply to an annotation's thread.
+    pub fn reply_to_annotation(&mut self, entity: EntityId, reply: AnnotationReply) -> Result<()> {
+        let mut annotation = self.annotation(entity)?;
+        annotation.replies.push(reply);
+        self.set(entity, &annotation)
+    }
+
+    pub fn resolve_annotation(&mut self, entity: EntityId, resolved: bool) -> Result<()> {
+        let mut annotation = self.annotation(entity)?;
+        if annotation.resolved == resolved {
+            return Ok(());
+        }
+        annotation.resolved = resolved;
+        self.set(entity, &annotation)
+    }
+
+    /// Names the character who speaks a text content, or clears its speaker.
+    pub fn set_speaker(&mut self, content: EntityId, character: Option<EntityId>) -> Result<()> {
+        self.replace_relation::<SpokenBy>(content, character)
+    }
+
+    /// Applies a project text style to a text layer, or clears its style.
+    /// The layer's own typography keeps precedence over the style.
+    pub fn set_text_style(&mut self, layer: EntityId, style: Option<EntityId>) -> Result<()> {
+        self.replace_relation::<StyledBy>(layer, style)
+    }
+
+    /// Bases a text style on another one, or makes it stand alone. A style
+    /// cannot inherit from itself, directly or through other styles.
+    pub fn set_text_style_base(&mut self, style: EntityId, base: Option<EntityId>) -> Result<()> {
+        self.replace_relation::<BasedOn>(style, base)
+    }
+
+    /// Places a page in a project chapter, or takes it out of its chapter.
+    /// The page keeps its position until its chapter moves.
+    pub fn set_chapter(&mut self, page: EntityId, chapter: Option<EntityId>) -> Result<()> {
+        self.replace_relation::<InChapter>(page, chapter)
+    }
+
+    /// Moves a chapter into `volume`, or to the project root, and moves the
+    /// pages of every chapter into chapter order along with it.
+    pub fn move_chapter(
+        &mut self,
+        chapter: EntityId,
+        volume: Option<EntityId>,
+        at: At,
+    ) -> Result<()> {
+        self.require_project_entity::<Chapter>(chapter, "chapter")?;
+        self.move_entity(chapter, Some(volume.unwrap_or(EntityId::PROJECT)), at)?;
+        self.arrange_chapter_pages()
+    }
+
+    /// Moves a volume with its chapters, and their pages along with them.
+    pub fn move_volume(&mut self, volume: EntityId, at: At) -> Result<()> {
+        self.require_project_entity::<Volume>(volume, "volume")?;
+        self.move_entity(volume, Some(EntityId::PROJECT), at)?;
+        self.arrange_chapter_pages()
+    }
+
+    /// Pairs two adjacent pages as one spread, or with `None` returns `page`
+    /// to standing alone. Pages paired elsewhere leave their previous spreads,
+    /// and moving either page away from the other later unpairs them.
+    pub fn set_spread(&mut self, page: EntityId, partner: Option<EntityId>) -> Result<()> {
+        for page in std::iter::once(page).chain(partner) {
+            if page == EntityId::PROJECT || !self.state.pages.contains_key(&page) {
+                return Err(Error::EntityNotFound(page));
+            }
+        }
+        let kind = SpreadWith::kind();
+        let current = self
+            .state
+            .outgoing
+            .get(&page)
+            .into_iter()
+            .chain(self.state.incoming.get(&page))
+            .chain(partner.and_then(|partner| self.state.outgoing.get(&partner)))
+            .chain(partner.and_then(|partner| self.state.incoming.get(&partner)))
+            .flat_map(|relations| relations.iter().copied())
+            .filter(|id| self.state.relations[id].value.kind == kind)
+            .collect::<BTreeSet<_>>();
+        if let Some(partner) = partner
+            && !self.state.adjacent_pages(page, partner)
+        {
+            return Err(Error::invalid("only adjacent pages can form a spread"));
+        }
+        let (first, second) = match partner {
+            Some(partner) => {
+                let position = |page| self.state.page_order.iter().position(|id| *id == page);
+                if position(partner) < position(page) {
+                    (partner, Some(page))
+                } else {
+                    (page, Some(partner))
+                }
+            }
+            None => (page, None),
+        };
+        let unchanged = match (current.first(), second) {
+            (Some(relation), Some(second)) if current.len() == 1 => {
+                let relation = &self.state.relations[relation].value;
+                (relation.source, relation.target) == (first, second)
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return Ok(());
+        }
+        for relation in current {
+            self.remove_relation(relation)?;
+        }
+        if let Some(second) = second {
+            self.relate::<SpreadWith>(first, second)?;
+        }
+        Ok(())
+    }
+
+    fn require_project_entity<T: Component>(&self, entity: EntityId, role: &str) -> Result<()> {
+        if self.state.component(entity, &key::<T>()?)?.is_some() {
+            Ok(())
+        } else {
+            Err(Error::invalid(format!("entity {entity} is not a {role}")))
+        }
+    }
+
+    /// Reorders the pages that belong to chapters into the chapters' reading
+    /// order. Pages without a chapter keep their positions, and pages of one
+    /// chapter keep their relative order.
+    fn arrange_chapter_pages(&mut self) -> Result<()> {
+        let chapter_key = key::<Chapter>()?;
+        let mut chapters = HashMap::new();
+        for id in self.state.project_entities() {
+            if self.state.component(id, &chapter_key)?.is_some() {
+                chapters.insert(id, chapters.len());
+            }
+        }
+        let kind = InChapter::kind();
+        let mut slots = Vec::new();
+        let mut members = Vec::new();
+        for (slot, page) in self.state.page_order.iter().enumerate() {
+            let chapter = self
+                .state
+                .outgoing
+                .get(page)
+                .into_iter()
+                .flat_map(|relations| relations.iter())
+                .map(|id| &self.state.relations[id].value)
+                .find(|relation| relation.kind == kind)
+                .and_then(|relation| chapters.get(&relation.target));
+            if let Some(chapter) = chapter {
+                slots.push(slot);
+                members.push((*chapter, slot, *page));
+            }
+        }
+        members.sort_unstable();
+        let mut desired = self.state.page_order.to_vec();
+        for (slot, (_, _, page)) in slots.into_iter().zip(members) {
+            desired[slot] = page;
+        }
+        for (index, page) in desired.iter().enumerate() {
+            let at = match index {
+                0 => At::Start,
+                _ => At::After(desired[index - 1]),
+            };
+            self.reorder_page(*page, at)?;
+        }
+        self.unpair_split_spreads()
+    }
+
+    fn replace_relation<R: FunctionalRelation>(
+        &mut self,
+        source: EntityId,
+        target: Option<EntityId>,
+    ) -> Result<()> {
+        let current = self
+            .state
+            .outgoing
+            .get(&source)
+            .into_iter()
+            .flat_map(|relations| relations.iter())
+            .find(|id| self.state.relations[*id].value.kind == R::kind())
+            .map(|id| (*id, self.state.relations[id].value.target));
+        if current.map(|(_, current)| current) == target {
+            return Ok(());
+        }
+        if let Some((relation, _)) = current {
+            self.remove_relation(relation)?;
+        }
+        if let Some(target) = target {
+            self.relate::<R>(source, target)?;
+        }
+        Ok(())
+    }
+
     pub fn add_relation(
         &mut self,
         kind: RelationKind,
@@ -516,6 +1009,24 @@ impl Edit {
         self.replace_component(owner, key::<T>()?, None)
     }
 
+    /// Resolves a merge conflict with the value of the merged branch.
+    pub fn take_theirs(&mut self, conflict: &ComponentConflict) -> Result<()> {
+        for (blob, bytes) in conflict.attachments.iter() {
+            if !self.base.storage.blobs().contains(*blob) {
+                self.attachments.push((*blob, bytes.clone()));
+            }
+        }
+        if let ComponentOwner::Entity(entity) = conflict.owner {
+            self.validate_entities.insert(entity);
+        }
+        self.observe_component(conflict.owner, conflict.key.clone())?;
+        self.replace_component(
+            conflict.owner,
+            conflict.key.clone(),
+            conflict.theirs.clone(),
+        )
+    }
+
     pub fn finish(self) -> Result<Patch> {
         for entity in &self.validate_entities {
             schema::validate_entity(&self.state, *entity)?;
@@ -615,6 +1126,9 @@ impl Edit {
         key: ComponentKey,
         after: Option<ComponentRecord>,
     ) -> Result<()> {
+        if let ComponentOwner::Entity(entity) = owner {
+            reject_project_root(entity)?;
+        }
         let before = self.component(owner, &key)?.cloned();
         if before == after {
             return Ok(());
@@ -746,6 +1260,48 @@ impl Edit {
         Ok(group)
     }
 
+    /// Replaces every translation of a text content, removing the component
+    /// once no language is left.
+    fn write_translations(&mut self, entity: EntityId, translations: Translations) -> Result<()> {
+        let owner = ComponentOwner::Entity(entity);
+        let key = key::<Translations>()?;
+        self.observe_component(owner, key.clone())?;
+        let record = if translations.languages.is_empty() {
+            None
+        } else {
+            Some(self.encode_value(&translations)?)
+        };
+        self.replace_component(owner, key, record)?;
+        self.validate_entities.insert(entity);
+        Ok(())
+    }
+
+    fn annotation(&self, entity: EntityId) -> Result<Annotation> {
+        self.decode_component(ComponentOwner::Entity(entity), &key::<Annotation>()?)?
+            .ok_or_else(|| Error::invalid(format!("entity {entity} is not an annotation")))
+    }
+
+    fn translations(&mut self, entity: EntityId) -> Result<Option<Translations>> {
+        let owner = ComponentOwner::Entity(entity);
+        let key = key::<Translations>()?;
+        self.observe_component(owner, key.clone())?;
+        self.decode_component(owner, &key)
+    }
+
+    fn decode_component<T: Component>(
+        &self,
+        owner: ComponentOwner,
+        key: &ComponentKey,
+    ) -> Result<Option<T>> {
+        self.component(owner, key)?
+            .map(|value| {
+                let record_exists = |id| self.state.contains_entity(id);
+                let blob_exists = |_id| true;
+                decode::<T>(value, &ValidationContext::new(&record_exists, &blob_exists))
+            })
+            .transpose()
+    }
+
     fn encode_value<T: Component>(&self, value: &T) -> Result<ComponentRecord> {
         let record_exists = |id| self.state.contains_entity(id);
         let blob_exists = |id| {
@@ -833,6 +1389,20 @@ impl Edit {
         }
     }
 
+    /// The origin a value written with `origin` keeps. A user edit keeps the
+    /// caller's origin as given. A generation edit keeps it only when it is
+    /// the edit's own producer, with provenance such as a confidence, and
+    /// otherwise stamps the edit's generation as `set` does.
+    fn claimed_origin(&self, origin: &Origin) -> Origin {
+        match (&self.generation, origin) {
+            (Some(expected), Origin::Generated(actual)) if actual.producer == expected.producer => {
+                origin.clone()
+            }
+            (Some(expected), _) => Origin::Generated(expected.clone()),
+            (None, origin) => origin.clone(),
+        }
+    }
+
     fn lifecycle_origin(&self) -> Origin {
         self.generation
             .clone()
@@ -904,6 +1474,14 @@ impl Edit {
     }
 }
 
+fn reject_project_root(entity: EntityId) -> Result<()> {
+    if entity == EntityId::PROJECT {
+        Err(Error::invalid("the project root cannot be edited"))
+    } else {
+        Ok(())
+    }
+}
+
 fn resolve_position(values: &[EntityId], at: At, moving: Option<EntityId>) -> Result<usize> {
     let filtered = values
         .iter()
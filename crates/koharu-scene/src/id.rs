@@ -45,6 +45,12 @@ macro_rules! entity_id {
 entity_id!(EntityId);
 entity_id!(RelationId);
 
+impl EntityId {
+    /// The root of the project arena, which owns entities that belong to no
+    /// page, such as characters. It is never a page and carries no components.
+    pub const PROJECT: Self = Self(Uuid::nil());
+}
+
 #[derive(
     Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize, Type,
 )]
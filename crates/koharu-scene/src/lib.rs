@@ -8,41 +8,63 @@
 mod change;
 mod component;
 mod components;
+mod diff;
 mod document;
 mod edit;
 mod error;
 mod id;
+mod merge;
+mod migration;
 mod patch;
+mod query;
 mod schema;
 mod semantics;
 mod session;
 mod snapshot;
 mod state;
+mod undo;
 
 pub use change::{
     Change, ComponentChange, ComponentOwner, EntityChange, RelationChange, ValueChangeKind,
 };
 pub use component::{Component, ValidationContext};
 pub use components::{
-    Asset, AssetInput, AssetMetadata, AssetRole, Authored, DetectionAnalysis, DetectionLabel,
-    EntityOrigin, FontStyle, Generation, Geometry, Group, LanguageTag, OcrAnalysis, Origin, Page,
-    PageDraft, Point, Project, RasterLayer, RasterLayerKind, Region, RegionKind, Relation,
-    RelationKind, SourceText, TextAlignment, TextContent, TextDirection, TextGroup, TextLayout,
-    TextLayoutKind, TextRole, Translation, Typography, Visibility, WritingMode,
+    Annotation, AnnotationMark, AnnotationReply, Asset, AssetInput, AssetMetadata, AssetRole,
+    Authored, BlendMode, Blending, Chapter, Character, DetectionAnalysis, DetectionLabel, Edition,
+    EntityOrigin, FontStyle, Generation, Geometry, GlossaryTerm, Group, LanguageTag, OcrAnalysis,
+    Origin, Page, PageDraft, Point, Project, RasterLayer, RasterLayerKind, Reading,
+    ReadingDirection, Region, RegionKind, Relation, RelationKind, ReviewState, ShapeLayer,
+    ShapeOutline, ShapeStroke, SourceText, SpanStyle, TextAlignment, TextContent, TextDirection,
+    TextGroup, TextLayout, TextLayoutKind, TextRole, TextSpan, TextStyle, Translation,
+    TranslationCandidate, TranslationCandidates, Typography, TypographyOverride,
+    TypographyOverrides, Visibility, Volume, WritingMode,
+};
+pub use diff::{
+    DiffPage, DiffReport, GeometryDiff, LayerDiff, PageChange, PageDiff, TextDiff, TextEdit,
+    TextField,
+};
+pub use document::{
+    AnalysisRegionRef, AnnotationRef, ChapterRef, CharacterRef, GlossaryTermRef, GroupRef,
+    TextContentRef, TextLayerRef, TextStyleRef, VolumeRef,
 };
-pub use document::{AnalysisRegionRef, GroupRef, TextContentRef, TextLayerRef};
 pub use edit::{At, Edit, RemovePolicy};
 pub use error::{Error, Result};
 pub use id::{EntityId, ProducerId, ProjectId, RelationId};
+pub use merge::{ComponentConflict, Conflict, Merge, Side};
 pub use patch::Patch;
+pub use query::{Direction, Filter, GenerationFilter, OriginFilter, PageRange, Query};
 pub use semantics::{
-    BubbleRegion, FitsTo, FlowsIn, FunctionalRelation, Inside, PanelRegion, Presents,
-    RecognizedFrom, RegionSpec, RelationSpec, TextRegion,
+    Annotates, BasedOn, BubbleRegion, FitsTo, FlowsIn, FunctionalRelation, InChapter, Inside,
+    OnomatopoeiaRegion, PanelRegion, Presents, RecognizedFrom, RegionSpec, RelationSpec, SpokenBy,
+    SpreadWith, StyledBy, TextRegion,
 };
-pub use session::{Commit, Session};
+pub use session::{Commit, MAX_LOG_RECORDS, Session};
 pub use snapshot::{EntityRef, PageRef, RelationRef, Snapshot};
+pub use undo::DEFAULT_UNDO_DEPTH;
 
-pub use koharu_storage::{BlobId, PatchId, Revision};
+pub use koharu_storage::{
+    BlobId, HistoryEntry, IntegrityReport, PatchId, RepairReport, Retention, Revision,
+};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,484 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use bytes::Bytes;
+
+use crate::{
+    BlobId, Commit, ComponentOwner, EntityId, Error, Result,
+    component::{Component, ComponentKey, ComponentRecord, ValidationContext, decode},
+    patch::Operation,
+    state::{Components, State, store_components},
+};
+
+/// One of the two branches taking part in a merge.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Side {
+    /// The session that merges.
+    Ours,
+    /// The branch being merged into it.
+    Theirs,
+}
+
+/// A change of the merged branch that was not applied.
+#[derive(Clone, Debug)]
+pub enum Conflict {
+    /// Both branches changed one component to different values.
+    Component(Box<ComponentConflict>),
+    /// Both branches moved an entity or changed a relation differently, or
+    /// the merged branch moved an entity somewhere it cannot go.
+    Structure(ComponentOwner),
+    /// One branch removed an entity or relation that the other changed.
+    Removed { owner: ComponentOwner, by: Side },
+}
+
+/// The three values of a component that both branches changed. The merge
+/// keeps ours; [`crate::Edit::take_theirs`] applies the other branch's value.
+#[derive(Clone, Debug)]
+pub struct ComponentConflict {
+    pub owner: ComponentOwner,
+    pub kind: String,
+    pub(crate) key: ComponentKey,
+    base: Option<ComponentRecord>,
+    ours: Option<ComponentRecord>,
+    pub(crate) theirs: Option<ComponentRecord>,
+    /// Blob bytes that their value references and our project may lack.
+    pub(crate) attachments: Arc<[(BlobId, Bytes)]>,
+}
+
+impl ComponentConflict {
+    pub fn base<T: Component>(&self) -> Result<Option<T>> {
+        self.decode(self.base.as_ref())
+    }
+
+    pub fn ours<T: Component>(&self) -> Result<Option<T>> {
+        self.decode(self.ours.as_ref())
+    }
+
+    pub fn theirs<T: Component>(&self) -> Result<Option<T>> {
+        self.decode(self.theirs.as_ref())
+    }
+
+    fn decode<T: Component>(&self, record: Option<&ComponentRecord>) -> Result<Option<T>> {
+        if T::KIND != self.kind {
+            return Err(Error::invalid(format!(
+                "the conflict concerns {}, not {}",
+                self.kind,
+                T::KIND
+            )));
+        }
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        // Each value was validated in the branch that wrote it.
+        let record_exists = |_id| true;
+        let blob_exists = |_id| true;
+        decode::<T>(
+            record,
+            &ValidationContext::new(&record_exists, &blob_exists),
+        )
+        .map(Some)
+    }
+}
+
+/// The result of [`crate::Session::merge`].
+#[derive(Clone, Debug)]
+pub struct Merge {
+    /// The commit that applied every non-conflicting change.
+    pub commit: Commit,
+    pub conflicts: Vec<Conflict>,
+}
+
+pub(crate) struct Merged {
+    pub(crate) state: State,
+    pub(crate) operations: Vec<Operation>,
+    pub(crate) conflicts: Vec<Conflict>,
+}
+
+/// Replays the changes `theirs` made since `base` on top of `ours`. Only
+/// changes that `ours` did not make differently are applied, and every applied
+/// change is recorded as an ordinary operation.
+pub(crate) fn merge(base: &State, ours: &State, theirs: &State) -> Result<Merged> {
+    let mut merger = Merger {
+        base,
+        ours,
+        theirs,
+        state: ours.clone(),
+        operations: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    merger.insert_entities()?;
+    merger.move_entities()?;
+    merger.merge_entity_components()?;
+    merger.merge_relations()?;
+    merger.remove_entities()?;
+    merger
+        .state
+        .validate()
+        .map_err(|error| Error::PatchConflict(format!("the merged scene is invalid: {error}")))?;
+    Ok(Merged {
+        state: merger.state,
+        operations: merger.operations,
+        conflicts: merger.conflicts,
+    })
+}
+
+/// An entity's parent and its nearest preceding sibling that all three
+/// branches share. Comparing anchors rather than indexes keeps sibling
+/// insertions and removals from reading as moves.
+type Place = (Option<EntityId>, Option<EntityId>);
+
+struct Merger<'a> {
+    base: &'a State,
+    ours: &'a State,
+    theirs: &'a State,
+    state: State,
+    operations: Vec<Operation>,
+    conflicts: Vec<Conflict>,
+}
+
+impl Merger<'_> {
+    fn apply(&mut self, operation: Operation) -> Result<()> {
+        operation.apply(&mut self.state)?;
+        self.operations.push(operation);
+        Ok(())
+    }
+
+    fn shared(&self, id: EntityId) -> bool {
+        self.base.contains_entity(id)
+            && self.ours.contains_entity(id)
+            && self.theirs.contains_entity(id)
+    }
+
+    fn place(&self, state: &State, id: EntityId) -> Result<Place> {
+        let (parent, position) = state.parent_and_position(id)?;
+        let siblings = siblings(state, parent)?;
+        let anchor = siblings[..position]
+            .iter()
+            .rev()
+            .copied()
+            .find(|sibling| self.shared(*sibling));
+        Ok((parent, anchor))
+    }
+
+    /// The position after the nearest preceding sibling that `id` has in
+    /// their branch and that the merged state already contains.
+    fn position(&self, parent: Option<EntityId>, id: EntityId) -> Result<usize> {
+        let (theirs_parent, position) = self.theirs.parent_and_position(id)?;
+        let preceding = siblings(self.theirs, theirs_parent)?;
+        let current = siblings(&self.state, parent)?
+            .into_iter()
+            .filter(|sibling| *sibling != id)
+            .collect::<Vec<_>>();
+        Ok(preceding[..position]
+            .iter()
+            .rev()
+            .find_map(|sibling| current.iter().position(|candidate| candidate == sibling))
+            .map_or(0, |index| index + 1))
+    }
+
+    fn insert_entities(&mut self) -> Result<()> {
+        for id in ordered(self.theirs) {
+            if self.base.contains_entity(id) || self.state.contains_entity(id) {
+                continue;
+            }
+            let components = store_components(&self.theirs.entity(id)?.components);
+            match self.theirs.parent_and_position(id)?.0 {
+                None => {
+                    let position = self.position(None, id)? as u32;
+                    self.apply(Operation::InsertPage {
+                        id,
+                        position,
+                        components,
+                    })?;
+                }
+                Some(parent) if self.state.contains_entity(parent) => {
+                    let page = self.state.page_for(parent)?;
+                    let position = self.position(Some(parent), id)? as u32;
+                    self.apply(Operation::InsertEntity {
+                        page,
+                        id,
+                        parent,
+                        position,
+                        components,
+                    })?;
+                }
+                // A parent that only their branch created was already refused.
+                Some(parent) => {
+                    if self.base.contains_entity(parent) {
+                        self.removed(ComponentOwner::Entity(parent), Side::Ours);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn move_entities(&mut self) -> Result<()> {
+        for id in ordered(self.theirs) {
+            if !self.shared(id) {
+                continue;
+            }
+            let base = self.place(self.base, id)?;
+            let theirs = self.place(self.theirs, id)?;
+            let ours = self.place(self.ours, id)?;
+            if theirs == base || ours == theirs {
+                continue;
+            }
+            if ours != base {
+                self.conflicts
+                    .push(Conflict::Structure(ComponentOwner::Entity(id)));
+                continue;
+            }
+            let (before_parent, before_position) = self.state.parent_and_position(id)?;
+            let Some(parent) = theirs.0 else {
+                let after = self.position(None, id)?;
+                if after != before_position {
+                    self.apply(Operation::MovePage {
+                        id,
+                        before: before_position as u32,
+                        after: after as u32,
+                    })?;
+                }
+                continue;
+            };
+            let before_parent = before_parent.expect("a shared non-page entity has a parent");
+            if !self.state.contains_entity(parent) {
+                self.removed(ComponentOwner::Entity(parent), Side::Ours);
+                continue;
+            }
+            let page = self.state.page_for(id)?;
+            let target_page = self.state.page_for(parent)?;
+            if page == target_page && self.state.page(page)?.descendants(id)?.contains(&parent) {
+                self.conflicts
+                    .push(Conflict::Structure(ComponentOwner::Entity(id)));
+                continue;
+            }
+            let after_position = self.position(Some(parent), id)?;
+            self.apply(Operation::MoveEntity {
+                id,
+                before_page: page,
+                before_parent,
+                before_position: before_position as u32,
+                after_page: target_page,
+                after_parent: parent,
+                after_position: after_position as u32,
+            })?;
+        }
+        Ok(())
+    }
+
+    fn merge_entity_components(&mut self) -> Result<()> {
+        let (base, ours, theirs) = (self.base, self.ours, self.theirs);
+        self.merge_components(
+            ComponentOwner::Project,
+            &base.project_components,
+            &ours.project_components,
+            &theirs.project_components,
+        )?;
+        for id in ordered(theirs) {
+            if !base.contains_entity(id) {
+                continue;
+            }
+            let before = &base.entity(id)?.components;
+            let after = &theirs.entity(id)?.components;
+            if !ours.contains_entity(id) {
+                let moved = self.place(theirs, id)? != self.place(base, id)?;
+                if after != before || moved {
+                    self.removed(ComponentOwner::Entity(id), Side::Ours);
+                }
+                continue;
+            }
+            let current = &ours.entity(id)?.components;
+            self.merge_components(ComponentOwner::Entity(id), before, current, after)?;
+        }
+        Ok(())
+    }
+
+    fn merge_components(
+        &mut self,
+        owner: ComponentOwner,
+        base: &Components,
+        ours: &Components,
+        theirs: &Components,
+    ) -> Result<()> {
+        let mut keys = BTreeMap::<&ComponentKey, [Option<&ComponentRecord>; 3]>::new();
+        for (index, components) in [base, ours, theirs].into_iter().enumerate() {
+            for (key, record) in components {
+                keys.entry(key).or_default()[index] = Some(record);
+            }
+        }
+        for (key, [base, ours, theirs]) in keys {
+            if theirs == base || ours == theirs {
+                continue;
+            }
+            if ours != base {
+                self.conflicts
+                    .push(Conflict::Component(Box::new(ComponentConflict {
+                        owner,
+                        kind: key.kind.clone(),
+                        key: key.clone(),
+                        base: base.cloned(),
+                        ours: ours.cloned(),
+                        theirs: theirs.cloned(),
+                        attachments: Arc::from([]),
+                    })));
+                continue;
+            }
+            self.apply(Operation::ReplaceComponent {
+                owner,
+                key: key.clone(),
+                before: ours.map(ComponentRecord::to_stored),
+                after: theirs.map(ComponentRecord::to_stored),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn merge_relations(&mut self) -> Result<()> {
+        let (base, ours, theirs) = (self.base, self.ours, self.theirs);
+        let ids = theirs.relations.keys().copied().collect::<BTreeSet<_>>();
+        for id in &ids {
+            let after = &theirs.relations[id];
+            let Some(before) = base.relations.get(id) else {
+                let missing = [after.value.source, after.value.target]
+                    .into_iter()
+                    .find(|endpoint| !self.state.contains_entity(*endpoint));
+                match missing {
+                    None => self.apply(Operation::InsertRelation {
+                        id: *id,
+                        value: after.value.clone(),
+                        components: store_components(&after.components),
+                    })?,
+                    Some(endpoint) if base.contains_entity(endpoint) => {
+                        self.removed(ComponentOwner::Entity(endpoint), Side::Ours);
+                    }
+                    Some(_) => {}
+                }
+                continue;
+            };
+            let Some(current) = ours.relations.get(id) else {
+                if after.value != before.value || after.components != before.components {
+                    self.removed(ComponentOwner::Relation(*id), Side::Ours);
+                }
+                continue;
+            };
+            if after.value != before.value && current.value != after.value {
+                if current.value == before.value {
+                    self.apply(Operation::ReplaceRelation {
+                        id: *id,
+                        before: current.value.clone(),
+                        after: after.value.clone(),
+                    })?;
+                } else {
+                    self.conflicts
+                        .push(Conflict::Structure(ComponentOwner::Relation(*id)));
+                }
+            }
+            self.merge_components(
+                ComponentOwner::Relation(*id),
+                &before.components,
+                &current.components,
+                &after.components,
+            )?;
+        }
+
+        let removed = base
+            .relations
+            .keys()
+            .filter(|id| !ids.contains(id))
+            .copied()
+            .collect::<BTreeSet<_>>();
+        for id in removed {
+            let Some(current) = ours.relations.get(&id) else {
+                continue;
+            };
+            let before = &base.relations[&id];
+            if current.value != before.value || current.components != before.components {
+                self.removed(ComponentOwner::Relation(id), Side::Theirs);
+                continue;
+            }
+            self.apply(Operation::RemoveRelation {
+                id,
+                value: current.value.clone(),
+                components: store_components(&current.components),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn remove_entities(&mut self) -> Result<()> {
+        let mut kept = BTreeSet::new();
+        for id in ordered(&self.state).into_iter().rev() {
+            if !self.base.contains_entity(id) || self.theirs.contains_entity(id) {
+                continue;
+            }
+            let changed = self.ours.entity(id)?.components != self.base.entity(id)?.components
+                || self.place(self.ours, id)? != self.place(self.base, id)?;
+            let children = self.state.child_ids(id)?;
+            if children.iter().any(|child| kept.contains(child)) {
+                kept.insert(id);
+                continue;
+            }
+            if changed || !children.is_empty() || self.incident(id) {
+                self.removed(ComponentOwner::Entity(id), Side::Theirs);
+                kept.insert(id);
+                continue;
+            }
+            let (parent, position) = self.state.parent_and_position(id)?;
+            let components = store_components(&self.state.entity(id)?.components);
+            let operation = match parent {
+                None => Operation::RemovePage {
+                    id,
+                    position: position as u32,
+                    components,
+                },
+                Some(parent) => Operation::RemoveEntity {
+                    page: self.state.page_for(id)?,
+                    id,
+                    parent,
+                    position: position as u32,
+                    components,
+                },
+            };
+            self.apply(operation)?;
+        }
+        Ok(())
+    }
+
+    fn incident(&self, id: EntityId) -> bool {
+        [&self.state.outgoing, &self.state.incoming]
+            .into_iter()
+            .any(|relations| relations.get(&id).is_some_and(|ids| !ids.is_empty()))
+    }
+
+    fn removed(&mut self, owner: ComponentOwner, by: Side) {
+        let conflict = Conflict::Removed { owner, by };
+        if !self.conflicts.iter().any(|existing| {
+            matches!(existing, Conflict::Removed { owner: other, by: side } if *other == owner && *side == by)
+        }) {
+            self.conflicts.push(conflict);
+        }
+    }
+}
+
+/// Every entity of the project arena, then every entity in page order, each
+/// parent before its children.
+fn ordered(state: &State) -> Vec<EntityId> {
+    let mut ids = state.project_entities();
+    ids.extend(
+        state
+            .page_order
+            .iter()
+            .flat_map(|page| state.pages[page].ordered_ids()),
+    );
+    ids
+}
+
+fn siblings(state: &State, parent: Option<EntityId>) -> Result<Vec<EntityId>> {
+    match parent {
+        Some(parent) => state.child_ids(parent),
+        None => Ok(state.page_order.to_vec()),
+    }
+}
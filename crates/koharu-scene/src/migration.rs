@@ -0,0 +1,101 @@
+//! Upgrades stored component payloads written by older schema revisions.
+//!
+//! Additive changes are absorbed by `revision` attributes on the component
+//! type itself. A change the new type cannot read directly, such as a
+//! restructured payload, registers one upgrade step per revision instead.
+//! Steps run while a checkpoint, its logged operations, and the undo journal
+//! are decoded, so every later precondition compares upgraded payloads.
+
+use crate::{
+    Error, Result,
+    component::{Component, ComponentKey, StoredComponent},
+    components::{Translations, translation_store},
+    patch::Operation,
+    state::{StoredComponentEntry, StoredState},
+};
+
+/// Rewrites the payload of one component kind from schema revision `from` to
+/// `from + 1`. The entity and blob references of the payload are kept.
+pub(crate) struct Migration {
+    pub(crate) kind: &'static str,
+    pub(crate) from: u32,
+    pub(crate) upgrade: fn(&[u8]) -> Result<Vec<u8>>,
+}
+
+/// Every built-in upgrade step. A component revision without a step is read
+/// by its type as it is, and a revision newer than the type is preserved
+/// without being decoded.
+pub(crate) const MIGRATIONS: &[Migration] = &[Migration {
+    kind: Translations::KIND,
+    from: 1,
+    upgrade: translation_store,
+}];
+
+pub(crate) fn upgrade_state(migrations: &[Migration], state: &mut StoredState) -> Result<()> {
+    upgrade_components(migrations, &mut state.project_components)?;
+    for entity in state
+        .pages
+        .iter_mut()
+        .flat_map(|page| &mut page.entities)
+        .chain(&mut state.project_entities)
+    {
+        upgrade_components(migrations, &mut entity.components)?;
+    }
+    for relation in &mut state.relations {
+        upgrade_components(migrations, &mut relation.components)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn upgrade_operation(migrations: &[Migration], operation: &mut Operation) -> Result<()> {
+    match operation {
+        Operation::InsertPage { components, .. }
+        | Operation::RemovePage { components, .. }
+        | Operation::InsertEntity { components, .. }
+        | Operation::RemoveEntity { components, .. }
+        | Operation::InsertRelation { components, .. }
+        | Operation::RemoveRelation { components, .. } => {
+            upgrade_components(migrations, components)
+        }
+        Operation::ReplaceComponent {
+            key, before, after, ..
+        } => {
+            for value in [before, after].into_iter().flatten() {
+                upgrade_component(migrations, key, value)?;
+            }
+            Ok(())
+        }
+        Operation::MovePage { .. }
+        | Operation::MoveEntity { .. }
+        | Operation::ReplaceRelation { .. } => Ok(()),
+    }
+}
+
+fn upgrade_components(
+    migrations: &[Migration],
+    components: &mut [StoredComponentEntry],
+) -> Result<()> {
+    for entry in components {
+        upgrade_component(migrations, &entry.key, &mut entry.value)?;
+    }
+    Ok(())
+}
+
+fn upgrade_component(
+    migrations: &[Migration],
+    key: &ComponentKey,
+    component: &mut StoredComponent,
+) -> Result<()> {
+    while let Some(step) = migrations
+        .iter()
+        .find(|step| step.kind == key.kind && step.from == component.schema)
+    {
+        let schema = component
+            .schema
+            .checked_add(1)
+            .ok_or_else(|| Error::invalid(format!("{} schema overflow", key.kind)))?;
+        component.payload = (step.upgrade)(&component.payload)?;
+        component.schema = schema;
+    }
+    Ok(())
+}
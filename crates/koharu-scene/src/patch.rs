@@ -73,6 +73,27 @@ pub(crate) enum Operation {
 }
 
 impl Operation {
+    /// Blobs referenced by the components this operation writes.
+    pub(crate) fn written_blobs(&self) -> impl Iterator<Item = BlobId> + '_ {
+        let components: &[StoredComponentEntry] = match self {
+            Self::InsertPage { components, .. }
+            | Self::InsertEntity { components, .. }
+            | Self::InsertRelation { components, .. } => components,
+            _ => &[],
+        };
+        let replaced = match self {
+            Self::ReplaceComponent {
+                after: Some(after), ..
+            } => after.blob_refs.as_slice(),
+            _ => &[],
+        };
+        components
+            .iter()
+            .flat_map(|entry| entry.value.blob_refs.iter())
+            .chain(replaced)
+            .copied()
+    }
+
     pub(crate) fn apply(&self, state: &mut State) -> Result<()> {
         match self {
             Self::InsertPage {
@@ -0,0 +1,385 @@
+//! Declarative entity selection over a snapshot.
+//!
+//! A [`Query`] is plain data, so the desktop, the pipeline, and agent tools can
+//! pass one across a process or JSON boundary. Typed builder methods derive the
+//! component, region, and relation kinds from their Rust types.
+
+use std::{cell::OnceCell, collections::HashMap, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{
+    Component, EntityId, EntityRef, Generation, Origin, Presents, ProducerId, RegionKind,
+    RegionSpec, RelationKind, RelationSpec, Result, Snapshot, SourceText, TextContent, TextLayout,
+    component::ComponentKey,
+};
+
+/// Selects the entities that satisfy every filter, optionally on a range of
+/// pages only. Results are in project order: page by page, each page's
+/// hierarchy depth first.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct Query {
+    pub pages: Option<PageRange>,
+    pub filters: Vec<Filter>,
+}
+
+/// Zero-based positions of the first and last page to search, inclusive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
+pub struct PageRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "filter", content = "value", rename_all = "snake_case")]
+pub enum Filter {
+    /// The entity carries a component of this kind.
+    Has(String),
+    /// The entity carries no component of this kind.
+    Lacks(String),
+    /// The entity carries this built-in component with a matching origin.
+    Origin {
+        component: String,
+        origin: OriginFilter,
+    },
+    /// The entity is an analysis region of this kind.
+    Region(RegionKind),
+    /// The translation the current edition shows for a text content, or for
+    /// the content a text layer presents, exists and has a matching origin.
+    Translation(OriginFilter),
+    /// The source text or the edition's translation contains this text,
+    /// ignoring case.
+    Text(String),
+    /// A relation of this kind connects the entity to one that matches the
+    /// nested query.
+    Related {
+        kind: RelationKind,
+        direction: Direction,
+        query: Box<Query>,
+    },
+}
+
+/// Which endpoint of a relation the filtered entity is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// The entity is the relation's source.
+    Outgoing,
+    /// The entity is the relation's target.
+    Incoming,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "origin", content = "value", rename_all = "snake_case")]
+pub enum OriginFilter {
+    User,
+    Generated(GenerationFilter),
+}
+
+/// Matches generated values. Every field that is set must match, and a
+/// confidence bound never matches a generation without a confidence.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct GenerationFilter {
+    pub producer: Option<ProducerId>,
+    pub model: Option<String>,
+    /// Inclusive lower confidence bound.
+    pub min_confidence: Option<f32>,
+    /// Exclusive upper confidence bound.
+    pub below_confidence: Option<f32>,
+}
+
+impl Query {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn pages(mut self, positions: RangeInclusive<u32>) -> Self {
+        self.pages = Some(PageRange {
+            first: *positions.start(),
+            last: *positions.end(),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    #[must_use]
+    pub fn with<T: Component>(self) -> Self {
+        self.filter(Filter::Has(T::KIND.to_owned()))
+    }
+
+    #[must_use]
+    pub fn without<T: Component>(self) -> Self {
+        self.filter(Filter::Lacks(T::KIND.to_owned()))
+    }
+
+    #[must_use]
+    pub fn origin<T: Component>(self, origin: OriginFilter) -> Self {
+        self.filter(Filter::Origin {
+            component: T::KIND.to_owned(),
+            origin,
+        })
+    }
+
+    #[must_use]
+    pub fn region<R: RegionSpec>(self) -> Self {
+        self.filter(Filter::Region(R::kind()))
+    }
+
+    #[must_use]
+    pub fn translation(self, origin: OriginFilter) -> Self {
+        self.filter(Filter::Translation(origin))
+    }
+
+    #[must_use]
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.filter(Filter::Text(text.into()))
+    }
+
+    /// Keeps entities that are the source of an `R` relation to a match of `target`.
+    #[must_use]
+    pub fn relates<R: RelationSpec>(self, target: Query) -> Self {
+        self.filter(Filter::Related {
+            kind: R::kind(),
+            direction: Direction::Outgoing,
+            query: Box::new(target),
+        })
+    }
+
+    /// Keeps entities that are the target of an `R` relation from a match of `source`.
+    #[must_use]
+    pub fn related_by<R: RelationSpec>(self, source: Query) -> Self {
+        self.filter(Filter::Related {
+            kind: R::kind(),
+            direction: Direction::Incoming,
+            query: Box::new(source),
+        })
+    }
+
+    /// Reports whether `entity` satisfies the query in `snapshot`.
+    pub fn matches(&self, snapshot: &Snapshot, entity: EntityId) -> Result<bool> {
+        self.matches_in(&Evaluation::new(snapshot), entity)
+    }
+
+    fn matches_in(&self, evaluation: &Evaluation<'_>, entity: EntityId) -> Result<bool> {
+        if let Some(pages) = self.pages {
+            let page = evaluation.snapshot.state.page_for(entity)?;
+            if !evaluation
+                .position(page)
+                .is_some_and(|position| pages.contains(position))
+            {
+                return Ok(false);
+            }
+        }
+        self.filters_match(evaluation, entity)
+    }
+
+    fn filters_match(&self, evaluation: &Evaluation<'_>, entity: EntityId) -> Result<bool> {
+        for filter in &self.filters {
+            if !filter.matches(evaluation, entity)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// One evaluation of a query. Page positions are indexed at most once, when a
+/// page range is first checked, and shared by every related query it reaches.
+struct Evaluation<'a> {
+    snapshot: &'a Snapshot,
+    positions: OnceCell<HashMap<EntityId, usize>>,
+}
+
+impl<'a> Evaluation<'a> {
+    fn new(snapshot: &'a Snapshot) -> Self {
+        Self {
+            snapshot,
+            positions: OnceCell::new(),
+        }
+    }
+
+    fn position(&self, page: EntityId) -> Option<usize> {
+        self.positions
+            .get_or_init(|| {
+                self.snapshot
+                    .state
+                    .page_order
+                    .iter()
+                    .enumerate()
+                    .map(|(position, page)| (*page, position))
+                    .collect()
+            })
+            .get(&page)
+            .copied()
+    }
+}
+
+impl PageRange {
+    fn contains(self, position: usize) -> bool {
+        u32::try_from(position).is_ok_and(|position| (self.first..=self.last).contains(&position))
+    }
+}
+
+impl Filter {
+    fn matches(&self, evaluation: &Evaluation<'_>, entity: EntityId) -> Result<bool> {
+        let snapshot = evaluation.snapshot;
+        match self {
+            Self::Has(kind) => snapshot.has_component(entity, &ComponentKey::new(kind.as_str())?),
+            Self::Lacks(kind) => {
+                Ok(!snapshot.has_component(entity, &ComponentKey::new(kind.as_str())?)?)
+            }
+            Self::Origin { component, origin } => Ok(snapshot
+                .component_origin(entity, &ComponentKey::new(component.as_str())?)?
+                .is_some_and(|value| origin.matches(&value))),
+            Self::Region(kind) => Ok(snapshot
+                .component::<crate::Region>(entity)?
+                .is_some_and(|region| region.kind == *kind)),
+            Self::Translation(origin) => {
+                let Some(content) = text_content(snapshot, entity)? else {
+                    return Ok(false);
+                };
+                Ok(snapshot
+                    .text_content(content)?
+                    .translation()?
+                    .is_some_and(|translation| origin.matches(&translation.text.origin)))
+            }
+            Self::Text(text) => {
+                let Some(content) = text_content(snapshot, entity)? else {
+                    return Ok(false);
+                };
+                let needle = text.to_lowercase();
+                let content = snapshot.text_content(content)?;
+                let source = content
+                    .source()?
+                    .map(|source: SourceText| source.text.value);
+                let translation = content
+                    .translation()?
+                    .map(|translation| translation.text.value);
+                Ok([source, translation]
+                    .into_iter()
+                    .flatten()
+                    .any(|value| value.to_lowercase().contains(&needle)))
+            }
+            Self::Related {
+                kind,
+                direction,
+                query,
+            } => {
+                let relations = match direction {
+                    Direction::Outgoing => snapshot
+                        .relations_from(entity, Some(kind))
+                        .collect::<Vec<_>>(),
+                    Direction::Incoming => snapshot.relations_to(entity, Some(kind)).collect(),
+                };
+                for relation in relations {
+                    let value = relation.value();
+                    let other = match direction {
+                        Direction::Outgoing => value.target,
+                        Direction::Incoming => value.source,
+                    };
+                    if query.matches_in(evaluation, other)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl OriginFilter {
+    /// Matches values generated by `producer`.
+    #[must_use]
+    pub fn generated_by(producer: ProducerId) -> Self {
+        Self::Generated(GenerationFilter {
+            producer: Some(producer),
+            ..GenerationFilter::default()
+        })
+    }
+
+    #[must_use]
+    pub fn matches(&self, origin: &Origin) -> bool {
+        match (self, origin) {
+            (Self::User, Origin::User) => true,
+            (Self::Generated(filter), Origin::Generated(generation)) => filter.matches(generation),
+            _ => false,
+        }
+    }
+}
+
+impl GenerationFilter {
+    #[must_use]
+    pub fn matches(&self, generation: &Generation) -> bool {
+        let confidence = |bound: Option<f32>, accepts: fn(f32, f32) -> bool| {
+            bound.is_none_or(|bound| {
+                generation
+                    .confidence
+                    .is_some_and(|confidence| accepts(confidence, bound))
+            })
+        };
+        self.producer
+            .as_ref()
+            .is_none_or(|producer| *producer == generation.producer)
+            && self
+                .model
+                .as_ref()
+                .is_none_or(|model| generation.model.as_ref() == Some(model))
+            && confidence(self.min_confidence, |confidence, bound| confidence >= bound)
+            && confidence(self.below_confidence, |confidence, bound| {
+                confidence < bound
+            })
+    }
+}
+
+/// The text content an entity is, or the one a text layer presents.
+fn text_content(snapshot: &Snapshot, entity: EntityId) -> Result<Option<EntityId>> {
+    if snapshot.component::<TextContent>(entity)?.is_some() {
+        return Ok(Some(entity));
+    }
+    if snapshot.component::<TextLayout>(entity)?.is_none() {
+        return Ok(None);
+    }
+    Ok(snapshot
+        .relation_from::<Presents>(entity)?
+        .map(|relation| relation.value().target))
+}
+
+impl Snapshot {
+    /// Returns the entities that satisfy `query` in project order.
+    pub fn query(&self, query: &Query) -> Result<Vec<EntityRef<'_>>> {
+        let pages = self
+            .state
+            .page_order
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| query.pages.is_none_or(|pages| pages.contains(*position)));
+        let evaluation = Evaluation::new(self);
+        let mut matches = Vec::new();
+        for (_, page) in pages {
+            // A required component narrows the candidates to its page index.
+            let candidates = match query.filters.iter().find_map(|filter| match filter {
+                Filter::Has(kind) => Some(kind),
+                _ => None,
+            }) {
+                Some(kind) => {
+                    self.state.pages[page].entities_with(&ComponentKey::new(kind.as_str())?)
+                }
+                None => self.state.pages[page].ordered_ids(),
+            };
+            for entity in candidates {
+                if query.filters_match(&evaluation, entity)? {
+                    matches.push(self.entity(entity)?);
+                }
+            }
+        }
+        Ok(matches)
+    }
+}
@@ -5,11 +5,13 @@
 //! relation endpoints.
 
 use crate::{
-    BubbleRegion, DetectionAnalysis, EntityId, EntityOrigin, Error, Geometry, Group, OcrAnalysis,
-    Page, Project, RasterLayer, Region, RegionSpec, Relation, Result, SourceText, TextContent,
-    TextGroup, TextLayout, TextRegion, TextRole, Translation, Typography, Visibility,
+    Annotation, Blending, BubbleRegion, Chapter, Character, DetectionAnalysis, Edition, EntityId,
+    EntityOrigin, Error, Geometry, GlossaryTerm, Group, OcrAnalysis, Origin, Page, Project,
+    RasterLayer, Reading, Region, RegionSpec, Relation, Result, ShapeLayer, SourceText,
+    TextContent, TextGroup, TextLayout, TextRegion, TextRole, TextStyle, TranslationCandidates,
+    Typography, TypographyOverrides, Visibility, Volume,
     component::{Component, ComponentRecord, ValidationContext, decode, key},
-    components::Assets,
+    components::{Assets, Translations},
     state::{Components, State},
 };
 
@@ -30,7 +32,11 @@ macro_rules! component_schema {
             context: &ValidationContext<'_>,
         ) -> Result<()> {
             $(if kind == <$component as Component>::KIND {
-                decode::<$component>(raw, context)?;
+                // A revision written by a newer release is preserved opaquely.
+                // Reading or replacing it reports an unsupported component.
+                if raw.schema <= u32::from(<$component as revision::Revisioned>::revision()) {
+                    decode::<$component>(raw, context)?;
+                }
                 return Ok(());
             })+
             // Extension components remain open-ended. Their owning crate is
@@ -38,6 +44,19 @@ macro_rules! component_schema {
             // revision, references, and fingerprints.
             Ok(())
         }
+
+        /// Decodes the origin of a built-in component by kind. Extension
+        /// components and components without an origin report none.
+        pub(crate) fn component_origin(
+            kind: &str,
+            raw: &ComponentRecord,
+            context: &ValidationContext<'_>,
+        ) -> Result<Option<Origin>> {
+            $(if kind == <$component as Component>::KIND {
+                return Ok(decode::<$component>(raw, context)?.origin().cloned());
+            })+
+            Ok(None)
+        }
     };
 }
 
@@ -53,13 +72,25 @@ component_schema! {
     TEXT_CONTENT = 8 => TextContent,
     TEXT_LAYOUT = 9 => TextLayout,
     OCR_ANALYSIS = 10 => OcrAnalysis,
-    TRANSLATION = 11 => Translation,
+    TRANSLATION = 11 => Translations,
     TEXT_ROLE = 12 => TextRole,
     TYPOGRAPHY = 13 => Typography,
     REGION = 14 => Region,
     DETECTION_ANALYSIS = 15 => DetectionAnalysis,
     ASSETS = 16 => Assets,
     ENTITY_ORIGIN = 17 => EntityOrigin,
+    TYPOGRAPHY_OVERRIDES = 19 => TypographyOverrides,
+    EDITION = 20 => Edition,
+    TRANSLATION_CANDIDATES = 21 => TranslationCandidates,
+    ANNOTATION = 22 => Annotation,
+    CHARACTER = 23 => Character,
+    TEXT_STYLE = 25 => TextStyle,
+    VOLUME = 26 => Volume,
+    CHAPTER = 27 => Chapter,
+    READING = 28 => Reading,
+    SHAPE_LAYER = 29 => ShapeLayer,
+    BLENDING = 30 => Blending,
+    GLOSSARY_TERM = 31 => GlossaryTerm,
 }
 
 pub(crate) fn validate_components(
@@ -72,6 +103,16 @@ pub(crate) fn validate_components(
     Ok(())
 }
 
+/// Kinds only the project owns, each alone on its entity beside its lifecycle
+/// origin, with the name validation errors give them.
+const PROJECT_KINDS: &[(u32, &str)] = &[
+    (CHARACTER, "character"),
+    (TEXT_STYLE, "text style"),
+    (GLOSSARY_TERM, "glossary term"),
+    (VOLUME, "volume"),
+    (CHAPTER, "chapter"),
+];
+
 pub(crate) fn validate_entity(state: &State, id: EntityId) -> Result<()> {
     let entity = state.entity(id)?;
     let kinds = entity
@@ -81,13 +122,13 @@ pub(crate) fn validate_entity(state: &State, id: EntityId) -> Result<()> {
     let has = |component| kinds & component != 0;
     let has_source = has(SOURCE_TEXT);
     let has_content = has(TEXT_CONTENT);
-    let has_translation = has(TRANSLATION);
+    let has_translation = has(TRANSLATION) || has(TRANSLATION_CANDIDATES);
     let has_region = has(REGION);
     let has_geometry = has(GEOMETRY);
     let has_raster = has(RASTER_LAYER);
     let has_assets = has(ASSETS);
     let has_layout = has(TEXT_LAYOUT);
-    let has_typography = has(TYPOGRAPHY);
+    let has_typography = has(TYPOGRAPHY) || has(TYPOGRAPHY_OVERRIDES);
     let has_detection = has(DETECTION_ANALYSIS);
     let has_ocr = has(OCR_ANALYSIS);
     let has_group = has(GROUP);
@@ -102,7 +143,53 @@ pub(crate) fn validate_entity(state: &State, id: EntityId) -> Result<()> {
         })
     });
 
-    if (has_source || has_translation || has(TEXT_ROLE)) && !has_content {
+    let parent_is_volume = parent.is_some_and(|parent| {
+        state.entity(parent).is_ok_and(|entity| {
+            entity
+                .components
+                .iter()
+                .any(|(key, _)| component_mask(&key.kind) == VOLUME)
+        })
+    });
+
+    let in_project = state.page_for(id)? == EntityId::PROJECT;
+    let project_kind = PROJECT_KINDS.iter().copied().find(|(mask, _)| has(*mask));
+
+    if id == EntityId::PROJECT {
+        if entity.components.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::invalid("the project root carries no components"))
+        }
+    } else if in_project
+        && (project_kind.is_none()
+            || !(parent == Some(EntityId::PROJECT) || has(CHAPTER) && parent_is_volume))
+    {
+        Err(Error::invalid(format!(
+            "project entity {id} is not a {}",
+            PROJECT_KINDS
+                .iter()
+                .map(|(_, name)| *name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    } else if let Some((_, name)) = project_kind
+        && !in_project
+    {
+        Err(Error::invalid(format!(
+            "{name} {id} is not owned by the project"
+        )))
+    } else if let Some((mask, name)) = project_kind
+        && kinds & !(mask | ENTITY_ORIGIN) != 0
+    {
+        Err(Error::invalid(format!(
+            "{name} {id} also carries other document components"
+        )))
+    } else if has(ANNOTATION) && kinds & !(ANNOTATION | ENTITY_ORIGIN) != 0 {
+        Err(Error::invalid(format!(
+            "annotation {id} also carries other document components"
+        )))
+    } else if (has_source || has_translation || has(TEXT_ROLE)) && !has_content {
         Err(Error::invalid(format!(
             "entity {id} carries text content data but is not text content"
         )))
@@ -151,6 +238,25 @@ pub(crate) fn validate_entity(state: &State, id: EntityId) -> Result<()> {
         Err(Error::invalid(format!(
             "text layer {id} is not contained by the page text group"
         )))
+    } else if has(SHAPE_LAYER)
+        && (has_content
+            || has_region
+            || has_raster
+            || has_assets
+            || has_layout
+            || has_typography
+            || has_detection
+            || has_ocr)
+    {
+        Err(Error::invalid(format!(
+            "shape layer {id} also carries content, analysis, or other layer components"
+        )))
+    } else if has(BLENDING) && !has_raster {
+        Err(Error::invalid(format!(
+            "blending on entity {id} requires a raster layer"
+        )))
+    } else if has(SHAPE_LAYER) && !has_geometry {
+        Err(Error::invalid(format!("shape layer {id} has no geometry")))
     } else if has_content
         && (has_region || has_geometry || has_layout || has_typography || has_detection || has_ocr)
     {
@@ -273,6 +379,34 @@ fn validate_relation_endpoints(
                 && has(relation.target, Geometry::KIND)
                 && region_kind(relation.target)?.is_some_and(|kind| kind == BubbleRegion::kind())
         }
+        <crate::Annotates as crate::RelationSpec>::KIND => {
+            has(relation.source, Annotation::KIND)
+                && (has(relation.target, Page::KIND)
+                    || has(relation.target, Region::KIND)
+                    || has(relation.target, TextLayout::KIND))
+        }
+        <crate::SpokenBy as crate::RelationSpec>::KIND => {
+            has(relation.source, TextContent::KIND) && has(relation.target, Character::KIND)
+        }
+        <crate::StyledBy as crate::RelationSpec>::KIND => {
+            has(relation.source, TextLayout::KIND) && has(relation.target, TextStyle::KIND)
+        }
+        <crate::InChapter as crate::RelationSpec>::KIND => {
+            has(relation.source, Page::KIND) && has(relation.target, Chapter::KIND)
+        }
+        <crate::SpreadWith as crate::RelationSpec>::KIND => {
+            relation.source != relation.target
+                && has(relation.source, Page::KIND)
+                && has(relation.target, Page::KIND)
+                && other_spreads(state, relation.source, relation) == 0
+                && other_spreads(state, relation.target, relation) == 0
+                && state.adjacent_pages(relation.source, relation.target)
+        }
+        <crate::BasedOn as crate::RelationSpec>::KIND => {
+            has(relation.source, TextStyle::KIND)
+                && has(relation.target, TextStyle::KIND)
+                && !inherits_from(state, relation.target, relation.source)
+        }
         <crate::Inside as crate::RelationSpec>::KIND => {
             has(relation.source, Region::KIND)
                 && has(relation.target, Region::KIND)
@@ -298,9 +432,58 @@ fn is_functional(kind: &crate::RelationKind) -> bool {
             | <crate::RecognizedFrom as crate::RelationSpec>::KIND
             | <crate::FitsTo as crate::RelationSpec>::KIND
             | <crate::FlowsIn as crate::RelationSpec>::KIND
+            | <crate::Annotates as crate::RelationSpec>::KIND
+            | <crate::SpokenBy as crate::RelationSpec>::KIND
+            | <crate::StyledBy as crate::RelationSpec>::KIND
+            | <crate::BasedOn as crate::RelationSpec>::KIND
+            | <crate::InChapter as crate::RelationSpec>::KIND
+            | <crate::SpreadWith as crate::RelationSpec>::KIND
     )
 }
 
+/// Counts the spread relations of `page` other than `relation`, so each page
+/// belongs to at most one spread.
+fn other_spreads(state: &State, page: EntityId, relation: &Relation) -> usize {
+    state
+        .outgoing
+        .get(&page)
+        .into_iter()
+        .chain(state.incoming.get(&page))
+        .flat_map(|relations| relations.iter())
+        .map(|id| &state.relations[id].value)
+        .filter(|other| {
+            other.kind == relation.kind
+                && (other.source, other.target) != (relation.source, relation.target)
+        })
+        .count()
+}
+
+/// Reports whether `style` is `ancestor` or inherits from it, so a `based-on`
+/// relation to `style` from `ancestor` would close a cycle.
+fn inherits_from(state: &State, style: EntityId, ancestor: EntityId) -> bool {
+    let mut visited = std::collections::HashSet::new();
+    let mut current = Some(style);
+    while let Some(style) = current {
+        if style == ancestor {
+            return true;
+        }
+        if !visited.insert(style) {
+            return false;
+        }
+        current = state
+            .outgoing
+            .get(&style)
+            .into_iter()
+            .flat_map(|relations| relations.iter())
+            .map(|id| &state.relations[id].value)
+            .find(|relation| {
+                relation.kind.as_str() == <crate::BasedOn as crate::RelationSpec>::KIND
+            })
+            .map(|relation| relation.target);
+    }
+    false
+}
+
 fn validate_automatic_placement(state: &State, relation: &Relation) -> Result<()> {
     let other = match relation.kind.as_str() {
         <crate::FitsTo as crate::RelationSpec>::KIND => {
@@ -25,6 +25,12 @@ impl RegionSpec for PanelRegion {
     const KIND: &'static str = "dev.koharu.region.panel";
 }
 
+/// A sound effect lettered into the artwork rather than placed in a balloon.
+pub struct OnomatopoeiaRegion;
+impl RegionSpec for OnomatopoeiaRegion {
+    const KIND: &'static str = "dev.koharu.region.onomatopoeia";
+}
+
 pub trait RelationSpec {
     const KIND: &'static str;
 
@@ -64,6 +70,49 @@ impl RelationSpec for FlowsIn {
 }
 impl FunctionalRelation for FlowsIn {}
 
+/// An annotation is anchored to a page, analysis region, or text layer.
+pub struct Annotates;
+impl RelationSpec for Annotates {
+    const KIND: &'static str = "dev.koharu.relation.annotates";
+}
+impl FunctionalRelation for Annotates {}
+
+/// Text content is spoken by a project character.
+pub struct SpokenBy;
+impl RelationSpec for SpokenBy {
+    const KIND: &'static str = "dev.koharu.relation.spoken-by";
+}
+impl FunctionalRelation for SpokenBy {}
+
+/// A text presentation layer uses a project text style.
+pub struct StyledBy;
+impl RelationSpec for StyledBy {
+    const KIND: &'static str = "dev.koharu.relation.styled-by";
+}
+impl FunctionalRelation for StyledBy {}
+
+/// A project text style inherits the fields it leaves unset from another.
+pub struct BasedOn;
+impl RelationSpec for BasedOn {
+    const KIND: &'static str = "dev.koharu.relation.based-on";
+}
+impl FunctionalRelation for BasedOn {}
+
+/// A page belongs to a project chapter.
+pub struct InChapter;
+impl RelationSpec for InChapter {
+    const KIND: &'static str = "dev.koharu.relation.in-chapter";
+}
+impl FunctionalRelation for InChapter {}
+
+/// Two pages face each other as one spread. Their page order decides which of
+/// them is read first.
+pub struct SpreadWith;
+impl RelationSpec for SpreadWith {
+    const KIND: &'static str = "dev.koharu.relation.spread-with";
+}
+impl FunctionalRelation for SpreadWith {}
+
 /// A source-analysis region is spatially contained by another region.
 pub struct Inside;
 impl RelationSpec for Inside {
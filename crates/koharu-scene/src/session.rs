@@ -1,29 +1,40 @@
 use std::{collections::BTreeMap, path::Path, sync::Arc};
 
 use bytes::Bytes;
+use revision::revisioned;
 
 use crate::{
-    Change, Error, Patch, ProjectId, Result, Snapshot,
+    Change, Conflict, Error, Merge, Patch, ProjectId, Result, Snapshot, merge,
+    migration::{self, Migration},
     patch::{Operation, apply_operations},
     state::{State, StoredState},
+    undo::{DEFAULT_UNDO_DEPTH, JournalWrite, UndoHistory},
 };
 
-struct HistoryEntry {
-    inverse: Arc<[Operation]>,
-    // A revision's inverse may restore blobs that the current state no longer
-    // references. Retain their lease for as long as that revision is undoable.
-    _blobs: koharu_storage::Blobs,
+/// A commit writes a complete checkpoint instead of a log record once the log
+/// holds this many records. It also does so once the log would outgrow the
+/// checkpoint it extends, which bounds replay on open by the checkpoint size.
+pub const MAX_LOG_RECORDS: usize = 256;
+
+/// The payload of one project log record.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct LoggedPatch {
+    operations: Vec<Operation>,
 }
 
-/// One open project and its in-memory undo history.
+/// One open project and its undo history.
 ///
-/// Every successful commit publishes a complete scene snapshot. Undo commands
-/// are retained only for the lifetime of this session; they are UI history, not
-/// part of the durable project format.
+/// Every successful commit appends its operations to the project log, so its
+/// cost follows the size of the edit rather than the size of the project.
+/// Periodically a commit publishes a complete scene checkpoint instead, which
+/// storage also records in the project history. Undo and redo stacks and their
+/// inverse operations are written to the storage journal after each change, so
+/// they survive reopening the project.
 pub struct Session {
     storage: koharu_storage::Session,
     current: Snapshot,
-    history: BTreeMap<crate::Revision, HistoryEntry>,
+    undo: UndoHistory,
 }
 
 impl std::fmt::Debug for Session {
@@ -32,7 +43,7 @@ impl std::fmt::Debug for Session {
             .debug_struct("Session")
             .field("project", &self.project_id())
             .field("revision", &self.current.revision())
-            .field("undoable", &self.history.len())
+            .field("undoable", &self.undo.len())
             .finish_non_exhaustive()
     }
 }
@@ -57,6 +68,46 @@ impl Session {
         Self::recover(storage).await
     }
 
+    /// Recreates a project at `path` from a portable bundle. Storage verifies
+    /// every blob, and the scene decodes and validates the state, before
+    /// storage publishes it, so a rejected bundle never becomes a project.
+    #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn import_bundle(bundle: impl AsRef<Path>, path: impl AsRef<Path>) -> Result<Self> {
+        let decoded = Arc::new(std::sync::Mutex::new(None));
+        let imported = koharu_storage::Session::import_bundle(bundle, path, {
+            let decoded = decoded.clone();
+            move |state| {
+                let state = decode_state(state);
+                let accepted = state.is_ok();
+                *decoded.lock().expect("decoded state lock") = Some(state);
+                accepted
+            }
+        })
+        .await;
+        let decoded = decoded.lock().expect("decoded state lock").take();
+        match (imported, decoded) {
+            (Ok(storage), Some(Ok(state))) => Self::assemble(storage, state).await,
+            (_, Some(Err(error))) => Err(error),
+            (Ok(_), None) => Err(Error::invalid("imported bundle was not validated")),
+            (Err(error), _) => Err(error.into()),
+        }
+    }
+
+    /// Repairs a closed project so that it opens at the newest state whose
+    /// blobs are intact and whose scene decodes and validates.
+    #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn repair(path: impl AsRef<Path>) -> Result<crate::RepairReport> {
+        koharu_storage::Session::repair(path, |state| {
+            decode_state(state)
+                .inspect_err(|error| {
+                    tracing::warn!(%error, revision = %state.revision(), "rejecting invalid scene state");
+                })
+                .is_ok()
+        })
+        .await
+        .map_err(Into::into)
+    }
+
     pub async fn memory() -> Result<Self> {
         let document = koharu_storage::DocumentId::new();
         let state = State::empty(document);
@@ -76,8 +127,19 @@ impl Session {
         self.current.clone()
     }
 
-    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id(), base = %patch.base_revision))]
+    /// Publishes a patch and records it as the newest undo step.
     pub async fn commit(&mut self, patch: Patch) -> Result<Commit> {
+        let base = self.current.revision();
+        let commit = self.publish(patch).await?;
+        if commit.revision != base {
+            self.undo.record(commit.revision);
+            self.save_undo().await;
+        }
+        Ok(commit)
+    }
+
+    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id(), base = %patch.base_revision))]
+    async fn publish(&mut self, patch: Patch) -> Result<Commit> {
         if patch.project != self.current.state.document {
             return Err(Error::invalid("patch belongs to another project"));
         }
@@ -94,6 +156,7 @@ impl Session {
                 revision: self.current.revision(),
                 changes: Change::empty(self.current.revision()),
                 snapshot: self.current.clone(),
+                checkpoint: false,
             });
         }
 
@@ -104,13 +167,34 @@ impl Session {
             .ok_or_else(|| Error::invalid("project revision overflow"))?;
         let mut state = (*patch.state).clone();
         state.revision = next_revision;
-        let proposed = self.current.storage.update(
-            next_revision,
-            Bytes::from(encode_checkpoint(&state)?),
-            state.referenced_blobs(),
-            patch.attachments.iter().cloned(),
-        )?;
+        let record = revision::to_vec(&LoggedPatch {
+            operations: patch.operations.to_vec(),
+        })?;
+        let current = &self.current.storage;
+        let logged = current
+            .log()
+            .iter()
+            .map(|record| record.payload().len())
+            .sum::<usize>()
+            + record.len();
+        let proposed = if current.log().len() >= MAX_LOG_RECORDS || logged > current.payload().len()
+        {
+            current.update(
+                next_revision,
+                Bytes::from(encode_checkpoint(&state)?),
+                state.referenced_blobs(),
+                patch.attachments.iter().cloned(),
+            )?
+        } else {
+            current.append(
+                next_revision,
+                Bytes::from(record),
+                patch.operations.iter().flat_map(Operation::written_blobs),
+                patch.attachments.iter().cloned(),
+            )?
+        };
         let stored = self.storage.save(&proposed).await?;
+        let checkpoint = stored.log().is_empty();
 
         let inverse = patch
             .operations
@@ -118,13 +202,8 @@ impl Session {
             .rev()
             .map(Operation::reversed)
             .collect::<Vec<_>>();
-        self.history.insert(
-            next_revision,
-            HistoryEntry {
-                inverse: inverse.into(),
-                _blobs: self.current.storage.blobs().clone(),
-            },
-        );
+        self.undo
+            .insert(next_revision, inverse, self.current.storage.blobs().clone());
 
         let state = Arc::new(state);
         let snapshot = Snapshot::new(state, stored)?;
@@ -135,9 +214,12 @@ impl Session {
             revision: next_revision,
             changes,
             snapshot,
+            checkpoint,
         })
     }
 
+    /// Reverts one retained revision as a new edit. See
+    /// [`Session::undo_last`] for stack-based undo.
     pub async fn undo(&mut self, revision: crate::Revision) -> Result<Commit> {
         self.undo_many([revision]).await
     }
@@ -147,6 +229,61 @@ impl Session {
         &mut self,
         revisions: impl IntoIterator<Item = crate::Revision>,
     ) -> Result<Commit> {
+        let patch = self.inverse_patch(revisions)?;
+        self.commit(patch).await
+    }
+
+    #[must_use]
+    pub fn can_undo(&self) -> bool {
+        self.undo.can_undo()
+    }
+
+    #[must_use]
+    pub fn can_redo(&self) -> bool {
+        self.undo.can_redo()
+    }
+
+    /// Reverts the newest undo step and makes the revert redoable.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn undo_last(&mut self) -> Result<Commit> {
+        let revision = self
+            .undo
+            .last_undo()
+            .ok_or_else(|| Error::invalid("nothing to undo"))?;
+        let patch = self.inverse_patch([revision])?;
+        let commit = self.publish(patch).await?;
+        self.undo.undone(commit.revision);
+        self.save_undo().await;
+        Ok(commit)
+    }
+
+    /// Reverts the newest undo, which makes it undoable again.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn redo_last(&mut self) -> Result<Commit> {
+        let revision = self
+            .undo
+            .last_redo()
+            .ok_or_else(|| Error::invalid("nothing to redo"))?;
+        let patch = self.inverse_patch([revision])?;
+        let commit = self.publish(patch).await?;
+        self.undo.redone(commit.revision);
+        self.save_undo().await;
+        Ok(commit)
+    }
+
+    #[must_use]
+    pub fn undo_depth(&self) -> usize {
+        self.undo.depth()
+    }
+
+    /// Limits how many undo and redo steps are retained. Older steps, and the
+    /// blobs only they could restore, are released at once.
+    pub async fn set_undo_depth(&mut self, depth: usize) {
+        self.undo.set_depth(depth);
+        self.save_undo().await;
+    }
+
+    fn inverse_patch(&self, revisions: impl IntoIterator<Item = crate::Revision>) -> Result<Patch> {
         let mut revisions = revisions.into_iter().collect::<Vec<_>>();
         revisions.sort_unstable_by(|left, right| right.cmp(left));
         revisions.dedup();
@@ -155,12 +292,11 @@ impl Session {
         }
         let mut operations = Vec::new();
         for revision in &revisions {
-            let entry = self.history.get(revision).ok_or_else(|| {
-                Error::invalid(format!(
-                    "revision {revision} is not undoable in this session"
-                ))
-            })?;
-            operations.extend(entry.inverse.iter().cloned());
+            let inverse = self
+                .undo
+                .inverse(*revision)
+                .ok_or_else(|| Error::invalid(format!("revision {revision} is not undoable")))?;
+            operations.extend(inverse.iter().cloned());
         }
         let state = apply_operations(&self.current.state, &operations)?;
         let label: Arc<str> = if revisions.len() == 1 {
@@ -168,15 +304,241 @@ impl Session {
         } else {
             format!("Undo {} revisions", revisions.len()).into()
         };
-        let patch = Patch::new(
+        Patch::new(
             &self.current,
             state,
             Vec::new(),
             operations,
             Vec::new(),
             Some(label),
+        )
+    }
+
+    /// Writes the undo history for the current revision, appending the change
+    /// when the journal allows it. The commit it follows is already durable,
+    /// so a failure only costs undo steps after reopening.
+    async fn save_undo(&mut self) {
+        let revision = self.current.revision();
+        let write = match self.undo.journal_write() {
+            Ok(write) => write,
+            Err(error) => {
+                tracing::warn!(%error, "failed to encode undo history");
+                self.undo.journaled(revision, None);
+                return;
+            }
+        };
+        let replaced = matches!(write, JournalWrite::Replace(_));
+        let result = match write {
+            JournalWrite::Replace(payload) => {
+                self.storage
+                    .save_journal(revision, Bytes::from(payload), self.undo.blobs())
+                    .await
+            }
+            JournalWrite::Append {
+                base,
+                record,
+                blobs,
+            } => {
+                self.storage
+                    .append_journal(base, revision, Bytes::from(record), blobs)
+                    .await
+            }
+        };
+        match result {
+            Ok(()) => self.undo.journaled(revision, Some(replaced)),
+            Err(error) => {
+                tracing::warn!(%error, "failed to persist undo history");
+                self.undo.journaled(revision, None);
+            }
+        }
+    }
+
+    /// Checks blob hashes and durable states without changing the project.
+    pub async fn verify(&self) -> Result<crate::IntegrityReport> {
+        self.storage.verify().await.map_err(Into::into)
+    }
+
+    /// Writes the current snapshot and its blobs into one portable file. The
+    /// log is compacted first because a bundle holds a single checkpoint.
+    pub async fn export_bundle(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.compact().await?;
+        self.storage.export_bundle(path).await.map_err(Into::into)
+    }
+
+    /// Publishes the current snapshot as a complete checkpoint and empties the
+    /// project log. Commits compact periodically on their own.
+    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id()))]
+    pub async fn compact(&mut self) -> Result<()> {
+        let current = &self.current.storage;
+        if current.log().is_empty() {
+            return Ok(());
+        }
+        let proposed = current.update(
+            current.revision(),
+            Bytes::from(encode_checkpoint(&self.current.state)?),
+            self.current.state.referenced_blobs(),
+            [],
         )?;
-        self.commit(patch).await
+        let stored = self.storage.compact(&proposed).await?;
+        self.current = Snapshot::new(self.current.state.clone(), stored)?;
+        Ok(())
+    }
+
+    /// Lists durable revisions recorded in the project history, oldest first.
+    /// Commits that appended a log record are listed as well; storage archives
+    /// them next to the checkpoint they extend.
+    pub async fn history(&self) -> Result<Vec<crate::HistoryEntry>> {
+        self.storage.history().await.map_err(Into::into)
+    }
+
+    /// Names a recorded revision. Naming the current revision compacts the log
+    /// first, so the name marks a complete checkpoint.
+    pub async fn name_checkpoint(
+        &mut self,
+        revision: crate::Revision,
+        name: impl Into<String>,
+    ) -> Result<()> {
+        if revision == self.current.revision() {
+            self.compact().await?;
+        }
+        self.storage
+            .name_checkpoint(revision, name)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn clear_checkpoint(&self, revision: crate::Revision) -> Result<()> {
+        self.storage
+            .clear_checkpoint(revision)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn prune_history(&self, retention: crate::Retention) -> Result<usize> {
+        self.storage
+            .prune_history(retention)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Opens a recorded revision as a read-only snapshot. Patches built from
+    /// it cannot be committed because their base is not the current snapshot.
+    /// A logged revision is replayed on top of the checkpoint it extends.
+    pub async fn open_revision(&self, revision: crate::Revision) -> Result<Snapshot> {
+        if revision == self.current.revision() {
+            return Ok(self.current.clone());
+        }
+        let stored = self.storage.load_revision(revision).await?;
+        let state = decode_state(&stored)?;
+        Snapshot::new(Arc::new(state), stored)
+    }
+
+    /// Commits the content of a recorded revision as the next revision. The
+    /// in-session undo history is discarded because its inverse operations
+    /// describe the replaced timeline.
+    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id(), revision = %revision))]
+    pub async fn restore(&mut self, revision: crate::Revision) -> Result<Commit> {
+        let historical = self.storage.load_revision(revision).await?;
+        let mut state = decode_state(&historical)?;
+        let next_revision = self
+            .current
+            .revision()
+            .next()
+            .ok_or_else(|| Error::invalid("project revision overflow"))?;
+        state.revision = next_revision;
+        let proposed = self.current.storage.update(
+            next_revision,
+            Bytes::from(encode_checkpoint(&state)?),
+            state.referenced_blobs(),
+            [],
+        )?;
+        let stored = self.storage.save(&proposed).await?;
+        drop(historical);
+
+        let changes = Change::between(&self.current.state, &state);
+        let snapshot = Snapshot::new(Arc::new(state), stored)?;
+        self.undo.clear();
+        self.current = snapshot.clone();
+        self.save_undo().await;
+        Ok(Commit {
+            revision: next_revision,
+            changes,
+            snapshot,
+            checkpoint: true,
+        })
+    }
+
+    /// Creates an independent branch of this project at `path`, starting from
+    /// the recorded `revision`. Both branches keep the project ID, and both
+    /// record `revision` in their history, so either can later load it as the
+    /// base of [`Session::merge`]. Forking the current revision compacts the
+    /// log first.
+    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id(), revision = %revision))]
+    pub async fn fork(
+        &mut self,
+        revision: crate::Revision,
+        path: impl AsRef<Path>,
+    ) -> Result<Self> {
+        if revision == self.current.revision() {
+            self.compact().await?;
+        }
+        let storage = self.storage.fork(revision, path).await?;
+        Self::recover(storage).await
+    }
+
+    /// Merges what `theirs` changed since `base` into the current snapshot and
+    /// commits the result as one undoable revision. A change is applied when
+    /// this branch left the same value, entity, or relation as it was in
+    /// `base`; otherwise this branch's state is kept and the change is
+    /// returned as a conflict. Blobs that the merged values reference are
+    /// copied from `theirs`.
+    #[tracing::instrument(level = "info", skip_all, fields(project = %self.project_id(), base = %base.revision(), theirs = %theirs.revision()))]
+    pub async fn merge(&mut self, base: &Snapshot, theirs: &Snapshot) -> Result<Merge> {
+        if base.state.document != self.current.state.document
+            || theirs.state.document != self.current.state.document
+        {
+            return Err(Error::invalid("merged snapshots belong to another project"));
+        }
+        let merged = merge::merge(&base.state, &self.current.state, &theirs.state)?;
+        let ours = self.current.storage.blobs();
+        let mut attachments = BTreeMap::new();
+        for blob in merged.operations.iter().flat_map(Operation::written_blobs) {
+            if !ours.contains(blob) && !attachments.contains_key(&blob) {
+                attachments.insert(blob, theirs.read_blob(blob).await?);
+            }
+        }
+        let mut conflicts = merged.conflicts;
+        for conflict in &mut conflicts {
+            let Conflict::Component(conflict) = conflict else {
+                continue;
+            };
+            let mut missing = Vec::new();
+            for blob in conflict
+                .theirs
+                .iter()
+                .flat_map(|value| value.blob_refs.iter())
+            {
+                if !ours.contains(*blob) {
+                    missing.push((*blob, theirs.read_blob(*blob).await?));
+                }
+            }
+            conflict.attachments = missing.into();
+        }
+        let patch = Patch::new(
+            &self.current,
+            merged.state,
+            Vec::new(),
+            merged.operations,
+            attachments.into_iter().collect(),
+            Some(format!("Merge revision {} of a branch", theirs.revision()).into()),
+        )?;
+        let commit = self.commit(patch).await?;
+        Ok(Merge { commit, conflicts })
+    }
+
+    /// Compresses blob files written before storage compressed blobs.
+    pub async fn compress_blobs(&self) -> Result<koharu_storage::CompressionReport> {
+        self.storage.compress_blobs().await.map_err(Into::into)
     }
 
     pub async fn collect_garbage(&self) -> Result<koharu_storage::GcReport> {
@@ -189,20 +551,19 @@ impl Session {
         Ok(Self {
             storage,
             current,
-            history: BTreeMap::new(),
+            undo: UndoHistory::new(DEFAULT_UNDO_DEPTH),
         })
     }
 
     async fn recover(storage: koharu_storage::Session) -> Result<Self> {
         let stored = storage.load().await?;
-        let checkpoint: StoredState = revision::from_slice(stored.payload())?;
-        let state = State::from_checkpoint(stored.document_id(), stored.revision(), checkpoint)?;
-        state.validate()?;
+        let state = decode_state(&stored)?;
         let current = Snapshot::new(Arc::new(state), stored)?;
+        let undo = recover_undo(&storage, current.revision()).await;
         Ok(Self {
             storage,
             current,
-            history: BTreeMap::new(),
+            undo,
         })
     }
 }
@@ -212,8 +573,61 @@ pub struct Commit {
     pub revision: crate::Revision,
     pub changes: Change,
     pub snapshot: Snapshot,
+    /// Whether the commit wrote a checkpoint rather than a log record.
+    pub checkpoint: bool,
 }
 
 fn encode_checkpoint(state: &State) -> Result<Vec<u8>> {
     revision::to_vec(&state.to_checkpoint()).map_err(Into::into)
 }
+
+/// Loads the undo history written for `revision`. A journal written for another
+/// revision describes a different timeline and is ignored.
+async fn recover_undo(storage: &koharu_storage::Session, revision: crate::Revision) -> UndoHistory {
+    let journal = match storage.load_journal().await {
+        Ok(Some(journal)) if journal.revision() == revision => journal,
+        Ok(Some(journal)) => {
+            tracing::warn!(journal = %journal.revision(), %revision, "ignoring undo history of another revision");
+            return UndoHistory::new(DEFAULT_UNDO_DEPTH);
+        }
+        Ok(None) => return UndoHistory::new(DEFAULT_UNDO_DEPTH),
+        Err(error) => {
+            tracing::warn!(%error, "ignoring unreadable undo history");
+            return UndoHistory::new(DEFAULT_UNDO_DEPTH);
+        }
+    };
+    UndoHistory::decode(&journal, DEFAULT_UNDO_DEPTH).unwrap_or_else(|error| {
+        tracing::warn!(%error, "ignoring undecodable undo history");
+        UndoHistory::new(DEFAULT_UNDO_DEPTH)
+    })
+}
+
+/// Decodes the checkpoint payload and replays the logged operations after it.
+fn decode_state(stored: &koharu_storage::State) -> Result<State> {
+    decode_state_with(stored, migration::MIGRATIONS)
+}
+
+/// Decodes a stored state, upgrading the components of its checkpoint and its
+/// logged operations with `migrations` before they are applied.
+pub(crate) fn decode_state_with(
+    stored: &koharu_storage::State,
+    migrations: &[Migration],
+) -> Result<State> {
+    let checkpoint: StoredState = revision::from_slice(stored.payload())?;
+    let mut state = State::from_checkpoint(
+        stored.document_id(),
+        stored.checkpoint_revision(),
+        checkpoint,
+        migrations,
+    )?;
+    for record in stored.log() {
+        let mut logged: LoggedPatch = revision::from_slice(record.payload())?;
+        for operation in &mut logged.operations {
+            migration::upgrade_operation(migrations, operation)?;
+            operation.apply(&mut state)?;
+        }
+        state.revision = record.revision();
+    }
+    state.validate()?;
+    Ok(state)
+}
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use crate::{
     Asset, AssetRole, BlobId, Edit, EntityId, Error, FunctionalRelation, Patch, ProjectId,
     RelationId, RelationKind, RelationSpec, Result,
-    component::{Component, ValidationContext, decode, key},
+    component::{Component, ComponentKey, ValidationContext, decode, key},
     components::Assets,
     state::State,
 };
@@ -75,7 +75,7 @@ impl Snapshot {
     }
 
     pub fn page(&self, id: EntityId) -> Result<PageRef<'_>> {
-        if self.state.pages.contains_key(&id) {
+        if id != EntityId::PROJECT && self.state.pages.contains_key(&id) {
             Ok(PageRef { snapshot: self, id })
         } else {
             Err(Error::EntityNotFound(id))
@@ -137,6 +137,27 @@ impl Snapshot {
         self.decode(self.state.relation_component(relation, &key)?)
     }
 
+    pub(crate) fn has_component(&self, entity: EntityId, key: &ComponentKey) -> Result<bool> {
+        Ok(self.state.component(entity, key)?.is_some())
+    }
+
+    pub(crate) fn component_origin(
+        &self,
+        entity: EntityId,
+        key: &ComponentKey,
+    ) -> Result<Option<crate::Origin>> {
+        let Some(raw) = self.state.component(entity, key)? else {
+            return Ok(None);
+        };
+        let record_exists = |id| self.state.contains_entity(id);
+        let blob_exists = |id| self.storage.blobs().contains(id);
+        crate::schema::component_origin(
+            &key.kind,
+            raw,
+            &ValidationContext::new(&record_exists, &blob_exists),
+        )
+    }
+
     fn decode<T: Component>(
         &self,
         raw: Option<&crate::component::ComponentRecord>,
@@ -224,6 +245,15 @@ impl Snapshot {
         Edit::new(self.clone(), Some(generation))
     }
 
+    /// Describes how this snapshot differs from `base`, such as what one
+    /// branch changed since the revision it was forked from.
+    pub fn changes_since(&self, base: &Self) -> Result<crate::Change> {
+        if base.state.document != self.state.document {
+            return Err(Error::invalid("snapshot belongs to another project"));
+        }
+        Ok(crate::Change::between(&base.state, &self.state))
+    }
+
     pub fn patch(&self, f: impl FnOnce(&mut Edit) -> Result<()>) -> Result<Patch> {
         let mut edit = self.edit();
         f(&mut edit)?;
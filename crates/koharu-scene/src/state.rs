@@ -9,6 +9,7 @@ use smallvec::SmallVec;
 use crate::{
     BlobId, EntityId, EntityOrigin, Error, Page, Relation, RelationId, Result,
     component::{ComponentKey, ComponentRecord, StoredComponent, ValidationContext},
+    migration::{self, Migration},
     schema,
 };
 
@@ -452,13 +453,18 @@ pub(crate) struct State {
 
 impl State {
     pub(crate) fn empty(document: koharu_storage::DocumentId) -> Self {
+        // The project arena is stored like a page but never enters the page order.
+        let project = EntityId::PROJECT;
         Self {
             document,
             revision: koharu_storage::Revision::ZERO,
             page_order: Arc::new(Vec::new()),
             page_order_epoch: 0,
-            pages: PersistentHashMap::new(),
-            entity_pages: PersistentHashMap::new(),
+            pages: PersistentHashMap::unit(
+                project,
+                Arc::new(PageState::new(project, SmallVec::new())),
+            ),
+            entity_pages: PersistentHashMap::unit(project, project),
             project_components: Arc::new(SmallVec::new()),
             relations: PersistentHashMap::new(),
             outgoing: PersistentHashMap::new(),
@@ -466,6 +472,13 @@ impl State {
         }
     }
 
+    /// The entities of the project arena in hierarchy order, without its root.
+    pub(crate) fn project_entities(&self) -> Vec<EntityId> {
+        let mut ids = self.pages[&EntityId::PROJECT].ordered_ids();
+        ids.remove(0);
+        ids
+    }
+
     pub(crate) fn contains_entity(&self, id: EntityId) -> bool {
         self.entity_pages.contains_key(&id)
     }
@@ -477,6 +490,13 @@ impl State {
             .ok_or(Error::EntityNotFound(id))
     }
 
+    /// Whether two pages sit next to each other in page order.
+    pub(crate) fn adjacent_pages(&self, a: EntityId, b: EntityId) -> bool {
+        self.page_order
+            .windows(2)
+            .any(|pair| pair == [a, b] || pair == [b, a])
+    }
+
     pub(crate) fn page(&self, id: EntityId) -> Result<&PageState> {
         self.pages
             .get(&id)
@@ -669,6 +689,9 @@ impl State {
 
     pub(crate) fn parent_and_position(&self, id: EntityId) -> Result<(Option<EntityId>, usize)> {
         let page_id = self.page_for(id)?;
+        if id == EntityId::PROJECT {
+            return Ok((None, 0));
+        }
         if id == page_id {
             let position = self
                 .page_order
@@ -823,6 +846,15 @@ impl State {
     }
 
     pub(crate) fn to_checkpoint(&self) -> StoredState {
+        let stored_entity = |page: &PageState, id| {
+            let entity = page.entity(id).expect("ordered entity exists");
+            StoredEntity {
+                id,
+                parent: entity.parent.map(|parent| page.entities[parent].id),
+                components: store_components(&entity.components),
+            }
+        };
+        let project = &self.pages[&EntityId::PROJECT];
         StoredState {
             page_order_epoch: self.page_order_epoch,
             project_components: store_components(&self.project_components),
@@ -837,18 +869,17 @@ impl State {
                         entities: page
                             .ordered_ids()
                             .into_iter()
-                            .map(|id| {
-                                let entity = page.entity(id).expect("ordered entity exists");
-                                StoredEntity {
-                                    id,
-                                    parent: entity.parent.map(|parent| page.entities[parent].id),
-                                    components: store_components(&entity.components),
-                                }
-                            })
+                            .map(|id| stored_entity(page, id))
                             .collect(),
                     }
                 })
                 .collect(),
+            project_epoch: project.epoch,
+            project_entities: self
+                .project_entities()
+                .into_iter()
+                .map(|id| stored_entity(project, id))
+                .collect(),
             relations: self
                 .relations
                 .iter()
@@ -864,8 +895,10 @@ impl State {
     pub(crate) fn from_checkpoint(
         document: koharu_storage::DocumentId,
         revision: koharu_storage::Revision,
-        stored: StoredState,
+        mut stored: StoredState,
+        migrations: &[Migration],
     ) -> Result<Self> {
+        migration::upgrade_state(migrations, &mut stored)?;
         let mut state = Self::empty(document);
         state.revision = revision;
         let page_order_epoch = stored.page_order_epoch;
@@ -899,6 +932,23 @@ impl State {
             state.page_mut(page.id)?.epoch = page.epoch;
         }
         state.page_order_epoch = page_order_epoch;
+        for entity in stored.project_entities {
+            let parent = entity
+                .parent
+                .ok_or_else(|| Error::invalid("stored project entity has no parent"))?;
+            let position = state.entity(parent)?.children.len();
+            if state.page_for(parent)? != EntityId::PROJECT {
+                return Err(Error::invalid("stored project entity is owned by a page"));
+            }
+            state.insert_entity(
+                EntityId::PROJECT,
+                entity.id,
+                parent,
+                position,
+                load_components(entity.components)?,
+            )?;
+        }
+        state.page_mut(EntityId::PROJECT)?.epoch = stored.project_epoch;
         for relation in stored.relations {
             state.insert_relation(relation.id, relation.value)?;
             Arc::make_mut(
@@ -946,6 +996,24 @@ impl State {
             }
             validate_depth(page)?;
         }
+        for id in self.project_entities() {
+            let entity = self.entity(id)?;
+            if entity
+                .component(&crate::component::key::<EntityOrigin>()?)
+                .is_none()
+            {
+                return Err(Error::invalid("entity origin is missing"));
+            }
+            if entity
+                .component(&crate::component::key::<Page>()?)
+                .is_some()
+            {
+                return Err(Error::invalid("project entity carries a page marker"));
+            }
+            schema::validate_components(&entity.components, &context)?;
+            schema::validate_entity(self, id)?;
+        }
+        validate_depth(self.page(EntityId::PROJECT)?)?;
         for relation in self.relations.values() {
             schema::validate_relation(self, &relation.value, &context)?;
             schema::validate_components(&relation.components, &context)?;
@@ -997,13 +1065,18 @@ pub(crate) struct StoredRelation {
     pub(crate) components: Vec<StoredComponentEntry>,
 }
 
-#[revisioned(revision = 1)]
+#[revisioned(revision = 2)]
 #[derive(Clone, Debug)]
 pub(crate) struct StoredState {
     pub(crate) page_order_epoch: u64,
     pub(crate) project_components: Vec<StoredComponentEntry>,
     pub(crate) pages: Vec<StoredPage>,
     pub(crate) relations: Vec<StoredRelation>,
+    #[revision(start = 2)]
+    pub(crate) project_epoch: u64,
+    /// Entities of the project arena in hierarchy order, without its root.
+    #[revision(start = 2)]
+    pub(crate) project_entities: Vec<StoredEntity>,
 }
 
 pub(crate) fn store_components(components: &Components) -> Vec<StoredComponentEntry> {
@@ -102,7 +102,7 @@ async fn components_on_new_entities_do_not_observe_missing_base_state() {
 }
 
 #[tokio::test]
-async fn built_in_component_schemas_remain_revision_one() {
+async fn built_in_component_schemas_keep_their_revisions() {
     fn schema<T: Component>() -> u16 {
         <T as revision::Revisioned>::revision()
     }
@@ -116,7 +116,6 @@ async fn built_in_component_schemas_remain_revision_one() {
             schema::<Visibility>(),
             schema::<SourceText>(),
             schema::<TextLayout>(),
-            schema::<Translation>(),
             schema::<TextRole>(),
             schema::<OcrAnalysis>(),
             schema::<Group>(),
@@ -128,9 +127,22 @@ async fn built_in_component_schemas_remain_revision_one() {
             schema::<TextContent>(),
             schema::<crate::components::Assets>(),
             schema::<Relation>(),
+            schema::<TypographyOverrides>(),
+            schema::<Edition>(),
+            schema::<TranslationCandidates>(),
+            schema::<Annotation>(),
+            schema::<Character>(),
+            schema::<TextStyle>(),
+            schema::<Volume>(),
+            schema::<Chapter>(),
+            schema::<Reading>(),
+            schema::<ShapeLayer>(),
+            schema::<Blending>(),
+            schema::<GlossaryTerm>(),
         ],
-        [1; 19]
+        [1; 30]
     );
+    assert_eq!(schema::<crate::components::Translations>(), 2);
 }
 
 #[tokio::test]
@@ -147,7 +159,7 @@ async fn built_in_component_kinds_express_domain_ownership() {
             TextLayout::KIND,
             TextContent::KIND,
             SourceText::KIND,
-            Translation::KIND,
+            crate::components::Translations::KIND,
             TextRole::KIND,
             Group::KIND,
             TextGroup::KIND,
@@ -156,6 +168,18 @@ async fn built_in_component_kinds_express_domain_ownership() {
             DetectionAnalysis::KIND,
             OcrAnalysis::KIND,
             crate::components::Assets::KIND,
+            TypographyOverrides::KIND,
+            Edition::KIND,
+            TranslationCandidates::KIND,
+            Annotation::KIND,
+            Character::KIND,
+            TextStyle::KIND,
+            Volume::KIND,
+            Chapter::KIND,
+            Reading::KIND,
+            ShapeLayer::KIND,
+            Blending::KIND,
+            GlossaryTerm::KIND,
         ],
         [
             "dev.koharu.project",
@@ -177,6 +201,18 @@ async fn built_in_component_kinds_express_domain_ownership() {
             "dev.koharu.analysis.detection",
             "dev.koharu.analysis.ocr",
             "dev.koharu.assets",
+            "dev.koharu.text.typography.overrides",
+            "dev.koharu.project.edition",
+            "dev.koharu.text.candidates",
+            "dev.koharu.annotation",
+            "dev.koharu.character",
+            "dev.koharu.text.style",
+            "dev.koharu.volume",
+            "dev.koharu.chapter",
+            "dev.koharu.project.reading",
+            "dev.koharu.layer.shape",
+            "dev.koharu.layer.blending",
+            "dev.koharu.glossary.term",
         ]
     );
 }
@@ -241,6 +277,555 @@ async fn revision_one_project_components_upgrade_when_their_schema_evolves() {
     );
 }
 
+const NOTE_KIND: &str = "dev.koharu.test.note";
+
+#[revisioned(revision = 1)]
+#[derive(Clone)]
+struct NoteV1 {
+    text: String,
+}
+
+impl Component for NoteV1 {
+    const KIND: &'static str = NOTE_KIND;
+}
+
+// Revision two restructures the payload, which revision attributes cannot
+// express, so reading revision one requires a registered migration.
+#[revisioned(revision = 2)]
+#[derive(Clone, Debug, PartialEq)]
+struct NoteV2 {
+    lines: Vec<String>,
+}
+
+impl Component for NoteV2 {
+    const KIND: &'static str = NOTE_KIND;
+}
+
+fn split_note(payload: &[u8]) -> Result<Vec<u8>> {
+    let note: NoteV1 = revision::from_slice(payload)?;
+    Ok(revision::to_vec(&NoteV2 {
+        lines: note.text.lines().map(str::to_owned).collect(),
+    })?)
+}
+
+#[tokio::test]
+async fn registered_migrations_upgrade_checkpoints_and_logged_operations() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("migrating.khrproj");
+    let mut entities = None;
+    {
+        let mut session = Session::create(&path).await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(page(), At::End)?;
+                let first = edit.add_entity(page, At::End)?;
+                edit.set(
+                    first,
+                    &NoteV1 {
+                        text: "one\ntwo".to_owned(),
+                    },
+                )?;
+                let second = edit.add_entity(page, At::End)?;
+                edit.set(
+                    second,
+                    &NoteV1 {
+                        text: "draft".to_owned(),
+                    },
+                )?;
+                entities = Some((first, second));
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        session.compact().await.unwrap();
+        let (_, second) = entities.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                edit.set(
+                    second,
+                    &NoteV1 {
+                        text: "three\nfour".to_owned(),
+                    },
+                )
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+    }
+    let (first, second) = entities.unwrap();
+
+    let storage = koharu_storage::Session::open(&path).await.unwrap();
+    let stored = storage.load().await.unwrap();
+    assert_eq!(stored.log().len(), 1);
+    let unmigrated = crate::session::decode_state_with(&stored, &[]).unwrap();
+    let unmigrated = Snapshot::new(Arc::new(unmigrated), stored.clone()).unwrap();
+    assert!(!matches!(
+        unmigrated.component::<NoteV2>(second),
+        Ok(Some(NoteV2 { ref lines })) if lines.len() == 2
+    ));
+    let migrations = [crate::migration::Migration {
+        kind: NOTE_KIND,
+        from: 1,
+        upgrade: split_note,
+    }];
+    let state = crate::session::decode_state_with(&stored, &migrations).unwrap();
+    let snapshot = Snapshot::new(Arc::new(state), stored).unwrap();
+    assert_eq!(
+        snapshot.component::<NoteV2>(first).unwrap(),
+        Some(NoteV2 {
+            lines: vec!["one".to_owned(), "two".to_owned()],
+        })
+    );
+    assert_eq!(
+        snapshot.component::<NoteV2>(second).unwrap(),
+        Some(NoteV2 {
+            lines: vec!["three".to_owned(), "four".to_owned()],
+        })
+    );
+}
+
+/// A typography payload written by a release whose schema is one revision
+/// ahead of this one.
+#[revisioned(revision = 2)]
+#[derive(Clone, Debug, PartialEq)]
+struct FutureTypography {
+    variable_axes: Vec<String>,
+}
+
+impl Component for FutureTypography {
+    const KIND: &'static str = Typography::KIND;
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, PartialEq)]
+struct FutureExtension {
+    value: u64,
+}
+
+impl Component for FutureExtension {
+    const KIND: &'static str = "dev.koharu.future.extension";
+}
+
+#[tokio::test]
+async fn components_from_newer_releases_are_preserved_opaquely() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("future.khrproj");
+    let mut layer = None;
+    let future = FutureTypography {
+        variable_axes: vec!["wght".to_owned()],
+    };
+    {
+        let mut session = Session::create(&path).await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(page(), At::End)?;
+                let content = edit.add_text_content(page, At::End)?;
+                let id = edit.add_text_layer(
+                    page,
+                    At::End,
+                    content,
+                    &TextLayout {
+                        origin: Origin::User,
+                        kind: TextLayoutKind::Paragraph,
+                    },
+                )?;
+                edit.set(id, &future)?;
+                edit.set(id, &FutureExtension { value: 7 })?;
+                layer = Some(id);
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        session.compact().await.unwrap();
+    }
+    let layer = layer.unwrap();
+
+    let mut session = Session::open(&path).await.unwrap();
+    let snapshot = session.snapshot();
+    assert!(matches!(
+        snapshot.component::<Typography>(layer),
+        Err(Error::UnsupportedComponent { schema: 2, .. })
+    ));
+    assert_eq!(
+        snapshot.component::<FutureTypography>(layer).unwrap(),
+        Some(future.clone())
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set(layer, &TypographyOverride::default().apply(None)))
+            .is_err()
+    );
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.set(
+                layer,
+                &Visibility {
+                    origin: Origin::User,
+                    visible: false,
+                    opacity: 1.0,
+                },
+            )
+        })
+        .unwrap();
+    session.commit(patch).await.unwrap();
+    session.compact().await.unwrap();
+    drop((snapshot, session));
+    let reopened = Session::open(&path).await.unwrap().snapshot();
+    assert_eq!(
+        reopened.component::<FutureTypography>(layer).unwrap(),
+        Some(future)
+    );
+    assert_eq!(
+        reopened.component::<FutureExtension>(layer).unwrap(),
+        Some(FutureExtension { value: 7 })
+    );
+}
+
+const PROJECT_FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/projects");
+
+fn recorded_project_fixtures() -> Vec<std::path::PathBuf> {
+    let mut fixtures = std::fs::read_dir(PROJECT_FIXTURES)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "khrproj")
+        })
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    fixtures
+}
+
+fn copy_directory(from: &std::path::Path, to: &std::path::Path) {
+    std::fs::create_dir_all(to).unwrap();
+    for entry in std::fs::read_dir(from).unwrap() {
+        let entry = entry.unwrap();
+        let target = to.join(entry.file_name());
+        if entry.file_type().unwrap().is_dir() {
+            copy_directory(&entry.path(), &target);
+        } else {
+            std::fs::copy(entry.path(), target).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn recorded_project_fixtures_keep_opening() {
+    let directory = tempfile::tempdir().unwrap();
+    let fixtures = recorded_project_fixtures();
+    assert!(!fixtures.is_empty());
+    for fixture in &fixtures {
+        // Opening writes history and truncates the log, so each run opens a
+        // copy and the recorded files stay as their version wrote them.
+        let path = directory.path().join(fixture.file_name().unwrap());
+        copy_directory(fixture, &path);
+        let session = Session::open(&path)
+            .await
+            .unwrap_or_else(|error| panic!("{} no longer opens: {error}", fixture.display()));
+        assert!(
+            session.verify().await.unwrap().is_healthy(),
+            "{}",
+            fixture.display()
+        );
+        let snapshot = session.snapshot();
+        assert_ne!(snapshot.pages().len(), 0, "{}", fixture.display());
+        let edition = snapshot.edition_locale().unwrap();
+        snapshot.reading_direction().unwrap();
+        for page in snapshot.pages() {
+            page.chapter().unwrap();
+        }
+        for layer in snapshot.text_layers().unwrap() {
+            let content = layer.content().unwrap();
+            assert!(content.translation().unwrap().is_some());
+            for translation in content.translations().unwrap() {
+                content.spans(&translation).unwrap();
+            }
+            content.candidates().unwrap();
+            layer.typography_for(edition.as_ref()).unwrap();
+            layer.style().unwrap();
+            layer.frame().unwrap();
+        }
+        for annotation in snapshot.annotations().unwrap() {
+            annotation.annotation().unwrap();
+            assert!(annotation.anchor().unwrap().is_some());
+        }
+        for character in snapshot.characters().unwrap() {
+            character.character().unwrap();
+            for line in character.lines() {
+                line.source().unwrap();
+            }
+        }
+        for style in snapshot.text_styles().unwrap() {
+            style.typography().unwrap();
+        }
+        for volume in snapshot.volumes().unwrap() {
+            volume.volume().unwrap();
+        }
+        for chapter in snapshot.chapters().unwrap() {
+            chapter.chapter().unwrap();
+        }
+        for term in snapshot.glossary().unwrap() {
+            term.term().unwrap();
+        }
+    }
+}
+
+/// Records the current project format as a new fixture next to every earlier
+/// one. Run it after each change to a built-in component schema:
+/// `cargo test -p koharu-scene -- --ignored record_project_fixture`. The
+/// last commits stay in the log, so fixtures also keep logged operations
+/// replaying.
+#[tokio::test]
+#[ignore = "writes a new fixture into the project corpus"]
+async fn record_project_fixture() {
+    let sequence = recorded_project_fixtures()
+        .iter()
+        .filter_map(|path| {
+            path.file_name()?
+                .to_str()?
+                .split_once('-')?
+                .0
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+        .map_or(1, |sequence| sequence + 1);
+    let path = std::path::Path::new(PROJECT_FIXTURES).join(format!(
+        "{sequence:02}-v{}.khrproj",
+        env!("CARGO_PKG_VERSION")
+    ));
+    let mut session = Session::create(&path).await.unwrap();
+    let spanish = LanguageTag::new("es").unwrap();
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            edit.set_project(&Project {
+                source_locale: Some(LanguageTag::new("ja")?),
+                target_locales: vec![LanguageTag::new("en")?, spanish.clone()],
+            })?;
+            edit.set_project(&Edition {
+                locale: spanish.clone(),
+            })?;
+            edit.set_project(&Reading {
+                direction: ReadingDirection::RightToLeft,
+            })?;
+            let volume = edit.add_volume(&Volume::new("Volume 1", Some(1.0)), At::End)?;
+            let chapter =
+                edit.add_chapter(Some(volume), &Chapter::new("Arrival", Some(1.0)), At::End)?;
+            let page = edit.add_page(page(), At::End)?;
+            let facing = edit.add_page(PageDraft::new("facing", 1200.0, 1800.0), At::End)?;
+            edit.set_chapter(page, Some(chapter))?;
+            edit.set_chapter(facing, Some(chapter))?;
+            edit.set_spread(page, Some(facing))?;
+            edit.set_asset(
+                page,
+                &AssetRole::new("source")?,
+                AssetInput::new(
+                    Arc::<[u8]>::from(&b"page artwork"[..]),
+                    "image/test",
+                    AssetMetadata {
+                        width: Some(1200),
+                        height: Some(1800),
+                        attributes: BTreeMap::new(),
+                    },
+                ),
+            )?;
+            let paint = edit.add_entity(page, At::End)?;
+            edit.set(
+                paint,
+                &RasterLayer {
+                    origin: Origin::User,
+                    name: "Paint 1".to_owned(),
+                    kind: RasterLayerKind::Paint,
+                },
+            )?;
+            edit.set(
+                paint,
+                &Blending {
+                    origin: Origin::User,
+                    mode: BlendMode::Multiply,
+                    clipped: false,
+                },
+            )?;
+            edit.add_shape_layer(
+                page,
+                At::End,
+                &ShapeLayer {
+                    origin: Origin::User,
+                    name: "Balloon 1".to_owned(),
+                    outline: ShapeOutline::Balloon {
+                        tail: Point { x: 0.2, y: 1.4 },
+                    },
+                    fill: Some([255, 255, 255, 255]),
+                    stroke: Some(ShapeStroke {
+                        color: [0, 0, 0, 255],
+                        width: 3.0,
+                    }),
+                },
+                &Geometry::rectangle(90.0, 90.0, 420.0, 320.0),
+            )?;
+            let bubble = edit.add_analysis_region::<BubbleRegion>(
+                page,
+                At::End,
+                &Geometry::rectangle(100.0, 100.0, 400.0, 300.0),
+                None,
+            )?;
+            let region = edit.add_analysis_region::<TextRegion>(
+                page,
+                At::End,
+                &Geometry::rectangle(150.0, 150.0, 200.0, 100.0),
+                Some("dialogue".to_owned()),
+            )?;
+            edit.set(
+                region,
+                &DetectionAnalysis {
+                    origin: Origin::User,
+                    labels: vec![DetectionLabel {
+                        kind: TextRegion::kind(),
+                        confidence: 0.9,
+                    }],
+                },
+            )?;
+            edit.set(
+                region,
+                &OcrAnalysis {
+                    origin: Origin::User,
+                    direction: TextDirection::Vertical,
+                    confidence: Some(0.8),
+                    line_boundaries: Vec::new(),
+                },
+            )?;
+            edit.relate::<Inside>(region, bubble)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("こんにちは"))?;
+            edit.set(
+                content,
+                &TextRole {
+                    origin: Origin::User,
+                    role: "dev.koharu.role.dialogue".to_owned(),
+                },
+            )?;
+            edit.set_translation(
+                content,
+                &Translation {
+                    text: Authored::user("hello".to_owned()),
+                    language: None,
+                },
+            )?;
+            edit.set_translation_spans(
+                content,
+                None,
+                vec![TextSpan {
+                    start: 0,
+                    end: 5,
+                    style: SpanStyle {
+                        font_weight: Some(700),
+                        ..SpanStyle::default()
+                    },
+                }],
+            )?;
+            edit.relate::<RecognizedFrom>(content, region)?;
+            let mut speaker = Character::new("Hana");
+            speaker.aliases.push("Hana-chan".to_owned());
+            speaker.speech_notes = "cheerful, drops honorifics".to_owned();
+            speaker.typography = Some(TypographyOverride {
+                color: Some([200, 40, 40, 255]),
+                ..TypographyOverride::default()
+            });
+            let speaker = edit.add_character(&speaker)?;
+            edit.set_speaker(content, Some(speaker))?;
+            edit.add_glossary_term(&GlossaryTerm::new("ハナ", "Hana"))?;
+            let style = edit.add_text_style(
+                &TextStyle::new(
+                    "Dialogue",
+                    TypographyOverride {
+                        size: Some(20.0),
+                        ..TypographyOverride::default()
+                    },
+                ),
+                None,
+            )?;
+            let layer = edit.add_text_layer(
+                page,
+                At::End,
+                content,
+                &TextLayout {
+                    origin: Origin::User,
+                    kind: TextLayoutKind::Paragraph,
+                },
+            )?;
+            edit.relate::<FitsTo>(layer, region)?;
+            edit.set_text_style(layer, Some(style))?;
+            edit.set(
+                layer,
+                &Typography {
+                    preferred_font: Some("Comic Neue".to_owned()),
+                    ..TypographyOverride::default().apply(None)
+                },
+            )?;
+            edit.set(
+                layer,
+                &TypographyOverrides {
+                    origin: Origin::User,
+                    languages: BTreeMap::from([(
+                        spanish.clone(),
+                        TypographyOverride {
+                            size: Some(18.0),
+                            ..TypographyOverride::default()
+                        },
+                    )]),
+                },
+            )?;
+            edit.set(
+                layer,
+                &Visibility {
+                    origin: Origin::User,
+                    visible: true,
+                    opacity: 0.9,
+                },
+            )?;
+            let mut note = Annotation::new("Reviewer", "check this pun with the editor");
+            note.mark = Some(AnnotationMark::Point(Point { x: 160.0, y: 160.0 }));
+            note.replies.push(AnnotationReply {
+                author: "Editor".to_owned(),
+                body: "keep it".to_owned(),
+            });
+            edit.add_annotation(layer, &note)?;
+            ids = Some(content);
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let content = ids.unwrap();
+    let producer = ProducerId::new("dev.koharu.pipeline.translation").unwrap();
+    let mut edit = snapshot.edit_as(Generation::new(producer));
+    edit.add_translation_candidate(content, &spanish, "hola".to_owned(), Some(0.7))
+        .unwrap();
+    let snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+    let patch = snapshot
+        .patch(|edit| {
+            edit.review_translation_candidate(
+                content,
+                &spanish,
+                0,
+                ReviewState::Approved,
+                String::new(),
+            )
+        })
+        .unwrap();
+    session.commit(patch).await.unwrap();
+    drop((snapshot, session));
+    std::fs::remove_file(path.join("project.lock")).unwrap();
+}
+
 #[tokio::test]
 async fn stale_disjoint_patches_can_rebase_without_hiding_conflicts() {
     let mut session = Session::memory().await.unwrap();
@@ -929,7 +1514,7 @@ async fn translation_requires_source_text() {
     let result = session.snapshot().patch(|edit| {
         let page = edit.add_page(page(), At::End)?;
         let entity = edit.add_entity(page, At::End)?;
-        edit.set(
+        edit.set_translation(
             entity,
             &Translation {
                 text: Authored::user("hello".to_owned()),
@@ -947,7 +1532,7 @@ async fn text_components_have_one_value_per_entity() {
         let page = edit.add_page(page(), At::End)?;
         let entity = edit.add_text_content(page, At::End)?;
         edit.set(entity, &source("source"))?;
-        edit.set(
+        edit.set_translation(
             entity,
             &Translation {
                 text: Authored::user("translation".to_owned()),
@@ -959,11 +1544,1350 @@ async fn text_components_have_one_value_per_entity() {
 }
 
 #[tokio::test]
-async fn independent_pipeline_components_rebase() {
+async fn text_content_keeps_one_translation_per_edition() {
     let mut session = Session::memory().await.unwrap();
-    let mut entities = None;
-    let create = session
-        .snapshot()
+    let english = LanguageTag::new("en").unwrap();
+    let spanish = LanguageTag::new("es").unwrap();
+    let translation = |text: &str, language: Option<&LanguageTag>| Translation {
+        text: Authored::user(text.to_owned()),
+        language: language.cloned(),
+    };
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("source"))?;
+            edit.set_translation(content, &translation("hello", Some(&english)))?;
+            edit.set_translation(content, &translation("hola", Some(&spanish)))?;
+            let layer = edit.add_text_layer(
+                page,
+                At::End,
+                content,
+                &TextLayout {
+                    origin: Origin::User,
+                    kind: TextLayoutKind::Paragraph,
+                },
+            )?;
+            edit.set(
+                layer,
+                &TypographyOverrides {
+                    origin: Origin::User,
+                    languages: BTreeMap::from([(
+                        spanish.clone(),
+                        TypographyOverride {
+                            size: Some(18.0),
+                            auto_fit: Some(false),
+                            ..TypographyOverride::default()
+                        },
+                    )]),
+                },
+            )?;
+            ids = Some((content, layer));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (content, layer) = ids.unwrap();
+    let text = snapshot.text_content(content).unwrap();
+    assert_eq!(
+        text.translations().unwrap(),
+        [
+            translation("hello", Some(&english)),
+            translation("hola", Some(&spanish))
+        ]
+    );
+    assert_eq!(
+        text.translation().unwrap(),
+        Some(translation("hello", Some(&english)))
+    );
+    assert_eq!(
+        snapshot
+            .text_layer(layer)
+            .unwrap()
+            .typography_for(Some(&spanish))
+            .unwrap()
+            .and_then(|typography| typography.size),
+        Some(18.0)
+    );
+    assert_eq!(
+        snapshot
+            .text_layer(layer)
+            .unwrap()
+            .typography_for(Some(&english))
+            .unwrap(),
+        None
+    );
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.set_project(&Edition {
+                locale: spanish.clone(),
+            })?;
+            edit.set_translation(content, &translation("fallback", None))
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let text = snapshot.text_content(content).unwrap();
+    assert_eq!(snapshot.edition_locale().unwrap(), Some(spanish.clone()));
+    assert_eq!(
+        text.translation().unwrap(),
+        Some(translation("hola", Some(&spanish)))
+    );
+    assert_eq!(
+        text.translation_for(Some(&LanguageTag::new("fr").unwrap()))
+            .unwrap(),
+        Some(translation("fallback", None))
+    );
+    // Setting the fallback leaves the English translation alone.
+    assert_eq!(
+        snapshot.translation(content, Some(&english)).unwrap(),
+        Some(translation("hello", Some(&english)))
+    );
+
+    let generated = Generation::new(ProducerId::new("dev.koharu.test.translator").unwrap());
+    let mut edit = snapshot.edit_as(generated.clone());
+    assert!(matches!(
+        edit.set_translation(content, &translation("generated", Some(&spanish))),
+        Err(Error::Authorship(_))
+    ));
+    // A producer's own provenance, such as its confidence, is kept.
+    let french = LanguageTag::new("fr").unwrap();
+    let origin = Origin::Generated(Generation {
+        confidence: Some(0.7),
+        ..generated.clone()
+    });
+    let mut edit = snapshot.edit_as(generated);
+    edit.set_translation(
+        content,
+        &Translation {
+            text: Authored {
+                value: "bonjour".to_owned(),
+                origin: origin.clone(),
+            },
+            language: Some(french.clone()),
+        },
+    )
+    .unwrap();
+    let snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+    assert_eq!(snapshot.translations(content).unwrap().len(), 4);
+    assert_eq!(
+        snapshot
+            .translation(content, Some(&french))
+            .unwrap()
+            .map(|translation| translation.text.origin),
+        Some(origin)
+    );
+
+    let patch = snapshot
+        .patch(|edit| edit.remove_translation(content, Some(&spanish)))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot
+            .text_content(content)
+            .unwrap()
+            .translation()
+            .unwrap(),
+        Some(translation("fallback", None))
+    );
+}
+
+#[tokio::test]
+async fn translation_spans_style_ranges_until_the_text_changes() {
+    let mut session = Session::memory().await.unwrap();
+    let english = LanguageTag::new("en").unwrap();
+    let translation = |text: &str, language: Option<&LanguageTag>| Translation {
+        text: Authored::user(text.to_owned()),
+        language: language.cloned(),
+    };
+    let bold = TextSpan {
+        start: 4,
+        end: 8,
+        style: SpanStyle {
+            font_weight: Some(700),
+            size_scale: Some(1.5),
+            ..SpanStyle::default()
+        },
+    };
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("source"))?;
+            edit.set_translation(content, &translation("Run away!", None))?;
+            edit.set_translation(content, &translation("¡Corre!", Some(&english)))?;
+            ids = Some(content);
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let content = ids.unwrap();
+
+    let mut edit = snapshot.edit();
+    // "¡" is two bytes, so one byte into it is not a character boundary.
+    assert!(
+        edit.set_translation_spans(
+            content,
+            Some(&english),
+            vec![TextSpan {
+                start: 1,
+                end: 3,
+                style: SpanStyle::default(),
+            }],
+        )
+        .is_err()
+    );
+    assert!(
+        edit.set_translation_spans(
+            content,
+            None,
+            vec![
+                bold.clone(),
+                TextSpan {
+                    start: 6,
+                    end: 9,
+                    style: SpanStyle::default(),
+                },
+            ],
+        )
+        .is_err()
+    );
+    assert!(
+        edit.set_translation_spans(
+            content,
+            Some(&LanguageTag::new("fr").unwrap()),
+            vec![bold.clone()]
+        )
+        .is_err()
+    );
+    let patch = snapshot
+        .patch(|edit| edit.set_translation_spans(content, None, vec![bold.clone()]))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let text = snapshot.text_content(content).unwrap();
+    let fallback = text.translation_for(None).unwrap().unwrap();
+    assert_eq!(text.spans(&fallback).unwrap(), std::slice::from_ref(&bold));
+    let spanish = text.translation_for(Some(&english)).unwrap().unwrap();
+    assert!(text.spans(&spanish).unwrap().is_empty());
+
+    // Keeping the text keeps its spans; rewriting it discards them.
+    let patch = snapshot
+        .patch(|edit| {
+            edit.set_translation(content, &translation("Run away!", None))?;
+            edit.set_translation(content, &translation("¡Huye!", Some(&english)))
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot.translation_spans(content, None).unwrap(),
+        std::slice::from_ref(&bold)
+    );
+    let patch = snapshot
+        .patch(|edit| edit.set_translation(content, &translation("Go!", None)))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert!(
+        snapshot
+            .translation_spans(content, None)
+            .unwrap()
+            .is_empty()
+    );
+    assert!(
+        snapshot
+            .entity(content)
+            .unwrap()
+            .component::<crate::components::Translations>()
+            .unwrap()
+            .unwrap()
+            .languages[&None]
+            .spans
+            .is_empty()
+    );
+
+    let snapshot = session.undo_last().await.unwrap().snapshot;
+    assert_eq!(snapshot.translation_spans(content, None).unwrap(), [bold]);
+}
+
+#[tokio::test]
+async fn generation_edits_hand_translations_between_producers() {
+    let mut session = Session::memory().await.unwrap();
+    let spanish = LanguageTag::new("es").unwrap();
+    let mut content = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let entity = edit.add_text_content(page, At::End)?;
+            edit.set(entity, &source("source"))?;
+            content = Some(entity);
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let content = content.unwrap();
+    let translator = Generation::new(ProducerId::new("dev.koharu.pipeline.translation").unwrap());
+    let memory =
+        Generation::new(ProducerId::new("dev.koharu.pipeline.translation-memory").unwrap());
+    let translation = |text: &str, generation: &Generation| Translation {
+        text: Authored::generated(text.to_owned(), generation.clone()),
+        language: Some(spanish.clone()),
+    };
+    let mut edit = snapshot.edit_as(translator.clone());
+    edit.set_translation(content, &translation("uno", &translator))
+        .unwrap();
+    let snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+
+    let mut edit = snapshot.edit_as(translator.clone());
+    assert!(matches!(
+        edit.with_generation(memory.clone(), |edit| {
+            edit.set_translation(content, &translation("una", &memory))
+        }),
+        Err(Error::Authorship(_))
+    ));
+    let mut edit = snapshot.edit_as(translator.clone());
+    edit.remove_translation(content, Some(&spanish)).unwrap();
+    edit.with_generation(memory.clone(), |edit| {
+        edit.add_translation_candidate(content, &spanish, "una".to_owned(), Some(1.0))?;
+        edit.set_translation(content, &translation("una", &memory))
+    })
+    .unwrap();
+    let snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+    assert_eq!(
+        snapshot.translation(content, Some(&spanish)).unwrap(),
+        Some(translation("una", &memory))
+    );
+    let candidates = snapshot
+        .component::<TranslationCandidates>(content)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        candidates.languages[&spanish][0].generation.producer,
+        memory.producer
+    );
+
+    let mut edit = snapshot.edit();
+    assert!(matches!(
+        edit.with_generation(memory, |_| Ok(())),
+        Err(Error::Authorship(_))
+    ));
+}
+
+#[tokio::test]
+async fn translation_candidates_keep_the_approved_choice() {
+    let mut session = Session::memory().await.unwrap();
+    let spanish = LanguageTag::new("es").unwrap();
+    let mut content = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let entity = edit.add_text_content(page, At::End)?;
+            edit.set(entity, &source("source"))?;
+            content = Some(entity);
+            Ok(())
+        })
+        .unwrap();
+    let mut snapshot = session.commit(patch).await.unwrap().snapshot;
+    let content = content.unwrap();
+    let producer = ProducerId::new("dev.koharu.pipeline.translation").unwrap();
+    let provider = |model: &str| Generation {
+        model: Some(model.to_owned()),
+        ..Generation::new(producer.clone())
+    };
+    for (model, text, index) in [("a", "uno", 0), ("b", "dos", 1), ("a", "otra", 0)] {
+        let mut edit = snapshot.edit_as(provider(model));
+        assert_eq!(
+            edit.add_translation_candidate(content, &spanish, text.to_owned(), Some(0.5))
+                .unwrap(),
+            index
+        );
+        snapshot = session
+            .commit(edit.finish().unwrap())
+            .await
+            .unwrap()
+            .snapshot;
+    }
+    let candidates = snapshot
+        .text_content(content)
+        .unwrap()
+        .candidates()
+        .unwrap()
+        .unwrap();
+    let texts = candidates.languages[&spanish]
+        .iter()
+        .map(|candidate| candidate.text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["otra", "dos"]);
+    assert_eq!(
+        candidates.languages[&spanish][0].generation.confidence,
+        Some(0.5)
+    );
+
+    let mut edit = snapshot.edit_as(provider("b"));
+    assert!(matches!(
+        edit.review_translation_candidate(
+            content,
+            &spanish,
+            1,
+            ReviewState::Approved,
+            String::new()
+        ),
+        Err(Error::Authorship(_))
+    ));
+    let patch = snapshot
+        .patch(|edit| {
+            edit.review_translation_candidate(
+                content,
+                &spanish,
+                1,
+                ReviewState::Approved,
+                "reads naturally".to_owned(),
+            )
+        })
+        .unwrap();
+    snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot.translation(content, Some(&spanish)).unwrap(),
+        Some(Translation {
+            text: Authored::user("dos".to_owned()),
+            language: Some(spanish.clone()),
+        })
+    );
+
+    // A producer never replaces the approved candidate.
+    let mut edit = snapshot.edit_as(provider("b"));
+    assert_eq!(
+        edit.add_translation_candidate(content, &spanish, "tres".to_owned(), None)
+            .unwrap(),
+        2
+    );
+    snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+    let candidates = snapshot
+        .component::<TranslationCandidates>(content)
+        .unwrap()
+        .unwrap();
+    let approved = candidates.approved(&spanish).unwrap();
+    assert_eq!(approved.text, "dos");
+    assert_eq!(approved.notes, "reads naturally");
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.review_translation_candidate(
+                content,
+                &spanish,
+                0,
+                ReviewState::Approved,
+                String::new(),
+            )
+        })
+        .unwrap();
+    snapshot = session.commit(patch).await.unwrap().snapshot;
+    let candidates = snapshot
+        .component::<TranslationCandidates>(content)
+        .unwrap()
+        .unwrap();
+    assert_eq!(candidates.approved(&spanish).unwrap().text, "otra");
+    assert_eq!(
+        candidates.languages[&spanish][1].review,
+        ReviewState::Unreviewed
+    );
+}
+
+#[tokio::test]
+async fn queries_select_entities_by_components_provenance_and_relations() {
+    let mut session = Session::memory().await.unwrap();
+    let translator = ProducerId::new("dev.koharu.pipeline.translation").unwrap();
+    let mut entities = Vec::new();
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            for index in 0..4 {
+                let page = edit.add_page(page(), At::End)?;
+                let region = edit.add_analysis_region::<TextRegion>(
+                    page,
+                    At::End,
+                    &Geometry::rectangle(0.0, 0.0, 10.0, 10.0),
+                    None,
+                )?;
+                let content = edit.add_text_content(page, At::End)?;
+                edit.set(content, &source(&format!("Line {index}")))?;
+                let layer = edit.add_text_layer(
+                    page,
+                    At::End,
+                    content,
+                    &TextLayout {
+                        origin: Origin::User,
+                        kind: TextLayoutKind::Paragraph,
+                    },
+                )?;
+                if index != 1 {
+                    edit.relate::<FitsTo>(layer, region)?;
+                }
+                entities.push((content, layer));
+            }
+            Ok(())
+        })
+        .unwrap();
+    let mut snapshot = session.commit(patch).await.unwrap().snapshot;
+    for (index, confidence) in [0.3, 0.4, 0.9, 0.2].into_iter().enumerate() {
+        let mut edit = snapshot.edit_as(Generation {
+            producer: translator.clone(),
+            model: Some("model".to_owned()),
+            confidence: Some(confidence),
+        });
+        edit.set_translation(
+            entities[index].0,
+            &Translation {
+                text: Authored::user(format!("Hello {index}")),
+                language: None,
+            },
+        )
+        .unwrap();
+        snapshot = session
+            .commit(edit.finish().unwrap())
+            .await
+            .unwrap()
+            .snapshot;
+    }
+    let layers = entities.iter().map(|(_, layer)| *layer).collect::<Vec<_>>();
+    let ids = |query: &Query| {
+        snapshot
+            .query(query)
+            .unwrap()
+            .into_iter()
+            .map(|entity| entity.id())
+            .collect::<Vec<_>>()
+    };
+
+    let uncertain = Query::new()
+        .pages(1..=3)
+        .with::<TextLayout>()
+        .translation(OriginFilter::Generated(GenerationFilter {
+            below_confidence: Some(0.6),
+            ..GenerationFilter::default()
+        }))
+        .translation(OriginFilter::generated_by(translator.clone()));
+    assert_eq!(ids(&uncertain), [layers[1], layers[3]]);
+    assert!(uncertain.matches(&snapshot, layers[3]).unwrap());
+    assert!(!uncertain.matches(&snapshot, layers[0]).unwrap());
+    assert_eq!(
+        ids(&Query::new()
+            .with::<TextLayout>()
+            .translation(OriginFilter::User)),
+        []
+    );
+    assert_eq!(
+        ids(&Query::new().with::<TextLayout>().text("hello 2")),
+        [layers[2]]
+    );
+
+    let fitted = Query::new()
+        .region::<TextRegion>()
+        .related_by::<FitsTo>(Query::new().text("line 3"));
+    let fitted = ids(&fitted);
+    assert_eq!(fitted.len(), 1);
+    assert_eq!(
+        snapshot
+            .text_layer(layers[3])
+            .unwrap()
+            .fit_target()
+            .unwrap()
+            .map(|region| region.id()),
+        Some(fitted[0])
+    );
+    assert_eq!(
+        ids(&Query::new()
+            .with::<TextLayout>()
+            .filter(Filter::Lacks(Geometry::KIND.to_owned()))
+            .relates::<FitsTo>(Query::new().origin::<Region>(OriginFilter::User))),
+        [layers[0], layers[2], layers[3]]
+    );
+    assert!(
+        snapshot
+            .query(&Query::new().filter(Filter::Has("not a kind".to_owned())))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn annotations_thread_replies_on_their_anchor() {
+    let mut session = Session::memory().await.unwrap();
+    let mut entities = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            entities = Some((page, content));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (page, content) = entities.unwrap();
+    let producer = ProducerId::new("dev.koharu.pipeline.detection").unwrap();
+    let mut edit = snapshot.edit_as(Generation::new(producer));
+    let region = edit
+        .add_analysis_region::<BubbleRegion>(
+            page,
+            At::End,
+            &Geometry::rectangle(0.0, 0.0, 10.0, 10.0),
+            None,
+        )
+        .unwrap();
+    assert!(matches!(
+        edit.add_annotation(region, &Annotation::new("pipeline", "note")),
+        Err(Error::Authorship(_))
+    ));
+    let snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+
+    let mut note = Annotation::new("Aki", "check this pun with the editor");
+    note.mark = Some(AnnotationMark::Point(Point { x: 4.0, y: 5.0 }));
+    assert!(
+        snapshot
+            .patch(|edit| edit
+                .add_annotation(content, &Annotation::new("Aki", "note"))
+                .map(drop))
+            .is_err()
+    );
+    let mut annotation = None;
+    let patch = snapshot
+        .patch(|edit| {
+            let entity = edit.add_annotation(region, &note)?;
+            edit.reply_to_annotation(
+                entity,
+                AnnotationReply {
+                    author: "Editor".to_owned(),
+                    body: "keep the wordplay".to_owned(),
+                },
+            )?;
+            edit.resolve_annotation(entity, true)?;
+            annotation = Some(entity);
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let annotation = annotation.unwrap();
+    assert!(matches!(
+        snapshot.component::<EntityOrigin>(region).unwrap().unwrap(),
+        EntityOrigin {
+            origin: Origin::Generated(_)
+        }
+    ));
+    let anchored = snapshot.annotations_on(region);
+    assert_eq!(anchored.len(), 1);
+    assert_eq!(anchored[0].anchor().unwrap(), Some(region));
+    let value = anchored[0].annotation().unwrap();
+    assert_eq!(value.replies[0].body, "keep the wordplay");
+    assert!(value.resolved);
+    assert_eq!(snapshot.parent(annotation).unwrap(), Some(page));
+
+    let patch = snapshot
+        .patch(|edit| edit.remove_entity(region, RemovePolicy::Cascade))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot.annotation(annotation).unwrap().anchor().unwrap(),
+        None
+    );
+    assert_eq!(snapshot.annotations().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn characters_voice_their_lines_and_default_typography() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("characters.khrproj");
+    let mut session = Session::create(&path).await.unwrap();
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let mut hana = Character::new("Hana");
+            hana.aliases.push("Hana-chan".to_owned());
+            hana.speech_notes = "cheerful, drops honorifics".to_owned();
+            hana.typography = Some(TypographyOverride {
+                preferred_font: Some("Comic Hand".to_owned()),
+                color: Some([200, 40, 40, 255]),
+                ..TypographyOverride::default()
+            });
+            let hana = edit.add_character(&hana)?;
+            let narrator = edit.add_character(&Character::new("Narrator"))?;
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("おはよう"))?;
+            edit.set_speaker(content, Some(narrator))?;
+            edit.set_speaker(content, Some(hana))?;
+            let layer = edit.add_text_layer(
+                page,
+                At::End,
+                content,
+                &TextLayout {
+                    origin: Origin::User,
+                    kind: TextLayoutKind::Paragraph,
+                },
+            )?;
+            edit.set(
+                layer,
+                &Typography {
+                    color: Some([0, 0, 0, 255]),
+                    ..TypographyOverride::default().apply(None)
+                },
+            )?;
+            ids = Some((hana, narrator, content, layer));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (hana, narrator, content, layer) = ids.unwrap();
+    assert_eq!(snapshot.pages().len(), 1);
+    assert!(snapshot.page(EntityId::PROJECT).is_err());
+    assert_eq!(
+        snapshot
+            .text_content(content)
+            .unwrap()
+            .speaker()
+            .unwrap()
+            .map(CharacterRef::id),
+        Some(hana)
+    );
+    assert!(snapshot.character(narrator).unwrap().lines().is_empty());
+    let typography = snapshot
+        .text_layer(layer)
+        .unwrap()
+        .typography_for(None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(typography.preferred_font.as_deref(), Some("Comic Hand"));
+    assert_eq!(typography.color, Some([0, 0, 0, 255]));
+
+    assert!(
+        snapshot
+            .patch(|edit| edit.set(content, &Character::new("Content")))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.add_entity(EntityId::PROJECT, At::End).map(drop))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.remove_entity(EntityId::PROJECT, RemovePolicy::Cascade))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.relate::<SpokenBy>(layer, hana).map(drop))
+            .is_err()
+    );
+    drop((snapshot, session));
+
+    let mut session = Session::open(&path).await.unwrap();
+    let snapshot = session.snapshot();
+    let names = snapshot
+        .characters()
+        .unwrap()
+        .into_iter()
+        .map(|character| character.character().unwrap().name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Hana", "Narrator"]);
+    assert_eq!(snapshot.character(hana).unwrap().lines().len(), 1);
+
+    let patch = snapshot
+        .patch(|edit| edit.remove_entity(hana, RemovePolicy::Cascade))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert!(
+        snapshot
+            .text_content(content)
+            .unwrap()
+            .speaker()
+            .unwrap()
+            .is_none()
+    );
+    let snapshot = session.undo_last().await.unwrap().snapshot;
+    assert_eq!(snapshot.characters().unwrap().len(), 2);
+    assert!(
+        snapshot
+            .text_content(content)
+            .unwrap()
+            .speaker()
+            .unwrap()
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn glossary_terms_belong_to_the_project_and_flag_candidates() {
+    let mut session = Session::memory().await.unwrap();
+    let spanish = LanguageTag::new("es-ES").unwrap();
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let hana = edit.add_glossary_term(&GlossaryTerm::new("ハナ", "Hana"))?;
+            let kept = edit.add_glossary_term(&GlossaryTerm {
+                do_not_translate: true,
+                ..GlossaryTerm::new("Koharu", "")
+            })?;
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("ハナ、Koharuへ行こう"))?;
+            ids = Some((hana, kept, page, content));
+            Ok(())
+        })
+        .unwrap();
+    let mut snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (hana, kept, page, content) = ids.unwrap();
+    let terms = snapshot
+        .glossary()
+        .unwrap()
+        .into_iter()
+        .map(|term| term.term().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(terms.len(), 2);
+    assert!(terms[0].occurs_in("ハナ、Koharuへ行こう"));
+    assert!(terms[1].occurs_in("koharu"));
+    assert!(terms[1].is_rendered_in("Let's go to KOHARU"));
+    assert!(!terms[0].is_rendered_in("Let's go, Hanna"));
+    assert_eq!(snapshot.glossary_term(kept).unwrap().id(), kept);
+    assert!(
+        snapshot
+            .patch(|edit| edit.set(page, &GlossaryTerm::new("ハナ", "Hana")))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set(hana, &GlossaryTerm::new("ハナ", " ")))
+            .is_err()
+    );
+
+    let mut edit = snapshot.edit_as(Generation::new(
+        ProducerId::new("dev.koharu.pipeline.translation").unwrap(),
+    ));
+    let index = edit
+        .add_translation_candidate(content, &spanish, "Hanna".to_owned(), None)
+        .unwrap();
+    edit.flag_translation_candidate(content, &spanish, index, "missing Hana".to_owned())
+        .unwrap();
+    snapshot = session
+        .commit(edit.finish().unwrap())
+        .await
+        .unwrap()
+        .snapshot;
+    let candidates = snapshot
+        .text_content(content)
+        .unwrap()
+        .candidates()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        candidates.languages[&spanish][0].review,
+        ReviewState::NeedsWork
+    );
+    assert_eq!(candidates.languages[&spanish][0].notes, "missing Hana");
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.review_translation_candidate(
+                content,
+                &spanish,
+                0,
+                ReviewState::Approved,
+                String::new(),
+            )
+        })
+        .unwrap();
+    snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert!(
+        snapshot
+            .patch(|edit| edit.flag_translation_candidate(content, &spanish, 0, "again".to_owned()))
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn text_styles_inherit_and_yield_to_layer_typography() {
+    let mut session = Session::memory().await.unwrap();
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let dialogue = edit.add_text_style(
+                &TextStyle::new(
+                    "Dialogue",
+                    TypographyOverride {
+                        preferred_font: Some("Wild Words".to_owned()),
+                        size: Some(20.0),
+                        color: Some([0, 0, 0, 255]),
+                        ..TypographyOverride::default()
+                    },
+                ),
+                None,
+            )?;
+            let narration = edit.add_text_style(
+                &TextStyle::new(
+                    "Narration",
+                    TypographyOverride {
+                        preferred_font: Some("Narration Serif".to_owned()),
+                        ..TypographyOverride::default()
+                    },
+                ),
+                Some(dialogue),
+            )?;
+            edit.add_character(&Character::new("Hana"))?;
+            let page = edit.add_page(page(), At::End)?;
+            let content = edit.add_text_content(page, At::End)?;
+            edit.set(content, &source("その頃"))?;
+            let layer = edit.add_text_layer(
+                page,
+                At::End,
+                content,
+                &TextLayout {
+                    origin: Origin::User,
+                    kind: TextLayoutKind::Paragraph,
+                },
+            )?;
+            edit.set(
+                layer,
+                &Typography {
+                    color: Some([40, 40, 200, 255]),
+                    ..TypographyOverride::default().apply(None)
+                },
+            )?;
+            edit.set_text_style(layer, Some(dialogue))?;
+            edit.set_text_style(layer, Some(narration))?;
+            ids = Some((dialogue, narration, content, layer));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (dialogue, narration, content, layer) = ids.unwrap();
+    assert_eq!(snapshot.characters().unwrap().len(), 1);
+    let names = snapshot
+        .text_styles()
+        .unwrap()
+        .into_iter()
+        .map(|style| style.style().unwrap().name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Dialogue", "Narration"]);
+    assert!(snapshot.text_style(dialogue).unwrap().layers().is_empty());
+    let layer_ref = snapshot.text_layer(layer).unwrap();
+    assert_eq!(
+        layer_ref.style().unwrap().map(TextStyleRef::id),
+        Some(narration)
+    );
+    let typography = layer_ref.typography_for(None).unwrap().unwrap();
+    assert_eq!(
+        typography.preferred_font.as_deref(),
+        Some("Narration Serif")
+    );
+    assert_eq!(typography.size, Some(20.0));
+    assert_eq!(typography.color, Some([40, 40, 200, 255]));
+
+    assert!(
+        snapshot
+            .patch(|edit| edit.set_text_style_base(dialogue, Some(narration)))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set_text_style_base(dialogue, Some(dialogue)))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set_text_style(content, Some(dialogue)))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set(
+                content,
+                &TextStyle::new("Content", TypographyOverride::default())
+            ))
+            .is_err()
+    );
+
+    let patch = snapshot
+        .patch(|edit| {
+            let mut style = snapshot.text_style(dialogue)?.style()?;
+            style.typography.size = Some(24.0);
+            edit.set(dialogue, &style)
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let typography = snapshot
+        .text_layer(layer)
+        .unwrap()
+        .typography_for(None)
+        .unwrap()
+        .unwrap();
+    assert_eq!(typography.size, Some(24.0));
+
+    let patch = snapshot
+        .patch(|edit| edit.remove_entity(dialogue, RemovePolicy::Cascade))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let style = snapshot.text_style(narration).unwrap();
+    assert!(style.base().unwrap().is_none());
+    assert_eq!(style.typography().unwrap().size, None);
+    assert_eq!(style.layers().len(), 1);
+}
+
+#[tokio::test]
+async fn chapters_group_pages_and_move_them_together() {
+    let mut session = Session::memory().await.unwrap();
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let volume = edit.add_volume(&Volume::new("Volume 1", Some(1.0)), At::End)?;
+            let first =
+                edit.add_chapter(Some(volume), &Chapter::new("Arrival", Some(1.0)), At::End)?;
+            let second =
+                edit.add_chapter(Some(volume), &Chapter::new("Departure", Some(2.0)), At::End)?;
+            let mut pages = Vec::new();
+            for (label, chapter) in [
+                ("cover", None),
+                ("1-1", Some(first)),
+                ("1-2", Some(first)),
+                ("2-1", Some(second)),
+            ] {
+                let page = edit.add_page(PageDraft::new(label, 100.0, 100.0), At::End)?;
+                edit.set_chapter(page, chapter)?;
+                pages.push(page);
+            }
+            ids = Some((volume, first, second, pages));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (volume, first, second, pages) = ids.unwrap();
+    let labels = |snapshot: &Snapshot| {
+        snapshot
+            .pages()
+            .map(|page| page.page().unwrap().label)
+            .collect::<Vec<_>>()
+    };
+    let chapter_ids = |snapshot: &Snapshot| {
+        snapshot
+            .chapters()
+            .unwrap()
+            .into_iter()
+            .map(ChapterRef::id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(chapter_ids(&snapshot), [first, second]);
+    let chapter = snapshot.chapter(first).unwrap();
+    assert_eq!(chapter.volume().unwrap().map(VolumeRef::id), Some(volume));
+    assert_eq!(
+        chapter
+            .pages()
+            .into_iter()
+            .map(PageRef::id)
+            .collect::<Vec<_>>(),
+        pages[1..3]
+    );
+    assert!(
+        snapshot
+            .page(pages[0])
+            .unwrap()
+            .chapter()
+            .unwrap()
+            .is_none()
+    );
+
+    assert!(
+        snapshot
+            .patch(|edit| edit
+                .add_chapter(Some(first), &Chapter::new("Nested", None), At::End)
+                .map(drop))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit
+                .add_volume(&Volume::new("Page", None), At::End)
+                .and_then(|volume| edit.set_chapter(volume, Some(first))))
+            .is_err()
+    );
+
+    let patch = snapshot
+        .patch(|edit| edit.move_chapter(second, None, At::Start))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(chapter_ids(&snapshot), [second, first]);
+    assert_eq!(labels(&snapshot), ["cover", "2-1", "1-1", "1-2"]);
+    assert!(
+        snapshot
+            .chapter(second)
+            .unwrap()
+            .volume()
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        snapshot.volume(volume).unwrap().chapters().unwrap().len(),
+        1
+    );
+    let patch = snapshot
+        .patch(|edit| edit.remove_entity(volume, RemovePolicy::Cascade))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(chapter_ids(&snapshot), [second]);
+    assert_eq!(snapshot.pages().len(), 4);
+    assert!(
+        snapshot
+            .page(pages[1])
+            .unwrap()
+            .chapter()
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn spreads_pair_pages_and_the_project_reads_in_one_direction() {
+    let mut session = Session::memory().await.unwrap();
+    let mut pages = Vec::new();
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            for label in ["1", "2", "3"] {
+                pages.push(edit.add_page(PageDraft::new(label, 100.0, 150.0), At::End)?);
+            }
+            edit.set_spread(pages[2], Some(pages[1]))
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let spread = |snapshot: &Snapshot, page| {
+        snapshot
+            .page(page)
+            .unwrap()
+            .spread()
+            .into_iter()
+            .map(PageRef::id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        snapshot.reading_direction().unwrap(),
+        ReadingDirection::RightToLeft
+    );
+    assert_eq!(spread(&snapshot, pages[1]), pages[1..3]);
+    assert_eq!(spread(&snapshot, pages[2]), pages[1..3]);
+    assert_eq!(spread(&snapshot, pages[0]), [pages[0]]);
+    assert!(
+        snapshot
+            .patch(|edit| edit.relate::<SpreadWith>(pages[0], pages[1]).map(drop))
+            .is_err()
+    );
+    assert!(
+        snapshot
+            .patch(|edit| edit.set_spread(pages[0], Some(pages[0])))
+            .is_err()
+    );
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.set_project(&Reading {
+                direction: ReadingDirection::LeftToRight,
+            })?;
+            edit.set_spread(pages[0], Some(pages[1]))
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot.reading_direction().unwrap(),
+        ReadingDirection::LeftToRight
+    );
+    assert_eq!(spread(&snapshot, pages[1]), pages[0..2]);
+    assert_eq!(spread(&snapshot, pages[2]), [pages[2]]);
+
+    assert!(
+        snapshot
+            .patch(|edit| edit.set_spread(pages[0], Some(pages[2])))
+            .is_err()
+    );
+
+    let patch = snapshot
+        .patch(|edit| edit.set_spread(pages[1], None))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(spread(&snapshot, pages[0]), [pages[0]]);
+
+    let patch = snapshot
+        .patch(|edit| {
+            edit.set_spread(pages[1], Some(pages[2]))?;
+            edit.move_entity(pages[2], None, At::Start)
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(spread(&snapshot, pages[1]), [pages[1]]);
+    assert_eq!(spread(&snapshot, pages[2]), [pages[2]]);
+}
+
+#[tokio::test]
+async fn shape_layers_are_framed_vector_outlines() {
+    let mut session = Session::memory().await.unwrap();
+    let balloon = ShapeLayer {
+        origin: Origin::User,
+        name: "Balloon 1".to_owned(),
+        outline: ShapeOutline::Balloon {
+            tail: Point { x: 0.2, y: 1.4 },
+        },
+        fill: Some([255, 255, 255, 255]),
+        stroke: Some(ShapeStroke {
+            color: [0, 0, 0, 255],
+            width: 3.0,
+        }),
+    };
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let layer = edit.add_shape_layer(
+                page,
+                At::End,
+                &balloon,
+                &Geometry::rectangle(10.0, 10.0, 80.0, 40.0),
+            )?;
+            ids = Some((page, layer));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (page, layer) = ids.unwrap();
+    assert_eq!(
+        snapshot.component::<ShapeLayer>(layer).unwrap(),
+        Some(balloon.clone())
+    );
+
+    let patch = snapshot
+        .patch(|edit| edit.set(layer, &Geometry::rectangle(20.0, 30.0, 40.0, 20.0)))
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    assert_eq!(
+        snapshot.component::<ShapeLayer>(layer).unwrap(),
+        Some(balloon.clone())
+    );
+
+    let unframed = snapshot.patch(|edit| {
+        let shape = edit.add_entity(page, At::End)?;
+        edit.set(shape, &balloon)
+    });
+    assert!(unframed.is_err());
+    let styled = snapshot.patch(|edit| {
+        edit.set(
+            layer,
+            &Typography {
+                origin: Origin::User,
+                preferred_font: None,
+                font_weight: None,
+                font_style: None,
+                size: None,
+                auto_fit: true,
+                color: None,
+                stroke_color: None,
+                stroke_width: None,
+                alignment: None,
+                writing_mode: None,
+                extensions: BTreeMap::new(),
+            },
+        )
+    });
+    assert!(styled.is_err());
+    for outline in [
+        ShapeOutline::Balloon {
+            tail: Point { x: 0.5, y: 0.9 },
+        },
+        ShapeOutline::RoundedRectangle { radius: 0.7 },
+        ShapeOutline::Path {
+            points: vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 1.0 }],
+            closed: true,
+        },
+    ] {
+        let invalid = ShapeLayer {
+            outline,
+            ..balloon.clone()
+        };
+        assert!(snapshot.patch(|edit| edit.set(layer, &invalid)).is_err());
+    }
+}
+
+#[tokio::test]
+async fn blending_belongs_to_raster_layers() {
+    let mut session = Session::memory().await.unwrap();
+    let blending = Blending {
+        origin: Origin::User,
+        mode: BlendMode::Multiply,
+        clipped: true,
+    };
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let paint = edit.add_entity(page, At::End)?;
+            edit.set(
+                paint,
+                &RasterLayer {
+                    origin: Origin::User,
+                    name: "Paint 1".to_owned(),
+                    kind: RasterLayerKind::Paint,
+                },
+            )?;
+            edit.set(paint, &blending)?;
+            ids = Some((page, paint));
+            Ok(())
+        })
+        .unwrap();
+    let snapshot = session.commit(patch).await.unwrap().snapshot;
+    let (page, paint) = ids.unwrap();
+    assert_eq!(
+        snapshot.component::<Blending>(paint).unwrap(),
+        Some(blending.clone())
+    );
+
+    let detached = snapshot.patch(|edit| {
+        let entity = edit.add_entity(page, At::End)?;
+        edit.set(entity, &blending)
+    });
+    assert!(detached.is_err());
+    let unlayered = snapshot.patch(|edit| edit.remove::<RasterLayer>(paint));
+    assert!(unlayered.is_err());
+}
+
+#[tokio::test]
+async fn independent_pipeline_components_rebase() {
+    let mut session = Session::memory().await.unwrap();
+    let mut entities = None;
+    let create = session
+        .snapshot()
         .patch(|edit| {
             let page = edit.add_page(page(), At::End)?;
             let content = edit.add_text_content(page, At::End)?;
@@ -985,7 +2909,7 @@ async fn independent_pipeline_components_rebase() {
     let (content, layer) = entities.unwrap();
     let translation = base
         .patch(|edit| {
-            edit.set(
+            edit.set_translation(
                 content,
                 &Translation {
                     text: Authored::user("translation".to_owned()),
@@ -1020,7 +2944,7 @@ async fn independent_pipeline_components_rebase() {
     let snapshot = session.commit(typography).await.unwrap().snapshot;
     assert!(
         snapshot
-            .component::<Translation>(content)
+            .translation(content, Some(&LanguageTag::new("en").unwrap()))
             .unwrap()
             .is_some()
     );
@@ -1082,29 +3006,217 @@ async fn assets_attach_bytes_without_decoding() {
 }
 
 #[tokio::test]
-async fn repeated_asset_writes_rebase_over_an_unrelated_page_edit() {
-    let mut session = Session::memory().await.unwrap();
-    let mut ids = None;
-    let create = session
-        .snapshot()
-        .patch(|edit| {
-            let first_page = edit.add_page(page(), At::End)?;
-            let second_page = edit.add_page(page(), At::End)?;
-            ids = Some((first_page, second_page));
-            Ok(())
-        })
-        .unwrap();
-    let base = session.commit(create).await.unwrap().snapshot;
-    let (first_page, second_page) = ids.unwrap();
-
-    let assets = base
-        .patch(|edit| {
-            for role in ["text-mask", "bubble-mask"] {
+async fn repeated_asset_writes_rebase_over_an_unrelated_page_edit() {
+    let mut session = Session::memory().await.unwrap();
+    let mut ids = None;
+    let create = session
+        .snapshot()
+        .patch(|edit| {
+            let first_page = edit.add_page(page(), At::End)?;
+            let second_page = edit.add_page(page(), At::End)?;
+            ids = Some((first_page, second_page));
+            Ok(())
+        })
+        .unwrap();
+    let base = session.commit(create).await.unwrap().snapshot;
+    let (first_page, second_page) = ids.unwrap();
+
+    let assets = base
+        .patch(|edit| {
+            for role in ["text-mask", "bubble-mask"] {
+                edit.set_asset(
+                    second_page,
+                    &AssetRole::new(role)?,
+                    AssetInput::new(
+                        Arc::<[u8]>::from(role.as_bytes()),
+                        "image/test",
+                        AssetMetadata {
+                            width: None,
+                            height: None,
+                            attributes: BTreeMap::new(),
+                        },
+                    ),
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    let unrelated = base
+        .patch(|edit| {
+            edit.set_asset(
+                first_page,
+                &AssetRole::new("source")?,
+                AssetInput::new(
+                    Arc::<[u8]>::from(&b"source"[..]),
+                    "image/test",
+                    AssetMetadata {
+                        width: None,
+                        height: None,
+                        attributes: BTreeMap::new(),
+                    },
+                ),
+            )
+        })
+        .unwrap();
+    let current = session.commit(unrelated).await.unwrap().snapshot;
+
+    let assets = assets.rebase_on(&current).unwrap();
+    let snapshot = session.commit(assets).await.unwrap().snapshot;
+    for role in ["text-mask", "bubble-mask"] {
+        assert!(
+            snapshot
+                .asset(second_page, &AssetRole::new(role).unwrap())
+                .unwrap()
+                .is_some()
+        );
+    }
+}
+
+#[tokio::test]
+async fn disk_scene_reopens_and_validates() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("scene.khrproj");
+    {
+        let mut session = Session::create(&path).await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| edit.add_page(page(), At::End).map(|_| ()))
+            .unwrap();
+        session.commit(patch).await.unwrap();
+    }
+    let session = Session::open(&path).await.unwrap();
+    assert_eq!(session.snapshot().pages().len(), 1);
+}
+
+#[tokio::test]
+async fn named_checkpoints_open_read_only_and_restore_as_new_revisions() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("history.khrproj");
+    let role = AssetRole::new("source").unwrap();
+    let mut first_page = None;
+    let checkpoint = {
+        let mut session = Session::create(&path).await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(page(), At::End)?;
+                first_page = Some(page);
+                edit.set_asset(
+                    page,
+                    &role,
+                    AssetInput::new(
+                        Arc::<[u8]>::from(&b"original scan"[..]),
+                        "image/test",
+                        AssetMetadata {
+                            width: None,
+                            height: None,
+                            attributes: BTreeMap::new(),
+                        },
+                    ),
+                )
+            })
+            .unwrap();
+        let checkpoint = session.commit(patch).await.unwrap().revision;
+        session
+            .name_checkpoint(checkpoint, "Before cleanup")
+            .await
+            .unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                edit.remove_entity(first_page.unwrap(), RemovePolicy::Cascade)?;
+                edit.add_page(page(), At::End).map(|_| ())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        checkpoint
+    };
+    let first_page = first_page.unwrap();
+
+    let mut session = Session::open(&path).await.unwrap();
+    session.prune_history(Retention::Latest(0)).await.unwrap();
+    session.collect_garbage().await.unwrap();
+    let history = session.history().await.unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| (entry.revision, entry.checkpoint.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (checkpoint, Some("Before cleanup")),
+            (Revision::new(2), None)
+        ]
+    );
+
+    let old = session.open_revision(checkpoint).await.unwrap();
+    assert_eq!(old.revision(), checkpoint);
+    assert!(old.page(first_page).is_ok());
+    let stale = old
+        .patch(|edit| edit.add_page(page(), At::End).map(|_| ()))
+        .unwrap();
+    assert!(session.commit(stale).await.is_err());
+
+    let restored = session.restore(checkpoint).await.unwrap();
+    assert_eq!(restored.revision, Revision::new(3));
+    assert_eq!(restored.changes.from, Revision::new(2));
+    assert!(
+        restored
+            .changes
+            .entities
+            .contains(&EntityChange::Inserted(first_page))
+    );
+    assert_eq!(restored.snapshot.pages().len(), 1);
+    let asset = restored.snapshot.asset(first_page, &role).unwrap().unwrap();
+    assert_eq!(
+        restored
+            .snapshot
+            .read_blob(asset.blob)
+            .await
+            .unwrap()
+            .as_ref(),
+        b"original scan"
+    );
+    assert!(session.undo(Revision::new(2)).await.is_err());
+}
+
+#[tokio::test]
+async fn commits_append_to_the_log_and_replay_on_open() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("log.khrproj");
+    let role = AssetRole::new("source").unwrap();
+    let mut texts = Vec::new();
+    let mut page_id = None;
+    {
+        let mut session = Session::create(&path).await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(page(), At::End)?;
+                page_id = Some(page);
+                for index in 0..64 {
+                    let content = edit.add_text_content(page, At::End)?;
+                    edit.set(content, &source(&format!("original {index}")))?;
+                    texts.push(content);
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert!(session.commit(patch).await.unwrap().checkpoint);
+        for (index, text) in texts.iter().take(3).enumerate() {
+            let patch = session
+                .snapshot()
+                .patch(|edit| edit.set(*text, &source(&format!("edited {index}"))))
+                .unwrap();
+            assert!(!session.commit(patch).await.unwrap().checkpoint);
+        }
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
                 edit.set_asset(
-                    second_page,
-                    &AssetRole::new(role)?,
+                    page_id.unwrap(),
+                    &role,
                     AssetInput::new(
-                        Arc::<[u8]>::from(role.as_bytes()),
+                        Arc::<[u8]>::from(&b"logged scan"[..]),
                         "image/test",
                         AssetMetadata {
                             width: None,
@@ -1112,46 +3224,97 @@ async fn repeated_asset_writes_rebase_over_an_unrelated_page_edit() {
                             attributes: BTreeMap::new(),
                         },
                     ),
-                )?;
-            }
-            Ok(())
-        })
+                )
+            })
+            .unwrap();
+        assert_eq!(
+            session.commit(patch).await.unwrap().revision,
+            Revision::new(5)
+        );
+    }
+    assert!(std::fs::metadata(path.join("log.khr")).unwrap().len() > 0);
+
+    let mut session = Session::open(&path).await.unwrap();
+    let snapshot = session.snapshot();
+    assert_eq!(snapshot.revision(), Revision::new(5));
+    assert_eq!(
+        snapshot.component::<SourceText>(texts[1]).unwrap(),
+        Some(source("edited 1"))
+    );
+    assert_eq!(
+        snapshot.component::<SourceText>(texts[3]).unwrap(),
+        Some(source("original 3"))
+    );
+    let asset = snapshot.asset(page_id.unwrap(), &role).unwrap().unwrap();
+    assert_eq!(
+        snapshot.read_blob(asset.blob).await.unwrap().as_ref(),
+        b"logged scan"
+    );
+    assert!(session.verify().await.unwrap().is_healthy());
+    let recorded = |history: Vec<HistoryEntry>| {
+        history
+            .into_iter()
+            .map(|entry| entry.revision)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        recorded(session.history().await.unwrap()),
+        (0..=5).map(Revision::new).collect::<Vec<_>>()
+    );
+    let logged = session.open_revision(Revision::new(3)).await.unwrap();
+    assert_eq!(
+        logged.component::<SourceText>(texts[1]).unwrap(),
+        Some(source("edited 1"))
+    );
+    assert_eq!(
+        logged.component::<SourceText>(texts[2]).unwrap(),
+        Some(source("original 2"))
+    );
+
+    session.compact().await.unwrap();
+    assert_eq!(std::fs::metadata(path.join("log.khr")).unwrap().len(), 0);
+    assert_eq!(
+        recorded(session.history().await.unwrap()),
+        (0..=5).map(Revision::new).collect::<Vec<_>>()
+    );
+    drop((snapshot, logged));
+    drop(session);
+    let session = Session::open(&path).await.unwrap();
+    assert_eq!(
+        session
+            .snapshot()
+            .component::<SourceText>(texts[2])
+            .unwrap(),
+        Some(source("edited 2"))
+    );
+}
+
+#[tokio::test]
+async fn bundles_import_as_validated_scenes() {
+    let directory = tempfile::tempdir().unwrap();
+    let bundle = directory.path().join("scene.khrz");
+    let mut session = Session::create(directory.path().join("source.khrproj"))
+        .await
         .unwrap();
-    let unrelated = base
-        .patch(|edit| {
-            edit.set_asset(
-                first_page,
-                &AssetRole::new("source")?,
-                AssetInput::new(
-                    Arc::<[u8]>::from(&b"source"[..]),
-                    "image/test",
-                    AssetMetadata {
-                        width: None,
-                        height: None,
-                        attributes: BTreeMap::new(),
-                    },
-                ),
-            )
-        })
+    let patch = session
+        .snapshot()
+        .patch(|edit| edit.add_page(page(), At::End).map(|_| ()))
         .unwrap();
-    let current = session.commit(unrelated).await.unwrap().snapshot;
+    session.commit(patch).await.unwrap();
+    session.export_bundle(&bundle).await.unwrap();
 
-    let assets = assets.rebase_on(&current).unwrap();
-    let snapshot = session.commit(assets).await.unwrap().snapshot;
-    for role in ["text-mask", "bubble-mask"] {
-        assert!(
-            snapshot
-                .asset(second_page, &AssetRole::new(role).unwrap())
-                .unwrap()
-                .is_some()
-        );
-    }
+    let imported = Session::import_bundle(&bundle, directory.path().join("copy.khrproj"))
+        .await
+        .unwrap();
+    assert_eq!(imported.project_id(), session.project_id());
+    assert_eq!(imported.snapshot().revision(), Revision::new(1));
+    assert_eq!(imported.snapshot().pages().len(), 1);
 }
 
 #[tokio::test]
-async fn disk_scene_reopens_and_validates() {
+async fn repair_falls_back_past_states_that_fail_scene_validation() {
     let directory = tempfile::tempdir().unwrap();
-    let path = directory.path().join("scene.khrproj");
+    let path = directory.path().join("repair.khrproj");
     {
         let mut session = Session::create(&path).await.unwrap();
         let patch = session
@@ -1159,7 +3322,27 @@ async fn disk_scene_reopens_and_validates() {
             .patch(|edit| edit.add_page(page(), At::End).map(|_| ()))
             .unwrap();
         session.commit(patch).await.unwrap();
+        assert!(session.verify().await.unwrap().is_healthy());
+    }
+    {
+        // A foreign writer publishes a payload that is not a scene checkpoint.
+        let storage = koharu_storage::Session::open(&path).await.unwrap();
+        let current = storage.load().await.unwrap();
+        let broken = current
+            .update(
+                Revision::new(2),
+                bytes::Bytes::from_static(b"not a scene"),
+                [],
+                [],
+            )
+            .unwrap();
+        storage.save(&broken).await.unwrap();
     }
+    assert!(Session::open(&path).await.is_err());
+
+    let report = Session::repair(&path).await.unwrap();
+    assert_eq!(report.head, Revision::new(1));
+    assert_eq!(report.abandoned, Some(Revision::new(2)));
     let session = Session::open(&path).await.unwrap();
     assert_eq!(session.snapshot().pages().len(), 1);
 }
@@ -1490,3 +3673,317 @@ async fn user_promotion_protects_generated_entity_and_relation_lifecycle() {
         Err(Error::Authorship(_))
     ));
 }
+
+#[tokio::test]
+async fn undo_history_survives_reopening_and_retains_restorable_blobs() {
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("undo.khrproj");
+    let role = AssetRole::new("source").unwrap();
+    let mut ids = None;
+    {
+        let mut session = Session::create(&path).await.unwrap();
+        assert_eq!(session.undo_depth(), DEFAULT_UNDO_DEPTH);
+        let patch = session
+            .snapshot()
+            .patch(|edit| {
+                let page = edit.add_page(page(), At::End)?;
+                let text = edit.add_text_content(page, At::End)?;
+                edit.set(text, &source("original"))?;
+                edit.set_asset(
+                    page,
+                    &role,
+                    AssetInput::new(
+                        Arc::<[u8]>::from(&b"undoable scan"[..]),
+                        "image/test",
+                        AssetMetadata {
+                            width: None,
+                            height: None,
+                            attributes: BTreeMap::new(),
+                        },
+                    ),
+                )?;
+                ids = Some((page, text));
+                Ok(())
+            })
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        let journal = path.join("journal.khr");
+        let written = std::fs::read(&journal).unwrap();
+        let (page_id, text) = ids.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| edit.remove_asset(page_id, &role))
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        let appended = std::fs::read(&journal).unwrap();
+        assert!(appended.len() > written.len() && appended.starts_with(&written));
+        session.compact().await.unwrap();
+        let patch = session
+            .snapshot()
+            .patch(|edit| edit.set(text, &source("edited")))
+            .unwrap();
+        session.commit(patch).await.unwrap();
+        session.compact().await.unwrap();
+        assert!(session.can_undo());
+        assert!(!session.can_redo());
+    }
+
+    let (page_id, text) = ids.unwrap();
+    let mut session = Session::open(&path).await.unwrap();
+    assert!(session.can_undo());
+    assert!(session.prune_history(Retention::Latest(1)).await.unwrap() > 0);
+    assert_eq!(session.collect_garbage().await.unwrap().blobs, 0);
+
+    let reverted = session.undo_last().await.unwrap().snapshot;
+    assert_eq!(
+        reverted.component::<SourceText>(text).unwrap(),
+        Some(source("original"))
+    );
+    assert!(session.can_redo());
+    let undone = session.undo_last().await.unwrap().snapshot;
+    let asset = undone.asset(page_id, &role).unwrap().unwrap();
+    assert_eq!(
+        undone.read_blob(asset.blob).await.unwrap().as_ref(),
+        b"undoable scan"
+    );
+    drop((reverted, undone, asset, session));
+
+    let mut session = Session::open(&path).await.unwrap();
+    let redone = session.redo_last().await.unwrap().snapshot;
+    assert!(redone.asset(page_id, &role).unwrap().is_none());
+    assert!(session.can_redo());
+
+    session.set_undo_depth(1).await;
+    drop(redone);
+    drop(session);
+    let mut session = Session::open(&path).await.unwrap();
+    session.undo_last().await.unwrap();
+    assert!(!session.can_undo());
+    assert!(session.undo_last().await.is_err());
+    let undone = session.snapshot();
+    assert!(undone.asset(page_id, &role).unwrap().is_some());
+}
+
+#[tokio::test]
+async fn diff_reports_describe_pages_text_geometry_and_repainted_layers() {
+    let mut session = Session::memory().await.unwrap();
+    let english = LanguageTag::new("en").unwrap();
+    let role = AssetRole::new("pixels").unwrap();
+    let asset = |bytes: &'static [u8]| {
+        AssetInput::new(
+            Arc::<[u8]>::from(bytes),
+            "image/test",
+            AssetMetadata {
+                width: Some(1),
+                height: Some(1),
+                attributes: BTreeMap::new(),
+            },
+        )
+    };
+    let translation = |text: &str| Translation {
+        text: Authored::user(text.to_owned()),
+        language: Some(english.clone()),
+    };
+    let mut ids = None;
+    let patch = session
+        .snapshot()
+        .patch(|edit| {
+            let mut pages = Vec::new();
+            for label in ["a", "b", "c", "d"] {
+                pages.push(edit.add_page(PageDraft::new(label, 100.0, 100.0), At::End)?);
+            }
+            let [a, b, c, d] = pages[..] else {
+                unreachable!()
+            };
+            let content = edit.add_text_content(b, At::End)?;
+            edit.set(content, &source("こんにちは"))?;
+            edit.set_translation(content, &translation("Hello world"))?;
+            let layer = edit.add_text_layer(
+                b,
+                At::End,
+                content,
+                &TextLayout {
+                    origin: Origin::User,
+                    kind: TextLayoutKind::Paragraph,
+                },
+            )?;
+            edit.set(layer, &Geometry::rectangle(10.0, 10.0, 20.0, 20.0))?;
+            let paint = edit.add_entity(c, At::End)?;
+            edit.set(
+                paint,
+                &RasterLayer {
+                    origin: Origin::User,
+                    name: "Paint".to_owned(),
+                    kind: RasterLayerKind::Paint,
+                },
+            )?;
+            edit.set_asset(paint, &role, asset(b"before"))?;
+            ids = Some(([a, b, c, d], content, layer, paint));
+            Ok(())
+        })
+        .unwrap();
+    let base = session.commit(patch).await.unwrap().snapshot;
+    let ([a, b, c, d], content, layer, paint) = ids.unwrap();
+    assert!(base.diff_since(&base).unwrap().is_empty());
+
+    let patch = base
+        .patch(|edit| {
+            edit.move_entity(a, None, At::After(c))?;
+            edit.remove_entity(d, RemovePolicy::Cascade)?;
+            edit.add_page(PageDraft::new("e", 100.0, 100.0), At::End)?;
+            edit.set_translation(content, &translation("Hello there, world"))?;
+            edit.set(layer, &Geometry::rectangle(15.0, 5.0, 20.0, 20.0))?;
+            edit.set_asset(paint, &role, asset(b"after"))
+        })
+        .unwrap();
+    let delivered = session.commit(patch).await.unwrap().snapshot;
+    let report = delivered.diff_since(&base).unwrap();
+
+    let pages = report
+        .pages
+        .iter()
+        .map(|page| (page.page.label.as_str(), page.change))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        pages,
+        [
+            ("a", PageChange::Moved { from: 0, to: 2 }),
+            ("e", PageChange::Added { position: 3 }),
+            ("d", PageChange::Removed { position: 3 }),
+        ]
+    );
+    let [text] = report.texts.as_slice() else {
+        panic!("one text changed: {:?}", report.texts);
+    };
+    assert_eq!(text.page.id, b);
+    assert_eq!(text.field, TextField::Translation(Some(english.clone())));
+    assert_eq!(
+        text.edits,
+        [
+            TextEdit::Equal("Hello ".to_owned()),
+            TextEdit::Insert("there, ".to_owned()),
+            TextEdit::Equal("world".to_owned()),
+        ]
+    );
+    let [moved] = report.geometry.as_slice() else {
+        panic!("one geometry moved: {:?}", report.geometry);
+    };
+    assert_eq!(
+        (moved.entity, moved.offset),
+        (layer, Point { x: 5.0, y: -5.0 })
+    );
+    let [repainted] = report.layers.as_slice() else {
+        panic!("one layer repainted: {:?}", report.layers);
+    };
+    assert_eq!(repainted.entity, paint);
+    assert_eq!(repainted.kind, Some(RasterLayerKind::Paint));
+    assert_eq!(repainted.roles, [role]);
+
+    let text = report.to_string();
+    assert!(text.contains("moved \"a\" from 1 to 3"));
+    assert!(text.contains("translation (en): Hello {+there, +}world"));
+}
+
+#[tokio::test]
+async fn forks_merge_back_with_structured_conflicts() {
+    let directory = tempfile::tempdir().unwrap();
+    let mut ours = Session::create(directory.path().join("main.khrproj"))
+        .await
+        .unwrap();
+    let mut ids = None;
+    let patch = ours
+        .snapshot()
+        .patch(|edit| {
+            let page = edit.add_page(page(), At::End)?;
+            let mut texts = Vec::new();
+            for text in ["shared", "theirs only", "removed", "kept"] {
+                let content = edit.add_text_content(page, At::End)?;
+                edit.set(content, &source(text))?;
+                texts.push(content);
+            }
+            ids = Some((page, texts));
+            Ok(())
+        })
+        .unwrap();
+    let forked_at = ours.commit(patch).await.unwrap().revision;
+    let (page_id, texts) = ids.unwrap();
+    let mut theirs = ours
+        .fork(forked_at, directory.path().join("branch.khrproj"))
+        .await
+        .unwrap();
+    assert_eq!(theirs.project_id(), ours.project_id());
+    assert_eq!(theirs.snapshot().revision(), forked_at);
+
+    let patch = ours
+        .snapshot()
+        .patch(|edit| edit.set(texts[0], &source("ours")))
+        .unwrap();
+    ours.commit(patch).await.unwrap();
+    let mut added = None;
+    let patch = theirs
+        .snapshot()
+        .patch(|edit| {
+            edit.set(texts[0], &source("theirs"))?;
+            edit.set(texts[1], &source("edited in the branch"))?;
+            edit.remove_entity(texts[2], RemovePolicy::RejectNonEmpty)?;
+            let content = edit.add_text_content(page_id, At::End)?;
+            edit.set(content, &source("added in the branch"))?;
+            added = Some(content);
+            Ok(())
+        })
+        .unwrap();
+    theirs.commit(patch).await.unwrap();
+
+    let base = ours.open_revision(forked_at).await.unwrap();
+    let changes = theirs.snapshot().changes_since(&base).unwrap();
+    assert_eq!(changes.from, forked_at);
+    assert!(changes.entities.contains(&EntityChange::Removed(texts[2])));
+
+    let merge = ours.merge(&base, &theirs.snapshot()).await.unwrap();
+    let merged = &merge.commit.snapshot;
+    assert_eq!(
+        merged.component::<SourceText>(texts[1]).unwrap(),
+        Some(source("edited in the branch"))
+    );
+    assert_eq!(
+        merged.component::<SourceText>(added.unwrap()).unwrap(),
+        Some(source("added in the branch"))
+    );
+    assert!(merged.entity(texts[2]).is_err());
+    assert_eq!(
+        merged.component::<SourceText>(texts[3]).unwrap(),
+        Some(source("kept"))
+    );
+    assert_eq!(
+        merged.component::<SourceText>(texts[0]).unwrap(),
+        Some(source("ours"))
+    );
+    let [Conflict::Component(conflict)] = merge.conflicts.as_slice() else {
+        panic!("expected one component conflict: {:?}", merge.conflicts);
+    };
+    assert_eq!(conflict.owner, ComponentOwner::Entity(texts[0]));
+    assert_eq!(
+        conflict.base::<SourceText>().unwrap(),
+        Some(source("shared"))
+    );
+    assert_eq!(
+        conflict.theirs::<SourceText>().unwrap(),
+        Some(source("theirs"))
+    );
+    assert!(conflict.ours::<Typography>().is_err());
+
+    let resolution = ours
+        .snapshot()
+        .patch(|edit| edit.take_theirs(conflict))
+        .unwrap();
+    let resolved = ours.commit(resolution).await.unwrap().snapshot;
+    assert_eq!(
+        resolved.component::<SourceText>(texts[0]).unwrap(),
+        Some(source("theirs"))
+    );
+
+    // Merging the same branch again finds nothing left to apply.
+    let again = ours.merge(&base, &theirs.snapshot()).await.unwrap();
+    assert!(again.conflicts.is_empty());
+    assert_eq!(again.commit.revision, resolved.revision());
+}
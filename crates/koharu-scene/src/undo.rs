@@ -0,0 +1,280 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use revision::revisioned;
+
+use crate::{
+    BlobId, Result, Revision,
+    migration::{MIGRATIONS, upgrade_operation},
+    patch::Operation,
+};
+
+/// The number of undo steps a session retains unless configured otherwise.
+pub const DEFAULT_UNDO_DEPTH: usize = 100;
+
+struct Entry {
+    inverse: Arc<[Operation]>,
+    // A revision's inverse may restore blobs that the current state no longer
+    // references. Retain their lease for as long as that revision is undoable.
+    _blobs: koharu_storage::Blobs,
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct StoredEntry {
+    revision: Revision,
+    inverse: Vec<Operation>,
+}
+
+/// The complete history, written when the journal is replaced. Each record
+/// appended after it has the same shape and carries only the inverses added
+/// since the previous write, together with the stacks as they now stand.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct StoredUndo {
+    entries: Vec<StoredEntry>,
+    undo: Vec<Revision>,
+    redo: Vec<Revision>,
+}
+
+/// The next write of an undo history to the project journal.
+pub(crate) enum JournalWrite {
+    /// Replaces the journal with the complete history.
+    Replace(Vec<u8>),
+    /// Appends the change since the journal was written for `base`.
+    Append {
+        base: Revision,
+        record: Vec<u8>,
+        blobs: BTreeSet<BlobId>,
+    },
+}
+
+/// Undo and redo stacks of committed revisions and the inverse operations
+/// that revert them. Only revisions on a stack keep their inverse.
+pub(crate) struct UndoHistory {
+    entries: BTreeMap<Revision, Entry>,
+    undo: Vec<Revision>,
+    redo: Vec<Revision>,
+    depth: usize,
+    /// Revisions whose inverse is not in the journal yet.
+    unsaved: Vec<Revision>,
+    /// The revision the journal was last written for and how many inverses it
+    /// holds, including those no longer retained. `None` forces a replace.
+    journaled: Option<(Revision, usize)>,
+}
+
+impl UndoHistory {
+    pub(crate) const fn new(depth: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            depth,
+            unsaved: Vec::new(),
+            journaled: None,
+        }
+    }
+
+    /// Decodes a history persisted by [`UndoHistory::encode`] and the records
+    /// appended after it. Every inverse shares the journal blob lease, and its
+    /// components are upgraded like the state they revert.
+    pub(crate) fn decode(journal: &koharu_storage::Journal, depth: usize) -> Result<Self> {
+        let mut history = Self::new(depth);
+        let mut written = 0;
+        for payload in std::iter::once(journal.payload()).chain(journal.records()) {
+            let stored: StoredUndo = revision::from_slice(payload)?;
+            for mut entry in stored.entries {
+                for operation in &mut entry.inverse {
+                    upgrade_operation(MIGRATIONS, operation)?;
+                }
+                history.entries.insert(
+                    entry.revision,
+                    Entry {
+                        inverse: entry.inverse.into(),
+                        _blobs: journal.blobs().clone(),
+                    },
+                );
+                written += 1;
+            }
+            history.undo = stored.undo;
+            history.redo = stored.redo;
+        }
+        if history
+            .undo
+            .iter()
+            .chain(&history.redo)
+            .any(|revision| !history.entries.contains_key(revision))
+        {
+            return Err(crate::Error::invalid(
+                "undo history references a revision without an inverse",
+            ));
+        }
+        history.trim();
+        history.journaled = Some((journal.revision(), written));
+        Ok(history)
+    }
+
+    pub(crate) fn encode(&self) -> Result<Vec<u8>> {
+        self.encode_entries(self.entries.keys().copied())
+    }
+
+    fn encode_entries(&self, revisions: impl IntoIterator<Item = Revision>) -> Result<Vec<u8>> {
+        let stored = StoredUndo {
+            entries: revisions
+                .into_iter()
+                .filter_map(|revision| {
+                    self.entries.get(&revision).map(|entry| StoredEntry {
+                        revision,
+                        inverse: entry.inverse.to_vec(),
+                    })
+                })
+                .collect(),
+            undo: self.undo.clone(),
+            redo: self.redo.clone(),
+        };
+        revision::to_vec(&stored).map_err(Into::into)
+    }
+
+    /// Chooses the next journal write. Changes are appended, and the journal
+    /// is replaced once the history was cleared or once inverses that are no
+    /// longer retained outnumber the retained ones.
+    pub(crate) fn journal_write(&self) -> Result<JournalWrite> {
+        match self.journaled {
+            Some((base, written)) if written <= 2 * self.entries.len().max(self.depth) => {
+                Ok(JournalWrite::Append {
+                    base,
+                    record: self.encode_entries(self.unsaved.iter().copied())?,
+                    blobs: self
+                        .unsaved
+                        .iter()
+                        .filter_map(|revision| self.entries.get(revision))
+                        .flat_map(|entry| entry.inverse.iter().flat_map(Operation::written_blobs))
+                        .collect(),
+                })
+            }
+            _ => self.encode().map(JournalWrite::Replace),
+        }
+    }
+
+    /// Records a journal write for `revision`. `replaced` tells whether it
+    /// replaced the journal, or is `None` when the write failed.
+    pub(crate) fn journaled(&mut self, revision: Revision, replaced: Option<bool>) {
+        self.journaled = match replaced {
+            Some(true) => Some((revision, self.entries.len())),
+            Some(false) => self.journaled.map(|(_, written)| {
+                let appended = self
+                    .unsaved
+                    .iter()
+                    .filter(|revision| self.entries.contains_key(revision))
+                    .count();
+                (revision, written + appended)
+            }),
+            None => None,
+        };
+        self.unsaved.clear();
+    }
+
+    /// Blobs that undoing or redoing a retained revision can restore.
+    pub(crate) fn blobs(&self) -> BTreeSet<BlobId> {
+        self.entries
+            .values()
+            .flat_map(|entry| entry.inverse.iter().flat_map(Operation::written_blobs))
+            .collect()
+    }
+
+    pub(crate) const fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub(crate) fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.trim();
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub(crate) fn last_undo(&self) -> Option<Revision> {
+        self.undo.last().copied()
+    }
+
+    pub(crate) fn last_redo(&self) -> Option<Revision> {
+        self.redo.last().copied()
+    }
+
+    pub(crate) fn inverse(&self, revision: Revision) -> Option<&[Operation]> {
+        self.entries.get(&revision).map(|entry| &*entry.inverse)
+    }
+
+    /// Keeps the inverse of a published revision until the stacks settle.
+    pub(crate) fn insert(
+        &mut self,
+        revision: Revision,
+        inverse: Vec<Operation>,
+        blobs: koharu_storage::Blobs,
+    ) {
+        self.entries.insert(
+            revision,
+            Entry {
+                inverse: inverse.into(),
+                _blobs: blobs,
+            },
+        );
+        self.unsaved.push(revision);
+    }
+
+    /// Records a new edit. Anything that could be redone is discarded.
+    pub(crate) fn record(&mut self, revision: Revision) {
+        self.undo.push(revision);
+        self.redo.clear();
+        self.trim();
+    }
+
+    /// Moves the newest undo step to the redo stack as the revision `by` that
+    /// reverted it.
+    pub(crate) fn undone(&mut self, by: Revision) {
+        self.undo.pop();
+        self.redo.push(by);
+        self.trim();
+    }
+
+    pub(crate) fn redone(&mut self, by: Revision) {
+        self.redo.pop();
+        self.undo.push(by);
+        self.trim();
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.undo.clear();
+        self.redo.clear();
+        self.unsaved.clear();
+        self.journaled = None;
+    }
+
+    fn trim(&mut self) {
+        for stack in [&mut self.undo, &mut self.redo] {
+            let excess = stack.len().saturating_sub(self.depth);
+            stack.drain(..excess);
+        }
+        let retained = self
+            .undo
+            .iter()
+            .chain(&self.redo)
+            .copied()
+            .collect::<BTreeSet<_>>();
+        self.entries
+            .retain(|revision, _| retained.contains(revision));
+    }
+}
@@ -1,10 +1,17 @@
-use std::hint::black_box;
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
 
 use bytes::Bytes;
-use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
-use koharu_storage::{BlobId, DocumentId, Session};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use koharu_storage::{BlobId, DocumentId, Retention, Session, State};
 use tokio::runtime::Runtime;
 
+/// Records a commit appends before the benchmark compacts outside the timing,
+/// matching the scene compaction bound.
+const LOG_RECORDS: usize = 256;
+
 fn storage_benchmarks(criterion: &mut Criterion) {
     let runtime = Runtime::new().expect("create benchmark runtime");
 
@@ -36,29 +43,135 @@ fn storage_benchmarks(criterion: &mut Criterion) {
         .block_on(Session::memory(document, Bytes::new()))
         .expect("create blob benchmark project");
     let current = runtime.block_on(session.load()).expect("load state");
-    let bytes = Bytes::from(vec![11; 4 * 1024 * 1024]);
-    let blob = BlobId::for_bytes(&bytes);
+    // Encoded page images do not compress; masks are mostly uniform.
+    let image = noise(4 * 1024 * 1024);
+    let mask = Bytes::from(vec![11; 4 * 1024 * 1024]);
+    let image_id = BlobId::for_bytes(&image);
+    let mask_id = BlobId::for_bytes(&mask);
     let next = current
         .update(
             current.revision().next().unwrap(),
             Bytes::new(),
-            [blob],
-            [(blob, bytes)],
+            [image_id, mask_id],
+            [(image_id, image), (mask_id, mask)],
         )
         .expect("derive blob state");
     let durable = runtime
         .block_on(session.save(&next))
-        .expect("publish benchmark blob");
+        .expect("publish benchmark blobs");
     criterion.bench_function("storage/mmap_blob_4m", |bencher| {
         bencher.iter(|| {
             black_box(
                 runtime
-                    .block_on(durable.blobs().get(black_box(blob)))
+                    .block_on(durable.blobs().get(black_box(image_id)))
                     .expect("map blob"),
             )
         });
     });
+    criterion.bench_function("storage/read_compressed_blob_4m", |bencher| {
+        bencher.iter(|| {
+            black_box(
+                runtime
+                    .block_on(durable.blobs().get(black_box(mask_id)))
+                    .expect("decompress blob"),
+            )
+        });
+    });
+}
+
+/// Compares one small edit committed as a log record with the same edit
+/// committed as a complete checkpoint, across project sizes. Appends should
+/// stay flat while checkpoints grow with the project.
+fn commit_benchmarks(criterion: &mut Criterion) {
+    let runtime = Runtime::new().expect("create benchmark runtime");
+    let record = noise(512);
+    let mut group = criterion.benchmark_group("storage/commit");
+    group.sample_size(20);
+    for size in [64 * 1024, 4 * 1024 * 1024, 32 * 1024 * 1024] {
+        let session = runtime
+            .block_on(Session::memory(DocumentId::new(), noise(size)))
+            .expect("create commit benchmark project");
+        let mut state = runtime.block_on(session.load()).expect("load state");
+        group.bench_with_input(
+            BenchmarkId::new("append_record", size),
+            &size,
+            |bencher, _| {
+                bencher.iter_custom(|iterations| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iterations {
+                        if state.log().len() >= LOG_RECORDS {
+                            state = compact(&runtime, &session, &state);
+                        }
+                        let next = state
+                            .append(state.revision().next().unwrap(), record.clone(), [], [])
+                            .expect("append record");
+                        let started = Instant::now();
+                        state = runtime.block_on(session.save(&next)).expect("save record");
+                        elapsed += started.elapsed();
+                    }
+                    elapsed
+                });
+            },
+        );
+        state = compact(&runtime, &session, &state);
+        group.bench_with_input(
+            BenchmarkId::new("save_checkpoint", size),
+            &size,
+            |bencher, _| {
+                bencher.iter_custom(|iterations| {
+                    let mut elapsed = Duration::ZERO;
+                    for _ in 0..iterations {
+                        let next = state
+                            .update(
+                                state.revision().next().unwrap(),
+                                state.payload().clone(),
+                                [],
+                                [],
+                            )
+                            .expect("derive checkpoint");
+                        let started = Instant::now();
+                        state = runtime
+                            .block_on(session.save(&next))
+                            .expect("save checkpoint");
+                        elapsed += started.elapsed();
+                        runtime
+                            .block_on(session.prune_history(Retention::Latest(1)))
+                            .expect("prune benchmark history");
+                    }
+                    elapsed
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Rewrites the head as a checkpoint. Storage payloads are opaque, so the
+/// benchmark reuses the checkpoint payload as the replayed content.
+fn compact(runtime: &Runtime, session: &Session, state: &State) -> State {
+    let checkpoint = state
+        .update(state.revision(), state.payload().clone(), [], [])
+        .expect("derive compacted state");
+    let compacted = runtime
+        .block_on(session.compact(&checkpoint))
+        .expect("compact log");
+    runtime
+        .block_on(session.prune_history(Retention::Latest(1)))
+        .expect("prune benchmark history");
+    compacted
+}
+
+fn noise(len: usize) -> Bytes {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
 }
 
-criterion_group!(benches, storage_benchmarks);
+criterion_group!(benches, storage_benchmarks, commit_benchmarks);
 criterion_main!(benches);
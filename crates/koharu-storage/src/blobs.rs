@@ -1,6 +1,7 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     fs::{self, File},
+    io::Read,
     path::{Path, PathBuf},
     sync::{Arc, Weak},
 };
@@ -10,7 +11,11 @@ use memmap2::Mmap;
 use parking_lot::Mutex;
 use tempfile::TempDir;
 
-use crate::{BlobId, Error, Result, durability};
+use crate::{
+    BlobId, Error, Result,
+    codec::{self, Codec},
+    durability,
+};
 
 const MMAP_MIN_BYTES: u64 = 256 * 1024;
 
@@ -19,6 +24,7 @@ pub(crate) struct BlobStore {
     _lock: File,
     _temporary: Option<TempDir>,
     leases: Mutex<Vec<Weak<BTreeSet<BlobId>>>>,
+    readers: Mutex<Vec<(BlobId, Weak<()>)>>,
 }
 
 impl BlobStore {
@@ -28,9 +34,20 @@ impl BlobStore {
             _lock: lock,
             _temporary: temporary,
             leases: Mutex::new(Vec::new()),
+            readers: Mutex::new(Vec::new()),
         }
     }
 
+    /// Registers a reader of a blob file. The file is not rewritten while the
+    /// returned token, which a mapping keeps, is alive.
+    fn reader(&self, id: BlobId) -> Arc<()> {
+        let token = Arc::new(());
+        let mut readers = self.readers.lock();
+        readers.retain(|(_, reader)| reader.strong_count() > 0);
+        readers.push((id, Arc::downgrade(&token)));
+        token
+    }
+
     pub(crate) fn scope(
         self: &Arc<Self>,
         referenced: BTreeSet<BlobId>,
@@ -67,7 +84,17 @@ impl BlobStore {
     }
 
     pub(crate) fn persist(&self, blobs: &Blobs) -> Result<()> {
-        for id in blobs.inner.lease.iter().copied() {
+        self.persist_only(blobs, blobs.ids())
+    }
+
+    /// Publishes the listed members of a scope. Appends use this so that their
+    /// cost follows the edit rather than the size of the whole blob closure.
+    pub(crate) fn persist_only(
+        &self,
+        blobs: &Blobs,
+        ids: impl IntoIterator<Item = BlobId>,
+    ) -> Result<()> {
+        for id in ids {
             let target = self.path(id);
             if target.try_exists()? {
                 continue;
@@ -77,7 +104,7 @@ impl BlobStore {
                 .available
                 .get(&id)
                 .ok_or(Error::BlobNotFound(id))?;
-            durability::publish(&target, bytes)?;
+            durability::publish(&target, &codec::encode(bytes)?)?;
         }
         Ok(())
     }
@@ -101,9 +128,86 @@ impl BlobStore {
 
         let mut removed = 0;
         let mut bytes = 0u64;
+        for (id, path) in self.files()? {
+            if marked.contains(&id) {
+                continue;
+            }
+            let size = path.metadata()?.len();
+            fs::remove_file(path)?;
+            removed += 1;
+            bytes = bytes.saturating_add(size);
+        }
+        Ok((removed, bytes))
+    }
+
+    /// Rewrites headerless blob files through the current codec and returns
+    /// how many were compressed and how many bytes that saved. A tempfile is
+    /// renamed over each file, so a crash leaves the old or the new encoding.
+    /// Files that a reader has open are skipped, since Windows refuses to
+    /// replace a mapped file; a later run compresses them.
+    pub(crate) fn compress(&self) -> Result<(usize, u64)> {
+        let mut compressed = 0;
+        let mut saved = 0u64;
+        for (id, path) in self.files()? {
+            let file = fs::read(&path)?;
+            if codec::header(&file)?.is_some() {
+                continue;
+            }
+            if BlobId::for_bytes(&file) != id {
+                tracing::warn!(blob = %id, "skipping blob whose bytes do not match its id");
+                continue;
+            }
+            let encoded = codec::encode(&file)?;
+            if encoded.len() >= file.len() {
+                continue;
+            }
+            // Holding the registry keeps new readers from opening the file
+            // until the rename has published the replacement.
+            let readers = self.readers.lock();
+            if readers
+                .iter()
+                .any(|(reader, token)| *reader == id && token.strong_count() > 0)
+            {
+                continue;
+            }
+            durability::replace(&path, &encoded)?;
+            drop(readers);
+            compressed += 1;
+            saved = saved.saturating_add((file.len() - encoded.len()) as u64);
+        }
+        Ok((compressed, saved))
+    }
+
+    /// Opens one published blob for streaming its decoded bytes without
+    /// mapping it, and returns their length.
+    pub(crate) fn open(&self, id: BlobId) -> Result<(u64, Box<dyn Read + Send>)> {
+        let file = File::open(self.path(id)).map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => Error::BlobNotFound(id),
+            _ => error.into(),
+        })?;
+        codec::decode_stream(file)
+    }
+
+    /// Publishes the file of another store's blob unchanged. Files are named
+    /// by their decoded content, so the encoded bytes are valid here as well.
+    pub(crate) fn copy_from(&self, source: &Self, id: BlobId) -> Result<()> {
+        let target = self.path(id);
+        if target.try_exists()? {
+            return Ok(());
+        }
+        let file = fs::read(source.path(id)).map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => Error::BlobNotFound(id),
+            _ => error.into(),
+        })?;
+        durability::publish(&target, &file)
+    }
+
+    /// Lists every published blob file by ID.
+    pub(crate) fn files(&self) -> Result<Vec<(BlobId, PathBuf)>> {
         let root = self.root.join("blobs");
+        let mut files = Vec::new();
         if !root.try_exists()? {
-            return Ok((0, 0));
+            return Ok(files);
         }
         for shard in fs::read_dir(root)? {
             let shard = shard?;
@@ -115,25 +219,20 @@ impl BlobStore {
                 if !entry.file_type()?.is_file() {
                     continue;
                 }
-                let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                let Some(id) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse::<BlobId>().ok())
+                else {
                     continue;
                 };
-                let Ok(id) = name.parse::<BlobId>() else {
-                    continue;
-                };
-                if marked.contains(&id) {
-                    continue;
-                }
-                let size = entry.metadata()?.len();
-                fs::remove_file(entry.path())?;
-                removed += 1;
-                bytes = bytes.saturating_add(size);
+                files.push((id, entry.path()));
             }
         }
-        Ok((removed, bytes))
+        Ok(files)
     }
 
-    fn path(&self, id: BlobId) -> PathBuf {
+    pub(crate) fn path(&self, id: BlobId) -> PathBuf {
         let name = id.to_string();
         self.root.join("blobs").join(&name[..2]).join(name)
     }
@@ -190,7 +289,8 @@ impl Blobs {
         }
         let path = self.inner.store.path(id);
         let lease = self.inner.lease.clone();
-        tokio::task::spawn_blocking(move || map_or_read(&path, lease))
+        let reader = self.inner.store.reader(id);
+        tokio::task::spawn_blocking(move || map_or_read(&path, lease, reader))
             .await
             .map_err(|error| Error::Task(error.to_string()))?
     }
@@ -219,6 +319,7 @@ impl Blobs {
 struct MappedBlob {
     map: Mmap,
     _lease: Arc<BTreeSet<BlobId>>,
+    _reader: Arc<()>,
 }
 
 impl AsRef<[u8]> for MappedBlob {
@@ -227,18 +328,41 @@ impl AsRef<[u8]> for MappedBlob {
     }
 }
 
-fn map_or_read(path: &Path, lease: Arc<BTreeSet<BlobId>>) -> Result<Bytes> {
+fn map_or_read(path: &Path, lease: Arc<BTreeSet<BlobId>>, reader: Arc<()>) -> Result<Bytes> {
     let file = File::open(path)?;
     let length = file.metadata()?.len();
     if length == 0 {
         return Ok(Bytes::new());
     }
     if length < MMAP_MIN_BYTES {
-        return fs::read(path).map(Bytes::from).map_err(Into::into);
+        let file = fs::read(path)?;
+        return match codec::header(&file)? {
+            None => Ok(Bytes::from(file)),
+            Some(_) => Ok(Bytes::from(codec::decode(&file)?.into_owned())),
+        };
     }
     // SAFETY: published blob paths are immutable. Koharu never truncates or
-    // overwrites them, and the retained lease excludes the file from GC until
-    // the final owner-backed Bytes value is dropped.
+    // overwrites them in place, the retained lease excludes the file from GC,
+    // and the reader token keeps compression from replacing it, until the
+    // final owner-backed Bytes value is dropped.
     let map = unsafe { Mmap::map(&file)? };
-    Ok(Bytes::from_owner(MappedBlob { map, _lease: lease }))
+    match codec::header(&map)? {
+        None => Ok(Bytes::from_owner(MappedBlob {
+            map,
+            _lease: lease,
+            _reader: reader,
+        })),
+        Some(header) if header.codec == Codec::Raw => {
+            let decoded = codec::decode(&map)?.len();
+            Ok(Bytes::from_owner(MappedBlob {
+                map,
+                _lease: lease,
+                _reader: reader,
+            })
+            .slice(codec::HEADER_BYTES..codec::HEADER_BYTES + decoded))
+        }
+        // Compressed blobs are decoded into an owned buffer; the map is
+        // released as soon as decompression finishes.
+        Some(_) => Ok(Bytes::from(codec::decode(&map)?.into_owned())),
+    }
 }
@@ -0,0 +1,192 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use revision::revisioned;
+use zip::{CompressionMethod, ZipArchive, ZipWriter, read::ZipFile, write::SimpleFileOptions};
+
+use crate::{
+    BlobId, DocumentId, Error, Result, Revision,
+    blobs::BlobStore,
+    codec, durability,
+    format::{self, StoredState},
+};
+
+const MANIFEST_MAGIC: &[u8; 8] = b"KHRBUNDL";
+const MANIFEST_ENTRY: &str = "manifest.khr";
+const STATE_ENTRY: &str = "state.khr";
+const BLOB_PREFIX: &str = "blobs/";
+const MAX_MANIFEST_BYTES: u64 = 64 * 1024 * 1024;
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct Manifest {
+    document: DocumentId,
+    revision: Revision,
+    state_bytes: u64,
+    state_checksum: [u8; 32],
+    blobs: Vec<BundledBlob>,
+}
+
+#[revisioned(revision = 1)]
+#[derive(Copy, Clone, Debug)]
+struct BundledBlob {
+    id: BlobId,
+    bytes: u64,
+}
+
+/// Writes one state and exactly its decoded blob closure into a single
+/// archive. Entries are stored uncompressed so import can check their lengths
+/// before reading, and the manifest is written last once every length is known.
+pub(crate) fn pack(target: &Path, state: &StoredState, store: &BlobStore) -> Result<()> {
+    let encoded = format::encode(state)?;
+    durability::publish_with(target, |file| {
+        let mut archive = ZipWriter::new(file);
+        let options = |bytes: u64| {
+            SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                .large_file(bytes >= u64::from(u32::MAX))
+        };
+        archive.start_file(STATE_ENTRY, options(encoded.len() as u64))?;
+        archive.write_all(&encoded)?;
+        let mut blobs = Vec::with_capacity(state.blobs.len());
+        for id in &state.blobs {
+            // Blobs are decoded while they are copied, so a large page image
+            // is never held in memory.
+            let (bytes, mut source) = store.open(*id)?;
+            archive.start_file(format!("{BLOB_PREFIX}{id}"), options(bytes))?;
+            if io::copy(&mut source, &mut archive)? != bytes {
+                return Err(Error::invalid(format!(
+                    "blob {id} decoded length is invalid"
+                )));
+            }
+            blobs.push(BundledBlob { id: *id, bytes });
+        }
+        let manifest = Manifest {
+            document: state.document,
+            revision: state.revision,
+            state_bytes: encoded.len() as u64,
+            state_checksum: *blake3::hash(&encoded).as_bytes(),
+            blobs,
+        };
+        let manifest = format::frame(MANIFEST_MAGIC, &revision::to_vec(&manifest)?)?;
+        archive.start_file(MANIFEST_ENTRY, options(manifest.len() as u64))?;
+        archive.write_all(&manifest)?;
+        archive.finish()?;
+        Ok(())
+    })
+}
+
+/// Verifies a bundle and publishes its blobs into `store`, returning the state
+/// to publish once every referenced blob is durable.
+pub(crate) fn unpack(source: &Path, store: &BlobStore) -> Result<StoredState> {
+    let mut archive = ZipArchive::new(File::open(source)?)?;
+    let manifest = {
+        let entry = archive.by_name(MANIFEST_ENTRY)?;
+        let bytes = read_entry(entry, None, MAX_MANIFEST_BYTES)?;
+        revision::from_slice::<Manifest>(format::unframe(MANIFEST_MAGIC, &bytes)?)?
+    };
+    let state = {
+        let entry = archive.by_name(STATE_ENTRY)?;
+        let bytes = read_entry(
+            entry,
+            Some(manifest.state_bytes),
+            format::MAX_ENCODED_BYTES as u64,
+        )?;
+        if blake3::hash(&bytes).as_bytes() != &manifest.state_checksum {
+            return Err(Error::invalid("bundle state checksum mismatch"));
+        }
+        format::decode(&bytes)?
+    };
+    if state.document != manifest.document || state.revision != manifest.revision {
+        return Err(Error::invalid(
+            "bundle manifest does not describe its state",
+        ));
+    }
+    if !manifest
+        .blobs
+        .iter()
+        .map(|blob| blob.id)
+        .eq(state.blobs.iter().copied())
+    {
+        return Err(Error::invalid(
+            "bundle manifest does not list exactly the state blobs",
+        ));
+    }
+    let expected = manifest
+        .blobs
+        .iter()
+        .map(|blob| format!("{BLOB_PREFIX}{}", blob.id))
+        .chain([MANIFEST_ENTRY.to_owned(), STATE_ENTRY.to_owned()])
+        .collect::<BTreeSet<_>>();
+    if archive.len() != expected.len() || archive.file_names().any(|name| !expected.contains(name))
+    {
+        return Err(Error::invalid("bundle contains unexpected entries"));
+    }
+
+    for blob in &manifest.blobs {
+        let entry = archive.by_name(&format!("{BLOB_PREFIX}{}", blob.id))?;
+        if entry.size() != blob.bytes || blob.bytes > codec::MAX_DECODED_BYTES {
+            return Err(Error::invalid("bundle entry length is invalid"));
+        }
+        let target = store.path(blob.id);
+        if target.try_exists()? {
+            continue;
+        }
+        // Blobs are streamed through the hash and the codec so a large page
+        // image is never held in memory; a mismatch discards the tempfile
+        // before publication.
+        durability::publish_with(&target, |file| {
+            let mut source = Hashed {
+                inner: entry.take(blob.bytes),
+                hasher: blake3::Hasher::new(),
+                bytes: 0,
+            };
+            codec::encode_stream(&mut source, blob.bytes, file)?;
+            if source.bytes != blob.bytes
+                || source.hasher.finalize().as_bytes() != blob.id.as_bytes()
+            {
+                return Err(Error::invalid(format!(
+                    "bundle blob {} has mismatched bytes",
+                    blob.id
+                )));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(state)
+}
+
+/// Hashes and counts the bytes read through it.
+struct Hashed<R> {
+    inner: R,
+    hasher: blake3::Hasher,
+    bytes: u64,
+}
+
+impl<R: Read> Read for Hashed<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buffer)?;
+        self.hasher.update(&buffer[..read]);
+        self.bytes += read as u64;
+        Ok(read)
+    }
+}
+
+fn read_entry(mut entry: ZipFile<'_, File>, expected: Option<u64>, limit: u64) -> Result<Vec<u8>> {
+    let size = entry.size();
+    if expected.is_some_and(|expected| expected != size) || size > limit {
+        return Err(Error::invalid("bundle entry length is invalid"));
+    }
+    let capacity = usize::try_from(size)
+        .map_err(|_| Error::invalid("bundle entry does not fit this platform"))?;
+    let mut bytes = Vec::with_capacity(capacity);
+    (&mut entry).take(size).read_to_end(&mut bytes)?;
+    if bytes.len() != capacity {
+        return Err(Error::invalid("bundle entry length is invalid"));
+    }
+    Ok(bytes)
+}
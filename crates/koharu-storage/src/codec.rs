@@ -0,0 +1,168 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Read, Seek as _, Write},
+};
+
+use crate::{Error, Result};
+
+const MAGIC: &[u8; 8] = b"KHRBLOB\0";
+pub(crate) const HEADER_BYTES: usize = 8 + 1 + 8;
+const MIN_COMPRESSED_BYTES: usize = 4 * 1024;
+const STREAM_SAMPLE_BYTES: u64 = 1024 * 1024;
+const ZSTD_LEVEL: i32 = 3;
+pub(crate) const MAX_DECODED_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// How a blob file stores its bytes. Files without a header are raw; that is
+/// the only layout older projects contain.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Codec {
+    Raw,
+    Zstd,
+}
+
+impl Codec {
+    const fn tag(self) -> u8 {
+        match self {
+            Self::Raw => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(Self::Raw),
+            1 => Ok(Self::Zstd),
+            _ => Err(Error::invalid(format!("unknown blob codec {tag}"))),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Header {
+    pub(crate) codec: Codec,
+    pub(crate) decoded_bytes: u64,
+}
+
+/// Reads the codec header of a blob file, or `None` for a headerless raw file.
+pub(crate) fn header(file: &[u8]) -> Result<Option<Header>> {
+    if file.len() < HEADER_BYTES || &file[..8] != MAGIC {
+        return Ok(None);
+    }
+    let codec = Codec::from_tag(file[8])?;
+    let decoded_bytes = u64::from_le_bytes(file[9..17].try_into().expect("fixed header range"));
+    if decoded_bytes > MAX_DECODED_BYTES {
+        return Err(Error::invalid("blob decoded length is invalid"));
+    }
+    Ok(Some(Header {
+        codec,
+        decoded_bytes,
+    }))
+}
+
+/// Encodes blob bytes for publication. Content is compressed only when that
+/// saves at least an eighth of its size, so already compressed images stay
+/// headerless and remain directly mappable.
+pub(crate) fn encode(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if bytes.len() >= MIN_COMPRESSED_BYTES {
+        let compressed = zstd::bulk::compress(bytes, ZSTD_LEVEL)?;
+        if HEADER_BYTES + compressed.len() <= bytes.len() - bytes.len() / 8 {
+            return Ok(Cow::Owned(framed(Codec::Zstd, bytes.len(), &compressed)));
+        }
+    }
+    // Raw content that happens to start with the magic is escaped by a header.
+    if bytes.starts_with(MAGIC) {
+        return Ok(Cow::Owned(framed(Codec::Raw, bytes.len(), bytes)));
+    }
+    Ok(Cow::Borrowed(bytes))
+}
+
+/// Decodes a complete blob file. Raw files are returned as a subslice.
+pub(crate) fn decode(file: &[u8]) -> Result<Cow<'_, [u8]>> {
+    let Some(header) = header(file)? else {
+        return Ok(Cow::Borrowed(file));
+    };
+    let body = &file[HEADER_BYTES..];
+    let decoded = match header.codec {
+        Codec::Raw => Cow::Borrowed(body),
+        Codec::Zstd => {
+            let capacity = usize::try_from(header.decoded_bytes)
+                .map_err(|_| Error::invalid("blob does not fit this platform"))?;
+            Cow::Owned(zstd::bulk::decompress(body, capacity)?)
+        }
+    };
+    if decoded.len() as u64 != header.decoded_bytes {
+        return Err(Error::invalid("blob decoded length is invalid"));
+    }
+    Ok(decoded)
+}
+
+/// Streams `decoded_bytes` of blob content from `source` into `target` in
+/// the published encoding. The codec is chosen as [`encode`] would choose it,
+/// judged on a leading sample so a large blob is never held in memory.
+pub(crate) fn encode_stream(
+    source: &mut impl Read,
+    decoded_bytes: u64,
+    target: &mut impl Write,
+) -> Result<()> {
+    let mut sample = Vec::new();
+    source
+        .by_ref()
+        .take(STREAM_SAMPLE_BYTES)
+        .read_to_end(&mut sample)?;
+    let compress = sample.len() >= MIN_COMPRESSED_BYTES && {
+        let compressed = zstd::bulk::compress(&sample, ZSTD_LEVEL)?;
+        HEADER_BYTES + compressed.len() <= sample.len() - sample.len() / 8
+    };
+    if compress {
+        target.write_all(&header_bytes(Codec::Zstd, decoded_bytes))?;
+        let mut encoder = zstd::stream::write::Encoder::new(target, ZSTD_LEVEL)?;
+        encoder.write_all(&sample)?;
+        io::copy(source, &mut encoder)?;
+        encoder.finish()?;
+    } else {
+        if sample.starts_with(MAGIC) {
+            target.write_all(&header_bytes(Codec::Raw, decoded_bytes))?;
+        }
+        target.write_all(&sample)?;
+        io::copy(source, target)?;
+    }
+    Ok(())
+}
+
+/// Streams the decoded bytes of an opened blob file and returns their length.
+pub(crate) fn decode_stream(mut file: File) -> Result<(u64, Box<dyn Read + Send>)> {
+    let length = file.metadata()?.len();
+    let mut prefix = [0; HEADER_BYTES];
+    let header = if length >= HEADER_BYTES as u64 {
+        file.read_exact(&mut prefix)?;
+        header(&prefix)?
+    } else {
+        None
+    };
+    let Some(header) = header else {
+        file.rewind()?;
+        return Ok((length, Box::new(file)));
+    };
+    let body = file.take(length - HEADER_BYTES as u64);
+    let reader: Box<dyn Read + Send> = match header.codec {
+        Codec::Raw => Box::new(body),
+        Codec::Zstd => Box::new(zstd::stream::read::Decoder::new(body)?),
+    };
+    Ok((header.decoded_bytes, reader))
+}
+
+fn header_bytes(codec: Codec, decoded_bytes: u64) -> [u8; HEADER_BYTES] {
+    let mut header = [0; HEADER_BYTES];
+    header[..8].copy_from_slice(MAGIC);
+    header[8] = codec.tag();
+    header[9..].copy_from_slice(&decoded_bytes.to_le_bytes());
+    header
+}
+
+fn framed(codec: Codec, decoded_bytes: usize, body: &[u8]) -> Vec<u8> {
+    let mut file = Vec::with_capacity(HEADER_BYTES + body.len());
+    file.extend_from_slice(&header_bytes(codec, decoded_bytes as u64));
+    file.extend_from_slice(body);
+    file
+}
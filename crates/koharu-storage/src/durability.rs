@@ -3,13 +3,15 @@ use std::{fs, io::Write, path::Path};
 use crate::Result;
 
 pub(crate) fn publish(target: &Path, bytes: &[u8]) -> Result<()> {
-    let parent = target
-        .parent()
-        .ok_or_else(|| crate::Error::invalid("published path has no parent"))?;
-    fs::create_dir_all(parent)?;
-    let mut file = tempfile::NamedTempFile::new_in(parent)?;
-    file.write_all(bytes)?;
-    file.as_file().sync_all()?;
+    publish_with(target, |file| file.write_all(bytes).map_err(Into::into))
+}
+
+/// Publishes content streamed by `write` into a destination-local tempfile.
+pub(crate) fn publish_with(
+    target: &Path,
+    write: impl FnOnce(&mut tempfile::NamedTempFile) -> Result<()>,
+) -> Result<()> {
+    let file = synced_tempfile(target, write)?;
 
     // State slots alternate, so removing the inactive target cannot destroy the
     // currently committed state. The final rename is the publication point.
@@ -17,12 +19,33 @@ pub(crate) fn publish(target: &Path, bytes: &[u8]) -> Result<()> {
         fs::remove_file(target)?;
     }
     file.persist(target).map_err(|error| error.error)?;
-    sync_parent(target)?;
-    Ok(())
+    sync_parent(target)
+}
+
+/// Replaces a live file by renaming a synced tempfile over it. The target is
+/// never removed first, so a crash leaves either the old or the new file.
+pub(crate) fn replace(target: &Path, bytes: &[u8]) -> Result<()> {
+    let file = synced_tempfile(target, |file| file.write_all(bytes).map_err(Into::into))?;
+    file.persist(target).map_err(|error| error.error)?;
+    sync_parent(target)
+}
+
+fn synced_tempfile(
+    target: &Path,
+    write: impl FnOnce(&mut tempfile::NamedTempFile) -> Result<()>,
+) -> Result<tempfile::NamedTempFile> {
+    let parent = target
+        .parent()
+        .ok_or_else(|| crate::Error::invalid("published path has no parent"))?;
+    fs::create_dir_all(parent)?;
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    write(&mut file)?;
+    file.as_file().sync_all()?;
+    Ok(file)
 }
 
 #[cfg(unix)]
-fn sync_parent(path: &Path) -> Result<()> {
+pub(crate) fn sync_parent(path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
         std::fs::File::open(parent)?.sync_all()?;
     }
@@ -30,6 +53,6 @@ fn sync_parent(path: &Path) -> Result<()> {
 }
 
 #[cfg(not(unix))]
-fn sync_parent(_path: &Path) -> Result<()> {
+pub(crate) fn sync_parent(_path: &Path) -> Result<()> {
     Ok(())
 }
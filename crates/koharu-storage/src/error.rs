@@ -8,6 +8,8 @@ pub enum Error {
     Io(#[from] std::io::Error),
     #[error(transparent)]
     Codec(#[from] revision::Error),
+    #[error(transparent)]
+    Archive(#[from] zip::result::ZipError),
     #[error("not a Koharu project")]
     NotAProject,
     #[error("project is already open for writing")]
@@ -21,6 +23,8 @@ pub enum Error {
     },
     #[error("blob {0} was not found")]
     BlobNotFound(BlobId),
+    #[error("revision {0} is not recorded in the project history")]
+    RevisionNotFound(Revision),
     #[error("state generation conflict: expected newer than {current}, got {proposed}")]
     RevisionConflict {
         current: Revision,
@@ -12,6 +12,7 @@ const MAGIC: &[u8; 8] = b"KHRSTATE";
 const VERSION: u32 = 1;
 const HEADER_BYTES: usize = 8 + 4 + 8 + 32;
 const MAX_STATE_BYTES: usize = 512 * 1024 * 1024;
+pub(crate) const MAX_ENCODED_BYTES: usize = HEADER_BYTES + MAX_STATE_BYTES;
 
 #[revisioned(revision = 1)]
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -36,7 +37,7 @@ impl Slot {
         }
     }
 
-    fn filename(self) -> &'static str {
+    pub(crate) const fn filename(self) -> &'static str {
         match self {
             Self::A => "state-a.khr",
             Self::B => "state-b.khr",
@@ -49,7 +50,14 @@ pub(crate) fn path(root: &Path, slot: Slot) -> PathBuf {
 }
 
 pub(crate) fn load(root: &Path, slot: Slot) -> Result<Option<StoredState>> {
-    let path = path(root, slot);
+    load_file(&path(root, slot))
+}
+
+pub(crate) fn save(root: &Path, slot: Slot, state: &StoredState) -> Result<()> {
+    save_file(&path(root, slot), state)
+}
+
+pub(crate) fn load_file(path: &Path) -> Result<Option<StoredState>> {
     if !path.try_exists()? {
         return Ok(None);
     }
@@ -57,29 +65,50 @@ pub(crate) fn load(root: &Path, slot: Slot) -> Result<Option<StoredState>> {
     decode(&bytes).map(Some)
 }
 
-pub(crate) fn save(root: &Path, slot: Slot, state: &StoredState) -> Result<()> {
+pub(crate) fn save_file(path: &Path, state: &StoredState) -> Result<()> {
     let bytes = encode(state)?;
-    durability::publish(&path(root, slot), &bytes)
+    durability::publish(path, &bytes)
 }
 
-fn encode(state: &StoredState) -> Result<Vec<u8>> {
+pub(crate) fn encode(state: &StoredState) -> Result<Vec<u8>> {
     validate(state)?;
-    let body = revision::to_vec(state)?;
+    frame(MAGIC, &revision::to_vec(state)?)
+}
+
+pub(crate) fn decode(encoded: &[u8]) -> Result<StoredState> {
+    let state: StoredState = revision::from_slice(unframe(MAGIC, encoded)?)?;
+    validate(&state)?;
+    Ok(state)
+}
+
+/// Prefixes a body with its magic, format version, length, and checksum.
+pub(crate) fn frame(magic: &[u8; 8], body: &[u8]) -> Result<Vec<u8>> {
     if body.len() > MAX_STATE_BYTES {
         return Err(Error::invalid("state exceeds the maximum encoded size"));
     }
-    let checksum = blake3::hash(&body);
+    let checksum = blake3::hash(body);
     let mut encoded = Vec::with_capacity(HEADER_BYTES + body.len());
-    encoded.extend_from_slice(MAGIC);
+    encoded.extend_from_slice(magic);
     encoded.extend_from_slice(&VERSION.to_le_bytes());
     encoded.extend_from_slice(&(body.len() as u64).to_le_bytes());
     encoded.extend_from_slice(checksum.as_bytes());
-    encoded.extend_from_slice(&body);
+    encoded.extend_from_slice(body);
     Ok(encoded)
 }
 
-fn decode(encoded: &[u8]) -> Result<StoredState> {
-    if encoded.len() < HEADER_BYTES || &encoded[..8] != MAGIC {
+/// Validates a framed file and returns its checksummed body.
+pub(crate) fn unframe<'a>(magic: &[u8; 8], encoded: &'a [u8]) -> Result<&'a [u8]> {
+    let (body, rest) = split_frame(magic, encoded)?;
+    if !rest.is_empty() {
+        return Err(Error::invalid("state length is invalid"));
+    }
+    Ok(body)
+}
+
+/// Validates the first of several concatenated frames and returns its
+/// checksummed body and the bytes that follow it.
+pub(crate) fn split_frame<'a>(magic: &[u8; 8], encoded: &'a [u8]) -> Result<(&'a [u8], &'a [u8])> {
+    if encoded.len() < HEADER_BYTES || &encoded[..8] != magic {
         return Err(Error::NotAProject);
     }
     let version = u32::from_le_bytes(encoded[8..12].try_into().expect("fixed header range"));
@@ -89,17 +118,15 @@ fn decode(encoded: &[u8]) -> Result<StoredState> {
     let body_len = u64::from_le_bytes(encoded[12..20].try_into().expect("fixed header range"));
     let body_len = usize::try_from(body_len)
         .map_err(|_| Error::invalid("state length does not fit this platform"))?;
-    if body_len > MAX_STATE_BYTES || encoded.len() != HEADER_BYTES + body_len {
+    if body_len > MAX_STATE_BYTES || encoded.len() - HEADER_BYTES < body_len {
         return Err(Error::invalid("state length is invalid"));
     }
     let expected = &encoded[20..52];
-    let body = &encoded[HEADER_BYTES..];
+    let (body, rest) = encoded[HEADER_BYTES..].split_at(body_len);
     if blake3::hash(body).as_bytes() != expected {
         return Err(Error::invalid("state checksum mismatch"));
     }
-    let state: StoredState = revision::from_slice(body)?;
-    validate(&state)?;
-    Ok(state)
+    Ok((body, rest))
 }
 
 fn validate(state: &StoredState) -> Result<()> {
@@ -0,0 +1,435 @@
+use std::{
+    collections::BTreeSet,
+    fs::{self, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bytes::Bytes;
+use revision::revisioned;
+
+use crate::{
+    BlobId, Error, Result, Revision, durability,
+    format::{self, StoredState},
+    log::LogRecord,
+};
+
+const INDEX_MAGIC: &[u8; 8] = b"KHRHISTY";
+const LOG_MAGIC: &[u8; 8] = b"KHRHSLOG";
+const LOG_SUFFIX: &str = ".log.khr";
+const INDEX_FILE: &str = "index.khr";
+const MAX_CHECKPOINT_NAME_BYTES: usize = 4096;
+
+/// Selects the unnamed revisions that survive history pruning. Named
+/// checkpoints and the durable head are always retained.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Retention {
+    #[default]
+    All,
+    /// Retains this many of the newest revisions.
+    Latest(usize),
+    /// Retains revisions saved no longer than this ago.
+    Within(Duration),
+}
+
+/// One durable revision in the append-only project history.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub revision: Revision,
+    pub saved_at: SystemTime,
+    pub checkpoint: Option<String>,
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct StoredEntry {
+    revision: Revision,
+    saved_at: SystemTime,
+    checkpoint: Option<String>,
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug, Default)]
+struct StoredIndex {
+    entries: Vec<StoredEntry>,
+}
+
+/// One logged revision archived next to the checkpoint it extends.
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct StoredLogged {
+    checkpoint: Revision,
+    revision: Revision,
+    saved_at: SystemTime,
+    /// Blobs first referenced by this record. Earlier references are implied.
+    blobs: Vec<BlobId>,
+    payload: Vec<u8>,
+}
+
+/// One readable archived record and the file offset just past it.
+#[derive(Clone, Debug)]
+pub(crate) struct Logged {
+    pub(crate) record: LogRecord,
+    pub(crate) saved_at: SystemTime,
+    pub(crate) blobs: Vec<BlobId>,
+    pub(crate) end: u64,
+}
+
+/// A recorded checkpoint and the logged revisions archived on top of it.
+#[derive(Clone, Debug)]
+pub(crate) struct Group {
+    pub(crate) checkpoint: HistoryEntry,
+    pub(crate) logged: Vec<HistoryEntry>,
+    /// Blobs referenced by the logged revisions but not by the checkpoint.
+    pub(crate) blobs: BTreeSet<BlobId>,
+}
+
+impl Group {
+    fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
+        std::iter::once(&self.checkpoint).chain(&self.logged)
+    }
+}
+
+/// A recorded revision as the checkpoint it extends and the records that
+/// replay on top of it.
+#[derive(Clone, Debug)]
+pub(crate) struct Recorded {
+    pub(crate) stored: StoredState,
+    pub(crate) records: Vec<LogRecord>,
+    pub(crate) blobs: BTreeSet<BlobId>,
+}
+
+pub(crate) fn directory(root: &Path) -> PathBuf {
+    root.join("history")
+}
+
+pub(crate) fn path(root: &Path, revision: Revision) -> PathBuf {
+    directory(root).join(format!("{:020}.khr", revision.get()))
+}
+
+/// The archive of records logged on top of `checkpoint`.
+pub(crate) fn log_path(root: &Path, checkpoint: Revision) -> PathBuf {
+    directory(root).join(format!("{:020}{LOG_SUFFIX}", checkpoint.get()))
+}
+
+/// Appends a complete state to the history. Republishing a revision that never
+/// became the durable head replaces its content and forgets its name.
+pub(crate) fn record(root: &Path, state: &StoredState, saved_at: SystemTime) -> Result<()> {
+    format::save_file(&path(root, state.revision), state)?;
+    let mut entries = list(root)?;
+    entries.retain(|entry| entry.revision != state.revision);
+    entries.push(HistoryEntry {
+        revision: state.revision,
+        saved_at,
+        checkpoint: None,
+    });
+    save_index(root, entries)
+}
+
+/// Archives records appended to the log on top of `checkpoint`, so logged
+/// revisions stay restorable after a later checkpoint empties the log.
+/// `blobs` lists references new to the log and are attributed to the first
+/// record, like the log does. Archived records from `records[0]` on are
+/// replaced, since only a republished revision can reach them.
+pub(crate) fn record_log(
+    root: &Path,
+    checkpoint: Revision,
+    records: &[LogRecord],
+    mut blobs: Vec<BlobId>,
+    saved_at: SystemTime,
+) -> Result<()> {
+    let Some(first) = records.first() else {
+        return Ok(());
+    };
+    let path = log_path(root, checkpoint);
+    let kept = read_log(root, checkpoint)?
+        .iter()
+        .take_while(|logged| logged.record.revision() < first.revision())
+        .last()
+        .map_or(0, |logged| logged.end);
+    let mut encoded = Vec::new();
+    for record in records {
+        let stored = StoredLogged {
+            checkpoint,
+            revision: record.revision(),
+            saved_at,
+            blobs: std::mem::take(&mut blobs),
+            payload: record.payload().to_vec(),
+        };
+        encoded.extend(format::frame(LOG_MAGIC, &revision::to_vec(&stored)?)?);
+    }
+    let created = !path.try_exists()?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    file.set_len(kept)?;
+    file.seek(SeekFrom::Start(kept))?;
+    file.write_all(&encoded)?;
+    file.sync_all()?;
+    if created {
+        durability::sync_parent(&path)?;
+    }
+    Ok(())
+}
+
+/// Reads the records archived on top of `checkpoint`, stopping at the first
+/// torn, corrupt, foreign, or out-of-order frame.
+pub(crate) fn read_log(root: &Path, checkpoint: Revision) -> Result<Vec<Logged>> {
+    let bytes = match fs::read(log_path(root, checkpoint)) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut logged = Vec::new();
+    let mut rest = bytes.as_slice();
+    let mut previous = checkpoint;
+    while !rest.is_empty() {
+        let Ok((body, next)) = format::split_frame(LOG_MAGIC, rest) else {
+            break;
+        };
+        let Ok(stored) = revision::from_slice::<StoredLogged>(body) else {
+            break;
+        };
+        if stored.checkpoint != checkpoint || stored.revision <= previous {
+            break;
+        }
+        rest = next;
+        previous = stored.revision;
+        logged.push(Logged {
+            record: LogRecord::new(stored.revision, Bytes::from(stored.payload)),
+            saved_at: stored.saved_at,
+            blobs: stored.blobs,
+            end: (bytes.len() - rest.len()) as u64,
+        });
+    }
+    Ok(logged)
+}
+
+/// Loads a recorded checkpoint. Logged revisions are loaded with
+/// [`load_recorded`].
+pub(crate) fn load(root: &Path, revision: Revision) -> Result<StoredState> {
+    format::load_file(&path(root, revision))?.ok_or(Error::RevisionNotFound(revision))
+}
+
+/// Loads any recorded revision, logged or not.
+pub(crate) fn load_recorded(root: &Path, revision: Revision) -> Result<Recorded> {
+    let checkpoint = groups(root)?
+        .into_iter()
+        .find(|group| group.entries().any(|entry| entry.revision == revision))
+        .ok_or(Error::RevisionNotFound(revision))?
+        .checkpoint
+        .revision;
+    let stored = load(root, checkpoint)?;
+    let mut blobs = stored.blobs.iter().copied().collect::<BTreeSet<_>>();
+    let mut records = Vec::new();
+    if checkpoint != revision {
+        for logged in read_log(root, checkpoint)? {
+            if logged.record.revision() > revision {
+                break;
+            }
+            blobs.extend(logged.blobs);
+            records.push(logged.record);
+        }
+    }
+    Ok(Recorded {
+        stored,
+        records,
+        blobs,
+    })
+}
+
+/// Lists recorded revisions in ascending order, logged revisions included.
+pub(crate) fn list(root: &Path) -> Result<Vec<HistoryEntry>> {
+    Ok(groups(root)?
+        .into_iter()
+        .flat_map(|group| std::iter::once(group.checkpoint).chain(group.logged))
+        .collect())
+}
+
+/// Lists recorded checkpoints in ascending order, each with its archived log.
+/// The index only carries metadata; revision files decide which entries
+/// exist, so an interrupted save or a damaged index never hides or invents a
+/// revision. Archived records at or past the next checkpoint are stale and
+/// skipped.
+pub(crate) fn groups(root: &Path) -> Result<Vec<Group>> {
+    let directory = directory(root);
+    if !directory.try_exists()? {
+        return Ok(Vec::new());
+    }
+    let index = match fs::read(directory.join(INDEX_FILE)) {
+        Ok(bytes) => decode_index(&bytes).unwrap_or_else(|error| {
+            tracing::warn!(%error, "project history index is unreadable; rebuilding it");
+            StoredIndex::default()
+        }),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => StoredIndex::default(),
+        Err(error) => return Err(error.into()),
+    };
+
+    let entry = |revision: Revision, saved_at: SystemTime| match index
+        .entries
+        .iter()
+        .find(|candidate| candidate.revision == revision)
+    {
+        Some(indexed) => HistoryEntry {
+            revision,
+            saved_at: indexed.saved_at,
+            checkpoint: indexed.checkpoint.clone(),
+        },
+        None => HistoryEntry {
+            revision,
+            saved_at,
+            checkpoint: None,
+        },
+    };
+
+    let mut groups = Vec::new();
+    for file in fs::read_dir(&directory)? {
+        let file = file?;
+        let name = file.file_name();
+        let Some(revision) = name
+            .to_str()
+            .filter(|name| !name.ends_with(LOG_SUFFIX))
+            .and_then(|name| name.strip_suffix(".khr"))
+            .and_then(|stem| stem.parse::<u64>().ok())
+            .map(Revision::new)
+        else {
+            continue;
+        };
+        groups.push(Group {
+            checkpoint: entry(revision, file.metadata()?.modified()?),
+            logged: Vec::new(),
+            blobs: BTreeSet::new(),
+        });
+    }
+    groups.sort_by_key(|group| group.checkpoint.revision);
+    let next = groups
+        .iter()
+        .skip(1)
+        .map(|group| Some(group.checkpoint.revision))
+        .chain([None])
+        .collect::<Vec<_>>();
+    for (group, next) in groups.iter_mut().zip(next) {
+        for logged in read_log(root, group.checkpoint.revision)? {
+            if next.is_some_and(|next| logged.record.revision() >= next) {
+                break;
+            }
+            group.blobs.extend(logged.blobs);
+            group
+                .logged
+                .push(entry(logged.record.revision(), logged.saved_at));
+        }
+    }
+    Ok(groups)
+}
+
+pub(crate) fn set_checkpoint(
+    root: &Path,
+    revision: Revision,
+    checkpoint: Option<String>,
+) -> Result<()> {
+    if let Some(name) = &checkpoint
+        && (name.trim().is_empty() || name.len() > MAX_CHECKPOINT_NAME_BYTES || name.contains('\0'))
+    {
+        return Err(Error::invalid("checkpoint name is invalid"));
+    }
+    let mut entries = list(root)?;
+    let entry = entries
+        .iter_mut()
+        .find(|entry| entry.revision == revision)
+        .ok_or(Error::RevisionNotFound(revision))?;
+    entry.checkpoint = checkpoint;
+    save_index(root, entries)
+}
+
+/// Removes unnamed revisions outside the retention policy and returns how
+/// many were removed. Logged revisions replay on their checkpoint, so a
+/// checkpoint and its archived log are kept or removed together. `head` is
+/// the checkpoint of the durable head.
+pub(crate) fn prune(
+    root: &Path,
+    retention: Retention,
+    head: Revision,
+    now: SystemTime,
+) -> Result<usize> {
+    let groups = groups(root)?;
+    let total = groups
+        .iter()
+        .map(|group| group.entries().count())
+        .sum::<usize>();
+    let latest = match retention {
+        Retention::Latest(count) => total.saturating_sub(count),
+        Retention::All | Retention::Within(_) => 0,
+    };
+    let mut index = 0;
+    let (retained, removed): (Vec<_>, Vec<_>) = groups.into_iter().partition(|group| {
+        let kept = group.entries().enumerate().any(|(offset, entry)| {
+            entry.checkpoint.is_some()
+                || entry.revision == head
+                || match retention {
+                    Retention::All => true,
+                    Retention::Latest(_) => index + offset >= latest,
+                    Retention::Within(age) => now
+                        .duration_since(entry.saved_at)
+                        .is_ok_and(|elapsed| elapsed <= age),
+                }
+        });
+        index += group.entries().count();
+        kept
+    });
+    if removed.is_empty() {
+        return Ok(0);
+    }
+    // Shrink the index first so an interrupted prune leaves only unindexed
+    // files, which the next listing adopts again rather than losing names.
+    save_index(
+        root,
+        retained
+            .into_iter()
+            .flat_map(|group| std::iter::once(group.checkpoint).chain(group.logged))
+            .collect(),
+    )?;
+    for group in &removed {
+        for target in [
+            log_path(root, group.checkpoint.revision),
+            path(root, group.checkpoint.revision),
+        ] {
+            if target.try_exists()? {
+                fs::remove_file(target)?;
+            }
+        }
+    }
+    Ok(removed.iter().map(|group| group.entries().count()).sum())
+}
+
+/// Collects blobs referenced by every recorded revision and fails on the
+/// first checkpoint that cannot be read.
+pub(crate) fn referenced_blobs(root: &Path) -> Result<BTreeSet<BlobId>> {
+    let mut referenced = BTreeSet::new();
+    for group in groups(root)? {
+        referenced.extend(load(root, group.checkpoint.revision)?.blobs);
+        referenced.extend(group.blobs);
+    }
+    Ok(referenced)
+}
+
+fn save_index(root: &Path, entries: Vec<HistoryEntry>) -> Result<()> {
+    let index = StoredIndex {
+        entries: entries
+            .into_iter()
+            .map(|entry| StoredEntry {
+                revision: entry.revision,
+                saved_at: entry.saved_at,
+                checkpoint: entry.checkpoint,
+            })
+            .collect(),
+    };
+    let bytes = format::frame(INDEX_MAGIC, &revision::to_vec(&index)?)?;
+    durability::publish(&directory(root).join(INDEX_FILE), &bytes)
+}
+
+fn decode_index(bytes: &[u8]) -> Result<StoredIndex> {
+    revision::from_slice(format::unframe(INDEX_MAGIC, bytes)?).map_err(Into::into)
+}
@@ -0,0 +1,264 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    BlobId, Result, Revision,
+    blobs::BlobStore,
+    codec, durability,
+    format::{self, Slot, StoredState},
+    history, journal, log,
+};
+
+/// The condition of one alternating state slot.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SlotStatus {
+    Missing,
+    /// The file exists but its header, checksum, or encoding is invalid.
+    Corrupt(String),
+    /// The state decodes but references missing or corrupt blobs.
+    Incomplete(Revision),
+    Valid(Revision),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SlotReport {
+    pub file: &'static str,
+    pub status: SlotStatus,
+}
+
+/// The result of rehashing every blob and decoding every durable state.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IntegrityReport {
+    pub slots: Vec<SlotReport>,
+    /// Recorded history revisions that cannot be decoded.
+    pub unreadable_revisions: Vec<Revision>,
+    /// Blobs referenced by a slot or history revision without a file.
+    pub missing_blobs: Vec<BlobId>,
+    /// Blob files whose decoded bytes do not hash to their ID.
+    pub corrupt_blobs: Vec<BlobId>,
+    /// Blob files no durable state references. Collection removes them.
+    pub orphaned_blobs: Vec<BlobId>,
+}
+
+impl IntegrityReport {
+    /// Orphaned blobs are wasted space, not damage, and do not count.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.slots
+            .iter()
+            .all(|slot| matches!(slot.status, SlotStatus::Missing | SlotStatus::Valid(_)))
+            && self
+                .slots
+                .iter()
+                .any(|slot| matches!(slot.status, SlotStatus::Valid(_)))
+            && self.unreadable_revisions.is_empty()
+            && self.missing_blobs.is_empty()
+            && self.corrupt_blobs.is_empty()
+    }
+}
+
+/// What a repair changed. `integrity` describes the project before repair.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepairReport {
+    pub integrity: IntegrityReport,
+    pub head: Revision,
+    /// The newest revision found before repair, when repair had to fall back.
+    pub abandoned: Option<Revision>,
+    pub quarantined_blobs: Vec<BlobId>,
+    pub quarantined_slots: Vec<&'static str>,
+    pub quarantined_revisions: Vec<Revision>,
+    /// Whether log records that no longer extend the head were moved aside.
+    pub quarantined_log: bool,
+    /// Whether a journal for another head or with missing blobs was moved aside.
+    pub quarantined_journal: bool,
+}
+
+pub(crate) fn quarantine_directory(root: &Path) -> PathBuf {
+    root.join("quarantine")
+}
+
+pub(crate) fn verify(root: &Path, store: &BlobStore) -> Result<IntegrityReport> {
+    let mut report = IntegrityReport::default();
+    let mut present = BTreeSet::new();
+    for (id, path) in store.files()? {
+        let intact = fs::read(&path).is_ok_and(|file| {
+            codec::decode(&file).is_ok_and(|bytes| BlobId::for_bytes(&bytes) == id)
+        });
+        if intact {
+            present.insert(id);
+        } else {
+            report.corrupt_blobs.push(id);
+        }
+    }
+
+    let mut referenced = BTreeSet::new();
+    for slot in [Slot::A, Slot::B] {
+        let status = match format::load(root, slot) {
+            Ok(None) => SlotStatus::Missing,
+            Err(error) => SlotStatus::Corrupt(error.to_string()),
+            Ok(Some(state)) => {
+                referenced.extend(state.blobs.iter().copied());
+                for entry in log::read(root, state.document, state.revision)? {
+                    referenced.extend(entry.blobs);
+                }
+                if complete(&state, &present) {
+                    SlotStatus::Valid(state.revision)
+                } else {
+                    SlotStatus::Incomplete(state.revision)
+                }
+            }
+        };
+        report.slots.push(SlotReport {
+            file: slot.filename(),
+            status,
+        });
+    }
+    referenced.extend(journal::referenced_blobs(root).unwrap_or_default());
+    for group in history::groups(root)? {
+        match history::load(root, group.checkpoint.revision) {
+            Ok(state) => referenced.extend(state.blobs),
+            Err(_) => report.unreadable_revisions.push(group.checkpoint.revision),
+        }
+        referenced.extend(group.blobs);
+    }
+
+    let corrupt = report
+        .corrupt_blobs
+        .iter()
+        .copied()
+        .collect::<BTreeSet<_>>();
+    report.missing_blobs = referenced
+        .iter()
+        .filter(|id| !present.contains(id) && !corrupt.contains(id))
+        .copied()
+        .collect();
+    report.orphaned_blobs = present
+        .iter()
+        .filter(|id| !referenced.contains(id))
+        .copied()
+        .collect();
+    report.corrupt_blobs.sort_unstable();
+    Ok(report)
+}
+
+/// Moves damaged files aside during one repair. Every file keeps its name
+/// with the repair time appended, so a later repair never replaces what an
+/// earlier one kept.
+pub(crate) struct Quarantine<'a> {
+    root: &'a Path,
+    stamp: u128,
+}
+
+impl<'a> Quarantine<'a> {
+    pub(crate) fn new(root: &'a Path, now: SystemTime) -> Self {
+        Self {
+            root,
+            stamp: now
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis()),
+        }
+    }
+
+    /// Moves corrupt blob files aside so that no state can map them. They are
+    /// kept for inspection rather than deleted.
+    pub(crate) fn blobs(&self, store: &BlobStore, corrupt: &[BlobId]) -> Result<Vec<BlobId>> {
+        let mut moved = Vec::new();
+        for id in corrupt {
+            if self.move_file(&store.path(*id), Some("blobs"), &id.to_string())? {
+                moved.push(*id);
+            }
+        }
+        Ok(moved)
+    }
+
+    /// Moves unnamed recorded revisions newer than `head` aside. After a
+    /// fallback their revision numbers are reused by the next saves. Named
+    /// checkpoints stay in the history, and so does every revision they
+    /// replay on. An archived log that outlives `head` is moved aside whole
+    /// and its retained prefix written back.
+    pub(crate) fn history_after(&self, head: Revision) -> Result<Vec<Revision>> {
+        let mut moved = Vec::new();
+        for group in history::groups(self.root)? {
+            let entries = std::iter::once(&group.checkpoint)
+                .chain(&group.logged)
+                .collect::<Vec<_>>();
+            let retained = entries
+                .iter()
+                .rposition(|entry| entry.revision <= head || entry.checkpoint.is_some());
+            let abandoned = entries[retained.map_or(0, |last| last + 1)..]
+                .iter()
+                .map(|entry| entry.revision)
+                .collect::<Vec<_>>();
+            if abandoned.is_empty() {
+                continue;
+            }
+            let checkpoint = group.checkpoint.revision;
+            let source = history::log_path(self.root, checkpoint);
+            let kept = match retained {
+                Some(last) if last > 0 => {
+                    let end = history::read_log(self.root, checkpoint)?[last - 1].end;
+                    Some(fs::read(&source)?[..end as usize].to_vec())
+                }
+                _ => None,
+            };
+            let name = source.file_name().expect("history file name").to_owned();
+            self.move_file(&source, Some("history"), &name.to_string_lossy())?;
+            if let Some(kept) = kept {
+                durability::publish(&source, &kept)?;
+            }
+            if retained.is_none() {
+                let source = history::path(self.root, checkpoint);
+                let name = source.file_name().expect("history file name").to_owned();
+                self.move_file(&source, Some("history"), &name.to_string_lossy())?;
+            }
+            moved.extend(abandoned);
+        }
+        Ok(moved)
+    }
+
+    pub(crate) fn slot(&self, slot: Slot) -> Result<()> {
+        self.move_file(&format::path(self.root, slot), None, slot.filename())?;
+        Ok(())
+    }
+
+    /// Moves a non-empty log aside. Returns whether anything was moved.
+    pub(crate) fn log(&self) -> Result<bool> {
+        let source = log::path(self.root);
+        if !source.try_exists()? || source.metadata()?.len() == 0 {
+            return Ok(false);
+        }
+        self.move_file(&source, None, log::FILENAME)
+    }
+
+    pub(crate) fn journal(&self) -> Result<()> {
+        self.move_file(&journal::path(self.root), None, journal::FILENAME)?;
+        Ok(())
+    }
+
+    fn move_file(&self, source: &Path, directory: Option<&str>, name: &str) -> Result<bool> {
+        if !source.try_exists()? {
+            return Ok(false);
+        }
+        let mut directory_path = quarantine_directory(self.root);
+        if let Some(directory) = directory {
+            directory_path.push(directory);
+        }
+        fs::create_dir_all(&directory_path)?;
+        let mut target = directory_path.join(format!("{name}.{}", self.stamp));
+        let mut attempt = 1;
+        while target.try_exists()? {
+            attempt += 1;
+            target = directory_path.join(format!("{name}.{}.{attempt}", self.stamp));
+        }
+        fs::rename(source, target)?;
+        Ok(true)
+    }
+}
+
+fn complete(state: &StoredState, present: &BTreeSet<BlobId>) -> bool {
+    state.blobs.iter().all(|id| present.contains(id))
+}
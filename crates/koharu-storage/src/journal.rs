@@ -0,0 +1,163 @@
+use std::{
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use revision::revisioned;
+
+use crate::{
+    BlobId, Blobs, DocumentId, Result, Revision, durability,
+    format::{self, MAX_ENCODED_BYTES},
+};
+
+const MAGIC: &[u8; 8] = b"KHRJRNAL";
+const RECORD_MAGIC: &[u8; 8] = b"KHRJRREC";
+pub(crate) const FILENAME: &str = "journal.khr";
+
+/// Opaque data that describes one durable head, such as undo history. The
+/// journal is a base payload followed by records appended for later heads. It
+/// is replaced as a whole to compact it, and retains its blobs through
+/// collection.
+#[derive(Clone, Debug)]
+pub struct Journal {
+    revision: Revision,
+    payload: Bytes,
+    records: Vec<Bytes>,
+    blobs: Blobs,
+}
+
+impl Journal {
+    pub(crate) const fn new(
+        revision: Revision,
+        payload: Bytes,
+        records: Vec<Bytes>,
+        blobs: Blobs,
+    ) -> Self {
+        Self {
+            revision,
+            payload,
+            records,
+            blobs,
+        }
+    }
+
+    /// The head revision the journal was last written for.
+    #[must_use]
+    pub const fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    /// The payload the journal was last replaced with.
+    #[must_use]
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+
+    /// Payloads appended after [`Journal::payload`], oldest first.
+    #[must_use]
+    pub fn records(&self) -> &[Bytes] {
+        &self.records
+    }
+
+    #[must_use]
+    pub const fn blobs(&self) -> &Blobs {
+        &self.blobs
+    }
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+pub(crate) struct StoredJournal {
+    pub(crate) document: DocumentId,
+    pub(crate) revision: Revision,
+    pub(crate) blobs: Vec<BlobId>,
+    pub(crate) payload: Vec<u8>,
+}
+
+/// A journal as read from disk: its base, every readable record after it, and
+/// the file offset just past the last of them.
+#[derive(Clone, Debug)]
+pub(crate) struct Loaded {
+    pub(crate) document: DocumentId,
+    /// The revision of the newest readable record, or of the base.
+    pub(crate) revision: Revision,
+    /// Blobs referenced by the base or by any readable record.
+    pub(crate) blobs: Vec<BlobId>,
+    pub(crate) payload: Vec<u8>,
+    pub(crate) records: Vec<Vec<u8>>,
+    pub(crate) end: u64,
+}
+
+pub(crate) fn path(root: &Path) -> PathBuf {
+    root.join(FILENAME)
+}
+
+/// Reads the journal. An unreadable base is an error; reading stops quietly
+/// at the first torn, foreign, or out-of-order record.
+pub(crate) fn load(root: &Path) -> Result<Option<Loaded>> {
+    let path = path(root);
+    if !path.try_exists()? {
+        return Ok(None);
+    }
+    if path.metadata()?.len() > MAX_ENCODED_BYTES as u64 {
+        return Err(crate::Error::invalid("journal length is invalid"));
+    }
+    let bytes = std::fs::read(path)?;
+    let (body, mut rest) = format::split_frame(MAGIC, &bytes)?;
+    let base: StoredJournal = revision::from_slice(body)?;
+    let mut loaded = Loaded {
+        document: base.document,
+        revision: base.revision,
+        blobs: base.blobs,
+        payload: base.payload,
+        records: Vec::new(),
+        end: (bytes.len() - rest.len()) as u64,
+    };
+    while !rest.is_empty() {
+        let Ok((body, next)) = format::split_frame(RECORD_MAGIC, rest) else {
+            break;
+        };
+        let Ok(record) = revision::from_slice::<StoredJournal>(body) else {
+            break;
+        };
+        if record.document != loaded.document || record.revision <= loaded.revision {
+            break;
+        }
+        rest = next;
+        loaded.revision = record.revision;
+        loaded.blobs.extend(record.blobs);
+        loaded.records.push(record.payload);
+        loaded.end = (bytes.len() - rest.len()) as u64;
+    }
+    loaded.blobs.sort_unstable();
+    loaded.blobs.dedup();
+    Ok(Some(loaded))
+}
+
+/// Replaces the journal with a single base and returns its length.
+pub(crate) fn save(root: &Path, journal: &StoredJournal) -> Result<u64> {
+    let bytes = format::frame(MAGIC, &revision::to_vec(journal)?)?;
+    durability::publish(&path(root), &bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+/// Appends one record at `end`, the length of the journal it extends, and
+/// returns the new length. A torn record from an earlier failed append is
+/// overwritten.
+pub(crate) fn append(root: &Path, end: u64, record: &StoredJournal) -> Result<u64> {
+    let bytes = format::frame(RECORD_MAGIC, &revision::to_vec(record)?)?;
+    let mut file = OpenOptions::new().write(true).open(path(root))?;
+    file.set_len(end)?;
+    file.seek(SeekFrom::Start(end))?;
+    file.write_all(&bytes)?;
+    file.sync_data()?;
+    Ok(end + bytes.len() as u64)
+}
+
+/// Blobs referenced by the journal. An unreadable journal is an error, since
+/// collection cannot tell which blobs it still needs.
+pub(crate) fn referenced_blobs(root: &Path) -> Result<Vec<BlobId>> {
+    Ok(load(root)?.map(|journal| journal.blobs).unwrap_or_default())
+}
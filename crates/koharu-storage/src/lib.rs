@@ -1,17 +1,27 @@
 //! Filesystem-native project snapshots and immutable content-addressed blobs.
 
 mod blobs;
+mod bundle;
+mod codec;
 mod durability;
 mod error;
 mod format;
+mod history;
 mod ids;
+mod integrity;
+mod journal;
+mod log;
 mod session;
 
 pub use blobs::Blobs;
 pub use bytes::Bytes;
 pub use error::{Error, Result};
+pub use history::{HistoryEntry, Retention};
 pub use ids::{BlobId, DocumentId, PatchId, Revision};
-pub use session::{GcReport, Session, State};
+pub use integrity::{IntegrityReport, RepairReport, SlotReport, SlotStatus};
+pub use journal::Journal;
+pub use log::LogRecord;
+pub use session::{CompressionReport, GcReport, Session, State};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,166 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use revision::revisioned;
+
+use crate::{BlobId, DocumentId, Result, Revision, format};
+
+const MAGIC: &[u8; 8] = b"KHRWALOG";
+pub(crate) const FILENAME: &str = "log.khr";
+
+/// One opaque record appended after a checkpoint. Storage never interprets
+/// the payload; its owning layer replays records in order on top of the
+/// checkpoint payload.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LogRecord {
+    revision: Revision,
+    payload: Bytes,
+}
+
+impl LogRecord {
+    pub(crate) const fn new(revision: Revision, payload: Bytes) -> Self {
+        Self { revision, payload }
+    }
+
+    #[must_use]
+    pub const fn revision(&self) -> Revision {
+        self.revision
+    }
+
+    #[must_use]
+    pub fn payload(&self) -> &Bytes {
+        &self.payload
+    }
+}
+
+#[revisioned(revision = 1)]
+#[derive(Clone, Debug)]
+struct StoredRecord {
+    document: DocumentId,
+    checkpoint: Revision,
+    revision: Revision,
+    /// Blobs first referenced by this record. Earlier references are implied.
+    blobs: Vec<BlobId>,
+    payload: Vec<u8>,
+}
+
+/// One readable record and the file offset just past it.
+#[derive(Clone, Debug)]
+pub(crate) struct Entry {
+    pub(crate) record: LogRecord,
+    pub(crate) blobs: Vec<BlobId>,
+    pub(crate) end: u64,
+}
+
+pub(crate) fn path(root: &Path) -> PathBuf {
+    root.join(FILENAME)
+}
+
+/// Reads the records that extend `checkpoint` of `document`, stopping at the
+/// first torn, corrupt, foreign, or out-of-order frame.
+pub(crate) fn read(root: &Path, document: DocumentId, checkpoint: Revision) -> Result<Vec<Entry>> {
+    let path = path(root);
+    if !path.try_exists()? {
+        return Ok(Vec::new());
+    }
+    let bytes = fs::read(path)?;
+    let mut entries = Vec::new();
+    let mut rest = bytes.as_slice();
+    let mut previous = checkpoint;
+    while !rest.is_empty() {
+        let Ok((body, next)) = format::split_frame(MAGIC, rest) else {
+            break;
+        };
+        let Ok(stored) = revision::from_slice::<StoredRecord>(body) else {
+            break;
+        };
+        if stored.document != document
+            || stored.checkpoint != checkpoint
+            || stored.revision <= previous
+        {
+            break;
+        }
+        rest = next;
+        previous = stored.revision;
+        entries.push(Entry {
+            record: LogRecord::new(stored.revision, Bytes::from(stored.payload)),
+            blobs: stored.blobs,
+            end: (bytes.len() - rest.len()) as u64,
+        });
+    }
+    Ok(entries)
+}
+
+/// The append handle of the project log. Callers serialize access through the
+/// session writer.
+pub(crate) struct Writer {
+    file: File,
+    len: u64,
+}
+
+impl Writer {
+    /// Opens the log and discards everything after the first `len` bytes,
+    /// which callers obtain from [`read`].
+    pub(crate) fn open(root: &Path, len: u64) -> Result<Self> {
+        let path = path(root);
+        let created = !path.try_exists()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .read(true)
+            .write(true)
+            .open(&path)?;
+        if created {
+            file.sync_all()?;
+            crate::durability::sync_parent(&path)?;
+        }
+        if file.metadata()?.len() > len {
+            file.set_len(len)?;
+            file.sync_all()?;
+        }
+        Ok(Self { file, len })
+    }
+
+    /// Appends records and makes them durable. `blobs` lists references new
+    /// to the log and are attributed to the first record, so any replayed
+    /// prefix over-approximates rather than misses a reference.
+    pub(crate) fn append(
+        &mut self,
+        document: DocumentId,
+        checkpoint: Revision,
+        records: &[LogRecord],
+        mut blobs: Vec<BlobId>,
+    ) -> Result<()> {
+        let mut encoded = Vec::new();
+        for record in records {
+            let stored = StoredRecord {
+                document,
+                checkpoint,
+                revision: record.revision,
+                blobs: std::mem::take(&mut blobs),
+                payload: record.payload.to_vec(),
+            };
+            encoded.extend(format::frame(MAGIC, &revision::to_vec(&stored)?)?);
+        }
+        // A failed write may leave a torn tail. It is overwritten by the next
+        // append and never replayed because its frame does not validate.
+        self.file.seek(SeekFrom::Start(self.len))?;
+        self.file.write_all(&encoded)?;
+        self.file.sync_data()?;
+        self.len += encoded.len() as u64;
+        Ok(())
+    }
+
+    /// Discards every record once a checkpoint that contains them is durable.
+    /// Appends restart at the beginning even if truncation fails.
+    pub(crate) fn reset(&mut self) -> Result<()> {
+        self.len = 0;
+        self.file.set_len(0)?;
+        self.file.sync_all()?;
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     fs::{self, File, OpenOptions},
     path::{Path, PathBuf},
     sync::Arc,
+    time::SystemTime,
 };
 
 use bytes::Bytes;
@@ -14,19 +15,52 @@ use tokio::sync::Mutex;
 use crate::{
     BlobId, Blobs, DocumentId, Error, Result, Revision,
     blobs::BlobStore,
+    bundle,
     format::{self, Slot, StoredState},
+    history::{self, HistoryEntry, Retention},
+    integrity::{self, IntegrityReport, Quarantine, RepairReport},
+    journal::{self, Journal, StoredJournal},
+    log::{self, LogRecord},
 };
 
 #[derive(Clone, Debug)]
 struct Head {
     slot: Slot,
+    /// The checkpoint published in `slot`.
     stored: Arc<StoredState>,
+    /// Records appended to the log since that checkpoint.
+    log: Arc<[LogRecord]>,
+    /// Blobs referenced by the checkpoint or by any logged record.
+    referenced: Arc<BTreeSet<BlobId>>,
+}
+
+impl Head {
+    fn checkpoint(slot: Slot, stored: StoredState) -> Self {
+        let referenced = stored.blobs.iter().copied().collect();
+        Self {
+            slot,
+            stored: Arc::new(stored),
+            log: Arc::from([]),
+            referenced: Arc::new(referenced),
+        }
+    }
+
+    fn revision(&self) -> Revision {
+        self.log
+            .last()
+            .map_or(self.stored.revision, LogRecord::revision)
+    }
 }
 
 struct Inner {
     root: PathBuf,
     blobs: Arc<BlobStore>,
     head: RwLock<Head>,
+    log: parking_lot::Mutex<log::Writer>,
+    /// The revision and length of the journal as this session last read or
+    /// wrote it. Appends require it, so they never extend a journal that
+    /// changed underneath.
+    journal: parking_lot::Mutex<Option<(Revision, u64)>>,
     writer: Mutex<()>,
 }
 
@@ -42,18 +76,24 @@ impl std::fmt::Debug for Session {
         formatter
             .debug_struct("Session")
             .field("document", &head.stored.document)
-            .field("revision", &head.stored.revision)
+            .field("revision", &head.revision())
+            .field("logged", &head.log.len())
             .field("root", &self.inner.root)
             .finish_non_exhaustive()
     }
 }
 
 /// One immutable serialized scene state and its complete blob closure.
+///
+/// A state is a checkpoint payload followed by zero or more log records. The
+/// owning layer reconstructs the revision by replaying the records in order.
 #[derive(Clone, Debug)]
 pub struct State {
     document: DocumentId,
     revision: Revision,
     payload: Bytes,
+    checkpoint: Revision,
+    log: Arc<[LogRecord]>,
     blobs: Blobs,
 }
 
@@ -68,11 +108,23 @@ impl State {
         self.revision
     }
 
+    /// The checkpoint payload. Records in [`State::log`] apply on top of it.
     #[must_use]
     pub fn payload(&self) -> &Bytes {
         &self.payload
     }
 
+    /// The revision whose complete payload is [`State::payload`].
+    #[must_use]
+    pub const fn checkpoint_revision(&self) -> Revision {
+        self.checkpoint
+    }
+
+    #[must_use]
+    pub fn log(&self) -> &[LogRecord] {
+        &self.log
+    }
+
     #[must_use]
     pub const fn blobs(&self) -> &Blobs {
         &self.blobs
@@ -94,6 +146,38 @@ impl State {
             document: self.document,
             revision,
             payload,
+            checkpoint: revision,
+            log: Arc::from([]),
+            blobs,
+        })
+    }
+
+    /// Builds the next state by appending one opaque record to this state's
+    /// log. `referenced` lists blobs the record newly references. Blobs that
+    /// the record stops referencing stay in scope until the next checkpoint.
+    pub fn append(
+        &self,
+        revision: Revision,
+        record: Bytes,
+        referenced: impl IntoIterator<Item = BlobId>,
+        available: impl IntoIterator<Item = (BlobId, Bytes)>,
+    ) -> Result<Self> {
+        if revision <= self.revision {
+            return Err(Error::invalid(
+                "appended revision does not follow the state",
+            ));
+        }
+        let referenced = self.blobs.ids().chain(referenced).collect::<BTreeSet<_>>();
+        let blobs = self.blobs.derive(referenced, available)?;
+        let mut log = Vec::with_capacity(self.log.len() + 1);
+        log.extend(self.log.iter().cloned());
+        log.push(LogRecord::new(revision, record));
+        Ok(Self {
+            document: self.document,
+            revision,
+            payload: self.payload.clone(),
+            checkpoint: self.checkpoint,
+            log: log.into(),
             blobs,
         })
     }
@@ -105,6 +189,12 @@ pub struct GcReport {
     pub bytes: u64,
 }
 
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompressionReport {
+    pub blobs: usize,
+    pub bytes: u64,
+}
+
 impl Session {
     #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display(), document = %document))]
     pub async fn create(
@@ -140,6 +230,39 @@ impl Session {
         .map_err(|error| Error::Task(error.to_string()))?
     }
 
+    /// Recreates a project at `path` from a bundle written by
+    /// [`Session::export_bundle`]. Every blob is rehashed against its ID, and
+    /// `accept` must approve the unpacked state, before the state is
+    /// published, so a damaged or rejected bundle never becomes a project.
+    #[tracing::instrument(level = "info", skip_all, fields(bundle = %bundle.as_ref().display(), path = %path.as_ref().display()))]
+    pub async fn import_bundle(
+        bundle: impl AsRef<Path>,
+        path: impl AsRef<Path>,
+        accept: impl FnOnce(&State) -> bool + Send + 'static,
+    ) -> Result<Self> {
+        let bundle = bundle.as_ref().to_owned();
+        let path = path.as_ref().to_owned();
+        tokio::task::spawn_blocking(move || import_project(&bundle, path, accept))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Repairs a closed project in place. Corrupt blobs are quarantined, and
+    /// the head becomes the newest slot or history revision whose blobs are
+    /// intact and whose payload `accept` approves. Slots that would shadow it
+    /// on open are quarantined rather than deleted. Named checkpoints newer
+    /// than that state are kept, and the head is renumbered past them.
+    #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn repair(
+        path: impl AsRef<Path>,
+        accept: impl FnMut(&State) -> bool + Send + 'static,
+    ) -> Result<RepairReport> {
+        let path = path.as_ref().to_owned();
+        tokio::task::spawn_blocking(move || repair_project(path, accept))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))?
+    }
+
     #[must_use]
     pub fn document_id(&self) -> DocumentId {
         self.inner.head.read().stored.document
@@ -147,25 +270,68 @@ impl Session {
 
     #[must_use]
     pub fn revision(&self) -> Revision {
-        self.inner.head.read().stored.revision
+        self.inner.head.read().revision()
     }
 
     pub async fn load(&self) -> Result<State> {
-        let stored = self.inner.head.read().stored.clone();
-        let referenced = stored.blobs.iter().copied().collect();
-        let blobs = self.inner.blobs.durable_scope(referenced)?;
+        let head = self.inner.head.read().clone();
+        let blobs = self
+            .inner
+            .blobs
+            .durable_scope(head.referenced.as_ref().clone())?;
         Ok(State {
-            document: stored.document,
-            revision: stored.revision,
-            payload: Bytes::from(stored.payload.clone()),
+            document: head.stored.document,
+            revision: head.revision(),
+            payload: Bytes::from(head.stored.payload.clone()),
+            checkpoint: head.stored.revision,
+            log: head.log.clone(),
             blobs,
         })
     }
 
-    /// Publishes all missing blob contents and then the complete state. The
-    /// returned state has a durable blob scope and drops pending byte owners.
+    /// Publishes all missing blob contents and then the state. A state whose
+    /// log extends the durable head only appends its new records; any other
+    /// state must be a complete checkpoint. The returned state has a durable
+    /// blob scope and drops pending byte owners.
     #[tracing::instrument(level = "info", skip_all, fields(document = %state.document, revision = %state.revision))]
     pub async fn save(&self, state: &State) -> Result<State> {
+        self.check_ownership(state)?;
+        let _writer = self.inner.writer.lock().await;
+        let current = self.inner.head.read().clone();
+        if state.revision <= current.revision() {
+            return Err(Error::RevisionConflict {
+                current: current.revision(),
+                proposed: state.revision,
+            });
+        }
+        if state.log.is_empty() {
+            self.write_checkpoint(current.slot.other(), state).await?;
+        } else {
+            self.append_log(&current, state).await?;
+        }
+        self.durable(state)
+    }
+
+    /// Rewrites the durable head as a checkpoint and empties the log. Storage
+    /// cannot replay records, so `state` must be the complete payload of the
+    /// head revision produced by the layer that owns the payload.
+    #[tracing::instrument(level = "info", skip_all, fields(document = %state.document, revision = %state.revision))]
+    pub async fn compact(&self, state: &State) -> Result<State> {
+        self.check_ownership(state)?;
+        let _writer = self.inner.writer.lock().await;
+        let current = self.inner.head.read().clone();
+        if state.revision != current.revision() || !state.log.is_empty() {
+            return Err(Error::invalid(
+                "compaction requires a checkpoint of the durable head",
+            ));
+        }
+        if !current.log.is_empty() {
+            self.write_checkpoint(current.slot.other(), state).await?;
+        }
+        self.durable(state)
+    }
+
+    fn check_ownership(&self, state: &State) -> Result<()> {
         if state.document != self.document_id() {
             return Err(Error::DocumentMismatch {
                 state: state.document,
@@ -175,51 +341,389 @@ impl Session {
         if !state.blobs.belongs_to(&self.inner.blobs) {
             return Err(Error::invalid("state blobs belong to another session"));
         }
+        Ok(())
+    }
 
+    /// Publishes blobs, history, and the slot, then empties the log. Callers
+    /// hold the writer lock.
+    async fn write_checkpoint(&self, slot: Slot, state: &State) -> Result<()> {
+        let inner = self.inner.clone();
+        let blobs = state.blobs.clone();
+        let stored = StoredState {
+            document: state.document,
+            revision: state.revision,
+            blobs: blobs.ids().collect(),
+            payload: state.payload.to_vec(),
+        };
+        let stored = tokio::task::spawn_blocking(move || {
+            inner.blobs.persist(&blobs)?;
+            // History is written first so every durable checkpoint is restorable.
+            history::record(&inner.root, &stored, SystemTime::now())?;
+            format::save(&inner.root, slot, &stored)?;
+            // Records left behind by a failed reset name the previous
+            // checkpoint, so replay ignores them.
+            if let Err(error) = inner.log.lock().reset() {
+                tracing::warn!(%error, "failed to empty the project log after a checkpoint");
+            }
+            Ok::<_, Error>(stored)
+        })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))??;
+        *self.inner.head.write() = Head::checkpoint(slot, stored);
+        Ok(())
+    }
+
+    /// Publishes blobs new to the log, appends the records that `state` adds
+    /// to the durable head, and archives them in the history. Callers hold the
+    /// writer lock.
+    async fn append_log(&self, current: &Head, state: &State) -> Result<()> {
+        if state.checkpoint != current.stored.revision
+            || state.log.len() <= current.log.len()
+            || state
+                .log
+                .iter()
+                .zip(current.log.iter())
+                .any(|(proposed, durable)| proposed.revision() != durable.revision())
+        {
+            return Err(Error::invalid("state log does not extend the durable head"));
+        }
+        let records = state.log[current.log.len()..].to_vec();
+        let added = state
+            .blobs
+            .ids()
+            .filter(|id| !current.referenced.contains(id))
+            .collect::<Vec<_>>();
+        let inner = self.inner.clone();
+        let blobs = state.blobs.clone();
+        let document = state.document;
+        let checkpoint = state.checkpoint;
+        let logged = added.clone();
+        tokio::task::spawn_blocking(move || {
+            inner.blobs.persist_only(&blobs, logged.iter().copied())?;
+            inner
+                .log
+                .lock()
+                .append(document, checkpoint, &records, logged.clone())?;
+            // The log is already durable; opening the project archives
+            // whatever this fails to.
+            if let Err(error) =
+                history::record_log(&inner.root, checkpoint, &records, logged, SystemTime::now())
+            {
+                tracing::warn!(%error, "failed to archive logged revisions in project history");
+            }
+            Ok::<_, Error>(())
+        })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))??;
+
+        let mut referenced = current.referenced.as_ref().clone();
+        referenced.extend(added);
+        *self.inner.head.write() = Head {
+            slot: current.slot,
+            stored: current.stored.clone(),
+            log: state.log.clone(),
+            referenced: Arc::new(referenced),
+        };
+        Ok(())
+    }
+
+    fn durable(&self, state: &State) -> Result<State> {
+        let blobs = self
+            .inner
+            .blobs
+            .scope(state.blobs.ids().collect(), BTreeMap::new())?;
+        Ok(State {
+            document: state.document,
+            revision: state.revision,
+            payload: state.payload.clone(),
+            checkpoint: state.checkpoint,
+            log: state.log.clone(),
+            blobs,
+        })
+    }
+
+    /// Writes the durable head and exactly its blob closure into one portable
+    /// file. History and unreferenced blobs are not exported. A head with
+    /// logged records must be compacted first.
+    #[tracing::instrument(level = "info", skip_all, fields(path = %path.as_ref().display()))]
+    pub async fn export_bundle(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref().to_owned();
+        let head = self.inner.head.read().clone();
+        if !head.log.is_empty() {
+            return Err(Error::invalid(
+                "the durable head has log records; compact it before export",
+            ));
+        }
+        let stored = head.stored;
+        self.copy_blobs(stored.blobs.iter().copied().collect(), move |store| {
+            bundle::pack(&path, &stored, store)
+        })
+        .await
+    }
+
+    /// Creates a project at `path` whose only state is `revision` of this one,
+    /// with exactly its blob closure. The fork keeps the document ID so that
+    /// the two branches can exchange patches and be merged; its history
+    /// starts at `revision`. A head with logged records must be compacted
+    /// first.
+    #[tracing::instrument(level = "info", skip_all, fields(revision = %revision, path = %path.as_ref().display()))]
+    pub async fn fork(&self, revision: Revision, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        let head = self.inner.head.read().clone();
+        let stored = if revision == head.revision() {
+            if !head.log.is_empty() {
+                return Err(Error::invalid(
+                    "the durable head has log records; compact it before forking",
+                ));
+            }
+            head.stored.as_ref().clone()
+        } else {
+            let root = self.inner.root.clone();
+            tokio::task::spawn_blocking(move || history::load(&root, revision))
+                .await
+                .map_err(|error| Error::Task(error.to_string()))??
+        };
+        self.copy_blobs(stored.blobs.iter().copied().collect(), move |source| {
+            fork_project(source, stored, path)
+        })
+        .await
+    }
+
+    /// Runs `copy` on a blocking thread with `blobs` leased. The lease keeps
+    /// collection from removing blobs while they are copied.
+    async fn copy_blobs<T: Send + 'static>(
+        &self,
+        blobs: BTreeSet<BlobId>,
+        copy: impl FnOnce(&BlobStore) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let lease = self.inner.blobs.durable_scope(blobs)?;
+        let store = self.inner.blobs.clone();
+        tokio::task::spawn_blocking(move || {
+            let result = copy(&store);
+            drop(lease);
+            result
+        })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Rehashes every blob and decodes both slots and every history revision.
+    /// Verification changes nothing; see [`Session::repair`].
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn verify(&self) -> Result<IntegrityReport> {
         let _writer = self.inner.writer.lock().await;
-        let current = self.inner.head.read().clone();
-        if state.revision <= current.stored.revision {
+        let root = self.inner.root.clone();
+        let store = self.inner.blobs.clone();
+        tokio::task::spawn_blocking(move || integrity::verify(&root, &store))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Replaces the project journal. `revision` must be the durable head, and
+    /// every listed blob must already be published. Those blobs survive
+    /// garbage collection for as long as the journal references them.
+    #[tracing::instrument(level = "debug", skip_all, fields(revision = %revision))]
+    pub async fn save_journal(
+        &self,
+        revision: Revision,
+        payload: Bytes,
+        blobs: impl IntoIterator<Item = BlobId>,
+    ) -> Result<()> {
+        let _writer = self.inner.writer.lock().await;
+        let current = self.revision();
+        if revision != current {
             return Err(Error::RevisionConflict {
-                current: current.stored.revision,
-                proposed: state.revision,
+                current,
+                proposed: revision,
             });
         }
+        let stored = StoredJournal {
+            document: self.document_id(),
+            revision,
+            blobs: blobs
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            payload: payload.to_vec(),
+        };
+        let root = self.inner.root.clone();
+        let store = self.inner.blobs.clone();
+        *self.inner.journal.lock() = None;
+        let end = tokio::task::spawn_blocking(move || {
+            store.verify_references(&stored.blobs.iter().copied().collect())?;
+            journal::save(&root, &stored)
+        })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))??;
+        *self.inner.journal.lock() = Some((revision, end));
+        Ok(())
+    }
 
+    /// Appends a record for the durable head `revision` to a journal last
+    /// written or loaded by this session for `base`. Replaying the records on
+    /// the saved payload is up to the owner. Listed blobs must already be
+    /// published and are retained like the journal's own.
+    #[tracing::instrument(level = "debug", skip_all, fields(base = %base, revision = %revision))]
+    pub async fn append_journal(
+        &self,
+        base: Revision,
+        revision: Revision,
+        payload: Bytes,
+        blobs: impl IntoIterator<Item = BlobId>,
+    ) -> Result<()> {
+        let _writer = self.inner.writer.lock().await;
+        let current = self.revision();
+        if revision != current || revision <= base {
+            return Err(Error::RevisionConflict {
+                current,
+                proposed: revision,
+            });
+        }
+        let Some((journaled, end)) = *self.inner.journal.lock() else {
+            return Err(Error::invalid("the journal must be saved before appending"));
+        };
+        if journaled != base {
+            return Err(Error::RevisionConflict {
+                current: journaled,
+                proposed: base,
+            });
+        }
+        let record = StoredJournal {
+            document: self.document_id(),
+            revision,
+            blobs: blobs
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            payload: payload.to_vec(),
+        };
         let root = self.inner.root.clone();
         let store = self.inner.blobs.clone();
-        let blobs = state.blobs.clone();
-        let document = state.document;
-        let revision = state.revision;
-        let payload = state.payload.clone();
-        let slot = current.slot.other();
-        let stored = tokio::task::spawn_blocking(move || {
-            store.persist(&blobs)?;
-            let stored = StoredState {
-                document,
-                revision,
-                blobs: blobs.ids().collect(),
-                payload: payload.to_vec(),
-            };
-            format::save(&root, slot, &stored)?;
-            Ok::<_, Error>(stored)
+        *self.inner.journal.lock() = None;
+        let end = tokio::task::spawn_blocking(move || {
+            store.verify_references(&record.blobs.iter().copied().collect())?;
+            journal::append(&root, end, &record)
         })
         .await
         .map_err(|error| Error::Task(error.to_string()))??;
+        *self.inner.journal.lock() = Some((revision, end));
+        Ok(())
+    }
 
-        self.inner.head.write().clone_from(&Head {
-            slot,
-            stored: Arc::new(stored),
-        });
-        let durable = self
+    /// Loads the project journal. A journal written for another revision is
+    /// returned as is; the caller decides whether it still applies.
+    pub async fn load_journal(&self) -> Result<Option<Journal>> {
+        let _writer = self.inner.writer.lock().await;
+        let root = self.inner.root.clone();
+        *self.inner.journal.lock() = None;
+        let Some(stored) = tokio::task::spawn_blocking(move || journal::load(&root))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))??
+        else {
+            return Ok(None);
+        };
+        if stored.document != self.document_id() {
+            return Err(Error::DocumentMismatch {
+                state: stored.document,
+                session: self.document_id(),
+            });
+        }
+        *self.inner.journal.lock() = Some((stored.revision, stored.end));
+        let blobs = self
             .inner
             .blobs
-            .scope(state.blobs.ids().collect(), BTreeMap::new())?;
+            .durable_scope(stored.blobs.into_iter().collect())?;
+        Ok(Some(Journal::new(
+            stored.revision,
+            Bytes::from(stored.payload),
+            stored.records.into_iter().map(Bytes::from).collect(),
+            blobs,
+        )))
+    }
+
+    /// Lists every recorded revision, oldest first. Logged revisions are
+    /// archived next to the checkpoint they extend.
+    pub async fn history(&self) -> Result<Vec<HistoryEntry>> {
+        let root = self.inner.root.clone();
+        tokio::task::spawn_blocking(move || history::list(&root))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Loads a recorded revision with a durable blob scope. The head does not
+    /// move; restoring is a scene-level commit of the returned payload. A
+    /// logged revision comes back as its checkpoint and the records up to it.
+    pub async fn load_revision(&self, revision: Revision) -> Result<State> {
+        if revision == self.revision() {
+            return self.load().await;
+        }
+        let root = self.inner.root.clone();
+        let recorded = tokio::task::spawn_blocking(move || history::load_recorded(&root, revision))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))??;
+        if recorded.stored.document != self.document_id() {
+            return Err(Error::DocumentMismatch {
+                state: recorded.stored.document,
+                session: self.document_id(),
+            });
+        }
+        let blobs = self.inner.blobs.durable_scope(recorded.blobs)?;
         Ok(State {
-            document,
+            document: recorded.stored.document,
             revision,
-            payload: state.payload.clone(),
-            blobs: durable,
+            payload: Bytes::from(recorded.stored.payload),
+            checkpoint: recorded.stored.revision,
+            log: recorded.records.into(),
+            blobs,
+        })
+    }
+
+    /// Names a recorded revision. Named checkpoints survive pruning, and their
+    /// blobs survive garbage collection.
+    pub async fn name_checkpoint(&self, revision: Revision, name: impl Into<String>) -> Result<()> {
+        self.set_checkpoint(revision, Some(name.into())).await
+    }
+
+    pub async fn clear_checkpoint(&self, revision: Revision) -> Result<()> {
+        self.set_checkpoint(revision, None).await
+    }
+
+    async fn set_checkpoint(&self, revision: Revision, name: Option<String>) -> Result<()> {
+        let _writer = self.inner.writer.lock().await;
+        let root = self.inner.root.clone();
+        tokio::task::spawn_blocking(move || history::set_checkpoint(&root, revision, name))
+            .await
+            .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Removes unnamed revisions outside `retention`. Blob contents are only
+    /// reclaimed by the next garbage collection.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn prune_history(&self, retention: Retention) -> Result<usize> {
+        let _writer = self.inner.writer.lock().await;
+        let root = self.inner.root.clone();
+        let head = self.inner.head.read().stored.revision;
+        tokio::task::spawn_blocking(move || {
+            history::prune(&root, retention, head, SystemTime::now())
+        })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))?
+    }
+
+    /// Compresses blob files written before blob compression existed. Blob IDs
+    /// and readers are unaffected; only the on-disk encoding changes.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn compress_blobs(&self) -> Result<CompressionReport> {
+        let _writer = self.inner.writer.lock().await;
+        let store = self.inner.blobs.clone();
+        tokio::task::spawn_blocking(move || {
+            let (blobs, bytes) = store.compress()?;
+            Ok::<_, Error>(CompressionReport { blobs, bytes })
         })
+        .await
+        .map_err(|error| Error::Task(error.to_string()))?
     }
 
     #[tracing::instrument(level = "info", skip_all)]
@@ -227,10 +731,13 @@ impl Session {
         let _writer = self.inner.writer.lock().await;
         let root = self.inner.root.clone();
         let store = self.inner.blobs.clone();
+        let logged = self.inner.head.read().referenced.clone();
         tokio::task::spawn_blocking(move || {
-            let mut saved = BTreeSet::new();
+            let mut saved = history::referenced_blobs(&root)?;
+            saved.extend(logged.iter().copied());
+            saved.extend(journal::referenced_blobs(&root)?);
             for slot in [Slot::A, Slot::B] {
-                if let Ok(Some(state)) = format::load(&root, slot) {
+                if let Some(state) = format::load(&root, slot)? {
                     saved.extend(state.blobs);
                 }
             }
@@ -259,20 +766,305 @@ fn create_project(
         blobs: Vec::new(),
         payload: payload.to_vec(),
     };
+    history::record(&root, &stored, SystemTime::now())?;
     format::save(&root, Slot::A, &stored)?;
+    let log = log::Writer::open(&root, 0)?;
     Ok(Session {
         inner: Arc::new(Inner {
             blobs: Arc::new(BlobStore::new(root.clone(), lock, temporary)),
             root,
-            head: RwLock::new(Head {
-                slot: Slot::A,
-                stored: Arc::new(stored),
-            }),
+            head: RwLock::new(Head::checkpoint(Slot::A, stored)),
+            log: parking_lot::Mutex::new(log),
+            journal: parking_lot::Mutex::new(None),
             writer: Mutex::new(()),
         }),
     })
 }
 
+fn import_project(
+    bundle: &Path,
+    root: PathBuf,
+    accept: impl FnOnce(&State) -> bool,
+) -> Result<Session> {
+    fs::create_dir_all(root.join("blobs"))?;
+    if format::path(&root, Slot::A).try_exists()? || format::path(&root, Slot::B).try_exists()? {
+        return Err(Error::invalid("project already contains a state"));
+    }
+    let lock = lock_project(&root)?;
+    let store = Arc::new(BlobStore::new(root.clone(), lock, None));
+    let stored = bundle::unpack(bundle, &store)?;
+    let referenced = stored.blobs.iter().copied().collect();
+    store.verify_references(&referenced)?;
+    let state = State {
+        document: stored.document,
+        revision: stored.revision,
+        payload: Bytes::from(stored.payload.clone()),
+        checkpoint: stored.revision,
+        log: Arc::from([]),
+        blobs: store.durable_scope(referenced)?,
+    };
+    if !accept(&state) {
+        return Err(Error::invalid("bundle state was rejected"));
+    }
+    drop(state);
+    history::record(&root, &stored, SystemTime::now())?;
+    format::save(&root, Slot::A, &stored)?;
+    let log = log::Writer::open(&root, 0)?;
+    Ok(Session {
+        inner: Arc::new(Inner {
+            root,
+            blobs: store,
+            head: RwLock::new(Head::checkpoint(Slot::A, stored)),
+            log: parking_lot::Mutex::new(log),
+            journal: parking_lot::Mutex::new(None),
+            writer: Mutex::new(()),
+        }),
+    })
+}
+
+fn fork_project(source: &BlobStore, stored: StoredState, root: PathBuf) -> Result<Session> {
+    fs::create_dir_all(root.join("blobs"))?;
+    if format::path(&root, Slot::A).try_exists()? || format::path(&root, Slot::B).try_exists()? {
+        return Err(Error::invalid("project already contains a state"));
+    }
+    let lock = lock_project(&root)?;
+    let store = Arc::new(BlobStore::new(root.clone(), lock, None));
+    for id in &stored.blobs {
+        store.copy_from(source, *id)?;
+    }
+    history::record(&root, &stored, SystemTime::now())?;
+    format::save(&root, Slot::A, &stored)?;
+    let log = log::Writer::open(&root, 0)?;
+    Ok(Session {
+        inner: Arc::new(Inner {
+            root,
+            blobs: store,
+            head: RwLock::new(Head::checkpoint(Slot::A, stored)),
+            log: parking_lot::Mutex::new(log),
+            journal: parking_lot::Mutex::new(None),
+            writer: Mutex::new(()),
+        }),
+    })
+}
+
+/// A checkpoint and the logged records that replay on top of it.
+struct Replay {
+    records: Vec<LogRecord>,
+    referenced: BTreeSet<BlobId>,
+    /// The log length that holds exactly `records`.
+    end: u64,
+}
+
+/// Reads the log records that extend `stored`, stopping before the first
+/// record whose blobs are missing.
+fn replay(root: &Path, store: &BlobStore, stored: &StoredState) -> Result<Replay> {
+    let mut replay = Replay {
+        records: Vec::new(),
+        referenced: stored.blobs.iter().copied().collect(),
+        end: 0,
+    };
+    for entry in log::read(root, stored.document, stored.revision)? {
+        let blobs = entry.blobs.iter().copied().collect();
+        if let Err(error) = store.verify_references(&blobs) {
+            tracing::warn!(%error, revision = %entry.record.revision(), "stopping log replay at a record with missing blobs");
+            break;
+        }
+        replay.referenced.extend(blobs);
+        replay.records.push(entry.record);
+        replay.end = entry.end;
+    }
+    Ok(replay)
+}
+
+/// Archives replayed records that an interrupted save left out of the
+/// history. Their blobs are attributed to the first of them.
+fn archive_replay(root: &Path, stored: &StoredState, replay: &Replay) -> Result<()> {
+    let archived = history::read_log(root, stored.revision)?;
+    let missing = replay
+        .records
+        .iter()
+        .filter(|record| {
+            archived
+                .last()
+                .is_none_or(|last| record.revision() > last.record.revision())
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let blobs = replay
+        .referenced
+        .iter()
+        .filter(|id| !stored.blobs.contains(id))
+        .copied()
+        .collect();
+    history::record_log(root, stored.revision, &missing, blobs, SystemTime::now())
+}
+
+fn repair_project(root: PathBuf, mut accept: impl FnMut(&State) -> bool) -> Result<RepairReport> {
+    if !root.is_dir() {
+        return Err(Error::NotAProject);
+    }
+    let lock = lock_project(&root)?;
+    let store = Arc::new(BlobStore::new(root.clone(), lock, None));
+    let report = integrity::verify(&root, &store)?;
+    let quarantine = Quarantine::new(&root, SystemTime::now());
+    let quarantined_blobs = quarantine.blobs(&store, &report.corrupt_blobs)?;
+
+    // A slot is tried with its replayed log first and then on its own.
+    let mut candidates = Vec::new();
+    for slot in [Slot::A, Slot::B] {
+        if let Ok(Some(state)) = format::load(&root, slot) {
+            let replay = replay(&root, &store, &state)?;
+            if !replay.records.is_empty() {
+                candidates.push((Some(slot), state.clone(), replay));
+            }
+            let referenced = state.blobs.iter().copied().collect();
+            let bare = Replay {
+                records: Vec::new(),
+                referenced,
+                end: 0,
+            };
+            candidates.push((Some(slot), state, bare));
+        }
+    }
+    for entry in history::list(&root)? {
+        if let Ok(state) = history::load(&root, entry.revision) {
+            let referenced = state.blobs.iter().copied().collect();
+            let bare = Replay {
+                records: Vec::new(),
+                referenced,
+                end: 0,
+            };
+            candidates.push((None, state, bare));
+        }
+    }
+    let revision = |state: &StoredState, replay: &Replay| {
+        replay
+            .records
+            .last()
+            .map_or(state.revision, LogRecord::revision)
+    };
+    let newest = candidates
+        .iter()
+        .map(|(_, state, replay)| revision(state, replay))
+        .max();
+    candidates.sort_by_key(|(slot, state, replay)| {
+        std::cmp::Reverse((revision(state, replay), slot.is_some()))
+    });
+    let mut selected = None;
+    for (slot, stored, replay) in candidates {
+        if store.verify_references(&replay.referenced).is_err() {
+            continue;
+        }
+        let state = State {
+            document: stored.document,
+            revision: revision(&stored, &replay),
+            payload: Bytes::from(stored.payload.clone()),
+            checkpoint: stored.revision,
+            log: replay.records.iter().cloned().collect(),
+            blobs: store.durable_scope(replay.referenced.clone())?,
+        };
+        if accept(&state) {
+            selected = Some((slot, stored, replay));
+            break;
+        }
+    }
+    let (mut slot, mut stored, mut replay) =
+        selected.ok_or_else(|| Error::invalid("project has no recoverable state"))?;
+    let selected = revision(&stored, &replay);
+
+    let mut quarantined_slots = Vec::new();
+    for candidate in [Slot::A, Slot::B] {
+        if Some(candidate) == slot {
+            continue;
+        }
+        let shadows = match format::load(&root, candidate) {
+            Ok(None) => false,
+            Ok(Some(state)) => state.revision >= stored.revision,
+            Err(_) => true,
+        };
+        if shadows {
+            quarantine.slot(candidate)?;
+            quarantined_slots.push(candidate.filename());
+        }
+    }
+    // Named checkpoints newer than the selected state stay in the history, so
+    // the head moves past them and later saves never reuse their revisions.
+    let named = history::list(&root)?
+        .into_iter()
+        .filter(|entry| entry.revision > selected && entry.checkpoint.is_some())
+        .map(|entry| entry.revision)
+        .max();
+    if let Some(named) = named {
+        let offset = named
+            .next()
+            .ok_or_else(|| Error::invalid("project revision overflow"))?
+            .get()
+            - stored.revision.get();
+        let shift = |revision: Revision| Revision::new(revision.get() + offset);
+        stored.revision = shift(stored.revision);
+        replay.records = replay
+            .records
+            .iter()
+            .map(|record| LogRecord::new(shift(record.revision()), record.payload().clone()))
+            .collect();
+        slot = None;
+    }
+    if slot.is_none() {
+        let target = match (format::load(&root, Slot::A), format::load(&root, Slot::B)) {
+            (Ok(Some(a)), Ok(Some(b))) if a.revision > b.revision => Slot::B,
+            (Ok(Some(_)), Ok(None)) => Slot::B,
+            _ => Slot::A,
+        };
+        format::save(&root, target, &stored)?;
+    }
+    let quarantined_log = if replay.records.is_empty() {
+        quarantine.log()?
+    } else {
+        if named.is_some() {
+            let logged = replay
+                .referenced
+                .iter()
+                .filter(|id| !stored.blobs.contains(id))
+                .copied()
+                .collect();
+            let mut log = log::Writer::open(&root, 0)?;
+            log.append(stored.document, stored.revision, &replay.records, logged)?;
+        }
+        false
+    };
+    let head = revision(&stored, &replay);
+    let quarantined_revisions = quarantine.history_after(selected)?;
+    let quarantined_journal = match journal::load(&root) {
+        Ok(None) => false,
+        Ok(Some(journal)) => {
+            journal.revision != head
+                || store
+                    .verify_references(&journal.blobs.iter().copied().collect())
+                    .is_err()
+        }
+        Err(_) => true,
+    };
+    if quarantined_journal {
+        quarantine.journal()?;
+    }
+    if !history::path(&root, stored.revision).try_exists()? {
+        history::record(&root, &stored, SystemTime::now())?;
+    }
+    Ok(RepairReport {
+        integrity: report,
+        head,
+        abandoned: newest.filter(|newest| *newest != selected),
+        quarantined_blobs,
+        quarantined_slots,
+        quarantined_revisions,
+        quarantined_log,
+        quarantined_journal,
+    })
+}
+
 fn open_project(root: PathBuf, temporary: Option<TempDir>) -> Result<Session> {
     if !root.is_dir() {
         return Err(Error::NotAProject);
@@ -300,14 +1092,37 @@ fn open_project(root: PathBuf, temporary: Option<TempDir>) -> Result<Session> {
         }
     }
     let (slot, stored) = selected.ok_or_else(|| first_error.unwrap_or(Error::NotAProject))?;
+    // Projects saved before history existed start their history at the head.
+    if !history::path(&root, stored.revision).try_exists()?
+        && let Err(error) = history::record(&root, &stored, SystemTime::now())
+    {
+        tracing::warn!(%error, "failed to record the opened revision in project history");
+    }
+    let replay = replay(&root, &store, &stored)?;
+    if let Err(error) = archive_replay(&root, &stored, &replay) {
+        tracing::warn!(%error, "failed to archive logged revisions in project history");
+    }
+    let logged = fs::metadata(log::path(&root)).map_or(0, |metadata| metadata.len());
+    if logged > replay.end {
+        tracing::warn!(
+            discarded = logged - replay.end,
+            "discarding log bytes that do not extend the opened checkpoint"
+        );
+    }
+    let log = log::Writer::open(&root, replay.end)?;
+    let head = Head {
+        slot,
+        stored: Arc::new(stored),
+        log: replay.records.into(),
+        referenced: Arc::new(replay.referenced),
+    };
     Ok(Session {
         inner: Arc::new(Inner {
             root,
             blobs: store,
-            head: RwLock::new(Head {
-                slot,
-                stored: Arc::new(stored),
-            }),
+            head: RwLock::new(head),
+            log: parking_lot::Mutex::new(log),
+            journal: parking_lot::Mutex::new(None),
             writer: Mutex::new(()),
         }),
     })
@@ -2,13 +2,36 @@ use std::fs;
 
 use bytes::Bytes;
 
-use crate::{BlobId, DocumentId, Error, Revision, Session};
+use crate::{BlobId, DocumentId, Error, Retention, Revision, Session, SlotStatus};
 
 fn blob_path(root: &std::path::Path, id: BlobId) -> std::path::PathBuf {
     let name = id.to_string();
     root.join("blobs").join(&name[..2]).join(name)
 }
 
+fn quarantined(directory: &std::path::Path) -> Vec<String> {
+    let mut names = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name != "blobs" && name != "history")
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+/// Incompressible bytes, standing in for already compressed page images.
+fn noise(len: usize) -> Bytes {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
 #[tokio::test]
 async fn saves_reopens_and_maps_large_blobs() {
     let root = tempfile::tempdir().unwrap();
@@ -17,7 +40,7 @@ async fn saves_reopens_and_maps_large_blobs() {
         .await
         .unwrap();
     let initial = session.load().await.unwrap();
-    let bytes = Bytes::from(vec![7; 512 * 1024]);
+    let bytes = noise(512 * 1024);
     let id = BlobId::for_bytes(&bytes);
     let proposed = initial
         .update(
@@ -143,6 +166,19 @@ async fn garbage_collection_keeps_both_slots_and_active_states() {
     let third = session.save(&third_proposed).await.unwrap();
     drop(third_proposed);
     drop((initial, first, second, third));
+    assert_eq!(session.collect_garbage().await.unwrap().blobs, 0);
+
+    let revision = root.path().join("history").join(format!("{:020}.khr", 1));
+    let intact = std::fs::read(&revision).unwrap();
+    std::fs::write(&revision, b"damaged").unwrap();
+    assert!(session.collect_garbage().await.is_err());
+    assert!(blob_path(root.path(), id).exists());
+    std::fs::write(&revision, intact).unwrap();
+
+    assert_eq!(
+        session.prune_history(Retention::Latest(2)).await.unwrap(),
+        2
+    );
     let report = session.collect_garbage().await.unwrap();
     assert_eq!(report.blobs, 1);
     assert!(!blob_path(root.path(), id).exists());
@@ -159,3 +195,961 @@ async fn rejects_stale_saves() {
         Err(Error::RevisionConflict { .. })
     ));
 }
+
+#[tokio::test]
+async fn history_records_saves_and_loads_old_revisions() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::from_static(b"zero"))
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from_static(b"first image");
+    let id = BlobId::for_bytes(&bytes);
+    let first_proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [id],
+            [(id, bytes.clone())],
+        )
+        .unwrap();
+    let first = session.save(&first_proposed).await.unwrap();
+    let second_proposed = first
+        .update(Revision::new(2), Bytes::from_static(b"two"), [], [])
+        .unwrap();
+    let second = session.save(&second_proposed).await.unwrap();
+    session
+        .name_checkpoint(Revision::new(1), "Lettered")
+        .await
+        .unwrap();
+    assert!(matches!(
+        session.name_checkpoint(Revision::new(1), " ").await,
+        Err(Error::Invalid(_))
+    ));
+    assert!(matches!(
+        session.name_checkpoint(Revision::new(9), "Missing").await,
+        Err(Error::RevisionNotFound(_))
+    ));
+    drop((
+        initial,
+        first_proposed,
+        first,
+        second_proposed,
+        second,
+        session,
+    ));
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let history = reopened.history().await.unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| (entry.revision.get(), entry.checkpoint.as_deref()))
+            .collect::<Vec<_>>(),
+        [(0, None), (1, Some("Lettered")), (2, None)]
+    );
+
+    // Pruning keeps the named checkpoint and head; collection keeps their blobs.
+    assert_eq!(
+        reopened.prune_history(Retention::Latest(0)).await.unwrap(),
+        1
+    );
+    assert_eq!(reopened.collect_garbage().await.unwrap().blobs, 0);
+    let old = reopened.load_revision(Revision::new(1)).await.unwrap();
+    assert_eq!(old.payload(), &Bytes::from_static(b"one"));
+    assert_eq!(old.blobs().get(id).await.unwrap(), bytes);
+    drop(old);
+    assert!(matches!(
+        reopened.load_revision(Revision::ZERO).await,
+        Err(Error::RevisionNotFound(_))
+    ));
+
+    reopened.clear_checkpoint(Revision::new(1)).await.unwrap();
+    assert_eq!(
+        reopened.prune_history(Retention::Latest(1)).await.unwrap(),
+        1
+    );
+    assert!(matches!(
+        reopened.load_revision(Revision::new(1)).await,
+        Err(Error::RevisionNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn history_survives_a_lost_index() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let proposed = initial
+        .update(Revision::new(1), Bytes::from_static(b"one"), [], [])
+        .unwrap();
+    session.save(&proposed).await.unwrap();
+    fs::write(root.path().join("history").join("index.khr"), b"torn").unwrap();
+
+    let history = session.history().await.unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| entry.revision)
+            .collect::<Vec<_>>(),
+        [Revision::ZERO, Revision::new(1)]
+    );
+    assert_eq!(
+        session
+            .load_revision(Revision::ZERO)
+            .await
+            .unwrap()
+            .payload(),
+        &Bytes::new()
+    );
+}
+
+#[tokio::test]
+async fn bundles_round_trip_exactly_the_head_blob_closure() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(
+        root.path().join("source"),
+        DocumentId::new(),
+        Bytes::from_static(b"initial"),
+    )
+    .await
+    .unwrap();
+    let initial = session.load().await.unwrap();
+    let stale = Bytes::from_static(b"replaced scan");
+    let stale_id = BlobId::for_bytes(&stale);
+    let first = initial
+        .update(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [stale_id],
+            [(stale_id, stale)],
+        )
+        .unwrap();
+    let first = session.save(&first).await.unwrap();
+    let bytes = Bytes::from(vec![3; 512 * 1024]);
+    let id = BlobId::for_bytes(&bytes);
+    let second = first
+        .update(
+            Revision::new(2),
+            Bytes::from_static(b"two"),
+            [id],
+            [(id, bytes.clone())],
+        )
+        .unwrap();
+    session.save(&second).await.unwrap();
+    let bundle = root.path().join("project.khrz");
+    session.export_bundle(&bundle).await.unwrap();
+
+    let rejected = root.path().join("rejected");
+    assert!(
+        Session::import_bundle(&bundle, &rejected, |state| state.payload().as_ref()
+            != b"two")
+        .await
+        .is_err()
+    );
+    assert!(!rejected.join("state-a.khr").exists());
+    assert!(!rejected.join("history").exists());
+
+    let imported = Session::import_bundle(&bundle, root.path().join("imported"), |_| true)
+        .await
+        .unwrap();
+    assert_eq!(imported.document_id(), session.document_id());
+    let loaded = imported.load().await.unwrap();
+    assert_eq!(loaded.revision(), Revision::new(2));
+    assert_eq!(loaded.payload(), &Bytes::from_static(b"two"));
+    assert_eq!(loaded.blobs().get(id).await.unwrap(), bytes);
+    assert!(
+        fs::metadata(blob_path(&root.path().join("imported"), id))
+            .unwrap()
+            .len()
+            < 64 * 1024
+    );
+    assert!(!blob_path(&root.path().join("imported"), stale_id).exists());
+    assert!(matches!(
+        Session::import_bundle(&bundle, root.path().join("imported"), |_| true).await,
+        Err(Error::Locked | Error::Invalid(_))
+    ));
+}
+
+#[tokio::test]
+async fn tampered_bundles_do_not_become_projects() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path().join("source"), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from(b"page image bytes".repeat(64));
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .update(Revision::new(1), Bytes::new(), [id], [(id, bytes.clone())])
+        .unwrap();
+    session.save(&proposed).await.unwrap();
+    let bundle = root.path().join("project.khrz");
+    session.export_bundle(&bundle).await.unwrap();
+
+    let mut archive = fs::read(&bundle).unwrap();
+    let offset = archive
+        .windows(bytes.len())
+        .position(|window| window == bytes.as_ref())
+        .unwrap();
+    archive[offset] ^= 0xff;
+    fs::write(&bundle, archive).unwrap();
+
+    let target = root.path().join("imported");
+    assert!(
+        Session::import_bundle(&bundle, &target, |_| true)
+            .await
+            .is_err()
+    );
+    assert!(!target.join("state-a.khr").exists());
+    assert!(!blob_path(&target, id).exists());
+}
+
+#[tokio::test]
+async fn compressible_blobs_are_stored_compressed_under_their_content_id() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let mask = Bytes::from(vec![0; 1024 * 1024]);
+    let scan = noise(512 * 1024);
+    let disguised = Bytes::from([&b"KHRBLOB\0"[..], &[1; 64]].concat());
+    let blobs = [mask.clone(), scan.clone(), disguised.clone()].map(|bytes| {
+        let id = BlobId::for_bytes(&bytes);
+        (id, bytes)
+    });
+    let proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::new(),
+            blobs.iter().map(|(id, _)| *id),
+            blobs.iter().cloned(),
+        )
+        .unwrap();
+    session.save(&proposed).await.unwrap();
+    drop((initial, proposed, session));
+
+    let [(mask_id, _), (scan_id, _), (disguised_id, _)] = &blobs;
+    let mask_file = fs::metadata(blob_path(root.path(), *mask_id))
+        .unwrap()
+        .len();
+    assert!(mask_file < 64 * 1024);
+    assert_eq!(
+        fs::read(blob_path(root.path(), *scan_id)).unwrap(),
+        scan.as_ref()
+    );
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let loaded = reopened.load().await.unwrap();
+    for (id, bytes) in &blobs {
+        assert_eq!(&loaded.blobs().get(*id).await.unwrap(), bytes);
+    }
+    assert_eq!(loaded.blobs().get(*disguised_id).await.unwrap(), disguised);
+}
+
+#[tokio::test]
+async fn legacy_raw_blobs_open_unchanged_and_compress_in_place() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from(b"legacy mask row ".repeat(32 * 1024));
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .update(Revision::new(1), Bytes::new(), [id], [(id, bytes.clone())])
+        .unwrap();
+    session.save(&proposed).await.unwrap();
+    drop((initial, proposed, session));
+
+    // Projects written before compression hold raw files without a header.
+    let path = blob_path(root.path(), id);
+    fs::write(&path, &bytes).unwrap();
+    let reopened = Session::open(root.path()).await.unwrap();
+    let mapped = reopened
+        .load()
+        .await
+        .unwrap()
+        .blobs()
+        .get(id)
+        .await
+        .unwrap();
+    assert_eq!(mapped, bytes);
+
+    // A mapped file is left for a later run rather than replaced under it.
+    assert_eq!(reopened.compress_blobs().await.unwrap().blobs, 0);
+    assert_eq!(fs::read(&path).unwrap(), bytes.as_ref());
+    drop(mapped);
+
+    let report = reopened.compress_blobs().await.unwrap();
+    assert_eq!(report.blobs, 1);
+    assert_eq!(
+        report.bytes,
+        bytes.len() as u64 - fs::metadata(&path).unwrap().len()
+    );
+    assert_eq!(
+        reopened
+            .load()
+            .await
+            .unwrap()
+            .blobs()
+            .get(id)
+            .await
+            .unwrap(),
+        bytes
+    );
+    assert_eq!(reopened.compress_blobs().await.unwrap().blobs, 0);
+}
+
+#[tokio::test]
+async fn verification_reports_missing_corrupt_and_orphaned_blobs() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let [kept, damaged, lost, stray] =
+        [&b"kept"[..], b"damaged", b"lost", b"stray"].map(Bytes::from_static);
+    let ids = [&kept, &damaged, &lost, &stray].map(|bytes| BlobId::for_bytes(bytes));
+    let proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::new(),
+            ids[..3].iter().copied(),
+            [(ids[0], kept), (ids[1], damaged), (ids[2], lost)],
+        )
+        .unwrap();
+    session.save(&proposed).await.unwrap();
+    drop((initial, proposed));
+    assert!(session.verify().await.unwrap().is_healthy());
+
+    fs::write(blob_path(root.path(), ids[1]), b"bit rot").unwrap();
+    fs::remove_file(blob_path(root.path(), ids[2])).unwrap();
+    fs::create_dir_all(blob_path(root.path(), ids[3]).parent().unwrap()).unwrap();
+    fs::write(blob_path(root.path(), ids[3]), stray).unwrap();
+    fs::write(root.path().join("state-a.khr"), b"torn").unwrap();
+
+    let report = session.verify().await.unwrap();
+    assert!(!report.is_healthy());
+    assert_eq!(report.corrupt_blobs, [ids[1]]);
+    assert_eq!(report.missing_blobs, [ids[2]]);
+    assert_eq!(report.orphaned_blobs, [ids[3]]);
+    assert!(matches!(report.slots[0].status, SlotStatus::Corrupt(_)));
+    assert_eq!(
+        report.slots[1].status,
+        SlotStatus::Incomplete(Revision::new(1))
+    );
+}
+
+#[tokio::test]
+async fn repair_quarantines_corrupt_blobs_and_falls_back_to_an_intact_state() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::from_static(b"zero"))
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let proposed = initial
+        .update(Revision::new(1), Bytes::from_static(b"one"), [], [])
+        .unwrap();
+    let first = session.save(&proposed).await.unwrap();
+    let bytes = Bytes::from_static(b"newest page");
+    let id = BlobId::for_bytes(&bytes);
+    let second = first
+        .update(
+            Revision::new(2),
+            Bytes::from_static(b"two"),
+            [id],
+            [(id, bytes)],
+        )
+        .unwrap();
+    session.save(&second).await.unwrap();
+    drop((initial, proposed, first, second, session));
+    fs::write(blob_path(root.path(), id), b"disk full").unwrap();
+
+    let report = Session::repair(root.path(), |state| state.payload().as_ref() != b"one")
+        .await
+        .unwrap();
+    assert_eq!(report.integrity.corrupt_blobs, [id]);
+    assert_eq!(report.quarantined_blobs, [id]);
+    assert_eq!(report.head, Revision::ZERO);
+    assert_eq!(report.abandoned, Some(Revision::new(2)));
+    assert_eq!(report.quarantined_slots, ["state-a.khr", "state-b.khr"]);
+    assert_eq!(
+        report.quarantined_revisions,
+        [Revision::new(1), Revision::new(2)]
+    );
+    assert_eq!(
+        quarantined(&root.path().join("quarantine").join("blobs")).len(),
+        1
+    );
+    assert!(
+        quarantined(&root.path().join("quarantine").join("blobs"))[0]
+            .starts_with(&format!("{id}."))
+    );
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    assert_eq!(reopened.revision(), Revision::ZERO);
+    assert_eq!(
+        reopened.load().await.unwrap().payload(),
+        &Bytes::from_static(b"zero")
+    );
+    assert_eq!(
+        reopened
+            .history()
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.revision)
+            .collect::<Vec<_>>(),
+        [Revision::ZERO]
+    );
+    assert!(reopened.verify().await.unwrap().is_healthy());
+}
+
+#[tokio::test]
+async fn repair_keeps_named_checkpoints_and_never_overwrites_quarantined_files() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::from_static(b"zero"))
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from_static(b"named page");
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::from_static(b"named"),
+            [id],
+            [(id, bytes)],
+        )
+        .unwrap();
+    let named = session.save(&proposed).await.unwrap();
+    session
+        .name_checkpoint(Revision::new(1), "before cleanup")
+        .await
+        .unwrap();
+    let unnamed = named
+        .update(Revision::new(2), Bytes::from_static(b"two"), [id], [])
+        .unwrap();
+    session.save(&unnamed).await.unwrap();
+    drop((initial, proposed, named, unnamed, session));
+    let intact = fs::read(blob_path(root.path(), id)).unwrap();
+    fs::write(blob_path(root.path(), id), b"disk full").unwrap();
+
+    let report = Session::repair(root.path(), |_| true).await.unwrap();
+    assert_eq!(report.abandoned, Some(Revision::new(2)));
+    assert_eq!(report.head, Revision::new(2));
+    assert_eq!(report.quarantined_revisions, [Revision::new(2)]);
+
+    // A second repair that quarantines the same files keeps both copies.
+    fs::write(blob_path(root.path(), id), &intact).unwrap();
+    let reopened = Session::open(root.path()).await.unwrap();
+    assert_eq!(
+        reopened.load().await.unwrap().payload(),
+        &Bytes::from_static(b"zero")
+    );
+    let restored = reopened
+        .load()
+        .await
+        .unwrap()
+        .update(Revision::new(3), Bytes::from_static(b"three"), [id], [])
+        .unwrap();
+    reopened.save(&restored).await.unwrap();
+    drop((restored, reopened));
+    fs::write(blob_path(root.path(), id), b"disk full").unwrap();
+    let report = Session::repair(root.path(), |_| true).await.unwrap();
+    assert_eq!(report.head, Revision::new(2));
+    assert_eq!(report.quarantined_revisions, [Revision::new(3)]);
+    assert_eq!(
+        quarantined(&root.path().join("quarantine").join("blobs")).len(),
+        2
+    );
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let history = reopened.history().await.unwrap();
+    assert_eq!(
+        history
+            .iter()
+            .map(|entry| (entry.revision, entry.checkpoint.as_deref()))
+            .collect::<Vec<_>>(),
+        [
+            (Revision::ZERO, None),
+            (Revision::new(1), Some("before cleanup")),
+            (Revision::new(2), None),
+        ]
+    );
+    assert_eq!(reopened.revision(), Revision::new(2));
+}
+
+#[tokio::test]
+async fn appended_records_replay_on_open_and_compact_into_checkpoints() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(
+        root.path(),
+        DocumentId::new(),
+        Bytes::from_static(b"checkpoint"),
+    )
+    .await
+    .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from_static(b"attached");
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .append(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [id],
+            [(id, bytes.clone())],
+        )
+        .unwrap();
+    let first = session.save(&proposed).await.unwrap();
+    drop(proposed);
+    let second = first
+        .append(Revision::new(2), Bytes::from_static(b"two"), [], [])
+        .unwrap();
+    session.save(&second).await.unwrap();
+    assert!(matches!(
+        session
+            .save(
+                &first
+                    .append(Revision::new(3), Bytes::new(), [], [])
+                    .unwrap()
+            )
+            .await,
+        Err(Error::Invalid(_))
+    ));
+    assert!(matches!(
+        session.export_bundle(root.path().join("copy.khrz")).await,
+        Err(Error::Invalid(_))
+    ));
+    // Appends never touch the slots; the history archives them.
+    assert_eq!(
+        session
+            .history()
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.revision)
+            .collect::<Vec<_>>(),
+        [Revision::ZERO, Revision::new(1), Revision::new(2)]
+    );
+    drop((initial, first, second, session));
+
+    let session = Session::open(root.path()).await.unwrap();
+    let loaded = session.load().await.unwrap();
+    assert_eq!(loaded.revision(), Revision::new(2));
+    assert_eq!(loaded.checkpoint_revision(), Revision::ZERO);
+    assert_eq!(loaded.payload(), &Bytes::from_static(b"checkpoint"));
+    assert_eq!(
+        loaded
+            .log()
+            .iter()
+            .map(|record| (record.revision(), record.payload().clone()))
+            .collect::<Vec<_>>(),
+        [
+            (Revision::new(1), Bytes::from_static(b"one")),
+            (Revision::new(2), Bytes::from_static(b"two")),
+        ]
+    );
+    assert_eq!(loaded.blobs().get(id).await.unwrap(), bytes);
+    session.collect_garbage().await.unwrap();
+    assert!(blob_path(root.path(), id).exists());
+
+    let checkpoint = loaded
+        .update(Revision::new(2), Bytes::from_static(b"replayed"), [id], [])
+        .unwrap();
+    let compacted = session.compact(&checkpoint).await.unwrap();
+    assert!(compacted.log().is_empty());
+    assert_eq!(fs::metadata(root.path().join("log.khr")).unwrap().len(), 0);
+    assert!(
+        session
+            .history()
+            .await
+            .unwrap()
+            .iter()
+            .any(|entry| entry.revision == Revision::new(2))
+    );
+    drop((loaded, checkpoint, compacted, session));
+
+    let session = Session::open(root.path()).await.unwrap();
+    let loaded = session.load().await.unwrap();
+    assert_eq!(loaded.revision(), Revision::new(2));
+    assert_eq!(loaded.checkpoint_revision(), Revision::new(2));
+    assert!(loaded.log().is_empty());
+    assert_eq!(loaded.payload(), &Bytes::from_static(b"replayed"));
+}
+
+#[tokio::test]
+async fn logged_revisions_stay_restorable_after_a_checkpoint() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(
+        root.path(),
+        DocumentId::new(),
+        Bytes::from_static(b"checkpoint"),
+    )
+    .await
+    .unwrap();
+    let mut state = session.load().await.unwrap();
+    let bytes = noise(256);
+    let id = BlobId::for_bytes(&bytes);
+    let next = state
+        .append(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [id],
+            [(id, bytes.clone())],
+        )
+        .unwrap();
+    state = session.save(&next).await.unwrap();
+    drop(next);
+    for (revision, payload) in [(2, "two"), (3, "three")] {
+        let next = state
+            .append(
+                Revision::new(revision),
+                Bytes::from_static(payload.as_bytes()),
+                [id],
+                [],
+            )
+            .unwrap();
+        state = session.save(&next).await.unwrap();
+    }
+    let checkpoint = state
+        .update(Revision::new(4), Bytes::from_static(b"four"), [], [])
+        .unwrap();
+    session.save(&checkpoint).await.unwrap();
+    assert_eq!(fs::metadata(root.path().join("log.khr")).unwrap().len(), 0);
+    drop((state, checkpoint, session));
+
+    let session = Session::open(root.path()).await.unwrap();
+    assert_eq!(
+        session
+            .history()
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.revision.get())
+            .collect::<Vec<_>>(),
+        [0, 1, 2, 3, 4]
+    );
+    session
+        .name_checkpoint(Revision::new(2), "logged")
+        .await
+        .unwrap();
+    session.collect_garbage().await.unwrap();
+    let loaded = session.load_revision(Revision::new(2)).await.unwrap();
+    assert_eq!(loaded.revision(), Revision::new(2));
+    assert_eq!(loaded.checkpoint_revision(), Revision::ZERO);
+    assert_eq!(loaded.payload(), &Bytes::from_static(b"checkpoint"));
+    assert_eq!(
+        loaded
+            .log()
+            .iter()
+            .map(|record| (record.revision(), record.payload().clone()))
+            .collect::<Vec<_>>(),
+        [
+            (Revision::new(1), Bytes::from_static(b"one")),
+            (Revision::new(2), Bytes::from_static(b"two")),
+        ]
+    );
+    assert_eq!(loaded.blobs().get(id).await.unwrap(), bytes);
+
+    // Restoring commits the replayed payload as a new revision.
+    let restored = loaded
+        .update(Revision::new(5), Bytes::from_static(b"two"), [id], [])
+        .unwrap();
+    session.save(&restored).await.unwrap();
+    assert!(
+        session
+            .verify()
+            .await
+            .unwrap()
+            .unreadable_revisions
+            .is_empty()
+    );
+    // The named logged revision keeps its checkpoint from pruning.
+    session.prune_history(Retention::Latest(1)).await.unwrap();
+    assert_eq!(
+        session
+            .history()
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.revision.get())
+            .collect::<Vec<_>>(),
+        [0, 1, 2, 3, 5]
+    );
+}
+
+#[tokio::test]
+async fn torn_log_tails_and_stale_records_are_not_replayed() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let mut state = session.load().await.unwrap();
+    for revision in 1..=3 {
+        let next = state
+            .append(Revision::new(revision), noise(64), [], [])
+            .unwrap();
+        state = session.save(&next).await.unwrap();
+    }
+    drop((state, session));
+    let log = root.path().join("log.khr");
+    let length = fs::metadata(&log).unwrap().len();
+    fs::OpenOptions::new()
+        .write(true)
+        .open(&log)
+        .unwrap()
+        .set_len(length - 5)
+        .unwrap();
+
+    let session = Session::open(root.path()).await.unwrap();
+    assert_eq!(session.revision(), Revision::new(2));
+    let state = session.load().await.unwrap();
+    let next = state.append(Revision::new(3), noise(16), [], []).unwrap();
+    let saved = session.save(&next).await.unwrap();
+    // A checkpoint supersedes the log even if the log is never emptied.
+    let stale = fs::read(&log).unwrap();
+    let checkpoint = saved
+        .update(Revision::new(4), Bytes::from_static(b"full"), [], [])
+        .unwrap();
+    session.save(&checkpoint).await.unwrap();
+    drop((state, next, saved, checkpoint, session));
+    fs::write(&log, stale).unwrap();
+
+    let session = Session::open(root.path()).await.unwrap();
+    let loaded = session.load().await.unwrap();
+    assert_eq!(loaded.revision(), Revision::new(4));
+    assert!(loaded.log().is_empty());
+    assert_eq!(fs::metadata(&log).unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn journals_follow_the_head_and_retain_their_blobs() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let bytes = Bytes::from_static(b"restorable");
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [id],
+            [(id, bytes.clone())],
+        )
+        .unwrap();
+    let first = session.save(&proposed).await.unwrap();
+    drop(proposed);
+    let proposed = first
+        .update(Revision::new(2), Bytes::from_static(b"two"), [], [])
+        .unwrap();
+    let second = session.save(&proposed).await.unwrap();
+    drop(proposed);
+    let proposed = second
+        .update(Revision::new(3), Bytes::from_static(b"three"), [], [])
+        .unwrap();
+    let third = session.save(&proposed).await.unwrap();
+    drop((proposed, initial, first, second));
+    assert!(session.load_journal().await.unwrap().is_none());
+    assert!(matches!(
+        session
+            .save_journal(Revision::new(2), Bytes::from_static(b"stale"), [id])
+            .await,
+        Err(Error::RevisionConflict { .. })
+    ));
+    assert!(matches!(
+        session
+            .save_journal(
+                Revision::new(3),
+                Bytes::from_static(b"missing"),
+                [BlobId::for_bytes(b"never published")],
+            )
+            .await,
+        Err(Error::BlobNotFound(_))
+    ));
+    session
+        .save_journal(Revision::new(3), Bytes::from_static(b"undo"), [id, id])
+        .await
+        .unwrap();
+    assert_eq!(
+        session.prune_history(Retention::Latest(1)).await.unwrap(),
+        3
+    );
+    assert_eq!(session.collect_garbage().await.unwrap().blobs, 0);
+    drop((third, session));
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let journal = reopened.load_journal().await.unwrap().unwrap();
+    assert_eq!(journal.revision(), Revision::new(3));
+    assert_eq!(journal.payload(), &Bytes::from_static(b"undo"));
+    assert_eq!(journal.blobs().get(id).await.unwrap(), bytes);
+    assert!(reopened.verify().await.unwrap().is_healthy());
+
+    reopened
+        .save_journal(Revision::new(3), Bytes::new(), [])
+        .await
+        .unwrap();
+    drop(journal);
+    assert_eq!(reopened.collect_garbage().await.unwrap().blobs, 1);
+    assert!(!blob_path(root.path(), id).exists());
+}
+
+#[tokio::test]
+async fn journal_records_append_after_the_saved_payload() {
+    let root = tempfile::tempdir().unwrap();
+    let session = Session::create(root.path(), DocumentId::new(), Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    assert!(
+        session
+            .append_journal(Revision::ZERO, Revision::ZERO, Bytes::new(), [])
+            .await
+            .is_err()
+    );
+    session
+        .save_journal(Revision::ZERO, Bytes::from_static(b"base"), [])
+        .await
+        .unwrap();
+    let bytes = Bytes::from_static(b"restorable");
+    let id = BlobId::for_bytes(&bytes);
+    let proposed = initial
+        .append(Revision::new(1), Bytes::new(), [id], [(id, bytes.clone())])
+        .unwrap();
+    let first = session.save(&proposed).await.unwrap();
+    assert!(matches!(
+        session
+            .append_journal(Revision::new(1), Revision::new(1), Bytes::new(), [])
+            .await,
+        Err(Error::RevisionConflict { .. })
+    ));
+    session
+        .append_journal(
+            Revision::ZERO,
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [id],
+        )
+        .await
+        .unwrap();
+    drop((initial, proposed, first, session));
+    let journal = root.path().join("journal.khr");
+    let mut torn = fs::read(&journal).unwrap();
+    torn.extend_from_slice(b"KHRJRREC torn");
+    fs::write(&journal, torn).unwrap();
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let loaded = reopened.load_journal().await.unwrap().unwrap();
+    assert_eq!(loaded.revision(), Revision::new(1));
+    assert_eq!(loaded.payload(), &Bytes::from_static(b"base"));
+    assert_eq!(loaded.records(), [Bytes::from_static(b"one")]);
+    assert_eq!(loaded.blobs().get(id).await.unwrap(), bytes);
+    let proposed = reopened
+        .load()
+        .await
+        .unwrap()
+        .append(Revision::new(2), Bytes::new(), [], [])
+        .unwrap();
+    reopened.save(&proposed).await.unwrap();
+    reopened
+        .append_journal(
+            Revision::new(1),
+            Revision::new(2),
+            Bytes::from_static(b"two"),
+            [],
+        )
+        .await
+        .unwrap();
+    drop((loaded, proposed, reopened));
+
+    let reopened = Session::open(root.path()).await.unwrap();
+    let loaded = reopened.load_journal().await.unwrap().unwrap();
+    assert_eq!(loaded.revision(), Revision::new(2));
+    assert_eq!(
+        loaded.records(),
+        [Bytes::from_static(b"one"), Bytes::from_static(b"two")]
+    );
+}
+
+#[tokio::test]
+async fn forks_copy_one_revision_and_its_blob_closure() {
+    let root = tempfile::tempdir().unwrap();
+    let document = DocumentId::new();
+    let session = Session::create(root.path().join("main"), document, Bytes::new())
+        .await
+        .unwrap();
+    let initial = session.load().await.unwrap();
+    let kept = noise(64 * 1024);
+    let kept_id = BlobId::for_bytes(&kept);
+    let proposed = initial
+        .update(
+            Revision::new(1),
+            Bytes::from_static(b"one"),
+            [kept_id],
+            [(kept_id, kept.clone())],
+        )
+        .unwrap();
+    let first = session.save(&proposed).await.unwrap();
+    drop(proposed);
+    let later = Bytes::from_static(b"later");
+    let later_id = BlobId::for_bytes(&later);
+    let proposed = first
+        .append(
+            Revision::new(2),
+            Bytes::from_static(b"record"),
+            [later_id],
+            [(later_id, later)],
+        )
+        .unwrap();
+    let second = session.save(&proposed).await.unwrap();
+    drop(proposed);
+    assert!(
+        session
+            .fork(Revision::new(2), root.path().join("head"))
+            .await
+            .is_err()
+    );
+
+    let fork = session
+        .fork(Revision::new(1), root.path().join("branch"))
+        .await
+        .unwrap();
+    let forked = fork.load().await.unwrap();
+    assert_eq!(forked.document_id(), document);
+    assert_eq!(forked.revision(), Revision::new(1));
+    assert_eq!(forked.payload(), &Bytes::from_static(b"one"));
+    assert_eq!(forked.blobs().get(kept_id).await.unwrap(), kept);
+    assert!(!blob_path(&root.path().join("branch"), later_id).exists());
+    assert_eq!(
+        fork.history()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.revision)
+            .collect::<Vec<_>>(),
+        [Revision::new(1)]
+    );
+    assert!(
+        session
+            .fork(Revision::new(1), root.path().join("branch"))
+            .await
+            .is_err()
+    );
+    drop((forked, fork));
+    assert!(
+        Session::open(root.path().join("branch"))
+            .await
+            .unwrap()
+            .verify()
+            .await
+            .unwrap()
+            .is_healthy()
+    );
+    drop((initial, first, second));
+}
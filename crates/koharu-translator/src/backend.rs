@@ -10,6 +10,24 @@ use crate::Language;
 const MAX_IMAGE_DIMENSION: u32 = 2048;
 const JPEG_QUALITY: u8 = 88;
 
+/// A translated segment and how sure the provider is of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslatedSegment {
+    pub text: String,
+    /// From 0 to 1, when the provider rates its translations. Machine
+    /// translation services do not.
+    pub confidence: Option<f32>,
+}
+
+impl From<String> for TranslatedSegment {
+    fn from(text: String) -> Self {
+        Self {
+            text,
+            confidence: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct TranslationRequest {
     pub segments: Vec<String>,
@@ -17,6 +35,16 @@ pub struct TranslationRequest {
     pub target_language: Language,
     pub instructions: Option<String>,
     pub context: Vec<TranslationContext>,
+    /// Remembered translations of lines similar to some segments.
+    pub examples: Vec<TranslationContext>,
+    /// The characters who speak some of the segments.
+    pub characters: Vec<TranslationCharacter>,
+    /// For each segment, the index of its speaker in `characters`, if known.
+    pub speakers: Vec<Option<usize>>,
+    /// Whether each segment is a sound effect lettered into the artwork.
+    pub sound_effects: Vec<bool>,
+    /// The project terms that occur in the segments.
+    pub glossary: Vec<TranslationGlossaryTerm>,
     pub image: Option<Arc<DynamicImage>>,
 }
 
@@ -32,6 +60,11 @@ impl TranslationRequest {
             target_language,
             instructions: None,
             context: Vec::new(),
+            examples: Vec::new(),
+            characters: Vec::new(),
+            speakers: Vec::new(),
+            sound_effects: Vec::new(),
+            glossary: Vec::new(),
             image: None,
         }
     }
@@ -55,13 +88,81 @@ impl TranslationRequest {
         self
     }
 
-    #[cfg(test)]
+    /// Adds earlier source and translation pairs, oldest first, for continuity.
     #[must_use]
     pub fn with_context(mut self, context: impl IntoIterator<Item = TranslationContext>) -> Self {
         self.context = context.into_iter().collect();
         self
     }
 
+    /// Adds translations of similar lines, such as translation memory fuzzy
+    /// matches, for the model to reuse the wording of.
+    #[must_use]
+    pub fn with_examples(mut self, examples: impl IntoIterator<Item = TranslationContext>) -> Self {
+        self.examples = examples.into_iter().collect();
+        self
+    }
+
+    /// Tags segments with their speakers. `speakers` holds one entry per
+    /// segment, indexing into `characters`.
+    #[must_use]
+    pub fn with_speakers(
+        mut self,
+        characters: impl IntoIterator<Item = TranslationCharacter>,
+        speakers: impl IntoIterator<Item = Option<usize>>,
+    ) -> Self {
+        self.characters = characters.into_iter().collect();
+        self.speakers = speakers.into_iter().collect();
+        self
+    }
+
+    /// Marks sound effect segments, one entry per segment, so the model
+    /// translates them as sounds rather than speech.
+    #[must_use]
+    pub fn with_sound_effects(mut self, sound_effects: impl IntoIterator<Item = bool>) -> Self {
+        self.sound_effects = sound_effects.into_iter().collect();
+        self
+    }
+
+    /// Drops the oldest context entries until the rest fit in about `tokens`.
+    pub(crate) fn fit_context(&mut self, tokens: usize) {
+        let mut used = 0;
+        let kept = self
+            .context
+            .iter()
+            .rev()
+            .take_while(|entry| {
+                used += estimated_tokens(&entry.source) + estimated_tokens(&entry.translation);
+                used <= tokens
+            })
+            .count();
+        self.context.drain(..self.context.len() - kept);
+    }
+
+    #[must_use]
+    pub fn with_glossary(
+        mut self,
+        glossary: impl IntoIterator<Item = TranslationGlossaryTerm>,
+    ) -> Self {
+        self.glossary = glossary.into_iter().collect();
+        self
+    }
+
+    /// The position in `characters` of the character who speaks segment
+    /// `index`, if known.
+    pub(crate) fn speaker(&self, index: usize) -> Option<usize> {
+        self.speakers
+            .get(index)
+            .copied()
+            .flatten()
+            .filter(|character| *character < self.characters.len())
+    }
+
+    /// Whether segment `index` is a sound effect.
+    pub(crate) fn is_sound_effect(&self, index: usize) -> bool {
+        self.sound_effects.get(index).copied().unwrap_or(false)
+    }
+
     pub(crate) fn prepare_image(&mut self) -> anyhow::Result<()> {
         let Some(image) = self.image.as_ref() else {
             return Ok(());
@@ -95,6 +196,17 @@ impl TranslationRequest {
     }
 }
 
+/// A rough token count that needs no tokenizer: about four ASCII characters
+/// make a token, while CJK and other scripts take about one each.
+fn estimated_tokens(text: &str) -> usize {
+    let ascii = text.bytes().filter(u8::is_ascii).count();
+    ascii.div_ceil(4)
+        + text
+            .chars()
+            .filter(|character| !character.is_ascii())
+            .count()
+}
+
 pub(crate) struct EncodedImage {
     pub(crate) data: String,
 }
@@ -128,7 +240,6 @@ pub struct TranslationContext {
 }
 
 impl TranslationContext {
-    #[cfg(test)]
     #[must_use]
     pub fn new(source: impl Into<String>, translation: impl Into<String>) -> Self {
         Self {
@@ -137,3 +248,32 @@ impl TranslationContext {
         }
     }
 }
+
+/// A character of the work, so the translation keeps their voice consistent.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TranslationCharacter {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub speech_notes: String,
+}
+
+impl TranslationCharacter {
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            aliases: Vec::new(),
+            speech_notes: String::new(),
+        }
+    }
+}
+
+/// A term the translation must render one way wherever the source uses it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TranslationGlossaryTerm {
+    pub source: String,
+    /// The required rendering, which is the source itself for a term that
+    /// stays untranslated.
+    pub target: String,
+    pub notes: String,
+}
@@ -95,10 +95,14 @@ async fn main() -> Result<()> {
         .await?;
 
     if args.json {
-        println!("{}", serde_json::to_string_pretty(&translated)?);
+        let texts = translated
+            .iter()
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&texts)?);
     } else {
         for (index, segment) in translated.iter().enumerate() {
-            println!("[{}] {segment}", index + 1);
+            println!("[{}] {}", index + 1, segment.text);
         }
     }
     Ok(())
@@ -5,6 +5,7 @@ mod error;
 mod json;
 mod language;
 mod local;
+mod memory;
 mod model;
 mod prompt;
 mod provider;
@@ -17,8 +18,12 @@ use koharu_ml::Device;
 use error::{Error, Result};
 use local::LocalTranslator;
 
-pub use backend::{TranslationContext, TranslationRequest};
+pub use backend::{
+    TranslatedSegment, TranslationCharacter, TranslationContext, TranslationGlossaryTerm,
+    TranslationRequest,
+};
 pub use language::Language;
+pub use memory::{MemoryMatch, MemoryUnit, TranslationMemory, UNDETERMINED_LANGUAGE};
 pub use model::{GenerationConfig, Model, ModelSelection, Quantization};
 pub(crate) use model::{ModelGeneration, QuantizationDefinition, display_name};
 pub use provider::{Provider, ProviderConfig, ProvidersConfig};
@@ -114,7 +119,7 @@ impl Translator {
         selection: &ModelSelection,
         generation: GenerationConfig,
         mut request: TranslationRequest,
-    ) -> anyhow::Result<(&'static str, Vec<String>)> {
+    ) -> anyhow::Result<(&'static str, Vec<TranslatedSegment>)> {
         let _metric = tracing::info_span!(
             target: "koharu_metrics",
             "translation_request",
@@ -126,10 +131,11 @@ impl Translator {
         let provider_id: &'static str = provider.into();
         if request.segments.is_empty() {
             tracing::Span::current().record("outcome", "skipped");
-            return Ok((provider_id, request.segments));
+            return Ok((provider_id, Vec::new()));
         }
 
         let generation = generation.for_model(selection);
+        request.fit_context(provider.context_tokens());
 
         if Self::supports_vision(selection, &generation) {
             request.prepare_image()?;
@@ -204,6 +210,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn context_keeps_the_most_recent_entries_within_the_provider_budget() {
+        let mut request = TranslationRequest::new(["new"], Language::English).with_context([
+            TranslationContext::new("oldest line of dialogue", "a"),
+            TranslationContext::new("middle", "b"),
+            TranslationContext::new("newest", "c"),
+        ]);
+        request.fit_context(6);
+        assert_eq!(
+            request
+                .context
+                .iter()
+                .map(|entry| entry.source.as_str())
+                .collect::<Vec<_>>(),
+            ["middle", "newest"]
+        );
+        request.fit_context(Provider::Caiyun.context_tokens());
+        assert!(request.context.is_empty());
+    }
+
     #[test]
     fn local_vision_requires_capability_and_generation_setting() {
         assert!(Translator::supports_vision(
@@ -13,7 +13,7 @@ pub(crate) use catalog::{DEFAULT_MODEL, DEFAULT_QUANTIZATION};
 
 use crate::{
     Device, Error, GenerationConfig, Model, ModelSelection, Provider, Quantization, Result,
-    TranslationRequest, prompt,
+    TranslatedSegment, TranslationRequest, prompt,
 };
 
 #[derive(Debug)]
@@ -57,7 +57,7 @@ impl LocalTranslator {
         &self,
         request: TranslationRequest,
         generation: GenerationConfig,
-    ) -> Result<Vec<String>> {
+    ) -> Result<Vec<TranslatedSegment>> {
         let expected = request.segments.len();
         if expected == 0 {
             return Ok(Vec::new());
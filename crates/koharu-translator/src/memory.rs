@@ -0,0 +1,665 @@
+//! A translation memory shared by every project on this machine.
+
+use std::{
+    collections::BTreeMap,
+    io::{Read as _, Seek as _, SeekFrom, Write as _},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result};
+use parking_lot::Mutex;
+
+const MEMORY_FILE: &str = "translation-memory.tmx";
+/// The language tag recorded for lines whose source language is unknown.
+pub const UNDETERMINED_LANGUAGE: &str = "und";
+/// How alike two lines must be, from 0 to 1, to be offered as a fuzzy match.
+const MIN_SIMILARITY: f32 = 0.7;
+/// The TMX unit property that marks a translation an editor wrote or approved.
+const REVIEWED_PROPERTY: &str = "x-reviewed";
+/// How every saved memory ends; changes are written in its place.
+const TMX_END: &str = "  </body>\n</tmx>\n";
+
+/// Source lines and their translations, keyed by normalized source text and
+/// language pair. The memory is saved as TMX, and each change is appended to
+/// the saved file.
+#[derive(Clone)]
+pub struct TranslationMemory {
+    path: Option<Arc<PathBuf>>,
+    pairs: Arc<Mutex<BTreeMap<(String, String), LanguagePair>>>,
+    save: Arc<tokio::sync::Mutex<()>>,
+}
+
+struct LanguagePair {
+    source_language: String,
+    target_language: String,
+    /// Shared with searches in progress, which work on their own copy.
+    units: Arc<BTreeMap<String, MemoryEntry>>,
+}
+
+#[derive(Clone)]
+struct MemoryEntry {
+    unit: MemoryUnit,
+    reviewed: bool,
+}
+
+/// One source line and its translation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryUnit {
+    pub source: String,
+    pub target: String,
+}
+
+/// A remembered line similar to the one looked up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemoryMatch {
+    pub source: String,
+    pub target: String,
+    /// How alike the two source lines are, where 1 is identical after
+    /// normalization.
+    pub similarity: f32,
+    /// Whether an editor wrote or approved the translation. Lines without
+    /// this were kept from model output by earlier releases.
+    pub reviewed: bool,
+}
+
+impl TranslationMemory {
+    /// Opens the memory stored next to the shared configuration file.
+    pub fn load() -> Result<Self> {
+        Self::open(koharu_config::path()?.with_file_name(MEMORY_FILE))
+    }
+
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let units = if path.is_file() {
+            let tmx = std::fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            parse_tmx(&tmx).with_context(|| format!("failed to parse {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        let read = units.len();
+        let memory = Self {
+            path: Some(Arc::new(path.clone())),
+            ..Self::memory()
+        };
+        memory.insert(units);
+        // A line changed since the file was last written is stored once for
+        // each change, so the file is rewritten with one unit per line.
+        let held = memory
+            .pairs
+            .lock()
+            .values()
+            .map(|pair| pair.units.len())
+            .sum::<usize>();
+        if held < read
+            && let Err(error) = write_atomically(&path, memory.export_tmx().as_bytes())
+        {
+            tracing::warn!(%error, "failed to compact the translation memory");
+        }
+        Ok(memory)
+    }
+
+    /// A memory that is never saved.
+    #[must_use]
+    pub fn memory() -> Self {
+        Self {
+            path: None,
+            pairs: Arc::default(),
+            save: Arc::default(),
+        }
+    }
+
+    /// The remembered line identical to `source` after normalization.
+    #[must_use]
+    pub fn exact(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        source: &str,
+    ) -> Option<MemoryMatch> {
+        self.pairs
+            .lock()
+            .get(&pair_key(source_language, target_language))?
+            .units
+            .get(&normalize(source))
+            .map(|entry| MemoryMatch {
+                source: entry.unit.source.clone(),
+                target: entry.unit.target.clone(),
+                similarity: 1.0,
+                reviewed: entry.reviewed,
+            })
+    }
+
+    /// Up to `limit` remembered lines like `source` but not identical to it,
+    /// most similar first. The search runs off the async runtime.
+    pub async fn similar(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        source: &str,
+        limit: usize,
+    ) -> Result<Vec<MemoryMatch>> {
+        let Some(units) = self
+            .pairs
+            .lock()
+            .get(&pair_key(source_language, target_language))
+            .map(|pair| pair.units.clone())
+        else {
+            return Ok(Vec::new());
+        };
+        let key = normalize(source).chars().collect::<Vec<_>>();
+        tokio::task::spawn_blocking(move || {
+            let mut matches = units
+                .iter()
+                .filter_map(|(candidate, entry)| {
+                    let candidate = candidate.chars().collect::<Vec<_>>();
+                    let longest = key.len().max(candidate.len());
+                    // The distance is at least the difference in length.
+                    if longest == 0
+                        || (longest - key.len().min(candidate.len())) as f32
+                            > longest as f32 * (1.0 - MIN_SIMILARITY)
+                    {
+                        return None;
+                    }
+                    let similarity = 1.0 - edit_distance(&key, &candidate) as f32 / longest as f32;
+                    (MIN_SIMILARITY..1.0)
+                        .contains(&similarity)
+                        .then(|| MemoryMatch {
+                            source: entry.unit.source.clone(),
+                            target: entry.unit.target.clone(),
+                            similarity,
+                            reviewed: entry.reviewed,
+                        })
+                })
+                .collect::<Vec<_>>();
+            matches.sort_by(|left, right| right.similarity.total_cmp(&left.similarity));
+            matches.truncate(limit);
+            matches
+        })
+        .await
+        .context("translation memory search failed")
+    }
+
+    /// Adds translations an editor wrote or approved, replacing what the
+    /// memory holds for their lines. Model output is never remembered, so a
+    /// bad generation cannot come back as a match.
+    pub async fn correct(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        units: impl IntoIterator<Item = MemoryUnit>,
+    ) -> Result<()> {
+        let units = units
+            .into_iter()
+            .map(|unit| {
+                (
+                    source_language.to_owned(),
+                    target_language.to_owned(),
+                    unit,
+                    true,
+                )
+            })
+            .collect::<Vec<_>>();
+        self.record(units).await
+    }
+
+    /// Merges the translation units of a TMX document, replacing remembered
+    /// lines they also contain, and returns how many units were read. An
+    /// editor chose to import them, so every unit counts as reviewed.
+    pub async fn import_tmx(&self, tmx: &str) -> Result<usize> {
+        let units = parse_tmx(tmx)?
+            .into_iter()
+            .map(|(source_language, target_language, unit, _)| {
+                (source_language, target_language, unit, true)
+            })
+            .collect::<Vec<_>>();
+        let count = units.len();
+        self.record(units).await?;
+        Ok(count)
+    }
+
+    /// Writes every remembered line as a TMX 1.4 document.
+    #[must_use]
+    pub fn export_tmx(&self) -> String {
+        let mut tmx = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<tmx version=\"1.4\">\n",
+            "  <header creationtool=\"Koharu\" creationtoolversion=\"",
+            env!("CARGO_PKG_VERSION"),
+            "\" segtype=\"sentence\" o-tmf=\"Koharu\" adminlang=\"en-US\" srclang=\"*all*\" datatype=\"plaintext\"/>\n",
+            "  <body>\n",
+        ));
+        for pair in self.pairs.lock().values() {
+            for entry in pair.units.values() {
+                tmx.push_str(&unit_tmx(
+                    &pair.source_language,
+                    &pair.target_language,
+                    entry,
+                ));
+            }
+        }
+        tmx.push_str(TMX_END);
+        tmx
+    }
+
+    /// Inserts the units and saves the ones that changed. Holding the save
+    /// lock throughout keeps the file's order of changes the memory's.
+    async fn record(&self, units: Vec<(String, String, MemoryUnit, bool)>) -> Result<()> {
+        let _save = self.save.lock().await;
+        let changed = self.insert(units);
+        if changed.is_empty() {
+            return Ok(());
+        }
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let memory = self.clone();
+        tokio::task::spawn_blocking(move || append(&path, &changed, || memory.export_tmx()))
+            .await
+            .context("translation memory save task failed")?
+    }
+
+    /// Returns the units that changed, written as TMX.
+    fn insert(&self, units: Vec<(String, String, MemoryUnit, bool)>) -> String {
+        let mut pairs = self.pairs.lock();
+        let mut changed = String::new();
+        for (source_language, target_language, unit, reviewed) in units {
+            let key = normalize(&unit.source);
+            if key.is_empty() || unit.target.trim().is_empty() {
+                continue;
+            }
+            let pair = pairs
+                .entry(pair_key(&source_language, &target_language))
+                .or_insert_with(|| LanguagePair {
+                    source_language,
+                    target_language,
+                    units: Arc::default(),
+                });
+            if pair
+                .units
+                .get(&key)
+                .is_none_or(|existing| existing.unit != unit || existing.reviewed != reviewed)
+            {
+                let entry = MemoryEntry { unit, reviewed };
+                changed.push_str(&unit_tmx(
+                    &pair.source_language,
+                    &pair.target_language,
+                    &entry,
+                ));
+                Arc::make_mut(&mut pair.units).insert(key, entry);
+            }
+        }
+        changed
+    }
+}
+
+/// Writes `units` in place of the end of the memory saved at `path`. The
+/// whole `document` is written instead when there is no such file or it
+/// does not end the way this memory writes it.
+fn append(path: &Path, units: &str, document: impl FnOnce() -> String) -> Result<()> {
+    let mut file = match std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+    {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return write_atomically(path, document().as_bytes());
+        }
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to open {}", path.display()));
+        }
+    };
+    let end = SeekFrom::End(-(TMX_END.len() as i64));
+    let mut ending = vec![0; TMX_END.len()];
+    if file.seek(end).is_err()
+        || file.read_exact(&mut ending).is_err()
+        || ending != TMX_END.as_bytes()
+    {
+        drop(file);
+        return write_atomically(path, document().as_bytes());
+    }
+    file.seek(end)
+        .and_then(|_| file.write_all(units.as_bytes()))
+        .and_then(|()| file.write_all(TMX_END.as_bytes()))
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("failed to save {}", path.display()))
+}
+
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let temporary = path.with_extension("tmx.tmp");
+    // The data reaches the disk before the rename, so a crash leaves the old
+    // memory or the new one, never an empty file.
+    std::fs::File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .with_context(|| format!("failed to write {}", temporary.display()))?;
+    std::fs::rename(&temporary, path)
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// One translation unit of a TMX body.
+fn unit_tmx(source_language: &str, target_language: &str, entry: &MemoryEntry) -> String {
+    let source_language = escape(source_language);
+    let reviewed = if entry.reviewed {
+        format!("\n      <prop type=\"{REVIEWED_PROPERTY}\">yes</prop>")
+    } else {
+        String::new()
+    };
+    format!(
+        "    <tu srclang=\"{source_language}\">{reviewed}\n      <tuv xml:lang=\"{source_language}\"><seg>{}</seg></tuv>\n      <tuv xml:lang=\"{}\"><seg>{}</seg></tuv>\n    </tu>\n",
+        escape(&entry.unit.source),
+        escape(target_language),
+        escape(&entry.unit.target),
+    )
+}
+
+fn pair_key(source_language: &str, target_language: &str) -> (String, String) {
+    (
+        source_language.to_ascii_lowercase(),
+        target_language.to_ascii_lowercase(),
+    )
+}
+
+/// Folds the differences that do not change what a line says: case,
+/// full-width ASCII forms, and runs of whitespace.
+fn normalize(text: &str) -> String {
+    let folded = text
+        .chars()
+        .map(|character| match character {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => {
+                char::from_u32(character as u32 - 0xfee0).unwrap_or(character)
+            }
+            _ => character,
+        })
+        .collect::<String>()
+        .to_lowercase();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The Levenshtein distance between two lines, in characters.
+fn edit_distance(left: &[char], right: &[char]) -> usize {
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    let mut current = vec![0; right.len() + 1];
+    for (row, left) in left.iter().enumerate() {
+        current[0] = row + 1;
+        for (column, right) in right.iter().enumerate() {
+            current[column + 1] = (previous[column] + usize::from(left != right))
+                .min(previous[column + 1] + 1)
+                .min(current[column] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[right.len()]
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Reads every translation unit as source and target pairs, and whether the
+/// unit is marked as reviewed. A unit's source is the variant in its
+/// `srclang`, or the header's, or its first variant.
+fn parse_tmx(tmx: &str) -> Result<Vec<(String, String, MemoryUnit, bool)>> {
+    let document = roxmltree::Document::parse_with_options(
+        tmx,
+        roxmltree::ParsingOptions {
+            allow_dtd: true,
+            ..roxmltree::ParsingOptions::default()
+        },
+    )
+    .context("translation memory is not valid XML")?;
+    let root = document.root_element();
+    if !root.has_tag_name("tmx") {
+        anyhow::bail!("translation memory is not a TMX document");
+    }
+    let header_language = root
+        .children()
+        .find(|node| node.has_tag_name("header"))
+        .and_then(|header| header.attribute("srclang"))
+        .filter(|language| *language != "*all*");
+    let mut units = Vec::new();
+    for unit in root
+        .children()
+        .filter(|node| node.has_tag_name("body"))
+        .flat_map(|body| body.children())
+        .filter(|node| node.has_tag_name("tu"))
+    {
+        let variants = unit
+            .children()
+            .filter(|node| node.has_tag_name("tuv"))
+            .filter_map(|variant| {
+                let language = variant
+                    .attribute((roxmltree::NS_XML_URI, "lang"))
+                    .or_else(|| variant.attribute("lang"))?;
+                let segment = variant.children().find(|node| node.has_tag_name("seg"))?;
+                Some((language, segment_text(segment)))
+            })
+            .collect::<Vec<_>>();
+        let source_language = unit
+            .attribute("srclang")
+            .filter(|language| *language != "*all*")
+            .or(header_language);
+        let source = match source_language {
+            Some(language) => variants
+                .iter()
+                .position(|(candidate, _)| candidate.eq_ignore_ascii_case(language)),
+            None => (!variants.is_empty()).then_some(0),
+        };
+        let Some(source) = source else {
+            continue;
+        };
+        let reviewed = unit.children().any(|node| {
+            node.has_tag_name("prop") && node.attribute("type") == Some(REVIEWED_PROPERTY)
+        });
+        let (source_language, source_text) = &variants[source];
+        for (index, (target_language, target)) in variants.iter().enumerate() {
+            if index != source {
+                units.push((
+                    (*source_language).to_owned(),
+                    (*target_language).to_owned(),
+                    MemoryUnit {
+                        source: source_text.clone(),
+                        target: target.clone(),
+                    },
+                    reviewed,
+                ));
+            }
+        }
+    }
+    Ok(units)
+}
+
+/// The text of a segment without the native codes of its inline markup.
+fn segment_text(segment: roxmltree::Node<'_, '_>) -> String {
+    segment
+        .descendants()
+        .filter(|node| node.is_text())
+        .filter(|node| {
+            !node.ancestors().any(|ancestor| {
+                ["bpt", "ept", "it", "ph"]
+                    .iter()
+                    .any(|code| ancestor.has_tag_name(*code))
+            })
+        })
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(source: &str, target: &str) -> MemoryUnit {
+        MemoryUnit {
+            source: source.to_owned(),
+            target: target.to_owned(),
+        }
+    }
+
+    fn target(hit: Option<MemoryMatch>) -> Option<String> {
+        hit.map(|hit| hit.target)
+    }
+
+    #[tokio::test]
+    async fn exact_matches_ignore_case_width_and_spacing() {
+        let memory = TranslationMemory::memory();
+        memory
+            .correct("ja-JP", "en-US", [unit("えっ？！", "Huh?!")])
+            .await
+            .unwrap();
+        assert_eq!(
+            target(memory.exact("ja-jp", "en-US", " えっ?! ")),
+            Some("Huh?!".to_owned())
+        );
+        assert_eq!(memory.exact("ja-JP", "fr-FR", "えっ?!"), None);
+
+        memory
+            .correct("ja-JP", "en-US", [unit("えっ?!", "What?!")])
+            .await
+            .unwrap();
+        assert_eq!(
+            target(memory.exact("ja-JP", "en-US", "えっ?!")),
+            Some("What?!".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn similar_lines_are_ranked_by_edit_distance() {
+        let memory = TranslationMemory::memory();
+        memory
+            .correct(
+                "ja-JP",
+                "en-US",
+                [
+                    unit("今日はいい天気だね", "Nice weather today."),
+                    unit("今日はいい天気だな", "Nice weather today, huh."),
+                    unit("明日は雨だよ", "It'll rain tomorrow."),
+                ],
+            )
+            .await
+            .unwrap();
+        let matches = memory
+            .similar("ja-JP", "en-US", "今日はいい天気だね！", 5)
+            .await
+            .unwrap();
+        assert_eq!(
+            matches
+                .iter()
+                .map(|entry| entry.target.as_str())
+                .collect::<Vec<_>>(),
+            ["Nice weather today.", "Nice weather today, huh."]
+        );
+        assert!(matches[0].similarity > matches[1].similarity);
+        let matches = memory
+            .similar("ja-JP", "en-US", "今日はいい天気だね", 5)
+            .await
+            .unwrap();
+        assert!(matches[0].similarity < 1.0);
+    }
+
+    #[tokio::test]
+    async fn tmx_round_trips_through_import_and_export() {
+        let memory = TranslationMemory::memory();
+        memory
+            .correct(
+                UNDETERMINED_LANGUAGE,
+                "en-US",
+                [unit("<ドン>", "*BOOM* & \"crash\"")],
+            )
+            .await
+            .unwrap();
+        let copy = TranslationMemory::memory();
+        assert_eq!(copy.import_tmx(&memory.export_tmx()).await.unwrap(), 1);
+        assert_eq!(
+            target(copy.exact(UNDETERMINED_LANGUAGE, "en-US", "<ドン>")),
+            Some("*BOOM* & \"crash\"".to_owned())
+        );
+
+        let count = copy
+            .import_tmx(indoc::indoc! {r#"
+                <?xml version="1.0"?>
+                <!DOCTYPE tmx SYSTEM "tmx14.dtd">
+                <tmx version="1.4">
+                  <header srclang="en-US" segtype="sentence" o-tmf="x" adminlang="en" datatype="plaintext" creationtool="x" creationtoolversion="1"/>
+                  <body>
+                    <tu>
+                      <tuv xml:lang="ja-JP"><seg>よし<ph>{1}</ph>行こう</seg></tuv>
+                      <tuv xml:lang="en-US"><seg>Let's go</seg></tuv>
+                    </tu>
+                  </body>
+                </tmx>
+            "#})
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(
+            target(copy.exact("en-US", "ja-JP", "let's go")),
+            Some("よし行こう".to_owned())
+        );
+    }
+
+    #[tokio::test]
+    async fn saved_lines_keep_whether_they_were_reviewed() {
+        let memory = TranslationMemory::memory();
+        memory
+            .correct("ja-JP", "en-US", [unit("よし", "Alright.")])
+            .await
+            .unwrap();
+        let tmx = memory.export_tmx();
+        assert!(parse_tmx(&tmx).unwrap()[0].3);
+
+        let legacy = tmx.replace(
+            &format!("<prop type=\"{REVIEWED_PROPERTY}\">yes</prop>"),
+            "",
+        );
+        let units = parse_tmx(&legacy).unwrap();
+        assert!(!units[0].3);
+        let memory = TranslationMemory::memory();
+        memory.insert(units);
+        assert!(!memory.exact("ja-JP", "en-US", "よし").unwrap().reviewed);
+    }
+
+    #[tokio::test]
+    async fn changes_are_appended_and_compacted_on_open() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join(MEMORY_FILE);
+        let memory = TranslationMemory::open(path.clone()).unwrap();
+        memory
+            .correct("ja-JP", "en-US", [unit("よし", "Alright.")])
+            .await
+            .unwrap();
+        memory
+            .correct("ja-JP", "en-US", [unit("よし", "Okay.")])
+            .await
+            .unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(parse_tmx(&saved).unwrap().len(), 2);
+        assert!(saved.ends_with(TMX_END));
+
+        let reopened = TranslationMemory::open(path.clone()).unwrap();
+        assert_eq!(
+            target(reopened.exact("ja-JP", "en-US", "よし")),
+            Some("Okay.".to_owned())
+        );
+        let compacted = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(parse_tmx(&compacted).unwrap().len(), 1);
+    }
+}
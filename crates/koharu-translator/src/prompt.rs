@@ -3,18 +3,29 @@ use indoc::indoc;
 use serde::{Deserialize, Deserializer, Serialize, de};
 use serde_json::{Value, json};
 
-use crate::{Language, TranslationContext, TranslationRequest};
+use crate::{
+    Language, TranslatedSegment, TranslationCharacter, TranslationContext, TranslationGlossaryTerm,
+    TranslationRequest,
+};
 
 pub(crate) fn prompts(request: &TranslationRequest) -> anyhow::Result<(String, String)> {
     let input = TranslationInput {
         source_language: request.source_language,
         target_language: request.target_language,
         context: &request.context,
+        examples: &request.examples,
+        characters: speaking_characters(request),
+        glossary: &request.glossary,
         segments: request
             .segments
             .iter()
             .enumerate()
-            .map(|(id, text)| TranslationInputSegment { id, text })
+            .map(|(id, text)| TranslationInputSegment {
+                id,
+                text,
+                speaker: request.speaker(id),
+                hint: request.is_sound_effect(id).then_some("SFX"),
+            })
             .collect(),
     };
     let user = serde_json::to_string(&input).context("failed to serialize translation input")?;
@@ -25,15 +36,22 @@ pub(crate) fn translations(
     provider: &str,
     text: &str,
     source_segments: &[String],
-) -> anyhow::Result<Vec<String>> {
+) -> anyhow::Result<Vec<TranslatedSegment>> {
     let output = crate::json::from_str::<TranslationOutput>(text)
         .with_context(|| format!("{provider} returned invalid translation JSON"))?;
-    let mut translations = source_segments.to_vec();
+    let mut translations = source_segments
+        .iter()
+        .cloned()
+        .map(TranslatedSegment::from)
+        .collect::<Vec<_>>();
     let mut translated = vec![false; source_segments.len()];
 
     for translation in output.translations {
         if translation.id < translations.len() && !translated[translation.id] {
-            translations[translation.id] = translation.text;
+            translations[translation.id] = TranslatedSegment {
+                text: translation.text,
+                confidence: translation.confidence,
+            };
             translated[translation.id] = true;
         }
     }
@@ -61,9 +79,15 @@ pub(crate) fn output_schema(expected: usize) -> Value {
                         "text": {
                             "type": "string",
                             "description": "The translation of the input segment with this ID."
+                        },
+                        "confidence": {
+                            "type": "number",
+                            "minimum": 0,
+                            "maximum": 1,
+                            "description": "How sure the translator is that the translation is accurate and natural, from 0 to 1."
                         }
                     },
-                    "required": ["id", "text"],
+                    "required": ["id", "text", "confidence"],
                     "additionalProperties": false
                 }
             }
@@ -92,7 +116,8 @@ fn translation_system_prompt(request: &TranslationRequest) -> String {
 
             Output requirements:
             - Each input segment has a numeric `id`.
-            - Return only a JSON object whose `translations` array contains one object with `id` and translated `text` for every input segment.
+            - Return only a JSON object whose `translations` array contains one object with `id`, translated `text`, and `confidence` for every input segment.
+            - Rate each `confidence` from 0 to 1 by how sure you are that the translation is accurate and natural; rate ambiguous or unclear segments lower.
             - Copy every input ID exactly once; order does not matter.
             - Never merge, split, omit, duplicate, or add segments.
         "},
@@ -111,6 +136,52 @@ fn translation_system_prompt(request: &TranslationRequest) -> String {
         "}.trim_end());
     }
 
+    if !request.examples.is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(indoc! {"
+            Example requirements:
+            Each `examples` entry is an earlier translation of a line similar to one of the segments.
+            Where a segment says the same thing as an example, reuse the example's wording and adapt only what differs.
+            Do not translate or return the examples.
+        "}.trim_end());
+    }
+
+    if !speaking_characters(request).is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(indoc! {"
+            Speaker requirements:
+            A segment with a `speaker` is spoken by the entry of `characters` with that `id`; names may repeat, so always match by `id`.
+            Keep each character's voice consistent across segments and follow their `speech_notes`.
+            Refer to each character by one consistent translated name, whichever alias the source uses.
+            Never add the speaker's name to the translated text.
+        "}.trim_end());
+    }
+
+    if request.sound_effects.contains(&true) {
+        prompt.push_str("\n\n");
+        prompt.push_str(
+            format!(
+                indoc! {"
+                    Sound effect requirements:
+                    A segment whose `hint` is `SFX` is a sound effect lettered into the artwork, not speech.
+                    Translate it as a short {target} sound effect for the same sound or action; never describe or explain it.
+                "},
+                target = request.target_language,
+            )
+            .trim_end(),
+        );
+    }
+
+    if !request.glossary.is_empty() {
+        prompt.push_str("\n\n");
+        prompt.push_str(indoc! {"
+            Glossary requirements:
+            Wherever a segment contains the `source` of a `glossary` entry, its translation must use that entry's `target` exactly.
+            Glossary entries take precedence over every other requirement, including entries whose `target` repeats the source.
+            Follow each entry's `notes`.
+        "}.trim_end());
+    }
+
     if request.image.is_some() {
         prompt.push_str("\n\n");
         prompt.push_str(indoc! {"
@@ -132,18 +203,48 @@ fn translation_system_prompt(request: &TranslationRequest) -> String {
     prompt
 }
 
+/// The characters who speak at least one segment, in `characters` order,
+/// each identified by its position there.
+fn speaking_characters(request: &TranslationRequest) -> Vec<TranslationInputCharacter<'_>> {
+    request
+        .characters
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| request.speakers.contains(&Some(*index)))
+        .map(|(id, character)| TranslationInputCharacter { id, character })
+        .collect()
+}
+
 #[derive(Serialize)]
 struct TranslationInput<'a> {
     source_language: Option<Language>,
     target_language: Language,
     context: &'a [TranslationContext],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    examples: &'a [TranslationContext],
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    characters: Vec<TranslationInputCharacter<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    glossary: &'a [TranslationGlossaryTerm],
     segments: Vec<TranslationInputSegment<'a>>,
 }
 
+/// A character the segments refer to by `id`, since names are not unique.
+#[derive(Serialize)]
+struct TranslationInputCharacter<'a> {
+    id: usize,
+    #[serde(flatten)]
+    character: &'a TranslationCharacter,
+}
+
 #[derive(Serialize)]
 struct TranslationInputSegment<'a> {
     id: usize,
     text: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speaker: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<&'static str>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,6 +257,8 @@ struct TranslationOutputSegment {
     #[serde(deserialize_with = "deserialize_segment_id")]
     id: usize,
     text: String,
+    #[serde(default, deserialize_with = "deserialize_confidence")]
+    confidence: Option<f32>,
 }
 
 fn deserialize_segment_id<'de, D>(deserializer: D) -> Result<usize, D::Error>
@@ -175,10 +278,34 @@ where
     }
 }
 
+/// Reads a confidence from 0 to 1. A missing or unreadable one only loses the
+/// rating, not the translation.
+fn deserialize_confidence<'de, D>(deserializer: D) -> Result<Option<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let confidence = match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::Number(number)) => number.as_f64(),
+        Some(Value::String(text)) => text.trim().parse().ok(),
+        _ => None,
+    };
+    Ok(confidence
+        .filter(|confidence| confidence.is_finite())
+        .map(|confidence| confidence.clamp(0.0, 1.0) as f32))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn texts(response: &str, source: &[String]) -> Vec<String> {
+        translations("test", response, source)
+            .unwrap()
+            .into_iter()
+            .map(|segment| segment.text)
+            .collect()
+    }
+
     #[test]
     fn parses_plain_json_and_markdown_fences() {
         let source = ["one".to_owned(), "two".to_owned()];
@@ -189,7 +316,7 @@ mod tests {
             "```JSON\n{\"translations\":[{\"id\":0,\"text\":\"hello\"},{\"id\":1,\"text\":\"world\"}]}\n```",
             "```\n{\"translations\":[{\"id\":0,\"text\":\"hello\"},{\"id\":1,\"text\":\"world\"}]}\n```",
         ] {
-            assert_eq!(translations("test", response, &source).unwrap(), expected);
+            assert_eq!(texts(response, &source), expected);
         }
     }
 
@@ -203,28 +330,42 @@ mod tests {
             "{\"translations\":[{\"id\":0,\"text\":\"hello\"},{\"id\":1,\"text\":\"world\"",
             r#"{"translations":[{"id":"0","text":"hello"},{"id":"1","text":"world"}]}"#,
         ] {
-            assert_eq!(translations("test", response, &source).unwrap(), expected);
+            assert_eq!(texts(response, &source), expected);
         }
     }
 
+    #[test]
+    fn confidence_is_read_leniently() {
+        let source = ["one".to_owned(), "two".to_owned(), "three".to_owned()];
+        let response = concat!(
+            r#"{"translations":["#,
+            r#"{"id":0,"text":"hello","confidence":0.8},"#,
+            r#"{"id":1,"text":"world","confidence":"1.4"},"#,
+            r#"{"id":2,"text":"again","confidence":"sure"}"#,
+            "]}"
+        );
+        assert_eq!(
+            translations("test", response, &source)
+                .unwrap()
+                .into_iter()
+                .map(|segment| segment.confidence)
+                .collect::<Vec<_>>(),
+            [Some(0.8), Some(1.0), None]
+        );
+    }
+
     #[test]
     fn restores_input_order_from_ids() {
         let source = ["one".to_owned(), "two".to_owned()];
         let response = r#"{"translations":[{"id":1,"text":"world"},{"id":0,"text":"hello"}]}"#;
-        assert_eq!(
-            translations("test", response, &source).unwrap(),
-            ["hello", "world"]
-        );
+        assert_eq!(texts(response, &source), ["hello", "world"]);
     }
 
     #[test]
     fn tolerates_duplicate_missing_and_out_of_range_ids() {
         let source = ["one".to_owned(), "two".to_owned()];
         let short = r#"{"translations":[{"id":1,"text":"world"}]}"#;
-        assert_eq!(
-            translations("test", short, &source).unwrap(),
-            ["one", "world"]
-        );
+        assert_eq!(texts(short, &source), ["one", "world"]);
 
         let response = concat!(
             r#"{"translations":["#,
@@ -233,10 +374,7 @@ mod tests {
             r#"{"id":9,"text":"extra"}"#,
             "]}"
         );
-        assert_eq!(
-            translations("test", response, &source).unwrap(),
-            ["hello", "two"]
-        );
+        assert_eq!(texts(response, &source), ["hello", "two"]);
     }
 
     #[test]
@@ -270,6 +408,10 @@ mod tests {
         assert_eq!(translations["maxItems"], 3);
         assert_eq!(translations["items"]["properties"]["id"]["minimum"], 0);
         assert_eq!(translations["items"]["properties"]["id"]["maximum"], 2);
+        assert_eq!(
+            translations["items"]["required"],
+            serde_json::json!(["id", "text", "confidence"])
+        );
         assert_eq!(translations["items"]["additionalProperties"], false);
         assert_eq!(schema["additionalProperties"], false);
     }
@@ -289,6 +431,92 @@ mod tests {
         assert!(prompt.contains("Do not translate or return the context"));
     }
 
+    #[test]
+    fn segments_are_tagged_with_their_speakers() {
+        let mut hana = TranslationCharacter::new("Hana");
+        hana.speech_notes = "cheerful, drops honorifics".to_owned();
+        let request =
+            TranslationRequest::new(["おはよう", "……", "行くぞ", "待って"], Language::English)
+                .with_speakers(
+                    [
+                        hana,
+                        TranslationCharacter::new("Narrator"),
+                        TranslationCharacter::new("Ren"),
+                        TranslationCharacter::new("Hana"),
+                    ],
+                    [Some(0), None, Some(2), Some(3)],
+                );
+        let (system, user) = prompts(&request).unwrap();
+        let input: serde_json::Value = serde_json::from_str(&user).unwrap();
+        assert_eq!(input["segments"][0]["speaker"], 0);
+        assert!(input["segments"][1].get("speaker").is_none());
+        assert_eq!(input["segments"][2]["speaker"], 2);
+        // Two characters share a name, and only their ids tell them apart.
+        assert_eq!(input["segments"][3]["speaker"], 3);
+        assert_eq!(input["characters"].as_array().unwrap().len(), 3);
+        assert_eq!(input["characters"][1]["id"], 2);
+        assert_eq!(input["characters"][1]["name"], "Ren");
+        assert_eq!(input["characters"][2]["id"], 3);
+        assert_eq!(
+            input["characters"][0]["speech_notes"],
+            "cheerful, drops honorifics"
+        );
+        assert!(system.contains("Keep each character's voice consistent"));
+
+        let (system, user) =
+            prompts(&TranslationRequest::new(["text"], Language::English)).unwrap();
+        assert!(!system.contains("Speaker requirements"));
+        assert!(!user.contains("characters"));
+    }
+
+    #[test]
+    fn glossary_terms_are_sent_with_their_requirements() {
+        let request = TranslationRequest::new(["ハナ、行こう"], Language::English).with_glossary([
+            TranslationGlossaryTerm {
+                source: "ハナ".to_owned(),
+                target: "Hana".to_owned(),
+                notes: "the heroine".to_owned(),
+            },
+        ]);
+        let (system, user) = prompts(&request).unwrap();
+        let input: serde_json::Value = serde_json::from_str(&user).unwrap();
+        assert_eq!(input["glossary"][0]["target"], "Hana");
+        assert_eq!(input["glossary"][0]["notes"], "the heroine");
+        assert!(system.contains("must use that entry's `target` exactly"));
+
+        let (system, user) =
+            prompts(&TranslationRequest::new(["text"], Language::English)).unwrap();
+        assert!(!system.contains("Glossary requirements"));
+        assert!(!user.contains("glossary"));
+    }
+
+    #[test]
+    fn sound_effects_are_hinted() {
+        let request = TranslationRequest::new(["ドドド", "行くぞ"], Language::English)
+            .with_sound_effects([true, false]);
+        let (system, user) = prompts(&request).unwrap();
+        let input: serde_json::Value = serde_json::from_str(&user).unwrap();
+        assert_eq!(input["segments"][0]["hint"], "SFX");
+        assert!(input["segments"][1].get("hint").is_none());
+        assert!(system.contains("short English sound effect"));
+
+        let (system, _) = prompts(&TranslationRequest::new(["text"], Language::English)).unwrap();
+        assert!(!system.contains("Sound effect requirements"));
+    }
+
+    #[test]
+    fn memory_examples_are_reference_only() {
+        let request =
+            TranslationRequest::new(["今日はいい天気だね！"], Language::English).with_examples([
+                TranslationContext::new("今日はいい天気だね", "Nice weather today."),
+            ]);
+        let (system, user) = prompts(&request).unwrap();
+        let input: serde_json::Value = serde_json::from_str(&user).unwrap();
+        assert_eq!(input["examples"][0]["translation"], "Nice weather today.");
+        assert_eq!(input["segments"].as_array().unwrap().len(), 1);
+        assert!(system.contains("Do not translate or return the examples"));
+    }
+
     #[test]
     fn image_context_does_not_expand_the_translation_scope() {
         let request = TranslationRequest::new(["text"], Language::English)
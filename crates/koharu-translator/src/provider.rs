@@ -201,6 +201,29 @@ define_providers! {
     }
 }
 
+impl Provider {
+    /// About how many tokens of earlier dialogue a request to this provider
+    /// carries as context. Services that ignore context get none.
+    #[must_use]
+    pub const fn context_tokens(self) -> usize {
+        match self {
+            Self::Local => 512,
+            Self::DeepL => 1024,
+            Self::GoogleCloudTranslation | Self::Caiyun => 0,
+            Self::AtlasCloud
+            | Self::OpenAi
+            | Self::Gemini
+            | Self::Claude
+            | Self::Grok
+            | Self::MiniMax
+            | Self::DeepSeek
+            | Self::OpenAiCompatible
+            | Self::OpenRouter
+            | Self::LmStudio => 4096,
+        }
+    }
+}
+
 impl ProvidersConfig {
     pub fn load() -> anyhow::Result<koharu_config::Config<Self>> {
         koharu_config::load("providers")
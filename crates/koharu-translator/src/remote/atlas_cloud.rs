@@ -6,7 +6,7 @@ use koharu_secrets::ExposeSecret;
 use reqwest::Client;
 
 use super::openai_compatible::{ChatBackend, ResponseMode};
-use crate::{GenerationConfig, Model, Provider, Result, TranslationRequest};
+use crate::{GenerationConfig, Model, Provider, Result, TranslatedSegment, TranslationRequest};
 
 const CHAT_URL: &str = "https://api.atlascloud.ai/v1/chat/completions";
 const MODELS_URL: &str = "https://api.atlascloud.ai/v1/models";
@@ -21,7 +21,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key =
         koharu_secrets::get("atlas-cloud")?.context("atlas-cloud API key is not configured")?;
     let backend = ChatBackend {
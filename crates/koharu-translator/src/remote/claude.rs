@@ -10,7 +10,8 @@ use serde::{Deserialize, Serialize};
 
 use super::send_json;
 use crate::{
-    GenerationConfig, Model, Provider, Result, TranslationRequest, backend::encode_image, prompt,
+    GenerationConfig, Model, Provider, Result, TranslatedSegment, TranslationRequest,
+    backend::encode_image, prompt,
 };
 
 const URL: &str = "https://api.anthropic.com/v1/messages";
@@ -55,7 +56,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key = koharu_secrets::get("claude")?.context("claude API key is not configured")?;
     let (system, user) = prompt::prompts(request)?;
     let body = Request {
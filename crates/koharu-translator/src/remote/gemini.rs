@@ -11,8 +11,8 @@ use url::Url;
 
 use super::send_json;
 use crate::{
-    GenerationConfig as TranslationGeneration, Model, Provider, Result, TranslationRequest,
-    backend::encode_image, prompt,
+    GenerationConfig as TranslationGeneration, Model, Provider, Result, TranslatedSegment,
+    TranslationRequest, backend::encode_image, prompt,
 };
 
 const ROOT: &str = "https://generativelanguage.googleapis.com/v1beta/models";
@@ -79,7 +79,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &TranslationGeneration,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key = koharu_secrets::get("gemini")?.context("gemini API key is not configured")?;
     let (system, user) = prompt::prompts(request)?;
     let schema = prompt::output_schema(request.segments.len());
@@ -9,8 +9,8 @@ use url::Url;
 
 use super::send_json;
 use crate::{
-    GenerationConfig, Model, Provider, Result, TranslationRequest, backend::encode_image,
-    display_name, prompt,
+    GenerationConfig, Model, Provider, Result, TranslatedSegment, TranslationRequest,
+    backend::encode_image, display_name, prompt,
 };
 
 const DEFAULT_BASE_URL: &str = "http://localhost:1234";
@@ -35,7 +35,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key = koharu_secrets::get("lm-studio")?;
     let (system, input) = prompt::prompts(request)?;
     let body = ChatRequest {
@@ -7,7 +7,10 @@ use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
 use super::send_json;
-use crate::{GenerationConfig, Model, Provider, Result, TranslationRequest, display_name, prompt};
+use crate::{
+    GenerationConfig, Model, Provider, Result, TranslatedSegment, TranslationRequest, display_name,
+    prompt,
+};
 
 // MiniMax recommends compatibility APIs, but this provider intentionally uses its native route.
 const URL: &str = "https://api.minimax.io/v1/text/chatcompletion_v2";
@@ -46,7 +49,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key = koharu_secrets::get("minimax")?.context("minimax API key is not configured")?;
     let (system, user) = prompt::prompts(request)?;
     let response: Response = send_json(
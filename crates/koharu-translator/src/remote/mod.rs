@@ -33,7 +33,7 @@ pub use openrouter::OpenRouterConfig;
 
 use crate::{
     Error, GenerationConfig, Model, ModelSelection, Provider, ProvidersConfig, Result,
-    TranslationRequest,
+    TranslatedSegment, TranslationRequest,
 };
 
 pub(crate) async fn translate(
@@ -42,7 +42,7 @@ pub(crate) async fn translate(
     selection: &ModelSelection,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let model = || {
         selection
             .model
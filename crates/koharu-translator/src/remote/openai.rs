@@ -7,7 +7,9 @@ use serde::Deserialize;
 
 use super::openai_compatible::{ChatBackend, ResponseMode};
 use super::send_json;
-use crate::{GenerationConfig, Model, Provider, Result, TranslationRequest, display_name};
+use crate::{
+    GenerationConfig, Model, Provider, Result, TranslatedSegment, TranslationRequest, display_name,
+};
 
 const URL: &str = "https://api.openai.com/v1/chat/completions";
 const MODELS_URL: &str = "https://api.openai.com/v1/models";
@@ -73,7 +75,7 @@ pub(super) async fn translate(
     model: &str,
     generation: &GenerationConfig,
     request: &TranslationRequest,
-) -> Result<Vec<String>> {
+) -> Result<Vec<TranslatedSegment>> {
     let api_key = koharu_secrets::get("openai")?.context("openai API key is not configured")?;
     let backend = ChatBackend {
         max_tokens: None,
@@ -33,13 +33,22 @@ export const commands = {
 	size: PageSize,
 	layers: Layer[],
 	regions: AnalysisRegion[],
+	annotations: Annotation[],
 } | null>("get_page").then((v) => (v==null?v:({...v,regions:v.regions.map(i=>({...i,geometry:({...i.geometry,points:i.geometry.points.map(i=>i)})}))}) as typeof v)),
+	queryEntities: (query: Query) => __TAURI_INVOKE<QueryMatch[]>("query_entities", { query }),
+	listCharacters: () => __TAURI_INVOKE<Character[]>("list_characters"),
+	listTextStyles: () => __TAURI_INVOKE<TextStyle[]>("list_text_styles"),
+	listGlossary: () => __TAURI_INVOKE<GlossaryTerm[]>("list_glossary"),
+	listVolumes: () => __TAURI_INVOKE<Volume[]>("list_volumes"),
+	listChapters: () => __TAURI_INVOKE<Chapter[]>("list_chapters"),
+	getReadingDirection: () => __TAURI_INVOKE<ReadingDirection>("get_reading_direction"),
 	listProjects: () => __TAURI_INVOKE<ProjectSummary[]>("list_projects"),
 	createProject: (name: string) => __TAURI_INVOKE<null>("create_project", { name }),
 	openProject: (name: string) => __TAURI_INVOKE<null>("open_project", { name }),
 	deleteProject: (name: string) => __TAURI_INVOKE<null>("delete_project", { name }),
 	closeProject: () => __TAURI_INVOKE<null>("close_project"),
 	importPages: (source: PageImportSource) => __TAURI_INVOKE<null>("import_pages", { source }),
+	importTranslationMemory: () => __TAURI_INVOKE<number | null>("import_translation_memory"),
 	selectPage: (page: EntityId) => __TAURI_INVOKE<PageSelection>("select_page", { page }).then((v) => (({...v,page:({...v.page,regions:v.page.regions.map(i=>({...i,geometry:({...i.geometry,points:i.geometry.points.map(i=>i)})}))})}) as typeof v)),
 	renamePage: (page: EntityId, label: string) => __TAURI_INVOKE<null>("rename_page", { page, label }),
 	deletePages: (pages: EntityId[]) => __TAURI_INVOKE<null>("delete_pages", { pages }),
@@ -48,14 +57,43 @@ export const commands = {
 	setTranslation: (layer: EntityId, text: string | null) => __TAURI_INVOKE<null>("set_translation", { layer, text }),
 	setTypography: (updates: TypographyUpdate[]) => __TAURI_INVOKE<null>("set_typography", { updates: updates.map(i=>({...i,typography:({...i.typography,size:i.typography.size==null?i.typography.size:i.typography.size,stroke_width:i.typography.stroke_width==null?i.typography.stroke_width:i.typography.stroke_width})})) }),
 	setGeometry: (updates: GeometryUpdate[]) => __TAURI_INVOKE<null>("set_geometry", { updates: updates.map(i=>({...i,points:i.points==null?i.points:i.points.map(i=>i)})) }),
+	setShape: (layer: EntityId, shape: ShapeDraft) => __TAURI_INVOKE<null>("set_shape", { layer, shape }),
 	setVisibility: (layers: EntityId[], visible: boolean | null, opacity: number | null) => __TAURI_INVOKE<null>("set_visibility", { layers, visible, opacity: opacity==null?opacity:opacity }),
+	setBlending: (layers: EntityId[], mode: BlendMode | null, clipped: boolean | null) => __TAURI_INVOKE<null>("set_blending", { layers, mode, clipped }),
+	clearMask: (layer: EntityId) => __TAURI_INVOKE<null>("clear_mask", { layer }),
 	deleteLayers: (layers: EntityId[]) => __TAURI_INVOKE<null>("delete_layers", { layers }),
 	moveLayer: (layer: EntityId, parent: EntityId, index: number) => __TAURI_INVOKE<Page>("move_layer", { layer, parent, index }).then((v) => (({...v,regions:v.regions.map(i=>({...i,geometry:({...i.geometry,points:i.geometry.points.map(i=>i)})}))}) as typeof v)),
+	addAnnotation: (anchor: EntityId, author: string, body: string, mark: Point[] | null) => __TAURI_INVOKE<EntityId>("add_annotation", { anchor, author, body, mark: mark==null?mark:mark.map(i=>i) }),
+	replyToAnnotation: (annotation: EntityId, author: string, body: string) => __TAURI_INVOKE<null>("reply_to_annotation", { annotation, author, body }),
+	resolveAnnotation: (annotation: EntityId, resolved: boolean) => __TAURI_INVOKE<null>("resolve_annotation", { annotation, resolved }),
+	addCharacter: (draft: CharacterDraft) => __TAURI_INVOKE<EntityId>("add_character", { draft }),
+	updateCharacter: (character: EntityId, draft: CharacterDraft) => __TAURI_INVOKE<null>("update_character", { character, draft }),
+	removeCharacter: (character: EntityId) => __TAURI_INVOKE<null>("remove_character", { character }),
+	setSpeaker: (layers: EntityId[], character: EntityId | null) => __TAURI_INVOKE<null>("set_speaker", { layers, character }),
+	addTextStyle: (draft: TextStyleDraft) => __TAURI_INVOKE<EntityId>("add_text_style", { draft }),
+	updateTextStyle: (style: EntityId, draft: TextStyleDraft) => __TAURI_INVOKE<null>("update_text_style", { style, draft }),
+	removeTextStyle: (style: EntityId) => __TAURI_INVOKE<null>("remove_text_style", { style }),
+	applyTextStyle: (layers: EntityId[], style: EntityId | null, clearOverrides: boolean) => __TAURI_INVOKE<null>("apply_text_style", { layers, style, clearOverrides }),
+	addGlossaryTerm: (draft: GlossaryTermDraft) => __TAURI_INVOKE<EntityId>("add_glossary_term", { draft }),
+	updateGlossaryTerm: (term: EntityId, draft: GlossaryTermDraft) => __TAURI_INVOKE<null>("update_glossary_term", { term, draft }),
+	removeGlossaryTerm: (term: EntityId) => __TAURI_INVOKE<null>("remove_glossary_term", { term }),
+	addVolume: (draft: HeadingDraft, before: EntityId | null) => __TAURI_INVOKE<EntityId>("add_volume", { draft, before }),
+	updateVolume: (volume: EntityId, draft: HeadingDraft) => __TAURI_INVOKE<null>("update_volume", { volume, draft }),
+	moveVolume: (volume: EntityId, before: EntityId | null) => __TAURI_INVOKE<null>("move_volume", { volume, before }),
+	removeVolume: (volume: EntityId) => __TAURI_INVOKE<null>("remove_volume", { volume }),
+	addChapter: (draft: HeadingDraft, volume: EntityId | null, before: EntityId | null) => __TAURI_INVOKE<EntityId>("add_chapter", { draft, volume, before }),
+	updateChapter: (chapter: EntityId, draft: HeadingDraft) => __TAURI_INVOKE<null>("update_chapter", { chapter, draft }),
+	moveChapter: (chapter: EntityId, volume: EntityId | null, before: EntityId | null) => __TAURI_INVOKE<null>("move_chapter", { chapter, volume, before }),
+	removeChapter: (chapter: EntityId) => __TAURI_INVOKE<null>("remove_chapter", { chapter }),
+	setChapter: (pages: EntityId[], chapter: EntityId | null) => __TAURI_INVOKE<null>("set_chapter", { pages, chapter }),
+	setReadingDirection: (direction: ReadingDirection) => __TAURI_INVOKE<null>("set_reading_direction", { direction }),
+	setSpread: (page: EntityId, partner: EntityId | null) => __TAURI_INVOKE<null>("set_spread", { page, partner }),
 	undo: () => __TAURI_INVOKE<null>("undo"),
 	redo: () => __TAURI_INVOKE<null>("redo"),
 	process: (scope: Scope, operation: Operation) => __TAURI_INVOKE<JobId>("process", { scope, operation }),
 	stopJob: (job: JobId) => __TAURI_INVOKE<null>("stop_job", { job }),
-	exportPages: (pages: EntityId[], format: ExportFormat) => __TAURI_INVOKE<null>("export_pages", { pages, format }),
+	exportPages: (pages: EntityId[], format: ExportFormat, spreads: boolean) => __TAURI_INVOKE<null>("export_pages", { pages, format, spreads }),
+	exportTranslationMemory: () => __TAURI_INVOKE<null>("export_translation_memory"),
 	getThumbnail: (page: EntityId) => __TAURI_INVOKE<ThumbnailBytes>("get_thumbnail", { page }),
 	getFonts: () => __TAURI_INVOKE<FontFamily[]>("get_fonts"),
 	getFontPreview: (familyName: string) => __TAURI_INVOKE<FontPreviewBytes>("get_font_preview", { familyName }),
@@ -72,8 +110,10 @@ export const commands = {
 	getCanvasPageResource: (page: EntityId, revision: Revision, resource: string) => __TAURI_INVOKE<CanvasBytes>("get_canvas_page_resource", { page, revision, resource }),
 	addPointText: (point: Point) => __TAURI_INVOKE<LayerCommit>("add_point_text", { point }),
 	addTextBox: (frame: Frame) => __TAURI_INVOKE<LayerCommit>("add_text_box", { frame }),
+	addShape: (frame: Frame, shape: ShapeDraft) => __TAURI_INVOKE<LayerCommit>("add_shape", { frame, shape }),
 	commitPaint: (expectedRevision: Revision, layer: string | null, points: Point[], brush: PaintBrush) => __TAURI_INVOKE<LayerCommit>("commit_paint", { expectedRevision, layer, points: points.map(i=>i), brush }),
 	commitErase: (expectedRevision: Revision, layer: EntityId, points: Point[], diameter: number) => __TAURI_INVOKE<LayerCommit>("commit_erase", { expectedRevision, layer, points: points.map(i=>i), diameter }),
+	commitMask: (expectedRevision: Revision, layer: EntityId, points: Point[], diameter: number, reveal: boolean) => __TAURI_INVOKE<LayerCommit>("commit_mask", { expectedRevision, layer, points: points.map(i=>i), diameter, reveal }),
 	commitTransform: (expectedRevision: Revision, elements: TransformFrame[]) => __TAURI_INVOKE<number | null>("commit_transform", { expectedRevision, elements }).then((v) => (v==null?v:v as typeof v)),
 	commitInpaint: (expectedRevision: Revision, points: Point[], diameter: number) => __TAURI_INVOKE<string | null>("commit_inpaint", { expectedRevision, points: points.map(i=>i), diameter }),
 };
@@ -100,8 +140,25 @@ export type AnalysisRegion = {
 	label: string | null,
 };
 
+export type Annotation = {
+	id: EntityId,
+	anchor: EntityId | null,
+	author: string,
+	body: string,
+	mark: Point[] | null,
+	replies: AnnotationReply[],
+	resolved: boolean,
+};
+
+export type AnnotationReply = {
+	author: string,
+	body: string,
+};
+
 export type AtlasCloudConfig = Record<string, never>;
 
+export type BlendMode = "normal" | "multiply" | "screen" | "overlay";
+
 export type Bounds = {
 	x: number,
 	y: number,
@@ -128,6 +185,29 @@ export type CanvasState = {
 	element_frames: TransformFrame[],
 };
 
+export type Chapter = {
+	id: EntityId,
+	title: string,
+	number: number | null,
+	volume: EntityId | null,
+	pages: EntityId[],
+};
+
+export type Character = {
+	id: EntityId,
+	name: string,
+	aliases: string[],
+	speech_notes: string,
+	typography: TypographyOverride | null,
+};
+
+export type CharacterDraft = {
+	name: string,
+	aliases: string[],
+	speech_notes: string,
+	typography: TypographyOverride | null,
+};
+
 export type ClaudeConfig = Record<string, never>;
 
 export type CodexModel = {
@@ -165,6 +245,8 @@ export type DeviceResources = {
 	utilization: number | null,
 };
 
+export type Direction = "outgoing" | "incoming";
+
 export type Download = {
 	id: number,
 	state: DownloadState,
@@ -182,7 +264,16 @@ export type Error = string;
 
 export type Event = { type: "started"; run: RunId } | { type: "text_delta"; run: RunId; delta: string } | { type: "reasoning_delta"; run: RunId; delta: string } | { type: "tool_started"; run: RunId; call_id: string; name: string } | { type: "tool_finished"; run: RunId; call_id: string; name: string; changed: boolean; output: string } | { type: "completed"; run: RunId; message: string } | { type: "failed"; run: RunId; message: string } | { type: "cancelled"; run: RunId };
 
-export type ExportFormat = "png" | "psd";
+export type ExportFormat = "png" | "psd" | "cbz";
+
+export type Filter = { filter: "has"; value: string } | { filter: "lacks"; value: string } | { filter: "origin"; value: {
+	component: string,
+	origin: OriginFilter,
+} } | { filter: "region"; value: RegionKind } | { filter: "translation"; value: OriginFilter } | { filter: "text"; value: string } | { filter: "related"; value: {
+	kind: RelationKind,
+	direction: Direction,
+	query: Query,
+} };
 
 export type Flux2KleinConfig = {
 	prompt?: string,
@@ -202,6 +293,15 @@ export type FontFamily = {
 	faces: FontFace[],
 };
 
+export type FontMapping = {
+	source: SourceFontClass,
+	/**
+	 *  Tried ahead of the typesetting font stack.
+	 */
+	family: string | null,
+	weight: number | null,
+};
+
 export type FontMetadata = {
 	primary_script: string | null,
 	scripts: string[],
@@ -222,6 +322,18 @@ export type FontSource = "system" | "bundled";
 
 export type FontStyle = "normal" | "italic" | "oblique";
 
+export type FontsConfig = {
+	/**
+	 *  Whether detection predicts the source font of each text region.
+	 */
+	detect?: boolean,
+	/**
+	 *  The family and weight each class of source font is lettered in.
+	 *  Classes without a mapping keep the typesetting font stack.
+	 */
+	families?: FontMapping[],
+};
+
 export type Frame = {
 	x: number,
 	y: number,
@@ -245,6 +357,13 @@ export type GenerationConfig = {
 	vision?: boolean | null,
 };
 
+export type GenerationFilter = {
+	producer: ProducerId | null,
+	model: string | null,
+	min_confidence: number | null,
+	below_confidence: number | null,
+};
+
 export type Geometry = {
 	points: Point[],
 };
@@ -254,12 +373,37 @@ export type GeometryUpdate = {
 	points: Point[] | null,
 };
 
+export type GlossaryTerm = {
+	id: EntityId,
+	source: string,
+	target: string,
+	notes: string,
+	case_sensitive: boolean,
+	do_not_translate: boolean,
+};
+
+export type GlossaryTermDraft = {
+	source: string,
+	/**
+	 *  The required rendering; ignored when the term is kept untranslated.
+	 */
+	target: string,
+	notes: string,
+	case_sensitive: boolean,
+	do_not_translate: boolean,
+};
+
 export type GoogleCloudConfig = Record<string, never>;
 
 export type GrokConfig = Record<string, never>;
 
 export type GroupRole = "text";
 
+export type HeadingDraft = {
+	title: string,
+	number: number | null,
+};
+
 export type InpaintingModel = { model: "lama" } | { model: "aot-inpainting" } | {
 	model: "flux2-klein",
 } & Flux2KleinConfig | {
@@ -292,7 +436,7 @@ export type LanguageChoice = {
 	name: string,
 };
 
-export type Layer = { type: "group"; id: EntityId; parent: EntityId | null; visibility: LayerVisibility; name: string; role: GroupRole | null } | { type: "text"; id: EntityId; parent: EntityId | null; geometry: Geometry | null; visibility: LayerVisibility; content: TextContent; typography: Typography | null; layout: TextLayoutKind; automatic_region: EntityId | null } | { type: "raster"; id: EntityId; parent: EntityId | null; visibility: LayerVisibility; image: string | null; name: string; kind: RasterLayerKind } | { type: "image"; id: EntityId; parent: EntityId | null; geometry: Geometry; visibility: LayerVisibility; image: string } | { type: "artwork"; id: EntityId; parent: EntityId | null; geometry: Geometry; visibility: LayerVisibility; image: string };
+export type Layer = { type: "group"; id: EntityId; parent: EntityId | null; visibility: LayerVisibility; name: string; role: GroupRole | null } | { type: "text"; id: EntityId; parent: EntityId | null; geometry: Geometry | null; visibility: LayerVisibility; content: TextContent; typography: Typography | null; style: EntityId | null; layout: TextLayoutKind; automatic_region: EntityId | null } | { type: "raster"; id: EntityId; parent: EntityId | null; visibility: LayerVisibility; image: string | null; name: string; kind: RasterLayerKind; blend: BlendMode; clipped: boolean; mask: string | null } | { type: "image"; id: EntityId; parent: EntityId | null; geometry: Geometry; visibility: LayerVisibility; image: string } | { type: "artwork"; id: EntityId; parent: EntityId | null; geometry: Geometry; visibility: LayerVisibility; image: string } | { type: "shape"; id: EntityId; parent: EntityId | null; geometry: Geometry; visibility: LayerVisibility; name: string; outline: ShapeOutline; fill: [number, number, number, number] | null; stroke: ShapeStroke | null };
 
 export type LayerCommit = {
 	revision: Revision,
@@ -350,16 +494,24 @@ export type OpenRouterConfig = Record<string, never>;
 
 export type Operation = { operation: "full" } | { operation: "through"; stage: Stage } | { operation: "only"; stage: Stage } | { operation: "stages"; stages: Stage[] };
 
+export type OriginFilter = { origin: "user" } | { origin: "generated"; value: GenerationFilter };
+
 export type Page = {
 	id: EntityId,
 	label: string,
 	size: PageSize,
 	layers: Layer[],
 	regions: AnalysisRegion[],
+	annotations: Annotation[],
 };
 
 export type PageImportSource = "files" | "folder";
 
+export type PageRange = {
+	first: number,
+	last: number,
+};
+
 export type PageSelection = {
 	project: ProjectInfo,
 	page: Page,
@@ -376,6 +528,10 @@ export type PageSummary = {
 	size: PageSize,
 	source_asset: string | null,
 	layer_count: number,
+	/**
+	 *  The page facing this one in a two-page spread.
+	 */
+	spread: EntityId | null,
 };
 
 export type PaintBrush = {
@@ -387,6 +543,8 @@ export type PipelineConfig = {
 	detection: DetectionModel,
 	ocr: OcrModel,
 	translation: TranslationConfig,
+	sfx: SfxConfig,
+	fonts: FontsConfig,
 	inpainting: InpaintingModel,
 	/**
 	 *  Settings for every model are kept independently of the active model.
@@ -413,6 +571,8 @@ export type ProcessorConfig = {
 	"rorem-mixed"?: RoremMixedConfig | null,
 };
 
+export type ProducerId = string;
+
 export type ProjectInfo = {
 	name: string,
 	revision: Revision,
@@ -444,10 +604,26 @@ export type Quantization = {
 	name: string,
 };
 
+export type Query = {
+	pages: PageRange | null,
+	filters: Filter[],
+};
+
+export type QueryMatch = {
+	id: EntityId,
+	page: EntityId,
+};
+
 export type RasterLayerKind = "cleanup" | "paint";
 
+export type ReadingDirection = "RightToLeft" | "LeftToRight" | "VerticalScroll";
+
 export type Reasoning = "low" | "medium" | "high" | "xhigh" | "max" | "ultra";
 
+export type RegionKind = string;
+
+export type RelationKind = string;
+
 export type Revision = number;
 
 export type RoremMixedConfig = {
@@ -460,14 +636,40 @@ export type RunId = string;
 export type Scope = { scope: "project" } | { scope: "pages"; value: EntityId[] } | { scope: "region"; value: {
 	page: EntityId,
 	bounds: Bounds,
-} } | { scope: "entities"; value: EntityId[] };
+} } | { scope: "entities"; value: EntityId[] } | { scope: "chapter"; value: EntityId } | { scope: "query"; value: Query };
+
+export type SfxConfig = {
+	/**
+	 *  Whether full, translation, and inpainting runs read sound effects.
+	 *  Runs that name the SFX stage read them either way.
+	 */
+	enabled?: boolean,
+	treatment?: SfxTreatment,
+};
+
+export type SfxTreatment = "leave" | "replace" | "annotate";
+
+export type ShapeDraft = {
+	outline: ShapeOutline,
+	fill: [number, number, number, number] | null,
+	stroke: ShapeStroke | null,
+};
+
+export type ShapeOutline = "rectangle" | { rounded_rectangle: { radius: number } } | "ellipse" | { path: { points: Point[]; closed: boolean } } | { balloon: { tail: Point } };
+
+export type ShapeStroke = {
+	color: [number, number, number, number],
+	width: number,
+};
+
+export type SourceFontClass = "gothic" | "bold_gothic" | "mincho" | "bold_mincho";
 
 export type SourceText = {
 	text: string,
 	language: string | null,
 };
 
-export type Stage = "detection" | "ocr" | "translation" | "inpainting";
+export type Stage = "detection" | "ocr" | "sfx" | "translation" | "inpainting";
 
 export type StartupState = {
 	preferences: Preferences,
@@ -483,10 +685,27 @@ export type TextContent = {
 	translation: Translation | null,
 	role: string | null,
 	source_region: EntityId | null,
+	speaker: EntityId | null,
 };
 
 export type TextLayoutKind = "point" | "paragraph";
 
+export type TextStyle = {
+	id: EntityId,
+	name: string,
+	typography: TypographyOverride,
+	based_on: EntityId | null,
+};
+
+export type TextStyleDraft = {
+	name: string,
+	typography: TypographyOverride,
+	/**
+	 *  The style this one inherits unset fields from.
+	 */
+	based_on: EntityId | null,
+};
+
 export type ThumbnailBytes = number[];
 
 export type TransformFrame = {
@@ -504,6 +723,10 @@ export type TranslationConfig = {
 	generation: GenerationConfig,
 	target_language: string,
 	instructions: string | null,
+	/**
+	 *  How many preceding pages, in reading order, are sent as context.
+	 */
+	context_pages?: number,
 };
 
 export type TypesettingConfig = {
@@ -523,11 +746,31 @@ export type Typography = {
 	writing_mode: WritingMode | null,
 };
 
+export type TypographyOverride = {
+	preferred_font: string | null,
+	font_weight: number | null,
+	font_style: FontStyle | null,
+	size: number | null,
+	auto_fit: boolean | null,
+	color: [number, number, number, number] | null,
+	stroke_color: [number, number, number, number] | null,
+	stroke_width: number | null,
+	alignment: TextAlignment | null,
+	writing_mode: WritingMode | null,
+};
+
 export type TypographyUpdate = {
 	layer: EntityId,
 	typography: Typography,
 };
 
+export type Volume = {
+	id: EntityId,
+	title: string,
+	number: number | null,
+	chapters: EntityId[],
+};
+
 export type WritingMode = "Horizontal" | "Vertical";
 
 /* Tauri Specta runtime */
@@ -20,8 +20,8 @@ import {
   usePages,
   useProject,
 } from '@/lib/queries'
-import { useKoharuStore } from '@/lib/store'
-import { commands, type Operation, type Scope, type Stage } from '@koharu/bridge/protocol'
+import { pipelineStages, useKoharuStore } from '@/lib/store'
+import { commands, type Operation, type Scope } from '@koharu/bridge/protocol'
 import {
   Menubar,
   MenubarContent as UiMenubarContent,
@@ -105,7 +105,12 @@ export function TitleBar() {
               <MenubarItem
                 disabled={!project || pages.length === 0}
                 onClick={() =>
-                  void call(commands.exportPages, exportSelection(selectedPages, page?.id), 'png')
+                  void call(
+                    commands.exportPages,
+                    exportSelection(selectedPages, page?.id),
+                    'png',
+                    false,
+                  )
                 }
               >
                 {t('menu.exportPng')}
@@ -113,12 +118,28 @@ export function TitleBar() {
               <MenubarItem
                 disabled={!project || pages.length === 0}
                 onClick={() =>
-                  void call(commands.exportPages, exportSelection(selectedPages, page?.id), 'psd')
+                  void call(
+                    commands.exportPages,
+                    exportSelection(selectedPages, page?.id),
+                    'psd',
+                    false,
+                  )
                 }
               >
                 {t('menu.exportPsd')}
               </MenubarItem>
               <MenubarSeparator />
+              <MenubarItem
+                onClick={() => void call(commands.importTranslationMemory).catch(() => undefined)}
+              >
+                {t('menu.importTranslationMemory')}
+              </MenubarItem>
+              <MenubarItem
+                onClick={() => void call(commands.exportTranslationMemory).catch(() => undefined)}
+              >
+                {t('menu.exportTranslationMemory')}
+              </MenubarItem>
+              <MenubarSeparator />
               <MenubarItem disabled={!project} onClick={closeProject}>
                 {t('menu.closeProject')}
               </MenubarItem>
@@ -207,7 +228,7 @@ export function TitleBar() {
                 {t('menu.processLayers')}
               </MenubarItem>
               <MenubarSeparator />
-              {(['detection', 'ocr', 'translation', 'inpainting'] as Stage[]).map((stage) => (
+              {pipelineStages.map((stage) => (
                 <MenubarItem
                   key={stage}
                   disabled={!project || pages.length === 0}
@@ -3,7 +3,7 @@
 import { InferenceControl } from '@/components/editor/InferenceControl'
 import { call } from '@/lib/backend'
 import { usePage } from '@/lib/queries'
-import { pipelineStages, useKoharuStore, type PipelineScope } from '@/lib/store'
+import { defaultStages, useKoharuStore, type PipelineScope } from '@/lib/store'
 import { commands, type Scope, type Stage } from '@koharu/bridge/protocol'
 
 export function CanvasCommandBar() {
@@ -21,7 +21,7 @@ export function CanvasCommandBar() {
           ? { scope: 'pages', value: selectedPages }
           : { scope: 'pages', value: [page.id] }
     const operation =
-      stages.length === pipelineStages.length
+      stages.length === defaultStages.length && !stages.includes('sfx')
         ? ({ operation: 'full' } as const)
         : stages.length === 1
           ? ({ operation: 'only', stage: stages[0]! } as const)
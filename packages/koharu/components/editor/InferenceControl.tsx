@@ -314,6 +314,13 @@ function RuntimeSelector({
               selected={stages.includes('ocr')}
               onSelect={toggleStage}
             />
+            <SelectorOption
+              value='sfx'
+              label={t('phase.sfx')}
+              detail={t('phaseDescription.sfx')}
+              selected={stages.includes('sfx')}
+              onSelect={toggleStage}
+            />
             <SelectorOption
               value='translation'
               label={t('phase.translation')}
@@ -20,7 +20,12 @@ import {
   PreferenceSection,
   TextField,
 } from '@/components/preferences/PreferenceFields'
-import type { PipelineConfig } from '@koharu/bridge/protocol'
+import type {
+  FontMapping,
+  PipelineConfig,
+  SfxTreatment,
+  SourceFontClass,
+} from '@koharu/bridge/protocol'
 import {
   Select,
   SelectContent,
@@ -28,6 +33,7 @@ import {
   SelectTrigger,
   SelectValue,
 } from '@koharu/ui/components/select'
+import { Switch } from '@koharu/ui/components/switch'
 
 const stages = [
   ['detection', Search],
@@ -35,6 +41,34 @@ const stages = [
   ['inpainting', Eraser],
 ] as const satisfies ReadonlyArray<readonly [ModelStage, typeof Search]>
 
+const sfxTreatments = ['leave', 'replace', 'annotate'] as const satisfies readonly SfxTreatment[]
+
+const sourceFontClasses = [
+  'gothic',
+  'bold_gothic',
+  'mincho',
+  'bold_mincho',
+] as const satisfies readonly SourceFontClass[]
+
+/** Replaces one class's mapping, dropping it once it sets neither field. */
+function mapSourceFont(
+  value: PipelineConfig,
+  source: SourceFontClass,
+  change: Partial<Pick<FontMapping, 'family' | 'weight'>>,
+): PipelineConfig {
+  const families = value.fonts.families ?? []
+  const current = families.find((mapping) => mapping.source === source)
+  const next = { source, family: null, weight: null, ...current, ...change }
+  const others = families.filter((mapping) => mapping !== current)
+  return {
+    ...value,
+    fonts: {
+      ...value.fonts,
+      families: next.family === null && next.weight === null ? others : [...others, next],
+    },
+  }
+}
+
 export function PipelinePreferences({
   value,
   onChange,
@@ -96,6 +130,96 @@ export function PipelinePreferences({
             </PreferenceRow>
           )
         })}
+        <PreferenceRow
+          title={t('settings.pipeline.sfx.enabled')}
+          description={t('settings.pipeline.sfx.enabledDescription')}
+        >
+          <div className='flex h-8 items-center justify-end'>
+            <Switch
+              aria-label={t('settings.pipeline.sfx.enabled')}
+              checked={value.sfx.enabled ?? false}
+              onCheckedChange={(enabled) => onChange({ ...value, sfx: { ...value.sfx, enabled } })}
+            />
+          </div>
+        </PreferenceRow>
+        <PreferenceRow
+          title={t('settings.pipeline.sfx.title')}
+          description={t('settings.pipeline.sfx.description')}
+        >
+          <Select
+            value={value.sfx.treatment ?? 'leave'}
+            items={Object.fromEntries(
+              sfxTreatments.map((treatment) => [
+                treatment,
+                t(`settings.pipeline.sfx.treatments.${treatment}`),
+              ]),
+            )}
+            onValueChange={(treatment) => {
+              if (treatment) {
+                onChange({ ...value, sfx: { ...value.sfx, treatment: treatment as SfxTreatment } })
+              }
+            }}
+          >
+            <SelectTrigger
+              aria-label={t('settings.pipeline.sfx.label')}
+              className='h-8 min-w-0 text-[11px]'
+            >
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              {sfxTreatments.map((treatment) => (
+                <SelectItem key={treatment} value={treatment}>
+                  {t(`settings.pipeline.sfx.treatments.${treatment}`)}
+                </SelectItem>
+              ))}
+            </SelectContent>
+          </Select>
+        </PreferenceRow>
+      </PreferenceSection>
+      <PreferenceSection
+        title={t('settings.pipeline.fonts.title')}
+        description={t('settings.pipeline.fonts.description')}
+      >
+        <PreferenceRow
+          title={t('settings.pipeline.fonts.detect')}
+          description={t('settings.pipeline.fonts.detectDescription')}
+        >
+          <div className='flex h-8 items-center justify-end'>
+            <Switch
+              aria-label={t('settings.pipeline.fonts.detect')}
+              checked={value.fonts.detect ?? false}
+              onCheckedChange={(detect) =>
+                onChange({ ...value, fonts: { ...value.fonts, detect } })
+              }
+            />
+          </div>
+        </PreferenceRow>
+        {sourceFontClasses.map((source) => {
+          const mapping = value.fonts.families?.find((entry) => entry.source === source)
+          return (
+            <PreferenceRow key={source} title={t(`settings.pipeline.fonts.classes.${source}`)}>
+              <div className='grid grid-cols-2 gap-2'>
+                <TextField
+                  label={t('settings.pipeline.fonts.family')}
+                  value={mapping?.family ?? ''}
+                  onChange={(family) =>
+                    onChange(
+                      mapSourceFont(value, source, { family: family.trim() ? family : null }),
+                    )
+                  }
+                />
+                <NumberField
+                  label={t('settings.pipeline.fonts.weight')}
+                  value={mapping?.weight ?? null}
+                  min={1}
+                  max={1000}
+                  step={100}
+                  onChange={(weight) => onChange(mapSourceFont(value, source, { weight }))}
+                />
+              </div>
+            </PreferenceRow>
+          )
+        })}
       </PreferenceSection>
     </PreferencePage>
   )
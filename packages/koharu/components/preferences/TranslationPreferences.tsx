@@ -7,6 +7,7 @@ import { useTranslation } from 'react-i18next'
 import { ModelPicker } from '@/components/controls/ModelPicker'
 import { GenerationPreferences } from '@/components/preferences/GenerationPreferences'
 import {
+  NumberField,
   PreferencePage,
   PreferenceRow,
   PreferenceSection,
@@ -180,6 +181,21 @@ export function TranslationPreferences({
             }
           />
         </PreferenceRow>
+        <PreferenceRow
+          title={t('settings.translation.contextPages')}
+          description={t('settings.translation.contextPagesDescription')}
+        >
+          <NumberField
+            label={t('settings.translation.contextPages')}
+            value={value.context_pages ?? null}
+            min={0}
+            max={10}
+            step={1}
+            onChange={(context_pages) =>
+              onChange({ ...value, context_pages: context_pages ?? undefined })
+            }
+          />
+        </PreferenceRow>
       </PreferenceSection>
     </PreferencePage>
   )
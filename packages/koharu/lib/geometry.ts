@@ -59,12 +59,15 @@ export function draftFrame(start: Point, end: Point): Frame {
 }
 
 export function selectableLayer(layer: Layer): boolean {
-  return layer.type === 'text' || layer.type === 'image'
+  return layer.type === 'text' || layer.type === 'image' || layer.type === 'shape'
 }
 
 export function layerFrame(layer: Layer): Frame | null {
   const points =
-    layer.type === 'text' || layer.type === 'image' || layer.type === 'artwork'
+    layer.type === 'text' ||
+    layer.type === 'image' ||
+    layer.type === 'artwork' ||
+    layer.type === 'shape'
       ? layer.geometry?.points
       : null
   if (!points?.length || points.some((point) => !finite(point.x, point.y))) return null
@@ -32,7 +32,15 @@ export type InspectorSection = 'copy' | 'type' | 'layers'
 export type ShortcutAction = CanvasTool | 'fit'
 export type Shortcuts = Record<ShortcutAction, string>
 export type PipelineScope = 'page' | 'selected-pages' | 'project'
-export const pipelineStages: readonly Stage[] = ['detection', 'ocr', 'translation', 'inpainting']
+export const pipelineStages: readonly Stage[] = [
+  'detection',
+  'ocr',
+  'sfx',
+  'translation',
+  'inpainting',
+]
+// Sound effects are read on request, or by full runs when enabled in settings.
+export const defaultStages: readonly Stage[] = pipelineStages.filter((stage) => stage !== 'sfx')
 
 interface KoharuStore {
   initialized: boolean
@@ -102,7 +110,7 @@ export const useKoharuStore = create<KoharuStore>()((set) => ({
   brush: { diameter: 48, color: '#111111' },
   inspector: 'copy',
   processingScope: 'page',
-  processingStages: [...pipelineStages],
+  processingStages: [...defaultStages],
   settingsOpen: false,
   shortcuts: defaultShortcuts,
   selectPages: (selectedPages) => set({ selectedPages: [...new Set(selectedPages)] }),
@@ -71,6 +71,7 @@ const textLayer: Layer = {
     translation: { text: 'Hello', language: null },
     role: null,
     source_region: null,
+    speaker: null,
   },
   typography: {
     preferred_font: 'Noto Sans',
@@ -84,6 +85,7 @@ const textLayer: Layer = {
     alignment: 'Center',
     writing_mode: null,
   },
+  style: null,
   layout: 'paragraph',
   automatic_region: null,
 }
@@ -104,6 +106,8 @@ const preferences: Preferences = {
       target_language: 'en-US',
       instructions: null,
     },
+    sfx: {},
+    fonts: {},
     inpainting: { model: 'lama' },
     processor: {},
   },
@@ -150,6 +154,7 @@ function installProject() {
     size: { width: 1000, height: 1500 },
     layers: [textLayer],
     regions: [],
+    annotations: [],
   }
   queryClient.setQueryData(projectKey, {
     name: 'Book',
@@ -165,6 +170,7 @@ function installProject() {
       size: { width: 1000, height: 1500 },
       source_asset: 'source',
       layer_count: 1,
+      spread: null,
     },
   ])
   queryClient.setQueryData(pageKey, page)
@@ -333,13 +339,21 @@ describe('greenfield editor', () => {
   it('keeps rapid page switches on the latest native selection', async () => {
     installProject()
     const pages = [
-      { id: 'page', label: 'Page 1', size: { width: 1000, height: 1500 }, layers: [], regions: [] },
+      {
+        id: 'page',
+        label: 'Page 1',
+        size: { width: 1000, height: 1500 },
+        layers: [],
+        regions: [],
+        annotations: [],
+      },
       {
         id: 'page-2',
         label: 'Page 2',
         size: { width: 1000, height: 1500 },
         layers: [],
         regions: [],
+        annotations: [],
       },
       {
         id: 'page-3',
@@ -347,6 +361,7 @@ describe('greenfield editor', () => {
         size: { width: 1000, height: 1500 },
         layers: [],
         regions: [],
+        annotations: [],
       },
     ]
     queryClient.setQueryData(
@@ -357,6 +372,7 @@ describe('greenfield editor', () => {
         size: page.size,
         source_asset: null,
         layer_count: 0,
+        spread: null,
       })),
     )
     vi.spyOn(canvasRuntime, 'showCanvasPage').mockReturnValue(false)
@@ -421,6 +437,7 @@ describe('greenfield editor', () => {
         size: { width: 1000, height: 1500 },
         source_asset: null,
         layer_count: 0,
+        spread: null,
       },
     ])
     vi.spyOn(canvasRuntime, 'showCanvasPage').mockReturnValue(true)
@@ -450,6 +467,7 @@ describe('greenfield editor', () => {
       size: { width: 1000, height: 1500 },
       layers: [],
       regions: [],
+      annotations: [],
     }
     queryClient.setQueryData(pagesKey, [
       ...(queryClient.getQueryData<PageSummary[]>(pagesKey) ?? []),
@@ -459,6 +477,7 @@ describe('greenfield editor', () => {
         size: page.size,
         source_asset: null,
         layer_count: 0,
+        spread: null,
       },
     ])
     const prepared = { revision: 1, page }
@@ -483,6 +502,7 @@ describe('greenfield editor', () => {
       size: { width: 1000, height: 1500 },
       layers: [],
       regions: [],
+      annotations: [],
     }
     queryClient.setQueryData(pagesKey, [
       ...(queryClient.getQueryData<PageSummary[]>(pagesKey) ?? []),
@@ -492,6 +512,7 @@ describe('greenfield editor', () => {
         size: page.size,
         source_asset: null,
         layer_count: 0,
+        spread: null,
       },
     ])
     const prefetch = vi
@@ -815,6 +836,17 @@ describe('greenfield editor', () => {
         { operation: 'full' },
       ),
     )
+
+    fireEvent.click(screen.getByRole('button', { name: 'Processing settings' }))
+    fireEvent.click(screen.getByRole('button', { name: /Stages 4 stages/ }))
+    fireEvent.click(screen.getByRole('button', { name: /SFX/ }))
+    fireEvent.click(screen.getByRole('button', { name: 'Run processing' }))
+    await waitFor(() =>
+      expect(run).toHaveBeenLastCalledWith(
+        { scope: 'pages', value: ['page'] },
+        { operation: 'stages', stages: ['detection', 'ocr', 'sfx', 'translation', 'inpainting'] },
+      ),
+    )
   })
 
   it('runs the current page and exposes the runtime shortcuts', async () => {
@@ -838,7 +870,7 @@ describe('greenfield editor', () => {
     await waitFor(() => expect(commands.getTranslationModels).toHaveBeenCalled())
     expect(screen.getByRole('button', { name: /Model Gemma 4 E2B Instruct/ })).toBeInTheDocument()
     expect(screen.getByRole('button', { name: /Scope Page/ })).toBeInTheDocument()
-    expect(screen.getByRole('button', { name: /Stages 4 stages/ })).toBeInTheDocument()
+    expect(screen.getByRole('button', { name: /Stages 5 stages/ })).toBeInTheDocument()
     expect(screen.getByRole('button', { name: /Output English/ })).toBeInTheDocument()
     fireEvent.click(screen.getByRole('button', { name: 'Settings' }))
     expect(useKoharuStore.getState().settingsOpen).toBe(true)
@@ -921,7 +953,7 @@ describe('greenfield editor', () => {
     await user.click(screen.getByRole('button', { name: 'Processing settings' }))
     await user.click(screen.getByRole('button', { name: /Scope Page/ }))
     await user.click(screen.getByRole('button', { name: /Entire project/ }))
-    await user.click(screen.getByRole('button', { name: /Stages 4 stages/ }))
+    await user.click(screen.getByRole('button', { name: /Stages 5 stages/ }))
     await user.click(screen.getByRole('button', { name: /Translation/ }))
     await user.click(screen.getByRole('button', { name: /Inpainting/ }))
     await user.click(screen.getByRole('button', { name: 'Back' }))
@@ -935,7 +967,7 @@ describe('greenfield editor', () => {
     await user.click(screen.getByRole('button', { name: 'Processing settings' }))
 
     expect(screen.getByRole('button', { name: /Scope Project/ })).toBeInTheDocument()
-    expect(screen.getByRole('button', { name: /Stages 2 stages/ })).toBeInTheDocument()
+    expect(screen.getByRole('button', { name: /Stages 3 stages/ })).toBeInTheDocument()
     expect(screen.getByRole('button', { name: /Output Japanese/ })).toBeInTheDocument()
   })
 
@@ -71,6 +71,9 @@ const paintLayer: Layer = {
   image: 'paint-image',
   name: 'Paint 1',
   kind: 'paint',
+  blend: 'normal',
+  clipped: false,
+  mask: null,
 }
 
 let nextAnimationFrame = 1
@@ -101,6 +104,7 @@ function installProject() {
     size: { width: 1000, height: 1000 },
     layers: [layer],
     regions: [],
+    annotations: [],
   }
   queryClient.setQueryData(projectKey, {
     name: 'Book',
@@ -145,6 +149,7 @@ describe('canvas interaction adapter', () => {
         size: { width: 1000, height: 1000 },
         source_asset: null,
         layer_count: 1,
+        spread: null,
       },
       {
         id: 'next',
@@ -152,6 +157,7 @@ describe('canvas interaction adapter', () => {
         size: { width: 1000, height: 1000 },
         source_asset: null,
         layer_count: 1,
+        spread: null,
       },
     ])
     useKoharuStore.setState({ canvasPage: 'previous' })
@@ -289,8 +295,10 @@ describe('canvas interaction adapter', () => {
             translation: { text: 'Rendered', language: null },
             role: null,
             source_region: null,
+            speaker: null,
           },
           typography: null,
+          style: null,
           layout: 'paragraph',
           automatic_region: null,
         },
@@ -390,8 +398,10 @@ describe('canvas interaction adapter', () => {
             translation: { text: 'Rendered', language: null },
             role: null,
             source_region: null,
+            speaker: null,
           },
           typography: null,
+          style: null,
           layout: 'paragraph',
           automatic_region: 'bubble',
         },
@@ -412,6 +422,7 @@ describe('canvas interaction adapter', () => {
           label: null,
         },
       ],
+      annotations: [],
     }))
     useKoharuStore.setState({
       selectedLayers: ['element'],
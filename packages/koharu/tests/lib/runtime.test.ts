@@ -40,6 +40,8 @@ const preferences: Preferences = {
       target_language: 'en-US',
       instructions: null,
     },
+    sfx: {},
+    fonts: {},
     inpainting: { model: 'lama' },
     processor: {},
   },